@@ -0,0 +1,1864 @@
+/*!
+ * LOCAL HTTP API
+ * An opt-in HTTP/JSON server inside the app, so companion apps and
+ * integrations (the accountant's spreadsheets, a mobile stock check) can
+ * read the books without copying the database file. It listens on this
+ * computer only (`localhost`) or on the local network (`lan`, plain HTTP,
+ * so only on a trusted network), and is described by an OpenAPI document
+ * at `/api/v1/openapi.json`.
+ *
+ * Every other request carries an API key as `Authorization: Bearer <key>`,
+ * `X-Api-Key: <key>` or, for spreadsheet tools that cannot send headers, an
+ * `api_key` query parameter. An admin creates and revokes keys in the app;
+ * only a SHA-256 hash is stored and the key itself is shown once.
+ *
+ * Endpoints read products, stock, customers, balances, invoices (archived
+ * years included) and the daily ledger. The one write, a stock count from
+ * the mobile app, lands in a review queue (`api_stock_counts`) and never
+ * changes stock by itself; staff adjust stock in the app after checking it.
+ *
+ * Settings, keys and counts stay on this terminal and are not synced.
+ */
+
+use std::collections::HashMap;
+use std::io::Read;
+use std::net::{SocketAddr, TcpStream, UdpSocket};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use log::{error, info};
+use rusqlite::{params, Connection, OptionalExtension, TransactionBehavior};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+use sha2::{Digest, Sha256};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::archive::attach_archives;
+use crate::audit::{self, AuditEvent};
+use crate::customer_balance::{customer_statement, GUEST_CUSTOMER_ID};
+use crate::database::{current_date, get_db_path, open_connection, open_connection_at};
+use crate::day_close::{day_closing, DayClosing};
+use crate::money::{money_from_row, paisa_from_row, paisa_sql, Money};
+use crate::quantity::{format_stock_text, parse_stock_text, to_unit_number, value_to_text};
+use crate::search::{self, SearchHit};
+use crate::session::require_admin;
+use crate::stock_engine::stock_as_of;
+use crate::vendor_payables::{vendor_aging, VendorAging};
+
+pub const DEFAULT_PORT: u16 = 47821;
+
+const API_PREFIX: &str = "/api/v1";
+const KEY_PREFIX: &str = "isk_";
+
+/// Rows per page of a list endpoint, by default and at most
+const DEFAULT_LIMIT: i64 = 100;
+const MAX_LIMIT: i64 = 500;
+
+/// Largest request body accepted
+const MAX_BODY: u64 = 64 * 1024;
+
+/// How long a restart waits for the old listener to let go of the port
+const REBIND_ATTEMPTS: usize = 20;
+const REBIND_DELAY: Duration = Duration::from_millis(100);
+
+/// Create the settings, key and stock count tables
+pub fn ensure_schema(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS api_settings (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            enabled INTEGER NOT NULL DEFAULT 0,
+            bind TEXT NOT NULL DEFAULT 'localhost' CHECK (bind IN ('localhost', 'lan')),
+            port INTEGER NOT NULL,
+            updated_by TEXT,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE TABLE IF NOT EXISTS api_keys (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            key_prefix TEXT NOT NULL,
+            key_hash TEXT NOT NULL UNIQUE,
+            can_submit_counts INTEGER NOT NULL DEFAULT 0,
+            created_by TEXT NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            last_used_at DATETIME,
+            request_count INTEGER NOT NULL DEFAULT 0,
+            revoked_by TEXT,
+            revoked_at DATETIME
+        );
+        CREATE TABLE IF NOT EXISTS api_stock_counts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            product_id INTEGER NOT NULL,
+            product_name TEXT NOT NULL,
+            unit_type TEXT NOT NULL,
+            counted_stock TEXT NOT NULL,
+            counted_base INTEGER NOT NULL,
+            system_stock TEXT NOT NULL,
+            system_base INTEGER,
+            counted_by TEXT,
+            notes TEXT,
+            api_key_id INTEGER NOT NULL,
+            status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'reviewed')),
+            reviewed_by TEXT,
+            reviewed_at DATETIME,
+            submitted_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (api_key_id) REFERENCES api_keys(id)
+        );
+        CREATE INDEX IF NOT EXISTS idx_api_stock_counts_status ON api_stock_counts(status);",
+    )
+}
+
+// ===================================================================
+// SETTINGS AND SERVER
+// ===================================================================
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ApiSettings {
+    pub enabled: bool,
+    /// "localhost" (this computer only) or "lan" (the local network)
+    pub bind: String,
+    pub port: u16,
+}
+
+impl ApiSettings {
+    fn listen_address(&self) -> SocketAddr {
+        let ip = if self.bind == "lan" {
+            [0, 0, 0, 0]
+        } else {
+            [127, 0, 0, 1]
+        };
+        SocketAddr::from((ip, self.port))
+    }
+}
+
+fn load_settings(conn: &Connection) -> Result<ApiSettings, String> {
+    let stored = conn
+        .query_row("SELECT enabled, bind, port FROM api_settings WHERE id = 1", [], |row| {
+            Ok(ApiSettings {
+                enabled: row.get(0)?,
+                bind: row.get(1)?,
+                port: row.get(2)?,
+            })
+        })
+        .optional()
+        .map_err(|e| format!("Failed to load API settings: {}", e))?;
+    Ok(stored.unwrap_or(ApiSettings {
+        enabled: false,
+        bind: "localhost".to_string(),
+        port: DEFAULT_PORT,
+    }))
+}
+
+struct Running {
+    settings: ApiSettings,
+    server: Arc<Server>,
+}
+
+struct ServerState {
+    running: Option<Running>,
+    /// Why the last attempt to listen failed
+    error: Option<String>,
+}
+
+static SERVER: Mutex<ServerState> = Mutex::new(ServerState {
+    running: None,
+    error: None,
+});
+
+/// Bind, retrying while a listener that was just stopped still holds the port
+fn bind(address: SocketAddr) -> Result<Server, String> {
+    let mut attempt = 0;
+    loop {
+        match Server::http(address) {
+            Ok(server) => return Ok(server),
+            Err(e) if attempt + 1 >= REBIND_ATTEMPTS => {
+                return Err(format!("Failed to listen on {}: {}", address, e));
+            }
+            Err(_) => {
+                // The old accept thread only notices it was stopped on its next connection
+                let _ = TcpStream::connect_timeout(&SocketAddr::from(([127, 0, 0, 1], address.port())), REBIND_DELAY);
+                thread::sleep(REBIND_DELAY);
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// Start, restart or stop the server to match the stored settings
+pub fn apply_settings(db_path: &Path) -> Result<(), String> {
+    let settings = load_settings(&open_connection_at(db_path)?)?;
+    let mut state = SERVER
+        .lock()
+        .map_err(|_| "The API server state is unavailable".to_string())?;
+    if settings.enabled
+        && state
+            .running
+            .as_ref()
+            .is_some_and(|running| running.settings == settings)
+    {
+        return Ok(());
+    }
+    if let Some(running) = state.running.take() {
+        running.server.unblock();
+        info!("[API] Stopped listening on {}", running.settings.listen_address());
+    }
+    state.error = None;
+    if !settings.enabled {
+        return Ok(());
+    }
+
+    let address = settings.listen_address();
+    match bind(address) {
+        Ok(server) => {
+            let server = Arc::new(server);
+            let serving = Arc::clone(&server);
+            let db_path = db_path.to_path_buf();
+            thread::spawn(move || serve(serving, db_path));
+            info!("[API] Listening on http://{}{}", address, API_PREFIX);
+            state.running = Some(Running { settings, server });
+            Ok(())
+        }
+        Err(message) => {
+            state.error = Some(message.clone());
+            Err(message)
+        }
+    }
+}
+
+/// Start listening if the API is enabled
+pub fn start(db_path: PathBuf) {
+    thread::spawn(move || {
+        if let Err(e) = apply_settings(&db_path) {
+            error!("[API] {}", e);
+        }
+    });
+}
+
+/// Answer requests until the server is stopped
+fn serve(server: Arc<Server>, db_path: PathBuf) {
+    for request in server.incoming_requests() {
+        let db_path = db_path.clone();
+        thread::spawn(move || handle(request, &db_path));
+    }
+}
+
+// ===================================================================
+// REQUESTS
+// ===================================================================
+
+/// An error answered with its HTTP status
+#[derive(Debug)]
+struct ApiError {
+    status: u16,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: u16, message: impl Into<String>) -> ApiError {
+        ApiError {
+            status,
+            message: message.into(),
+        }
+    }
+
+    fn bad_request(message: impl Into<String>) -> ApiError {
+        ApiError::new(400, message)
+    }
+
+    fn not_found(message: impl Into<String>) -> ApiError {
+        ApiError::new(404, message)
+    }
+}
+
+impl From<String> for ApiError {
+    fn from(message: String) -> ApiError {
+        ApiError::new(500, message)
+    }
+}
+
+type ApiResult = Result<JsonValue, ApiError>;
+
+/// The key a request was made with
+struct ApiKey {
+    id: i64,
+    name: String,
+    can_submit_counts: bool,
+}
+
+/// Decode `%XX` escapes and `+` in a query string component
+fn decode_component(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = |byte: u8| (byte as char).to_digit(16);
+                match (hex(bytes[i + 1]), hex(bytes[i + 2])) {
+                    (Some(high), Some(low)) => {
+                        decoded.push((high * 16 + low) as u8);
+                        i += 2;
+                    }
+                    _ => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn parse_query(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode_component(name), decode_component(value))
+        })
+        .collect()
+}
+
+struct Query(HashMap<String, String>);
+
+impl Query {
+    /// A parameter's trimmed value; empty counts as absent
+    fn text(&self, name: &str) -> Option<&str> {
+        self.0
+            .get(name)
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        matches!(self.text(name), Some("1" | "true" | "yes"))
+    }
+
+    fn number(&self, name: &str) -> Result<Option<i64>, ApiError> {
+        self.text(name)
+            .map(|value| {
+                value
+                    .parse::<i64>()
+                    .map_err(|_| ApiError::bad_request(format!("{} must be a whole number", name)))
+            })
+            .transpose()
+    }
+
+    fn date(&self, name: &str) -> Result<Option<&str>, ApiError> {
+        match self.text(name) {
+            Some(date) if !is_date(date) => Err(ApiError::bad_request(format!("{} must be a YYYY-MM-DD date", name))),
+            date => Ok(date),
+        }
+    }
+
+    /// `limit` and `offset` of a list endpoint
+    fn paging(&self) -> Result<(i64, i64), ApiError> {
+        let limit = self.number("limit")?.unwrap_or(DEFAULT_LIMIT);
+        let offset = self.number("offset")?.unwrap_or(0);
+        if !(1..=MAX_LIMIT).contains(&limit) || offset < 0 {
+            return Err(ApiError::bad_request(format!(
+                "limit must be between 1 and {} and offset may not be negative",
+                MAX_LIMIT
+            )));
+        }
+        Ok((limit, offset))
+    }
+
+    /// The `q` parameter as a LIKE pattern
+    fn like(&self) -> Option<String> {
+        self.text("q").map(|q| format!("%{}%", q))
+    }
+}
+
+fn is_date(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    bytes.len() == 10
+        && bytes.iter().enumerate().all(|(i, byte)| {
+            if i == 4 || i == 7 {
+                *byte == b'-'
+            } else {
+                byte.is_ascii_digit()
+            }
+        })
+}
+
+fn parse_id(text: &str) -> Result<i64, ApiError> {
+    text.parse()
+        .map_err(|_| ApiError::not_found(format!("{} is not a valid id", text)))
+}
+
+fn to_json<T: Serialize>(value: &T) -> ApiResult {
+    serde_json::to_value(value).map_err(|e| ApiError::from(format!("Failed to encode response: {}", e)))
+}
+
+fn key_hash(key: &str) -> String {
+    format!("{:x}", Sha256::digest(key.as_bytes()))
+}
+
+fn header<'a>(request: &'a Request, name: &'static str) -> Option<&'a str> {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv(name))
+        .map(|header| header.value.as_str().trim())
+}
+
+fn authenticate(conn: &Connection, request: &Request, query: &Query) -> Result<ApiKey, ApiError> {
+    let presented = header(request, "Authorization")
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .or_else(|| header(request, "X-Api-Key"))
+        .or_else(|| query.text("api_key"))
+        .map(str::trim)
+        .ok_or_else(|| ApiError::new(401, "An API key is required (Authorization: Bearer <key>)"))?;
+
+    let key = conn
+        .query_row(
+            "SELECT id, name, can_submit_counts FROM api_keys WHERE key_hash = ?1 AND revoked_at IS NULL",
+            [key_hash(presented)],
+            |row| {
+                Ok(ApiKey {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    can_submit_counts: row.get(2)?,
+                })
+            },
+        )
+        .optional()
+        .map_err(|e| format!("Failed to check API key: {}", e))?
+        .ok_or_else(|| ApiError::new(401, "Unknown or revoked API key"))?;
+
+    if let Err(e) = conn.execute(
+        "UPDATE api_keys SET last_used_at = CURRENT_TIMESTAMP, request_count = request_count + 1 WHERE id = ?1",
+        [key.id],
+    ) {
+        error!("[API] Failed to record use of key {}: {}", key.name, e);
+    }
+    Ok(key)
+}
+
+fn handle(mut request: Request, db_path: &Path) {
+    let (status, body) = match route(&mut request, db_path) {
+        Ok(body) => (200, body),
+        Err(error) => {
+            if error.status >= 500 {
+                error!("[API] {} {}: {}", request.method(), request.url(), error.message);
+            }
+            (error.status, json!({ "error": error.message }))
+        }
+    };
+    let content_type =
+        Header::from_bytes(&b"Content-Type"[..], &b"application/json; charset=utf-8"[..]).expect("valid header");
+    let response = Response::from_data(serde_json::to_vec(&body).unwrap_or_default())
+        .with_status_code(status)
+        .with_header(content_type);
+    if let Err(e) = request.respond(response) {
+        error!("[API] Failed to answer request: {}", e);
+    }
+}
+
+fn route(request: &mut Request, db_path: &Path) -> ApiResult {
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+    let query = Query(parse_query(query));
+    let segments: Vec<&str> = path
+        .strip_prefix(API_PREFIX)
+        .ok_or_else(|| ApiError::not_found(format!("Endpoints are under {}", API_PREFIX)))?
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    let method = request.method().clone();
+
+    if method == Method::Get && segments == ["openapi.json"] {
+        return Ok(openapi());
+    }
+
+    let mut conn = open_connection_at(db_path)?;
+    let key = authenticate(&conn, request, &query)?;
+    match (&method, segments.as_slice()) {
+        (Method::Get, ["products"]) => list_products(&conn, &query),
+        (Method::Get, ["products", id]) => get_product(&conn, parse_id(id)?),
+        (Method::Get, ["stock"]) => stock_levels(&conn, &query),
+        (Method::Get, ["customers"]) => list_customers(&conn, &query),
+        (Method::Get, ["customers", id]) => get_customer(&conn, parse_id(id)?),
+        (Method::Get, ["customers", id, "statement"]) => get_statement(&conn, parse_id(id)?, &query),
+        (Method::Get, ["balances"]) => balances(&conn),
+        (Method::Get, ["invoices"]) => list_invoices(&conn, &query),
+        (Method::Get, ["invoices", id]) => get_invoice(&conn, parse_id(id)?),
+        (Method::Get, ["daily-ledger"]) => daily_ledger(&mut conn, &query),
+        (Method::Get, ["search"]) => search_books(&conn, &query),
+        (Method::Post, ["stock-counts"]) => {
+            if !key.can_submit_counts {
+                return Err(ApiError::new(403, "This API key may not submit stock counts"));
+            }
+            let mut body = String::new();
+            request
+                .as_reader()
+                .take(MAX_BODY)
+                .read_to_string(&mut body)
+                .map_err(|e| ApiError::bad_request(format!("Failed to read request body: {}", e)))?;
+            let count: StockCountRequest = serde_json::from_str(&body)
+                .map_err(|e| ApiError::bad_request(format!("Invalid stock count: {}", e)))?;
+            let count = submit_stock_count(&conn, &key, count)?;
+            info!(
+                "[API] Stock count of {} submitted with key {}: {}",
+                count.product_name, key.name, count.counted_stock
+            );
+            to_json(&count)
+        }
+        _ => Err(ApiError::not_found(format!("No endpoint {} {}", method, path))),
+    }
+}
+
+// ===================================================================
+// ENDPOINTS
+// ===================================================================
+
+#[derive(Debug, Serialize)]
+struct Page<T> {
+    data: Vec<T>,
+    total: i64,
+    limit: i64,
+    offset: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApiProduct {
+    pub id: i64,
+    pub name: String,
+    pub category: Option<String>,
+    pub sku: Option<String>,
+    pub barcode: Option<String>,
+    pub unit_type: String,
+    pub unit: String,
+    /// Stock as the app shows it ("12-500" for kg-grams)
+    pub current_stock: String,
+    /// Stock as a plain number of units (decimal kg for weight products)
+    pub stock_quantity: Option<f64>,
+    pub min_stock_alert: String,
+    pub low_stock: bool,
+    pub rate: Money,
+    pub cost_price: Money,
+    pub status: String,
+    pub updated_at: Option<String>,
+}
+
+const PRODUCT_COLUMNS: &str = "id, name, category, sku, barcode, unit_type, unit, current_stock,
+    COALESCE(min_stock_alert, '0'), rate_per_unit, cost_price, COALESCE(status, 'active'), updated_at";
+
+/// Products matching `q` (name, SKU or barcode), a category, and active unless `include_inactive`;
+/// never ones in the recycle bin
+const PRODUCT_FILTER: &str = "(?1 IS NULL OR name LIKE ?1 OR sku LIKE ?1 OR barcode LIKE ?1)
+    AND (?2 IS NULL OR category = ?2) AND (?3 OR COALESCE(status, 'active') = 'active') AND deleted_at IS NULL";
+
+fn product_from_row(row: &rusqlite::Row) -> rusqlite::Result<ApiProduct> {
+    let unit_type: String = row.get(5)?;
+    let current_stock = value_to_text(row.get(7)?);
+    let min_stock_alert = value_to_text(row.get(8)?);
+    let base = parse_stock_text(&current_stock, &unit_type);
+    let minimum = parse_stock_text(&min_stock_alert, &unit_type);
+    Ok(ApiProduct {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        category: row.get(2)?,
+        sku: row.get(3)?,
+        barcode: row.get(4)?,
+        unit: row.get(6)?,
+        stock_quantity: base.map(|base| to_unit_number(base, &unit_type)),
+        low_stock: matches!((base, minimum), (Some(base), Some(minimum)) if minimum > 0 && base <= minimum),
+        unit_type,
+        current_stock,
+        min_stock_alert,
+        rate: money_from_row(row, 9)?,
+        cost_price: money_from_row(row, 10)?,
+        status: row.get(11)?,
+        updated_at: row.get(12)?,
+    })
+}
+
+fn list_products(conn: &Connection, query: &Query) -> ApiResult {
+    let (limit, offset) = query.paging()?;
+    let filter = params![query.like(), query.text("category"), query.flag("include_inactive")];
+    let total: i64 = conn
+        .query_row(
+            &format!("SELECT COUNT(*) FROM products WHERE {}", PRODUCT_FILTER),
+            filter,
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to count products: {}", e))?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM products WHERE {} ORDER BY name, id LIMIT ?4 OFFSET ?5",
+            PRODUCT_COLUMNS, PRODUCT_FILTER
+        ))
+        .map_err(|e| format!("Failed to query products: {}", e))?;
+    let data = stmt
+        .query_map(
+            params![
+                query.like(),
+                query.text("category"),
+                query.flag("include_inactive"),
+                limit,
+                offset
+            ],
+            product_from_row,
+        )
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| format!("Failed to read products: {}", e))?;
+    to_json(&Page {
+        data,
+        total,
+        limit,
+        offset,
+    })
+}
+
+fn get_product(conn: &Connection, product_id: i64) -> ApiResult {
+    let product = conn
+        .query_row(
+            &format!("SELECT {} FROM products WHERE id = ?1 AND deleted_at IS NULL", PRODUCT_COLUMNS),
+            [product_id],
+            product_from_row,
+        )
+        .optional()
+        .map_err(|e| format!("Failed to load product {}: {}", product_id, e))?
+        .ok_or_else(|| ApiError::not_found(format!("Product {} not found", product_id)))?;
+    to_json(&product)
+}
+
+#[derive(Debug, Serialize)]
+pub struct StockLevel {
+    pub product_id: i64,
+    pub product_name: String,
+    pub category: Option<String>,
+    pub unit_type: String,
+    pub unit: String,
+    /// None when the product's stock or movements cannot be read
+    pub stock: Option<String>,
+    pub stock_quantity: Option<f64>,
+    pub min_stock_alert: String,
+    pub low_stock: bool,
+}
+
+/// Stock of every active product now, or at the end of `date` replayed from the movements
+fn stock_levels(conn: &Connection, query: &Query) -> ApiResult {
+    let date = query.date("date")?;
+    let mut products: Vec<StockLevel> = {
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {} FROM products WHERE {} ORDER BY name, id",
+                PRODUCT_COLUMNS, PRODUCT_FILTER
+            ))
+            .map_err(|e| format!("Failed to query products: {}", e))?;
+        let rows = stmt
+            .query_map(
+                params![None::<String>, query.text("category"), query.flag("include_inactive")],
+                product_from_row,
+            )
+            .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+            .map_err(|e| format!("Failed to read products: {}", e))?;
+        rows.into_iter()
+            .map(|product| StockLevel {
+                product_id: product.id,
+                product_name: product.name,
+                category: product.category,
+                stock: product.stock_quantity.map(|_| product.current_stock),
+                stock_quantity: product.stock_quantity,
+                unit_type: product.unit_type,
+                unit: product.unit,
+                min_stock_alert: product.min_stock_alert,
+                low_stock: product.low_stock,
+            })
+            .collect()
+    };
+
+    if let Some(date) = date {
+        let (stocks, _unreadable) = stock_as_of(conn, date)?;
+        let bases: HashMap<i64, i64> = stocks.into_iter().map(|stock| (stock.product_id, stock.base)).collect();
+        for product in &mut products {
+            let base = bases.get(&product.product_id).copied();
+            let minimum = parse_stock_text(&product.min_stock_alert, &product.unit_type);
+            product.stock = base.map(|base| format_stock_text(base, &product.unit_type));
+            product.stock_quantity = base.map(|base| to_unit_number(base, &product.unit_type));
+            product.low_stock =
+                matches!((base, minimum), (Some(base), Some(minimum)) if minimum > 0 && base <= minimum);
+        }
+    }
+    if query.flag("low_only") {
+        products.retain(|product| product.low_stock);
+    }
+    Ok(json!({ "date": date, "data": to_json(&products)? }))
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApiCustomer {
+    pub id: i64,
+    pub customer_code: String,
+    pub name: String,
+    pub phone: Option<String>,
+    pub address: Option<String>,
+    pub company_name: Option<String>,
+    pub balance: Money,
+    pub credit_limit: Money,
+    pub is_active: bool,
+    pub updated_at: Option<String>,
+}
+
+/// Exact paisa of a money column (`paisa_sql`)
+fn paisa(conn: &Connection, table: &str, column: &str) -> Result<String, String> {
+    paisa_sql(conn, table, column).map_err(|e| format!("Failed to inspect schema: {}", e))
+}
+
+fn customer_columns(conn: &Connection) -> Result<String, String> {
+    Ok(format!(
+        "id, customer_code, name, phone, address, company_name, {}, {}, is_active, updated_at",
+        paisa(conn, "customers", "balance")?,
+        paisa(conn, "customers", "credit_limit")?
+    ))
+}
+
+fn customer_from_row(row: &rusqlite::Row) -> rusqlite::Result<ApiCustomer> {
+    Ok(ApiCustomer {
+        id: row.get(0)?,
+        customer_code: row.get(1)?,
+        name: row.get(2)?,
+        phone: row.get(3)?,
+        address: row.get(4)?,
+        company_name: row.get(5)?,
+        balance: paisa_from_row(row, 6)?,
+        credit_limit: paisa_from_row(row, 7)?,
+        is_active: row.get(8)?,
+        updated_at: row.get(9)?,
+    })
+}
+
+fn list_customers(conn: &Connection, query: &Query) -> ApiResult {
+    let (limit, offset) = query.paging()?;
+    let filter = "id != ?1 AND deleted_at IS NULL AND (?2 IS NULL OR name LIKE ?2 OR phone LIKE ?2 OR customer_code LIKE ?2)
+                  AND (NOT ?3 OR balance != 0)";
+    let total: i64 = conn
+        .query_row(
+            &format!("SELECT COUNT(*) FROM customers WHERE {}", filter),
+            params![GUEST_CUSTOMER_ID, query.like(), query.flag("with_balance")],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to count customers: {}", e))?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM customers WHERE {} ORDER BY name, id LIMIT ?4 OFFSET ?5",
+            customer_columns(conn)?,
+            filter
+        ))
+        .map_err(|e| format!("Failed to query customers: {}", e))?;
+    let data = stmt
+        .query_map(
+            params![
+                GUEST_CUSTOMER_ID,
+                query.like(),
+                query.flag("with_balance"),
+                limit,
+                offset
+            ],
+            customer_from_row,
+        )
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| format!("Failed to read customers: {}", e))?;
+    to_json(&Page {
+        data,
+        total,
+        limit,
+        offset,
+    })
+}
+
+fn load_customer(conn: &Connection, customer_id: i64) -> Result<ApiCustomer, ApiError> {
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM customers WHERE id = ?1 AND id != ?2 AND deleted_at IS NULL",
+            customer_columns(conn)?
+        ),
+        params![customer_id, GUEST_CUSTOMER_ID],
+        customer_from_row,
+    )
+    .optional()
+    .map_err(|e| format!("Failed to load customer {}: {}", customer_id, e))?
+    .ok_or_else(|| ApiError::not_found(format!("Customer {} not found", customer_id)))
+}
+
+fn get_customer(conn: &Connection, customer_id: i64) -> ApiResult {
+    to_json(&load_customer(conn, customer_id)?)
+}
+
+/// A customer's ledger between `from` and `to`, with invoices from archived years
+fn get_statement(conn: &Connection, customer_id: i64, query: &Query) -> ApiResult {
+    load_customer(conn, customer_id)?;
+    let (from_date, to_date) = (query.date("from")?, query.date("to")?);
+    let archives_attached = attach_archives(conn)?;
+    let mut statement = customer_statement(conn, customer_id, from_date, to_date)?;
+    statement.archives_attached = archives_attached;
+    to_json(&statement)
+}
+
+#[derive(Debug, Serialize)]
+pub struct CustomerBalance {
+    pub customer_id: i64,
+    pub customer_name: String,
+    pub phone: Option<String>,
+    pub balance: Money,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Balances {
+    pub date: String,
+    /// Customers who owe money (or are owed it), largest balance first
+    pub customers: Vec<CustomerBalance>,
+    pub total_receivable: Money,
+    /// Vendors with a balance, with their payables by age
+    pub vendors: Vec<VendorAging>,
+    pub total_payable: Money,
+}
+
+fn balances(conn: &Connection) -> ApiResult {
+    let balance = paisa(conn, "customers", "balance")?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT id, name, phone, {balance} FROM customers
+             WHERE id != ?1 AND {balance} != 0 ORDER BY {balance} DESC, name"
+        ))
+        .map_err(|e| format!("Failed to query customer balances: {}", e))?;
+    let customers = stmt
+        .query_map([GUEST_CUSTOMER_ID], |row| {
+            Ok(CustomerBalance {
+                customer_id: row.get(0)?,
+                customer_name: row.get(1)?,
+                phone: row.get(2)?,
+                balance: paisa_from_row(row, 3)?,
+            })
+        })
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| format!("Failed to read customer balances: {}", e))?;
+    let mut vendors = vendor_aging(conn, None)?;
+    vendors.retain(|vendor| !vendor.balance.is_zero());
+
+    to_json(&Balances {
+        date: current_date(conn).map_err(|e| format!("Failed to read date: {}", e))?,
+        total_receivable: customers.iter().map(|customer| customer.balance).sum(),
+        total_payable: vendors.iter().map(|vendor| vendor.balance).sum(),
+        customers,
+        vendors,
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApiInvoiceItem {
+    pub product_id: Option<i64>,
+    pub product_name: String,
+    pub quantity: String,
+    pub unit: String,
+    pub unit_price: Money,
+    pub total_price: Money,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApiInvoice {
+    pub id: i64,
+    pub bill_number: String,
+    pub customer_id: i64,
+    pub customer_name: String,
+    pub date: String,
+    pub time: String,
+    pub grand_total: Money,
+    pub paid_amount: Money,
+    pub remaining_balance: Money,
+    pub payment_method: Option<String>,
+    pub payment_status: Option<String>,
+    pub status: String,
+    /// The invoice belongs to a fiscal year moved to its archive
+    pub archived: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub items: Option<Vec<ApiInvoiceItem>>,
+}
+
+/// Columns of `all_invoices`, the live and archived invoices combined
+fn invoice_columns(conn: &Connection) -> Result<String, String> {
+    Ok(format!(
+        "id, bill_number, customer_id, customer_name, date, time, {}, {}, {},
+         payment_method, payment_status, status, id NOT IN (SELECT id FROM main.invoices)",
+        paisa(conn, "all_invoices", "grand_total")?,
+        paisa(conn, "all_invoices", "paid_amount")?,
+        paisa(conn, "all_invoices", "remaining_balance")?
+    ))
+}
+
+fn invoice_from_row(row: &rusqlite::Row) -> rusqlite::Result<ApiInvoice> {
+    Ok(ApiInvoice {
+        id: row.get(0)?,
+        bill_number: row.get(1)?,
+        customer_id: row.get(2)?,
+        customer_name: row.get(3)?,
+        date: row.get(4)?,
+        time: row.get(5)?,
+        grand_total: paisa_from_row(row, 6)?,
+        paid_amount: paisa_from_row(row, 7)?,
+        remaining_balance: paisa_from_row(row, 8)?,
+        payment_method: row.get(9)?,
+        payment_status: row.get(10)?,
+        status: row.get(11)?,
+        archived: row.get(12)?,
+        items: None,
+    })
+}
+
+/// Invoices between `from` and `to`, of a customer or status, live and archived
+fn list_invoices(conn: &Connection, query: &Query) -> ApiResult {
+    let (limit, offset) = query.paging()?;
+    let (from_date, to_date) = (query.date("from")?, query.date("to")?);
+    let customer_id = query.number("customer_id")?;
+    let status = query.text("status");
+    attach_archives(conn)?;
+
+    let filter = "(?1 IS NULL OR date >= ?1) AND (?2 IS NULL OR date <= ?2)
+                  AND (?3 IS NULL OR customer_id = ?3) AND (?4 IS NULL OR status = ?4)";
+    let total: i64 = conn
+        .query_row(
+            &format!("SELECT COUNT(*) FROM all_invoices WHERE {}", filter),
+            params![from_date, to_date, customer_id, status],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to count invoices: {}", e))?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM all_invoices WHERE {} ORDER BY date DESC, time DESC, id DESC LIMIT ?5 OFFSET ?6",
+            invoice_columns(conn)?,
+            filter
+        ))
+        .map_err(|e| format!("Failed to query invoices: {}", e))?;
+    let data = stmt
+        .query_map(
+            params![from_date, to_date, customer_id, status, limit, offset],
+            invoice_from_row,
+        )
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| format!("Failed to read invoices: {}", e))?;
+    to_json(&Page {
+        data,
+        total,
+        limit,
+        offset,
+    })
+}
+
+fn get_invoice(conn: &Connection, invoice_id: i64) -> ApiResult {
+    attach_archives(conn)?;
+    let mut invoice = conn
+        .query_row(
+            &format!("SELECT {} FROM all_invoices WHERE id = ?1", invoice_columns(conn)?),
+            [invoice_id],
+            invoice_from_row,
+        )
+        .optional()
+        .map_err(|e| format!("Failed to load invoice {}: {}", invoice_id, e))?
+        .ok_or_else(|| ApiError::not_found(format!("Invoice {} not found", invoice_id)))?;
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT product_id, product_name, quantity, unit, {}, {}
+             FROM all_invoice_items WHERE invoice_id = ?1 ORDER BY id",
+            paisa(conn, "all_invoice_items", "unit_price")?,
+            paisa(conn, "all_invoice_items", "total_price")?
+        ))
+        .map_err(|e| format!("Failed to query invoice items: {}", e))?;
+    let items = stmt
+        .query_map([invoice_id], |row| {
+            Ok(ApiInvoiceItem {
+                product_id: row.get(0)?,
+                product_name: row.get(1)?,
+                quantity: value_to_text(row.get(2)?),
+                unit: row.get(3)?,
+                unit_price: paisa_from_row(row, 4)?,
+                total_price: paisa_from_row(row, 5)?,
+            })
+        })
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| format!("Failed to read items of invoice {}: {}", invoice_id, e))?;
+    invoice.items = Some(items);
+    to_json(&invoice)
+}
+
+#[derive(Debug, Serialize)]
+pub struct DailyLedgerEntry {
+    pub id: i64,
+    pub time: String,
+    /// "incoming", "outgoing" or "adjustment"
+    pub entry_type: String,
+    pub category: String,
+    pub description: String,
+    pub amount: Money,
+    pub customer_name: Option<String>,
+    pub vendor_name: Option<String>,
+    pub payment_method: Option<String>,
+    pub payment_channel_name: Option<String>,
+    pub reference_type: Option<String>,
+    pub bill_number: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DailyLedger {
+    pub closing: DayClosing,
+    pub entries: Vec<DailyLedgerEntry>,
+}
+
+/// A day's entries with its channel balances, as closed or as they stand in
+/// the journal (today by default). A read request takes no write lock.
+fn daily_ledger(conn: &mut Connection, query: &Query) -> ApiResult {
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Deferred)
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let date = match query.text("date") {
+        Some(date) => date.to_string(),
+        None => current_date(&tx).map_err(|e| format!("Failed to read date: {}", e))?,
+    };
+    let closing = day_closing(&tx, &date).map_err(ApiError::bad_request)?;
+
+    let entries = {
+        let mut stmt = tx
+            .prepare(&format!(
+                "SELECT id, time, type, category, description, {}, customer_name, vendor_name,
+                        payment_method, payment_channel_name, reference_type, bill_number
+                 FROM ledger_entries WHERE date = ?1 ORDER BY time, id",
+                paisa(&tx, "ledger_entries", "amount")?
+            ))
+            .map_err(|e| format!("Failed to query ledger entries: {}", e))?;
+        let rows = stmt
+            .query_map([&closing.date], |row| {
+                Ok(DailyLedgerEntry {
+                    id: row.get(0)?,
+                    time: row.get(1)?,
+                    entry_type: row.get(2)?,
+                    category: row.get(3)?,
+                    description: row.get(4)?,
+                    amount: paisa_from_row(row, 5)?,
+                    customer_name: row.get(6)?,
+                    vendor_name: row.get(7)?,
+                    payment_method: row.get(8)?,
+                    payment_channel_name: row.get(9)?,
+                    reference_type: row.get(10)?,
+                    bill_number: row.get(11)?,
+                })
+            })
+            .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+            .map_err(|e| format!("Failed to read ledger entries: {}", e))?;
+        rows
+    };
+    tx.finish()
+        .map_err(|e| format!("Failed to finish reading the daily ledger: {}", e))?;
+
+    to_json(&DailyLedger { closing, entries })
+}
+
+fn search_books(conn: &Connection, query: &Query) -> ApiResult {
+    let q = query.text("q").ok_or_else(|| ApiError::bad_request("q is required"))?;
+    let types: Option<Vec<String>> = query
+        .text("types")
+        .map(|types| types.split(',').map(|entity| entity.trim().to_string()).collect());
+    let limit = query.number("limit")?.unwrap_or(20).clamp(1, MAX_LIMIT) as usize;
+    let hits: Vec<SearchHit> = search::search(conn, q, types.as_deref(), limit)?;
+    to_json(&hits)
+}
+
+// ===================================================================
+// STOCK COUNTS
+// ===================================================================
+
+#[derive(Debug, Deserialize)]
+pub struct StockCountRequest {
+    pub product_id: i64,
+    /// As the app writes stock ("12-500", "150") or a plain number
+    pub counted_stock: JsonValue,
+    pub counted_by: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApiStockCount {
+    pub id: i64,
+    pub product_id: i64,
+    pub product_name: String,
+    pub counted_stock: String,
+    pub system_stock: String,
+    /// Counted minus recorded stock when the count was submitted
+    pub difference: Option<String>,
+    pub counted_by: Option<String>,
+    pub notes: Option<String>,
+    pub key_name: String,
+    /// "pending" or "reviewed"
+    pub status: String,
+    pub submitted_at: String,
+    pub reviewed_by: Option<String>,
+    pub reviewed_at: Option<String>,
+}
+
+fn load_stock_counts(
+    conn: &Connection,
+    filter: &str,
+    value: &dyn rusqlite::ToSql,
+) -> Result<Vec<ApiStockCount>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT c.id, c.product_id, c.product_name, c.unit_type, c.counted_stock, c.counted_base,
+                    c.system_stock, c.system_base, c.counted_by, c.notes, k.name, c.status,
+                    c.submitted_at, c.reviewed_by, c.reviewed_at
+             FROM api_stock_counts c JOIN api_keys k ON k.id = c.api_key_id
+             WHERE {} ORDER BY c.id DESC",
+            filter
+        ))
+        .map_err(|e| format!("Failed to query stock counts: {}", e))?;
+    let counts = stmt
+        .query_map([value], |row| {
+            let unit_type: String = row.get(3)?;
+            let counted_base: i64 = row.get(5)?;
+            let system_base: Option<i64> = row.get(7)?;
+            Ok(ApiStockCount {
+                id: row.get(0)?,
+                product_id: row.get(1)?,
+                product_name: row.get(2)?,
+                counted_stock: row.get(4)?,
+                system_stock: row.get(6)?,
+                difference: system_base.map(|system| format_stock_text(counted_base - system, &unit_type)),
+                counted_by: row.get(8)?,
+                notes: row.get(9)?,
+                key_name: row.get(10)?,
+                status: row.get(11)?,
+                submitted_at: row.get(12)?,
+                reviewed_by: row.get(13)?,
+                reviewed_at: row.get(14)?,
+            })
+        })
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| format!("Failed to read stock counts: {}", e))?;
+    Ok(counts)
+}
+
+fn submit_stock_count(conn: &Connection, key: &ApiKey, count: StockCountRequest) -> Result<ApiStockCount, ApiError> {
+    let counted = match &count.counted_stock {
+        JsonValue::String(text) => text.trim().to_string(),
+        JsonValue::Number(number) => number.to_string(),
+        _ => return Err(ApiError::bad_request("counted_stock must be a string or a number")),
+    };
+    let (product_name, unit_type, current_stock): (String, String, String) = conn
+        .query_row(
+            "SELECT name, unit_type, current_stock FROM products WHERE id = ?1 AND deleted_at IS NULL",
+            [count.product_id],
+            |row| Ok((row.get(0)?, row.get(1)?, value_to_text(row.get(2)?))),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to load product {}: {}", count.product_id, e))?
+        .ok_or_else(|| ApiError::not_found(format!("Product {} not found", count.product_id)))?;
+    let counted_base = parse_stock_text(&counted, &unit_type)
+        .filter(|base| *base >= 0)
+        .ok_or_else(|| ApiError::bad_request(format!("Cannot read '{}' as a {} stock", counted, unit_type)))?;
+
+    let trimmed = |text: &Option<String>| {
+        text.as_deref()
+            .map(str::trim)
+            .filter(|text| !text.is_empty())
+            .map(str::to_string)
+    };
+    conn.execute(
+        "INSERT INTO api_stock_counts (product_id, product_name, unit_type, counted_stock, counted_base,
+                                       system_stock, system_base, counted_by, notes, api_key_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            count.product_id,
+            product_name,
+            unit_type,
+            format_stock_text(counted_base, &unit_type),
+            counted_base,
+            current_stock,
+            parse_stock_text(&current_stock, &unit_type),
+            trimmed(&count.counted_by),
+            trimmed(&count.notes),
+            key.id
+        ],
+    )
+    .map_err(|e| format!("Failed to record stock count: {}", e))?;
+
+    let id = conn.last_insert_rowid();
+    load_stock_counts(conn, "c.id = ?1", &id)?
+        .pop()
+        .ok_or_else(|| ApiError::from(format!("Stock count {} vanished", id)))
+}
+
+// ===================================================================
+// OPENAPI
+// ===================================================================
+
+fn openapi() -> JsonValue {
+    let reference = |name: &str| json!({ "$ref": format!("#/components/schemas/{}", name) });
+    let page = |name: &str| {
+        json!({
+            "type": "object",
+            "properties": {
+                "data": { "type": "array", "items": reference(name) },
+                "total": { "type": "integer" },
+                "limit": { "type": "integer" },
+                "offset": { "type": "integer" }
+            }
+        })
+    };
+    let param = |name: &str, location: &str, kind: &str, description: &str| {
+        json!({
+            "name": name,
+            "in": location,
+            "required": location == "path",
+            "description": description,
+            "schema": if kind == "date" { json!({ "type": "string", "format": "date" }) } else { json!({ "type": kind }) }
+        })
+    };
+    let paging = || {
+        vec![
+            param("limit", "query", "integer", "Rows per page (1-500, default 100)"),
+            param("offset", "query", "integer", "Rows to skip"),
+        ]
+    };
+    let get = |summary: &str, parameters: Vec<JsonValue>, schema: JsonValue| {
+        json!({
+            "get": {
+                "summary": summary,
+                "parameters": parameters,
+                "responses": {
+                    "200": { "description": "OK", "content": { "application/json": { "schema": schema } } },
+                    "default": { "$ref": "#/components/responses/Error" }
+                }
+            }
+        })
+    };
+    let with = |mut parameters: Vec<JsonValue>, extra: Vec<JsonValue>| {
+        parameters.extend(extra);
+        parameters
+    };
+    let money = json!({ "type": "number", "description": "Rupees" });
+    let text = json!({ "type": "string" });
+    let nullable_text = json!({ "type": "string", "nullable": true });
+    let id_param = |what: &str| param("id", "path", "integer", &format!("{} id", what));
+
+    let paths = json!({
+        "/products": get("List products", with(paging(), vec![
+            param("q", "query", "string", "Name, SKU or barcode contains"),
+            param("category", "query", "string", "Exact category"),
+            param("include_inactive", "query", "boolean", "Include inactive and discontinued products")
+        ]), page("Product")),
+        "/products/{id}": get("Get a product", vec![id_param("Product")], reference("Product")),
+        "/stock": get("Stock of every product, now or at the end of a day", vec![
+            param("date", "query", "date", "Replay the stock movements up to the end of this day"),
+            param("category", "query", "string", "Exact category"),
+            param("low_only", "query", "boolean", "Only products at or below their minimum stock"),
+            param("include_inactive", "query", "boolean", "Include inactive and discontinued products")
+        ], json!({
+            "type": "object",
+            "properties": {
+                "date": { "type": "string", "format": "date", "nullable": true },
+                "data": { "type": "array", "items": reference("StockLevel") }
+            }
+        })),
+        "/customers": get("List customers", with(paging(), vec![
+            param("q", "query", "string", "Name, phone or customer code contains"),
+            param("with_balance", "query", "boolean", "Only customers with a balance")
+        ]), page("Customer")),
+        "/customers/{id}": get("Get a customer", vec![id_param("Customer")], reference("Customer")),
+        "/customers/{id}/statement": get("A customer's ledger with running balances", vec![
+            id_param("Customer"),
+            param("from", "query", "date", "First day"),
+            param("to", "query", "date", "Last day")
+        ], json!({ "type": "object" })),
+        "/balances": get("Customer receivables and vendor payables", vec![], reference("Balances")),
+        "/invoices": get("List invoices, newest first", with(paging(), vec![
+            param("from", "query", "date", "First day"),
+            param("to", "query", "date", "Last day"),
+            param("customer_id", "query", "integer", "Customer id"),
+            param("status", "query", "string", "Invoice status")
+        ]), page("Invoice")),
+        "/invoices/{id}": get("Get an invoice with its items", vec![id_param("Invoice")], reference("Invoice")),
+        "/daily-ledger": get("A day's ledger entries and channel balances", vec![
+            param("date", "query", "date", "The day (today by default)")
+        ], json!({
+            "type": "object",
+            "properties": {
+                "closing": { "type": "object", "description": "Opening and closing balance per payment channel" },
+                "entries": { "type": "array", "items": reference("LedgerEntry") }
+            }
+        })),
+        "/search": get("Search products, customers, invoices and vendors", vec![
+            param("q", "query", "string", "Search words"),
+            param("types", "query", "string", "Comma-separated: product, customer, invoice, vendor"),
+            param("limit", "query", "integer", "Most hits (default 20)")
+        ], json!({ "type": "array", "items": { "type": "object" } })),
+        "/stock-counts": {
+            "post": {
+                "summary": "Submit a physical stock count for review (needs a key allowed to submit counts)",
+                "requestBody": {
+                    "required": true,
+                    "content": { "application/json": { "schema": reference("StockCountRequest") } }
+                },
+                "responses": {
+                    "200": { "description": "Queued", "content": { "application/json": { "schema": reference("StockCount") } } },
+                    "default": { "$ref": "#/components/responses/Error" }
+                }
+            }
+        }
+    });
+    let schemas = json!({
+        "Product": { "type": "object", "properties": {
+            "id": { "type": "integer" }, "name": text, "category": nullable_text, "sku": nullable_text,
+            "barcode": nullable_text, "unit_type": text, "unit": text,
+            "current_stock": { "type": "string", "description": "As the app shows it, e.g. 12-500 for kg-grams" },
+            "stock_quantity": { "type": "number", "nullable": true, "description": "Units (decimal kg for weight)" },
+            "min_stock_alert": text, "low_stock": { "type": "boolean" }, "rate": money, "cost_price": money,
+            "status": text, "updated_at": nullable_text
+        } },
+        "StockLevel": { "type": "object", "properties": {
+            "product_id": { "type": "integer" }, "product_name": text, "category": nullable_text,
+            "unit_type": text, "unit": text, "stock": nullable_text,
+            "stock_quantity": { "type": "number", "nullable": true }, "min_stock_alert": text,
+            "low_stock": { "type": "boolean" }
+        } },
+        "Customer": { "type": "object", "properties": {
+            "id": { "type": "integer" }, "customer_code": text, "name": text, "phone": nullable_text,
+            "address": nullable_text, "company_name": nullable_text, "balance": money,
+            "credit_limit": money, "is_active": { "type": "boolean" }, "updated_at": nullable_text
+        } },
+        "Balances": { "type": "object", "properties": {
+            "date": { "type": "string", "format": "date" },
+            "customers": { "type": "array", "items": { "type": "object", "properties": {
+                "customer_id": { "type": "integer" }, "customer_name": text, "phone": nullable_text, "balance": money
+            } } },
+            "total_receivable": money,
+            "vendors": { "type": "array", "items": { "type": "object", "description": "Vendor balance and payables by age" } },
+            "total_payable": money
+        } },
+        "Invoice": { "type": "object", "properties": {
+            "id": { "type": "integer" }, "bill_number": text, "customer_id": { "type": "integer" },
+            "customer_name": text, "date": { "type": "string", "format": "date" }, "time": text,
+            "grand_total": money, "paid_amount": money, "remaining_balance": money,
+            "payment_method": nullable_text, "payment_status": nullable_text, "status": text,
+            "archived": { "type": "boolean" },
+            "items": { "type": "array", "description": "Only on a single invoice", "items": { "type": "object", "properties": {
+                "product_id": { "type": "integer", "nullable": true }, "product_name": text, "quantity": text,
+                "unit": text, "unit_price": money, "total_price": money
+            } } }
+        } },
+        "LedgerEntry": { "type": "object", "properties": {
+            "id": { "type": "integer" }, "time": text,
+            "entry_type": { "type": "string", "enum": ["incoming", "outgoing", "adjustment"] },
+            "category": text, "description": text, "amount": money, "customer_name": nullable_text,
+            "vendor_name": nullable_text, "payment_method": nullable_text,
+            "payment_channel_name": nullable_text, "reference_type": nullable_text, "bill_number": nullable_text
+        } },
+        "StockCountRequest": { "type": "object", "required": ["product_id", "counted_stock"], "properties": {
+            "product_id": { "type": "integer" },
+            "counted_stock": { "description": "As the app writes stock (12-500, 150) or a number", "oneOf": [text, { "type": "number" }] },
+            "counted_by": text, "notes": text
+        } },
+        "StockCount": { "type": "object", "properties": {
+            "id": { "type": "integer" }, "product_id": { "type": "integer" }, "product_name": text,
+            "counted_stock": text, "system_stock": text, "difference": nullable_text,
+            "counted_by": nullable_text, "notes": nullable_text, "key_name": text,
+            "status": { "type": "string", "enum": ["pending", "reviewed"] }, "submitted_at": text,
+            "reviewed_by": nullable_text, "reviewed_at": nullable_text
+        } }
+    });
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Store Management API",
+            "version": "1.0.0",
+            "description": "Read-only access to the books of this terminal, plus stock counts queued for review. Amounts are in rupees."
+        },
+        "servers": [{ "url": API_PREFIX }],
+        "security": [{ "bearer": [] }, { "header": [] }, { "query": [] }],
+        "paths": paths,
+        "components": {
+            "securitySchemes": {
+                "bearer": { "type": "http", "scheme": "bearer" },
+                "header": { "type": "apiKey", "in": "header", "name": "X-Api-Key" },
+                "query": { "type": "apiKey", "in": "query", "name": "api_key" }
+            },
+            "responses": {
+                "Error": {
+                    "description": "Error",
+                    "content": { "application/json": { "schema": {
+                        "type": "object", "properties": { "error": text }
+                    } } }
+                }
+            },
+            "schemas": schemas
+        }
+    })
+}
+
+// ===================================================================
+// KEYS AND SETTINGS (APP COMMANDS)
+// ===================================================================
+
+#[derive(Debug, Serialize)]
+pub struct ApiKeyInfo {
+    pub id: i64,
+    pub name: String,
+    /// The start of the key, to tell keys apart
+    pub key_prefix: String,
+    pub can_submit_counts: bool,
+    pub created_by: String,
+    pub created_at: String,
+    pub last_used_at: Option<String>,
+    pub request_count: i64,
+    pub revoked_at: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NewApiKey {
+    /// The key itself; it is not stored and cannot be shown again
+    pub key: String,
+    pub info: ApiKeyInfo,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApiStatus {
+    pub settings: ApiSettings,
+    pub listening: bool,
+    /// Base URLs clients can use
+    pub urls: Vec<String>,
+    pub error: Option<String>,
+    pub keys: Vec<ApiKeyInfo>,
+    pub pending_stock_counts: i64,
+}
+
+/// This computer's address on the local network (no packet is sent)
+fn lan_ip() -> Option<String> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("192.168.0.1:80").ok()?;
+    Some(socket.local_addr().ok()?.ip().to_string())
+}
+
+fn load_keys(conn: &Connection, key_id: Option<i64>) -> Result<Vec<ApiKeyInfo>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, name, key_prefix, can_submit_counts, created_by, created_at, last_used_at,
+                    request_count, revoked_at
+             FROM api_keys WHERE ?1 IS NULL OR id = ?1 ORDER BY revoked_at IS NOT NULL, id",
+        )
+        .map_err(|e| format!("Failed to query API keys: {}", e))?;
+    let keys = stmt
+        .query_map([key_id], |row| {
+            Ok(ApiKeyInfo {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                key_prefix: row.get(2)?,
+                can_submit_counts: row.get(3)?,
+                created_by: row.get(4)?,
+                created_at: row.get(5)?,
+                last_used_at: row.get(6)?,
+                request_count: row.get(7)?,
+                revoked_at: row.get(8)?,
+            })
+        })
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| format!("Failed to read API keys: {}", e))?;
+    Ok(keys)
+}
+
+/// Settings, whether the server is listening, keys and the review queue
+pub fn status(conn: &Connection) -> Result<ApiStatus, String> {
+    let settings = load_settings(conn)?;
+    let (listening, error) = match SERVER.lock() {
+        Ok(state) => (state.running.is_some(), state.error.clone()),
+        Err(_) => (false, None),
+    };
+    let mut urls = Vec::new();
+    if listening {
+        urls.push(format!("http://127.0.0.1:{}{}", settings.port, API_PREFIX));
+        if settings.bind == "lan" {
+            if let Some(ip) = lan_ip() {
+                urls.push(format!("http://{}:{}{}", ip, settings.port, API_PREFIX));
+            }
+        }
+    }
+    let pending_stock_counts = conn
+        .query_row(
+            "SELECT COUNT(*) FROM api_stock_counts WHERE status = 'pending'",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to count stock counts: {}", e))?;
+    Ok(ApiStatus {
+        settings,
+        listening,
+        urls,
+        error,
+        keys: load_keys(conn, None)?,
+        pending_stock_counts,
+    })
+}
+
+/// Store the API settings; the server is started, moved or stopped right away
+pub fn configure(
+    conn: &Connection,
+    enabled: bool,
+    bind: &str,
+    port: Option<u16>,
+    changed_by: &str,
+) -> Result<(), String> {
+    require_admin(conn, changed_by, "change the API settings")?;
+    let bind = bind.trim().to_ascii_lowercase();
+    if bind != "localhost" && bind != "lan" {
+        return Err(format!("Unknown API binding '{}' (use localhost or lan)", bind));
+    }
+    let port = port.unwrap_or(DEFAULT_PORT);
+    if port < 1024 {
+        return Err(format!("Port {} is reserved; use 1024 or above", port));
+    }
+    conn.execute(
+        "INSERT INTO api_settings (id, enabled, bind, port, updated_by, updated_at)
+         VALUES (1, ?1, ?2, ?3, ?4, CURRENT_TIMESTAMP)
+         ON CONFLICT(id) DO UPDATE SET enabled = excluded.enabled, bind = excluded.bind, port = excluded.port,
+                                       updated_by = excluded.updated_by, updated_at = excluded.updated_at",
+        params![enabled, bind, port, changed_by.trim()],
+    )
+    .map_err(|e| format!("Failed to save API settings: {}", e))?;
+    Ok(())
+}
+
+/// Create a key; the plain key is returned once and only its hash is kept
+pub fn create_key(
+    conn: &Connection,
+    name: &str,
+    can_submit_counts: bool,
+    created_by: &str,
+) -> Result<NewApiKey, String> {
+    require_admin(conn, created_by, "create API keys")?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("A name is required for the API key".to_string());
+    }
+    let secret: String = conn
+        .query_row("SELECT lower(hex(randomblob(24)))", [], |row| row.get(0))
+        .map_err(|e| format!("Failed to generate API key: {}", e))?;
+    let key = format!("{}{}", KEY_PREFIX, secret);
+    conn.execute(
+        "INSERT INTO api_keys (name, key_prefix, key_hash, can_submit_counts, created_by)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            name,
+            &key[..KEY_PREFIX.len() + 8],
+            key_hash(&key),
+            can_submit_counts,
+            created_by.trim()
+        ],
+    )
+    .map_err(|e| format!("Failed to save API key: {}", e))?;
+    let info = load_keys(conn, Some(conn.last_insert_rowid()))?
+        .pop()
+        .ok_or_else(|| "Failed to read the new API key".to_string())?;
+    Ok(NewApiKey { key, info })
+}
+
+pub fn revoke_key(conn: &Connection, key_id: i64, revoked_by: &str) -> Result<(), String> {
+    require_admin(conn, revoked_by, "revoke API keys")?;
+    let revoked = conn
+        .execute(
+            "UPDATE api_keys SET revoked_by = ?1, revoked_at = CURRENT_TIMESTAMP
+             WHERE id = ?2 AND revoked_at IS NULL",
+            params![revoked_by.trim(), key_id],
+        )
+        .map_err(|e| format!("Failed to revoke API key: {}", e))?;
+    if revoked == 0 {
+        return Err(format!("API key {} not found or already revoked", key_id));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_api_status() -> Result<ApiStatus, String> {
+    status(&open_connection()?)
+}
+
+/// Turn the API on or off, or move it; an address that cannot be bound is reported in the status
+#[tauri::command]
+pub async fn configure_api(
+    enabled: bool,
+    bind: String,
+    port: Option<u16>,
+    changed_by: String,
+) -> Result<ApiStatus, String> {
+    let mut conn = open_connection()?;
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    configure(&tx, enabled, &bind, port, &changed_by)?;
+    audit::record(
+        &tx,
+        &AuditEvent::new("configure", "api")
+            .by(&changed_by)
+            .after(json!({ "enabled": enabled, "bind": bind.trim(), "port": port })),
+    )?;
+    tx.commit()
+        .map_err(|e| format!("Failed to commit API settings: {}", e))?;
+    if let Err(e) = apply_settings(&get_db_path()?) {
+        error!("[API] {}", e);
+    }
+    status(&conn)
+}
+
+#[tauri::command]
+pub async fn create_api_key(name: String, can_submit_counts: bool, created_by: String) -> Result<NewApiKey, String> {
+    let mut conn = open_connection()?;
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let key = create_key(&tx, &name, can_submit_counts, &created_by)?;
+    // The key itself is never recorded
+    audit::record(
+        &tx,
+        &AuditEvent::new("create_key", "api")
+            .entity(key.info.id)
+            .by(&created_by)
+            .after(json!(&key.info)),
+    )?;
+    tx.commit().map_err(|e| format!("Failed to commit API key: {}", e))?;
+    info!("[API] Key {} created by {}", key.info.name, created_by.trim());
+    Ok(key)
+}
+
+#[tauri::command]
+pub async fn revoke_api_key(key_id: i64, revoked_by: String) -> Result<ApiStatus, String> {
+    let mut conn = open_connection()?;
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    revoke_key(&tx, key_id, &revoked_by)?;
+    audit::record(
+        &tx,
+        &AuditEvent::new("revoke_key", "api").entity(key_id).by(&revoked_by),
+    )?;
+    tx.commit()
+        .map_err(|e| format!("Failed to commit API key revocation: {}", e))?;
+    info!("[API] Key {} revoked by {}", key_id, revoked_by.trim());
+    status(&conn)
+}
+
+/// Stock counts submitted through the API, pending ones unless a status is given
+#[tauri::command]
+pub async fn get_api_stock_counts(status: Option<String>) -> Result<Vec<ApiStockCount>, String> {
+    let conn = open_connection()?;
+    let status = status.unwrap_or_else(|| "pending".to_string());
+    load_stock_counts(&conn, "c.status = ?1", &status)
+}
+
+/// Mark a submitted count as dealt with, after any stock adjustment was made in the app
+#[tauri::command]
+pub async fn review_api_stock_count(count_id: i64, reviewed_by: String) -> Result<ApiStockCount, String> {
+    let mut conn = open_connection()?;
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let reviewed = tx
+        .execute(
+            "UPDATE api_stock_counts SET status = 'reviewed', reviewed_by = ?1, reviewed_at = CURRENT_TIMESTAMP
+             WHERE id = ?2 AND status = 'pending'",
+            params![reviewed_by.trim(), count_id],
+        )
+        .map_err(|e| format!("Failed to review stock count: {}", e))?;
+    if reviewed == 0 {
+        return Err(format!("Stock count {} not found or already reviewed", count_id));
+    }
+    audit::record(
+        &tx,
+        &AuditEvent::new("review", "api_stock_count")
+            .entity(count_id)
+            .by(&reviewed_by),
+    )?;
+    tx.commit()
+        .map_err(|e| format!("Failed to commit stock count review: {}", e))?;
+    load_stock_counts(&conn, "c.id = ?1", &count_id)?
+        .pop()
+        .ok_or_else(|| format!("Stock count {} not found", count_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tiny_http::TestRequest;
+
+    /// A reader key, a key that may submit counts and a revoked key
+    fn database(conn: &Connection) {
+        conn.execute_batch(
+            "CREATE TABLE products (
+                id INTEGER PRIMARY KEY, name TEXT, unit_type TEXT, current_stock TEXT, deleted_at TEXT
+            );
+            INSERT INTO products (id, name, unit_type, current_stock) VALUES (1, 'Rice', 'kg-grams', '10');
+            INSERT INTO products (id, name, unit_type, current_stock, deleted_at)
+                VALUES (2, 'Old rice', 'kg-grams', '0', '2026-01-01');",
+        )
+        .unwrap();
+        ensure_schema(conn).unwrap();
+        for (name, key, can_submit_counts, revoked_at) in [
+            ("Reader", "isk_reader", false, None),
+            ("Stock taker", "isk_counter", true, None),
+            ("Lost phone", "isk_revoked", true, Some("2026-02-01")),
+        ] {
+            conn.execute(
+                "INSERT INTO api_keys (name, key_prefix, key_hash, can_submit_counts, created_by, revoked_at)
+                 VALUES (?1, ?2, ?3, ?4, 'admin', ?5)",
+                params![name, key, key_hash(key), can_submit_counts, revoked_at],
+            )
+            .unwrap();
+        }
+    }
+
+    fn header_line(name: &str, value: &str) -> Header {
+        Header::from_bytes(name.as_bytes(), value.as_bytes()).unwrap()
+    }
+
+    fn request(path: &str, headers: &[(&str, &str)]) -> Request {
+        headers
+            .iter()
+            .fold(TestRequest::new().with_path(path), |request, (name, value)| {
+                request.with_header(header_line(name, value))
+            })
+            .into()
+    }
+
+    fn authenticate_url(conn: &Connection, url: &str, headers: &[(&str, &str)]) -> Result<ApiKey, ApiError> {
+        let query = Query(parse_query(url.split_once('?').map_or("", |(_, query)| query)));
+        authenticate(conn, &request(url, headers), &query)
+    }
+
+    fn submit(db_path: &Path, key: &str, body: &'static str) -> ApiResult {
+        let mut request: Request = TestRequest::new()
+            .with_method(Method::Post)
+            .with_path("/api/v1/stock-counts")
+            .with_header(header_line("X-Api-Key", key))
+            .with_body(body)
+            .into();
+        route(&mut request, db_path)
+    }
+
+    #[test]
+    fn query_strings_are_decoded() {
+        assert_eq!(decode_component("12%2F500+kg"), "12/500 kg");
+        assert_eq!(decode_component("%D9%85%D8%AD%D9%85%D8%AF"), "محمد");
+        // Broken escapes are kept as typed
+        assert_eq!(decode_component("100%zz"), "100%zz");
+        assert_eq!(decode_component("50%"), "50%");
+        assert_eq!(decode_component("%4"), "%4");
+
+        let query = parse_query("q=basmati+rice&limit=5&&flag&a%26b=c%3Dd");
+        assert_eq!(query.len(), 4);
+        assert_eq!(query["q"], "basmati rice");
+        assert_eq!(query["limit"], "5");
+        assert_eq!(query["flag"], "");
+        assert_eq!(query["a&b"], "c=d");
+        assert!(parse_query("").is_empty());
+    }
+
+    #[test]
+    fn requests_need_a_live_key() {
+        let conn = Connection::open_in_memory().unwrap();
+        database(&conn);
+
+        let missing = authenticate_url(&conn, "/api/v1/products", &[]).err().unwrap();
+        assert_eq!(missing.status, 401);
+        assert!(missing.message.contains("API key is required"));
+        for key in ["isk_revoked", "isk_unknown"] {
+            let refused = authenticate_url(&conn, "/api/v1/products", &[("X-Api-Key", key)])
+                .err()
+                .unwrap();
+            assert_eq!(
+                (refused.status, refused.message.as_str()),
+                (401, "Unknown or revoked API key")
+            );
+        }
+        // A key without the Bearer scheme is not read from Authorization
+        assert!(authenticate_url(&conn, "/api/v1/products", &[("Authorization", "isk_reader")]).is_err());
+
+        let bearer = authenticate_url(&conn, "/api/v1/products", &[("Authorization", "Bearer isk_reader")]).unwrap();
+        assert_eq!((bearer.name.as_str(), bearer.can_submit_counts), ("Reader", false));
+        let header = authenticate_url(&conn, "/api/v1/products", &[("X-Api-Key", " isk_counter ")]).unwrap();
+        assert!(header.can_submit_counts);
+        let query = authenticate_url(&conn, "/api/v1/products?api_key=isk_reader", &[]).unwrap();
+        assert_eq!(query.name, "Reader");
+
+        let (uses, last_used): (i64, Option<String>) = conn
+            .query_row(
+                "SELECT request_count, last_used_at FROM api_keys WHERE name = 'Reader'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(uses, 2);
+        assert!(last_used.is_some());
+    }
+
+    #[test]
+    fn stock_counts_need_permission_and_a_readable_count() {
+        let dir = std::env::temp_dir().join(format!("api-counts-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let db_path = dir.join("store.db");
+        database(&Connection::open(&db_path).unwrap());
+
+        let status = |result: ApiResult| result.err().map(|error| (error.status, error.message));
+        let valid = r#"{"product_id": 1, "counted_stock": "12-500", "counted_by": " Sara ", "notes": ""}"#;
+        let (code, message) = status(submit(&db_path, "isk_reader", valid)).unwrap();
+        assert_eq!(
+            (code, message.as_str()),
+            (403, "This API key may not submit stock counts")
+        );
+
+        for (body, expected) in [
+            ("{", (400, "Invalid stock count")),
+            (r#"{"counted_stock": "5"}"#, (400, "Invalid stock count")),
+            (
+                r#"{"product_id": 1, "counted_stock": true}"#,
+                (400, "counted_stock must be"),
+            ),
+            (
+                r#"{"product_id": 1, "counted_stock": "twelve"}"#,
+                (400, "Cannot read 'twelve'"),
+            ),
+            (r#"{"product_id": 1, "counted_stock": "-2"}"#, (400, "Cannot read '-2'")),
+            (
+                r#"{"product_id": 2, "counted_stock": "1"}"#,
+                (404, "Product 2 not found"),
+            ),
+        ] {
+            let (code, message) = status(submit(&db_path, "isk_counter", body)).unwrap();
+            assert_eq!(code, expected.0, "{}", body);
+            assert!(message.starts_with(expected.1), "{}: {}", body, message);
+        }
+        let conn = open_connection_at(&db_path).unwrap();
+        let stored: i64 = conn
+            .query_row("SELECT COUNT(*) FROM api_stock_counts", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(stored, 0);
+
+        let count = submit(&db_path, "isk_counter", valid).unwrap();
+        assert_eq!(count["counted_stock"], "12-500");
+        assert_eq!(count["system_stock"], "10");
+        assert_eq!(count["difference"], "2-500");
+        assert_eq!(count["counted_by"], "Sara");
+        assert_eq!(count["notes"], JsonValue::Null);
+        assert_eq!(
+            (count["key_name"].as_str(), count["status"].as_str()),
+            (Some("Stock taker"), Some("pending"))
+        );
+        let stock: String = conn
+            .query_row("SELECT current_stock FROM products WHERE id = 1", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(stock, "10");
+        drop(conn);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
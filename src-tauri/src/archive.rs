@@ -0,0 +1,589 @@
+/*!
+ * HISTORICAL YEAR ARCHIVES
+ * Moving a closed fiscal year out of the live database refreshes the year's
+ * read-only archive file (see fiscal_year.rs) and then deletes the year's
+ * stock movements and settled invoices (with their items and payment
+ * allocations) from the live tables, so they stay small as years
+ * accumulate. Invoices with money still owing or a return against them
+ * stay live.
+ *
+ * A live row is only deleted when an identical copy is in the archive, and
+ * years move oldest first, so every product's remaining movements start
+ * with the opening marker its year-end close wrote. Journal postings of
+ * moved invoices are kept as they are.
+ *
+ * Reporting commands call `attach_archives`, which ATTACHes every moved
+ * year and creates `all_<table>` temp views over the live table plus the
+ * archived rows, so a customer statement or stock history can span any
+ * number of years. Reopening a moved year copies its rows back first.
+ */
+
+use std::path::Path;
+
+use log::info;
+use rusqlite::{params, Connection, OptionalExtension, Transaction, TransactionBehavior};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::audit::{self, AuditEvent};
+use crate::database::{open_connection, table_exists};
+use crate::day_close;
+use crate::fiscal_year::{archive_dir, archive_fiscal_year_to};
+use crate::money::paisa_sql;
+use crate::recycle_bin;
+
+/// Tables whose rows of a moved year live in its archive, parents first
+const ARCHIVED_TABLES: &[&str] = &[
+    "invoices",
+    "invoice_items",
+    "invoice_payments",
+    "invoice_payment_allocations",
+    "stock_movements",
+];
+
+/// Schema name a year's archive is attached under while it is moved or restored
+pub const MOVE_ALIAS: &str = "archive_move";
+
+/// Create the table recording which years have been moved to their archive
+pub fn ensure_schema(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS archive_moves (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            fiscal_year_id INTEGER NOT NULL UNIQUE,
+            archive_path TEXT NOT NULL,
+            invoices_moved INTEGER NOT NULL DEFAULT 0,
+            movements_moved INTEGER NOT NULL DEFAULT 0,
+            moved_by TEXT NOT NULL,
+            moved_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (fiscal_year_id) REFERENCES fiscal_years(id)
+        );",
+    )
+}
+
+/// A moved year: what moved and where it is kept
+#[derive(Debug, Serialize)]
+pub struct ArchivedYear {
+    pub fiscal_year_id: i64,
+    pub name: String,
+    pub start_date: String,
+    pub end_date: String,
+    pub archive_path: String,
+    pub invoices_moved: i64,
+    pub movements_moved: i64,
+    pub moved_by: String,
+    pub moved_at: Option<String>,
+}
+
+fn moved_years(conn: &Connection, fiscal_year_id: Option<i64>) -> Result<Vec<ArchivedYear>, String> {
+    if !table_exists(conn, "archive_moves").map_err(|e| format!("Failed to inspect schema: {}", e))? {
+        return Ok(Vec::new());
+    }
+    let mut stmt = conn
+        .prepare(
+            "SELECT y.id, y.name, y.start_date, y.end_date, m.archive_path, m.invoices_moved, m.movements_moved,
+                    m.moved_by, m.moved_at
+             FROM archive_moves m
+             JOIN fiscal_years y ON y.id = m.fiscal_year_id
+             WHERE ?1 IS NULL OR y.id = ?1
+             ORDER BY y.start_date",
+        )
+        .map_err(|e| format!("Failed to query archived years: {}", e))?;
+    let rows = stmt
+        .query_map([fiscal_year_id], |row| {
+            Ok(ArchivedYear {
+                fiscal_year_id: row.get(0)?,
+                name: row.get(1)?,
+                start_date: row.get(2)?,
+                end_date: row.get(3)?,
+                archive_path: row.get(4)?,
+                invoices_moved: row.get(5)?,
+                movements_moved: row.get(6)?,
+                moved_by: row.get(7)?,
+                moved_at: row.get(8)?,
+            })
+        })
+        .map_err(|e| format!("Failed to read archived years: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read archived year: {}", e))
+}
+
+/// The moved year of `fiscal_year_id`, if it has been moved
+pub fn moved_year(conn: &Connection, fiscal_year_id: i64) -> Result<Option<ArchivedYear>, String> {
+    Ok(moved_years(conn, Some(fiscal_year_id))?.pop())
+}
+
+/// Attach an archive file (never inside a transaction)
+pub fn attach(conn: &Connection, path: &str, alias: &str) -> Result<(), String> {
+    if !Path::new(path).exists() {
+        return Err(format!("Archive {} is missing; restore it from a backup", path));
+    }
+    conn.execute("ATTACH DATABASE ?1 AS ?2", params![path, alias])
+        .map_err(|e| format!("Failed to attach archive {}: {}", path, e))?;
+    Ok(())
+}
+
+pub fn detach(conn: &Connection, alias: &str) -> Result<(), String> {
+    conn.execute("DETACH DATABASE ?1", [alias])
+        .map_err(|e| format!("Failed to detach archive: {}", e))?;
+    Ok(())
+}
+
+/// Columns of a live table that every given schema also has
+fn common_columns(conn: &Connection, table: &str, schemas: &[&str]) -> Result<Vec<String>, String> {
+    let mut columns: Vec<String> = Vec::new();
+    for (index, schema) in std::iter::once(&"main").chain(schemas.iter()).enumerate() {
+        let mut stmt = conn
+            .prepare("SELECT name FROM pragma_table_info(?1, ?2)")
+            .map_err(|e| format!("Failed to read columns of {}: {}", table, e))?;
+        let names: Vec<String> = stmt
+            .query_map(params![table, schema], |row| row.get(0))
+            .map_err(|e| format!("Failed to read columns of {}: {}", table, e))?
+            .collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read column of {}: {}", table, e))?;
+        if index == 0 {
+            columns = names;
+        } else {
+            columns.retain(|column| names.contains(column));
+        }
+    }
+    Ok(columns)
+}
+
+/// Condition selecting a table's rows that belong to a year, in the given schema
+fn year_rows_sql(table: &str, schema: &str, start_date: &str, end_date: &str) -> String {
+    let in_year = |column: &str| format!("substr({column}, 1, 10) BETWEEN '{start_date}' AND '{end_date}'");
+    match table {
+        "invoices" | "stock_movements" => in_year("date"),
+        _ => format!(
+            "invoice_id IN (SELECT id FROM {schema}.invoices WHERE {})",
+            in_year("date")
+        ),
+    }
+}
+
+/// Attach every moved year's archive and create `all_<table>` temp views that
+/// combine the live rows with the archived ones. Returns the attached years.
+pub fn attach_archives(conn: &Connection) -> Result<Vec<String>, String> {
+    let years = moved_years(conn, None)?;
+    let mut aliases = Vec::with_capacity(years.len());
+    for year in &years {
+        let alias = format!("archive_{}", year.fiscal_year_id);
+        attach(conn, &year.archive_path, &alias)?;
+        aliases.push(alias);
+    }
+
+    for table in ARCHIVED_TABLES {
+        if !table_exists(conn, table).map_err(|e| format!("Failed to inspect schema: {}", e))? {
+            continue;
+        }
+        let alias_refs: Vec<&str> = aliases.iter().map(String::as_str).collect();
+        let columns = common_columns(conn, table, &alias_refs)?.join(", ");
+        let mut select = format!("SELECT {columns} FROM main.{table}");
+        for (year, alias) in years.iter().zip(&aliases) {
+            select.push_str(&format!(
+                " UNION ALL SELECT {columns} FROM {alias}.{table} WHERE {} AND id NOT IN (SELECT id FROM main.{table})",
+                year_rows_sql(table, alias, &year.start_date, &year.end_date)
+            ));
+        }
+        conn.execute_batch(&format!(
+            "DROP VIEW IF EXISTS temp.all_{table};
+             CREATE TEMP VIEW all_{table} AS {select};"
+        ))
+        .map_err(|e| format!("Failed to combine archived {}: {}", table, e))?;
+    }
+
+    Ok(years.into_iter().map(|year| year.name).collect())
+}
+
+/// Drop the day locks of the archived tables, run `body`, and put the locks back
+fn with_day_locks_lifted<T>(tx: &Transaction, body: impl FnOnce() -> Result<T, String>) -> Result<T, String> {
+    for table in ARCHIVED_TABLES {
+        tx.execute_batch(&format!(
+            "DROP TRIGGER IF EXISTS trg_{table}_day_lock_insert;
+             DROP TRIGGER IF EXISTS trg_{table}_day_lock_delete;"
+        ))
+        .map_err(|e| format!("Failed to lift day locks of {}: {}", table, e))?;
+    }
+    let result = body()?;
+    day_close::ensure_schema(tx).map_err(|e| format!("Failed to restore day locks: {}", e))?;
+    Ok(result)
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArchiveMoveResult {
+    pub archived_year: ArchivedYear,
+    /// Invoices of the year left live because money is still owing or they were returned
+    pub invoices_kept: i64,
+}
+
+/// Delete a closed year's settled invoices and stock movements from the live
+/// tables. The year's refreshed archive must be attached as `alias`.
+pub fn move_year_in_transaction(
+    tx: &Transaction,
+    fiscal_year_id: i64,
+    alias: &str,
+    archive_path: &str,
+    moved_by: &str,
+) -> Result<ArchiveMoveResult, String> {
+    let (name, start_date, end_date, status): (String, String, String, String) = tx
+        .query_row(
+            "SELECT name, start_date, end_date, status FROM fiscal_years WHERE id = ?1",
+            [fiscal_year_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to load fiscal year: {}", e))?
+        .ok_or_else(|| format!("Fiscal year {} not found", fiscal_year_id))?;
+    if status != "closed" {
+        return Err(format!("{} must be closed before it is moved to its archive", name));
+    }
+    if moved_year(tx, fiscal_year_id)?.is_some() {
+        return Err(format!("{} has already been moved to its archive", name));
+    }
+    let earlier: Option<String> = tx
+        .query_row(
+            "SELECT name FROM fiscal_years y
+             WHERE start_date < ?1 AND NOT EXISTS (SELECT 1 FROM archive_moves m WHERE m.fiscal_year_id = y.id)
+             ORDER BY start_date LIMIT 1",
+            [&start_date],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to check earlier fiscal years: {}", e))?;
+    if let Some(earlier) = earlier {
+        return Err(format!("{} must be moved to its archive before {}", earlier, name));
+    }
+
+    let present = |table: &str| table_exists(tx, table).map_err(|e| format!("Failed to inspect schema: {}", e));
+    let identical = |table: &str, key: &str| -> Result<String, String> {
+        let columns = common_columns(tx, table, &[alias])?.join(", ");
+        Ok(format!(
+            "NOT EXISTS (SELECT {columns} FROM main.{table} WHERE {key} = i.id
+                         EXCEPT SELECT {columns} FROM {alias}.{table} WHERE {key} = i.id)"
+        ))
+    };
+
+    // Settled invoices of the year whose rows are all in the archive unchanged
+    let mut conditions = vec![
+        year_rows_sql("invoices", "main", &start_date, &end_date),
+        format!(
+            "(i.status = 'cancelled' OR {} <= 0)",
+            paisa_sql(tx, "invoices", "i.remaining_balance").map_err(|e| format!("Failed to inspect schema: {}", e))?
+        ),
+    ];
+    if present("returns")? {
+        conditions.push("NOT EXISTS (SELECT 1 FROM main.returns r WHERE r.original_invoice_id = i.id)".to_string());
+    }
+    for table in ARCHIVED_TABLES.iter().filter(|table| **table != "stock_movements") {
+        if present(table)? {
+            let key = if *table == "invoices" { "id" } else { "invoice_id" };
+            conditions.push(identical(table, key)?);
+        }
+    }
+    tx.execute_batch(&format!(
+        "DROP TABLE IF EXISTS temp.moving_invoices;
+         CREATE TEMP TABLE moving_invoices AS
+         SELECT i.id FROM main.invoices i WHERE {};",
+        conditions.join(" AND ")
+    ))
+    .map_err(|e| format!("Failed to select invoices of {}: {}", name, e))?;
+
+    let count = |sql: &str| -> Result<i64, String> {
+        tx.query_row(sql, [], |row| row.get(0))
+            .map_err(|e| format!("Failed to count rows of {}: {}", name, e))
+    };
+    let invoices_moved = count("SELECT COUNT(*) FROM temp.moving_invoices")?;
+    let invoices_kept = count(&format!(
+        "SELECT COUNT(*) FROM main.invoices WHERE {}",
+        year_rows_sql("invoices", "main", &start_date, &end_date)
+    ))? - invoices_moved;
+
+    // Movements move all together or not at all: replay needs the year complete
+    let movements = year_rows_sql("stock_movements", "main", &start_date, &end_date);
+    let movement_columns = common_columns(tx, "stock_movements", &[alias])?.join(", ");
+    let movements_moved = count(&format!("SELECT COUNT(*) FROM main.stock_movements WHERE {movements}"))?;
+    let changed = count(&format!(
+        "SELECT COUNT(*) FROM (SELECT {movement_columns} FROM main.stock_movements WHERE {movements}
+                               EXCEPT SELECT {movement_columns} FROM {alias}.stock_movements)"
+    ))?;
+    if changed > 0 {
+        return Err(format!(
+            "{} stock movements of {} changed while the archive was written; try again",
+            changed, name
+        ));
+    }
+
+    with_day_locks_lifted(tx, || {
+        for table in ARCHIVED_TABLES.iter().rev() {
+            if !present(table)? {
+                continue;
+            }
+            let rows = match *table {
+                "stock_movements" => movements.clone(),
+                "invoices" => "id IN (SELECT id FROM temp.moving_invoices)".to_string(),
+                _ => "invoice_id IN (SELECT id FROM temp.moving_invoices)".to_string(),
+            };
+            tx.execute(&format!("DELETE FROM main.{table} WHERE {rows}"), [])
+                .map_err(|e| format!("Failed to move {} of {}: {}", table, name, e))?;
+        }
+        Ok(())
+    })?;
+    tx.execute_batch("DROP TABLE temp.moving_invoices;")
+        .map_err(|e| format!("Failed to clean up: {}", e))?;
+
+    tx.execute(
+        "INSERT INTO archive_moves (fiscal_year_id, archive_path, invoices_moved, movements_moved, moved_by)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![fiscal_year_id, archive_path, invoices_moved, movements_moved, moved_by],
+    )
+    .map_err(|e| format!("Failed to record move of {}: {}", name, e))?;
+
+    Ok(ArchiveMoveResult {
+        archived_year: moved_year(tx, fiscal_year_id)?.ok_or_else(|| format!("Failed to record move of {}", name))?,
+        invoices_kept,
+    })
+}
+
+/// Copy a moved year's rows back from its archive (attached as `alias`)
+/// into the live tables. Returns the number of rows restored.
+pub fn restore_year_in_transaction(tx: &Transaction, fiscal_year_id: i64, alias: &str) -> Result<usize, String> {
+    let year = moved_year(tx, fiscal_year_id)?.ok_or_else(|| "This fiscal year has not been moved".to_string())?;
+
+    let restored = with_day_locks_lifted(tx, || {
+        let mut restored = 0;
+        for table in ARCHIVED_TABLES {
+            if !table_exists(tx, table).map_err(|e| format!("Failed to inspect schema: {}", e))? {
+                continue;
+            }
+            let columns = common_columns(tx, table, &[alias])?.join(", ");
+            restored += tx
+                .execute(
+                    &format!(
+                        "INSERT INTO main.{table} ({columns})
+                         SELECT {columns} FROM {alias}.{table}
+                         WHERE {} AND id NOT IN (SELECT id FROM main.{table})",
+                        year_rows_sql(table, alias, &year.start_date, &year.end_date)
+                    ),
+                    [],
+                )
+                .map_err(|e| format!("Failed to restore {} of {}: {}", table, year.name, e))?;
+        }
+        Ok(restored)
+    })?;
+
+    tx.execute("DELETE FROM archive_moves WHERE fiscal_year_id = ?1", [fiscal_year_id])
+        .map_err(|e| format!("Failed to record restore of {}: {}", year.name, e))?;
+    Ok(restored)
+}
+
+/// Years whose invoices and stock movements have been moved to archives
+#[tauri::command]
+pub async fn get_archived_years() -> Result<Vec<ArchivedYear>, String> {
+    let conn = open_connection()?;
+    moved_years(&conn, None)
+}
+
+/// Move a closed fiscal year's settled invoices and stock movements out of
+/// the live database into its read-only archive
+#[tauri::command]
+pub async fn move_fiscal_year_to_archive(fiscal_year_id: i64, moved_by: String) -> Result<ArchiveMoveResult, String> {
+    let mut conn = open_connection()?;
+    let moved_by = match moved_by.trim() {
+        "" => "system".to_string(),
+        user => user.to_string(),
+    };
+
+    // A fresh copy, so everything deleted below is in the archive as it is now
+    let year = archive_fiscal_year_to(&conn, fiscal_year_id, &archive_dir()?)?;
+    let archive_path = year
+        .archive_path
+        .ok_or_else(|| format!("{} has no archive", year.name))?;
+
+    attach(&conn, &archive_path, MOVE_ALIAS)?;
+    let result = (|| {
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+        // Thousands of deletions; the archive holds the rows, the log one entry
+        let actor = audit::act_as(&tx, &moved_by, false)?;
+        // The archive keeps the rows, so the recycle bin guard does not apply
+        let permit = recycle_bin::permit_hard_delete(&tx)?;
+        let result = move_year_in_transaction(&tx, fiscal_year_id, MOVE_ALIAS, &archive_path, &moved_by)?;
+        recycle_bin::revoke_hard_delete(&tx, permit)?;
+        audit::restore_actor(&tx, actor)?;
+        audit::record(
+            &tx,
+            &AuditEvent::new("move_to_archive", "fiscal_year")
+                .entity(fiscal_year_id)
+                .by(&moved_by)
+                .after(json!(&result)),
+        )?;
+        tx.commit()
+            .map_err(|e| format!("Failed to commit archive move: {}", e))?;
+        Ok::<_, String>(result)
+    })();
+    detach(&conn, MOVE_ALIAS)?;
+    let result = result?;
+
+    info!(
+        "[ARCHIVE] {} moved to {}: {} invoices and {} stock movements ({} invoices kept live)",
+        result.archived_year.name,
+        result.archived_year.archive_path,
+        result.archived_year.invoices_moved,
+        result.archived_year.movements_moved,
+        result.invoices_kept
+    );
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::customer_balance::customer_statement;
+    use crate::fiscal_year::{close_fiscal_year_in_transaction, fiscal_years};
+    use crate::money::Money;
+    use crate::stock_engine::check_stock;
+
+    /// Ali's 2025: I00001 still owes Rs 1,000, I00002 is settled; cement
+    /// moves with both and is restocked in 2026
+    fn database() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE customers (id INTEGER PRIMARY KEY, name TEXT NOT NULL, balance REAL NOT NULL DEFAULT 0);
+            CREATE TABLE customer_ledger_entries (
+                id INTEGER PRIMARY KEY, customer_id INTEGER, customer_name TEXT, entry_type TEXT,
+                transaction_type TEXT, amount REAL, balance_before REAL, balance_after REAL, description TEXT,
+                reference_type TEXT, reference_id INTEGER, reference_number TEXT, invoice_id INTEGER, date TEXT,
+                time TEXT, notes TEXT, created_by TEXT
+            );
+            CREATE TABLE invoices (
+                id INTEGER PRIMARY KEY, bill_number TEXT, customer_id INTEGER, customer_name TEXT, grand_total REAL,
+                remaining_balance REAL, status TEXT, date TEXT, time TEXT
+            );
+            CREATE TABLE invoice_items (
+                id INTEGER PRIMARY KEY, invoice_id INTEGER, product_id INTEGER, product_name TEXT, quantity TEXT,
+                unit TEXT, unit_price REAL, total_price REAL, cost_price REAL, is_non_stock_item INTEGER,
+                is_misc_item INTEGER
+            );
+            CREATE TABLE vendors (id INTEGER PRIMARY KEY, name TEXT NOT NULL, balance REAL NOT NULL DEFAULT 0);
+            CREATE TABLE vendor_ledger_entries (
+                id INTEGER PRIMARY KEY, vendor_id INTEGER, vendor_name TEXT, entry_type TEXT, amount REAL,
+                reference_type TEXT, reference_id INTEGER, reference_number TEXT, date TEXT, time TEXT
+            );
+            CREATE TABLE stock_receiving (
+                id INTEGER PRIMARY KEY, receiving_number TEXT, vendor_id INTEGER, vendor_name TEXT,
+                received_date TEXT, received_time TEXT, status TEXT, total_cost REAL, grand_total REAL,
+                payment_status TEXT
+            );
+            CREATE TABLE vendor_payments (
+                id INTEGER PRIMARY KEY, payment_number TEXT, vendor_id INTEGER, vendor_name TEXT,
+                receiving_id INTEGER, amount REAL, payment_method TEXT, payment_channel_id INTEGER,
+                status TEXT, date TEXT, time TEXT
+            );
+            CREATE TABLE products (
+                id INTEGER PRIMARY KEY, name TEXT NOT NULL, unit_type TEXT, unit TEXT, cost_price REAL,
+                current_stock TEXT NOT NULL DEFAULT '0', stock_quantity REAL
+            );
+            CREATE TABLE stock_movements (
+                id INTEGER PRIMARY KEY, product_id INTEGER, product_name TEXT, movement_type TEXT,
+                transaction_type TEXT, quantity TEXT, unit TEXT, previous_stock TEXT, stock_before TEXT,
+                stock_after TEXT, new_stock TEXT, reason TEXT, reference_type TEXT, reference_id INTEGER,
+                reference_number TEXT, notes TEXT, date TEXT, time TEXT, created_by TEXT
+            );
+            INSERT INTO customers (id, name, balance) VALUES (1, 'Ali', 1000);
+            INSERT INTO invoices (id, bill_number, customer_id, customer_name, grand_total, remaining_balance, status,
+                date, time)
+            VALUES (1, 'I00001', 1, 'Ali', 1500, 1000, 'partial', '2025-03-01', '10:00:00'),
+                   (2, 'I00002', 1, 'Ali', 600, 0, 'paid', '2025-06-01', '10:00:00');
+            INSERT INTO invoice_items (id, invoice_id, product_id, product_name, quantity, unit, unit_price,
+                total_price)
+            VALUES (1, 1, 1, 'Cement', '2', 'bag', 750, 1500), (2, 2, 1, 'Cement', '1', 'bag', 600, 600);
+            INSERT INTO customer_ledger_entries (customer_id, customer_name, entry_type, transaction_type, amount,
+                description, invoice_id, date, time)
+            VALUES (1, 'Ali', 'debit', 'invoice', 1500, 'Invoice I00001', 1, '2025-03-01', '10:00:00'),
+                   (1, 'Ali', 'credit', 'payment', 500, 'Payment', NULL, '2025-03-01', '10:00:00'),
+                   (1, 'Ali', 'debit', 'invoice', 600, 'Invoice I00002', 2, '2025-06-01', '10:00:00'),
+                   (1, 'Ali', 'credit', 'payment', 600, 'Payment', NULL, '2025-06-01', '10:00:00');
+            INSERT INTO products (id, name, unit_type, unit, cost_price, current_stock, stock_quantity)
+                VALUES (1, 'Cement', 'bag', 'bag', 500, '52', 52);
+            INSERT INTO stock_movements (product_id, product_name, movement_type, quantity, stock_before, stock_after,
+                reference_type, reference_id, date, time)
+            VALUES (1, 'Cement', 'in', '50', '0', '50', 'purchase', NULL, '2025-02-01', '09:00:00'),
+                   (1, 'Cement', 'out', '2', '50', '48', 'invoice', 1, '2025-03-01', '10:00:00'),
+                   (1, 'Cement', 'out', '1', '48', '47', 'invoice', 2, '2025-06-01', '10:00:00'),
+                   (1, 'Cement', 'in', '5', '47', '52', 'purchase', NULL, '2026-03-01', '09:00:00');",
+        )
+        .unwrap();
+        crate::vendor_payables::ensure_schema(&conn).unwrap();
+        crate::journal::ensure_schema(&conn).unwrap();
+        crate::fiscal_year::ensure_schema(&conn).unwrap();
+        day_close::ensure_schema(&conn).unwrap();
+        ensure_schema(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn statements_read_invoices_of_a_moved_year_from_its_archive() {
+        let mut conn = database();
+        let fy2025 = fiscal_years(&conn).unwrap()[0].id;
+        let tx = conn.transaction().unwrap();
+        close_fiscal_year_in_transaction(&tx, fy2025, "sara", None).unwrap();
+        tx.commit().unwrap();
+
+        let dir = std::env::temp_dir().join(format!("archive-move-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = archive_fiscal_year_to(&conn, fy2025, &dir)
+            .unwrap()
+            .archive_path
+            .unwrap();
+        attach(&conn, &path, MOVE_ALIAS).unwrap();
+        let tx = conn.transaction().unwrap();
+        let moved = move_year_in_transaction(&tx, fy2025, MOVE_ALIAS, &path, "sara").unwrap();
+        tx.commit().unwrap();
+        detach(&conn, MOVE_ALIAS).unwrap();
+        assert_eq!(
+            (
+                moved.archived_year.invoices_moved,
+                moved.archived_year.movements_moved,
+                moved.invoices_kept
+            ),
+            (1, 3, 1)
+        );
+        let live_invoices: i64 = conn
+            .query_row("SELECT COUNT(*) FROM invoices", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(live_invoices, 1);
+        assert!(check_stock(&conn, None).unwrap().divergent.is_empty());
+
+        // The settled invoice comes from the archive, the one still owing from the live tables
+        assert_eq!(attach_archives(&conn).unwrap(), ["FY2025"]);
+        let statement = customer_statement(&conn, 1, None, None).unwrap();
+        let invoices: Vec<(&str, bool, usize)> = statement
+            .lines
+            .iter()
+            .filter_map(|line| line.invoice.as_ref())
+            .map(|invoice| (invoice.bill_number.as_str(), invoice.archived, invoice.items.len()))
+            .collect();
+        assert_eq!(invoices, [("I00001", false, 1), ("I00002", true, 1)]);
+        assert_eq!(statement.closing_balance, Money::from_paisa(100_000));
+        let statement = customer_statement(&conn, 1, Some("2025-06-01"), None).unwrap();
+        assert_eq!(statement.opening_balance, Money::from_paisa(100_000));
+        assert_eq!(
+            statement.lines[0].invoice.as_ref().unwrap().grand_total,
+            Money::from_paisa(60_000)
+        );
+        detach(&conn, "archive_1").unwrap();
+
+        // Restoring copies the moved rows back
+        attach(&conn, &path, MOVE_ALIAS).unwrap();
+        let tx = conn.transaction().unwrap();
+        assert_eq!(restore_year_in_transaction(&tx, fy2025, MOVE_ALIAS).unwrap(), 5);
+        tx.commit().unwrap();
+        detach(&conn, MOVE_ALIAS).unwrap();
+        let live_invoices: i64 = conn
+            .query_row("SELECT COUNT(*) FROM invoices", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(live_invoices, 2);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,912 @@
+/*!
+ * TAMPER-EVIDENT AUDIT LOG
+ *
+ * Every change to the business tables, and every business command, is
+ * recorded in `audit_chain`: who, when, what, and the row before and after.
+ * Each entry carries the hash of the entry before it and its own hash over
+ * both, so editing or deleting an entry breaks the chain from that point on.
+ *
+ * Capture: the frontend writes through its own connection, so row changes
+ * are caught by AFTER triggers on the audited tables (installed at every
+ * start, so columns added since are included). They write the old and new
+ * row as JSON into `audit_pending`, attributed to the user the window set
+ * with `set_audit_actor`. Business commands add their own entry with the
+ * performer they were given. A Rust loop moves pending entries into the
+ * chain every few seconds, hashing them in order.
+ *
+ * Protection: the chain refuses UPDATE and DELETE through triggers, and the
+ * hash of its newest entry is kept next to the database (`store.db.audit-head`)
+ * so deleting the newest entries is caught as well. Someone who rewrites the
+ * database and that file together can forge a chain; the hashes make that a
+ * deliberate act rather than a quiet edit. `verify_audit_chain` reports the
+ * first entry where the chain breaks.
+ *
+ * Password columns are left out of the recorded rows; a change to one is
+ * still recorded as an update of the row.
+ */
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use log::{error, info, warn};
+use rusqlite::{params, Connection, OptionalExtension, TransactionBehavior};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+use sha2::{Digest, Sha256};
+
+use crate::database::{column_names, get_db_path, open_connection, open_connection_at, table_exists};
+
+/// Tables whose row changes are recorded; missing ones are skipped
+const AUDITED_TABLES: &[&str] = &[
+    "invoices",
+    "invoice_items",
+    "invoice_payments",
+    "invoice_payment_allocations",
+    "invoice_cancellations",
+    "payments",
+    "enhanced_payments",
+    "customers",
+    "customer_ledger_entries",
+    "ledger_entries",
+    "products",
+    "stock_movements",
+    "stock_receiving",
+    "stock_receiving_items",
+    "vendors",
+    "vendor_payments",
+    "returns",
+    "return_items",
+    "payment_channels",
+    "business_expenses",
+    "business_income",
+    "salary_payments",
+    "staff",
+    "staff_management",
+    "users",
+    "settings",
+    "app_settings",
+];
+
+/// Never copied into the log
+const REDACTED_COLUMNS: &[&str] = &["password", "password_hash", "salt"];
+
+/// Integer shadows kept in step with their source column by triggers; a
+/// change to one alone is not a change of its own
+const SHADOW_SUFFIXES: &[&str] = &["_paisa", "_base"];
+
+/// SQLite takes at most 127 function arguments
+const SNAPSHOT_CHUNK: usize = 60;
+
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+const HEAD_SUFFIX: &str = ".audit-head";
+const APPEND_ONLY_MESSAGE: &str = "AUDIT_APPEND_ONLY: audit log entries cannot be changed or deleted";
+const UNKNOWN_ACTOR: &str = "unknown";
+
+/// How often the background loop seals pending entries
+const SEAL_INTERVAL: Duration = Duration::from_secs(5);
+const MAX_PROBLEMS: usize = 100;
+
+/// Keeps the head file in step with the order commits land in
+static SEAL_LOCK: Mutex<()> = Mutex::new(());
+
+// ==================== SCHEMA ====================
+
+pub fn ensure_schema(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(&format!(
+        "CREATE TABLE IF NOT EXISTS audit_chain (
+            seq INTEGER PRIMARY KEY,
+            recorded_at TEXT NOT NULL,
+            actor TEXT NOT NULL,
+            action TEXT NOT NULL,
+            entity_type TEXT NOT NULL,
+            entity_id TEXT,
+            before_values TEXT,
+            after_values TEXT,
+            source TEXT NOT NULL,
+            prev_hash TEXT NOT NULL,
+            hash TEXT NOT NULL UNIQUE
+        );
+        CREATE INDEX IF NOT EXISTS idx_audit_chain_entity ON audit_chain(entity_type, entity_id);
+        CREATE TABLE IF NOT EXISTS audit_pending (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            recorded_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+            actor TEXT NOT NULL,
+            action TEXT NOT NULL,
+            entity_type TEXT NOT NULL,
+            entity_id TEXT,
+            before_values TEXT,
+            after_values TEXT,
+            source TEXT NOT NULL DEFAULT 'trigger'
+        );
+        CREATE TABLE IF NOT EXISTS audit_actor (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            actor TEXT,
+            capture_rows INTEGER NOT NULL DEFAULT 1,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        INSERT OR IGNORE INTO audit_actor (id) VALUES (1);
+
+        DROP TRIGGER IF EXISTS trg_audit_chain_no_update;
+        DROP TRIGGER IF EXISTS trg_audit_chain_no_delete;
+        CREATE TRIGGER trg_audit_chain_no_update BEFORE UPDATE ON audit_chain
+        BEGIN
+            SELECT RAISE(ABORT, '{message}');
+        END;
+        CREATE TRIGGER trg_audit_chain_no_delete BEFORE DELETE ON audit_chain
+        BEGIN
+            SELECT RAISE(ABORT, '{message}');
+        END;",
+        message = APPEND_ONLY_MESSAGE,
+    ))?;
+
+    for table in AUDITED_TABLES {
+        install_capture(conn, table)?;
+    }
+    Ok(())
+}
+
+fn quoted(column: &str) -> String {
+    format!("\"{}\"", column.replace('"', "\"\""))
+}
+
+/// A column value as JSON can hold it
+fn value_sql(row: &str, column: &str) -> String {
+    let column = quoted(column);
+    format!("CASE WHEN typeof({row}.{column}) = 'blob' THEN hex({row}.{column}) ELSE {row}.{column} END")
+}
+
+/// The whole row as a JSON object, built in chunks under the argument limit
+fn snapshot_sql(row: &str, columns: &[&str]) -> String {
+    let mut chunks = columns.chunks(SNAPSHOT_CHUNK);
+    let first = chunks
+        .next()
+        .unwrap_or_default()
+        .iter()
+        .map(|column| format!("'{}', {}", column, value_sql(row, column)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    chunks.fold(format!("json_object({})", first), |object, chunk| {
+        let rest = chunk
+            .iter()
+            .map(|column| format!("'$.\"{}\"', {}", column, value_sql(row, column)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("json_insert({}, {})", object, rest)
+    })
+}
+
+fn install_capture(conn: &Connection, table: &str) -> rusqlite::Result<()> {
+    if !table_exists(conn, table)? {
+        return Ok(());
+    }
+    let columns = column_names(conn, table)?;
+    let recorded: Vec<&str> = columns
+        .iter()
+        .map(String::as_str)
+        .filter(|column| !REDACTED_COLUMNS.contains(column))
+        .collect();
+    let changed = columns
+        .iter()
+        .filter(|column| !SHADOW_SUFFIXES.iter().any(|suffix| column.ends_with(suffix)))
+        .map(|column| format!("OLD.{column} IS NOT NEW.{column}", column = quoted(column)))
+        .collect::<Vec<_>>()
+        .join(" OR ");
+    if recorded.is_empty() || changed.is_empty() {
+        return Ok(());
+    }
+    let key = if columns.iter().any(|column| column == "id") {
+        "id"
+    } else {
+        "rowid"
+    };
+
+    conn.execute_batch(&format!(
+        "DROP TRIGGER IF EXISTS trg_{table}_audit_insert;
+         DROP TRIGGER IF EXISTS trg_{table}_audit_update;
+         DROP TRIGGER IF EXISTS trg_{table}_audit_delete;
+         CREATE TRIGGER trg_{table}_audit_insert AFTER INSERT ON {table}
+         WHEN {capturing}
+         BEGIN
+             INSERT INTO audit_pending (actor, action, entity_type, entity_id, after_values)
+             VALUES ({actor}, 'insert', '{table}', NEW.{key}, {new});
+         END;
+         CREATE TRIGGER trg_{table}_audit_update AFTER UPDATE ON {table}
+         WHEN {capturing} AND ({changed})
+         BEGIN
+             INSERT INTO audit_pending (actor, action, entity_type, entity_id, before_values, after_values)
+             VALUES ({actor}, 'update', '{table}', NEW.{key}, {old}, {new});
+         END;
+         CREATE TRIGGER trg_{table}_audit_delete AFTER DELETE ON {table}
+         WHEN {capturing}
+         BEGIN
+             INSERT INTO audit_pending (actor, action, entity_type, entity_id, before_values)
+             VALUES ({actor}, 'delete', '{table}', OLD.{key}, {old});
+         END;",
+        capturing = "COALESCE((SELECT capture_rows FROM audit_actor WHERE id = 1), 1) = 1",
+        actor = format!(
+            "COALESCE((SELECT actor FROM audit_actor WHERE id = 1), '{}')",
+            UNKNOWN_ACTOR
+        ),
+        old = snapshot_sql("OLD", &recorded),
+        new = snapshot_sql("NEW", &recorded),
+    ))
+}
+
+// ==================== RECORDING ====================
+
+/// One command-level entry, e.g. `AuditEvent::new("cancel", "invoice").entity(id).by(user)`
+#[derive(Debug, Clone, Default)]
+pub struct AuditEvent {
+    pub actor: Option<String>,
+    pub action: String,
+    pub entity_type: String,
+    pub entity_id: Option<String>,
+    pub before: Option<JsonValue>,
+    pub after: Option<JsonValue>,
+}
+
+impl AuditEvent {
+    pub fn new(action: &str, entity_type: &str) -> Self {
+        AuditEvent {
+            action: action.to_string(),
+            entity_type: entity_type.to_string(),
+            ..AuditEvent::default()
+        }
+    }
+
+    pub fn entity(mut self, id: impl ToString) -> Self {
+        self.entity_id = Some(id.to_string());
+        self
+    }
+
+    /// Performer; without one the signed-in user is recorded
+    pub fn by(mut self, actor: &str) -> Self {
+        let actor = actor.trim();
+        if !actor.is_empty() {
+            self.actor = Some(actor.to_string());
+        }
+        self
+    }
+
+    pub fn before(mut self, values: JsonValue) -> Self {
+        self.before = Some(values);
+        self
+    }
+
+    pub fn after(mut self, values: JsonValue) -> Self {
+        self.after = Some(values);
+        self
+    }
+}
+
+/// Queue a command's entry. Call it inside the command's transaction so the
+/// entry exists exactly when the change does.
+pub fn record(conn: &Connection, event: &AuditEvent) -> Result<(), String> {
+    record_from(conn, event, "command")
+}
+
+fn record_from(conn: &Connection, event: &AuditEvent, source: &str) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO audit_pending (actor, action, entity_type, entity_id, before_values, after_values, source)
+         VALUES (COALESCE(?1, (SELECT actor FROM audit_actor WHERE id = 1), ?2), ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            event.actor,
+            UNKNOWN_ACTOR,
+            event.action,
+            event.entity_type,
+            event.entity_id,
+            event.before.as_ref().map(JsonValue::to_string),
+            event.after.as_ref().map(JsonValue::to_string),
+            source
+        ],
+    )
+    .map(|_| ())
+    .map_err(|e| format!("Failed to record audit entry: {}", e))
+}
+
+/// Who row changes were attributed to before `act_as`
+#[derive(Debug)]
+pub struct ActorScope {
+    previous: Option<(Option<String>, bool)>,
+}
+
+/// Attribute the row changes of the open transaction to `actor`, or leave
+/// them out when `capture_rows` is false (bulk moves recorded as one entry).
+/// Undo with `restore_actor` before committing; other connections never see it.
+pub fn act_as(conn: &Connection, actor: &str, capture_rows: bool) -> Result<ActorScope, String> {
+    if !table_exists(conn, "audit_actor").map_err(|e| format!("Failed to inspect schema: {}", e))? {
+        return Ok(ActorScope { previous: None });
+    }
+    let previous = conn
+        .query_row("SELECT actor, capture_rows FROM audit_actor WHERE id = 1", [], |row| {
+            Ok((row.get::<_, Option<String>>(0)?, row.get::<_, bool>(1)?))
+        })
+        .optional()
+        .map_err(|e| format!("Failed to read the audit actor: {}", e))?;
+    conn.execute(
+        "UPDATE audit_actor SET actor = ?1, capture_rows = ?2 WHERE id = 1",
+        params![actor, capture_rows],
+    )
+    .map_err(|e| format!("Failed to set the audit actor: {}", e))?;
+    Ok(ActorScope { previous })
+}
+
+pub fn restore_actor(conn: &Connection, scope: ActorScope) -> Result<(), String> {
+    let Some((actor, capture_rows)) = scope.previous else {
+        return Ok(());
+    };
+    conn.execute(
+        "UPDATE audit_actor SET actor = ?1, capture_rows = ?2 WHERE id = 1",
+        params![actor, capture_rows],
+    )
+    .map(|_| ())
+    .map_err(|e| format!("Failed to restore the audit actor: {}", e))
+}
+
+// ==================== CHAIN ====================
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AuditEntry {
+    pub seq: i64,
+    pub recorded_at: String,
+    pub actor: String,
+    pub action: String,
+    pub entity_type: String,
+    pub entity_id: Option<String>,
+    pub before_values: Option<String>,
+    pub after_values: Option<String>,
+    /// `trigger` for row changes, `command` for business commands, `frontend` for window events
+    pub source: String,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+impl AuditEntry {
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(AuditEntry {
+            seq: row.get(0)?,
+            recorded_at: row.get(1)?,
+            actor: row.get(2)?,
+            action: row.get(3)?,
+            entity_type: row.get(4)?,
+            entity_id: row.get(5)?,
+            before_values: row.get(6)?,
+            after_values: row.get(7)?,
+            source: row.get(8)?,
+            prev_hash: row.get(9)?,
+            hash: row.get(10)?,
+        })
+    }
+
+    /// SHA-256 over every field but the hash itself, as a JSON array
+    pub fn compute_hash(&self) -> String {
+        let canonical = json!([
+            self.seq,
+            self.recorded_at,
+            self.actor,
+            self.action,
+            self.entity_type,
+            self.entity_id,
+            self.before_values,
+            self.after_values,
+            self.source,
+            self.prev_hash,
+        ]);
+        format!("{:x}", Sha256::digest(canonical.to_string().as_bytes()))
+    }
+}
+
+const ENTRY_COLUMNS: &str =
+    "seq, recorded_at, actor, action, entity_type, entity_id, before_values, after_values, source, prev_hash, hash";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ChainHead {
+    seq: i64,
+    hash: String,
+}
+
+fn head_path(db_path: &Path) -> PathBuf {
+    let mut name = db_path.file_name().unwrap_or_default().to_os_string();
+    name.push(HEAD_SUFFIX);
+    db_path.with_file_name(name)
+}
+
+fn load_head(db_path: &Path) -> Option<ChainHead> {
+    fs::read_to_string(head_path(db_path))
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+}
+
+fn save_head(db_path: &Path, head: &ChainHead) -> Result<(), String> {
+    let path = head_path(db_path);
+    let staged = path.with_extension("tmp");
+    let text = serde_json::to_string(head).map_err(|e| format!("Failed to encode the audit head: {}", e))?;
+    fs::write(&staged, text)
+        .and_then(|_| fs::rename(&staged, &path))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// The database was replaced on purpose (restore, rollback, joining a sync
+/// group); its chain is anchored afresh at the next seal
+pub fn forget_head(db_path: &Path) {
+    let path = head_path(db_path);
+    if path.exists() {
+        match fs::remove_file(&path) {
+            Ok(()) => info!("[AUDIT] Cleared the chain head for the replaced database"),
+            Err(e) => warn!("[AUDIT] Failed to remove {}: {}", path.display(), e),
+        }
+    }
+}
+
+/// Move pending entries into the chain; returns how many were sealed
+pub fn seal(conn: &mut Connection, db_path: &Path) -> Result<usize, String> {
+    let _guard = SEAL_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let pending = {
+        let mut stmt = tx
+            .prepare(
+                "SELECT id, recorded_at, actor, action, entity_type, entity_id, before_values, after_values, source
+                 FROM audit_pending ORDER BY id",
+            )
+            .map_err(|e| format!("Failed to read pending audit entries: {}", e))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    AuditEntry {
+                        seq: 0,
+                        recorded_at: row.get(1)?,
+                        actor: row.get(2)?,
+                        action: row.get(3)?,
+                        entity_type: row.get(4)?,
+                        entity_id: row.get(5)?,
+                        before_values: row.get(6)?,
+                        after_values: row.get(7)?,
+                        source: row.get(8)?,
+                        prev_hash: String::new(),
+                        hash: String::new(),
+                    },
+                ))
+            })
+            .map_err(|e| format!("Failed to read pending audit entries: {}", e))?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(|e| format!("Failed to read pending audit entry: {}", e))?
+    };
+    let Some(last_id) = pending.last().map(|(id, _)| *id) else {
+        return Ok(0);
+    };
+
+    let (mut seq, mut prev_hash) = tx
+        .query_row(
+            "SELECT seq, hash FROM audit_chain ORDER BY seq DESC LIMIT 1",
+            [],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to read the audit chain: {}", e))?
+        .unwrap_or_else(|| (0, GENESIS_HASH.to_string()));
+
+    for (_, mut entry) in pending.iter().cloned() {
+        seq += 1;
+        entry.seq = seq;
+        entry.prev_hash = prev_hash;
+        entry.hash = entry.compute_hash();
+        tx.execute(
+            &format!(
+                "INSERT INTO audit_chain ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                ENTRY_COLUMNS
+            ),
+            params![
+                entry.seq,
+                entry.recorded_at,
+                entry.actor,
+                entry.action,
+                entry.entity_type,
+                entry.entity_id,
+                entry.before_values,
+                entry.after_values,
+                entry.source,
+                entry.prev_hash,
+                entry.hash
+            ],
+        )
+        .map_err(|e| format!("Failed to seal audit entry: {}", e))?;
+        prev_hash = entry.hash;
+    }
+    tx.execute("DELETE FROM audit_pending WHERE id <= ?1", [last_id])
+        .map_err(|e| format!("Failed to clear pending audit entries: {}", e))?;
+    tx.commit()
+        .map_err(|e| format!("Failed to commit audit entries: {}", e))?;
+
+    save_head(db_path, &ChainHead { seq, hash: prev_hash })?;
+    Ok(pending.len())
+}
+
+/// Seal pending entries in the background for as long as the app runs
+pub fn start(db_path: PathBuf) {
+    // Nobody is signed in until the window says so
+    let cleared = open_connection_at(&db_path).and_then(|conn| {
+        conn.execute(
+            "UPDATE audit_actor SET actor = NULL, capture_rows = 1, updated_at = CURRENT_TIMESTAMP WHERE id = 1",
+            [],
+        )
+        .map_err(|e| e.to_string())
+    });
+    if let Err(e) = cleared {
+        warn!("[AUDIT] Failed to clear the audit actor: {}", e);
+    }
+
+    thread::spawn(move || loop {
+        thread::sleep(SEAL_INTERVAL);
+        let result = open_connection_at(&db_path).and_then(|mut conn| seal(&mut conn, &db_path));
+        if let Err(e) = result {
+            error!("[AUDIT] Failed to seal audit entries: {}", e);
+        }
+    });
+}
+
+// ==================== VERIFICATION ====================
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AuditProblem {
+    pub seq: Option<i64>,
+    /// `missing`, `relinked`, `altered`, `truncated` or `unprotected`
+    pub kind: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AuditVerification {
+    pub valid: bool,
+    pub entries: usize,
+    pub last_seq: Option<i64>,
+    pub last_hash: Option<String>,
+    /// The newest entry matches the head kept outside the database
+    pub anchored: bool,
+    pub first_broken_seq: Option<i64>,
+    pub problems: Vec<AuditProblem>,
+}
+
+impl AuditVerification {
+    fn problem(&mut self, seq: Option<i64>, kind: &str, message: String) {
+        if let Some(seq) = seq {
+            self.first_broken_seq = Some(self.first_broken_seq.map_or(seq, |first| first.min(seq)));
+        }
+        if self.problems.len() < MAX_PROBLEMS {
+            self.problems.push(AuditProblem {
+                seq,
+                kind: kind.to_string(),
+                message,
+            });
+        }
+    }
+}
+
+/// Walk the chain from the first entry and compare its end with the head file
+pub fn verify(conn: &Connection, db_path: &Path) -> Result<AuditVerification, String> {
+    let mut report = AuditVerification::default();
+
+    let protected: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM sqlite_master
+             WHERE type = 'trigger' AND name IN ('trg_audit_chain_no_update', 'trg_audit_chain_no_delete')",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to inspect schema: {}", e))?;
+    if protected < 2 {
+        report.problem(
+            None,
+            "unprotected",
+            "The triggers that keep the audit log append-only were removed".to_string(),
+        );
+    }
+
+    let mut stmt = conn
+        .prepare(&format!("SELECT {} FROM audit_chain ORDER BY seq", ENTRY_COLUMNS))
+        .map_err(|e| format!("Failed to read the audit chain: {}", e))?;
+    let mut rows = stmt
+        .query([])
+        .map_err(|e| format!("Failed to read the audit chain: {}", e))?;
+    let mut previous: Option<(i64, String)> = None;
+    let mut head_hash_at = None;
+    let head = load_head(db_path);
+
+    while let Some(row) = rows
+        .next()
+        .map_err(|e| format!("Failed to read the audit chain: {}", e))?
+    {
+        let entry = AuditEntry::from_row(row).map_err(|e| format!("Failed to read audit entry: {}", e))?;
+        let (expected_seq, expected_prev) = match &previous {
+            Some((seq, hash)) => (seq + 1, hash.as_str()),
+            None => (1, GENESIS_HASH),
+        };
+        if entry.seq != expected_seq {
+            let message = if entry.seq == expected_seq + 1 {
+                format!("Entry {} was deleted", expected_seq)
+            } else {
+                format!("Entries {} to {} were deleted", expected_seq, entry.seq - 1)
+            };
+            report.problem(Some(expected_seq), "missing", message);
+        } else if entry.prev_hash != expected_prev {
+            report.problem(
+                Some(entry.seq),
+                "relinked",
+                format!("Entry {} does not follow the entry before it", entry.seq),
+            );
+        }
+        if entry.compute_hash() != entry.hash {
+            report.problem(
+                Some(entry.seq),
+                "altered",
+                format!("Entry {} was changed after it was recorded", entry.seq),
+            );
+        }
+        if head.as_ref().is_some_and(|head| head.seq == entry.seq) {
+            head_hash_at = Some(entry.hash.clone());
+        }
+        report.entries += 1;
+        previous = Some((entry.seq, entry.hash));
+    }
+
+    report.last_seq = previous.as_ref().map(|(seq, _)| *seq);
+    report.last_hash = previous.map(|(_, hash)| hash);
+    if let Some(head) = head {
+        let last_seq = report.last_seq.unwrap_or(0);
+        if head.seq > last_seq {
+            report.problem(
+                Some(last_seq + 1),
+                "truncated",
+                format!(
+                    "Entries {} to {} were deleted from the end of the log",
+                    last_seq + 1,
+                    head.seq
+                ),
+            );
+        } else if head_hash_at.as_deref().is_some_and(|hash| hash != head.hash) {
+            report.problem(
+                Some(head.seq),
+                "altered",
+                format!("Entry {} does not match the recorded head of the log", head.seq),
+            );
+        } else {
+            report.anchored = head_hash_at.is_some();
+        }
+    }
+    report.valid = report.problems.is_empty();
+    Ok(report)
+}
+
+// ==================== COMMANDS ====================
+
+/// Seal what is pending and check the whole chain
+#[tauri::command]
+pub async fn verify_audit_chain() -> Result<AuditVerification, String> {
+    let db_path = get_db_path()?;
+    let mut conn = open_connection_at(&db_path)?;
+    seal(&mut conn, &db_path)?;
+    let report = verify(&conn, &db_path)?;
+
+    match report.first_broken_seq {
+        None if report.valid => info!("[AUDIT] Audit chain intact: {} entries", report.entries),
+        Some(seq) => warn!(
+            "[AUDIT] Audit chain broken at entry {}: {} problems",
+            seq,
+            report.problems.len()
+        ),
+        None => warn!("[AUDIT] Audit chain unprotected: {} problems", report.problems.len()),
+    }
+    Ok(report)
+}
+
+/// The user the window's row changes are attributed to; `None` on logout
+#[tauri::command]
+pub async fn set_audit_actor(actor: Option<String>) -> Result<(), String> {
+    let actor = actor
+        .map(|actor| actor.trim().to_string())
+        .filter(|actor| !actor.is_empty());
+    let conn = open_connection()?;
+    conn.execute(
+        "UPDATE audit_actor SET actor = ?1, capture_rows = 1, updated_at = CURRENT_TIMESTAMP WHERE id = 1",
+        [&actor],
+    )
+    .map_err(|e| format!("Failed to set the audit actor: {}", e))?;
+    Ok(())
+}
+
+/// Record an event the window handles itself, such as a login
+#[tauri::command]
+pub async fn record_audit_event(
+    actor: Option<String>,
+    action: String,
+    entity_type: String,
+    entity_id: Option<String>,
+    before: Option<JsonValue>,
+    after: Option<JsonValue>,
+) -> Result<(), String> {
+    let event = AuditEvent {
+        actor: actor
+            .map(|actor| actor.trim().to_string())
+            .filter(|actor| !actor.is_empty()),
+        action: action.trim().to_lowercase(),
+        entity_type: entity_type.trim().to_lowercase(),
+        entity_id,
+        before,
+        after,
+    };
+    if event.action.is_empty() || event.entity_type.is_empty() {
+        return Err("An audit event needs an action and an entity type".to_string());
+    }
+    let conn = open_connection()?;
+    record_from(&conn, &event, "frontend")
+}
+
+/// Newest sealed entries, optionally for one entity
+#[tauri::command]
+pub async fn get_audit_trail(
+    entity_type: Option<String>,
+    entity_id: Option<String>,
+    limit: Option<i64>,
+) -> Result<Vec<AuditEntry>, String> {
+    let db_path = get_db_path()?;
+    let mut conn = open_connection_at(&db_path)?;
+    seal(&mut conn, &db_path)?;
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM audit_chain
+             WHERE (?1 IS NULL OR entity_type = ?1) AND (?2 IS NULL OR entity_id = ?2)
+             ORDER BY seq DESC LIMIT ?3",
+            ENTRY_COLUMNS
+        ))
+        .map_err(|e| format!("Failed to read the audit log: {}", e))?;
+    let rows = stmt
+        .query_map(
+            params![entity_type, entity_id, limit.unwrap_or(200).clamp(1, 5000)],
+            AuditEntry::from_row,
+        )
+        .map_err(|e| format!("Failed to read the audit log: {}", e))?;
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to read audit entry: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_db(name: &str) -> (Connection, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("audit-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let db_path = dir.join("store.db");
+        let conn = Connection::open(&db_path).unwrap();
+        conn.execute_batch("CREATE TABLE customers (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+            .unwrap();
+        ensure_schema(&conn).unwrap();
+        (conn, db_path)
+    }
+
+    fn sealed_chain(name: &str, entries: usize) -> (Connection, PathBuf) {
+        let (mut conn, db_path) = temp_db(name);
+        for n in 0..entries {
+            record(&conn, &AuditEvent::new("login", "user").entity(n).by("admin")).unwrap();
+        }
+        assert_eq!(seal(&mut conn, &db_path).unwrap(), entries);
+        (conn, db_path)
+    }
+
+    fn unprotect(conn: &Connection) {
+        conn.execute_batch(
+            "DROP TRIGGER trg_audit_chain_no_update;
+             DROP TRIGGER trg_audit_chain_no_delete;",
+        )
+        .unwrap();
+    }
+
+    fn kinds(report: &AuditVerification) -> Vec<(Option<i64>, &str)> {
+        report.problems.iter().map(|p| (p.seq, p.kind.as_str())).collect()
+    }
+
+    #[test]
+    fn sealed_rows_and_commands_verify() {
+        let (mut conn, db_path) = temp_db("intact");
+        let scope = act_as(&conn, "cashier", true).unwrap();
+        conn.execute("INSERT INTO customers (name) VALUES ('Ali')", []).unwrap();
+        conn.execute("UPDATE customers SET name = 'Ali Khan' WHERE id = 1", [])
+            .unwrap();
+        restore_actor(&conn, scope).unwrap();
+        record(&conn, &AuditEvent::new("cancel", "invoice").entity(7).by("manager")).unwrap();
+        assert_eq!(seal(&mut conn, &db_path).unwrap(), 3);
+        assert_eq!(seal(&mut conn, &db_path).unwrap(), 0);
+
+        let report = verify(&conn, &db_path).unwrap();
+        assert!(report.valid && report.anchored);
+        assert_eq!(
+            (report.entries, report.last_seq, report.first_broken_seq),
+            (3, Some(3), None)
+        );
+
+        let (actor, action, before): (String, String, String) = conn
+            .query_row(
+                "SELECT actor, action, before_values FROM audit_chain WHERE seq = 2",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!((actor.as_str(), action.as_str()), ("cashier", "update"));
+        assert_eq!(serde_json::from_str::<JsonValue>(&before).unwrap()["name"], "Ali");
+
+        let refused = conn.execute("UPDATE audit_chain SET actor = 'someone' WHERE seq = 1", []);
+        assert!(refused.unwrap_err().to_string().contains(APPEND_ONLY_MESSAGE));
+        assert!(conn.execute("DELETE FROM audit_chain WHERE seq = 3", []).is_err());
+
+        // A replaced database starts unanchored, and is anchored again at the next seal
+        forget_head(&db_path);
+        let report = verify(&conn, &db_path).unwrap();
+        assert!(report.valid && !report.anchored);
+        record(&conn, &AuditEvent::new("login", "user").by("admin")).unwrap();
+        seal(&mut conn, &db_path).unwrap();
+        assert!(verify(&conn, &db_path).unwrap().anchored);
+
+        let _ = fs::remove_dir_all(db_path.parent().unwrap());
+    }
+
+    #[test]
+    fn edits_and_deletions_are_located() {
+        let (conn, db_path) = sealed_chain("edits", 5);
+        unprotect(&conn);
+        conn.execute("UPDATE audit_chain SET actor = 'someone' WHERE seq = 2", [])
+            .unwrap();
+        conn.execute("DELETE FROM audit_chain WHERE seq = 4", []).unwrap();
+
+        let report = verify(&conn, &db_path).unwrap();
+        assert!(!report.valid);
+        assert_eq!(report.first_broken_seq, Some(2));
+        assert_eq!(
+            kinds(&report),
+            vec![(None, "unprotected"), (Some(2), "altered"), (Some(4), "missing")]
+        );
+
+        let _ = fs::remove_dir_all(db_path.parent().unwrap());
+    }
+
+    #[test]
+    fn relinked_and_truncated_chains_are_caught() {
+        let (conn, db_path) = sealed_chain("relinked", 3);
+        unprotect(&conn);
+        // Rehashed after editing, so only the link to the entry before gives it away
+        let mut entry = conn
+            .query_row(
+                &format!("SELECT {} FROM audit_chain WHERE seq = 2", ENTRY_COLUMNS),
+                [],
+                AuditEntry::from_row,
+            )
+            .unwrap();
+        entry.prev_hash = GENESIS_HASH.to_string();
+        entry.hash = entry.compute_hash();
+        conn.execute(
+            "UPDATE audit_chain SET prev_hash = ?1, hash = ?2 WHERE seq = 2",
+            params![entry.prev_hash, entry.hash],
+        )
+        .unwrap();
+        let report = verify(&conn, &db_path).unwrap();
+        assert!(kinds(&report).contains(&(Some(2), "relinked")));
+        assert!(kinds(&report).contains(&(Some(3), "relinked")));
+        let _ = fs::remove_dir_all(db_path.parent().unwrap());
+
+        let (conn, db_path) = sealed_chain("truncated", 3);
+        unprotect(&conn);
+        conn.execute("DELETE FROM audit_chain WHERE seq = 3", []).unwrap();
+        let report = verify(&conn, &db_path).unwrap();
+        assert_eq!(kinds(&report)[1..], [(Some(3), "truncated")]);
+        assert!(!report.anchored);
+
+        let _ = fs::remove_dir_all(db_path.parent().unwrap());
+    }
+}
@@ -0,0 +1,307 @@
+/*!
+ * LIVE CHANGE EVENTS
+ * Every connection opened by `database::open_connection` carries SQLite
+ * update, commit and rollback hooks. Row changes are tallied per table while
+ * a transaction runs, handed over when it commits (dropped on rollback) and
+ * coalesced for a short debounce before being emitted to every window:
+ *
+ * - `db-tables-changed`: per table, how many rows were inserted, updated and
+ *   deleted, with the first changed row ids
+ * - `db-stock-changed`: products whose stock may have changed (product rows
+ *   written, and the products of stock movements written)
+ *
+ * The frontend writes through its own connection, which these hooks cannot
+ * see, so a watcher also checks `PRAGMA data_version` on a read-only
+ * connection. A commit from another connection is reported as an `external`
+ * change with no table detail, and windows refresh everything they show.
+ * Commits from hooked connections are never reported that way, even when
+ * they only wrote bookkeeping tables that raise no event of their own.
+ */
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use log::{error, info};
+use rusqlite::hooks::Action;
+use rusqlite::{Connection, OpenFlags};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+pub const TABLES_CHANGED_EVENT: &str = "db-tables-changed";
+pub const STOCK_CHANGED_EVENT: &str = "db-stock-changed";
+
+/// Changes committed within this window are emitted together
+const DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// How often the watcher looks for commits from other connections
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A watcher sighting this soon after a hooked commit is that same commit
+const EXTERNAL_GRACE: Duration = Duration::from_secs(1);
+
+/// Row ids kept per table in one event; the counts stay exact
+const MAX_ROW_IDS: usize = 100;
+
+static CHANGES: OnceLock<Sender<Notice>> = OnceLock::new();
+
+/// Rows of one table changed by the committed transactions
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TableChange {
+    pub table: String,
+    pub inserted: usize,
+    pub updated: usize,
+    pub deleted: usize,
+    /// Up to `MAX_ROW_IDS` ids of inserted or updated rows, then deleted ones
+    pub row_ids: Vec<i64>,
+    pub deleted_ids: Vec<i64>,
+}
+
+impl TableChange {
+    fn record(&mut self, action: &Action, row_id: i64) {
+        let (count, ids) = match action {
+            Action::SQLITE_INSERT => (&mut self.inserted, &mut self.row_ids),
+            Action::SQLITE_UPDATE => (&mut self.updated, &mut self.row_ids),
+            Action::SQLITE_DELETE => (&mut self.deleted, &mut self.deleted_ids),
+            _ => return,
+        };
+        *count += 1;
+        if ids.len() < MAX_ROW_IDS && !ids.contains(&row_id) {
+            ids.push(row_id);
+        }
+    }
+
+    fn merge(&mut self, other: TableChange) {
+        self.inserted += other.inserted;
+        self.updated += other.updated;
+        self.deleted += other.deleted;
+        for (ids, others) in [
+            (&mut self.row_ids, other.row_ids),
+            (&mut self.deleted_ids, other.deleted_ids),
+        ] {
+            for id in others {
+                if ids.len() < MAX_ROW_IDS && !ids.contains(&id) {
+                    ids.push(id);
+                }
+            }
+        }
+    }
+}
+
+/// Payload of `db-tables-changed`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TablesChanged {
+    pub tables: Vec<TableChange>,
+    /// Another connection (the frontend's) committed; tables are unknown
+    pub external: bool,
+}
+
+/// Payload of `db-stock-changed`
+#[derive(Debug, Clone, Serialize)]
+pub struct StockChanged {
+    pub product_ids: Vec<i64>,
+}
+
+enum Notice {
+    /// A hooked connection committed; the map is empty when only internal tables changed
+    Committed(BTreeMap<String, TableChange>),
+    External,
+}
+
+/// Bookkeeping tables whose writes are never worth a refresh: indexes, the
+/// sync and API state written on every round or request, the audit queue and
+/// the recycle bin's delete guard
+fn is_internal(table: &str) -> bool {
+    ["sqlite_", "search_", "sync_", "api_", "audit_", "recycle_bin_"]
+        .iter()
+        .any(|prefix| table.starts_with(prefix))
+}
+
+/// Install the change hooks on a connection. Does nothing until `start` has run.
+pub fn watch(conn: &Connection) {
+    let Some(sender) = CHANGES.get() else {
+        return;
+    };
+    let pending: Arc<Mutex<BTreeMap<String, TableChange>>> = Arc::default();
+    // Any row written in main, internal tables included. Those commits move
+    // `data_version` too, and the watcher must know they were ours.
+    let wrote: Arc<AtomicBool> = Arc::default();
+
+    let tally = Arc::clone(&pending);
+    let written = Arc::clone(&wrote);
+    conn.update_hook(Some(move |action: Action, db: &str, table: &str, row_id: i64| {
+        if db != "main" {
+            return;
+        }
+        written.store(true, Ordering::Relaxed);
+        if is_internal(table) {
+            return;
+        }
+        if let Ok(mut tally) = tally.lock() {
+            tally
+                .entry(table.to_string())
+                .or_insert_with(|| TableChange {
+                    table: table.to_string(),
+                    ..TableChange::default()
+                })
+                .record(&action, row_id);
+        }
+    }));
+
+    let committed = Arc::clone(&pending);
+    let written = Arc::clone(&wrote);
+    let sender = sender.clone();
+    conn.commit_hook(Some(move || {
+        if let Ok(mut committed) = committed.lock() {
+            if written.swap(false, Ordering::Relaxed) || !committed.is_empty() {
+                let _ = sender.send(Notice::Committed(std::mem::take(&mut *committed)));
+            }
+        }
+        false
+    }));
+
+    conn.rollback_hook(Some(move || {
+        wrote.store(false, Ordering::Relaxed);
+        if let Ok(mut pending) = pending.lock() {
+            pending.clear();
+        }
+    }));
+}
+
+/// Start emitting change events for the database at `db_path`
+pub fn start(app: AppHandle, db_path: PathBuf) {
+    let (sender, receiver) = mpsc::channel();
+    if CHANGES.set(sender.clone()).is_err() {
+        return;
+    }
+
+    let reader = db_path.clone();
+    thread::spawn(move || emit_changes(app, receiver, reader));
+    thread::spawn(move || watch_other_connections(sender, db_path));
+    info!("[EVENTS] Live change events started");
+}
+
+fn open_reader(db_path: &Path) -> Option<Connection> {
+    Connection::open_with_flags(
+        db_path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .ok()
+}
+
+/// Poll `PRAGMA data_version`, which moves when another connection commits
+fn watch_other_connections(sender: Sender<Notice>, db_path: PathBuf) {
+    let mut reader = None;
+    let mut last_version: Option<i64> = None;
+    loop {
+        thread::sleep(WATCH_INTERVAL);
+        if reader.is_none() {
+            reader = open_reader(&db_path);
+        }
+        let Some(conn) = reader.as_ref() else {
+            continue;
+        };
+        match conn.query_row("PRAGMA data_version", [], |row| row.get::<_, i64>(0)) {
+            Ok(version) => {
+                if last_version.is_some_and(|last| last != version) && sender.send(Notice::External).is_err() {
+                    return;
+                }
+                last_version = Some(version);
+            }
+            // The file was replaced (restore): reopen and start over
+            Err(_) => {
+                reader = None;
+                last_version = None;
+            }
+        }
+    }
+}
+
+/// Products touched by the changed tables: product rows, and the products of stock movement rows
+fn stock_product_ids(reader: Option<&Connection>, changes: &BTreeMap<String, TableChange>) -> Vec<i64> {
+    let mut product_ids = BTreeSet::new();
+    if let Some(products) = changes.get("products") {
+        product_ids.extend(&products.row_ids);
+    }
+    if let (Some(movements), Some(conn)) = (changes.get("stock_movements"), reader) {
+        if movements.row_ids.is_empty() {
+            return product_ids.into_iter().collect();
+        }
+        let ids = movements
+            .row_ids
+            .iter()
+            .map(i64::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        let found = conn
+            .prepare(&format!(
+                "SELECT DISTINCT product_id FROM stock_movements WHERE id IN ({ids})"
+            ))
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| row.get::<_, i64>(0))?
+                    .collect::<rusqlite::Result<Vec<_>>>()
+            });
+        if let Ok(found) = found {
+            product_ids.extend(found);
+        }
+    }
+    product_ids.into_iter().collect()
+}
+
+/// Coalesce notices for `DEBOUNCE` and emit them
+fn emit_changes(app: AppHandle, receiver: Receiver<Notice>, db_path: PathBuf) {
+    let mut reader = None;
+    let mut last_hooked_commit: Option<Instant> = None;
+
+    while let Ok(first) = receiver.recv() {
+        let mut changes: BTreeMap<String, TableChange> = BTreeMap::new();
+        let mut external = false;
+        let mut add = |notice: Notice, changes: &mut BTreeMap<String, TableChange>| match notice {
+            Notice::Committed(committed) => {
+                last_hooked_commit = Some(Instant::now());
+                for (table, change) in committed {
+                    changes.entry(table).or_default().merge(change);
+                }
+            }
+            Notice::External => external = true,
+        };
+        add(first, &mut changes);
+        let deadline = Instant::now() + DEBOUNCE;
+        loop {
+            match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(notice) => add(notice, &mut changes),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+        let external = external && !last_hooked_commit.is_some_and(|at| at.elapsed() <= EXTERNAL_GRACE);
+        if changes.is_empty() && !external {
+            continue;
+        }
+
+        if reader.is_none() && changes.contains_key("stock_movements") {
+            reader = open_reader(&db_path);
+        }
+        let product_ids = stock_product_ids(reader.as_ref(), &changes);
+        let tables = changes
+            .into_iter()
+            .map(|(table, mut change)| {
+                change.table = table;
+                change
+            })
+            .collect();
+
+        if let Err(e) = app.emit(TABLES_CHANGED_EVENT, TablesChanged { tables, external }) {
+            error!("[EVENTS] Failed to emit table changes: {}", e);
+        }
+        if !product_ids.is_empty() {
+            if let Err(e) = app.emit(STOCK_CHANGED_EVENT, StockChanged { product_ids }) {
+                error!("[EVENTS] Failed to emit stock changes: {}", e);
+            }
+        }
+    }
+}
@@ -0,0 +1,633 @@
+/*!
+ * HEADLESS MAINTENANCE COMMANDS
+ *
+ * `ittehad-iron-store <command>` runs one maintenance task without opening a
+ * window and exits, so it can be scheduled from cron or Task Scheduler:
+ *
+ *   backup [<file>]                          consistent copy, default backups/store-<timestamp>.db
+ *   restore <file>                           verify a backup, keep the current file, replace it
+ *   verify <file>                            integrity check and expected tables of a database file
+ *   migrate                                  the schema setup and conversions run at startup
+ *   integrity-check                          SQLite integrity, foreign keys, stock and balances
+ *   export [<dir>]                           every business table as CSV, default exports/<timestamp>
+ *   repair-stock [--product <id>] [--dry-run]  rebuild stored stock from the movement ledger
+ *
+ * `--db <file>` points any command at another database file. Each command runs
+ * the same code as the app (backup API copy, startup initialization, stock
+ * rebuild, balance report). Exit status is 0 on success, 1 when the command
+ * failed or found problems, and 2 on a usage error. Close the app before
+ * `restore`; the other commands are safe while it runs.
+ */
+
+use log::{error, info, warn};
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, OpenFlags, TransactionBehavior};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::customer_balance::balance_report;
+use crate::database::{self, open_connection_at, table_exists};
+use crate::logging;
+use crate::single_instance;
+use crate::stock_engine::{check_stock, rebuild_stock_in_transaction, StockDivergence, UnreadableStock};
+
+const COMMANDS: &[&str] = &[
+    "backup",
+    "restore",
+    "verify",
+    "migrate",
+    "integrity-check",
+    "export",
+    "repair-stock",
+];
+
+/// Tables every store database has; a file missing one is not a store backup
+const REQUIRED_TABLES: &[&str] = &["products", "customers", "invoices", "invoice_items", "stock_movements"];
+
+/// Index, search, sync and API bookkeeping left out of CSV exports
+const INTERNAL_PREFIXES: &[&str] = &["sqlite_", "search_", "sync_", "api_", "_sqlx"];
+
+const USAGE: &str = "\
+Usage: ittehad-iron-store <command> [options]
+
+Commands:
+  backup [<file>]              Write a consistent copy of the database
+  restore <file>               Replace the database with a verified backup (close the app first)
+  verify <file>                Check a database or backup file
+  migrate                      Bring the database schema up to date
+  integrity-check              Check SQLite integrity, stock and customer balances
+  export [<dir>]               Write every business table as CSV
+  repair-stock                 Rebuild stored stock from the movement ledger
+      --product <id>           Only this product
+      --dry-run                Report what would change without writing
+
+Options:
+  --db <file>                  Use this database instead of the app's
+  -h, --help                   Show this help
+
+Environment:
+  ITTEHAD_DATA_DIR             Use this data directory instead of the app's
+
+Exit status: 0 success, 1 failure or problems found, 2 usage error";
+
+struct Invocation {
+    command: String,
+    arguments: Vec<String>,
+    db: Option<PathBuf>,
+    product: Option<i64>,
+    dry_run: bool,
+}
+
+/// Run the command named by the first argument. `None` means the arguments
+/// are not a command and the window should start as usual.
+pub fn run(args: &[String]) -> Option<i32> {
+    let first = args.first()?;
+    let wants_help = matches!(first.as_str(), "help" | "--help" | "-h");
+    if !wants_help && !COMMANDS.contains(&first.as_str()) {
+        return None;
+    }
+
+    attach_parent_console();
+    // The console carries the command's result; records go to the log file only
+    logging::init(false);
+
+    if wants_help {
+        println!("{}", USAGE);
+        return Some(0);
+    }
+
+    let invocation = match parse(args) {
+        Ok(Some(invocation)) => invocation,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return Some(0);
+        }
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return Some(2);
+        }
+    };
+
+    let app_data_dir = match database::get_app_data_dir() {
+        Ok(dir) => dir,
+        Err(e) => {
+            eprintln!("{}", e);
+            return Some(1);
+        }
+    };
+    if let Err(e) = logging::attach_dir(&app_data_dir.join("logs")) {
+        eprintln!("⚠️ {}", e);
+    }
+    let db_path = match &invocation.db {
+        Some(path) => path.clone(),
+        None => app_db_path(&app_data_dir),
+    };
+    info!(
+        "[CLI] {} {} on {}",
+        invocation.command,
+        invocation.arguments.join(" "),
+        db_path.display()
+    );
+
+    if invocation.command == "restore" {
+        if let Some(pid) = single_instance::running_instance(&app_data_dir) {
+            eprintln!("❌ The app is running (process {}); close it before restoring", pid);
+            return Some(1);
+        }
+    }
+
+    match execute(&invocation, &db_path) {
+        Ok(true) => {
+            info!("[CLI] {} finished", invocation.command);
+            Some(0)
+        }
+        Ok(false) => {
+            warn!("[CLI] {} found problems", invocation.command);
+            Some(1)
+        }
+        Err(e) => {
+            error!("[CLI] {} failed: {}", invocation.command, e);
+            eprintln!("❌ {}", e);
+            Some(1)
+        }
+    }
+}
+
+/// `Ok(None)` when help was asked for
+fn parse(args: &[String]) -> Result<Option<Invocation>, String> {
+    let mut invocation = Invocation {
+        command: args[0].clone(),
+        arguments: Vec::new(),
+        db: None,
+        product: None,
+        dry_run: false,
+    };
+
+    let mut rest = args[1..].iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--db" => {
+                let path = rest.next().ok_or("--db needs a database file")?;
+                invocation.db = Some(PathBuf::from(path));
+            }
+            "--product" if invocation.command == "repair-stock" => {
+                let id = rest.next().ok_or("--product needs a product id")?;
+                let id = id.parse().map_err(|_| format!("Invalid product id: {}", id))?;
+                invocation.product = Some(id);
+            }
+            "--dry-run" if invocation.command == "repair-stock" => invocation.dry_run = true,
+            option if option.starts_with('-') => {
+                return Err(format!("Unknown option for {}: {}", invocation.command, option));
+            }
+            _ => invocation.arguments.push(arg.clone()),
+        }
+    }
+
+    let (required, allowed) = match invocation.command.as_str() {
+        "restore" | "verify" => (1, 1),
+        "backup" | "export" => (0, 1),
+        _ => (0, 0),
+    };
+    if invocation.arguments.len() < required {
+        return Err(format!("{} needs a file", invocation.command));
+    }
+    if invocation.arguments.len() > allowed {
+        return Err(format!(
+            "Unexpected argument for {}: {}",
+            invocation.command, invocation.arguments[allowed]
+        ));
+    }
+
+    Ok(Some(invocation))
+}
+
+/// The database the app itself opens, carrying a legacy year-named file over first
+fn app_db_path(app_data_dir: &Path) -> PathBuf {
+    if let Err(e) = database::migrate_legacy_db_file(app_data_dir) {
+        eprintln!("⚠️ Failed to rename legacy database: {}", e);
+    }
+    app_data_dir.join(database::DB_FILE_NAME)
+}
+
+/// `Ok(false)` when the command ran but found problems
+fn execute(invocation: &Invocation, db_path: &Path) -> Result<bool, String> {
+    let argument = invocation.arguments.first().map(PathBuf::from);
+    match invocation.command.as_str() {
+        "backup" => backup(db_path, argument).map(|_| true),
+        "restore" => restore(db_path, &argument.unwrap_or_default()).map(|_| true),
+        "verify" => verify(&argument.unwrap_or_default()).map(|report| report.print()),
+        "migrate" => crate::initialize_database(db_path).map(|report| report.print()),
+        "integrity-check" => integrity_check(db_path),
+        "export" => export(db_path, argument).map(|_| true),
+        "repair-stock" => repair_stock(db_path, invocation.product, invocation.dry_run),
+        other => Err(format!("Unknown command: {}", other)),
+    }
+}
+
+/// `store-YYYYMMDD-HHMMSS` in local time, for default backup and export names
+fn timestamped_name(conn: &Connection) -> Result<String, String> {
+    conn.query_row(
+        "SELECT strftime('store-%Y%m%d-%H%M%S', 'now', 'localtime')",
+        [],
+        |row| row.get(0),
+    )
+    .map_err(|e| format!("Failed to read the clock: {}", e))
+}
+
+pub fn backup(db_path: &Path, output: Option<PathBuf>) -> Result<PathBuf, String> {
+    let conn = open_connection_at(db_path)?;
+    let backup_path = match output {
+        Some(path) => path,
+        None => db_path
+            .with_file_name("backups")
+            .join(format!("{}.db", timestamped_name(&conn)?)),
+    };
+    drop(conn);
+
+    if backup_path.exists() {
+        return Err(format!("{} already exists", backup_path.display()));
+    }
+    if let Some(dir) = backup_path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+
+    let size = crate::consistent_backup(db_path, &backup_path)?;
+    let data = fs::read(&backup_path).map_err(|e| format!("Failed to read backup: {}", e))?;
+    println!("✅ Backup written to {}", backup_path.display());
+    println!("   {} bytes, sha256 {}", size, crate::calculate_checksum(&data));
+    Ok(backup_path)
+}
+
+/// Verify the backup before touching anything, then fold the WAL into the
+/// current file so the safety copy is complete and no stale frames are
+/// replayed onto the restored one
+pub fn restore(db_path: &Path, backup_path: &Path) -> Result<(), String> {
+    let report = verify(backup_path)?;
+    if !report.print() {
+        return Err(format!(
+            "{} failed verification; nothing was restored",
+            backup_path.display()
+        ));
+    }
+    let data = fs::read(backup_path).map_err(|e| format!("Failed to read {}: {}", backup_path.display(), e))?;
+
+    if db_path.exists() {
+        let conn = open_connection_at(db_path)?;
+        conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |row| row.get::<_, i64>(0))
+            .map_err(|e| format!("Failed to checkpoint the database: {}", e))
+            .and_then(|busy| match busy {
+                0 => Ok(()),
+                _ => Err("The database is in use; close the app before restoring".to_string()),
+            })?;
+    }
+
+    crate::restore_database_file(db_path, &data)?;
+    for suffix in ["-wal", "-shm"] {
+        let mut name = db_path.file_name().unwrap_or_default().to_os_string();
+        name.push(suffix);
+        let sidecar = db_path.with_file_name(name);
+        if sidecar.exists() {
+            fs::remove_file(&sidecar).map_err(|e| format!("Failed to remove {}: {}", sidecar.display(), e))?;
+        }
+    }
+
+    println!("✅ Restored {} from {}", db_path.display(), backup_path.display());
+    Ok(())
+}
+
+pub struct VerifyReport {
+    pub path: PathBuf,
+    pub size: u64,
+    pub checksum: String,
+    pub integrity: Vec<String>,
+    pub foreign_key_violations: usize,
+    pub missing_tables: Vec<String>,
+    pub row_counts: Vec<(String, i64)>,
+}
+
+impl VerifyReport {
+    pub fn is_ok(&self) -> bool {
+        self.integrity == ["ok"] && self.foreign_key_violations == 0 && self.missing_tables.is_empty()
+    }
+
+    /// Print the report and return whether the file passed
+    fn print(&self) -> bool {
+        println!("{}", self.path.display());
+        println!("   {} bytes, sha256 {}", self.size, self.checksum);
+        for (table, rows) in &self.row_counts {
+            println!("   {:<16} {} rows", table, rows);
+        }
+        if !self.missing_tables.is_empty() {
+            println!("   ❌ Missing tables: {}", self.missing_tables.join(", "));
+        }
+        if self.integrity != ["ok"] {
+            println!("   ❌ Integrity check failed:");
+            for line in &self.integrity {
+                println!("      {}", line);
+            }
+        }
+        if self.foreign_key_violations > 0 {
+            println!("   ❌ {} foreign key violations", self.foreign_key_violations);
+        }
+        if self.is_ok() {
+            println!("✅ Database is intact");
+        }
+        self.is_ok()
+    }
+}
+
+/// Check a database or backup file without changing its contents
+pub fn verify(path: &Path) -> Result<VerifyReport, String> {
+    let data = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    if !data.starts_with(b"SQLite format 3\0") {
+        return Err(format!("{} is not a SQLite database", path.display()));
+    }
+
+    // Not read-only: the full-text index check needs a writable handle. Nothing is changed.
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_WRITE)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+
+    let mut report = VerifyReport {
+        path: path.to_path_buf(),
+        size: data.len() as u64,
+        checksum: crate::calculate_checksum(&data),
+        integrity: pragma_rows(&conn, "PRAGMA integrity_check")?,
+        foreign_key_violations: pragma_rows(&conn, "PRAGMA foreign_key_check")?.len(),
+        missing_tables: Vec::new(),
+        row_counts: Vec::new(),
+    };
+
+    for table in REQUIRED_TABLES {
+        if !table_exists(&conn, table).map_err(|e| format!("Failed to read schema: {}", e))? {
+            report.missing_tables.push(table.to_string());
+            continue;
+        }
+        let rows = conn
+            .query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0))
+            .map_err(|e| format!("Failed to count {}: {}", table, e))?;
+        report.row_counts.push((table.to_string(), rows));
+    }
+
+    Ok(report)
+}
+
+/// First column of every row a PRAGMA returns, as text
+fn pragma_rows(conn: &Connection, pragma: &str) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare(pragma)
+        .map_err(|e| format!("Failed to run {}: {}", pragma, e))?;
+    let rows = stmt
+        .query_map([], |row| Ok(text_of(row.get_ref(0)?)))
+        .map_err(|e| format!("Failed to run {}: {}", pragma, e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to run {}: {}", pragma, e))
+}
+
+/// `Ok(false)` when anything is inconsistent
+pub fn integrity_check(db_path: &Path) -> Result<bool, String> {
+    let conn = open_connection_at(db_path)?;
+    let mut ok = true;
+
+    let integrity = pragma_rows(&conn, "PRAGMA integrity_check")?;
+    if integrity == ["ok"] {
+        println!("✅ SQLite integrity check passed");
+    } else {
+        ok = false;
+        println!("❌ SQLite integrity check failed:");
+        for line in &integrity {
+            println!("   {}", line);
+        }
+    }
+
+    let violations = pragma_rows(&conn, "PRAGMA foreign_key_check")?;
+    if violations.is_empty() {
+        println!("✅ No foreign key violations");
+    } else {
+        ok = false;
+        println!(
+            "❌ {} foreign key violations in: {}",
+            violations.len(),
+            distinct(&violations).join(", ")
+        );
+    }
+
+    let stock = check_stock(&conn, None)?;
+    if stock.divergent.is_empty() && stock.unreadable.is_empty() {
+        println!(
+            "✅ Stock matches the movement ledger for {} products",
+            stock.products_checked
+        );
+    } else {
+        ok = false;
+        println!(
+            "❌ Stock differs from the movement ledger for {} of {} products",
+            stock.divergent.len() + stock.unreadable.len(),
+            stock.products_checked
+        );
+        print_divergences(&stock.divergent);
+        print_unreadable(&stock.unreadable);
+    }
+
+    let balances = balance_report(&conn, None)?;
+    if balances.discrepancies.is_empty() {
+        println!(
+            "✅ Balances match the ledger for {} customers",
+            balances.customers_checked
+        );
+    } else {
+        ok = false;
+        println!(
+            "❌ Balances differ from the ledger for {} of {} customers",
+            balances.discrepancies.len(),
+            balances.customers_checked
+        );
+        for discrepancy in &balances.discrepancies {
+            println!(
+                "   #{} {}: stored {}, ledger {}",
+                discrepancy.customer_id,
+                discrepancy.customer_name,
+                discrepancy.stored_balance,
+                discrepancy.ledger_balance
+            );
+        }
+    }
+
+    Ok(ok)
+}
+
+fn distinct(values: &[String]) -> Vec<String> {
+    let mut values = values.to_vec();
+    values.sort();
+    values.dedup();
+    values
+}
+
+fn print_divergences(divergent: &[StockDivergence]) {
+    for product in divergent {
+        println!(
+            "   #{} {}: recorded {}, ledger {} ({} of {} movements off)",
+            product.product_id,
+            product.product_name,
+            product.recorded_stock,
+            product.computed_stock,
+            product.mismatched_movements,
+            product.movement_count
+        );
+    }
+}
+
+fn print_unreadable(unreadable: &[UnreadableStock]) {
+    for entry in unreadable {
+        let location = match entry.movement_id {
+            Some(id) => format!("movement #{}", id),
+            None => "stored stock".to_string(),
+        };
+        println!(
+            "   #{} {}: unreadable {} {:?}",
+            entry.product_id, entry.product_name, location, entry.raw_value
+        );
+    }
+}
+
+/// One CSV per business table, UTF-8 with a BOM so Excel keeps Urdu names
+pub fn export(db_path: &Path, output: Option<PathBuf>) -> Result<PathBuf, String> {
+    let conn = open_connection_at(db_path)?;
+    let dir = match output {
+        Some(dir) => dir,
+        None => db_path.with_file_name("exports").join(timestamped_name(&conn)?),
+    };
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+    let tables: Vec<String> = {
+        let mut stmt = conn
+            .prepare("SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name")
+            .map_err(|e| format!("Failed to list tables: {}", e))?;
+        let names = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(|e| format!("Failed to list tables: {}", e))?;
+        names
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to list tables: {}", e))?
+            .into_iter()
+            .filter(|name| !INTERNAL_PREFIXES.iter().any(|prefix| name.starts_with(prefix)))
+            .collect()
+    };
+
+    for table in &tables {
+        let rows = export_table(&conn, table, &dir.join(format!("{}.csv", table)))?;
+        println!("   {:<28} {} rows", table, rows);
+    }
+
+    println!("✅ Exported {} tables to {}", tables.len(), dir.display());
+    Ok(dir)
+}
+
+fn export_table(conn: &Connection, table: &str, path: &Path) -> Result<usize, String> {
+    let mut stmt = conn
+        .prepare(&format!("SELECT * FROM \"{}\"", table.replace('"', "\"\"")))
+        .map_err(|e| format!("Failed to read {}: {}", table, e))?;
+    let columns: Vec<String> = stmt.column_names().iter().map(|name| name.to_string()).collect();
+
+    let mut csv = String::from("\u{feff}");
+    push_record(&mut csv, columns.iter().cloned());
+
+    let mut count = 0;
+    let mut rows = stmt.query([]).map_err(|e| format!("Failed to read {}: {}", table, e))?;
+    while let Some(row) = rows.next().map_err(|e| format!("Failed to read {}: {}", table, e))? {
+        let values = (0..columns.len())
+            .map(|i| row.get_ref(i).map(text_of))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read {}: {}", table, e))?;
+        push_record(&mut csv, values.into_iter());
+        count += 1;
+    }
+
+    fs::write(path, csv).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(count)
+}
+
+fn text_of(value: ValueRef) -> String {
+    match value {
+        ValueRef::Null => String::new(),
+        ValueRef::Integer(i) => i.to_string(),
+        ValueRef::Real(f) => f.to_string(),
+        ValueRef::Text(bytes) => String::from_utf8_lossy(bytes).into_owned(),
+        ValueRef::Blob(bytes) => bytes.iter().map(|b| format!("{:02x}", b)).collect(),
+    }
+}
+
+fn push_record(csv: &mut String, fields: impl Iterator<Item = String>) {
+    let fields: Vec<String> = fields
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field
+            }
+        })
+        .collect();
+    csv.push_str(&fields.join(","));
+    csv.push_str("\r\n");
+}
+
+/// Rebuild stored stock and running balances from the movement ledger.
+/// Movements whose quantity cannot be read are never guessed at: their
+/// products are left alone and listed, and the run reports failure.
+pub fn repair_stock(db_path: &Path, product_id: Option<i64>, dry_run: bool) -> Result<bool, String> {
+    let mut conn = open_connection_at(db_path)?;
+
+    let (changes, skipped, products) = if dry_run {
+        let report = check_stock(&conn, product_id)?;
+        (report.divergent, report.unreadable, report.products_checked)
+    } else {
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+        let result = rebuild_stock_in_transaction(&tx, product_id, "cli")?;
+        tx.commit()
+            .map_err(|e| format!("Failed to commit stock rebuild: {}", e))?;
+        (result.changes, result.skipped, result.products_rebuilt)
+    };
+
+    if changes.is_empty() {
+        println!("✅ Stock already matches the movement ledger");
+    } else {
+        println!(
+            "{} {} products:",
+            if dry_run { "Rebuild would change" } else { "Rebuilt" },
+            changes.len()
+        );
+        print_divergences(&changes);
+    }
+    if !dry_run {
+        println!("   {} products rebuilt", products);
+    }
+    if !skipped.is_empty() {
+        println!(
+            "⚠️ {} products skipped because these values cannot be read:",
+            skipped.len()
+        );
+        print_unreadable(&skipped);
+        println!("   Correct the quantities in the app, then run repair-stock again.");
+    }
+
+    Ok(skipped.is_empty())
+}
+
+/// A Windows release build has no console of its own; borrow the one of the
+/// shell that started it so output and exit status reach the script
+#[cfg(windows)]
+fn attach_parent_console() {
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_parent_console() {}
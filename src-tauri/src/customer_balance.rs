@@ -0,0 +1,723 @@
+/*!
+ * CUSTOMER BALANCE AUTHORITY
+ * A customer's balance is the replay of their ledger: every invoice,
+ * payment, return and adjustment posted to `customer_ledger_entries` in
+ * chronological order (date, year openings first, then insertion order). Debits raise the
+ * balance, credits lower it, and 'adjustment' entry types are zero-amount
+ * references that never move it. `customers.balance` and each entry's
+ * `balance_before`/`balance_after` are derived copies of that replay.
+ *
+ * Every recalculation first produces the discrepancy report and only then
+ * rewrites the stored copies, inside one transaction; a recalculation run
+ * from the app is audited with that report.
+ *
+ * Ledger entries are never archived, so a statement can span any number of
+ * years; the invoices behind its lines may come from a year's archive.
+ */
+
+use std::collections::BTreeMap;
+
+use log::info;
+use rusqlite::{params, Connection, OptionalExtension, Transaction, TransactionBehavior};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::archive::attach_archives;
+use crate::audit::{self, AuditEvent};
+use crate::database::{open_connection, table_exists};
+use crate::fiscal_year::OPENING_FIRST_SQL;
+use crate::money::{paisa_from_row, paisa_sql, sum_paisa_sql, Money};
+use crate::quantity::value_to_text;
+use crate::session;
+
+/// Walk-in sales are billed to this customer and never posted to the ledger
+pub const GUEST_CUSTOMER_ID: i64 = -1;
+
+/// Signed effect of a ledger row on the customer's balance, given its amount in paisa
+pub fn ledger_effect_sql(amount: &str) -> String {
+    format!("CASE WHEN entry_type = 'debit' THEN {amount} WHEN entry_type = 'credit' THEN -{amount} ELSE 0 END")
+}
+
+pub fn ledger_effect(entry_type: &str, amount: Money) -> Money {
+    match entry_type {
+        "debit" => amount,
+        "credit" => -amount,
+        _ => Money::ZERO,
+    }
+}
+
+/// Current balance of a customer straight from the ledger
+pub fn ledger_balance(conn: &Connection, customer_id: i64) -> rusqlite::Result<Money> {
+    let amount = paisa_sql(conn, "customer_ledger_entries", "amount")?;
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM customer_ledger_entries WHERE customer_id = ?1",
+            sum_paisa_sql(&ledger_effect_sql(&amount))
+        ),
+        [customer_id],
+        |row| row.get(0).map(Money::from_paisa),
+    )
+}
+
+/// A customer whose stored balances disagree with the ledger replay
+#[derive(Debug, Serialize)]
+pub struct CustomerBalanceDiscrepancy {
+    pub customer_id: i64,
+    pub customer_name: String,
+    pub stored_balance: Money,
+    pub ledger_balance: Money,
+    pub difference: Money,
+    pub entry_count: usize,
+    pub mismatched_entries: usize,
+    pub unposted_invoices: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CustomerBalanceReport {
+    pub customers_checked: usize,
+    pub consistent_customers: usize,
+    pub total_stored_balance: Money,
+    pub total_ledger_balance: Money,
+    pub discrepancies: Vec<CustomerBalanceDiscrepancy>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BalanceRecalculation {
+    /// The discrepancies found before anything was written
+    pub report: CustomerBalanceReport,
+    pub customers_updated: usize,
+    pub entries_updated: usize,
+}
+
+struct CustomerRow {
+    id: i64,
+    name: String,
+    balance: Money,
+}
+
+struct EntryRow {
+    id: i64,
+    entry_type: String,
+    amount: Money,
+    balance_before: Money,
+    balance_after: Money,
+}
+
+/// One customer's ledger replayed from zero
+struct CustomerLedger {
+    customer: CustomerRow,
+    /// (entry, replayed balance before, replayed balance after)
+    balances: Vec<(EntryRow, Money, Money)>,
+    unposted_invoices: Vec<String>,
+}
+
+impl CustomerLedger {
+    fn balance(&self) -> Money {
+        self.balances.last().map(|(_, _, after)| *after).unwrap_or(Money::ZERO)
+    }
+
+    fn mismatched_entries(&self) -> impl Iterator<Item = &(EntryRow, Money, Money)> {
+        self.balances
+            .iter()
+            .filter(|(entry, before, after)| entry.balance_before != *before || entry.balance_after != *after)
+    }
+
+    fn discrepancy(&self) -> Option<CustomerBalanceDiscrepancy> {
+        let mismatched = self.mismatched_entries().count();
+        if self.customer.balance == self.balance() && mismatched == 0 && self.unposted_invoices.is_empty() {
+            return None;
+        }
+        Some(CustomerBalanceDiscrepancy {
+            customer_id: self.customer.id,
+            customer_name: self.customer.name.clone(),
+            stored_balance: self.customer.balance,
+            ledger_balance: self.balance(),
+            difference: self.balance() - self.customer.balance,
+            entry_count: self.balances.len(),
+            mismatched_entries: mismatched,
+            unposted_invoices: self.unposted_invoices.clone(),
+        })
+    }
+}
+
+fn load_customers(conn: &Connection, customer_id: Option<i64>) -> Result<Vec<CustomerRow>, String> {
+    let balance = paisa_sql(conn, "customers", "balance").map_err(|e| format!("Failed to inspect schema: {}", e))?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT id, name, {balance} FROM customers
+             WHERE id != ?1 AND (?2 IS NULL OR id = ?2)
+             ORDER BY id"
+        ))
+        .map_err(|e| format!("Failed to query customers: {}", e))?;
+    let rows = stmt
+        .query_map(params![GUEST_CUSTOMER_ID, customer_id], |row| {
+            Ok(CustomerRow {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                balance: paisa_from_row(row, 2)?,
+            })
+        })
+        .map_err(|e| format!("Failed to read customers: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read customer: {}", e))
+}
+
+fn load_entries(conn: &Connection, customer_id: Option<i64>) -> Result<BTreeMap<i64, Vec<EntryRow>>, String> {
+    let paisa = |column| {
+        paisa_sql(conn, "customer_ledger_entries", column).map_err(|e| format!("Failed to inspect schema: {}", e))
+    };
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT customer_id, id, entry_type, {}, {}, {}
+             FROM customer_ledger_entries
+             WHERE ?1 IS NULL OR customer_id = ?1
+             ORDER BY customer_id, date, {}, id",
+            paisa("amount")?,
+            paisa("balance_before")?,
+            paisa("balance_after")?,
+            OPENING_FIRST_SQL
+        ))
+        .map_err(|e| format!("Failed to query ledger entries: {}", e))?;
+    let rows = stmt
+        .query_map([customer_id], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                EntryRow {
+                    id: row.get(1)?,
+                    entry_type: row.get(2)?,
+                    amount: paisa_from_row(row, 3)?,
+                    balance_before: paisa_from_row(row, 4)?,
+                    balance_after: paisa_from_row(row, 5)?,
+                },
+            ))
+        })
+        .map_err(|e| format!("Failed to read ledger entries: {}", e))?;
+
+    let mut grouped: BTreeMap<i64, Vec<EntryRow>> = BTreeMap::new();
+    for row in rows {
+        let (customer_id, entry) = row.map_err(|e| format!("Failed to read ledger entry: {}", e))?;
+        grouped.entry(customer_id).or_default().push(entry);
+    }
+    Ok(grouped)
+}
+
+/// Live invoices with an amount due that have no invoice debit on the ledger.
+/// These are reported for review; posting them is left to the invoice flow.
+fn load_unposted_invoices(conn: &Connection, customer_id: Option<i64>) -> Result<BTreeMap<i64, Vec<String>>, String> {
+    let mut grouped: BTreeMap<i64, Vec<String>> = BTreeMap::new();
+    if !table_exists(conn, "invoices").map_err(|e| format!("Failed to inspect schema: {}", e))? {
+        return Ok(grouped);
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT i.customer_id, i.bill_number
+             FROM invoices i
+             WHERE i.customer_id != ?1 AND (?2 IS NULL OR i.customer_id = ?2)
+               AND COALESCE(i.status, '') != 'cancelled'
+               AND i.grand_total > 0
+               AND NOT EXISTS (
+                   SELECT 1 FROM customer_ledger_entries l
+                   WHERE l.customer_id = i.customer_id AND l.transaction_type = 'invoice'
+                     AND l.entry_type = 'debit' AND (l.reference_id = i.id OR l.invoice_id = i.id)
+               )
+             ORDER BY i.id",
+        )
+        .map_err(|e| format!("Failed to query unposted invoices: {}", e))?;
+    let rows = stmt
+        .query_map(params![GUEST_CUSTOMER_ID, customer_id], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(|e| format!("Failed to read unposted invoices: {}", e))?;
+    for row in rows {
+        let (customer_id, bill_number) = row.map_err(|e| format!("Failed to read unposted invoice: {}", e))?;
+        grouped.entry(customer_id).or_default().push(bill_number);
+    }
+    Ok(grouped)
+}
+
+fn replay_all(conn: &Connection, customer_id: Option<i64>) -> Result<Vec<CustomerLedger>, String> {
+    let customers = load_customers(conn, customer_id)?;
+    let mut entries = load_entries(conn, customer_id)?;
+    let mut unposted = load_unposted_invoices(conn, customer_id)?;
+
+    Ok(customers
+        .into_iter()
+        .map(|customer| {
+            let mut running = Money::ZERO;
+            let balances = entries
+                .remove(&customer.id)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|entry| {
+                    let before = running;
+                    running += ledger_effect(&entry.entry_type, entry.amount);
+                    (entry, before, running)
+                })
+                .collect();
+            CustomerLedger {
+                unposted_invoices: unposted.remove(&customer.id).unwrap_or_default(),
+                customer,
+                balances,
+            }
+        })
+        .collect())
+}
+
+fn build_report(ledgers: &[CustomerLedger]) -> CustomerBalanceReport {
+    let discrepancies: Vec<CustomerBalanceDiscrepancy> =
+        ledgers.iter().filter_map(CustomerLedger::discrepancy).collect();
+    CustomerBalanceReport {
+        customers_checked: ledgers.len(),
+        consistent_customers: ledgers.len() - discrepancies.len(),
+        total_stored_balance: ledgers.iter().map(|ledger| ledger.customer.balance).sum(),
+        total_ledger_balance: ledgers.iter().map(CustomerLedger::balance).sum(),
+        discrepancies,
+    }
+}
+
+/// Discrepancy report for one customer or all of them, without writing anything
+pub fn balance_report(conn: &Connection, customer_id: Option<i64>) -> Result<CustomerBalanceReport, String> {
+    Ok(build_report(&replay_all(conn, customer_id)?))
+}
+
+/// Report, then rewrite the running balances and `customers.balance` from the replay
+pub fn recalculate_in_transaction(tx: &Transaction, customer_id: Option<i64>) -> Result<BalanceRecalculation, String> {
+    let ledgers = replay_all(tx, customer_id)?;
+    if let Some(id) = customer_id {
+        if ledgers.is_empty() {
+            return Err(format!("Customer {} not found", id));
+        }
+    }
+    let report = build_report(&ledgers);
+
+    let mut customers_updated = 0;
+    let mut entries_updated = 0;
+    for ledger in &ledgers {
+        for (entry, before, after) in ledger.mismatched_entries() {
+            tx.execute(
+                "UPDATE customer_ledger_entries SET balance_before = ?1, balance_after = ?2 WHERE id = ?3",
+                params![before.to_rupees(), after.to_rupees(), entry.id],
+            )
+            .map_err(|e| format!("Failed to update ledger entry {}: {}", entry.id, e))?;
+            entries_updated += 1;
+        }
+
+        if ledger.customer.balance != ledger.balance() {
+            tx.execute(
+                "UPDATE customers SET balance = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+                params![ledger.balance().to_rupees(), ledger.customer.id],
+            )
+            .map_err(|e| format!("Failed to update balance of {}: {}", ledger.customer.name, e))?;
+            info!(
+                "[BALANCE] {}: Rs.{} -> Rs.{}",
+                ledger.customer.name,
+                ledger.customer.balance,
+                ledger.balance()
+            );
+            customers_updated += 1;
+        }
+    }
+
+    Ok(BalanceRecalculation {
+        report,
+        customers_updated,
+        entries_updated,
+    })
+}
+
+fn recalculate(customer_id: Option<i64>) -> Result<BalanceRecalculation, String> {
+    let mut conn = open_connection()?;
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let result = recalculate_in_transaction(&tx, customer_id)?;
+    let event = AuditEvent::new("recalculate_balance", "customer").by(&session::current_username());
+    let event = match customer_id {
+        Some(customer_id) => event.entity(customer_id),
+        None => event,
+    };
+    audit::record(&tx, &event.after(json!(&result)))?;
+
+    tx.commit()
+        .map_err(|e| format!("Failed to commit balance recalculation: {}", e))?;
+
+    info!(
+        "[BALANCE] Recalculated {} customers: {} balances and {} ledger rows corrected",
+        result.report.customers_checked, result.customers_updated, result.entries_updated
+    );
+    Ok(result)
+}
+
+/// An item of an invoice shown on a statement
+#[derive(Debug, Serialize)]
+pub struct StatementInvoiceItem {
+    pub product_name: String,
+    pub quantity: String,
+    pub unit: String,
+    pub unit_price: Money,
+    pub total_price: Money,
+}
+
+/// The invoice behind a statement line, live or from a year's archive
+#[derive(Debug, Serialize)]
+pub struct StatementInvoice {
+    pub id: i64,
+    pub bill_number: String,
+    pub grand_total: Money,
+    pub archived: bool,
+    pub items: Vec<StatementInvoiceItem>,
+}
+
+/// A ledger entry on a statement with the balance after it
+#[derive(Debug, Serialize)]
+pub struct StatementLine {
+    pub entry_id: i64,
+    pub date: String,
+    pub entry_type: String,
+    pub transaction_type: String,
+    pub description: String,
+    pub reference_number: Option<String>,
+    pub amount: Money,
+    pub balance: Money,
+    pub invoice: Option<StatementInvoice>,
+}
+
+/// A customer's ledger between two dates, opening from the replay before them
+#[derive(Debug, Serialize)]
+pub struct CustomerStatement {
+    pub customer_id: i64,
+    pub customer_name: String,
+    pub from_date: Option<String>,
+    pub to_date: Option<String>,
+    pub opening_balance: Money,
+    pub closing_balance: Money,
+    pub lines: Vec<StatementLine>,
+    /// Fiscal years whose archives were read for invoice details
+    pub archives_attached: Vec<String>,
+}
+
+/// Invoice details from `all_invoices`, the live and archived invoices combined
+fn statement_invoice(conn: &Connection, invoice_id: i64) -> Result<Option<StatementInvoice>, String> {
+    let grand_total = paisa_sql(conn, "all_invoices", "grand_total")
+        .map_err(|e| format!("Failed to inspect archived invoices: {}", e))?;
+    let invoice = conn
+        .query_row(
+            &format!(
+                "SELECT id, bill_number, {grand_total}, id NOT IN (SELECT id FROM main.invoices)
+                 FROM all_invoices WHERE id = ?1"
+            ),
+            [invoice_id],
+            |row| {
+                Ok(StatementInvoice {
+                    id: row.get(0)?,
+                    bill_number: row.get(1)?,
+                    grand_total: paisa_from_row(row, 2)?,
+                    archived: row.get(3)?,
+                    items: Vec::new(),
+                })
+            },
+        )
+        .optional()
+        .map_err(|e| format!("Failed to read invoice {}: {}", invoice_id, e))?;
+    let Some(mut invoice) = invoice else {
+        return Ok(None);
+    };
+
+    let paisa = |column| {
+        paisa_sql(conn, "all_invoice_items", column)
+            .map_err(|e| format!("Failed to inspect archived invoice items: {}", e))
+    };
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT product_name, quantity, unit, {}, {}
+             FROM all_invoice_items WHERE invoice_id = ?1 ORDER BY id",
+            paisa("unit_price")?,
+            paisa("total_price")?
+        ))
+        .map_err(|e| format!("Failed to query invoice items: {}", e))?;
+    invoice.items = stmt
+        .query_map([invoice_id], |row| {
+            Ok(StatementInvoiceItem {
+                product_name: row.get(0)?,
+                quantity: row.get::<_, rusqlite::types::Value>(1).map(value_to_text)?,
+                unit: row.get(2)?,
+                unit_price: paisa_from_row(row, 3)?,
+                total_price: paisa_from_row(row, 4)?,
+            })
+        })
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| format!("Failed to read items of invoice {}: {}", invoice_id, e))?;
+    Ok(Some(invoice))
+}
+
+/// Build a statement on a connection that has the archives attached
+pub fn customer_statement(
+    conn: &Connection,
+    customer_id: i64,
+    from_date: Option<&str>,
+    to_date: Option<&str>,
+) -> Result<CustomerStatement, String> {
+    let customer_name: String = conn
+        .query_row("SELECT name FROM customers WHERE id = ?1", [customer_id], |row| {
+            row.get(0)
+        })
+        .optional()
+        .map_err(|e| format!("Failed to load customer {}: {}", customer_id, e))?
+        .ok_or_else(|| format!("Customer {} not found", customer_id))?;
+
+    let amount = paisa_sql(conn, "customer_ledger_entries", "amount")
+        .map_err(|e| format!("Failed to inspect schema: {}", e))?;
+    let opening_balance = match from_date {
+        Some(from_date) => conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM customer_ledger_entries WHERE customer_id = ?1 AND date < ?2",
+                    sum_paisa_sql(&ledger_effect_sql(&amount))
+                ),
+                params![customer_id, from_date],
+                |row| row.get(0).map(Money::from_paisa),
+            )
+            .map_err(|e| format!("Failed to compute opening balance: {}", e))?,
+        None => Money::ZERO,
+    };
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT id, date, entry_type, transaction_type, description, reference_number, {},
+                    CASE WHEN transaction_type = 'invoice' THEN COALESCE(invoice_id, reference_id) END
+             FROM customer_ledger_entries
+             WHERE customer_id = ?1 AND (?2 IS NULL OR date >= ?2) AND (?3 IS NULL OR date <= ?3)
+             ORDER BY date, {}, id",
+            amount, OPENING_FIRST_SQL
+        ))
+        .map_err(|e| format!("Failed to query ledger entries: {}", e))?;
+    let rows = stmt
+        .query_map(params![customer_id, from_date, to_date], |row| {
+            Ok((
+                StatementLine {
+                    entry_id: row.get(0)?,
+                    date: row.get(1)?,
+                    entry_type: row.get(2)?,
+                    transaction_type: row.get(3)?,
+                    description: row.get(4)?,
+                    reference_number: row.get(5)?,
+                    amount: paisa_from_row(row, 6)?,
+                    balance: Money::ZERO,
+                    invoice: None,
+                },
+                row.get::<_, Option<i64>>(7)?,
+            ))
+        })
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| format!("Failed to read ledger entries: {}", e))?;
+
+    let mut balance = opening_balance;
+    let mut lines = Vec::with_capacity(rows.len());
+    for (mut line, invoice_id) in rows {
+        balance += ledger_effect(&line.entry_type, line.amount);
+        line.balance = balance;
+        if let Some(invoice_id) = invoice_id {
+            line.invoice = statement_invoice(conn, invoice_id)?;
+        }
+        lines.push(line);
+    }
+
+    Ok(CustomerStatement {
+        customer_id,
+        customer_name,
+        from_date: from_date.map(str::to_string),
+        to_date: to_date.map(str::to_string),
+        opening_balance,
+        closing_balance: balance,
+        lines,
+        archives_attached: Vec::new(),
+    })
+}
+
+/// Discrepancies between stored balances and the ledger replay (read-only)
+#[tauri::command]
+pub async fn get_customer_balance_report(customer_id: Option<i64>) -> Result<CustomerBalanceReport, String> {
+    let conn = open_connection()?;
+    balance_report(&conn, customer_id)
+}
+
+/// A customer's statement between two dates, reading archived invoices as well
+#[tauri::command]
+pub async fn get_customer_statement(
+    customer_id: i64,
+    from_date: Option<String>,
+    to_date: Option<String>,
+) -> Result<CustomerStatement, String> {
+    let conn = open_connection()?;
+    let archives_attached = attach_archives(&conn)?;
+    let mut statement = customer_statement(&conn, customer_id, from_date.as_deref(), to_date.as_deref())?;
+    statement.archives_attached = archives_attached;
+    Ok(statement)
+}
+
+/// Replay one customer's ledger and store the derived balances
+#[tauri::command]
+pub async fn recalculate_customer_balance(customer_id: i64) -> Result<BalanceRecalculation, String> {
+    if customer_id == GUEST_CUSTOMER_ID {
+        return Err("Walk-in customers have no ledger to recalculate".to_string());
+    }
+    recalculate(Some(customer_id))
+}
+
+/// Replay every customer's ledger and store the derived balances
+#[tauri::command]
+pub async fn recalculate_all() -> Result<BalanceRecalculation, String> {
+    recalculate(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rs(rupees: i64) -> Money {
+        Money::from_paisa(rupees * 100)
+    }
+
+    /// Ali's stored balances are stale: an invoice entered late on an earlier
+    /// date, a year opening marker and an adjustment were never replayed.
+    /// Bilal's ledger agrees, but one of his invoices never reached it.
+    fn database() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE customers (
+                id INTEGER PRIMARY KEY, name TEXT NOT NULL, balance REAL NOT NULL DEFAULT 0, updated_at TEXT
+            );
+            CREATE TABLE customer_ledger_entries (
+                id INTEGER PRIMARY KEY, customer_id INTEGER NOT NULL, entry_type TEXT NOT NULL,
+                transaction_type TEXT NOT NULL, amount REAL NOT NULL, description TEXT NOT NULL DEFAULT '',
+                reference_id INTEGER, reference_number TEXT, invoice_id INTEGER,
+                balance_before REAL NOT NULL DEFAULT 0, balance_after REAL NOT NULL DEFAULT 0, date TEXT NOT NULL
+            );
+            CREATE TABLE invoices (
+                id INTEGER PRIMARY KEY, customer_id INTEGER, bill_number TEXT, grand_total REAL, status TEXT
+            );
+            CREATE TABLE invoice_items (
+                id INTEGER PRIMARY KEY, invoice_id INTEGER, product_name TEXT, quantity TEXT, unit TEXT,
+                unit_price REAL, total_price REAL
+            );
+            INSERT INTO customers (id, name, balance) VALUES (-1, 'Walk-in', 0), (1, 'Ali', 0), (2, 'Bilal', 250);
+            INSERT INTO invoices (id, customer_id, bill_number, grand_total, status) VALUES
+                (1, 1, 'I00001', 1000, 'pending'), (2, 2, 'I00002', 250, 'pending'),
+                (3, 1, 'I00003', 300, 'pending'), (4, 2, 'I00004', 500, 'pending'),
+                (5, 2, 'I00005', 700, 'cancelled'), (6, -1, 'I00006', 90, 'paid');
+            INSERT INTO invoice_items (invoice_id, product_name, quantity, unit, unit_price, total_price)
+                VALUES (1, 'Pipe', '2', 'piece', 500, 1000);
+            INSERT INTO customer_ledger_entries (id, customer_id, entry_type, transaction_type, amount,
+                                                 reference_id, reference_number, balance_before, balance_after, date)
+            VALUES
+                (1, 1, 'debit', 'invoice', 1000, 1, 'I00001', 0, 1000, '2026-01-05'),
+                (2, 1, 'credit', 'payment', 400, NULL, 'PAY-1', 1000, 600, '2026-01-10'),
+                (3, 1, 'debit', 'invoice', 300, 3, 'I00003', 0, 300, '2026-01-03'),
+                (4, 1, 'adjustment', 'adjustment', 50, NULL, 'ADJ-1', 0, 0, '2026-01-10'),
+                (5, 1, 'adjustment', 'opening_balance', 0, NULL, 'OPENING-2026', 0, 0, '2026-01-10'),
+                (6, 2, 'debit', 'invoice', 250, 2, 'I00002', 0, 250, '2026-01-04');",
+        )
+        .unwrap();
+        conn
+    }
+
+    /// (entry id, balance before, balance after) in replay order
+    fn stored_balances(conn: &Connection, customer_id: i64) -> Vec<(i64, f64, f64)> {
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT id, balance_before, balance_after FROM customer_ledger_entries
+                 WHERE customer_id = ?1 ORDER BY date, {}, id",
+                OPENING_FIRST_SQL
+            ))
+            .unwrap();
+        let rows = stmt
+            .query_map([customer_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap();
+        rows.collect::<Result<_, _>>().unwrap()
+    }
+
+    #[test]
+    fn the_report_finds_stale_balances_and_unposted_invoices() {
+        let conn = database();
+        let report = balance_report(&conn, None).unwrap();
+        assert_eq!((report.customers_checked, report.consistent_customers), (2, 0));
+        assert_eq!(
+            (report.total_stored_balance, report.total_ledger_balance),
+            (rs(250), rs(1150))
+        );
+
+        let ali = &report.discrepancies[0];
+        assert_eq!(
+            (ali.customer_id, ali.stored_balance, ali.ledger_balance),
+            (1, rs(0), rs(900))
+        );
+        assert_eq!(
+            (ali.difference, ali.entry_count, ali.mismatched_entries),
+            (rs(900), 5, 4)
+        );
+        let bilal = &report.discrepancies[1];
+        assert_eq!((bilal.difference, bilal.mismatched_entries), (Money::ZERO, 0));
+        assert_eq!(bilal.unposted_invoices, ["I00004"]);
+    }
+
+    #[test]
+    fn recalculating_stores_the_replay_once() {
+        let mut conn = database();
+        let tx = conn.transaction().unwrap();
+        let result = recalculate_in_transaction(&tx, None).unwrap();
+        tx.commit().unwrap();
+        assert_eq!((result.customers_updated, result.entries_updated), (1, 4));
+        assert_eq!(result.report.discrepancies.len(), 2);
+
+        // Dated order, the opening marker first on its day, adjustments moving nothing
+        assert_eq!(
+            stored_balances(&conn, 1),
+            [
+                (3, 0.0, 300.0),
+                (1, 300.0, 1300.0),
+                (5, 1300.0, 1300.0),
+                (2, 1300.0, 900.0),
+                (4, 900.0, 900.0)
+            ]
+        );
+        assert_eq!(ledger_balance(&conn, 1).unwrap(), rs(900));
+        let stored: f64 = conn
+            .query_row("SELECT balance FROM customers WHERE id = 1", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(stored, 900.0);
+
+        // Only Bilal's unposted invoice is left to report, and nothing more to write
+        let report = balance_report(&conn, None).unwrap();
+        assert_eq!(report.discrepancies.len(), 1);
+        assert_eq!(report.discrepancies[0].customer_id, 2);
+        let tx = conn.transaction().unwrap();
+        let again = recalculate_in_transaction(&tx, Some(1)).unwrap();
+        assert_eq!((again.customers_updated, again.entries_updated), (0, 0));
+        assert!(recalculate_in_transaction(&tx, Some(9)).is_err());
+    }
+
+    #[test]
+    fn a_statement_opens_with_the_balance_before_its_first_day() {
+        let conn = database();
+        attach_archives(&conn).unwrap();
+        let statement = customer_statement(&conn, 1, Some("2026-01-05"), Some("2026-01-10")).unwrap();
+        assert_eq!(statement.opening_balance, rs(300));
+        let lines: Vec<_> = statement
+            .lines
+            .iter()
+            .map(|line| (line.entry_id, line.balance))
+            .collect();
+        assert_eq!(lines, [(1, rs(1300)), (5, rs(1300)), (2, rs(900)), (4, rs(900))]);
+        assert_eq!(statement.closing_balance, ledger_balance(&conn, 1).unwrap());
+
+        let invoice = statement.lines[0].invoice.as_ref().unwrap();
+        assert_eq!((invoice.bill_number.as_str(), invoice.archived), ("I00001", false));
+        assert_eq!(invoice.items[0].total_price, rs(1000));
+
+        let whole = customer_statement(&conn, 1, None, None).unwrap();
+        assert_eq!((whole.opening_balance, whole.lines.len()), (Money::ZERO, 5));
+        assert!(customer_statement(&conn, 9, None, None).is_err());
+    }
+}
@@ -0,0 +1,1570 @@
+/*!
+ * DATA IMPORT
+ *
+ * Onboarding a shop reads its products, customers and vendors from a CSV or
+ * XLSX file instead of typing them in. A mapping names, for each field, the
+ * header of the column holding it; headers that match a field's name are
+ * mapped without asking. Mappings are saved by name in `import_profiles` so
+ * the next file from the same source reuses them.
+ *
+ * Every row is checked before it is written: required fields, amounts, the
+ * unit type (kg-grams, kg, ton, piece, bag, foot, meter and their common
+ * spellings) and a stock quantity readable in that unit, and records that
+ * already exist or repeat within the file. Rows are then inserted one
+ * savepoint at a time, so database constraints report against their row too.
+ *
+ * A dry run does all of that and rolls back, returning the per-row errors. A
+ * real import commits only when every row is valid: the whole file or
+ * nothing. Opening stock is posted as an IN movement and opening balances as
+ * ledger entries (customer debit = owes us, vendor credit = we owe them),
+ * dated the opening date and referenced IMPORT-<run>, so stock and balances
+ * replay from the ledgers like any other figure.
+ */
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use calamine::{open_workbook_auto, Data, Reader};
+use log::{info, warn};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Transaction, TransactionBehavior};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::audit::{self, AuditEvent};
+use crate::customer_balance::recalculate_in_transaction;
+use crate::database::{current_date, has_column, open_connection};
+use crate::money::Money;
+use crate::quantity::{
+    format_movement_quantity, format_number, format_stock_text, parse_stock_text, set_movement_bases,
+    set_product_stock, to_movement_number, UnitType,
+};
+use crate::session;
+use crate::vendor_payables::{sync_vendor_in_transaction, VendorSyncResult};
+
+/// Opening postings are referenced as IMPORT-<import run id>
+const IMPORT_REFERENCE_PREFIX: &str = "IMPORT-";
+
+/// Rows shown by a preview
+const PREVIEW_ROWS: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FieldKind {
+    Text,
+    Money,
+    /// Stock text in the row's unit type
+    Stock,
+    Unit,
+}
+
+struct ImportField {
+    name: &'static str,
+    label: &'static str,
+    kind: FieldKind,
+    required: bool,
+    /// Column the value is written to; opening figures are posted instead
+    column: Option<&'static str>,
+    /// Other headers that mean this field
+    aliases: &'static [&'static str],
+}
+
+const fn field(name: &'static str, label: &'static str, kind: FieldKind) -> ImportField {
+    ImportField {
+        name,
+        label,
+        kind,
+        required: false,
+        column: Some(name),
+        aliases: &[],
+    }
+}
+
+const fn required(mut field: ImportField) -> ImportField {
+    field.required = true;
+    field
+}
+
+const fn posted(mut field: ImportField) -> ImportField {
+    field.column = None;
+    field
+}
+
+const fn aliases(mut field: ImportField, aliases: &'static [&'static str]) -> ImportField {
+    field.aliases = aliases;
+    field
+}
+
+const PRODUCT_FIELDS: &[ImportField] = &[
+    required(aliases(
+        field("name", "Product name", FieldKind::Text),
+        &["product", "item", "item name"],
+    )),
+    field("category", "Category", FieldKind::Text),
+    aliases(field("unit_type", "Unit type", FieldKind::Unit), &["unit", "uom"]),
+    aliases(
+        field("rate_per_unit", "Sale rate", FieldKind::Money),
+        &["rate", "price", "sale price", "selling price"],
+    ),
+    aliases(
+        field("cost_price", "Cost price", FieldKind::Money),
+        &["cost", "purchase price"],
+    ),
+    field("sku", "SKU", FieldKind::Text),
+    field("barcode", "Barcode", FieldKind::Text),
+    field("size", "Size", FieldKind::Text),
+    field("grade", "Grade", FieldKind::Text),
+    field("brand", "Brand", FieldKind::Text),
+    field("description", "Description", FieldKind::Text),
+    aliases(
+        field("min_stock_alert", "Minimum stock", FieldKind::Stock),
+        &["min stock", "reorder level", "low stock alert"],
+    ),
+    posted(aliases(
+        field("opening_stock", "Opening stock", FieldKind::Stock),
+        &["stock", "current stock", "quantity", "qty"],
+    )),
+];
+
+const CUSTOMER_FIELDS: &[ImportField] = &[
+    aliases(field("customer_code", "Customer code", FieldKind::Text), &["code"]),
+    required(aliases(
+        field("name", "Customer name", FieldKind::Text),
+        &["customer", "customer name"],
+    )),
+    aliases(
+        field("phone", "Phone", FieldKind::Text),
+        &["mobile", "contact", "phone number"],
+    ),
+    aliases(field("cnic", "CNIC", FieldKind::Text), &["nic"]),
+    field("email", "Email", FieldKind::Text),
+    field("address", "Address", FieldKind::Text),
+    aliases(
+        field("company_name", "Company", FieldKind::Text),
+        &["company", "business"],
+    ),
+    field("credit_limit", "Credit limit", FieldKind::Money),
+    field("notes", "Notes", FieldKind::Text),
+    posted(aliases(
+        field("opening_balance", "Opening balance (owes us)", FieldKind::Money),
+        &["balance", "receivable", "outstanding"],
+    )),
+];
+
+const VENDOR_FIELDS: &[ImportField] = &[
+    aliases(field("vendor_code", "Vendor code", FieldKind::Text), &["code"]),
+    required(aliases(
+        field("name", "Vendor name", FieldKind::Text),
+        &["vendor", "supplier", "supplier name"],
+    )),
+    aliases(field("company_name", "Company", FieldKind::Text), &["company", "firm"]),
+    aliases(field("contact_person", "Contact person", FieldKind::Text), &["contact"]),
+    aliases(field("phone", "Phone", FieldKind::Text), &["mobile", "phone number"]),
+    field("email", "Email", FieldKind::Text),
+    field("address", "Address", FieldKind::Text),
+    field("city", "City", FieldKind::Text),
+    field("notes", "Notes", FieldKind::Text),
+    posted(aliases(
+        field("opening_balance", "Opening balance (we owe)", FieldKind::Money),
+        &["balance", "payable", "outstanding"],
+    )),
+];
+
+struct ImportTarget {
+    name: &'static str,
+    table: &'static str,
+    fields: &'static [ImportField],
+}
+
+const TARGETS: &[ImportTarget] = &[
+    ImportTarget {
+        name: "product",
+        table: "products",
+        fields: PRODUCT_FIELDS,
+    },
+    ImportTarget {
+        name: "customer",
+        table: "customers",
+        fields: CUSTOMER_FIELDS,
+    },
+    ImportTarget {
+        name: "vendor",
+        table: "vendors",
+        fields: VENDOR_FIELDS,
+    },
+];
+
+fn import_target(name: &str) -> Result<&'static ImportTarget, String> {
+    let name = name.trim().to_lowercase();
+    TARGETS
+        .iter()
+        .find(|target| target.name == name || target.table == name)
+        .ok_or_else(|| format!("Cannot import '{}'; expected product, customer or vendor", name))
+}
+
+/// Spellings of a unit type found in shop spreadsheets
+fn parse_unit(text: &str) -> Option<UnitType> {
+    let text = text.trim().to_lowercase();
+    let canonical = match text.as_str() {
+        "kg grams" | "kg-g" | "kg/g" => "kg-grams",
+        "kgs" | "kilogram" | "kilograms" => "kg",
+        "tons" | "tonne" | "tonnes" => "ton",
+        "pieces" | "pcs" | "pc" | "nos" | "no" | "unit" | "units" => "piece",
+        "bags" => "bag",
+        "feet" | "ft" => "foot",
+        "meters" | "metre" | "metres" | "m" => "meter",
+        other => other,
+    };
+    UnitType::parse(canonical)
+}
+
+/// Lowercase letters and digits only, so "Sale Rate", "sale_rate" and "SALE-RATE" compare equal
+fn normalize_header(header: &str) -> String {
+    header
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+// ==================== SCHEMA ====================
+
+pub fn ensure_schema(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS import_profiles (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            target TEXT NOT NULL CHECK (target IN ('product', 'customer', 'vendor')),
+            mapping TEXT NOT NULL,
+            sheet TEXT,
+            saved_by TEXT NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE TABLE IF NOT EXISTS import_runs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            target TEXT NOT NULL,
+            file_name TEXT NOT NULL,
+            profile TEXT,
+            rows_imported INTEGER NOT NULL DEFAULT 0,
+            opening_date TEXT NOT NULL,
+            imported_by TEXT NOT NULL,
+            imported_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );",
+    )
+}
+
+// ==================== READING FILES ====================
+
+/// A data row and its line in the file (1 = the header row)
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceRow {
+    pub line: usize,
+    pub cells: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct SourceTable {
+    /// Worksheets of a workbook; empty for CSV
+    pub sheets: Vec<String>,
+    pub sheet: Option<String>,
+    pub headers: Vec<String>,
+    pub rows: Vec<SourceRow>,
+}
+
+/// Read the header row and data rows of a CSV file or a workbook sheet
+/// (the first sheet unless one is named). Blank rows are skipped.
+pub fn read_source(path: &Path, sheet: Option<&str>) -> Result<SourceTable, String> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    let mut table = match extension.as_str() {
+        "csv" | "txt" => SourceTable {
+            sheets: Vec::new(),
+            sheet: None,
+            headers: Vec::new(),
+            rows: read_csv(path)?,
+        },
+        "xlsx" | "xlsm" | "xls" | "xlsb" | "ods" => read_workbook(path, sheet)?,
+        _ => return Err(format!("Cannot import {}; choose a CSV or XLSX file", path.display())),
+    };
+    table.rows.retain(|row| row.cells.iter().any(|cell| !cell.is_empty()));
+    if table.rows.is_empty() {
+        return Err(format!("{} has no rows", path.display()));
+    }
+    table.headers = table.rows.remove(0).cells;
+    Ok(table)
+}
+
+fn read_csv(path: &Path) -> Result<Vec<SourceRow>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_path(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut rows = Vec::new();
+    for record in reader.byte_records() {
+        let record = record.map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let line = record
+            .position()
+            .map(|position| position.line() as usize)
+            .unwrap_or(rows.len() + 1);
+        // Spreadsheet programs save CSV in the local code page; keep what is readable
+        let cells = record
+            .iter()
+            .map(|cell| {
+                String::from_utf8_lossy(cell)
+                    .trim_start_matches('\u{feff}')
+                    .trim()
+                    .to_string()
+            })
+            .collect();
+        rows.push(SourceRow { line, cells });
+    }
+    Ok(rows)
+}
+
+/// Every row of the sheet; `read_source` takes the header row off
+fn read_workbook(path: &Path, sheet: Option<&str>) -> Result<SourceTable, String> {
+    let mut workbook = open_workbook_auto(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let sheets = workbook.sheet_names();
+    let name = match sheet {
+        Some(sheet) => sheets
+            .iter()
+            .find(|name| name.eq_ignore_ascii_case(sheet.trim()))
+            .cloned()
+            .ok_or_else(|| format!("{} has no sheet named '{}'", path.display(), sheet))?,
+        None => sheets
+            .first()
+            .cloned()
+            .ok_or_else(|| format!("{} has no sheets", path.display()))?,
+    };
+    let range = workbook
+        .worksheet_range(&name)
+        .map_err(|e| format!("Failed to read sheet '{}': {}", name, e))?;
+    let first_line = range.start().map(|(row, _)| row as usize + 1).unwrap_or(1);
+    let first_column = range.start().map(|(_, column)| column as usize).unwrap_or(0);
+    let rows = range
+        .rows()
+        .enumerate()
+        .map(|(index, cells)| SourceRow {
+            line: first_line + index,
+            // Columns left of the used range are blank, keep positions as the sheet shows them
+            cells: std::iter::repeat(String::new())
+                .take(first_column)
+                .chain(cells.iter().map(cell_text))
+                .collect(),
+        })
+        .collect();
+    Ok(SourceTable {
+        sheets,
+        sheet: Some(name),
+        headers: Vec::new(),
+        rows,
+    })
+}
+
+fn cell_text(cell: &Data) -> String {
+    match cell {
+        Data::Float(value) => format_number(*value),
+        Data::Error(_) => String::new(),
+        other => other.to_string().trim().to_string(),
+    }
+}
+
+// ==================== MAPPING ====================
+
+/// Field name -> column header
+pub type ImportMapping = BTreeMap<String, String>;
+
+#[derive(Debug, Serialize)]
+pub struct ImportFieldInfo {
+    pub name: String,
+    pub label: String,
+    pub required: bool,
+}
+
+fn field_info(target: &ImportTarget) -> Vec<ImportFieldInfo> {
+    target
+        .fields
+        .iter()
+        .map(|field| ImportFieldInfo {
+            name: field.name.to_string(),
+            label: field.label.to_string(),
+            required: field.required,
+        })
+        .collect()
+}
+
+/// Headers matching a field's name, label or aliases
+fn suggest_mapping(target: &ImportTarget, headers: &[String]) -> ImportMapping {
+    let mut mapping = ImportMapping::new();
+    for field in target.fields {
+        let names: Vec<String> = [field.name, field.label]
+            .iter()
+            .chain(field.aliases)
+            .map(|name| normalize_header(name))
+            .collect();
+        let taken: Vec<&String> = mapping.values().collect();
+        if let Some(header) = headers
+            .iter()
+            .find(|header| names.contains(&normalize_header(header)) && !taken.contains(header))
+        {
+            mapping.insert(field.name.to_string(), header.clone());
+        }
+    }
+    mapping
+}
+
+/// Column index of every mapped field; unknown fields and missing headers are errors
+fn resolve_mapping(
+    target: &ImportTarget,
+    headers: &[String],
+    mapping: &ImportMapping,
+) -> Result<Vec<(&'static ImportField, usize)>, String> {
+    let mut columns = Vec::new();
+    for (name, header) in mapping {
+        let field = target
+            .fields
+            .iter()
+            .find(|field| field.name == name)
+            .ok_or_else(|| format!("{} imports have no field '{}'", target.name, name))?;
+        if header.trim().is_empty() {
+            continue;
+        }
+        let index = headers
+            .iter()
+            .position(|candidate| candidate.trim().eq_ignore_ascii_case(header.trim()))
+            .ok_or_else(|| format!("The file has no column '{}' (mapped to {})", header, field.label))?;
+        columns.push((field, index));
+    }
+    let missing: Vec<&str> = target
+        .fields
+        .iter()
+        .filter(|field| field.required && !columns.iter().any(|(mapped, _)| mapped.name == field.name))
+        .map(|field| field.label)
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!("Map a column to {}", missing.join(", ")));
+    }
+    Ok(columns)
+}
+
+// ==================== PROFILES ====================
+
+#[derive(Debug, Serialize)]
+pub struct ImportProfile {
+    pub name: String,
+    pub target: String,
+    pub mapping: ImportMapping,
+    pub sheet: Option<String>,
+    pub saved_by: String,
+    pub updated_at: String,
+}
+
+fn profile_from_row(row: &rusqlite::Row) -> rusqlite::Result<ImportProfile> {
+    let mapping: String = row.get(2)?;
+    Ok(ImportProfile {
+        name: row.get(0)?,
+        target: row.get(1)?,
+        mapping: serde_json::from_str(&mapping).unwrap_or_default(),
+        sheet: row.get(3)?,
+        saved_by: row.get(4)?,
+        updated_at: row.get(5)?,
+    })
+}
+
+const PROFILE_COLUMNS: &str = "name, target, mapping, sheet, saved_by, COALESCE(updated_at, '')";
+
+fn load_profile(conn: &Connection, name: &str) -> Result<ImportProfile, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM import_profiles WHERE name = ?1", PROFILE_COLUMNS),
+        [name.trim()],
+        profile_from_row,
+    )
+    .optional()
+    .map_err(|e| format!("Failed to load import profile: {}", e))?
+    .ok_or_else(|| format!("No import profile named '{}'", name))
+}
+
+// ==================== IMPORT ====================
+
+#[derive(Debug, Deserialize)]
+pub struct ImportRequest {
+    pub path: String,
+    pub target: String,
+    pub sheet: Option<String>,
+    /// Saved mapping to use; `mapping` overrides it field by field
+    pub profile: Option<String>,
+    pub mapping: Option<ImportMapping>,
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Date of opening stock and balances, today by default
+    pub opening_date: Option<String>,
+    pub imported_by: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RowError {
+    pub line: usize,
+    /// Field the error is about, when it is about one
+    pub field: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ImportReport {
+    pub target: String,
+    pub dry_run: bool,
+    /// Whether the rows were written; false for a dry run or a file with errors
+    pub committed: bool,
+    pub import_id: Option<i64>,
+    pub rows: usize,
+    pub imported: usize,
+    pub opening_stock_posted: usize,
+    pub opening_balances_posted: usize,
+    /// Sum of the opening balances (receivable for customers, payable for vendors)
+    pub opening_balance_total: Money,
+    pub mapping: ImportMapping,
+    pub errors: Vec<RowError>,
+}
+
+/// One row's values by field name, checked and converted
+#[derive(Debug)]
+struct ParsedRow {
+    line: usize,
+    texts: BTreeMap<&'static str, String>,
+    money: BTreeMap<&'static str, Money>,
+    stock: BTreeMap<&'static str, i64>,
+    unit: Option<UnitType>,
+}
+
+impl ParsedRow {
+    fn text(&self, field: &str) -> Option<&str> {
+        self.texts.get(field).map(String::as_str)
+    }
+}
+
+fn row_error(line: usize, field: Option<&ImportField>, message: String) -> RowError {
+    RowError {
+        line,
+        field: field.map(|field| field.name.to_string()),
+        message,
+    }
+}
+
+fn parse_row(row: &SourceRow, columns: &[(&'static ImportField, usize)]) -> Result<ParsedRow, Vec<RowError>> {
+    let mut parsed = ParsedRow {
+        line: row.line,
+        texts: BTreeMap::new(),
+        money: BTreeMap::new(),
+        stock: BTreeMap::new(),
+        unit: None,
+    };
+    let mut errors = Vec::new();
+    let cell = |field: &ImportField| {
+        columns
+            .iter()
+            .find(|(mapped, _)| mapped.name == field.name)
+            .and_then(|(_, index)| row.cells.get(*index))
+            .map(|text| text.trim())
+            .filter(|text| !text.is_empty())
+    };
+
+    // The unit first: stock quantities are read in it
+    if let Some((field, _)) = columns.iter().find(|(field, _)| field.kind == FieldKind::Unit) {
+        match cell(field) {
+            Some(text) => match parse_unit(text) {
+                Some(unit) => parsed.unit = Some(unit),
+                None => errors.push(row_error(
+                    row.line,
+                    Some(field),
+                    format!(
+                        "Unknown unit type '{}'; use kg-grams, kg, ton, piece, bag, foot or meter",
+                        text
+                    ),
+                )),
+            },
+            None => parsed.unit = Some(UnitType::KgGrams),
+        }
+    }
+
+    for (field, _) in columns {
+        let Some(text) = cell(field) else {
+            if field.required {
+                errors.push(row_error(row.line, Some(field), format!("{} is empty", field.label)));
+            }
+            continue;
+        };
+        match field.kind {
+            FieldKind::Text => {
+                parsed.texts.insert(field.name, text.to_string());
+            }
+            FieldKind::Money => {
+                let amount = text
+                    .trim_start_matches("Rs.")
+                    .trim_start_matches("Rs")
+                    .trim_start_matches("PKR");
+                match Money::parse(amount) {
+                    Some(amount) => {
+                        parsed.money.insert(field.name, amount);
+                    }
+                    None => errors.push(row_error(
+                        row.line,
+                        Some(field),
+                        format!("{} '{}' is not an amount", field.label, text),
+                    )),
+                }
+            }
+            FieldKind::Stock => {
+                let unit = parsed.unit.unwrap_or(UnitType::KgGrams);
+                match parse_stock_text(text, unit.as_str()) {
+                    Some(base) if base >= 0 => {
+                        parsed.stock.insert(field.name, base);
+                    }
+                    Some(_) => errors.push(row_error(
+                        row.line,
+                        Some(field),
+                        format!("{} cannot be negative", field.label),
+                    )),
+                    None => errors.push(row_error(
+                        row.line,
+                        Some(field),
+                        format!("{} '{}' cannot be read as {}", field.label, text, unit.as_str()),
+                    )),
+                }
+            }
+            FieldKind::Unit => {}
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(parsed)
+    } else {
+        Err(errors)
+    }
+}
+
+/// What makes a row the same record as another: (description, key) pairs
+fn identity_keys(target: &ImportTarget, row: &ParsedRow) -> Vec<(&'static str, String)> {
+    let lower = |field: &str| row.text(field).map(str::to_lowercase);
+    let mut keys = Vec::new();
+    match target.name {
+        "product" => {
+            keys.extend(lower("name").map(|name| ("name", name)));
+            keys.extend(lower("sku").map(|sku| ("SKU", sku)));
+        }
+        "customer" => {
+            keys.extend(lower("customer_code").map(|code| ("code", code)));
+            keys.extend(lower("cnic").map(|cnic| ("CNIC", cnic)));
+            if let (Some(name), Some(phone)) = (lower("name"), lower("phone")) {
+                keys.push(("name and phone", format!("{}\u{1f}{}", name, phone)));
+            }
+        }
+        _ => {
+            keys.extend(lower("vendor_code").map(|code| ("code", code)));
+            keys.extend(lower("name").map(|name| ("name", name)));
+        }
+    }
+    keys
+}
+
+/// An existing live record with the same identity, as "<description> '<value>'"
+fn existing_record(tx: &Transaction, target: &ImportTarget, row: &ParsedRow) -> Result<Option<String>, String> {
+    let live = if has_column(tx, target.table, "deleted_at").map_err(|e| format!("Failed to inspect schema: {}", e))? {
+        " AND deleted_at IS NULL"
+    } else {
+        ""
+    };
+    let checks: Vec<(&str, &str, Vec<&str>)> = match target.name {
+        "product" => vec![
+            ("name", "LOWER(name) = LOWER(?1)", vec!["name"]),
+            ("SKU", "sku = ?1", vec!["sku"]),
+        ],
+        "customer" => vec![
+            ("code", "customer_code = ?1", vec!["customer_code"]),
+            ("CNIC", "cnic = ?1", vec!["cnic"]),
+            (
+                "name and phone",
+                "LOWER(name) = LOWER(?1) AND phone = ?2",
+                vec!["name", "phone"],
+            ),
+        ],
+        _ => vec![
+            ("code", "vendor_code = ?1", vec!["vendor_code"]),
+            ("name", "LOWER(name) = LOWER(?1)", vec!["name"]),
+        ],
+    };
+    for (description, condition, fields) in checks {
+        let values: Option<Vec<&str>> = fields.iter().map(|field| row.text(field)).collect();
+        let Some(values) = values else {
+            continue;
+        };
+        let found = tx
+            .query_row(
+                &format!(
+                    "SELECT EXISTS (SELECT 1 FROM {} WHERE {}{})",
+                    target.table, condition, live
+                ),
+                params_from_iter(values.iter()),
+                |row| row.get::<_, bool>(0),
+            )
+            .map_err(|e| format!("Failed to look for existing {}s: {}", target.name, e))?;
+        if found {
+            return Ok(Some(format!("{} '{}'", description, values.join(" / "))));
+        }
+    }
+    Ok(None)
+}
+
+/// Next free C0001-style code, the format the customer form generates
+fn next_customer_code(tx: &Transaction) -> Result<i64, String> {
+    tx.query_row(
+        "SELECT COALESCE(MAX(CAST(SUBSTR(customer_code, 2) AS INTEGER)), 0) + 1
+         FROM customers WHERE customer_code GLOB 'C[0-9][0-9][0-9][0-9]*'",
+        [],
+        |row| row.get(0),
+    )
+    .map_err(|e| format!("Failed to number customers: {}", e))
+}
+
+/// Shared state of one import run
+struct ImportRun<'a> {
+    target: &'static ImportTarget,
+    columns: &'a [(&'static ImportField, usize)],
+    reference: String,
+    opening_date: String,
+    opening_time: String,
+    imported_by: &'a str,
+    next_customer_code: i64,
+}
+
+/// Insert one record and its opening figures
+fn insert_row(tx: &Transaction, run: &mut ImportRun, row: &ParsedRow, report: &mut ImportReport) -> Result<(), String> {
+    let target = run.target;
+    let mut columns: Vec<String> = Vec::new();
+    let mut values: Vec<rusqlite::types::Value> = Vec::new();
+    let unit = row.unit.unwrap_or(UnitType::KgGrams);
+
+    for (field, _) in run.columns {
+        let Some(column) = field.column else {
+            continue;
+        };
+        let value = match field.kind {
+            FieldKind::Text => row.texts.get(field.name).cloned().map(rusqlite::types::Value::Text),
+            FieldKind::Money => row
+                .money
+                .get(field.name)
+                .map(|amount| rusqlite::types::Value::Real(amount.to_rupees())),
+            FieldKind::Stock => row
+                .stock
+                .get(field.name)
+                .map(|base| rusqlite::types::Value::Text(format_stock_text(*base, unit.as_str()))),
+            FieldKind::Unit => Some(rusqlite::types::Value::Text(unit.as_str().to_string())),
+        };
+        if let Some(value) = value {
+            columns.push(column.to_string());
+            values.push(value);
+        }
+    }
+
+    let name = row.text("name").unwrap_or_default().to_string();
+    match target.name {
+        "product" => {
+            // Named the way the product form names them: "Steel Rod • 12mm • A-Grade"
+            let mut full_name = name.clone();
+            for part in [row.text("size"), row.text("grade")].into_iter().flatten() {
+                if !full_name.contains(part) {
+                    full_name = format!("{} • {}", full_name, part);
+                }
+            }
+            if let Some(index) = columns.iter().position(|column| column == "name") {
+                values[index] = rusqlite::types::Value::Text(full_name);
+            }
+            columns.push("base_name".to_string());
+            values.push(rusqlite::types::Value::Text(name.clone()));
+            if !columns.iter().any(|column| column == "unit_type") {
+                columns.push("unit_type".to_string());
+                values.push(rusqlite::types::Value::Text(unit.as_str().to_string()));
+            }
+            // What the stock check expects beside the opening movement
+            let opening = row.stock.get("opening_stock").copied().unwrap_or(0);
+            columns.push("stock_quantity".to_string());
+            values.push(rusqlite::types::Value::Real(to_movement_number(opening, unit.as_str())));
+        }
+        "customer" if row.text("customer_code").is_none() => {
+            columns.push("customer_code".to_string());
+            values.push(rusqlite::types::Value::Text(format!("C{:04}", run.next_customer_code)));
+            run.next_customer_code += 1;
+        }
+        _ => {}
+    }
+    columns.push("created_by".to_string());
+    values.push(rusqlite::types::Value::Text(run.imported_by.to_string()));
+
+    tx.execute(
+        &format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            target.table,
+            columns.join(", "),
+            vec!["?"; columns.len()].join(", ")
+        ),
+        params_from_iter(values.iter()),
+    )
+    .map_err(|e| format!("Failed to insert {}: {}", name, e))?;
+    let id = tx.last_insert_rowid();
+
+    match target.name {
+        "product" => {
+            if let Some(base) = row.stock.get("opening_stock").copied().filter(|base| *base > 0) {
+                post_opening_stock(tx, run, id, &name_of(tx, target, id)?, unit, base)?;
+                report.opening_stock_posted += 1;
+            }
+        }
+        "customer" => {
+            if let Some(amount) = row
+                .money
+                .get("opening_balance")
+                .copied()
+                .filter(|amount| !amount.is_zero())
+            {
+                post_customer_opening(tx, run, id, &name, amount)?;
+                report.opening_balances_posted += 1;
+                report.opening_balance_total += amount;
+            }
+        }
+        _ => {
+            if let Some(amount) = row
+                .money
+                .get("opening_balance")
+                .copied()
+                .filter(|amount| !amount.is_zero())
+            {
+                post_vendor_opening(tx, run, id, &name, amount)?;
+                report.opening_balances_posted += 1;
+                report.opening_balance_total += amount;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn name_of(tx: &Transaction, target: &ImportTarget, id: i64) -> Result<String, String> {
+    tx.query_row(
+        &format!("SELECT name FROM {} WHERE id = ?1", target.table),
+        [id],
+        |row| row.get(0),
+    )
+    .map_err(|e| format!("Failed to read imported {}: {}", target.name, e))
+}
+
+fn post_opening_stock(
+    tx: &Transaction,
+    run: &ImportRun,
+    product_id: i64,
+    product_name: &str,
+    unit: UnitType,
+    base: i64,
+) -> Result<(), String> {
+    let unit_type = unit.as_str();
+    tx.execute(
+        "INSERT INTO stock_movements (
+            product_id, product_name, movement_type, transaction_type, quantity, unit,
+            previous_stock, stock_before, stock_after, new_stock, reason, reference_type,
+            reference_number, notes, date, time, created_by
+        ) VALUES (?1, ?2, 'in', 'adjustment', ?3, ?4, '0', '0', ?3, ?3, 'Opening stock', 'initial',
+            ?5, 'Imported opening stock', ?6, ?7, ?8)",
+        params![
+            product_id,
+            product_name,
+            format_movement_quantity(base, unit_type),
+            unit_type,
+            run.reference,
+            run.opening_date,
+            run.opening_time,
+            run.imported_by
+        ],
+    )
+    .map_err(|e| format!("Failed to post opening stock: {}", e))?;
+    set_movement_bases(tx, tx.last_insert_rowid(), base, 0, base)
+        .and_then(|_| set_product_stock(tx, product_id, base, unit_type))
+        .map_err(|e| format!("Failed to post opening stock: {}", e))
+}
+
+/// Positive: the customer owes us; negative: an advance we hold
+fn post_customer_opening(
+    tx: &Transaction,
+    run: &ImportRun,
+    customer_id: i64,
+    customer_name: &str,
+    amount: Money,
+) -> Result<(), String> {
+    let opening_flag = has_column(tx, "customer_ledger_entries", "is_opening_balance")
+        .map_err(|e| format!("Failed to read ledger columns: {}", e))?;
+    tx.execute(
+        &format!(
+            "INSERT INTO customer_ledger_entries (
+                customer_id, customer_name, entry_type, transaction_type, amount, balance_before,
+                balance_after, description, reference_type, reference_number, date, time, notes,
+                created_by{}
+            ) VALUES (?1, ?2, ?3, 'adjustment', ?4, 0, ?5, 'Opening balance', 'adjustment', ?6, ?7, ?8,
+                'Imported opening balance', ?9{})",
+            if opening_flag { ", is_opening_balance" } else { "" },
+            if opening_flag { ", 1" } else { "" }
+        ),
+        params![
+            customer_id,
+            customer_name,
+            if amount.is_negative() { "credit" } else { "debit" },
+            amount.abs().to_rupees(),
+            amount.to_rupees(),
+            run.reference,
+            run.opening_date,
+            run.opening_time,
+            run.imported_by
+        ],
+    )
+    .map_err(|e| format!("Failed to post opening balance: {}", e))?;
+    recalculate_in_transaction(tx, Some(customer_id)).map(|_| ())
+}
+
+/// Positive: we owe the vendor; negative: an advance we paid
+fn post_vendor_opening(
+    tx: &Transaction,
+    run: &ImportRun,
+    vendor_id: i64,
+    vendor_name: &str,
+    amount: Money,
+) -> Result<(), String> {
+    tx.execute(
+        "INSERT INTO vendor_ledger_entries (
+            vendor_id, vendor_name, entry_type, transaction_type, amount, balance_before, balance_after,
+            description, reference_type, reference_number, date, time, notes, created_by
+        ) VALUES (?1, ?2, ?3, 'adjustment', ?4, 0, ?5, 'Opening balance', 'adjustment', ?6, ?7, ?8,
+            'Imported opening balance', ?9)",
+        params![
+            vendor_id,
+            vendor_name,
+            if amount.is_negative() { "debit" } else { "credit" },
+            amount.abs().to_rupees(),
+            amount.to_rupees(),
+            run.reference,
+            run.opening_date,
+            run.opening_time,
+            run.imported_by
+        ],
+    )
+    .map_err(|e| format!("Failed to post opening balance: {}", e))?;
+    sync_vendor_in_transaction(tx, vendor_id, &mut VendorSyncResult::default()).map(|_| ())
+}
+
+/// Check and insert every row on the open transaction. The caller commits
+/// only when the report has no errors and it is not a dry run.
+pub fn import_in_transaction(
+    tx: &Transaction,
+    source: &SourceTable,
+    request: &ImportRequest,
+    mapping: ImportMapping,
+) -> Result<ImportReport, String> {
+    let target = import_target(&request.target)?;
+    let columns = resolve_mapping(target, &source.headers, &mapping)?;
+    let opening_date = match request
+        .opening_date
+        .as_deref()
+        .map(str::trim)
+        .filter(|date| !date.is_empty())
+    {
+        Some(date) => {
+            let valid: bool = tx
+                .query_row("SELECT date(?1) IS ?1", [date], |row| row.get(0))
+                .map_err(|e| format!("Failed to read the opening date: {}", e))?;
+            if !valid {
+                return Err(format!("Opening date '{}' is not a YYYY-MM-DD date", date));
+            }
+            date.to_string()
+        }
+        None => current_date(tx).map_err(|e| format!("Failed to read date: {}", e))?,
+    };
+
+    let file_name = Path::new(&request.path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| request.path.clone());
+    tx.execute(
+        "INSERT INTO import_runs (target, file_name, profile, opening_date, imported_by) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            target.name,
+            file_name,
+            request.profile,
+            opening_date,
+            request.imported_by
+        ],
+    )
+    .map_err(|e| format!("Failed to record the import: {}", e))?;
+    let import_id = tx.last_insert_rowid();
+
+    let mut run = ImportRun {
+        target,
+        columns: &columns,
+        reference: format!("{}{}", IMPORT_REFERENCE_PREFIX, import_id),
+        opening_date,
+        // Before the day's own transactions
+        opening_time: "00:00:00".to_string(),
+        imported_by: &request.imported_by,
+        next_customer_code: next_customer_code(tx)?,
+    };
+    let mut report = ImportReport {
+        target: target.name.to_string(),
+        dry_run: request.dry_run,
+        import_id: Some(import_id),
+        rows: source.rows.len(),
+        mapping,
+        ..ImportReport::default()
+    };
+    let mut seen: HashMap<(&'static str, String), usize> = HashMap::new();
+
+    for source_row in &source.rows {
+        let row = match parse_row(source_row, &columns) {
+            Ok(row) => row,
+            Err(errors) => {
+                report.errors.extend(errors);
+                continue;
+            }
+        };
+        let repeated = identity_keys(target, &row)
+            .into_iter()
+            .find_map(|key| match seen.get(&key) {
+                Some(line) => Some(format!("Same {} as line {}", key.0, line)),
+                None => {
+                    seen.insert(key, row.line);
+                    None
+                }
+            });
+        if let Some(message) = repeated {
+            report.errors.push(row_error(row.line, None, message));
+            continue;
+        }
+        if let Some(existing) = existing_record(tx, target, &row)? {
+            report.errors.push(row_error(
+                row.line,
+                None,
+                format!("A {} with {} already exists", target.name, existing),
+            ));
+            continue;
+        }
+
+        tx.execute_batch("SAVEPOINT import_row")
+            .map_err(|e| format!("Failed to start savepoint: {}", e))?;
+        match insert_row(tx, &mut run, &row, &mut report) {
+            Ok(()) => {
+                tx.execute_batch("RELEASE import_row")
+                    .map_err(|e| format!("Failed to release savepoint: {}", e))?;
+                report.imported += 1;
+            }
+            Err(message) => {
+                tx.execute_batch("ROLLBACK TO import_row; RELEASE import_row")
+                    .map_err(|e| format!("Failed to roll back savepoint: {}", e))?;
+                report.errors.push(row_error(row.line, None, message));
+            }
+        }
+    }
+
+    tx.execute(
+        "UPDATE import_runs SET rows_imported = ?1 WHERE id = ?2",
+        params![report.imported as i64, import_id],
+    )
+    .map_err(|e| format!("Failed to record the import: {}", e))?;
+    report.errors.sort_by_key(|error| error.line);
+    Ok(report)
+}
+
+/// The mapping a request uses: the profile's, overridden by any fields given
+fn request_mapping(conn: &Connection, request: &ImportRequest, source: &SourceTable) -> Result<ImportMapping, String> {
+    let target = import_target(&request.target)?;
+    let mut mapping = match request.profile.as_deref().filter(|name| !name.trim().is_empty()) {
+        Some(name) => {
+            let profile = load_profile(conn, name)?;
+            if profile.target != target.name {
+                return Err(format!(
+                    "Profile '{}' maps {} files, not {}",
+                    profile.name, profile.target, target.name
+                ));
+            }
+            profile.mapping
+        }
+        None => suggest_mapping(target, &source.headers),
+    };
+    if let Some(overrides) = &request.mapping {
+        mapping.extend(overrides.clone());
+    }
+    Ok(mapping)
+}
+
+/// Import rows already read from the request's file, committing only a
+/// real import without errors
+fn import_source(conn: &mut Connection, source: &SourceTable, request: &ImportRequest) -> Result<ImportReport, String> {
+    let mapping = request_mapping(conn, request, source)?;
+
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let actor = audit::act_as(&tx, &request.imported_by, true)?;
+    let mut report = import_in_transaction(&tx, source, request, mapping)?;
+    audit::restore_actor(&tx, actor)?;
+
+    if request.dry_run || !report.errors.is_empty() {
+        tx.rollback()
+            .map_err(|e| format!("Failed to roll back import: {}", e))?;
+        report.import_id = None;
+        info!(
+            "[IMPORT] {} {} of {} rows valid in {} ({} errors)",
+            if request.dry_run { "Dry run:" } else { "Not imported:" },
+            report.imported,
+            report.rows,
+            request.path,
+            report.errors.len()
+        );
+        return Ok(report);
+    }
+
+    audit::record(
+        &tx,
+        &AuditEvent::new("import", report.target.as_str())
+            .entity(report.import_id.unwrap_or_default())
+            .by(&request.imported_by)
+            .after(json!({
+                "file": request.path,
+                "rows": report.imported,
+                "opening_stock_posted": report.opening_stock_posted,
+                "opening_balances_posted": report.opening_balances_posted,
+                "opening_balance_total": report.opening_balance_total,
+            })),
+    )?;
+    tx.commit().map_err(|e| format!("Failed to commit import: {}", e))?;
+    report.committed = true;
+
+    info!(
+        "[IMPORT] {} {}s imported from {} by {} ({} opening stock, {} opening balances)",
+        report.imported,
+        report.target,
+        request.path,
+        request.imported_by.trim(),
+        report.opening_stock_posted,
+        report.opening_balances_posted
+    );
+    Ok(report)
+}
+
+// ==================== COMMANDS ====================
+
+#[derive(Debug, Serialize)]
+pub struct ImportPreview {
+    pub sheets: Vec<String>,
+    pub sheet: Option<String>,
+    pub headers: Vec<String>,
+    pub rows: Vec<SourceRow>,
+    pub total_rows: usize,
+    /// Fields of the target, with the headers they would be read from
+    pub fields: Vec<ImportFieldInfo>,
+    pub suggested_mapping: ImportMapping,
+}
+
+/// Headers, the first rows and a suggested mapping, for the mapping screen
+#[tauri::command]
+pub async fn preview_import_file(path: String, target: String, sheet: Option<String>) -> Result<ImportPreview, String> {
+    let target = import_target(&target)?;
+    let source = read_source(Path::new(&path), sheet.as_deref())?;
+    Ok(ImportPreview {
+        suggested_mapping: suggest_mapping(target, &source.headers),
+        fields: field_info(target),
+        total_rows: source.rows.len(),
+        rows: source.rows.into_iter().take(PREVIEW_ROWS).collect(),
+        sheets: source.sheets,
+        sheet: source.sheet,
+        headers: source.headers,
+    })
+}
+
+/// Dry run or import a file; nothing is written unless every row is valid
+#[tauri::command]
+pub async fn import_data(request: ImportRequest) -> Result<ImportReport, String> {
+    if request.imported_by.trim().is_empty() {
+        return Err("The importing user is required".to_string());
+    }
+    let source = read_source(Path::new(&request.path), request.sheet.as_deref())?;
+    import_source(&mut open_connection()?, &source, &request)
+}
+
+/// Save (or replace) a named mapping
+#[tauri::command]
+pub async fn save_import_profile(
+    name: String,
+    target: String,
+    mapping: ImportMapping,
+    sheet: Option<String>,
+    saved_by: String,
+) -> Result<ImportProfile, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("The profile needs a name".to_string());
+    }
+    let target = import_target(&target)?;
+    for field in mapping.keys() {
+        if !target.fields.iter().any(|known| known.name == field) {
+            return Err(format!("{} imports have no field '{}'", target.name, field));
+        }
+    }
+    let mapping_json = serde_json::to_string(&mapping).map_err(|e| format!("Failed to save the mapping: {}", e))?;
+
+    let mut conn = open_connection()?;
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let before = load_profile(&tx, name).ok();
+    tx.execute(
+        "INSERT INTO import_profiles (name, target, mapping, sheet, saved_by) VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(name) DO UPDATE SET target = excluded.target, mapping = excluded.mapping,
+             sheet = excluded.sheet, saved_by = excluded.saved_by, updated_at = CURRENT_TIMESTAMP",
+        params![name, target.name, mapping_json, sheet, saved_by.trim()],
+    )
+    .map_err(|e| format!("Failed to save import profile: {}", e))?;
+    let profile = load_profile(&tx, name)?;
+    let event = AuditEvent::new("save_profile", "import_profile")
+        .entity(name)
+        .by(&saved_by)
+        .after(json!(&profile));
+    audit::record(
+        &tx,
+        &match before {
+            Some(before) => event.before(json!(before)),
+            None => event,
+        },
+    )?;
+    tx.commit()
+        .map_err(|e| format!("Failed to commit import profile: {}", e))?;
+    info!(
+        "[IMPORT] Profile '{}' for {}s saved by {}",
+        name,
+        target.name,
+        saved_by.trim()
+    );
+    Ok(profile)
+}
+
+#[tauri::command]
+pub async fn get_import_profiles(target: Option<String>) -> Result<Vec<ImportProfile>, String> {
+    let target = target
+        .as_deref()
+        .map(import_target)
+        .transpose()?
+        .map(|target| target.name);
+    let conn = open_connection()?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM import_profiles WHERE ?1 IS NULL OR target = ?1 ORDER BY name",
+            PROFILE_COLUMNS
+        ))
+        .map_err(|e| format!("Failed to query import profiles: {}", e))?;
+    let rows = stmt
+        .query_map([target], profile_from_row)
+        .map_err(|e| format!("Failed to read import profiles: {}", e))?;
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to read import profile: {}", e))
+}
+
+#[tauri::command]
+pub async fn delete_import_profile(name: String) -> Result<(), String> {
+    let mut conn = open_connection()?;
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let Ok(profile) = load_profile(&tx, &name) else {
+        warn!("[IMPORT] No profile named '{}' to delete", name.trim());
+        return Ok(());
+    };
+    tx.execute("DELETE FROM import_profiles WHERE name = ?1", [&profile.name])
+        .map_err(|e| format!("Failed to delete import profile: {}", e))?;
+    audit::record(
+        &tx,
+        &AuditEvent::new("delete_profile", "import_profile")
+            .entity(&profile.name)
+            .by(&session::current_username())
+            .before(json!(&profile)),
+    )?;
+    tx.commit()
+        .map_err(|e| format!("Failed to commit import profile: {}", e))?;
+    info!("[IMPORT] Profile '{}' deleted", profile.name);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::customer_balance::balance_report;
+    use crate::stock_engine::{check_stock, stock_as_of};
+
+    /// The frontend's product and customer tables, with Cement already on the books
+    fn database() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE products (
+                id INTEGER PRIMARY KEY, name TEXT NOT NULL, base_name TEXT, category TEXT, unit_type TEXT, unit TEXT,
+                rate_per_unit REAL, cost_price REAL, sku TEXT UNIQUE, barcode TEXT, size TEXT, grade TEXT,
+                brand TEXT, description TEXT, min_stock_alert TEXT, current_stock TEXT NOT NULL DEFAULT '0',
+                stock_quantity REAL, created_by TEXT, deleted_at TEXT, updated_at TEXT
+            );
+            CREATE TABLE customers (
+                id INTEGER PRIMARY KEY, customer_code TEXT UNIQUE, name TEXT NOT NULL, phone TEXT, cnic TEXT,
+                email TEXT, address TEXT, company_name TEXT, credit_limit REAL, notes TEXT,
+                balance REAL NOT NULL DEFAULT 0, created_by TEXT, updated_at TEXT
+            );
+            CREATE TABLE stock_movements (
+                id INTEGER PRIMARY KEY, product_id INTEGER, product_name TEXT, movement_type TEXT,
+                transaction_type TEXT, quantity TEXT, unit TEXT, previous_stock TEXT, stock_before TEXT,
+                stock_after TEXT, new_stock TEXT, reason TEXT, reference_type TEXT, reference_id INTEGER,
+                reference_number TEXT, notes TEXT, date TEXT, time TEXT, created_by TEXT
+            );
+            CREATE TABLE customer_ledger_entries (
+                id INTEGER PRIMARY KEY, customer_id INTEGER, customer_name TEXT, entry_type TEXT,
+                transaction_type TEXT, amount REAL, balance_before REAL, balance_after REAL, description TEXT,
+                reference_type TEXT, reference_id INTEGER, reference_number TEXT, date TEXT, time TEXT,
+                notes TEXT, created_by TEXT
+            );
+            INSERT INTO products (id, name, unit_type, current_stock, stock_quantity, sku)
+                VALUES (1, 'Cement', 'bag', '40', 40, 'CEM-1');
+            INSERT INTO customers (id, customer_code, name, phone) VALUES (1, 'C0007', 'Ali', '0300');",
+        )
+        .unwrap();
+        ensure_schema(&conn).unwrap();
+        audit::ensure_schema(&conn).unwrap();
+        conn
+    }
+
+    fn source(headers: &[&str], rows: &[&[&str]]) -> SourceTable {
+        let texts = |cells: &[&str]| cells.iter().map(|cell| cell.to_string()).collect();
+        SourceTable {
+            sheets: Vec::new(),
+            sheet: None,
+            headers: texts(headers),
+            rows: rows
+                .iter()
+                .enumerate()
+                .map(|(index, cells)| SourceRow {
+                    line: index + 2,
+                    cells: texts(cells),
+                })
+                .collect(),
+        }
+    }
+
+    fn request(target: &str, dry_run: bool) -> ImportRequest {
+        ImportRequest {
+            path: format!("/imports/{}s.csv", target),
+            target: target.to_string(),
+            sheet: None,
+            profile: None,
+            mapping: None,
+            dry_run,
+            opening_date: Some("2026-01-01".to_string()),
+            imported_by: "sara".to_string(),
+        }
+    }
+
+    fn count(conn: &Connection, sql: &str) -> i64 {
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    fn messages(report: &ImportReport) -> Vec<(usize, String)> {
+        report
+            .errors
+            .iter()
+            .map(|error| (error.line, error.message.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn rows_are_read_in_their_unit() {
+        let target = import_target("products").unwrap();
+        let headers: Vec<String> = ["Item", "UOM", "Qty", "Price"].map(String::from).to_vec();
+        let columns = resolve_mapping(target, &headers, &suggest_mapping(target, &headers)).unwrap();
+        let parse = |cells: &[&str]| {
+            let row = SourceRow {
+                line: 2,
+                cells: cells.iter().map(|cell| cell.to_string()).collect(),
+            };
+            parse_row(&row, &columns)
+        };
+        let errors = |cells: &[&str]| -> Vec<String> {
+            parse(cells)
+                .unwrap_err()
+                .into_iter()
+                .map(|error| format!("{}: {}", error.field.unwrap_or_default(), error.message))
+                .collect()
+        };
+
+        let rice = parse(&["Rice", "kg grams", "12-500", "Rs.1,250.50"]).unwrap();
+        assert_eq!(rice.unit, Some(UnitType::KgGrams));
+        assert_eq!(rice.stock["opening_stock"], 12_500);
+        assert_eq!(rice.money["rate_per_unit"], Money::from_paisa(125_050));
+        assert_eq!(parse(&["Pipe", "PCS", "150", ""]).unwrap().stock["opening_stock"], 150);
+        assert_eq!(parse(&["Rod", "ft", "24", ""]).unwrap().stock["opening_stock"], 73_152);
+        // An empty unit is the app's default
+        assert_eq!(
+            parse(&["Sugar", "", "2-250", ""]).unwrap().stock["opening_stock"],
+            2_250
+        );
+
+        assert_eq!(
+            errors(&["Pipe", "pcs", "12-500", ""]),
+            ["opening_stock: Opening stock '12-500' cannot be read as piece"]
+        );
+        assert_eq!(
+            errors(&["Tile", "box", "4", ""]),
+            ["unit_type: Unknown unit type 'box'; use kg-grams, kg, ton, piece, bag, foot or meter"]
+        );
+        assert_eq!(
+            errors(&["", "bag", "-3", "abc"]),
+            [
+                "name: Product name is empty",
+                "opening_stock: Opening stock cannot be negative",
+                "rate_per_unit: Sale rate 'abc' is not an amount",
+            ]
+        );
+    }
+
+    #[test]
+    fn repeated_and_existing_rows_are_reported_against_their_line() {
+        let mut conn = database();
+        let rows = source(
+            &["Name", "SKU", "Unit", "Stock"],
+            &[
+                &["Sand", "", "ton", "3"],
+                &["cement", "", "bag", "5"],
+                &["Gravel", "CEM-1", "ton", "1"],
+                &["SAND", "", "ton", "2"],
+                &["Bricks", "BR-1", "piece", "1000"],
+                &["Blocks", "br-1", "piece", "10"],
+            ],
+        );
+        let report = import_source(&mut conn, &rows, &request("product", false)).unwrap();
+        assert_eq!(
+            messages(&report),
+            [
+                (3, "A product with name 'cement' already exists".to_string()),
+                (4, "A product with SKU 'CEM-1' already exists".to_string()),
+                (5, "Same name as line 2".to_string()),
+                (7, "Same SKU as line 6".to_string()),
+            ]
+        );
+        assert_eq!((report.imported, report.committed, report.import_id), (2, false, None));
+        // The whole file or nothing
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM products"), 1);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM import_runs"), 0);
+    }
+
+    #[test]
+    fn a_dry_run_writes_nothing_and_an_import_commits_everything() {
+        let mut conn = database();
+        let rows = source(
+            &["Customer", "Phone", "Balance"],
+            &[&["Ali", "0311", "1,500"], &["Bilal", "", "-200"], &["Sana", "0322", ""]],
+        );
+
+        let dry_run = import_source(&mut conn, &rows, &request("customer", true)).unwrap();
+        assert!(dry_run.errors.is_empty());
+        assert_eq!(
+            (dry_run.imported, dry_run.committed, dry_run.import_id),
+            (3, false, None)
+        );
+        assert_eq!(dry_run.opening_balance_total, Money::from_paisa(130_000));
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM customers"), 1);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM customer_ledger_entries"), 0);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM import_runs"), 0);
+
+        let report = import_source(&mut conn, &rows, &request("customer", false)).unwrap();
+        assert!(report.committed);
+        assert_eq!((report.imported, report.opening_balances_posted), (3, 2));
+        let codes: String = conn
+            .query_row(
+                "SELECT group_concat(customer_code || ' ' || name, ', ') FROM customers WHERE id > 1 ORDER BY id",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(codes, "C0008 Ali, C0009 Bilal, C0010 Sana");
+        assert_eq!(count(&conn, "SELECT rows_imported FROM import_runs"), 3);
+        assert_eq!(
+            count(&conn, "SELECT COUNT(*) FROM audit_pending WHERE action = 'import'"),
+            1
+        );
+
+        // The same file again: Ali and Sana are known by name and phone; Bilal,
+        // with no phone to tell him apart, would be added twice but for them
+        let again = import_source(&mut conn, &rows, &request("customer", false)).unwrap();
+        assert_eq!((again.imported, again.committed), (1, false));
+        assert_eq!(
+            messages(&again),
+            [
+                (
+                    2,
+                    "A customer with name and phone 'Ali / 0311' already exists".to_string()
+                ),
+                (
+                    4,
+                    "A customer with name and phone 'Sana / 0322' already exists".to_string()
+                ),
+            ]
+        );
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM customers"), 4);
+    }
+
+    #[test]
+    fn opening_figures_replay_from_the_ledgers() {
+        let mut conn = database();
+        let products = source(
+            &["Name", "Unit", "Opening stock"],
+            &[&["Rice", "kg-grams", "12-500"], &["Pipe", "piece", "0"]],
+        );
+        let report = import_source(&mut conn, &products, &request("product", false)).unwrap();
+        assert_eq!((report.imported, report.opening_stock_posted), (2, 1));
+        let reference = format!("IMPORT-{}", report.import_id.unwrap());
+        let movement: (String, String, String, String) = conn
+            .query_row(
+                "SELECT quantity, reference_type, reference_number, date FROM stock_movements",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .unwrap();
+        assert_eq!(
+            movement,
+            ("12500".into(), "initial".into(), reference, "2026-01-01".into())
+        );
+
+        let stock = check_stock(&conn, None).unwrap();
+        assert_eq!((stock.products_checked, stock.consistent_products), (3, 3));
+        let (before, _) = stock_as_of(&conn, "2025-12-31").unwrap();
+        let (after, _) = stock_as_of(&conn, "2026-01-01").unwrap();
+        let rice = |levels: &[crate::stock_engine::StockAsOf]| {
+            levels
+                .iter()
+                .find(|level| level.product_name == "Rice")
+                .map(|level| level.base)
+        };
+        assert_eq!((rice(&before), rice(&after)), (Some(0), Some(12_500)));
+
+        let customers = source(&["Name", "Balance"], &[&["Bilal", "-200"], &["Sana", "750.25"]]);
+        import_source(&mut conn, &customers, &request("customer", false)).unwrap();
+        let balances = balance_report(&conn, None).unwrap();
+        assert!(balances.discrepancies.is_empty());
+        assert_eq!(balances.total_ledger_balance, Money::from_paisa(55_025));
+        let entries: String = conn
+            .query_row(
+                "SELECT group_concat(customer_name || ' ' || entry_type || ' ' || amount, ', ')
+                 FROM customer_ledger_entries ORDER BY id",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(entries, "Bilal credit 200.0, Sana debit 750.25");
+    }
+}
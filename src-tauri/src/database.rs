@@ -0,0 +1,146 @@
+/*!
+ * SHARED DATABASE ACCESS FOR RUST-SIDE COMMANDS
+ * Resolves the live database location and opens connections configured
+ * the same way as the startup initialization in main.rs
+ */
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use rusqlite::{Connection, OpenFlags};
+
+use crate::change_events;
+use crate::windows_support::get_windows_app_data_dir;
+
+pub const APP_NAME: &str = "com.itehadironstore.management";
+pub const DB_FILE_NAME: &str = "store.db";
+
+/// Names the live database had before it became independent of the fiscal year
+pub const LEGACY_DB_FILE_NAMES: &[&str] = &["store-2025.db"];
+
+/// SQLite companion files that must move together with a database file
+const DB_COMPANION_SUFFIXES: &[&str] = &["-wal", "-shm", "-journal"];
+
+/// Points the whole application at another data directory, e.g. to run a
+/// second instance next to the installed one
+pub const DATA_DIR_ENV: &str = "ITTEHAD_DATA_DIR";
+
+/// Resolve the application data directory holding the database, backups,
+/// archives and logs: `ITTEHAD_DATA_DIR` when set, production-grade detection
+/// on Windows, ~/.local/share elsewhere. Startup, the CLI and every command
+/// derive their paths from here so they all open the same database file.
+pub fn get_app_data_dir() -> Result<PathBuf, String> {
+    if let Some(dir) = std::env::var_os(DATA_DIR_ENV).filter(|dir| !dir.is_empty()) {
+        return Ok(PathBuf::from(dir));
+    }
+    if cfg!(target_os = "windows") {
+        get_windows_app_data_dir(APP_NAME)
+    } else {
+        std::env::var("HOME")
+            .map(|home| PathBuf::from(home).join(".local/share").join(APP_NAME))
+            .map_err(|_| "Failed to get HOME directory".to_string())
+    }
+}
+
+/// Full path of the live database file
+pub fn get_db_path() -> Result<PathBuf, String> {
+    Ok(get_app_data_dir()?.join(DB_FILE_NAME))
+}
+
+/// Rename a legacy year-named database (with its WAL/SHM companions) to
+/// `DB_FILE_NAME`. Does nothing once the current file exists, so an old file
+/// left behind by a restore can never replace live data. Returns the legacy
+/// name that was migrated.
+pub fn migrate_legacy_db_file(app_data_dir: &Path) -> Result<Option<&'static str>, String> {
+    let db_path = app_data_dir.join(DB_FILE_NAME);
+    if db_path.exists() {
+        return Ok(None);
+    }
+
+    for legacy_name in LEGACY_DB_FILE_NAMES {
+        let legacy_path = app_data_dir.join(legacy_name);
+        if !legacy_path.exists() {
+            continue;
+        }
+
+        for suffix in DB_COMPANION_SUFFIXES {
+            let companion = app_data_dir.join(format!("{}{}", legacy_name, suffix));
+            if companion.exists() {
+                fs::rename(&companion, app_data_dir.join(format!("{}{}", DB_FILE_NAME, suffix)))
+                    .map_err(|e| format!("Failed to move {}: {}", companion.display(), e))?;
+            }
+        }
+        fs::rename(&legacy_path, &db_path)
+            .map_err(|e| format!("Failed to move {}: {}", legacy_path.display(), e))?;
+        return Ok(Some(legacy_name));
+    }
+
+    Ok(None)
+}
+
+/// Open a connection to the live database for a business command.
+/// The frontend holds its own connection pool, so every write must
+/// tolerate a short wait for the lock instead of failing immediately.
+pub fn open_connection() -> Result<Connection, String> {
+    open_connection_at(&get_db_path()?)
+}
+
+/// Open a connection to the database at `db_path`, configured like `open_connection`
+pub fn open_connection_at(db_path: &Path) -> Result<Connection, String> {
+    if !db_path.exists() {
+        return Err(format!("Database file not found: {}", db_path.display()));
+    }
+
+    let conn = Connection::open(db_path)
+        .map_err(|e| format!("Failed to open database: {}", e))?;
+
+    conn.busy_timeout(Duration::from_secs(30))
+        .map_err(|e| format!("Failed to set busy timeout: {}", e))?;
+    conn.pragma_update(None, "foreign_keys", true)
+        .map_err(|e| format!("Failed to enable foreign keys: {}", e))?;
+    change_events::watch(&conn);
+
+    Ok(conn)
+}
+
+/// Open an archived database for reading only
+pub fn open_read_only(path: &Path) -> Result<Connection, String> {
+    if !path.exists() {
+        return Err(format!("Archive not found: {}", path.display()));
+    }
+    Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| format!("Failed to open archive {}: {}", path.display(), e))
+}
+
+/// Check whether a table exists (the frontend creates most tables lazily)
+pub fn table_exists(conn: &Connection, table_name: &str) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1",
+        [table_name],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|count| count > 0)
+}
+
+/// Column names of a table, in declaration order
+pub fn column_names(conn: &Connection, table: &str) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let names = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    names.collect()
+}
+
+/// Check whether a table has a column (shadow columns are added on startup,
+/// so a table the frontend created during this session may not have them yet)
+pub fn has_column(conn: &Connection, table: &str, column: &str) -> rusqlite::Result<bool> {
+    Ok(column_names(conn, table)?.iter().any(|name| name == column))
+}
+
+/// Current local date in the `YYYY-MM-DD` format used by every date column
+pub fn current_date(conn: &Connection) -> rusqlite::Result<String> {
+    conn.query_row("SELECT date('now', 'localtime')", [], |row| row.get(0))
+}
+
+/// Current local time in the `HH:MM:SS` format used by every time column
+pub fn current_time(conn: &Connection) -> rusqlite::Result<String> {
+    conn.query_row("SELECT time('now', 'localtime')", [], |row| row.get(0))
+}
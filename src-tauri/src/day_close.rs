@@ -0,0 +1,717 @@
+/*!
+ * DAILY LEDGER CLOSING
+ * Closing a day snapshots the opening balance, money in, money out and
+ * closing balance of every payment channel (and cash in hand) from the
+ * general journal, and locks the day: triggers reject new, edited or
+ * deleted transactions dated inside a closed day until an admin reopens
+ * it with a reason.
+ *
+ * A day opens with the closing balance of the last closed day plus
+ * whatever moved in between, so closed figures are carried forward as they
+ * were counted. Re-closing a reopened day rolls its new closing forward
+ * through every later closed day.
+ *
+ * The same triggers lock every day of a closed fiscal year.
+ *
+ * Only the columns that change a day's figures are locked (dates, amounts,
+ * parties, channels); status changes such as cancelling an old invoice or
+ * recording a payment against it stay possible and post on the day they happen.
+ */
+
+use std::collections::HashMap;
+
+use log::{info, warn};
+use rusqlite::{params, Connection, OptionalExtension, Transaction, TransactionBehavior};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::audit::{self, AuditEvent};
+use crate::database::{column_names, current_date, open_connection, table_exists};
+use crate::fiscal_year::opening_marker_sql;
+use crate::journal::{sync_journal_in_transaction, CASH_ACCOUNT};
+use crate::money::{paisa_from_row, paisa_sql, sum_paisa_sql, Money};
+use crate::session;
+use crate::vendor_payables::sync_vendors_in_transaction;
+
+/// Transaction tables locked by date: table, date column, columns that may not change
+const LOCKED_TABLES: &[(&str, &str, &[&str])] = &[
+    (
+        "invoices",
+        "date",
+        &[
+            "customer_id",
+            "subtotal",
+            "discount_amount",
+            "tax_amount",
+            "total_amount",
+            "grand_total",
+        ],
+    ),
+    (
+        "payments",
+        "date",
+        &["customer_id", "payment_type", "amount", "payment_channel_id"],
+    ),
+    ("ledger_entries", "date", &["type", "amount", "payment_channel_id"]),
+    (
+        "customer_ledger_entries",
+        "date",
+        &["customer_id", "entry_type", "amount"],
+    ),
+    (
+        "vendor_payments",
+        "date",
+        &["vendor_id", "amount", "payment_channel_id"],
+    ),
+    ("vendor_ledger_entries", "date", &["vendor_id", "entry_type", "amount"]),
+    (
+        "stock_receiving",
+        "received_date",
+        &["vendor_id", "total_cost", "grand_total"],
+    ),
+    ("stock_movements", "date", &["product_id", "movement_type", "quantity"]),
+    (
+        "returns",
+        "date",
+        &[
+            "original_invoice_id",
+            "total_amount",
+            "settlement_type",
+            "settlement_amount",
+        ],
+    ),
+    (
+        "business_expenses",
+        "date",
+        &["amount", "total_amount", "payment_channel_id"],
+    ),
+    (
+        "business_income",
+        "date",
+        &["amount", "net_amount", "payment_channel_id"],
+    ),
+    (
+        "salary_payments",
+        "payment_date",
+        &["staff_id", "payment_amount", "total_amount", "payment_channel_id"],
+    ),
+];
+
+/// Line tables locked by their parent's date: table, parent key, parent table, parent date column, locked columns
+const LOCKED_LINE_TABLES: &[(&str, &str, &str, &str, &[&str])] = &[
+    (
+        "invoice_items",
+        "invoice_id",
+        "invoices",
+        "date",
+        &[
+            "product_id",
+            "quantity",
+            "unit_price",
+            "line_total",
+            "amount",
+            "total_price",
+        ],
+    ),
+    (
+        "return_items",
+        "return_id",
+        "returns",
+        "date",
+        &["product_id", "return_quantity", "total_price"],
+    ),
+];
+
+const DAY_CLOSED_MESSAGE: &str =
+    "DAY_CLOSED: this date is in a closed day or fiscal year; an admin must reopen it before it can change";
+
+/// Create the closing tables and (re)install the day locks on every transaction table
+pub fn ensure_schema(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS daily_closings (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            date TEXT NOT NULL UNIQUE,
+            status TEXT NOT NULL CHECK (status IN ('closed', 'reopened')),
+            closed_by TEXT NOT NULL,
+            closed_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            reopened_by TEXT,
+            reopened_at DATETIME,
+            reopen_reason TEXT,
+            notes TEXT
+        );
+        CREATE TABLE IF NOT EXISTS daily_closing_balances (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            date TEXT NOT NULL,
+            account_id INTEGER NOT NULL,
+            payment_channel_id INTEGER,
+            channel_name TEXT NOT NULL,
+            opening_balance REAL NOT NULL DEFAULT 0,
+            total_in REAL NOT NULL DEFAULT 0,
+            total_out REAL NOT NULL DEFAULT 0,
+            closing_balance REAL NOT NULL DEFAULT 0,
+            UNIQUE(date, account_id),
+            FOREIGN KEY (date) REFERENCES daily_closings(date),
+            FOREIGN KEY (account_id) REFERENCES gl_accounts(id)
+        );
+        CREATE TABLE IF NOT EXISTS daily_closing_events (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            date TEXT NOT NULL,
+            action TEXT NOT NULL CHECK (action IN ('closed', 'reopened')),
+            performed_by TEXT NOT NULL,
+            reason TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE INDEX IF NOT EXISTS idx_daily_closing_events_date ON daily_closing_events(date);",
+    )?;
+
+    // Closed fiscal years lock every day they span
+    let years = table_exists(conn, "fiscal_years")?;
+
+    for (table, date_column, columns) in LOCKED_TABLES {
+        let closed = |row: &str| closed_date_sql(&format!("substr({}.{}, 1, 10)", row, date_column), years);
+        // Zero-amount year opening markers move no figures and may land in a closed day
+        let insert_closed = match opening_marker_sql(table, "NEW") {
+            Some(marker) => format!("{} AND NOT {}", closed("NEW"), marker),
+            None => closed("NEW"),
+        };
+        install_day_lock(
+            conn,
+            table,
+            columns,
+            date_column,
+            &insert_closed,
+            &format!("({} OR {})", closed("OLD"), closed("NEW")),
+            &closed("OLD"),
+        )?;
+    }
+
+    for (table, parent_key, parent, parent_date, columns) in LOCKED_LINE_TABLES {
+        if !table_exists(conn, parent)? {
+            continue;
+        }
+        let closed = |row: &str| {
+            format!(
+                "EXISTS (SELECT 1 FROM {parent} p WHERE p.id = {row}.{parent_key} AND {})",
+                closed_date_sql(&format!("substr(p.{parent_date}, 1, 10)"), years)
+            )
+        };
+        install_day_lock(
+            conn,
+            table,
+            columns,
+            parent_key,
+            &closed("NEW"),
+            &format!("({} OR {})", closed("OLD"), closed("NEW")),
+            &closed("OLD"),
+        )?;
+    }
+    Ok(())
+}
+
+/// SQL condition that is true when a date falls in a closed day (or, once the
+/// fiscal year table exists, a closed fiscal year)
+fn closed_date_sql(date: &str, years: bool) -> String {
+    let day = format!("EXISTS (SELECT 1 FROM daily_closings WHERE status = 'closed' AND date = {})", date);
+    if !years {
+        return day;
+    }
+    format!(
+        "({} OR EXISTS (SELECT 1 FROM fiscal_years WHERE status = 'closed' AND {} BETWEEN start_date AND end_date))",
+        day, date
+    )
+}
+
+/// Whether a `YYYY-MM-DD` date lies in a closed day or closed fiscal year
+pub fn is_date_closed(conn: &Connection, date: &str) -> Result<bool, String> {
+    let exists = |table| table_exists(conn, table).map_err(|e| format!("Failed to inspect schema: {}", e));
+    if !exists("daily_closings")? {
+        return Ok(false);
+    }
+    let years = exists("fiscal_years")?;
+    conn.query_row(
+        &format!("SELECT {}", closed_date_sql("substr(?1, 1, 10)", years)),
+        [date],
+        |row| row.get(0),
+    )
+    .map_err(|e| format!("Failed to check whether {} is closed: {}", date, e))
+}
+
+/// Replace the insert, update and delete locks of one table. Updates are
+/// only rejected when a locked column actually changes; locked columns the
+/// table does not have are left out and missing tables are skipped.
+fn install_day_lock(
+    conn: &Connection,
+    table: &str,
+    columns: &[&str],
+    key_column: &str,
+    insert_closed: &str,
+    update_closed: &str,
+    delete_closed: &str,
+) -> rusqlite::Result<()> {
+    if !table_exists(conn, table)? {
+        return Ok(());
+    }
+    let existing = column_names(conn, table)?;
+    if !existing.iter().any(|name| name == key_column) {
+        return Ok(());
+    }
+    let locked: Vec<&str> = std::iter::once(key_column)
+        .chain(columns.iter().copied())
+        .filter(|column| existing.iter().any(|name| name == column))
+        .collect();
+    let changed = locked
+        .iter()
+        .map(|column| format!("OLD.{column} IS NOT NEW.{column}"))
+        .collect::<Vec<_>>()
+        .join(" OR ");
+
+    conn.execute_batch(&format!(
+        "DROP TRIGGER IF EXISTS trg_{table}_day_lock_insert;
+         DROP TRIGGER IF EXISTS trg_{table}_day_lock_update;
+         DROP TRIGGER IF EXISTS trg_{table}_day_lock_delete;
+         CREATE TRIGGER trg_{table}_day_lock_insert BEFORE INSERT ON {table}
+         WHEN {insert_closed}
+         BEGIN
+             SELECT RAISE(ABORT, '{message}');
+         END;
+         CREATE TRIGGER trg_{table}_day_lock_update BEFORE UPDATE OF {columns} ON {table}
+         WHEN ({changed}) AND {update_closed}
+         BEGIN
+             SELECT RAISE(ABORT, '{message}');
+         END;
+         CREATE TRIGGER trg_{table}_day_lock_delete BEFORE DELETE ON {table}
+         WHEN {delete_closed}
+         BEGIN
+             SELECT RAISE(ABORT, '{message}');
+         END;",
+        columns = locked.join(", "),
+        message = DAY_CLOSED_MESSAGE,
+    ))
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChannelDayBalance {
+    pub account_id: i64,
+    /// None for cash in hand (money moved without a payment channel)
+    pub payment_channel_id: Option<i64>,
+    pub channel_name: String,
+    pub opening_balance: Money,
+    pub total_in: Money,
+    pub total_out: Money,
+    pub closing_balance: Money,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DayClosing {
+    pub date: String,
+    /// "open", "closed" or "reopened"; balances of a day that is not closed are computed live
+    pub status: String,
+    pub closed_by: Option<String>,
+    pub closed_at: Option<String>,
+    pub reopened_by: Option<String>,
+    pub reopened_at: Option<String>,
+    pub reopen_reason: Option<String>,
+    pub notes: Option<String>,
+    pub balances: Vec<ChannelDayBalance>,
+    pub total_opening: Money,
+    pub total_closing: Money,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DayCloseResult {
+    pub closing: DayClosing,
+    /// Later closed days whose opening balances were carried forward again
+    pub later_days_rolled_forward: usize,
+}
+
+/// Accept only real `YYYY-MM-DD` dates
+fn validate_date(conn: &Connection, date: &str) -> Result<String, String> {
+    let date = date.trim();
+    let valid: bool = conn
+        .query_row("SELECT date(?1) IS ?1", [date], |row| row.get(0))
+        .map_err(|e| format!("Failed to validate date: {}", e))?;
+    if !valid {
+        return Err(format!("Invalid date '{}', expected YYYY-MM-DD", date));
+    }
+    Ok(date.to_string())
+}
+
+fn closing_status(conn: &Connection, date: &str) -> Result<Option<String>, String> {
+    conn.query_row("SELECT status FROM daily_closings WHERE date = ?1", [date], |row| {
+        row.get(0)
+    })
+    .optional()
+    .map_err(|e| format!("Failed to read day status: {}", e))
+}
+
+/// Channel balances for a day: opening carried from the last closed day
+/// before it, plus the day's journal movements
+fn compute_day(conn: &Connection, date: &str) -> Result<Vec<ChannelDayBalance>, String> {
+    let previous: Option<String> = conn
+        .query_row(
+            "SELECT MAX(date) FROM daily_closings WHERE status = 'closed' AND date < ?1",
+            [date],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to find previous closing: {}", e))?;
+
+    let paisa = |table, column| paisa_sql(conn, table, column).map_err(|e| format!("Failed to inspect schema: {}", e));
+    let mut carried: HashMap<i64, Money> = HashMap::new();
+    if let Some(previous) = &previous {
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT account_id, {} FROM daily_closing_balances WHERE date = ?1",
+                paisa("daily_closing_balances", "closing_balance")?
+            ))
+            .map_err(|e| format!("Failed to query previous closing: {}", e))?;
+        let rows = stmt
+            .query_map([previous], |row| Ok((row.get::<_, i64>(0)?, paisa_from_row(row, 1)?)))
+            .map_err(|e| format!("Failed to read previous closing: {}", e))?;
+        for row in rows {
+            let (account_id, closing) = row.map_err(|e| format!("Failed to read previous closing: {}", e))?;
+            carried.insert(account_id, closing);
+        }
+    }
+
+    let (debit, credit) = (paisa("journal_lines", "l.debit")?, paisa("journal_lines", "l.credit")?);
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT a.id, a.payment_channel_id, a.name, {until_previous}, {between}, {money_in}, {money_out}
+             FROM gl_accounts a
+             LEFT JOIN journal_lines l ON l.account_id = a.id
+             LEFT JOIN journal_entries e ON e.id = l.journal_entry_id
+             WHERE a.payment_channel_id IS NOT NULL OR a.code = ?3
+             GROUP BY a.id
+             ORDER BY a.code",
+            until_previous = sum_paisa_sql(&format!(
+                "CASE WHEN ?2 IS NOT NULL AND e.date <= ?2 THEN {debit} - {credit} ELSE 0 END"
+            )),
+            between = sum_paisa_sql(&format!(
+                "CASE WHEN (?2 IS NULL OR e.date > ?2) AND e.date < ?1 THEN {debit} - {credit} ELSE 0 END"
+            )),
+            money_in = sum_paisa_sql(&format!("CASE WHEN e.date = ?1 THEN {debit} ELSE 0 END")),
+            money_out = sum_paisa_sql(&format!("CASE WHEN e.date = ?1 THEN {credit} ELSE 0 END")),
+        ))
+        .map_err(|e| format!("Failed to query channel movements: {}", e))?;
+    let rows = stmt
+        .query_map(params![date, previous, CASH_ACCOUNT], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, Option<i64>>(1)?,
+                row.get::<_, String>(2)?,
+                Money::from_paisa(row.get(3)?),
+                Money::from_paisa(row.get(4)?),
+                Money::from_paisa(row.get(5)?),
+                Money::from_paisa(row.get(6)?),
+            ))
+        })
+        .map_err(|e| format!("Failed to read channel movements: {}", e))?;
+
+    let mut balances = Vec::new();
+    for row in rows {
+        let (account_id, payment_channel_id, channel_name, until_previous, between, total_in, total_out) =
+            row.map_err(|e| format!("Failed to read channel movement: {}", e))?;
+        let opening_balance = carried.get(&account_id).copied().unwrap_or(until_previous) + between;
+        balances.push(ChannelDayBalance {
+            account_id,
+            payment_channel_id,
+            channel_name,
+            opening_balance,
+            total_in,
+            total_out,
+            closing_balance: opening_balance + total_in - total_out,
+        });
+    }
+    Ok(balances)
+}
+
+fn stored_balances(conn: &Connection, date: &str) -> Result<Vec<ChannelDayBalance>, String> {
+    let paisa = |column| {
+        paisa_sql(conn, "daily_closing_balances", column).map_err(|e| format!("Failed to inspect schema: {}", e))
+    };
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT b.account_id, b.payment_channel_id, b.channel_name, {}, {}, {}, {}
+             FROM daily_closing_balances b
+             LEFT JOIN gl_accounts a ON a.id = b.account_id
+             WHERE b.date = ?1
+             ORDER BY a.code",
+            paisa("b.opening_balance")?,
+            paisa("b.total_in")?,
+            paisa("b.total_out")?,
+            paisa("b.closing_balance")?
+        ))
+        .map_err(|e| format!("Failed to query closing balances: {}", e))?;
+    let rows = stmt
+        .query_map([date], |row| {
+            Ok(ChannelDayBalance {
+                account_id: row.get(0)?,
+                payment_channel_id: row.get(1)?,
+                channel_name: row.get(2)?,
+                opening_balance: paisa_from_row(row, 3)?,
+                total_in: paisa_from_row(row, 4)?,
+                total_out: paisa_from_row(row, 5)?,
+                closing_balance: paisa_from_row(row, 6)?,
+            })
+        })
+        .map_err(|e| format!("Failed to read closing balances: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read closing balance: {}", e))
+}
+
+/// Replace the stored snapshot of a day with freshly computed balances
+fn snapshot_day(tx: &Transaction, date: &str) -> Result<(), String> {
+    let balances = compute_day(tx, date)?;
+    tx.execute("DELETE FROM daily_closing_balances WHERE date = ?1", [date])
+        .map_err(|e| format!("Failed to clear closing balances of {}: {}", date, e))?;
+    for balance in &balances {
+        tx.execute(
+            "INSERT INTO daily_closing_balances (
+                date, account_id, payment_channel_id, channel_name,
+                opening_balance, total_in, total_out, closing_balance
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                date,
+                balance.account_id,
+                balance.payment_channel_id,
+                balance.channel_name,
+                balance.opening_balance.to_rupees(),
+                balance.total_in.to_rupees(),
+                balance.total_out.to_rupees(),
+                balance.closing_balance.to_rupees()
+            ],
+        )
+        .map_err(|e| format!("Failed to store closing balance of {}: {}", balance.channel_name, e))?;
+    }
+    Ok(())
+}
+
+fn log_event(
+    tx: &Transaction,
+    date: &str,
+    action: &str,
+    performed_by: &str,
+    reason: Option<&str>,
+) -> Result<(), String> {
+    tx.execute(
+        "INSERT INTO daily_closing_events (date, action, performed_by, reason) VALUES (?1, ?2, ?3, ?4)",
+        params![date, action, performed_by, reason],
+    )
+    .map_err(|e| format!("Failed to record day {} event: {}", action, e))?;
+    Ok(())
+}
+
+/// A day's closing record with its stored snapshot, or live figures when the day is not closed
+pub fn day_closing(conn: &Connection, date: &str) -> Result<DayClosing, String> {
+    let date = validate_date(conn, date)?;
+    let record = conn
+        .query_row(
+            "SELECT status, closed_by, closed_at, reopened_by, reopened_at, reopen_reason, notes
+             FROM daily_closings WHERE date = ?1",
+            [&date],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                    row.get::<_, Option<String>>(5)?,
+                    row.get::<_, Option<String>>(6)?,
+                ))
+            },
+        )
+        .optional()
+        .map_err(|e| format!("Failed to load closing of {}: {}", date, e))?;
+
+    let (status, closed_by, closed_at, reopened_by, reopened_at, reopen_reason, notes) =
+        record.unwrap_or_else(|| ("open".to_string(), None, None, None, None, None, None));
+    let balances = if status == "closed" {
+        stored_balances(conn, &date)?
+    } else {
+        compute_day(conn, &date)?
+    };
+
+    Ok(DayClosing {
+        total_opening: balances.iter().map(|balance| balance.opening_balance).sum(),
+        total_closing: balances.iter().map(|balance| balance.closing_balance).sum(),
+        date,
+        status,
+        closed_by,
+        closed_at,
+        reopened_by,
+        reopened_at,
+        reopen_reason,
+        notes,
+        balances,
+    })
+}
+
+/// Close a day: post outstanding documents to the vendor payables and the
+/// journal, snapshot channel balances, lock the day and carry the closing
+/// through later closed days
+pub fn close_day_in_transaction(
+    tx: &Transaction,
+    date: &str,
+    closed_by: &str,
+    notes: Option<&str>,
+) -> Result<DayCloseResult, String> {
+    let date = validate_date(tx, date)?;
+    let today = current_date(tx).map_err(|e| format!("Failed to read date: {}", e))?;
+    if date > today {
+        return Err(format!("{} has not happened yet and cannot be closed", date));
+    }
+    if closing_status(tx, &date)?.as_deref() == Some("closed") {
+        return Err(format!("{} is already closed", date));
+    }
+    let closed_by = match closed_by.trim() {
+        "" => "system",
+        user => user,
+    };
+
+    // Once the day is locked its documents can no longer post on their own date
+    sync_vendors_in_transaction(tx, None)?;
+    sync_journal_in_transaction(tx)?;
+
+    tx.execute(
+        "INSERT INTO daily_closings (date, status, closed_by, notes) VALUES (?1, 'closed', ?2, ?3)
+         ON CONFLICT(date) DO UPDATE SET
+            status = 'closed', closed_by = excluded.closed_by, closed_at = CURRENT_TIMESTAMP,
+            notes = COALESCE(excluded.notes, daily_closings.notes)",
+        params![date, closed_by, notes],
+    )
+    .map_err(|e| format!("Failed to close {}: {}", date, e))?;
+    snapshot_day(tx, &date)?;
+    log_event(tx, &date, "closed", closed_by, notes)?;
+
+    let later_days: Vec<String> = {
+        let mut stmt = tx
+            .prepare("SELECT date FROM daily_closings WHERE status = 'closed' AND date > ?1 ORDER BY date")
+            .map_err(|e| format!("Failed to query later closings: {}", e))?;
+        let rows = stmt
+            .query_map([&date], |row| row.get(0))
+            .map_err(|e| format!("Failed to read later closings: {}", e))?;
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read later closing: {}", e))?
+    };
+    for later in &later_days {
+        snapshot_day(tx, later)?;
+    }
+
+    Ok(DayCloseResult {
+        closing: day_closing(tx, &date)?,
+        later_days_rolled_forward: later_days.len(),
+    })
+}
+
+/// Reopen a closed day so its transactions can change again. Admins only; the reason is kept.
+pub fn reopen_day_in_transaction(
+    tx: &Transaction,
+    date: &str,
+    reason: &str,
+    reopened_by: &str,
+) -> Result<DayClosing, String> {
+    let date = validate_date(tx, date)?;
+    let reason = reason.trim();
+    if reason.is_empty() {
+        return Err("A reason is required to reopen a closed day".to_string());
+    }
+    session::require_admin(tx, reopened_by, "reopen a closed day")?;
+    if closing_status(tx, &date)?.as_deref() != Some("closed") {
+        return Err(format!("{} is not closed", date));
+    }
+
+    tx.execute(
+        "UPDATE daily_closings SET status = 'reopened', reopened_by = ?2, reopened_at = CURRENT_TIMESTAMP,
+            reopen_reason = ?3
+         WHERE date = ?1",
+        params![date, reopened_by.trim(), reason],
+    )
+    .map_err(|e| format!("Failed to reopen {}: {}", date, e))?;
+    log_event(tx, &date, "reopened", reopened_by.trim(), Some(reason))?;
+
+    day_closing(tx, &date)
+}
+
+/// Close a day (today when no date is given) and lock it
+#[tauri::command]
+pub async fn close_day(
+    date: Option<String>,
+    closed_by: String,
+    notes: Option<String>,
+) -> Result<DayCloseResult, String> {
+    let mut conn = open_connection()?;
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let date = match date {
+        Some(date) => date,
+        None => current_date(&tx).map_err(|e| format!("Failed to read date: {}", e))?,
+    };
+    let notes = notes.as_deref().map(str::trim).filter(|notes| !notes.is_empty());
+    let result = close_day_in_transaction(&tx, &date, &closed_by, notes)?;
+    audit::record(
+        &tx,
+        &AuditEvent::new("close", "day")
+            .entity(&date)
+            .by(&closed_by)
+            .after(json!(&result)),
+    )?;
+
+    tx.commit().map_err(|e| format!("Failed to commit day close: {}", e))?;
+
+    info!(
+        "[DAY-CLOSE] {} closed by {}: opening Rs.{}, closing Rs.{} across {} channels",
+        result.closing.date,
+        result.closing.closed_by.as_deref().unwrap_or("system"),
+        result.closing.total_opening,
+        result.closing.total_closing,
+        result.closing.balances.len()
+    );
+    Ok(result)
+}
+
+/// Reopen a closed day; requires an admin and a reason
+#[tauri::command]
+pub async fn reopen_day(date: String, reason: String, reopened_by: String) -> Result<DayClosing, String> {
+    let mut conn = open_connection()?;
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let closing = reopen_day_in_transaction(&tx, &date, &reason, &reopened_by)?;
+    audit::record(
+        &tx,
+        &AuditEvent::new("reopen", "day")
+            .entity(&closing.date)
+            .by(&reopened_by)
+            .after(json!({ "reason": reason.trim(), "closing": &closing })),
+    )?;
+
+    tx.commit().map_err(|e| format!("Failed to commit day reopen: {}", e))?;
+
+    warn!(
+        "[DAY-CLOSE] {} reopened by {}: {}",
+        closing.date,
+        reopened_by.trim(),
+        reason.trim()
+    );
+    Ok(closing)
+}
+
+/// Opening and closing balances per channel for a day, as closed or as they
+/// stand in the journal. Reading takes no write lock; an open day shows
+/// documents entered since the last posting once `sync_journal` has run.
+#[tauri::command]
+pub async fn get_day_closing(date: String) -> Result<DayClosing, String> {
+    let mut conn = open_connection()?;
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Deferred)
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let closing = day_closing(&tx, &date)?;
+    tx.finish()
+        .map_err(|e| format!("Failed to finish reading the day closing: {}", e))?;
+
+    Ok(closing)
+}
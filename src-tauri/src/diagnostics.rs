@@ -0,0 +1,539 @@
+/*!
+ * DIAGNOSTIC BUNDLE
+ *
+ * One zip the owner can attach to a support request instead of screenshots:
+ *
+ *   manifest.json       app version, when and how the bundle was made
+ *   system.json         the get_system_info report
+ *   logs/recent.log     the newest log records
+ *   database/schema.sql every table, index, view and trigger
+ *   database/migrations.json  applied migrations, app_info, money and quantity conversion state
+ *   database/integrity.json   SQLite integrity and foreign keys, stock and balance checks
+ *   database/row_counts.json  rows per table
+ *   backups.json        backup files, year archives and pre-restore copies on disk
+ *   config.json         app settings, API and sync configuration, log filter
+ *
+ * Customer details stay out by default: customer names, phones, CNICs and
+ * addresses in the logs and reports become `customer #<id>`, and no data
+ * rows are included. With `include_customer_data` the logs are left as they
+ * are and a consistent copy of the database is added as `database/store.db`.
+ * Setting values that look like secrets are masked either way, and API key
+ * hashes and the sync group code are never included.
+ */
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use log::info;
+use rusqlite::types::ValueRef;
+use rusqlite::Connection;
+use serde::Serialize;
+use serde_json::{json, Map, Value as JsonValue};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::customer_balance::balance_report;
+use crate::database::{column_names, get_app_data_dir, get_db_path, open_connection_at, table_exists, DB_FILE_NAME};
+use crate::fiscal_year::archive_dir;
+use crate::logging;
+use crate::stock_engine::check_stock;
+
+const DIAGNOSTICS_DIR: &str = "diagnostics";
+const RECENT_LOG_RECORDS: usize = 10_000;
+/// Customer columns scrubbed from logs and reports unless customer data is included
+const CUSTOMER_DETAIL_COLUMNS: &[&str] = &["name", "phone", "cnic", "address", "email"];
+/// Words in a column or setting name whose values never leave the shop
+const SECRET_WORDS: &[&str] = &["password", "passcode", "secret", "token", "key", "pin", "hash"];
+/// The sync group code lets a terminal join the shop's books
+const SECRET_NAMES: &[&str] = &["group_code"];
+const MASK: &str = "***";
+
+#[derive(Debug, Serialize)]
+pub struct DiagnosticBundle {
+    pub path: String,
+    pub size: u64,
+    pub files: Vec<String>,
+    pub customer_data_included: bool,
+}
+
+struct BundleWriter {
+    zip: ZipWriter<File>,
+    files: Vec<String>,
+}
+
+impl BundleWriter {
+    fn add(&mut self, name: &str, contents: &[u8]) -> Result<(), String> {
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        self.zip
+            .start_file(name, options)
+            .and_then(|_| self.zip.write_all(contents).map_err(Into::into))
+            .map_err(|e| format!("Failed to add {} to the bundle: {}", name, e))?;
+        self.files.push(name.to_string());
+        Ok(())
+    }
+
+    fn add_json(&mut self, name: &str, value: &JsonValue) -> Result<(), String> {
+        let text = serde_json::to_string_pretty(value).map_err(|e| format!("Failed to encode {}: {}", name, e))?;
+        self.add(name, text.as_bytes())
+    }
+}
+
+/// Replaces customer details with `customer #<id>`
+struct CustomerScrubber {
+    /// Longest first, so a full name wins over a part of it
+    details: Vec<(String, String)>,
+}
+
+impl CustomerScrubber {
+    fn load(conn: &Connection) -> Result<CustomerScrubber, String> {
+        let mut details = Vec::new();
+        if table_exists(conn, "customers").map_err(|e| format!("Failed to read schema: {}", e))? {
+            let columns = column_names(conn, "customers").map_err(|e| format!("Failed to read customers: {}", e))?;
+            let present: Vec<&str> = CUSTOMER_DETAIL_COLUMNS
+                .iter()
+                .copied()
+                .filter(|column| columns.iter().any(|name| name == column))
+                .collect();
+            if !present.is_empty() {
+                let sql = format!("SELECT id, {} FROM customers", present.join(", "));
+                let mut stmt = conn
+                    .prepare(&sql)
+                    .map_err(|e| format!("Failed to read customers: {}", e))?;
+                let mut rows = stmt.query([]).map_err(|e| format!("Failed to read customers: {}", e))?;
+                while let Some(row) = rows.next().map_err(|e| format!("Failed to read customers: {}", e))? {
+                    let id: i64 = row.get(0).map_err(|e| format!("Failed to read customers: {}", e))?;
+                    for index in 1..=present.len() {
+                        if let Ok(ValueRef::Text(text)) = row.get_ref(index) {
+                            let text = String::from_utf8_lossy(text).trim().to_string();
+                            // Two-letter values would scrub ordinary words
+                            if text.chars().count() >= 3 {
+                                details.push((text, format!("customer #{}", id)));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        details.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then_with(|| a.0.cmp(&b.0)));
+        details.dedup_by(|a, b| a.0 == b.0);
+        Ok(CustomerScrubber { details })
+    }
+
+    fn scrub(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for (detail, replacement) in &self.details {
+            if text.contains(detail.as_str()) {
+                text = replace_whole(&text, detail, replacement);
+            }
+        }
+        text
+    }
+}
+
+/// Replace `needle` where it is not part of a longer word
+fn replace_whole(text: &str, needle: &str, replacement: &str) -> String {
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(found) = rest.find(needle) {
+        let before = rest[..found].chars().next_back().or_else(|| result.chars().next_back());
+        let after = rest[found + needle.len()..].chars().next();
+        result.push_str(&rest[..found]);
+        if is_word(before) || is_word(after) {
+            result.push_str(needle);
+        } else {
+            result.push_str(replacement);
+        }
+        rest = &rest[found + needle.len()..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// `api_key`, `admin_pin`, `smtpPassword` yes; `shipping_address` no
+fn looks_secret(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    SECRET_NAMES.contains(&name.as_str())
+        || name.split(|c: char| !c.is_ascii_alphanumeric()).any(|word| {
+            SECRET_WORDS
+                .iter()
+                .any(|secret| word == *secret || word.ends_with(secret) && word.len() > 6)
+        })
+}
+
+fn json_of(value: ValueRef) -> JsonValue {
+    match value {
+        ValueRef::Null => JsonValue::Null,
+        ValueRef::Integer(i) => json!(i),
+        ValueRef::Real(f) => json!(f),
+        ValueRef::Text(bytes) => json!(String::from_utf8_lossy(bytes)),
+        ValueRef::Blob(bytes) => json!(format!("<{} byte blob>", bytes.len())),
+    }
+}
+
+/// Every row of a query as JSON objects. Columns named like secrets are
+/// masked, and for key/value tables so is the value of a secret-named key.
+fn query_json(conn: &Connection, sql: &str) -> Result<Vec<JsonValue>, String> {
+    let mut stmt = conn.prepare(sql).map_err(|e| format!("Failed to run {}: {}", sql, e))?;
+    let columns: Vec<String> = stmt.column_names().iter().map(|name| name.to_string()).collect();
+    let mut rows = stmt.query([]).map_err(|e| format!("Failed to run {}: {}", sql, e))?;
+    let mut result = Vec::new();
+    while let Some(row) = rows.next().map_err(|e| format!("Failed to run {}: {}", sql, e))? {
+        let mut object = Map::new();
+        for (index, column) in columns.iter().enumerate() {
+            let value = row
+                .get_ref(index)
+                .map_err(|e| format!("Failed to run {}: {}", sql, e))?;
+            // `key` names the setting in key/value tables; its value is checked below
+            let value = if column != "key" && looks_secret(column) && value != ValueRef::Null {
+                json!(MASK)
+            } else {
+                json_of(value)
+            };
+            object.insert(column.clone(), value);
+        }
+        let secret_key = object.get("key").and_then(JsonValue::as_str).is_some_and(looks_secret);
+        if secret_key {
+            if let Some(value) = object.get_mut("value") {
+                *value = json!(MASK);
+            }
+        }
+        result.push(JsonValue::Object(object));
+    }
+    Ok(result)
+}
+
+/// Rows of `table` when it exists, else null
+fn table_json(conn: &Connection, table: &str, sql: &str) -> Result<JsonValue, String> {
+    if table_exists(conn, table).map_err(|e| format!("Failed to read schema: {}", e))? {
+        Ok(JsonValue::Array(query_json(conn, sql)?))
+    } else {
+        Ok(JsonValue::Null)
+    }
+}
+
+fn pragma_text(conn: &Connection, pragma: &str) -> Result<Vec<String>, String> {
+    let rows = query_json(conn, pragma)?;
+    Ok(rows
+        .iter()
+        .map(|row| match row {
+            JsonValue::Object(object) if object.len() == 1 => object
+                .values()
+                .next()
+                .map(|value| value.as_str().map(str::to_string).unwrap_or_else(|| value.to_string()))
+                .unwrap_or_default(),
+            other => other.to_string(),
+        })
+        .collect())
+}
+
+fn schema_sql(conn: &Connection) -> Result<String, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT type, name, sql FROM sqlite_master
+             WHERE sql IS NOT NULL
+             ORDER BY CASE type WHEN 'table' THEN 0 WHEN 'index' THEN 1 WHEN 'view' THEN 2 ELSE 3 END, name",
+        )
+        .map_err(|e| format!("Failed to read schema: {}", e))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })
+        .map_err(|e| format!("Failed to read schema: {}", e))?;
+    let mut schema = String::new();
+    for row in rows {
+        let (kind, name, sql) = row.map_err(|e| format!("Failed to read schema: {}", e))?;
+        schema.push_str(&format!("-- {} {}\n{};\n\n", kind, name, sql));
+    }
+    Ok(schema)
+}
+
+fn migrations_json(conn: &Connection) -> Result<JsonValue, String> {
+    let user_version: i64 = conn
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(|e| format!("Failed to read user_version: {}", e))?;
+    let unresolved_quantities: Option<i64> = if table_exists(conn, "quantity_conversion_issues").unwrap_or(false) {
+        conn.query_row("SELECT COUNT(*) FROM quantity_conversion_issues", [], |row| row.get(0))
+            .ok()
+    } else {
+        None
+    };
+
+    Ok(json!({
+        "user_version": user_version,
+        "journal_mode": pragma_text(conn, "PRAGMA journal_mode")?.join(""),
+        "page_size": pragma_text(conn, "PRAGMA page_size")?.join(""),
+        "page_count": pragma_text(conn, "PRAGMA page_count")?.join(""),
+        "freelist_count": pragma_text(conn, "PRAGMA freelist_count")?.join(""),
+        "plugin_migrations": table_json(
+            conn,
+            "_sqlx_migrations",
+            "SELECT version, description, installed_on, success, execution_time FROM _sqlx_migrations ORDER BY version",
+        )?,
+        "app_info": table_json(conn, "app_info", "SELECT * FROM app_info")?,
+        "money_conversion": table_json(conn, "money_conversion_report", "SELECT * FROM money_conversion_report")?,
+        "quantity_conversion_issues": unresolved_quantities,
+        "archive_moves": table_json(conn, "archive_moves", "SELECT * FROM archive_moves")?,
+    }))
+}
+
+fn integrity_json(conn: &Connection, scrubber: Option<&CustomerScrubber>) -> Result<JsonValue, String> {
+    let integrity = pragma_text(conn, "PRAGMA integrity_check")?;
+    let foreign_keys = query_json(conn, "PRAGMA foreign_key_check")?;
+
+    let stock = match check_stock(conn, None) {
+        Ok(report) => serde_json::to_value(&report).unwrap_or(JsonValue::Null),
+        Err(e) => json!({ "error": e }),
+    };
+    let balances = match balance_report(conn, None) {
+        Ok(report) => {
+            let mut value = serde_json::to_value(&report).unwrap_or(JsonValue::Null);
+            if let (Some(scrubber), Some(discrepancies)) = (
+                scrubber,
+                value.get_mut("discrepancies").and_then(JsonValue::as_array_mut),
+            ) {
+                for discrepancy in discrepancies {
+                    if let Some(name) = discrepancy.get_mut("customer_name") {
+                        *name = json!(scrubber.scrub(name.as_str().unwrap_or_default()));
+                    }
+                }
+            }
+            value
+        }
+        Err(e) => json!({ "error": e }),
+    };
+
+    Ok(json!({
+        "integrity_check": integrity,
+        "foreign_key_violations": foreign_keys,
+        "stock": stock,
+        "customer_balances": balances,
+    }))
+}
+
+fn row_counts_json(conn: &Connection) -> Result<JsonValue, String> {
+    let mut stmt = conn
+        .prepare("SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name")
+        .map_err(|e| format!("Failed to list tables: {}", e))?;
+    let tables = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("Failed to list tables: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to list tables: {}", e))?;
+
+    let mut counts = BTreeMap::new();
+    for table in tables {
+        let count = conn
+            .query_row(
+                &format!("SELECT COUNT(*) FROM \"{}\"", table.replace('"', "\"\"")),
+                [],
+                |row| row.get::<_, i64>(0),
+            )
+            .map(|count| json!(count))
+            .unwrap_or_else(|e| json!(format!("unreadable: {}", e)));
+        counts.insert(table, count);
+    }
+    Ok(json!(counts))
+}
+
+/// Name, size, modification time and whether the file starts like a SQLite database
+fn files_json(conn: &Connection, dir: &Path, matches: impl Fn(&str) -> bool) -> JsonValue {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return json!({ "directory": dir.display().to_string(), "exists": false });
+    };
+    let mut files: Vec<JsonValue> = entries
+        .flatten()
+        .filter(|entry| matches(&entry.file_name().to_string_lossy()))
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            if !metadata.is_file() {
+                return None;
+            }
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .and_then(|since| {
+                    conn.query_row(
+                        "SELECT datetime(?1, 'unixepoch', 'localtime')",
+                        [since.as_secs() as i64],
+                        |row| row.get::<_, String>(0),
+                    )
+                    .ok()
+                });
+            let mut header = [0u8; 16];
+            let is_sqlite = File::open(entry.path())
+                .and_then(|mut file| std::io::Read::read_exact(&mut file, &mut header))
+                .map(|_| &header == b"SQLite format 3\0")
+                .unwrap_or(false);
+            Some(json!({
+                "name": entry.file_name().to_string_lossy(),
+                "size": metadata.len(),
+                "modified": modified,
+                "sqlite_header": is_sqlite,
+            }))
+        })
+        .collect();
+    files.sort_by_key(|file| file["name"].as_str().unwrap_or_default().to_string());
+    json!({ "directory": dir.display().to_string(), "exists": true, "files": files })
+}
+
+fn backups_json(conn: &Connection, app_data_dir: &Path) -> JsonValue {
+    let archives = match archive_dir() {
+        Ok(dir) => files_json(conn, &dir, |_| true),
+        Err(e) => json!({ "error": e }),
+    };
+    json!({
+        "backups": files_json(conn, &app_data_dir.join("backups"), |_| true),
+        "archives": archives,
+        "data_directory": files_json(conn, app_data_dir, |name| name.starts_with(DB_FILE_NAME)),
+    })
+}
+
+fn config_json(conn: &Connection) -> Result<JsonValue, String> {
+    let log_filter_file = logging::log_dir().and_then(|dir| fs::read_to_string(dir.join("filter.txt")).ok());
+    Ok(json!({
+        "settings": table_json(conn, "settings", "SELECT category, key, value, data_type, updated_at FROM settings ORDER BY category, key")?,
+        "app_settings": table_json(conn, "app_settings", "SELECT * FROM app_settings")?,
+        "api_settings": table_json(conn, "api_settings", "SELECT * FROM api_settings")?,
+        "api_keys": table_json(
+            conn,
+            "api_keys",
+            "SELECT name, can_submit_counts, created_by, created_at, last_used_at, request_count, revoked_at FROM api_keys",
+        )?,
+        "sync_state": table_json(
+            conn,
+            "sync_state",
+            "SELECT node_name, port, enabled, last_round_at FROM sync_state",
+        )?,
+        "sync_peers": table_json(conn, "sync_peers", "SELECT * FROM sync_peers")?,
+        "log_filter_env": std::env::var("ITTEHAD_LOG").ok(),
+        "log_filter_file": log_filter_file,
+    }))
+}
+
+fn recent_logs_text(scrubber: Option<&CustomerScrubber>) -> Result<String, String> {
+    let Some(dir) = logging::log_dir() else {
+        return Ok("Log files are not available\n".to_string());
+    };
+    let mut text = String::new();
+    for line in logging::recent_lines(&dir, RECENT_LOG_RECORDS, None, None)? {
+        let message = line.message.replace('\n', "\n\t");
+        text.push_str(&format!(
+            "{} {:<5} {}: {}\n",
+            line.timestamp, line.level, line.module, message
+        ));
+    }
+    Ok(match scrubber {
+        Some(scrubber) => scrubber.scrub(&text),
+        None => text,
+    })
+}
+
+/// Write the bundle for the database at `db_path` to `bundle_path`
+pub fn write_bundle(
+    db_path: &Path,
+    app_data_dir: &Path,
+    bundle_path: &Path,
+    include_customer_data: bool,
+) -> Result<DiagnosticBundle, String> {
+    let conn = open_connection_at(db_path)?;
+    let scrubber = if include_customer_data {
+        None
+    } else {
+        Some(CustomerScrubber::load(&conn)?)
+    };
+
+    let created_at: String = conn
+        .query_row("SELECT datetime('now', 'localtime')", [], |row| row.get(0))
+        .map_err(|e| format!("Failed to read the clock: {}", e))?;
+
+    if let Some(dir) = bundle_path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let file = File::create(bundle_path).map_err(|e| format!("Failed to create {}: {}", bundle_path.display(), e))?;
+    let mut bundle = BundleWriter {
+        zip: ZipWriter::new(file),
+        files: Vec::new(),
+    };
+
+    bundle.add_json("system.json", &crate::system_info())?;
+    bundle.add("logs/recent.log", recent_logs_text(scrubber.as_ref())?.as_bytes())?;
+    bundle.add("database/schema.sql", schema_sql(&conn)?.as_bytes())?;
+    bundle.add_json("database/migrations.json", &migrations_json(&conn)?)?;
+    bundle.add_json("database/integrity.json", &integrity_json(&conn, scrubber.as_ref())?)?;
+    bundle.add_json("database/row_counts.json", &row_counts_json(&conn)?)?;
+    bundle.add_json("backups.json", &backups_json(&conn, app_data_dir))?;
+    bundle.add_json("config.json", &config_json(&conn)?)?;
+    drop(conn);
+
+    if include_customer_data {
+        let copy = bundle_path.with_extension("db.tmp");
+        let result = crate::consistent_backup(db_path, &copy)
+            .and_then(|_| fs::read(&copy).map_err(|e| format!("Failed to read database copy: {}", e)));
+        let _ = fs::remove_file(&copy);
+        bundle.add(&format!("database/{}", DB_FILE_NAME), &result?)?;
+    }
+
+    let manifest = json!({
+        "app_version": env!("CARGO_PKG_VERSION"),
+        "created_at": created_at,
+        "database": db_path.display().to_string(),
+        "customer_data_included": include_customer_data,
+        "files": bundle.files,
+    });
+    bundle.add_json("manifest.json", &manifest)?;
+
+    let BundleWriter { zip, files } = bundle;
+    zip.finish()
+        .map_err(|e| format!("Failed to finish the bundle: {}", e))?;
+    let size = fs::metadata(bundle_path)
+        .map_err(|e| format!("Failed to read bundle size: {}", e))?
+        .len();
+
+    info!(
+        "[DIAGNOSTICS] Wrote {} ({} files, {} bytes, customer data {})",
+        bundle_path.display(),
+        files.len(),
+        size,
+        if include_customer_data { "included" } else { "excluded" }
+    );
+
+    Ok(DiagnosticBundle {
+        path: bundle_path.to_string_lossy().to_string(),
+        size,
+        files,
+        customer_data_included: include_customer_data,
+    })
+}
+
+/// Build a diagnostic bundle under `<app data>/diagnostics/` for a support request
+#[tauri::command]
+pub async fn export_diagnostic_bundle(include_customer_data: Option<bool>) -> Result<DiagnosticBundle, String> {
+    let app_data_dir = get_app_data_dir()?;
+    let db_path = get_db_path()?;
+    let conn = open_connection_at(&db_path)?;
+    let name: String = conn
+        .query_row(
+            "SELECT strftime('diagnostics-%Y%m%d-%H%M%S.zip', 'now', 'localtime')",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to read the clock: {}", e))?;
+    drop(conn);
+
+    let bundle_path: PathBuf = app_data_dir.join(DIAGNOSTICS_DIR).join(name);
+    write_bundle(
+        &db_path,
+        &app_data_dir,
+        &bundle_path,
+        include_customer_data.unwrap_or(false),
+    )
+}
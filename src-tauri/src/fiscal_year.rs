@@ -0,0 +1,1057 @@
+/*!
+ * FISCAL YEARS AND YEAR-END CLOSE
+ * The live database file is no longer named after a year; years are rows in
+ * `fiscal_years` instead. They run January to December and are added as
+ * the calendar (or the oldest transaction) reaches them.
+ *
+ * Closing a year snapshots every customer and vendor balance and every
+ * product's stock as of its last day, and carries them into the next year
+ * as opening entries: zero-amount marker rows dated the first day of the
+ * new year whose running balance is the carried figure. Balances are still
+ * replayed from the full ledgers and movements, so the markers never move
+ * them. A closed year is locked by the day-close triggers until an admin
+ * reopens it with a reason; re-closing only adds markers for figures that
+ * changed.
+ *
+ * A closed year can be archived to a read-only copy of the database under
+ * `archives/`; its report is then read from the archive, and archive.rs can
+ * move its rows out of the live database.
+ */
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log::{info, warn};
+use rusqlite::{params, Connection, OptionalExtension, Transaction, TransactionBehavior};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::archive::{attach, detach, moved_year, restore_year_in_transaction, MOVE_ALIAS};
+use crate::audit::{self, AuditEvent};
+use crate::customer_balance::{ledger_effect_sql, GUEST_CUSTOMER_ID};
+use crate::database::{
+    current_date, get_app_data_dir, has_column, open_connection, open_read_only, table_exists,
+};
+use crate::journal::sync_journal_in_transaction;
+use crate::money::{paisa_from_row, paisa_sql, sum_paisa_sql, Money};
+use crate::quantity::{format_stock_text, set_movement_bases};
+use crate::session;
+use crate::stock_engine::stock_as_of;
+use crate::vendor_payables::{sync_vendors_in_transaction, vendor_ledger_effect_sql};
+
+/// Opening markers are referenced as OPENING-<year they open>
+pub const OPENING_REFERENCE_PREFIX: &str = "OPENING-";
+
+/// Sort key that replays a date's opening markers before its other rows
+pub const OPENING_FIRST_SQL: &str = "CASE WHEN reference_number LIKE 'OPENING-%' THEN 0 ELSE 1 END";
+
+/// Tables that receive opening markers, with the column that is zero on a marker
+const OPENING_MARKER_TABLES: &[(&str, &str)] = &[
+    ("customer_ledger_entries", "amount"),
+    ("vendor_ledger_entries", "amount"),
+    ("stock_movements", "quantity"),
+];
+
+/// Tables whose oldest date decides the first fiscal year: table, date column
+const DATED_TABLES: &[(&str, &str)] = &[
+    ("invoices", "date"),
+    ("customer_ledger_entries", "date"),
+    ("vendor_ledger_entries", "date"),
+    ("stock_movements", "date"),
+    ("stock_receiving", "received_date"),
+    ("journal_entries", "date"),
+];
+
+/// Archived years are kept in this directory next to the live database
+const ARCHIVE_DIR: &str = "archives";
+
+pub fn archive_dir() -> Result<PathBuf, String> {
+    Ok(get_app_data_dir()?.join(ARCHIVE_DIR))
+}
+
+/// SQL condition matching an opening marker row of a table that receives them
+pub fn opening_marker_sql(table: &str, row: &str) -> Option<String> {
+    OPENING_MARKER_TABLES
+        .iter()
+        .find(|(name, _)| *name == table)
+        .map(|(_, zero_column)| {
+            format!("({row}.reference_number LIKE '{OPENING_REFERENCE_PREFIX}%' AND {row}.{zero_column} IN (0, '0'))")
+        })
+}
+
+/// Create the fiscal year tables and the years transactions have reached
+pub fn ensure_schema(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS fiscal_years (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            start_date TEXT NOT NULL UNIQUE,
+            end_date TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'closed', 'reopened')),
+            closed_by TEXT,
+            closed_at DATETIME,
+            reopened_by TEXT,
+            reopened_at DATETIME,
+            reopen_reason TEXT,
+            archive_path TEXT,
+            archived_at DATETIME,
+            notes TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE TABLE IF NOT EXISTS fiscal_year_balances (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            fiscal_year_id INTEGER NOT NULL,
+            entity_type TEXT NOT NULL CHECK (entity_type IN ('customer', 'vendor', 'product')),
+            entity_id INTEGER NOT NULL,
+            entity_name TEXT NOT NULL,
+            balance REAL NOT NULL DEFAULT 0,
+            stock_base INTEGER,
+            stock_text TEXT,
+            unit_type TEXT,
+            UNIQUE(fiscal_year_id, entity_type, entity_id),
+            FOREIGN KEY (fiscal_year_id) REFERENCES fiscal_years(id)
+        );",
+    )?;
+    ensure_years(conn)
+}
+
+/// Add a calendar year for every year from the oldest transaction to today
+fn ensure_years(conn: &Connection) -> rusqlite::Result<()> {
+    let today = current_date(conn)?;
+    let current_year: i64 = today[..4].parse().unwrap_or(0);
+
+    let mut first_year = current_year;
+    for (table, column) in DATED_TABLES {
+        if !table_exists(conn, table)? || !has_column(conn, table, column)? {
+            continue;
+        }
+        let oldest: Option<i64> = conn.query_row(
+            &format!(
+                "SELECT MIN(CAST(substr({column}, 1, 4) AS INTEGER)) FROM {table}
+                 WHERE {column} GLOB '[0-9][0-9][0-9][0-9]-*'"
+            ),
+            [],
+            |row| row.get(0),
+        )?;
+        if let Some(year) = oldest.filter(|year| *year > 0) {
+            first_year = first_year.min(year);
+        }
+    }
+
+    for year in first_year..=current_year {
+        conn.execute(
+            "INSERT OR IGNORE INTO fiscal_years (name, start_date, end_date) VALUES (?1, ?2, ?3)",
+            params![
+                format!("FY{}", year),
+                format!("{}-01-01", year),
+                format!("{}-12-31", year)
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+pub struct FiscalYear {
+    pub id: i64,
+    pub name: String,
+    pub start_date: String,
+    pub end_date: String,
+    /// "open", "closed" or "reopened"
+    pub status: String,
+    pub closed_by: Option<String>,
+    pub closed_at: Option<String>,
+    pub reopened_by: Option<String>,
+    pub reopened_at: Option<String>,
+    pub reopen_reason: Option<String>,
+    pub archive_path: Option<String>,
+    pub archived_at: Option<String>,
+    pub notes: Option<String>,
+}
+
+/// A customer or vendor balance, or a product's stock, at the end of a year
+#[derive(Debug, Clone, Serialize)]
+pub struct FiscalYearBalance {
+    /// "customer", "vendor" or "product"
+    pub entity_type: String,
+    pub entity_id: i64,
+    pub entity_name: String,
+    /// Receivable for customers, payable for vendors, zero for products
+    pub balance: Money,
+    pub stock_base: Option<i64>,
+    pub stock_text: Option<String>,
+    pub unit_type: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FiscalYearCloseResult {
+    pub fiscal_year: FiscalYear,
+    pub customers_carried: usize,
+    pub vendors_carried: usize,
+    pub products_carried: usize,
+    /// Opening marker rows written into the next year
+    pub opening_entries_posted: usize,
+}
+
+/// Activity on one journal account during a year
+#[derive(Debug, Serialize)]
+pub struct FiscalYearAccountActivity {
+    pub code: String,
+    pub name: String,
+    pub account_type: String,
+    pub debit: Money,
+    pub credit: Money,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FiscalYearReport {
+    pub fiscal_year: FiscalYear,
+    /// "archive" when read from the year's read-only archive, else "live"
+    pub source: String,
+    pub accounts: Vec<FiscalYearAccountActivity>,
+    pub total_income: Money,
+    pub total_expenses: Money,
+    pub net_profit: Money,
+    /// Closing balances: the stored snapshot of a closed year, computed for an open one
+    pub balances: Vec<FiscalYearBalance>,
+}
+
+fn load_year(conn: &Connection, fiscal_year_id: i64) -> Result<FiscalYear, String> {
+    conn.query_row(
+        "SELECT id, name, start_date, end_date, status, closed_by, closed_at, reopened_by, reopened_at,
+                reopen_reason, archive_path, archived_at, notes
+         FROM fiscal_years WHERE id = ?1",
+        [fiscal_year_id],
+        |row| {
+            Ok(FiscalYear {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                start_date: row.get(2)?,
+                end_date: row.get(3)?,
+                status: row.get(4)?,
+                closed_by: row.get(5)?,
+                closed_at: row.get(6)?,
+                reopened_by: row.get(7)?,
+                reopened_at: row.get(8)?,
+                reopen_reason: row.get(9)?,
+                archive_path: row.get(10)?,
+                archived_at: row.get(11)?,
+                notes: row.get(12)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| format!("Failed to load fiscal year: {}", e))?
+    .ok_or_else(|| format!("Fiscal year {} not found", fiscal_year_id))
+}
+
+/// Every fiscal year, oldest first
+pub fn fiscal_years(conn: &Connection) -> Result<Vec<FiscalYear>, String> {
+    let ids: Vec<i64> = {
+        let mut stmt = conn
+            .prepare("SELECT id FROM fiscal_years ORDER BY start_date")
+            .map_err(|e| format!("Failed to query fiscal years: {}", e))?;
+        let rows = stmt
+            .query_map([], |row| row.get(0))
+            .map_err(|e| format!("Failed to read fiscal years: {}", e))?;
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read fiscal year: {}", e))?
+    };
+    ids.into_iter().map(|id| load_year(conn, id)).collect()
+}
+
+/// Customer and vendor balances and product stock at the end of `date`
+fn balances_as_of(conn: &Connection, date: &str) -> Result<Vec<FiscalYearBalance>, String> {
+    let mut balances = Vec::new();
+
+    for (entity_type, ledger, party, parties, effect) in [
+        (
+            "customer",
+            "customer_ledger_entries",
+            "customer_id",
+            "customers",
+            ledger_effect_sql as fn(&str) -> String,
+        ),
+        (
+            "vendor",
+            "vendor_ledger_entries",
+            "vendor_id",
+            "vendors",
+            vendor_ledger_effect_sql,
+        ),
+    ] {
+        if !table_exists(conn, ledger).map_err(|e| format!("Failed to inspect schema: {}", e))? {
+            continue;
+        }
+        let amount = paisa_sql(conn, ledger, "amount").map_err(|e| format!("Failed to inspect schema: {}", e))?;
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT p.id, p.name, COALESCE(t.balance, 0)
+                 FROM {parties} p
+                 LEFT JOIN (SELECT {party}, {sum} AS balance FROM {ledger}
+                            WHERE substr(date, 1, 10) <= ?1 GROUP BY {party}) t ON t.{party} = p.id
+                 WHERE p.id != ?2
+                 ORDER BY p.id",
+                sum = sum_paisa_sql(&effect(&amount)),
+            ))
+            .map_err(|e| format!("Failed to query {} balances: {}", entity_type, e))?;
+        let rows = stmt
+            .query_map(params![date, GUEST_CUSTOMER_ID], |row| {
+                Ok(FiscalYearBalance {
+                    entity_type: entity_type.to_string(),
+                    entity_id: row.get(0)?,
+                    entity_name: row.get(1)?,
+                    balance: Money::from_paisa(row.get(2)?),
+                    stock_base: None,
+                    stock_text: None,
+                    unit_type: None,
+                })
+            })
+            .map_err(|e| format!("Failed to read {} balances: {}", entity_type, e))?;
+        for row in rows {
+            balances.push(row.map_err(|e| format!("Failed to read {} balance: {}", entity_type, e))?);
+        }
+    }
+
+    let (stocks, unreadable) = stock_as_of(conn, date)?;
+    if !unreadable.is_empty() {
+        let names: Vec<&str> = unreadable.iter().map(|issue| issue.product_name.as_str()).collect();
+        return Err(format!(
+            "Stock movements of {} cannot be read; fix them in the stock consistency check first",
+            names.join(", ")
+        ));
+    }
+    for stock in stocks {
+        balances.push(FiscalYearBalance {
+            entity_type: "product".to_string(),
+            entity_id: stock.product_id,
+            entity_name: stock.product_name,
+            balance: Money::ZERO,
+            stock_base: Some(stock.base),
+            stock_text: Some(format_stock_text(stock.base, &stock.unit_type)),
+            unit_type: Some(stock.unit_type),
+        });
+    }
+    Ok(balances)
+}
+
+fn stored_balances(conn: &Connection, fiscal_year_id: i64) -> Result<Vec<FiscalYearBalance>, String> {
+    let balance =
+        paisa_sql(conn, "fiscal_year_balances", "balance").map_err(|e| format!("Failed to inspect schema: {}", e))?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT entity_type, entity_id, entity_name, {balance}, stock_base, stock_text, unit_type
+             FROM fiscal_year_balances WHERE fiscal_year_id = ?1
+             ORDER BY CASE entity_type WHEN 'customer' THEN 0 WHEN 'vendor' THEN 1 ELSE 2 END, entity_id"
+        ))
+        .map_err(|e| format!("Failed to query year-end balances: {}", e))?;
+    let rows = stmt
+        .query_map([fiscal_year_id], |row| {
+            Ok(FiscalYearBalance {
+                entity_type: row.get(0)?,
+                entity_id: row.get(1)?,
+                entity_name: row.get(2)?,
+                balance: paisa_from_row(row, 3)?,
+                stock_base: row.get(4)?,
+                stock_text: row.get(5)?,
+                unit_type: row.get(6)?,
+            })
+        })
+        .map_err(|e| format!("Failed to read year-end balances: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read year-end balance: {}", e))
+}
+
+/// Write the zero-amount marker that opens `next_year` with a carried balance
+fn post_opening_marker(
+    tx: &Transaction,
+    balance: &FiscalYearBalance,
+    next_year: &FiscalYear,
+    closed_year: &str,
+) -> Result<(), String> {
+    let reference = format!("{}{}", OPENING_REFERENCE_PREFIX, next_year.name);
+    let description = format!("Opening balance brought forward from {}", closed_year);
+    let carried = balance.balance.to_rupees();
+
+    let inserted = match balance.entity_type.as_str() {
+        "customer" => {
+            let opening_flag = if has_column(tx, "customer_ledger_entries", "is_opening_balance")
+                .map_err(|e| format!("Failed to read ledger columns: {}", e))?
+            {
+                ", is_opening_balance"
+            } else {
+                ""
+            };
+            tx.execute(
+                &format!(
+                    "INSERT INTO customer_ledger_entries (
+                        customer_id, customer_name, entry_type, transaction_type, amount, balance_before,
+                        balance_after, description, reference_type, reference_number, date, time, notes,
+                        created_by{opening_flag}
+                    ) VALUES (?1, ?2, 'adjustment', 'adjustment', 0, ?3, ?3, ?4, 'adjustment', ?5, ?6,
+                        '00:00:00', 'Year-end close', 'system'{})",
+                    if opening_flag.is_empty() { "" } else { ", 1" }
+                ),
+                params![
+                    balance.entity_id,
+                    balance.entity_name,
+                    carried,
+                    description,
+                    reference,
+                    next_year.start_date
+                ],
+            )
+        }
+        "vendor" => tx.execute(
+            "INSERT INTO vendor_ledger_entries (
+                vendor_id, vendor_name, entry_type, transaction_type, amount, balance_before, balance_after,
+                description, reference_type, reference_number, date, time, notes, created_by
+            ) VALUES (?1, ?2, 'adjustment', 'adjustment', 0, ?3, ?3, ?4, 'adjustment', ?5, ?6,
+                '00:00:00', 'Year-end close', 'system')",
+            params![
+                balance.entity_id,
+                balance.entity_name,
+                carried,
+                description,
+                reference,
+                next_year.start_date
+            ],
+        ),
+        _ => {
+            let stock_text = balance.stock_text.clone().unwrap_or_default();
+            tx.execute(
+                "INSERT INTO stock_movements (
+                    product_id, product_name, movement_type, transaction_type, quantity, unit,
+                    previous_stock, stock_before, stock_after, new_stock,
+                    reason, reference_type, reference_number, notes, date, time, created_by
+                ) SELECT id, name, 'adjustment', 'adjustment', '0', COALESCE(unit, 'kg'), ?2, ?2, ?2, ?2,
+                    ?3, 'adjustment', ?4, 'Year-end close', ?5, '00:00:00', 'system'
+                  FROM products WHERE id = ?1",
+                params![
+                    balance.entity_id,
+                    stock_text,
+                    description,
+                    reference,
+                    next_year.start_date
+                ],
+            )
+            .and_then(|inserted| {
+                let base = balance.stock_base.unwrap_or(0);
+                set_movement_bases(tx, tx.last_insert_rowid(), 0, base, base)?;
+                Ok(inserted)
+            })
+        }
+    };
+    inserted.map_err(|e| format!("Failed to carry forward {}: {}", balance.entity_name, e))?;
+    Ok(())
+}
+
+/// Close a year: snapshot its closing balances, carry them into the next
+/// year as opening entries and lock the year
+pub fn close_fiscal_year_in_transaction(
+    tx: &Transaction,
+    fiscal_year_id: i64,
+    closed_by: &str,
+    notes: Option<&str>,
+) -> Result<FiscalYearCloseResult, String> {
+    ensure_years(tx).map_err(|e| format!("Failed to create fiscal years: {}", e))?;
+    let year = load_year(tx, fiscal_year_id)?;
+    if year.status == "closed" {
+        return Err(format!("{} is already closed", year.name));
+    }
+    let today = current_date(tx).map_err(|e| format!("Failed to read date: {}", e))?;
+    if year.end_date >= today {
+        return Err(format!(
+            "{} runs until {} and cannot be closed yet",
+            year.name, year.end_date
+        ));
+    }
+    let earlier_open: Option<String> = tx
+        .query_row(
+            "SELECT name FROM fiscal_years WHERE start_date < ?1 AND status != 'closed' ORDER BY start_date LIMIT 1",
+            [&year.start_date],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to check earlier fiscal years: {}", e))?;
+    if let Some(earlier) = earlier_open {
+        return Err(format!("{} must be closed before {}", earlier, year.name));
+    }
+    let next_year_id: i64 = tx
+        .query_row(
+            "SELECT id FROM fiscal_years WHERE start_date = date(?1, '+1 day')",
+            [&year.end_date],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to find the year after {}: {}", year.name, e))?;
+    let next_year = load_year(tx, next_year_id)?;
+    let closed_by = match closed_by.trim() {
+        "" => "system",
+        user => user,
+    };
+
+    // The report of a closed (possibly archived) year is read from the journal,
+    // and vendor balances from the payables ledger
+    sync_vendors_in_transaction(tx, None)?;
+    sync_journal_in_transaction(tx)?;
+
+    let previous: HashMap<(String, i64), (Money, Option<i64>)> = stored_balances(tx, year.id)?
+        .into_iter()
+        .map(|balance| {
+            (
+                (balance.entity_type, balance.entity_id),
+                (balance.balance, balance.stock_base),
+            )
+        })
+        .collect();
+    tx.execute("DELETE FROM fiscal_year_balances WHERE fiscal_year_id = ?1", [year.id])
+        .map_err(|e| format!("Failed to clear year-end balances: {}", e))?;
+
+    let mut result = FiscalYearCloseResult {
+        fiscal_year: load_year(tx, year.id)?,
+        customers_carried: 0,
+        vendors_carried: 0,
+        products_carried: 0,
+        opening_entries_posted: 0,
+    };
+    for balance in balances_as_of(tx, &year.end_date)? {
+        let figure = (balance.balance, balance.stock_base);
+        let carried = !balance.balance.is_zero() || balance.stock_base.is_some_and(|base| base != 0);
+        if carried {
+            tx.execute(
+                "INSERT INTO fiscal_year_balances (
+                    fiscal_year_id, entity_type, entity_id, entity_name, balance, stock_base, stock_text, unit_type
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    year.id,
+                    balance.entity_type,
+                    balance.entity_id,
+                    balance.entity_name,
+                    balance.balance.to_rupees(),
+                    balance.stock_base,
+                    balance.stock_text,
+                    balance.unit_type
+                ],
+            )
+            .map_err(|e| format!("Failed to record year-end balance of {}: {}", balance.entity_name, e))?;
+            match balance.entity_type.as_str() {
+                "customer" => result.customers_carried += 1,
+                "vendor" => result.vendors_carried += 1,
+                _ => result.products_carried += 1,
+            }
+        }
+
+        // Markers only where the carried figure is new or changed since an earlier close
+        let changed = match previous.get(&(balance.entity_type.clone(), balance.entity_id)) {
+            Some(earlier) => *earlier != figure,
+            None => carried,
+        };
+        if changed {
+            post_opening_marker(tx, &balance, &next_year, &year.name)?;
+            result.opening_entries_posted += 1;
+        }
+    }
+
+    tx.execute(
+        "UPDATE fiscal_years SET status = 'closed', closed_by = ?2, closed_at = CURRENT_TIMESTAMP,
+            notes = COALESCE(?3, notes)
+         WHERE id = ?1",
+        params![year.id, closed_by, notes],
+    )
+    .map_err(|e| format!("Failed to close {}: {}", year.name, e))?;
+
+    result.fiscal_year = load_year(tx, year.id)?;
+    Ok(result)
+}
+
+/// Reopen a closed year so its transactions can change again. Admins only,
+/// with a reason; later closed years must be reopened first. The caller
+/// removes the archive, which is stale from then on.
+pub fn reopen_fiscal_year_in_transaction(
+    tx: &Transaction,
+    fiscal_year_id: i64,
+    reason: &str,
+    reopened_by: &str,
+) -> Result<FiscalYear, String> {
+    let reason = reason.trim();
+    if reason.is_empty() {
+        return Err("A reason is required to reopen a closed fiscal year".to_string());
+    }
+    session::require_admin(tx, reopened_by, "reopen a closed fiscal year")?;
+    let year = load_year(tx, fiscal_year_id)?;
+    if year.status != "closed" {
+        return Err(format!("{} is not closed", year.name));
+    }
+    let later_closed: Option<String> = tx
+        .query_row(
+            "SELECT name FROM fiscal_years WHERE start_date > ?1 AND status = 'closed' ORDER BY start_date DESC LIMIT 1",
+            [&year.start_date],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to check later fiscal years: {}", e))?;
+    if let Some(later) = later_closed {
+        return Err(format!("{} must be reopened before {}", later, year.name));
+    }
+
+    tx.execute(
+        "UPDATE fiscal_years SET status = 'reopened', reopened_by = ?2, reopened_at = CURRENT_TIMESTAMP,
+            reopen_reason = ?3, archive_path = NULL, archived_at = NULL
+         WHERE id = ?1",
+        params![year.id, reopened_by.trim(), reason],
+    )
+    .map_err(|e| format!("Failed to reopen {}: {}", year.name, e))?;
+
+    load_year(tx, year.id)
+}
+
+/// Delete an archive file (archives are read-only on disk)
+#[allow(clippy::permissions_set_readonly_false)]
+fn remove_archive(path: &Path) -> Result<(), String> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let mut permissions = fs::metadata(path)
+        .map_err(|e| format!("Failed to read archive {}: {}", path.display(), e))?
+        .permissions();
+    permissions.set_readonly(false);
+    fs::set_permissions(path, permissions)
+        .map_err(|e| format!("Failed to unlock archive {}: {}", path.display(), e))?;
+    fs::remove_file(path).map_err(|e| format!("Failed to remove archive {}: {}", path.display(), e))
+}
+
+/// Copy the database into a read-only archive of a closed year and record its path
+pub fn archive_fiscal_year_to(
+    conn: &Connection,
+    fiscal_year_id: i64,
+    archive_dir: &Path,
+) -> Result<FiscalYear, String> {
+    let year = load_year(conn, fiscal_year_id)?;
+    if year.status != "closed" {
+        return Err(format!("{} must be closed before it can be archived", year.name));
+    }
+    if moved_year(conn, year.id)?.is_some() {
+        return Err(format!(
+            "{} has been moved to its archive; the archive holds rows the live database no longer has",
+            year.name
+        ));
+    }
+
+    fs::create_dir_all(archive_dir).map_err(|e| format!("Failed to create archive directory: {}", e))?;
+    let path: PathBuf = archive_dir.join(format!("store-{}.db", year.name));
+    remove_archive(&path)?;
+
+    conn.execute("VACUUM INTO ?1", [path.to_string_lossy()])
+        .map_err(|e| format!("Failed to write archive of {}: {}", year.name, e))?;
+    let mut permissions = fs::metadata(&path)
+        .map_err(|e| format!("Failed to read archive {}: {}", path.display(), e))?
+        .permissions();
+    permissions.set_readonly(true);
+    fs::set_permissions(&path, permissions).map_err(|e| format!("Failed to make archive read-only: {}", e))?;
+
+    conn.execute(
+        "UPDATE fiscal_years SET archive_path = ?2, archived_at = CURRENT_TIMESTAMP WHERE id = ?1",
+        params![year.id, path.to_string_lossy()],
+    )
+    .map_err(|e| format!("Failed to record archive of {}: {}", year.name, e))?;
+
+    load_year(conn, year.id)
+}
+
+/// Journal activity and closing balances of a year, from whichever database `conn` is
+pub fn fiscal_year_report(conn: &Connection, fiscal_year_id: i64, source: &str) -> Result<FiscalYearReport, String> {
+    let fiscal_year = load_year(conn, fiscal_year_id)?;
+
+    let paisa = |column| {
+        paisa_sql(conn, "journal_lines", column).map_err(|e| format!("Failed to inspect schema: {}", e))
+    };
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT a.code, a.name, a.account_type, {debit}, {credit}
+             FROM journal_lines l
+             JOIN journal_entries e ON e.id = l.journal_entry_id
+             JOIN gl_accounts a ON a.id = l.account_id
+             WHERE e.date BETWEEN ?1 AND ?2
+             GROUP BY a.id
+             ORDER BY a.code",
+            debit = sum_paisa_sql(&paisa("l.debit")?),
+            credit = sum_paisa_sql(&paisa("l.credit")?),
+        ))
+        .map_err(|e| format!("Failed to query year activity: {}", e))?;
+    let rows = stmt
+        .query_map(params![fiscal_year.start_date, fiscal_year.end_date], |row| {
+            Ok(FiscalYearAccountActivity {
+                code: row.get(0)?,
+                name: row.get(1)?,
+                account_type: row.get(2)?,
+                debit: Money::from_paisa(row.get(3)?),
+                credit: Money::from_paisa(row.get(4)?),
+            })
+        })
+        .map_err(|e| format!("Failed to read year activity: {}", e))?;
+    let accounts: Vec<FiscalYearAccountActivity> = rows
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read year activity line: {}", e))?;
+
+    let total_income: Money = accounts
+        .iter()
+        .filter(|account| account.account_type == "income")
+        .map(|account| account.credit - account.debit)
+        .sum();
+    let total_expenses: Money = accounts
+        .iter()
+        .filter(|account| account.account_type == "expense")
+        .map(|account| account.debit - account.credit)
+        .sum();
+
+    let balances = if fiscal_year.status == "closed" {
+        stored_balances(conn, fiscal_year.id)?
+    } else {
+        let today = current_date(conn).map_err(|e| format!("Failed to read date: {}", e))?;
+        balances_as_of(conn, fiscal_year.end_date.as_str().min(today.as_str()))?
+            .into_iter()
+            .filter(|balance| !balance.balance.is_zero() || balance.stock_base.is_some_and(|base| base != 0))
+            .collect()
+    };
+
+    Ok(FiscalYearReport {
+        fiscal_year,
+        source: source.to_string(),
+        accounts,
+        total_income,
+        total_expenses,
+        net_profit: total_income - total_expenses,
+        balances,
+    })
+}
+
+/// List fiscal years, adding any the calendar has reached
+#[tauri::command]
+pub async fn get_fiscal_years() -> Result<Vec<FiscalYear>, String> {
+    let conn = open_connection()?;
+    ensure_years(&conn).map_err(|e| format!("Failed to create fiscal years: {}", e))?;
+    fiscal_years(&conn)
+}
+
+/// Close a fiscal year, carrying balances into the next one; optionally
+/// archive it to a read-only database afterwards
+#[tauri::command]
+pub async fn close_fiscal_year(
+    fiscal_year_id: i64,
+    closed_by: String,
+    notes: Option<String>,
+    archive: bool,
+) -> Result<FiscalYearCloseResult, String> {
+    let mut conn = open_connection()?;
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let notes = notes.as_deref().map(str::trim).filter(|notes| !notes.is_empty());
+    let mut result = close_fiscal_year_in_transaction(&tx, fiscal_year_id, &closed_by, notes)?;
+    audit::record(
+        &tx,
+        &AuditEvent::new("close", "fiscal_year")
+            .entity(fiscal_year_id)
+            .by(&closed_by)
+            .after(json!(&result)),
+    )?;
+
+    tx.commit()
+        .map_err(|e| format!("Failed to commit year-end close: {}", e))?;
+
+    info!(
+        "[FISCAL-YEAR] {} closed: {} customers, {} vendors and {} products carried forward ({} opening entries)",
+        result.fiscal_year.name,
+        result.customers_carried,
+        result.vendors_carried,
+        result.products_carried,
+        result.opening_entries_posted
+    );
+
+    if archive {
+        result.fiscal_year = archive_fiscal_year_to(&conn, fiscal_year_id, &archive_dir()?)?;
+        info!(
+            "[FISCAL-YEAR] {} archived to {}",
+            result.fiscal_year.name,
+            result.fiscal_year.archive_path.as_deref().unwrap_or("")
+        );
+    }
+    Ok(result)
+}
+
+/// Archive a closed fiscal year to a read-only database (replacing an older archive)
+#[tauri::command]
+pub async fn archive_fiscal_year(fiscal_year_id: i64) -> Result<FiscalYear, String> {
+    let conn = open_connection()?;
+    let year = archive_fiscal_year_to(&conn, fiscal_year_id, &archive_dir()?)?;
+    audit::record(
+        &conn,
+        &AuditEvent::new("archive", "fiscal_year")
+            .entity(fiscal_year_id)
+            .after(json!(&year)),
+    )?;
+
+    info!(
+        "[FISCAL-YEAR] {} archived to {}",
+        year.name,
+        year.archive_path.as_deref().unwrap_or("")
+    );
+    Ok(year)
+}
+
+/// Reopen a closed fiscal year; requires an admin and a reason
+#[tauri::command]
+pub async fn reopen_fiscal_year(
+    fiscal_year_id: i64,
+    reason: String,
+    reopened_by: String,
+) -> Result<FiscalYear, String> {
+    let mut conn = open_connection()?;
+    let archive_path = load_year(&conn, fiscal_year_id)?.archive_path;
+
+    // A year moved out of the live database gets its rows back first
+    let moved = moved_year(&conn, fiscal_year_id)?;
+    if let Some(moved) = &moved {
+        attach(&conn, &moved.archive_path, MOVE_ALIAS)?;
+    }
+    let result = (|| {
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+        if moved.is_some() {
+            // Recorded as the reopen below rather than row by row
+            let actor = audit::act_as(&tx, &reopened_by, false)?;
+            restore_year_in_transaction(&tx, fiscal_year_id, MOVE_ALIAS)?;
+            audit::restore_actor(&tx, actor)?;
+        }
+        let year = reopen_fiscal_year_in_transaction(&tx, fiscal_year_id, &reason, &reopened_by)?;
+        audit::record(
+            &tx,
+            &AuditEvent::new("reopen", "fiscal_year")
+                .entity(fiscal_year_id)
+                .by(&reopened_by)
+                .after(json!({ "reason": reason.trim(), "restored_from_archive": moved.is_some(), "fiscal_year": &year })),
+        )?;
+        tx.commit()
+            .map_err(|e| format!("Failed to commit year reopen: {}", e))?;
+        Ok::<_, String>(year)
+    })();
+    if moved.is_some() {
+        detach(&conn, MOVE_ALIAS)?;
+    }
+    let year = result?;
+
+    if let Some(archive) = archive_path {
+        remove_archive(Path::new(&archive))?;
+    }
+
+    warn!(
+        "[FISCAL-YEAR] {} reopened by {}: {}",
+        year.name,
+        reopened_by.trim(),
+        reason.trim()
+    );
+    Ok(year)
+}
+
+/// Income, expenses and closing balances of a fiscal year. Archived years
+/// are read from their read-only archive; others from the live database as
+/// the journal stands, without taking a write lock.
+#[tauri::command]
+pub async fn get_fiscal_year_report(fiscal_year_id: i64) -> Result<FiscalYearReport, String> {
+    let mut conn = open_connection()?;
+    let year = load_year(&conn, fiscal_year_id)?;
+
+    if let Some(archive) = year.archive_path.as_deref().filter(|path| Path::new(path).exists()) {
+        let archive_conn = open_read_only(Path::new(archive))?;
+        return fiscal_year_report(&archive_conn, fiscal_year_id, "archive");
+    }
+
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Deferred)
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let report = fiscal_year_report(&tx, fiscal_year_id, "live")?;
+    tx.finish()
+        .map_err(|e| format!("Failed to finish reading the fiscal year: {}", e))?;
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::customer_balance::customer_statement;
+    use crate::stock_engine::check_stock;
+
+    /// A customer who owes Rs 1,000, a vendor owed Rs 5,000 and 40 bags of
+    /// cement at the end of 2025, with some 2026 activity on top
+    fn database() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE customers (id INTEGER PRIMARY KEY, name TEXT NOT NULL, balance REAL NOT NULL DEFAULT 0);
+            CREATE TABLE customer_ledger_entries (
+                id INTEGER PRIMARY KEY, customer_id INTEGER, customer_name TEXT, entry_type TEXT,
+                transaction_type TEXT, amount REAL, balance_before REAL, balance_after REAL, description TEXT,
+                reference_type TEXT, reference_id INTEGER, reference_number TEXT, invoice_id INTEGER, date TEXT,
+                time TEXT, notes TEXT, created_by TEXT
+            );
+            CREATE TABLE vendors (
+                id INTEGER PRIMARY KEY, name TEXT NOT NULL, balance REAL NOT NULL DEFAULT 0, updated_at TEXT
+            );
+            CREATE TABLE vendor_ledger_entries (
+                id INTEGER PRIMARY KEY, vendor_id INTEGER, vendor_name TEXT, entry_type TEXT,
+                transaction_type TEXT, amount REAL, balance_before REAL, balance_after REAL, description TEXT,
+                reference_type TEXT, reference_id INTEGER, reference_number TEXT, payment_method TEXT,
+                date TEXT, time TEXT, notes TEXT, created_by TEXT
+            );
+            CREATE TABLE stock_receiving (
+                id INTEGER PRIMARY KEY, receiving_number TEXT, vendor_id INTEGER, vendor_name TEXT,
+                received_date TEXT, received_time TEXT, status TEXT, total_cost REAL, grand_total REAL,
+                payment_status TEXT, updated_at TEXT
+            );
+            CREATE TABLE vendor_payments (
+                id INTEGER PRIMARY KEY, payment_number TEXT, vendor_id INTEGER, vendor_name TEXT,
+                receiving_id INTEGER, amount REAL, payment_method TEXT, payment_channel_id INTEGER,
+                status TEXT, date TEXT, time TEXT
+            );
+            CREATE TABLE products (
+                id INTEGER PRIMARY KEY, name TEXT NOT NULL, unit_type TEXT, unit TEXT,
+                current_stock TEXT NOT NULL DEFAULT '0', stock_quantity REAL
+            );
+            CREATE TABLE stock_movements (
+                id INTEGER PRIMARY KEY, product_id INTEGER, product_name TEXT, movement_type TEXT,
+                transaction_type TEXT, quantity TEXT, unit TEXT, previous_stock TEXT, stock_before TEXT,
+                stock_after TEXT, new_stock TEXT, reason TEXT, reference_type TEXT, reference_id INTEGER,
+                reference_number TEXT, notes TEXT, date TEXT, time TEXT, created_by TEXT
+            );
+            INSERT INTO customers (id, name, balance) VALUES (1, 'Ali', 1200);
+            INSERT INTO customer_ledger_entries (customer_id, customer_name, entry_type, transaction_type, amount,
+                description, date, time)
+            VALUES (1, 'Ali', 'debit', 'invoice', 1500, 'Invoice', '2025-03-01', '10:00:00'),
+                   (1, 'Ali', 'credit', 'payment', 500, 'Payment', '2025-04-01', '10:00:00'),
+                   (1, 'Ali', 'debit', 'invoice', 200, 'Invoice', '2026-02-01', '10:00:00');
+            INSERT INTO vendors (id, name) VALUES (1, 'Ittefaq Steel');
+            INSERT INTO stock_receiving (id, receiving_number, vendor_id, vendor_name, received_date, received_time,
+                status, total_cost, grand_total, payment_status)
+            VALUES (1, 'SR-0001', 1, 'Ittefaq Steel', '2025-05-01', '09:00:00', 'completed', 5000, 5000, 'pending');
+            INSERT INTO products (id, name, unit_type, unit, current_stock, stock_quantity)
+                VALUES (1, 'Cement', 'bag', 'bag', '45', 45);
+            INSERT INTO stock_movements (product_id, product_name, movement_type, quantity, stock_before, stock_after,
+                date, time)
+            VALUES (1, 'Cement', 'in', '50', '0', '50', '2025-05-01', '09:00:00'),
+                   (1, 'Cement', 'out', '10', '50', '40', '2025-06-01', '10:00:00'),
+                   (1, 'Cement', 'in', '5', '40', '45', '2026-03-01', '10:00:00');",
+        )
+        .unwrap();
+        crate::vendor_payables::ensure_schema(&conn).unwrap();
+        crate::journal::ensure_schema(&conn).unwrap();
+        ensure_schema(&conn).unwrap();
+        conn
+    }
+
+    fn year_id(conn: &Connection, name: &str) -> i64 {
+        fiscal_years(conn)
+            .unwrap()
+            .into_iter()
+            .find(|year| year.name == name)
+            .unwrap()
+            .id
+    }
+
+    /// Carried figure, amount and date of each opening marker, by table
+    fn markers(conn: &Connection, sql: &str) -> Vec<(String, String, String)> {
+        let mut stmt = conn.prepare(sql).unwrap();
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap();
+        rows.map(Result::unwrap).collect()
+    }
+
+    fn customer_markers(conn: &Connection) -> Vec<(String, String, String)> {
+        markers(
+            conn,
+            "SELECT CAST(balance_after AS TEXT), CAST(amount AS TEXT), date || ' ' || time
+             FROM customer_ledger_entries WHERE reference_number = 'OPENING-FY2026' ORDER BY id",
+        )
+    }
+
+    #[test]
+    fn closing_a_year_carries_balances_and_stock_forward_as_opening_markers() {
+        let mut conn = database();
+        let fy2025 = year_id(&conn, "FY2025");
+
+        let tx = conn.transaction().unwrap();
+        assert!(
+            close_fiscal_year_in_transaction(&tx, fiscal_years(&tx).unwrap().last().unwrap().id, "sara", None)
+                .unwrap_err()
+                .contains("cannot be closed yet")
+        );
+        let result = close_fiscal_year_in_transaction(&tx, fy2025, "sara", None).unwrap();
+        tx.commit().unwrap();
+        assert_eq!(result.fiscal_year.status, "closed");
+        assert_eq!(
+            (
+                result.customers_carried,
+                result.vendors_carried,
+                result.products_carried,
+                result.opening_entries_posted
+            ),
+            (1, 1, 1, 3)
+        );
+
+        let opening = (
+            "1000.0".to_string(),
+            "0.0".to_string(),
+            "2026-01-01 00:00:00".to_string(),
+        );
+        assert_eq!(customer_markers(&conn), [opening]);
+        assert_eq!(
+            markers(
+                &conn,
+                "SELECT CAST(balance_after AS TEXT), CAST(amount AS TEXT), date || ' ' || time
+                 FROM vendor_ledger_entries WHERE reference_number = 'OPENING-FY2026'",
+            ),
+            [(
+                "5000.0".to_string(),
+                "0.0".to_string(),
+                "2026-01-01 00:00:00".to_string()
+            )]
+        );
+        assert_eq!(
+            markers(
+                &conn,
+                "SELECT stock_after, quantity, date || ' ' || time
+                 FROM stock_movements WHERE reference_number = 'OPENING-FY2026'",
+            ),
+            [("40".to_string(), "0".to_string(), "2026-01-01 00:00:00".to_string())]
+        );
+
+        // The markers carry figures without moving them
+        let statement = customer_statement(&conn, 1, Some("2026-01-01"), None).unwrap();
+        assert_eq!(statement.opening_balance, Money::from_paisa(100_000));
+        assert_eq!(statement.closing_balance, Money::from_paisa(120_000));
+        assert!(check_stock(&conn, None).unwrap().divergent.is_empty());
+
+        let tx = conn.transaction().unwrap();
+        assert!(close_fiscal_year_in_transaction(&tx, fy2025, "sara", None)
+            .unwrap_err()
+            .contains("already closed"));
+
+        // Re-closing after a late payment adds a marker for the customer alone
+        tx.execute_batch(
+            "UPDATE fiscal_years SET status = 'reopened' WHERE name = 'FY2025';
+            INSERT INTO customer_ledger_entries (customer_id, customer_name, entry_type, transaction_type, amount,
+                description, date, time)
+            VALUES (1, 'Ali', 'credit', 'payment', 200, 'Late payment', '2025-12-31', '16:00:00');",
+        )
+        .unwrap();
+        let result = close_fiscal_year_in_transaction(&tx, fy2025, "sara", None).unwrap();
+        tx.commit().unwrap();
+        assert_eq!(result.opening_entries_posted, 1);
+        assert_eq!(customer_markers(&conn).last().unwrap().0, "800.0");
+        let statement = customer_statement(&conn, 1, Some("2026-01-01"), None).unwrap();
+        assert_eq!(statement.opening_balance, Money::from_paisa(80_000));
+    }
+}
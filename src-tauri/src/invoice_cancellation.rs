@@ -0,0 +1,552 @@
+/*!
+ * INVOICE CANCELLATION ENGINE
+ * Reverses every effect of an invoice inside one transaction:
+ * compensating stock movements, a reversing customer ledger entry,
+ * unallocated payments and a permanent cancellation record.
+ * The invoice row itself is kept (status = 'cancelled') so history stays intact.
+ * The cancellation is recorded against the signed-in user.
+ */
+
+use log::info;
+use rusqlite::{params, Connection, OptionalExtension, Transaction, TransactionBehavior};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::audit::{self, AuditEvent};
+use crate::customer_balance::{ledger_balance, ledger_effect_sql};
+use crate::database::{current_date, current_time, open_connection, table_exists};
+use crate::journal::{post_document_in_transaction, SourceType};
+use crate::money::{paisa_from_row, paisa_sql, sum_paisa_sql, Money};
+use crate::quantity::{
+    format_movement_quantity, parse_movement_quantity, parse_stock_text, set_movement_bases, set_product_stock,
+    value_to_text,
+};
+use crate::session;
+use crate::stock_engine::movement_effect;
+
+/// Create the cancellation register. `invoice_id` is UNIQUE so the same
+/// invoice can never be reversed twice, even by concurrent requests.
+pub fn ensure_schema(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS invoice_cancellations (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            invoice_id INTEGER NOT NULL UNIQUE,
+            bill_number TEXT NOT NULL,
+            customer_id INTEGER NOT NULL,
+            customer_name TEXT NOT NULL,
+            reason TEXT NOT NULL,
+            cancelled_by TEXT NOT NULL,
+            stock_movements_reversed INTEGER NOT NULL DEFAULT 0,
+            reversed_ledger_amount REAL NOT NULL DEFAULT 0,
+            unallocated_payment_amount REAL NOT NULL DEFAULT 0,
+            date TEXT NOT NULL,
+            time TEXT NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE INDEX IF NOT EXISTS idx_invoice_cancellations_customer ON invoice_cancellations(customer_id);",
+    )
+}
+
+#[derive(Debug, Serialize)]
+pub struct InvoiceCancellationResult {
+    pub invoice_id: i64,
+    pub bill_number: String,
+    pub stock_movements_reversed: usize,
+    pub reversed_ledger_amount: Money,
+    pub unallocated_payment_amount: Money,
+    pub customer_balance_after: Money,
+}
+
+struct InvoiceRow {
+    bill_number: String,
+    customer_id: i64,
+    customer_name: String,
+    status: String,
+    paid_amount: Money,
+}
+
+struct MovementRow {
+    product_id: i64,
+    product_name: String,
+    movement_type: String,
+    quantity: String,
+    unit: String,
+}
+
+#[tauri::command]
+pub async fn cancel_invoice(invoice_id: i64, reason: String) -> Result<InvoiceCancellationResult, String> {
+    let cancelled_by = session::current_user("cancel invoices")?;
+    info!("[CANCEL-INVOICE] Cancelling invoice {} by {}", invoice_id, cancelled_by);
+
+    let reason = reason.trim();
+    if reason.is_empty() {
+        return Err("A cancellation reason is required".to_string());
+    }
+
+    let mut conn = open_connection()?;
+    ensure_schema(&conn)
+        .map_err(|e| format!("Failed to prepare cancellation table: {}", e))?;
+
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let result = cancel_invoice_in_transaction(&tx, invoice_id, reason, &cancelled_by)?;
+    audit::record(
+        &tx,
+        &AuditEvent::new("cancel", "invoice")
+            .entity(invoice_id)
+            .by(&cancelled_by)
+            .after(json!({ "reason": reason, "result": &result })),
+    )?;
+
+    tx.commit()
+        .map_err(|e| format!("Failed to commit invoice cancellation: {}", e))?;
+
+    info!(
+        "[CANCEL-INVOICE] Invoice {} cancelled: {} stock movements reversed, Rs.{} ledger reversed, Rs.{} payments unallocated",
+        result.bill_number,
+        result.stock_movements_reversed,
+        result.reversed_ledger_amount,
+        result.unallocated_payment_amount
+    );
+
+    Ok(result)
+}
+
+/// Perform the full reversal on an already-open transaction.
+/// Nothing is committed here; any error leaves the caller free to roll back.
+pub fn cancel_invoice_in_transaction(
+    tx: &Transaction,
+    invoice_id: i64,
+    reason: &str,
+    cancelled_by: &str,
+) -> Result<InvoiceCancellationResult, String> {
+    let paid_amount = paisa_sql(tx, "invoices", "paid_amount").map_err(|e| format!("Failed to inspect schema: {}", e))?;
+    let invoice = tx
+        .query_row(
+            &format!(
+                "SELECT bill_number, customer_id, customer_name, status, {paid_amount}
+                 FROM invoices WHERE id = ?1"
+            ),
+            [invoice_id],
+            |row| {
+                Ok(InvoiceRow {
+                    bill_number: row.get(0)?,
+                    customer_id: row.get(1)?,
+                    customer_name: row.get(2)?,
+                    status: row.get(3)?,
+                    paid_amount: paisa_from_row(row, 4)?,
+                })
+            },
+        )
+        .optional()
+        .map_err(|e| format!("Failed to load invoice: {}", e))?
+        .ok_or_else(|| format!("Invoice {} not found", invoice_id))?;
+
+    let already_cancelled: bool = tx
+        .query_row(
+            "SELECT COUNT(*) FROM invoice_cancellations WHERE invoice_id = ?1",
+            [invoice_id],
+            |row| row.get::<_, i64>(0),
+        )
+        .map_err(|e| format!("Failed to check cancellation register: {}", e))?
+        > 0;
+    if already_cancelled || invoice.status == "cancelled" {
+        return Err(format!("Invoice {} is already cancelled", invoice.bill_number));
+    }
+
+    // Returns already put part of the stock back and credited the customer;
+    // reversing the original sale on top of them would count those twice.
+    if table_exists(tx, "returns").map_err(|e| format!("Failed to inspect schema: {}", e))? {
+        let open_returns: i64 = tx
+            .query_row(
+                "SELECT COUNT(*) FROM returns WHERE original_invoice_id = ?1 AND status != 'cancelled'",
+                [invoice_id],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Failed to check returns: {}", e))?;
+        if open_returns > 0 {
+            return Err(format!(
+                "Invoice {} has {} processed return(s) and cannot be cancelled",
+                invoice.bill_number, open_returns
+            ));
+        }
+    }
+
+    let date = current_date(tx).map_err(|e| format!("Failed to read date: {}", e))?;
+    let time = current_time(tx).map_err(|e| format!("Failed to read time: {}", e))?;
+
+    let stock_movements_reversed = reverse_stock_movements(tx, invoice_id, &invoice, cancelled_by, &date, &time)?;
+    let (reversed_ledger_amount, customer_balance_after) =
+        reverse_customer_ledger(tx, invoice_id, &invoice, reason, cancelled_by, &date, &time)?;
+    let unallocated_payment_amount = unallocate_payments(tx, invoice_id, &invoice)?;
+
+    tx.execute(
+        "UPDATE invoices SET
+            status = 'cancelled',
+            paid_amount = 0,
+            payment_amount = 0,
+            remaining_balance = 0,
+            due_amount = 0,
+            internal_notes = TRIM(COALESCE(internal_notes, '') || ' ' || ?2),
+            updated_by = ?3,
+            updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?1",
+        params![invoice_id, format!("[Cancelled: {}]", reason), cancelled_by],
+    )
+    .map_err(|e| format!("Failed to mark invoice as cancelled: {}", e))?;
+
+    tx.execute(
+        "INSERT INTO invoice_cancellations (
+            invoice_id, bill_number, customer_id, customer_name, reason, cancelled_by,
+            stock_movements_reversed, reversed_ledger_amount, unallocated_payment_amount, date, time
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        params![
+            invoice_id,
+            invoice.bill_number,
+            invoice.customer_id,
+            invoice.customer_name,
+            reason,
+            cancelled_by,
+            stock_movements_reversed as i64,
+            reversed_ledger_amount.to_rupees(),
+            unallocated_payment_amount.to_rupees(),
+            date,
+            time
+        ],
+    )
+    .map_err(|e| format!("Failed to record cancellation: {}", e))?;
+
+    post_document_in_transaction(tx, SourceType::Invoice, invoice_id)?;
+
+    Ok(InvoiceCancellationResult {
+        invoice_id,
+        bill_number: invoice.bill_number,
+        stock_movements_reversed,
+        reversed_ledger_amount,
+        unallocated_payment_amount,
+        customer_balance_after,
+    })
+}
+
+/// Post a compensating movement for every stock movement the invoice created
+fn reverse_stock_movements(
+    tx: &Transaction,
+    invoice_id: i64,
+    invoice: &InvoiceRow,
+    cancelled_by: &str,
+    date: &str,
+    time: &str,
+) -> Result<usize, String> {
+    let movements: Vec<MovementRow> = {
+        let mut stmt = tx
+            .prepare(
+                "SELECT product_id, product_name, movement_type, quantity, unit
+                 FROM stock_movements
+                 WHERE reference_type = 'invoice' AND reference_id = ?1
+                 ORDER BY id",
+            )
+            .map_err(|e| format!("Failed to query stock movements: {}", e))?;
+        let rows = stmt
+            .query_map([invoice_id], |row| {
+                Ok(MovementRow {
+                    product_id: row.get(0)?,
+                    product_name: row.get(1)?,
+                    movement_type: row.get(2)?,
+                    quantity: row.get::<_, rusqlite::types::Value>(3).map(value_to_text)?,
+                    unit: row.get(4)?,
+                })
+            })
+            .map_err(|e| format!("Failed to read stock movements: {}", e))?;
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read stock movement: {}", e))?
+    };
+
+    for movement in &movements {
+        let (current_stock, unit_type): (String, String) = tx
+            .query_row(
+                "SELECT current_stock, COALESCE(unit_type, 'kg-grams') FROM products WHERE id = ?1",
+                [movement.product_id],
+                |row| Ok((row.get::<_, rusqlite::types::Value>(0).map(value_to_text)?, row.get(1)?)),
+            )
+            .map_err(|e| format!("Failed to load product {}: {}", movement.product_id, e))?;
+
+        let quantity = parse_movement_quantity(&movement.quantity, &unit_type).ok_or_else(|| {
+            format!(
+                "Cannot parse quantity '{}' of {} on invoice {}",
+                movement.quantity, movement.product_name, invoice.bill_number
+            )
+        })?;
+        let stock_before = parse_stock_text(&current_stock, &unit_type).ok_or_else(|| {
+            format!("Cannot parse current stock '{}' of {}", current_stock, movement.product_name)
+        })?;
+
+        // The reversal is the negation of the original movement's effect
+        let delta = -movement_effect(&movement.movement_type, quantity);
+        let stock_after = stock_before + delta;
+
+        set_product_stock(tx, movement.product_id, stock_after, &unit_type)
+            .map_err(|e| format!("Failed to update stock of {}: {}", movement.product_name, e))?;
+
+        tx.execute(
+            "INSERT INTO stock_movements (
+                product_id, product_name, movement_type, transaction_type, quantity, unit,
+                previous_stock, stock_before, stock_after, new_stock,
+                reason, reference_type, reference_id, reference_number,
+                customer_id, customer_name, notes, date, time, created_by
+            ) VALUES (?1, ?2, ?3, 'adjustment', ?4, ?5, ?6, ?6, ?7, ?7,
+                'Invoice cancellation reversal', 'invoice', ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+            params![
+                movement.product_id,
+                movement.product_name,
+                if delta >= 0 { "in" } else { "out" },
+                format_movement_quantity(delta, &unit_type),
+                movement.unit,
+                format_movement_quantity(stock_before, &unit_type),
+                format_movement_quantity(stock_after, &unit_type),
+                invoice_id,
+                invoice.bill_number,
+                invoice.customer_id,
+                invoice.customer_name,
+                format!("REVERSAL: Invoice {} cancelled", invoice.bill_number),
+                date,
+                time,
+                cancelled_by
+            ],
+        )
+        .map_err(|e| format!("Failed to record reversal movement: {}", e))?;
+        set_movement_bases(tx, tx.last_insert_rowid(), delta, stock_before, stock_after)
+            .map_err(|e| format!("Failed to record reversal movement: {}", e))?;
+    }
+
+    Ok(movements.len())
+}
+
+/// Credit the customer with exactly what the invoice debited.
+/// Returns (reversed amount, customer balance after the reversal).
+fn reverse_customer_ledger(
+    tx: &Transaction,
+    invoice_id: i64,
+    invoice: &InvoiceRow,
+    reason: &str,
+    cancelled_by: &str,
+    date: &str,
+    time: &str,
+) -> Result<(Money, Money), String> {
+    let amount = paisa_sql(tx, "customer_ledger_entries", "amount")
+        .map_err(|e| format!("Failed to inspect schema: {}", e))?;
+    let invoice_debit = tx
+        .query_row(
+            &format!(
+                "SELECT {} FROM customer_ledger_entries
+                 WHERE customer_id = ?1 AND transaction_type = 'invoice'
+                   AND (reference_id = ?2 OR invoice_id = ?2)",
+                sum_paisa_sql(&ledger_effect_sql(&amount))
+            ),
+            params![invoice.customer_id, invoice_id],
+            |row| row.get(0).map(Money::from_paisa),
+        )
+        .map_err(|e| format!("Failed to sum invoice ledger entries: {}", e))?;
+
+    let balance_before = ledger_balance(tx, invoice.customer_id)
+        .map_err(|e| format!("Failed to compute customer balance: {}", e))?;
+
+    // Walk-in sales never reach the customer ledger; nothing to reverse
+    if invoice_debit.is_zero() {
+        return Ok((Money::ZERO, balance_before));
+    }
+
+    let balance_after = balance_before - invoice_debit;
+    let entry_type = if invoice_debit.is_negative() { "debit" } else { "credit" };
+    let amount = invoice_debit.abs();
+
+    tx.execute(
+        "INSERT INTO customer_ledger_entries (
+            customer_id, customer_name, entry_type, transaction_type, amount,
+            balance_before, balance_after, description,
+            reference_type, reference_id, reference_number, invoice_id, invoice_number,
+            date, time, notes, created_by
+        ) VALUES (?1, ?2, ?3, 'adjustment', ?4, ?5, ?6, ?7, 'invoice', ?8, ?9, ?8, ?9, ?10, ?11, ?12, ?13)",
+        params![
+            invoice.customer_id,
+            invoice.customer_name,
+            entry_type,
+            amount.to_rupees(),
+            balance_before.to_rupees(),
+            balance_after.to_rupees(),
+            format!("Invoice {} cancelled", invoice.bill_number),
+            invoice_id,
+            invoice.bill_number,
+            date,
+            time,
+            reason,
+            cancelled_by
+        ],
+    )
+    .map_err(|e| format!("Failed to post reversing ledger entry: {}", e))?;
+
+    tx.execute(
+        "UPDATE customers SET balance = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        params![balance_after.to_rupees(), invoice.customer_id],
+    )
+    .map_err(|e| format!("Failed to update customer balance: {}", e))?;
+
+    Ok((invoice_debit, balance_after))
+}
+
+/// Detach payments from the invoice. The payment credits stay on the
+/// customer ledger, so the money received becomes customer credit.
+fn unallocate_payments(tx: &Transaction, invoice_id: i64, invoice: &InvoiceRow) -> Result<Money, String> {
+    if table_exists(tx, "invoice_payment_allocations").map_err(|e| format!("Failed to inspect schema: {}", e))? {
+        tx.execute("DELETE FROM invoice_payment_allocations WHERE invoice_id = ?1", [invoice_id])
+            .map_err(|e| format!("Failed to remove payment allocations: {}", e))?;
+    }
+
+    if table_exists(tx, "invoice_payments").map_err(|e| format!("Failed to inspect schema: {}", e))? {
+        tx.execute(
+            "UPDATE invoice_payments SET status = 'cancelled', updated_at = CURRENT_TIMESTAMP WHERE invoice_id = ?1",
+            [invoice_id],
+        )
+        .map_err(|e| format!("Failed to cancel invoice payment links: {}", e))?;
+    }
+
+    if table_exists(tx, "payments").map_err(|e| format!("Failed to inspect schema: {}", e))? {
+        tx.execute(
+            "UPDATE payments SET
+                invoice_id = NULL,
+                invoice_number = NULL,
+                notes = TRIM(COALESCE(notes, '') || ' ' || ?2),
+                updated_at = CURRENT_TIMESTAMP
+             WHERE invoice_id = ?1",
+            params![invoice_id, format!("[Unallocated: invoice {} cancelled]", invoice.bill_number)],
+        )
+        .map_err(|e| format!("Failed to unallocate payments: {}", e))?;
+    }
+
+    Ok(invoice.paid_amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ali bought four pipes on invoice I00001 for Rs.400 and paid Rs.100
+    fn database() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE products (
+                id INTEGER PRIMARY KEY, name TEXT, unit_type TEXT, current_stock TEXT, cost_price REAL, updated_at TEXT
+            );
+            CREATE TABLE customers (id INTEGER PRIMARY KEY, name TEXT, balance REAL, updated_at TEXT);
+            CREATE TABLE invoices (
+                id INTEGER PRIMARY KEY, bill_number TEXT, customer_id INTEGER, customer_name TEXT, status TEXT,
+                subtotal REAL, grand_total REAL, total_amount REAL, paid_amount REAL, payment_amount REAL,
+                remaining_balance REAL, due_amount REAL, internal_notes TEXT, date TEXT, time TEXT,
+                updated_by TEXT, updated_at TEXT
+            );
+            CREATE TABLE invoice_items (
+                id INTEGER PRIMARY KEY, invoice_id INTEGER, product_id INTEGER, product_name TEXT, quantity TEXT,
+                unit TEXT, unit_price REAL, line_total REAL, cost_price REAL, is_non_stock_item INTEGER DEFAULT 0,
+                is_misc_item INTEGER DEFAULT 0
+            );
+            CREATE TABLE stock_movements (
+                id INTEGER PRIMARY KEY, product_id INTEGER, product_name TEXT, movement_type TEXT,
+                transaction_type TEXT, quantity TEXT, unit TEXT, previous_stock TEXT, stock_before TEXT,
+                stock_after TEXT, new_stock TEXT, unit_price REAL, total_value REAL, reason TEXT,
+                reference_type TEXT, reference_id INTEGER, reference_number TEXT, customer_id INTEGER,
+                customer_name TEXT, notes TEXT, date TEXT, time TEXT, created_by TEXT
+            );
+            CREATE TABLE customer_ledger_entries (
+                id INTEGER PRIMARY KEY, customer_id INTEGER, customer_name TEXT, entry_type TEXT,
+                transaction_type TEXT, amount REAL, balance_before REAL, balance_after REAL, description TEXT,
+                reference_type TEXT, reference_id INTEGER, reference_number TEXT, invoice_id INTEGER,
+                invoice_number TEXT, payment_method TEXT, notes TEXT, date TEXT, time TEXT, created_by TEXT
+            );
+            CREATE TABLE payments (
+                id INTEGER PRIMARY KEY, customer_id INTEGER, amount REAL, invoice_id INTEGER, invoice_number TEXT,
+                notes TEXT, date TEXT, updated_at TEXT
+            );
+            INSERT INTO products (id, name, unit_type, current_stock, cost_price) VALUES (1, 'Pipe', 'piece', '6', 60);
+            INSERT INTO customers (id, name, balance) VALUES (1, 'Ali', 300);
+            INSERT INTO invoices (id, bill_number, customer_id, customer_name, status, subtotal, grand_total,
+                                  total_amount, paid_amount, payment_amount, remaining_balance, due_amount, date, time)
+                VALUES (1, 'I00001', 1, 'Ali', 'partially_paid', 400, 400, 400, 100, 100, 300, 300,
+                        '2026-03-01', '10:00:00');
+            INSERT INTO invoice_items (id, invoice_id, product_id, product_name, quantity, unit, unit_price, line_total,
+                                       cost_price)
+                VALUES (1, 1, 1, 'Pipe', '4', 'piece', 100, 400, 60);
+            INSERT INTO stock_movements (product_id, product_name, movement_type, transaction_type, quantity, unit,
+                                         stock_before, stock_after, reference_type, reference_id, date, time)
+                VALUES (1, 'Pipe', 'out', 'sale', '4', 'piece', '10', '6', 'invoice', 1, '2026-03-01', '10:00:00');
+            INSERT INTO customer_ledger_entries (customer_id, customer_name, entry_type, transaction_type, amount,
+                                                 balance_before, balance_after, invoice_id, date, time)
+                VALUES (1, 'Ali', 'debit', 'invoice', 400, 0, 400, 1, '2026-03-01', '10:00:00'),
+                       (1, 'Ali', 'credit', 'payment', 100, 400, 300, 1, '2026-03-01', '10:05:00');
+            INSERT INTO payments (id, customer_id, amount, invoice_id, invoice_number, date)
+                VALUES (1, 1, 100, 1, 'I00001', '2026-03-01');",
+        )
+        .unwrap();
+        ensure_schema(&conn).unwrap();
+        crate::journal::ensure_schema(&conn).unwrap();
+        conn
+    }
+
+    fn cancel(conn: &mut Connection) -> Result<InvoiceCancellationResult, String> {
+        let tx = conn.transaction().unwrap();
+        let result = cancel_invoice_in_transaction(&tx, 1, "Entered twice", "sara")?;
+        tx.commit().unwrap();
+        Ok(result)
+    }
+
+    fn count(conn: &Connection, table: &str) -> i64 {
+        conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn cancelling_reverses_the_sale_once() {
+        let mut conn = database();
+        let result = cancel(&mut conn).unwrap();
+        assert_eq!(result.stock_movements_reversed, 1);
+        assert_eq!(result.reversed_ledger_amount, Money::from_paisa(40_000));
+        assert_eq!(result.unallocated_payment_amount, Money::from_paisa(10_000));
+        // The payment stays on the account as credit
+        assert_eq!(result.customer_balance_after, Money::from_paisa(-10_000));
+
+        let stock: String = conn
+            .query_row("SELECT current_stock FROM products WHERE id = 1", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(stock, "10");
+        let (status, remaining): (String, f64) = conn
+            .query_row(
+                "SELECT status, remaining_balance FROM invoices WHERE id = 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((status.as_str(), remaining), ("cancelled", 0.0));
+        let payment_invoice: Option<i64> = conn
+            .query_row("SELECT invoice_id FROM payments WHERE id = 1", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(payment_invoice, None);
+
+        let movements = count(&conn, "stock_movements");
+        let ledger = count(&conn, "customer_ledger_entries");
+        let journal = count(&conn, "journal_entries");
+
+        let error = cancel(&mut conn).unwrap_err();
+        assert_eq!(error, "Invoice I00001 is already cancelled");
+        assert_eq!(count(&conn, "stock_movements"), movements);
+        assert_eq!(count(&conn, "customer_ledger_entries"), ledger);
+        assert_eq!(count(&conn, "journal_entries"), journal);
+        assert_eq!(count(&conn, "invoice_cancellations"), 1);
+
+        // The register refuses a second row even if the status was put back
+        conn.execute("UPDATE invoices SET status = 'paid' WHERE id = 1", [])
+            .unwrap();
+        assert!(cancel(&mut conn).is_err());
+        assert_eq!(count(&conn, "stock_movements"), movements);
+        assert_eq!(count(&conn, "customer_ledger_entries"), ledger);
+    }
+}
@@ -0,0 +1,1263 @@
+/*!
+ * GENERAL JOURNAL (DOUBLE ENTRY)
+ * The frontend keeps parallel single-entry books (`ledger_entries`,
+ * `business_income`, `business_expenses`, `payment_channel_daily_ledgers`).
+ * Underneath them every invoice, customer payment, return, stock receiving,
+ * vendor payment, expense and salary payment is posted here as a balanced
+ * journal entry against a chart of accounts with one account per payment
+ * channel.
+ *
+ * Postings are derived from the source document, so posting is idempotent:
+ * what a document should have posted minus what it has posted goes out as a
+ * correcting entry, and cancelled or deleted documents are reversed the
+ * same way. Journal lines are never updated or deleted and every entry
+ * balances, so the trial balance always sums to zero.
+ *
+ * Posting happens when something writes: the Rust commands that change
+ * documents, day and year closings, sync rounds, and `sync_journal` for what
+ * the frontend wrote through its own connection. Reports read the journal as
+ * posted and never post themselves, so they take no write lock; the trial
+ * balance lists the documents still waiting to post beside its figures.
+ *
+ * Cost of goods sold uses the invoice line cost price, falling back to the
+ * product cost price. Money moved without a payment channel is posted to
+ * cash in hand.
+ */
+
+use std::collections::{BTreeMap, HashMap};
+
+use log::{info, warn};
+use rusqlite::{params, Connection, OptionalExtension, Transaction, TransactionBehavior};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::audit::{self, AuditEvent};
+use crate::database::{current_date, current_time, has_column, open_connection, table_exists};
+use crate::money::{money_from_row, paisa_from_row, paisa_sql, sum_paisa_sql, Money};
+use crate::quantity::{parse_stock_text, value_to_text, UnitType};
+use crate::session;
+use crate::vendor_payables::payable_sql;
+
+pub const CASH_ACCOUNT: &str = "1000";
+const RECEIVABLES_ACCOUNT: &str = "1200";
+const INVENTORY_ACCOUNT: &str = "1300";
+const PAYABLES_ACCOUNT: &str = "2000";
+const SALES_ACCOUNT: &str = "4000";
+const SALES_RETURNS_ACCOUNT: &str = "4100";
+const COGS_ACCOUNT: &str = "5000";
+const EXPENSES_ACCOUNT: &str = "6000";
+const SALARIES_ACCOUNT: &str = "6100";
+
+/// Payment channel accounts are coded 1100-<channel id>
+const CHANNEL_ACCOUNT_PREFIX: &str = "1100-";
+
+/// System accounts: code, name, type
+const CHART_OF_ACCOUNTS: &[(&str, &str, &str)] = &[
+    (CASH_ACCOUNT, "Cash in Hand", "asset"),
+    (RECEIVABLES_ACCOUNT, "Accounts Receivable", "asset"),
+    (INVENTORY_ACCOUNT, "Inventory", "asset"),
+    (PAYABLES_ACCOUNT, "Accounts Payable", "liability"),
+    (SALES_ACCOUNT, "Sales", "income"),
+    (SALES_RETURNS_ACCOUNT, "Sales Returns", "income"),
+    (COGS_ACCOUNT, "Cost of Goods Sold", "expense"),
+    (EXPENSES_ACCOUNT, "Operating Expenses", "expense"),
+    (SALARIES_ACCOUNT, "Salaries", "expense"),
+];
+
+/// Business documents that post to the journal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SourceType {
+    Invoice,
+    CustomerPayment,
+    Return,
+    StockReceiving,
+    VendorPayment,
+    Expense,
+    SalaryPayment,
+}
+
+impl SourceType {
+    const ALL: [SourceType; 7] = [
+        SourceType::Invoice,
+        SourceType::CustomerPayment,
+        SourceType::Return,
+        SourceType::StockReceiving,
+        SourceType::VendorPayment,
+        SourceType::Expense,
+        SourceType::SalaryPayment,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SourceType::Invoice => "invoice",
+            SourceType::CustomerPayment => "customer_payment",
+            SourceType::Return => "return",
+            SourceType::StockReceiving => "stock_receiving",
+            SourceType::VendorPayment => "vendor_payment",
+            SourceType::Expense => "expense",
+            SourceType::SalaryPayment => "salary_payment",
+        }
+    }
+
+    fn parse(source_type: &str) -> Option<SourceType> {
+        SourceType::ALL
+            .into_iter()
+            .find(|source| source.as_str() == source_type)
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            SourceType::Invoice => "Invoice",
+            SourceType::CustomerPayment => "Customer payment",
+            SourceType::Return => "Return",
+            SourceType::StockReceiving => "Stock receiving",
+            SourceType::VendorPayment => "Vendor payment",
+            SourceType::Expense => "Expense",
+            SourceType::SalaryPayment => "Salary payment",
+        }
+    }
+}
+
+/// Create the chart of accounts and the journal, and seed the system accounts
+pub fn ensure_schema(conn: &Connection) -> rusqlite::Result<()> {
+    let source_types = SourceType::ALL
+        .iter()
+        .map(|source| format!("'{}'", source.as_str()))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    conn.execute_batch(&format!(
+        "CREATE TABLE IF NOT EXISTS gl_accounts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            code TEXT UNIQUE NOT NULL,
+            name TEXT NOT NULL,
+            account_type TEXT NOT NULL CHECK (account_type IN ('asset', 'liability', 'equity', 'income', 'expense')),
+            payment_channel_id INTEGER UNIQUE,
+            is_active INTEGER NOT NULL DEFAULT 1,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE TABLE IF NOT EXISTS journal_entries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            entry_number TEXT UNIQUE NOT NULL,
+            date TEXT NOT NULL,
+            time TEXT NOT NULL,
+            description TEXT NOT NULL,
+            source_type TEXT NOT NULL CHECK (source_type IN ({source_types})),
+            source_id INTEGER NOT NULL,
+            source_number TEXT,
+            created_by TEXT NOT NULL DEFAULT 'system',
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE INDEX IF NOT EXISTS idx_journal_entries_source ON journal_entries(source_type, source_id);
+        CREATE INDEX IF NOT EXISTS idx_journal_entries_date ON journal_entries(date);
+        CREATE TABLE IF NOT EXISTS journal_lines (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            journal_entry_id INTEGER NOT NULL,
+            account_id INTEGER NOT NULL,
+            debit REAL NOT NULL DEFAULT 0,
+            credit REAL NOT NULL DEFAULT 0,
+            CHECK (debit >= 0 AND credit >= 0),
+            FOREIGN KEY (journal_entry_id) REFERENCES journal_entries(id),
+            FOREIGN KEY (account_id) REFERENCES gl_accounts(id)
+        );
+        CREATE INDEX IF NOT EXISTS idx_journal_lines_entry ON journal_lines(journal_entry_id);
+        CREATE INDEX IF NOT EXISTS idx_journal_lines_account ON journal_lines(account_id);
+        CREATE TRIGGER IF NOT EXISTS trg_journal_lines_immutable
+        BEFORE UPDATE OF journal_entry_id, account_id, debit, credit ON journal_lines
+        BEGIN
+            SELECT RAISE(ABORT, 'Journal lines cannot be changed; post a correcting entry');
+        END;
+        CREATE TRIGGER IF NOT EXISTS trg_journal_lines_no_delete BEFORE DELETE ON journal_lines
+        BEGIN
+            SELECT RAISE(ABORT, 'Journal lines cannot be deleted; post a correcting entry');
+        END;
+        CREATE TRIGGER IF NOT EXISTS trg_journal_entries_no_delete BEFORE DELETE ON journal_entries
+        BEGIN
+            SELECT RAISE(ABORT, 'Journal entries cannot be deleted; post a correcting entry');
+        END;"
+    ))?;
+
+    for (code, name, account_type) in CHART_OF_ACCOUNTS {
+        conn.execute(
+            "INSERT OR IGNORE INTO gl_accounts (code, name, account_type) VALUES (?1, ?2, ?3)",
+            params![code, name, account_type],
+        )?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct JournalSyncResult {
+    pub documents_checked: usize,
+    pub entries_posted: usize,
+    pub corrections_posted: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TrialBalanceLine {
+    pub account_id: i64,
+    pub code: String,
+    pub name: String,
+    pub account_type: String,
+    pub payment_channel_id: Option<i64>,
+    pub debit: Money,
+    pub credit: Money,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TrialBalance {
+    pub as_of: Option<String>,
+    pub lines: Vec<TrialBalanceLine>,
+    pub total_debit: Money,
+    pub total_credit: Money,
+    pub difference: Money,
+    pub balanced: bool,
+    /// Entries whose own debits and credits differ; always empty unless the journal was edited by hand
+    pub unbalanced_entries: Vec<String>,
+    /// Documents written since the journal last posted, which the figures above leave out
+    pub unposted_documents: Vec<UnpostedDocument>,
+}
+
+/// A document whose postings are behind it: new, changed, cancelled or deleted
+#[derive(Debug, Serialize)]
+pub struct UnpostedDocument {
+    pub source_type: &'static str,
+    pub source_id: i64,
+    pub number: String,
+    /// Date its posting will carry: its own date the first time, today for a correction
+    pub date: String,
+    /// Debits still to post
+    pub amount: Money,
+}
+
+/// Where a posting goes before it is resolved to a gl_accounts row
+#[derive(Debug, Clone, Copy)]
+enum Account {
+    System(&'static str),
+    /// A payment channel's account, cash in hand when there is no channel
+    Channel(Option<i64>),
+}
+
+/// What a business document should have posted, debits positive
+struct Document {
+    id: i64,
+    number: String,
+    date: String,
+    time: String,
+    counterparty: Option<String>,
+    postings: Vec<(Account, Money)>,
+}
+
+impl Document {
+    fn new(id: i64, number: String, date: String, time: String) -> Document {
+        Document {
+            id,
+            number,
+            date,
+            time,
+            counterparty: None,
+            postings: Vec::new(),
+        }
+    }
+
+    /// Debit one account and credit another with the same amount
+    fn transfer(&mut self, debit: Account, credit: Account, amount: Money) {
+        if !amount.is_zero() {
+            self.postings.push((debit, amount));
+            self.postings.push((credit, -amount));
+        }
+    }
+}
+
+/// What is already posted for one document, per account
+#[derive(Default)]
+struct PostedSource {
+    source_number: String,
+    balances: BTreeMap<i64, Money>,
+}
+
+/// Account ids by code and by payment channel
+struct Accounts {
+    by_code: HashMap<String, i64>,
+    by_channel: HashMap<i64, i64>,
+}
+
+impl Accounts {
+    /// Open an account for every payment channel that has none, keep channel
+    /// account names in step with the channels, and load the chart
+    fn load(tx: &Transaction) -> Result<Accounts, String> {
+        if table_exists(tx, "payment_channels").map_err(|e| format!("Failed to inspect schema: {}", e))? {
+            tx.execute(
+                "INSERT INTO gl_accounts (code, name, account_type, payment_channel_id, is_active)
+                 SELECT ?1 || c.id, c.name, 'asset', c.id, COALESCE(c.is_active, 1)
+                 FROM payment_channels c
+                 WHERE NOT EXISTS (SELECT 1 FROM gl_accounts a WHERE a.payment_channel_id = c.id)",
+                [CHANNEL_ACCOUNT_PREFIX],
+            )
+            .map_err(|e| format!("Failed to open payment channel accounts: {}", e))?;
+            tx.execute(
+                "UPDATE gl_accounts
+                 SET name = (SELECT c.name FROM payment_channels c WHERE c.id = gl_accounts.payment_channel_id),
+                     is_active = (SELECT COALESCE(c.is_active, 1) FROM payment_channels c WHERE c.id = gl_accounts.payment_channel_id),
+                     updated_at = CURRENT_TIMESTAMP
+                 WHERE EXISTS (
+                     SELECT 1 FROM payment_channels c
+                     WHERE c.id = gl_accounts.payment_channel_id
+                       AND (c.name != gl_accounts.name OR COALESCE(c.is_active, 1) != gl_accounts.is_active)
+                 )",
+                [],
+            )
+            .map_err(|e| format!("Failed to update payment channel accounts: {}", e))?;
+        }
+        Accounts::read(tx)
+    }
+
+    /// Load the chart as it stands; a channel without an account yet reads as cash in hand
+    fn read(conn: &Connection) -> Result<Accounts, String> {
+        let mut accounts = Accounts {
+            by_code: HashMap::new(),
+            by_channel: HashMap::new(),
+        };
+        let mut stmt = conn
+            .prepare("SELECT id, code, payment_channel_id FROM gl_accounts")
+            .map_err(|e| format!("Failed to query chart of accounts: {}", e))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<i64>>(2)?,
+                ))
+            })
+            .map_err(|e| format!("Failed to read chart of accounts: {}", e))?;
+        for row in rows {
+            let (id, code, channel_id) = row.map_err(|e| format!("Failed to read account: {}", e))?;
+            if let Some(channel_id) = channel_id {
+                accounts.by_channel.insert(channel_id, id);
+            }
+            accounts.by_code.insert(code, id);
+        }
+        Ok(accounts)
+    }
+
+    /// What a document should have posted, per account id
+    fn expected(&self, document: &Document) -> Result<BTreeMap<i64, Money>, String> {
+        let mut expected: BTreeMap<i64, Money> = BTreeMap::new();
+        for (account, amount) in &document.postings {
+            *expected.entry(self.id(*account)?).or_default() += *amount;
+        }
+        Ok(expected)
+    }
+
+    fn id(&self, account: Account) -> Result<i64, String> {
+        match account {
+            Account::System(code) => self
+                .by_code
+                .get(code)
+                .copied()
+                .ok_or_else(|| format!("Account {} is missing from the chart of accounts", code)),
+            Account::Channel(channel_id) => match channel_id.and_then(|id| self.by_channel.get(&id)) {
+                Some(id) => Ok(*id),
+                None => self.id(Account::System(CASH_ACCOUNT)),
+            },
+        }
+    }
+}
+
+/// Cost of a sold or returned quantity at a per-unit cost
+fn cost_of(quantity: &str, unit_type: &str, unit_cost: Money) -> Money {
+    match (parse_stock_text(quantity, unit_type), UnitType::parse(unit_type)) {
+        (Some(base), Some(unit)) => unit_cost.mul_ratio(base, unit.base_per_unit()),
+        _ => Money::ZERO,
+    }
+}
+
+/// Sum item costs per document from rows of (document id, quantity, unit cost, unit type)
+fn load_costs(conn: &Connection, sql: &str, id: Option<i64>) -> Result<HashMap<i64, Money>, String> {
+    let mut stmt = conn
+        .prepare(sql)
+        .map_err(|e| format!("Failed to query item costs: {}", e))?;
+    let rows = stmt
+        .query_map([id], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, rusqlite::types::Value>(1).map(value_to_text)?,
+                money_from_row(row, 2)?,
+                row.get::<_, String>(3)?,
+            ))
+        })
+        .map_err(|e| format!("Failed to read item costs: {}", e))?;
+
+    let mut costs: HashMap<i64, Money> = HashMap::new();
+    for row in rows {
+        let (document_id, quantity, unit_cost, unit_type) =
+            row.map_err(|e| format!("Failed to read item cost: {}", e))?;
+        *costs.entry(document_id).or_default() += cost_of(&quantity, &unit_type, unit_cost);
+    }
+    Ok(costs)
+}
+
+/// Column name when the table has it, otherwise the fallback expression
+fn column_or(conn: &Connection, table: &str, column: &str, fallback: &str) -> Result<String, String> {
+    let present = has_column(conn, table, column).map_err(|e| format!("Failed to inspect schema: {}", e))?;
+    Ok(if present {
+        column.to_string()
+    } else {
+        fallback.to_string()
+    })
+}
+
+/// Exact paisa of a money column (`paisa_sql`), for the document queries
+fn paisa(conn: &Connection, table: &str, column: &str) -> Result<String, String> {
+    paisa_sql(conn, table, column).map_err(|e| format!("Failed to inspect schema: {}", e))
+}
+
+/// Run a document query whose first five columns are id, number, date, time, status
+fn query_documents<F>(
+    conn: &Connection,
+    source: SourceType,
+    sql: &str,
+    id: Option<i64>,
+    mut build: F,
+) -> Result<Vec<Document>, String>
+where
+    F: FnMut(&mut Document, &str, &rusqlite::Row) -> rusqlite::Result<()>,
+{
+    let label = source.label().to_lowercase();
+    let mut stmt = conn
+        .prepare(sql)
+        .map_err(|e| format!("Failed to query {}s: {}", label, e))?;
+    let rows = stmt
+        .query_map([id], |row| {
+            let mut document = Document::new(row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?);
+            let status: Option<String> = row.get(4)?;
+            build(&mut document, status.as_deref().unwrap_or(""), row)?;
+            Ok(document)
+        })
+        .map_err(|e| format!("Failed to read {}s: {}", label, e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read {}: {}", label, e))
+}
+
+/// Sale on account and the cost of the goods leaving inventory
+fn load_invoices(conn: &Connection, id: Option<i64>) -> Result<Vec<Document>, String> {
+    let costs = load_costs(
+        conn,
+        "SELECT ii.invoice_id, ii.quantity, COALESCE(NULLIF(ii.cost_price, 0), p.cost_price, 0),
+                COALESCE(p.unit_type, 'piece')
+         FROM invoice_items ii
+         JOIN products p ON p.id = ii.product_id
+         WHERE (?1 IS NULL OR ii.invoice_id = ?1)
+           AND NOT (COALESCE(ii.is_non_stock_item, 0) OR COALESCE(ii.is_misc_item, 0))",
+        id,
+    )?;
+
+    query_documents(
+        conn,
+        SourceType::Invoice,
+        &format!(
+            "SELECT id, bill_number, date, time, status, {}, customer_name
+             FROM invoices WHERE ?1 IS NULL OR id = ?1",
+            paisa(conn, "invoices", "grand_total")?
+        ),
+        id,
+        |document, status, row| {
+            document.counterparty = row.get(6)?;
+            if status != "cancelled" {
+                let cost = costs.get(&document.id).copied().unwrap_or_default();
+                document.transfer(
+                    Account::System(RECEIVABLES_ACCOUNT),
+                    Account::System(SALES_ACCOUNT),
+                    paisa_from_row(row, 5)?,
+                );
+                document.transfer(Account::System(COGS_ACCOUNT), Account::System(INVENTORY_ACCOUNT), cost);
+            }
+            Ok(())
+        },
+    )
+}
+
+/// Money received from (or refunded to) customers. Customer credit applied
+/// to an invoice moves no money and posts nothing.
+fn load_customer_payments(conn: &Connection, id: Option<i64>) -> Result<Vec<Document>, String> {
+    query_documents(
+        conn,
+        SourceType::CustomerPayment,
+        &format!(
+            "SELECT id, COALESCE(payment_number, payment_code, 'PAY-' || id), date, time, status,
+                    {}, payment_type, payment_channel_id, COALESCE(payment_code, ''), customer_name
+             FROM payments WHERE vendor_id IS NULL AND (?1 IS NULL OR id = ?1)",
+            paisa(conn, "payments", "amount")?
+        ),
+        id,
+        |document, status, row| {
+            document.counterparty = row.get(9)?;
+            let payment_code: String = row.get(8)?;
+            if status == "completed" && !payment_code.starts_with("CREDIT") {
+                let amount = paisa_from_row(row, 5)?;
+                let channel = Account::Channel(row.get(7)?);
+                let payment_type: String = row.get(6)?;
+                if payment_type == "outgoing" {
+                    document.transfer(Account::System(RECEIVABLES_ACCOUNT), channel, amount);
+                } else {
+                    document.transfer(channel, Account::System(RECEIVABLES_ACCOUNT), amount);
+                }
+            }
+            Ok(())
+        },
+    )
+}
+
+/// Credit note against sales, restocked goods back into inventory, and the cash refund if any
+fn load_returns(conn: &Connection, id: Option<i64>) -> Result<Vec<Document>, String> {
+    let costs = load_costs(
+        conn,
+        "SELECT ri.return_id, ri.return_quantity, COALESCE(NULLIF(ii.cost_price, 0), p.cost_price, 0),
+                COALESCE(p.unit_type, 'piece')
+         FROM return_items ri
+         JOIN products p ON p.id = ri.product_id
+         LEFT JOIN invoice_items ii ON ii.id = ri.original_invoice_item_id
+         WHERE (?1 IS NULL OR ri.return_id = ?1)
+           AND (COALESCE(ri.restocked, 0) = 1
+                OR (COALESCE(ri.condition_status, 'good') = 'good' AND COALESCE(ri.action, 'refund') != 'discard'))",
+        id,
+    )?;
+
+    query_documents(
+        conn,
+        SourceType::Return,
+        &format!(
+            "SELECT r.id, r.return_number, r.date, r.time, r.status, {},
+                    r.settlement_type = 'cash' AND COALESCE(r.settlement_processed, 0) = 1,
+                    COALESCE(NULLIF({}, 0), {}),
+                    (SELECT le.payment_channel_id FROM ledger_entries le
+                     WHERE le.category = 'Cash Refund' AND le.reference_id = r.id
+                     ORDER BY le.id LIMIT 1),
+                    r.customer_name
+             FROM returns r WHERE ?1 IS NULL OR r.id = ?1",
+            paisa(conn, "returns", "r.total_amount")?,
+            paisa(conn, "returns", "r.refund_amount")?,
+            paisa(conn, "returns", "r.settlement_amount")?
+        ),
+        id,
+        |document, status, row| {
+            document.counterparty = row.get(9)?;
+            if status != "rejected" && status != "cancelled" {
+                let cost = costs.get(&document.id).copied().unwrap_or_default();
+                document.transfer(
+                    Account::System(SALES_RETURNS_ACCOUNT),
+                    Account::System(RECEIVABLES_ACCOUNT),
+                    paisa_from_row(row, 5)?,
+                );
+                document.transfer(Account::System(INVENTORY_ACCOUNT), Account::System(COGS_ACCOUNT), cost);
+                if row.get::<_, bool>(6)? {
+                    document.transfer(
+                        Account::System(RECEIVABLES_ACCOUNT),
+                        Account::Channel(row.get(8)?),
+                        paisa_from_row(row, 7)?,
+                    );
+                }
+            }
+            Ok(())
+        },
+    )
+}
+
+/// Goods received into inventory on credit from the vendor
+fn load_receivings(conn: &Connection, id: Option<i64>) -> Result<Vec<Document>, String> {
+    query_documents(
+        conn,
+        SourceType::StockReceiving,
+        &format!(
+            "SELECT id, receiving_number, received_date, received_time, status, {}, vendor_name
+             FROM stock_receiving WHERE ?1 IS NULL OR id = ?1",
+            payable_sql(conn).map_err(|e| format!("Failed to inspect schema: {}", e))?
+        ),
+        id,
+        |document, status, row| {
+            document.counterparty = row.get(6)?;
+            if status != "cancelled" {
+                document.transfer(
+                    Account::System(INVENTORY_ACCOUNT),
+                    Account::System(PAYABLES_ACCOUNT),
+                    paisa_from_row(row, 5)?,
+                );
+            }
+            Ok(())
+        },
+    )
+}
+
+fn load_vendor_payments(conn: &Connection, id: Option<i64>) -> Result<Vec<Document>, String> {
+    query_documents(
+        conn,
+        SourceType::VendorPayment,
+        &format!(
+            "SELECT id, payment_number, date, time, status, {}, payment_channel_id, vendor_name
+             FROM vendor_payments WHERE ?1 IS NULL OR id = ?1",
+            paisa(conn, "vendor_payments", "amount")?
+        ),
+        id,
+        |document, status, row| {
+            document.counterparty = row.get(7)?;
+            if status == "completed" {
+                document.transfer(
+                    Account::System(PAYABLES_ACCOUNT),
+                    Account::Channel(row.get(6)?),
+                    paisa_from_row(row, 5)?,
+                );
+            }
+            Ok(())
+        },
+    )
+}
+
+fn load_expenses(conn: &Connection, id: Option<i64>) -> Result<Vec<Document>, String> {
+    query_documents(
+        conn,
+        SourceType::Expense,
+        &format!(
+            "SELECT id, expense_number, date, time, COALESCE(status, 'completed'),
+                    COALESCE(NULLIF({}, 0), {}), payment_channel_id, category
+             FROM business_expenses WHERE ?1 IS NULL OR id = ?1",
+            paisa(conn, "business_expenses", "total_amount")?,
+            paisa(conn, "business_expenses", "amount")?
+        ),
+        id,
+        |document, status, row| {
+            document.counterparty = row.get(7)?;
+            if status == "completed" {
+                document.transfer(
+                    Account::System(EXPENSES_ACCOUNT),
+                    Account::Channel(row.get(6)?),
+                    paisa_from_row(row, 5)?,
+                );
+            }
+            Ok(())
+        },
+    )
+}
+
+/// Salary payments; the table has had several shapes, so optional columns are looked up
+fn load_salary_payments(conn: &Connection, id: Option<i64>) -> Result<Vec<Document>, String> {
+    let number = column_or(conn, "salary_payments", "payment_number", "NULL")?;
+    let code = column_or(conn, "salary_payments", "payment_code", "NULL")?;
+    let amount = paisa(
+        conn,
+        "salary_payments",
+        &column_or(conn, "salary_payments", "payment_amount", "total_amount")?,
+    )?;
+    let channel = column_or(conn, "salary_payments", "payment_channel_id", "NULL")?;
+    let status = column_or(conn, "salary_payments", "status", "NULL")?;
+
+    query_documents(
+        conn,
+        SourceType::SalaryPayment,
+        &format!(
+            "SELECT id, COALESCE({number}, {code}, 'SAL-' || id), substr(payment_date, 1, 10),
+                    COALESCE(NULLIF(substr(payment_date, 12, 8), ''), '00:00:00'), COALESCE({status}, 'completed'),
+                    {amount}, {channel}, staff_name
+             FROM salary_payments WHERE ?1 IS NULL OR id = ?1"
+        ),
+        id,
+        |document, status, row| {
+            document.counterparty = row.get(7)?;
+            if !matches!(status, "cancelled" | "pending" | "failed") {
+                document.transfer(
+                    Account::System(SALARIES_ACCOUNT),
+                    Account::Channel(row.get(6)?),
+                    paisa_from_row(row, 5)?,
+                );
+            }
+            Ok(())
+        },
+    )
+}
+
+fn load_documents(conn: &Connection, source: SourceType, id: Option<i64>) -> Result<Vec<Document>, String> {
+    let table = match source {
+        SourceType::Invoice => "invoices",
+        SourceType::CustomerPayment => "payments",
+        SourceType::Return => "returns",
+        SourceType::StockReceiving => "stock_receiving",
+        SourceType::VendorPayment => "vendor_payments",
+        SourceType::Expense => "business_expenses",
+        SourceType::SalaryPayment => "salary_payments",
+    };
+    if !table_exists(conn, table).map_err(|e| format!("Failed to inspect schema: {}", e))? {
+        return Ok(Vec::new());
+    }
+
+    match source {
+        SourceType::Invoice => load_invoices(conn, id),
+        SourceType::CustomerPayment => load_customer_payments(conn, id),
+        SourceType::Return => load_returns(conn, id),
+        SourceType::StockReceiving => load_receivings(conn, id),
+        SourceType::VendorPayment => load_vendor_payments(conn, id),
+        SourceType::Expense => load_expenses(conn, id),
+        SourceType::SalaryPayment => load_salary_payments(conn, id),
+    }
+}
+
+/// Net debit per account already posted, per document
+fn load_posted(
+    conn: &Connection,
+    filter: Option<(SourceType, i64)>,
+) -> Result<BTreeMap<(SourceType, i64), PostedSource>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT e.source_type, e.source_id, MAX(e.source_number), l.account_id, {} - {}
+             FROM journal_lines l
+             JOIN journal_entries e ON e.id = l.journal_entry_id
+             WHERE ?1 IS NULL OR (e.source_type = ?1 AND e.source_id = ?2)
+             GROUP BY e.source_type, e.source_id, l.account_id",
+            sum_paisa_sql(&paisa(conn, "journal_lines", "l.debit")?),
+            sum_paisa_sql(&paisa(conn, "journal_lines", "l.credit")?)
+        ))
+        .map_err(|e| format!("Failed to query journal: {}", e))?;
+    let rows = stmt
+        .query_map(
+            params![filter.map(|(source, _)| source.as_str()), filter.map(|(_, id)| id)],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, i64>(3)?,
+                    Money::from_paisa(row.get(4)?),
+                ))
+            },
+        )
+        .map_err(|e| format!("Failed to read journal: {}", e))?;
+
+    let mut posted: BTreeMap<(SourceType, i64), PostedSource> = BTreeMap::new();
+    for row in rows {
+        let (source_type, source_id, source_number, account_id, net) =
+            row.map_err(|e| format!("Failed to read journal line: {}", e))?;
+        let source =
+            SourceType::parse(&source_type).ok_or_else(|| format!("Unknown journal source type '{}'", source_type))?;
+        let entry = posted.entry((source, source_id)).or_default();
+        entry.source_number = source_number.unwrap_or_default();
+        entry.balances.insert(account_id, net);
+    }
+    Ok(posted)
+}
+
+/// JE-YYYYMMDD-NNNN, numbered per day
+fn next_entry_number(tx: &Transaction, date: &str) -> Result<String, String> {
+    let prefix = format!("JE-{}-", date.replace('-', ""));
+    let last: Option<String> = tx
+        .query_row(
+            "SELECT entry_number FROM journal_entries WHERE entry_number GLOB ?1 || '[0-9][0-9][0-9][0-9]'
+             ORDER BY entry_number DESC LIMIT 1",
+            [&prefix],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to generate journal entry number: {}", e))?;
+
+    let next = last
+        .and_then(|number| number[prefix.len()..].parse::<u32>().ok())
+        .unwrap_or(0)
+        + 1;
+    Ok(format!("{}{:04}", prefix, next))
+}
+
+/// Header of a journal entry
+struct Entry<'a> {
+    source_type: SourceType,
+    source_id: i64,
+    source_number: &'a str,
+    date: &'a str,
+    time: &'a str,
+    description: String,
+}
+
+/// Post one balanced entry; lines are (account id, amount) with debits positive
+fn insert_entry(tx: &Transaction, entry: &Entry, lines: &[(i64, Money)], created_by: &str) -> Result<(), String> {
+    let total: Money = lines.iter().map(|(_, amount)| *amount).sum();
+    if !total.is_zero() {
+        return Err(format!(
+            "Journal entry for {} {} does not balance (off by Rs.{})",
+            entry.source_type.label(),
+            entry.source_number,
+            total
+        ));
+    }
+
+    let entry_number = next_entry_number(tx, entry.date)?;
+    tx.execute(
+        "INSERT INTO journal_entries (
+            entry_number, date, time, description, source_type, source_id, source_number, created_by
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            entry_number,
+            entry.date,
+            entry.time,
+            entry.description,
+            entry.source_type.as_str(),
+            entry.source_id,
+            entry.source_number,
+            created_by
+        ],
+    )
+    .map_err(|e| format!("Failed to create journal entry: {}", e))?;
+    let entry_id = tx.last_insert_rowid();
+
+    for &(account_id, amount) in lines {
+        tx.execute(
+            "INSERT INTO journal_lines (journal_entry_id, account_id, debit, credit) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                entry_id,
+                account_id,
+                amount.max(Money::ZERO).to_rupees(),
+                (-amount).max(Money::ZERO).to_rupees()
+            ],
+        )
+        .map_err(|e| format!("Failed to post journal line: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Bring the journal up to date for every document, or for one document
+fn sync_documents(tx: &Transaction, filter: Option<(SourceType, i64)>) -> Result<JournalSyncResult, String> {
+    let accounts = Accounts::load(tx)?;
+    let mut posted = load_posted(tx, filter)?;
+    let today = current_date(tx).map_err(|e| format!("Failed to read date: {}", e))?;
+    let now = current_time(tx).map_err(|e| format!("Failed to read time: {}", e))?;
+    let mut result = JournalSyncResult::default();
+
+    let sources: Vec<SourceType> = match filter {
+        Some((source, _)) => vec![source],
+        None => SourceType::ALL.to_vec(),
+    };
+    for source in sources {
+        for document in load_documents(tx, source, filter.map(|(_, id)| id))? {
+            result.documents_checked += 1;
+            let expected = accounts.expected(&document)?;
+            let already = posted.remove(&(source, document.id)).unwrap_or_default();
+            let label = format!("{} {}", source.label(), document.number);
+            let first_posting = already.balances.values().all(|amount| amount.is_zero());
+
+            let entry = if first_posting {
+                Entry {
+                    source_type: source,
+                    source_id: document.id,
+                    source_number: &document.number,
+                    date: &document.date,
+                    time: &document.time,
+                    description: match &document.counterparty {
+                        Some(counterparty) if !counterparty.is_empty() => format!("{} - {}", label, counterparty),
+                        _ => label,
+                    },
+                }
+            } else {
+                Entry {
+                    source_type: source,
+                    source_id: document.id,
+                    source_number: &document.number,
+                    date: &today,
+                    time: &now,
+                    description: if expected.values().all(|amount| amount.is_zero()) {
+                        format!("{} reversed", label)
+                    } else {
+                        format!("{} revised", label)
+                    },
+                }
+            };
+            if post_difference(tx, &entry, &expected, &already.balances)? {
+                if first_posting {
+                    result.entries_posted += 1;
+                } else {
+                    result.corrections_posted += 1;
+                }
+            }
+        }
+    }
+
+    // Whatever is left was posted for documents that no longer exist,
+    // except invoices moved to a year archive, which keep their postings
+    for ((source, source_id), already) in posted {
+        if source == SourceType::Invoice && moved_to_archive(tx, source_id)? {
+            continue;
+        }
+        let entry = Entry {
+            source_type: source,
+            source_id,
+            source_number: &already.source_number,
+            date: &today,
+            time: &now,
+            description: format!("{} {} deleted", source.label(), already.source_number),
+        };
+        if post_difference(tx, &entry, &BTreeMap::new(), &already.balances)? {
+            result.corrections_posted += 1;
+        }
+    }
+
+    Ok(result)
+}
+
+/// Whether an invoice's first posting falls in a fiscal year moved to its archive
+fn moved_to_archive(conn: &Connection, invoice_id: i64) -> Result<bool, String> {
+    if !table_exists(conn, "archive_moves").map_err(|e| format!("Failed to inspect schema: {}", e))? {
+        return Ok(false);
+    }
+    conn.query_row(
+        "SELECT EXISTS (
+            SELECT 1 FROM archive_moves m JOIN fiscal_years y ON y.id = m.fiscal_year_id
+            WHERE (SELECT MIN(date) FROM journal_entries WHERE source_type = ?1 AND source_id = ?2)
+                  BETWEEN y.start_date AND y.end_date)",
+        params![SourceType::Invoice.as_str(), invoice_id],
+        |row| row.get(0),
+    )
+    .map_err(|e| format!("Failed to check archived invoice: {}", e))
+}
+
+/// Expected minus posted per account, leaving out accounts that agree
+fn difference(expected: &BTreeMap<i64, Money>, posted: &BTreeMap<i64, Money>) -> Vec<(i64, Money)> {
+    let mut difference: BTreeMap<i64, Money> = expected.clone();
+    for (account_id, amount) in posted {
+        *difference.entry(*account_id).or_default() -= *amount;
+    }
+    difference.into_iter().filter(|(_, amount)| !amount.is_zero()).collect()
+}
+
+/// Post expected minus posted per account; returns whether anything was posted
+fn post_difference(
+    tx: &Transaction,
+    entry: &Entry,
+    expected: &BTreeMap<i64, Money>,
+    posted: &BTreeMap<i64, Money>,
+) -> Result<bool, String> {
+    let lines = difference(expected, posted);
+    if lines.is_empty() {
+        return Ok(false);
+    }
+    insert_entry(tx, entry, &lines, "system")?;
+    Ok(true)
+}
+
+/// Post one business document to the journal within the caller's transaction
+pub fn post_document_in_transaction(
+    tx: &Transaction,
+    source: SourceType,
+    id: i64,
+) -> Result<JournalSyncResult, String> {
+    sync_documents(tx, Some((source, id)))
+}
+
+/// Post every business document, reversing postings of deleted ones
+pub fn sync_journal_in_transaction(tx: &Transaction) -> Result<JournalSyncResult, String> {
+    sync_documents(tx, None)
+}
+
+/// Documents whose postings up to `as_of` would change if the journal were
+/// synced now, worked out the way `sync_journal` does but without posting
+pub fn unposted_documents(conn: &Connection, as_of: Option<&str>) -> Result<Vec<UnpostedDocument>, String> {
+    let accounts = Accounts::read(conn)?;
+    let mut posted = load_posted(conn, None)?;
+    let today = current_date(conn).map_err(|e| format!("Failed to read date: {}", e))?;
+    let debits = |lines: &[(i64, Money)]| -> Money { lines.iter().map(|&(_, amount)| amount.max(Money::ZERO)).sum() };
+    let mut unposted = Vec::new();
+
+    for source in SourceType::ALL {
+        for document in load_documents(conn, source, None)? {
+            let already = posted.remove(&(source, document.id)).unwrap_or_default();
+            let lines = difference(&accounts.expected(&document)?, &already.balances);
+            if lines.is_empty() {
+                continue;
+            }
+            let first_posting = already.balances.values().all(|amount| amount.is_zero());
+            unposted.push(UnpostedDocument {
+                source_type: source.as_str(),
+                source_id: document.id,
+                number: document.number,
+                date: if first_posting { document.date } else { today.clone() },
+                amount: debits(&lines),
+            });
+        }
+    }
+    for ((source, source_id), already) in posted {
+        let lines = difference(&BTreeMap::new(), &already.balances);
+        if lines.is_empty() || (source == SourceType::Invoice && moved_to_archive(conn, source_id)?) {
+            continue;
+        }
+        unposted.push(UnpostedDocument {
+            source_type: source.as_str(),
+            source_id,
+            number: already.source_number,
+            date: today.clone(),
+            amount: debits(&lines),
+        });
+    }
+
+    unposted.retain(|document| as_of.map_or(true, |as_of| document.date.as_str() <= as_of));
+    Ok(unposted)
+}
+
+/// Account balances up to and including `as_of` (all dates when None)
+pub fn trial_balance(conn: &Connection, as_of: Option<&str>) -> Result<TrialBalance, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT a.id, a.code, a.name, a.account_type, a.payment_channel_id,
+                    COALESCE(t.debit, 0), COALESCE(t.credit, 0)
+             FROM gl_accounts a
+             LEFT JOIN (SELECT l.account_id, {debit} AS debit, {credit} AS credit
+                        FROM journal_lines l
+                        JOIN journal_entries e ON e.id = l.journal_entry_id
+                        WHERE ?1 IS NULL OR e.date <= ?1
+                        GROUP BY l.account_id) t ON t.account_id = a.id
+             ORDER BY a.code",
+            debit = sum_paisa_sql(&paisa(conn, "journal_lines", "l.debit")?),
+            credit = sum_paisa_sql(&paisa(conn, "journal_lines", "l.credit")?),
+        ))
+        .map_err(|e| format!("Failed to query trial balance: {}", e))?;
+    let rows = stmt
+        .query_map([as_of], |row| {
+            let net = Money::from_paisa(row.get::<_, i64>(5)? - row.get::<_, i64>(6)?);
+            Ok(TrialBalanceLine {
+                account_id: row.get(0)?,
+                code: row.get(1)?,
+                name: row.get(2)?,
+                account_type: row.get(3)?,
+                payment_channel_id: row.get(4)?,
+                debit: net.max(Money::ZERO),
+                credit: (-net).max(Money::ZERO),
+            })
+        })
+        .map_err(|e| format!("Failed to read trial balance: {}", e))?;
+    let lines: Vec<TrialBalanceLine> = rows
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read trial balance line: {}", e))?;
+
+    let unbalanced_entries: Vec<String> = {
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT e.entry_number
+                 FROM journal_entries e
+                 JOIN journal_lines l ON l.journal_entry_id = e.id
+                 WHERE ?1 IS NULL OR e.date <= ?1
+                 GROUP BY e.id
+                 HAVING {} != {}
+                 ORDER BY e.id",
+                sum_paisa_sql(&paisa(conn, "journal_lines", "l.debit")?),
+                sum_paisa_sql(&paisa(conn, "journal_lines", "l.credit")?)
+            ))
+            .map_err(|e| format!("Failed to query journal entries: {}", e))?;
+        let rows = stmt
+            .query_map([as_of], |row| row.get(0))
+            .map_err(|e| format!("Failed to read journal entries: {}", e))?;
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read journal entry: {}", e))?
+    };
+
+    let total_debit: Money = lines.iter().map(|line| line.debit).sum();
+    let total_credit: Money = lines.iter().map(|line| line.credit).sum();
+    let difference = total_debit - total_credit;
+
+    Ok(TrialBalance {
+        as_of: as_of.map(str::to_string),
+        lines,
+        total_debit,
+        total_credit,
+        difference,
+        balanced: difference.is_zero() && unbalanced_entries.is_empty(),
+        unbalanced_entries,
+        unposted_documents: unposted_documents(conn, as_of)?,
+    })
+}
+
+/// Post every business document the journal has not caught up with yet
+#[tauri::command]
+pub async fn sync_journal() -> Result<JournalSyncResult, String> {
+    let mut conn = open_connection()?;
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let result = sync_journal_in_transaction(&tx)?;
+    audit::record(
+        &tx,
+        &AuditEvent::new("sync_journal", "journal")
+            .by(&session::current_username())
+            .after(json!(&result)),
+    )?;
+
+    tx.commit()
+        .map_err(|e| format!("Failed to commit journal sync: {}", e))?;
+
+    info!(
+        "[JOURNAL] Checked {} documents: {} entries and {} corrections posted",
+        result.documents_checked, result.entries_posted, result.corrections_posted
+    );
+    Ok(result)
+}
+
+/// Trial balance as of a date (YYYY-MM-DD, all dates when omitted), read
+/// from the journal as posted. Reading takes no write lock; documents entered
+/// since the last posting are listed beside it and count once `sync_journal`
+/// has run.
+#[tauri::command]
+pub async fn get_trial_balance(as_of: Option<String>) -> Result<TrialBalance, String> {
+    let as_of = as_of
+        .map(|date| date.trim().to_string())
+        .filter(|date| !date.is_empty());
+
+    let mut conn = open_connection()?;
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Deferred)
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let report = trial_balance(&tx, as_of.as_deref())?;
+    tx.finish()
+        .map_err(|e| format!("Failed to finish reading the trial balance: {}", e))?;
+
+    if !report.unposted_documents.is_empty() {
+        info!(
+            "[JOURNAL] Trial balance leaves out {} documents not yet posted",
+            report.unposted_documents.len()
+        );
+    }
+    if !report.balanced {
+        warn!(
+            "[JOURNAL] Trial balance is off by Rs.{} ({} unbalanced entries)",
+            report.difference,
+            report.unbalanced_entries.len()
+        );
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rs(rupees: i64) -> Money {
+        Money::from_paisa(rupees * 100)
+    }
+
+    /// The frontend's columns the invoice and payment postings read
+    fn database() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE products (id INTEGER PRIMARY KEY, name TEXT, unit_type TEXT, cost_price REAL);
+            CREATE TABLE invoices (
+                id INTEGER PRIMARY KEY, bill_number TEXT, customer_name TEXT, grand_total REAL,
+                status TEXT, date TEXT, time TEXT
+            );
+            CREATE TABLE invoice_items (
+                id INTEGER PRIMARY KEY, invoice_id INTEGER, product_id INTEGER, quantity TEXT, cost_price REAL,
+                is_non_stock_item INTEGER DEFAULT 0, is_misc_item INTEGER DEFAULT 0
+            );
+            CREATE TABLE payment_channels (id INTEGER PRIMARY KEY, name TEXT, is_active INTEGER DEFAULT 1);
+            CREATE TABLE payments (
+                id INTEGER PRIMARY KEY, payment_number TEXT, payment_code TEXT, customer_name TEXT,
+                vendor_id INTEGER, amount REAL, payment_type TEXT DEFAULT 'incoming',
+                payment_channel_id INTEGER, status TEXT DEFAULT 'completed', date TEXT, time TEXT
+            );
+            INSERT INTO products (id, name, unit_type, cost_price) VALUES (1, 'Pipe', 'piece', 300);
+            INSERT INTO payment_channels (id, name) VALUES (1, 'Meezan Bank');
+            INSERT INTO invoices (id, bill_number, customer_name, grand_total, status, date, time)
+                VALUES (1, 'I00001', 'Ali', 1000, 'partially_paid', '2026-03-01', '10:00:00');
+            INSERT INTO invoice_items (invoice_id, product_id, quantity, cost_price) VALUES (1, 1, '2', 0);
+            INSERT INTO payments (id, payment_number, customer_name, amount, payment_channel_id, date, time)
+                VALUES (1, 'PAY-1', 'Ali', 400, 1, '2026-03-01', '10:05:00');",
+        )
+        .unwrap();
+        ensure_schema(&conn).unwrap();
+        conn
+    }
+
+    fn sync(conn: &mut Connection) -> JournalSyncResult {
+        let tx = conn.transaction().unwrap();
+        let result = sync_journal_in_transaction(&tx).unwrap();
+        tx.commit().unwrap();
+        result
+    }
+
+    /// Net debit of an account in a trial balance
+    fn net(report: &TrialBalance, code: &str) -> Money {
+        let line = report.lines.iter().find(|line| line.code == code).unwrap();
+        line.debit - line.credit
+    }
+
+    #[test]
+    fn posted_documents_balance_and_post_once() {
+        let mut conn = database();
+        let before = trial_balance(&conn, None).unwrap();
+        assert_eq!(before.total_debit, Money::ZERO);
+        let unposted: Vec<_> = before
+            .unposted_documents
+            .iter()
+            .map(|document| (document.number.as_str(), document.date.as_str(), document.amount))
+            .collect();
+        assert_eq!(
+            unposted,
+            [("I00001", "2026-03-01", rs(1600)), ("PAY-1", "2026-03-01", rs(400))]
+        );
+        assert!(trial_balance(&conn, Some("2026-02-28"))
+            .unwrap()
+            .unposted_documents
+            .is_empty());
+
+        let result = sync(&mut conn);
+        assert_eq!((result.documents_checked, result.entries_posted), (2, 2));
+        let report = trial_balance(&conn, None).unwrap();
+        assert_eq!(net(&report, RECEIVABLES_ACCOUNT), rs(600));
+        assert_eq!(net(&report, SALES_ACCOUNT), -rs(1000));
+        assert_eq!(net(&report, COGS_ACCOUNT), rs(600));
+        assert_eq!(net(&report, INVENTORY_ACCOUNT), -rs(600));
+        assert_eq!(net(&report, "1100-1"), rs(400));
+        assert_eq!((report.total_debit, report.total_credit), (rs(1600), rs(1600)));
+        assert!(report.balanced && report.unbalanced_entries.is_empty());
+        assert!(report.unposted_documents.is_empty());
+
+        // Posting again finds nothing to do
+        let again = sync(&mut conn);
+        assert_eq!((again.entries_posted, again.corrections_posted), (0, 0));
+    }
+
+    #[test]
+    fn cancelled_and_deleted_documents_reverse_today() {
+        let mut conn = database();
+        sync(&mut conn);
+        let today = current_date(&conn).unwrap();
+        conn.execute_batch(
+            "UPDATE invoices SET status = 'cancelled' WHERE id = 1;
+             DELETE FROM payments WHERE id = 1;",
+        )
+        .unwrap();
+
+        let report = trial_balance(&conn, None).unwrap();
+        assert_eq!(net(&report, SALES_ACCOUNT), -rs(1000));
+        let unposted: Vec<_> = report
+            .unposted_documents
+            .iter()
+            .map(|document| (document.source_type, document.date.as_str(), document.amount))
+            .collect();
+        assert_eq!(
+            unposted,
+            [
+                ("invoice", today.as_str(), rs(1600)),
+                ("customer_payment", today.as_str(), rs(400))
+            ]
+        );
+        // Reversals post today, so a trial balance of the invoice's day is already complete
+        assert!(trial_balance(&conn, Some("2026-03-01"))
+            .unwrap()
+            .unposted_documents
+            .is_empty());
+
+        assert_eq!(sync(&mut conn).corrections_posted, 2);
+        let report = trial_balance(&conn, None).unwrap();
+        assert!(report
+            .lines
+            .iter()
+            .all(|line| line.debit.is_zero() && line.credit.is_zero()));
+        assert!(report.balanced && report.unposted_documents.is_empty());
+        // The original postings still stand on their own date
+        let march = trial_balance(&conn, Some("2026-03-01")).unwrap();
+        assert_eq!(net(&march, SALES_ACCOUNT), -rs(1000));
+        assert!(march.balanced);
+    }
+}
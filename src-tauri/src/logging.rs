@@ -0,0 +1,611 @@
+/*!
+ * LOG FILES
+ *
+ * Every diagnostic goes through the `log` crate into `<app data>/logs/app.log`,
+ * one line per record:
+ *
+ *   2026-01-15 18:04:11.207 +05:00 WARN  sync: [SYNC] Peer request failed: ...
+ *
+ * The file rolls over at 5 MB and the four previous files are kept as
+ * `app.log.1` (newest) to `app.log.4`, so a shop terminal never holds more
+ * than 25 MB of logs. Records written before the data directory is known are
+ * held in memory and written when the file is attached.
+ *
+ * Levels are chosen per module with a filter such as `info,sync=debug,api=warn`
+ * taken from the `ITTEHAD_LOG` environment variable, else the first line of
+ * `logs/filter.txt`, else `info` for the app and `warn` for libraries.
+ *
+ * Passwords, tokens and API keys are masked before a line reaches the file or
+ * the console, whatever module wrote it.
+ */
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use rusqlite::Connection;
+use serde::Serialize;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const LOG_FILE_NAME: &str = "app.log";
+const MAX_FILE_BYTES: u64 = 5 * 1024 * 1024;
+const ROTATED_FILES: usize = 4;
+const FILTER_ENV: &str = "ITTEHAD_LOG";
+const FILTER_FILE_NAME: &str = "filter.txt";
+const DEFAULT_FILTER: &str = "warn,ittehad_iron_store=info";
+/// Records kept in memory until the log file is attached
+const MAX_PENDING: usize = 1000;
+
+/// Names whose values are masked wherever they appear as `name=value`,
+/// `name: value` or `"name":"value"`
+const SECRET_NAMES: &[&str] = &[
+    "password",
+    "passwd",
+    "pwd",
+    "secret",
+    "token",
+    "api_key",
+    "apikey",
+    "x-api-key",
+    "bearer",
+];
+const MASK: &str = "***";
+
+static LOGGER: OnceLock<FileLogger> = OnceLock::new();
+
+struct FileLogger {
+    /// Mirror records to stderr; off for the headless commands, whose console
+    /// output is their result
+    console: bool,
+    /// Seconds east of UTC, read once at startup
+    utc_offset: i64,
+    state: Mutex<LoggerState>,
+}
+
+struct LoggerState {
+    filter: Filter,
+    filter_from_env: bool,
+    dir: Option<PathBuf>,
+    file: Option<File>,
+    size: u64,
+    pending: Vec<String>,
+}
+
+/// Install the logger. Safe to call more than once; only the first call counts.
+pub fn init(console: bool) {
+    let (filter, filter_from_env) = match std::env::var(FILTER_ENV) {
+        Ok(spec) if !spec.trim().is_empty() => (Filter::parse(&spec), true),
+        _ => (Filter::parse(DEFAULT_FILTER), false),
+    };
+
+    let logger = FileLogger {
+        console,
+        utc_offset: local_utc_offset(),
+        state: Mutex::new(LoggerState {
+            filter,
+            filter_from_env,
+            dir: None,
+            file: None,
+            size: 0,
+            pending: Vec::new(),
+        }),
+    };
+
+    if LOGGER.set(logger).is_err() {
+        return;
+    }
+    if let Some(logger) = LOGGER.get() {
+        if log::set_logger(logger).is_ok() {
+            log::set_max_level(logger.max_level());
+        }
+    }
+
+    // A panic in a command thread would otherwise vanish with the console
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        log::error!("[PANIC] {}", info);
+        default_hook(info);
+    }));
+}
+
+/// Start writing to `<dir>/app.log`, flushing anything logged so far
+pub fn attach_dir(dir: &Path) -> Result<(), String> {
+    let logger = LOGGER.get().ok_or("Logging is not initialized")?;
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create log directory {}: {}", dir.display(), e))?;
+
+    {
+        let mut state = logger.lock();
+        if !state.filter_from_env {
+            if let Some(spec) = read_filter_file(dir) {
+                state.filter = Filter::parse(&spec);
+            }
+        }
+        state.dir = Some(dir.to_path_buf());
+        state.open_file()?;
+
+        let pending = std::mem::take(&mut state.pending);
+        for line in pending {
+            state.write_line(&line);
+        }
+    }
+    log::set_max_level(logger.max_level());
+
+    log::info!("[LOG] Writing logs to {}", dir.join(LOG_FILE_NAME).display());
+    Ok(())
+}
+
+/// Directory of the log files, once attached
+pub fn log_dir() -> Option<PathBuf> {
+    LOGGER.get().and_then(|logger| logger.lock().dir.clone())
+}
+
+fn read_filter_file(dir: &Path) -> Option<String> {
+    let contents = fs::read_to_string(dir.join(FILTER_FILE_NAME)).ok()?;
+    contents
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+}
+
+impl FileLogger {
+    fn lock(&self) -> std::sync::MutexGuard<'_, LoggerState> {
+        // A panic while logging must not silence every later record
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        self.lock().filter.max_level()
+    }
+
+    fn format(&self, record: &Record) -> String {
+        let message = redact(&record.args().to_string());
+        // Continuation lines are indented so every record starts with a timestamp
+        let message = message.replace('\n', "\n\t");
+        format!(
+            "{} {:<5} {}: {}",
+            timestamp(self.utc_offset),
+            record.level(),
+            short_target(record.target()),
+            message
+        )
+    }
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.lock().filter.enabled(metadata.target(), metadata.level())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = self.format(record);
+        if self.console {
+            eprintln!("{}", line);
+        }
+
+        let mut state = self.lock();
+        if state.file.is_some() {
+            state.write_line(&line);
+        } else if state.pending.len() < MAX_PENDING {
+            state.pending.push(line);
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(file) = self.lock().file.as_mut() {
+            let _ = file.flush();
+        }
+    }
+}
+
+impl LoggerState {
+    fn current_path(&self) -> Option<PathBuf> {
+        self.dir.as_ref().map(|dir| dir.join(LOG_FILE_NAME))
+    }
+
+    fn open_file(&mut self) -> Result<(), String> {
+        let Some(path) = self.current_path() else {
+            return Ok(());
+        };
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| format!("Failed to open log file {}: {}", path.display(), e))?;
+        self.size = file.metadata().map(|m| m.len()).unwrap_or(0);
+        self.file = Some(file);
+        Ok(())
+    }
+
+    fn write_line(&mut self, line: &str) {
+        if self.size >= MAX_FILE_BYTES {
+            self.rotate();
+        }
+        let Some(file) = self.file.as_mut() else {
+            return;
+        };
+        // Logging must never fail the operation being logged
+        if writeln!(file, "{}", line).is_ok() {
+            self.size += line.len() as u64 + 1;
+        }
+    }
+
+    /// app.log -> app.log.1 -> ... -> app.log.4, dropping the oldest
+    fn rotate(&mut self) {
+        let Some(dir) = self.dir.clone() else {
+            return;
+        };
+        self.file = None;
+        let _ = fs::remove_file(rotated_path(&dir, ROTATED_FILES));
+        for index in (1..ROTATED_FILES).rev() {
+            let _ = fs::rename(rotated_path(&dir, index), rotated_path(&dir, index + 1));
+        }
+        let _ = fs::rename(dir.join(LOG_FILE_NAME), rotated_path(&dir, 1));
+        if let Err(e) = self.open_file() {
+            eprintln!("{}", e);
+        }
+    }
+}
+
+fn rotated_path(dir: &Path, index: usize) -> PathBuf {
+    dir.join(format!("{}.{}", LOG_FILE_NAME, index))
+}
+
+/// The current file first, then `app.log.1` to `app.log.4`
+pub fn log_files(dir: &Path) -> Vec<PathBuf> {
+    std::iter::once(dir.join(LOG_FILE_NAME))
+        .chain((1..=ROTATED_FILES).map(|index| rotated_path(dir, index)))
+        .filter(|path| path.exists())
+        .collect()
+}
+
+/// `ittehad_iron_store::sync` -> `sync`; library targets are kept whole
+fn short_target(target: &str) -> &str {
+    target
+        .strip_prefix(env!("CARGO_CRATE_NAME"))
+        .and_then(|rest| rest.strip_prefix("::"))
+        .unwrap_or(target)
+}
+
+/// Per-module levels parsed from `default,module=level,...`
+struct Filter {
+    default: LevelFilter,
+    modules: Vec<(String, LevelFilter)>,
+}
+
+impl Filter {
+    /// Unknown levels and empty parts are ignored rather than rejected, so a
+    /// typo in filter.txt can never stop the app from starting
+    fn parse(spec: &str) -> Filter {
+        let mut filter = Filter {
+            default: LevelFilter::Info,
+            modules: Vec::new(),
+        };
+        for part in spec.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+            match part.split_once('=') {
+                Some((module, level)) => {
+                    if let Ok(level) = level.trim().parse() {
+                        filter.modules.push((module.trim().to_string(), level));
+                    }
+                }
+                None => {
+                    if let Ok(level) = part.parse() {
+                        filter.default = level;
+                    }
+                }
+            }
+        }
+        filter
+    }
+
+    /// The most specific matching module wins: `sync` matches the app's
+    /// `ittehad_iron_store::sync`, `tauri` matches every `tauri::...` target
+    fn level_for(&self, target: &str) -> LevelFilter {
+        let short = short_target(target);
+        self.modules
+            .iter()
+            .filter(|(module, _)| {
+                [target, short]
+                    .iter()
+                    .any(|name| *name == module || name.starts_with(&format!("{}::", module)))
+            })
+            .max_by_key(|(module, _)| module.len())
+            .map(|(_, level)| *level)
+            .unwrap_or(self.default)
+    }
+
+    fn enabled(&self, target: &str, level: Level) -> bool {
+        level <= self.level_for(target)
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        self.modules
+            .iter()
+            .map(|(_, level)| *level)
+            .chain(std::iter::once(self.default))
+            .max()
+            .unwrap_or(self.default)
+    }
+}
+
+/// Mask the values of secret-looking names and any `isk_` API key
+pub fn redact(message: &str) -> String {
+    let lower = message.to_ascii_lowercase();
+    let bytes = message.as_bytes();
+    let mut masked: Vec<(usize, usize)> = Vec::new();
+
+    for name in SECRET_NAMES {
+        let mut from = 0;
+        while let Some(found) = lower[from..].find(name) {
+            let start = from + found;
+            from = start + name.len();
+            // Whole names only: `pwd` must not match inside `pwdhash_column`
+            let before = lower[..start].chars().next_back();
+            if before.is_some_and(|c| c.is_ascii_alphanumeric() || c == '_') {
+                continue;
+            }
+            let mut i = from;
+            // `"password": "x"`, `password=x`, `Bearer x`
+            if bytes.get(i) == Some(&b'"') || bytes.get(i) == Some(&b'\'') {
+                i += 1;
+            }
+            while bytes.get(i) == Some(&b' ') {
+                i += 1;
+            }
+            let separated = matches!(bytes.get(i), Some(b'=') | Some(b':'));
+            if separated {
+                i += 1;
+            } else if *name != "bearer" || i == from {
+                continue;
+            }
+            while bytes.get(i) == Some(&b' ') {
+                i += 1;
+            }
+            if bytes.get(i) == Some(&b'"') || bytes.get(i) == Some(&b'\'') {
+                i += 1;
+            }
+            let value_end = value_end(bytes, i);
+            if value_end > i {
+                masked.push((i, value_end));
+            }
+        }
+    }
+
+    let mut from = 0;
+    while let Some(found) = lower[from..].find("isk_") {
+        let start = from + found + "isk_".len();
+        from = start;
+        // `disk_space` is not a key
+        let before = lower[..start - "isk_".len()].chars().next_back();
+        if before.is_some_and(|c| c.is_ascii_alphanumeric() || c == '_') {
+            continue;
+        }
+        let end = value_end(bytes, start);
+        if end > start {
+            masked.push((start, end));
+        }
+    }
+
+    if masked.is_empty() {
+        return message.to_string();
+    }
+    masked.sort();
+    let mut result = String::with_capacity(message.len());
+    let mut copied = 0;
+    for (start, end) in masked {
+        if start < copied {
+            continue;
+        }
+        result.push_str(&message[copied..start]);
+        result.push_str(MASK);
+        copied = end;
+    }
+    result.push_str(&message[copied..]);
+    result
+}
+
+/// A value runs to the next space, quote or separator
+fn value_end(bytes: &[u8], start: usize) -> usize {
+    let mut end = start;
+    while let Some(&byte) = bytes.get(end) {
+        if byte.is_ascii_whitespace() || matches!(byte, b'"' | b'\'' | b',' | b'&' | b';' | b'}' | b')') {
+            break;
+        }
+        end += 1;
+    }
+    end
+}
+
+/// Seconds east of UTC from SQLite's view of local time, which is the same
+/// clock every stored date and time is written with
+fn local_utc_offset() -> i64 {
+    Connection::open_in_memory()
+        .and_then(|conn| {
+            conn.query_row(
+                "SELECT CAST(strftime('%s', 'now', 'localtime') AS INTEGER) - CAST(strftime('%s', 'now') AS INTEGER)",
+                [],
+                |row| row.get(0),
+            )
+        })
+        .unwrap_or(0)
+}
+
+/// `YYYY-MM-DD HH:MM:SS.mmm +HH:MM` in local time
+fn timestamp(utc_offset: i64) -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let local = now.as_secs() as i64 + utc_offset;
+    let (days, seconds) = (local.div_euclid(86_400), local.rem_euclid(86_400));
+
+    // Days since 1970-01-01 to a civil date (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    let sign = if utc_offset < 0 { '-' } else { '+' };
+    let offset = utc_offset.abs();
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:03} {}{:02}:{:02}",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60,
+        now.subsec_millis(),
+        sign,
+        offset / 3600,
+        offset % 3600 / 60
+    )
+}
+
+#[derive(Debug, Serialize)]
+pub struct LogLine {
+    pub timestamp: String,
+    pub level: String,
+    pub module: String,
+    pub message: String,
+}
+
+fn parse_line(line: &str) -> Option<LogLine> {
+    // date, time, offset, level, then `module: message`
+    let mut parts = line.splitn(5, ' ');
+    let (date, time, offset) = (parts.next()?, parts.next()?, parts.next()?);
+    let level = parts.next()?;
+    let rest = parts.next()?.trim_start();
+    level.parse::<Level>().ok()?;
+    let (module, message) = rest.split_once(": ")?;
+    Some(LogLine {
+        timestamp: format!("{} {} {}", date, time, offset),
+        level: level.to_string(),
+        module: module.to_string(),
+        message: message.to_string(),
+    })
+}
+
+/// The newest `limit` records at `min_level` or above, oldest first
+pub fn recent_lines(
+    dir: &Path,
+    limit: usize,
+    min_level: Option<Level>,
+    module: Option<&str>,
+) -> Result<Vec<LogLine>, String> {
+    let mut newest_first: Vec<LogLine> = Vec::new();
+
+    for path in log_files(dir) {
+        let file = File::open(&path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        let mut records: Vec<LogLine> = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            match (line.strip_prefix('\t'), records.last_mut()) {
+                (Some(continuation), Some(record)) => {
+                    record.message.push('\n');
+                    record.message.push_str(continuation);
+                }
+                _ => records.extend(parse_line(&line)),
+            }
+        }
+
+        let wanted = records.into_iter().rev().filter(|record| {
+            let level_ok = match (min_level, record.level.parse::<Level>()) {
+                (Some(min), Ok(level)) => level <= min,
+                _ => true,
+            };
+            level_ok && module.map_or(true, |module| record.module.starts_with(module))
+        });
+        newest_first.extend(wanted.take(limit - newest_first.len()));
+        if newest_first.len() >= limit {
+            break;
+        }
+    }
+
+    newest_first.reverse();
+    Ok(newest_first)
+}
+
+/// Recent log records for the diagnostics screen. `min_level` is one of
+/// error, warn, info, debug, trace; `module` narrows to e.g. `sync`.
+#[tauri::command]
+pub async fn get_recent_logs(
+    limit: Option<usize>,
+    min_level: Option<String>,
+    module: Option<String>,
+) -> Result<Vec<LogLine>, String> {
+    let dir = log_dir().ok_or("Log files are not available")?;
+    let min_level = match min_level.as_deref().map(str::trim).filter(|level| !level.is_empty()) {
+        Some(level) => Some(
+            level
+                .parse::<Level>()
+                .map_err(|_| format!("Invalid log level: {}", level))?,
+        ),
+        None => None,
+    };
+    let module = module.as_deref().map(str::trim).filter(|module| !module.is_empty());
+    recent_lines(&dir, limit.unwrap_or(200).clamp(1, 5000), min_level, module)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_secret_values() {
+        let cases = [
+            ("login password=hunter2 ok", "login password=*** ok"),
+            (
+                r#"{"password":"hunter2","user":"ali"}"#,
+                r#"{"password":"***","user":"ali"}"#,
+            ),
+            ("Authorization: Bearer abc.def", "Authorization: Bearer ***"),
+            ("token: abc, Secret = xyz;", "token: ***, Secret = ***;"),
+            ("X-API-Key: 1234&next=1", "X-API-Key: ***&next=1"),
+            ("key isk_abcd1234 used", "key isk_*** used"),
+            ("صارف password=راز ok", "صارف password=*** ok"),
+        ];
+        for (message, expected) in cases {
+            assert_eq!(redact(message), expected, "{}", message);
+        }
+    }
+
+    #[test]
+    fn leaves_lookalikes_alone() {
+        for message in [
+            "pwdhash_column=5",
+            "disk_space=10 GB",
+            "password reset requested",
+            "bearer",
+            "token=",
+        ] {
+            assert_eq!(redact(message), message);
+        }
+    }
+
+    #[test]
+    fn filter_picks_the_most_specific_module() {
+        let filter = Filter::parse("warn, sync=debug, sync::peer=trace, tauri=error, bogus=loud,");
+        let sync = format!("{}::sync", env!("CARGO_CRATE_NAME"));
+        assert!(filter.enabled(&sync, Level::Debug) && !filter.enabled(&sync, Level::Trace));
+        assert!(filter.enabled(&format!("{}::peer", sync), Level::Trace));
+        assert!(!filter.enabled("tauri::window", Level::Warn));
+        assert!(filter.enabled("hyper", Level::Warn) && !filter.enabled("hyper", Level::Info));
+        assert_eq!(filter.max_level(), LevelFilter::Trace);
+    }
+
+    #[test]
+    fn parses_written_lines() {
+        let line = parse_line("2026-03-01 09:15:02.120 +05:00 WARN  sync: Peer unreachable: timed out").unwrap();
+        assert_eq!(line.timestamp, "2026-03-01 09:15:02.120 +05:00");
+        assert_eq!((line.level.as_str(), line.module.as_str()), ("WARN", "sync"));
+        assert_eq!(line.message, "Peer unreachable: timed out");
+        assert!(parse_line("\tcontinuation of a message").is_none());
+    }
+}
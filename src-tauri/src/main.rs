@@ -3,67 +3,61 @@
     windows_subsystem = "windows"
 )]
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::fs;
 use std::time::Duration;
 use std::thread;
+use log::{error, info, warn};
 use rusqlite::Connection;
 use tauri_plugin_sql::{Builder, Migration, MigrationKind};
 
 mod windows_support;
 use windows_support::*;
 
-#[derive(serde::Serialize)]
-struct AuthResult {
-    success: bool,
-    role: String,
-    id: String,
-}
-
-#[tauri::command]
-async fn authenticate_user(username: String, password: String) -> Result<AuthResult, String> {
-    println!("Authentication attempt: {} / {}", username, password);
-    
-    if username == "admin" && password == "admin123" {
-        Ok(AuthResult {
-            success: true,
-            role: "admin".to_string(),
-            id: "1".to_string(),
-        })
-    } else {
-        Ok(AuthResult {
-            success: false,
-            role: "worker".to_string(),
-            id: "0".to_string(),
-        })
-    }
-}
+mod api;
+mod archive;
+mod audit;
+mod change_events;
+mod cli;
+mod customer_balance;
+mod data_import;
+mod database;
+mod day_close;
+mod diagnostics;
+mod fiscal_year;
+mod invoice_cancellation;
+mod journal;
+mod logging;
+mod migrations;
+mod money;
+mod offline_update;
+mod quantity;
+mod recycle_bin;
+mod restart;
+mod returns;
+mod search;
+mod session;
+mod single_instance;
+mod stock_engine;
+mod sync;
+mod vendor_payables;
 
 /// PRODUCTION BACKUP COMMANDS
 /// For your file-based backup approach
 
 #[tauri::command]
 async fn create_backup_directory(relative_path: String) -> Result<String, String> {
-    println!("[BACKUP] Creating backup directory: {}", relative_path);
-    
-    let app_name = "com.itehadironstore.management";
+    info!("[BACKUP] Creating backup directory: {}", relative_path);
     
-    // Use production-grade Windows directory detection
-    let app_data_dir = if cfg!(target_os = "windows") {
-        get_windows_app_data_dir(app_name)?
-    } else {
-        std::env::var("HOME")
-            .map(|path| std::path::PathBuf::from(path).join(".local/share").join(app_name))
-            .map_err(|_| "Failed to get HOME directory".to_string())?
-    };
+    let app_data_dir = database::get_app_data_dir()?;
     
     let full_path = app_data_dir.join(&relative_path);
     
     // Check if path exists and is a file (not directory) - remove it
     if full_path.exists() && !full_path.is_dir() {
-        println!("[BACKUP] Removing existing file at: {}", full_path.display());
+        info!("[BACKUP] Removing existing file at: {}", full_path.display());
         if let Err(e) = std::fs::remove_file(&full_path) {
-            println!("[BACKUP] Warning: Could not remove existing file: {}", e);
+            warn!("[BACKUP] Warning: Could not remove existing file: {}", e);
         }
     }
     
@@ -71,12 +65,12 @@ async fn create_backup_directory(relative_path: String) -> Result<String, String
     match fs::create_dir_all(&full_path) {
         Ok(_) => {
             let path_str = full_path.to_string_lossy().to_string();
-            println!("[BACKUP] Directory created successfully: {}", path_str);
+            info!("[BACKUP] Directory created successfully: {}", path_str);
             Ok(path_str)
         }
         Err(e) => {
             let error_msg = format!("Failed to create directory {}: {}", full_path.display(), e);
-            println!("[BACKUP] {}", error_msg);
+            error!("[BACKUP] {}", error_msg);
             Err(error_msg)
         }
     }
@@ -84,16 +78,16 @@ async fn create_backup_directory(relative_path: String) -> Result<String, String
 
 #[tauri::command]
 async fn delete_backup_file(path: String) -> Result<(), String> {
-    println!("[BACKUP] Deleting file: {}", path);
+    info!("[BACKUP] Deleting file: {}", path);
     
     match fs::remove_file(&path) {
         Ok(_) => {
-            println!("[BACKUP] File deleted successfully: {}", path);
+            info!("[BACKUP] File deleted successfully: {}", path);
             Ok(())
         }
         Err(e) => {
             let error_msg = format!("Failed to delete file {}: {}", path, e);
-            eprintln!("[BACKUP] {}", error_msg);
+            error!("[BACKUP] {}", error_msg);
             Err(error_msg)
         }
     }
@@ -101,73 +95,62 @@ async fn delete_backup_file(path: String) -> Result<(), String> {
 
 #[tauri::command]
 async fn close_database_connections() -> Result<(), String> {
-    println!("[BACKUP] Request to close database connections received");
+    info!("[BACKUP] Request to close database connections received");
     
     // For SQLite WAL mode, we need to:
     // 1. Close any active connections
     // 2. Force a checkpoint to merge WAL into main database
     // 3. Wait for file locks to be released
     
-    let app_name = "com.itehadironstore.management";
-    
     // Get the database path
-    let app_data_dir = if cfg!(target_os = "windows") {
-        std::env::var("APPDATA")
-            .map_err(|_| "Failed to get APPDATA directory".to_string())?
-    } else {
-        std::env::var("HOME")
-            .map(|home| format!("{}/.local/share", home))
-            .map_err(|_| "Failed to get HOME directory".to_string())?
-    };
-    
-    let db_dir = PathBuf::from(&app_data_dir).join(app_name);
-    let db_path = db_dir.join("store-2025.db");
+    let db_dir = database::get_app_data_dir()?;
+    let db_path = db_dir.join(database::DB_FILE_NAME);
     
     if db_path.exists() {
-        println!("[BACKUP] Attempting to close connections and checkpoint WAL for: {:?}", db_path);
+        info!("[BACKUP] Attempting to close connections and checkpoint WAL for: {:?}", db_path);
         
         // Try to open a connection and perform aggressive checkpoint
         match Connection::open(&db_path) {
             Ok(conn) => {
                 // First, try to ensure all pending transactions are committed
                 match conn.execute("BEGIN IMMEDIATE; COMMIT;", []) {
-                    Ok(_) => println!("[BACKUP] Ensured all transactions committed"),
-                    Err(e) => println!("[BACKUP] Transaction commit check: {}", e),
+                    Ok(_) => info!("[BACKUP] Ensured all transactions committed"),
+                    Err(e) => info!("[BACKUP] Transaction commit check: {}", e),
                 }
                 
                 // Execute multiple checkpoint strategies to ensure WAL is fully merged
                 
                 // Strategy 1: TRUNCATE checkpoint (most aggressive)
                 match conn.execute("PRAGMA wal_checkpoint(TRUNCATE);", []) {
-                    Ok(_) => println!("[BACKUP] WAL TRUNCATE checkpoint completed successfully"),
-                    Err(e) => println!("[BACKUP] WAL TRUNCATE checkpoint failed: {}", e),
+                    Ok(_) => info!("[BACKUP] WAL TRUNCATE checkpoint completed successfully"),
+                    Err(e) => warn!("[BACKUP] WAL TRUNCATE checkpoint failed: {}", e),
                 }
                 
                 // Strategy 2: Wait and try RESTART checkpoint
                 thread::sleep(Duration::from_millis(100));
-                match conn.execute("PRAGMA wal_checkpoint(RESTART);", []) {
-                    Ok(_) => println!("[BACKUP] WAL RESTART checkpoint completed successfully"),
-                    Err(e) => println!("[BACKUP] WAL RESTART checkpoint failed: {}", e),
+                match conn.query_row("PRAGMA wal_checkpoint(RESTART);", [], |_| Ok(())) {
+                    Ok(_) => info!("[BACKUP] WAL RESTART checkpoint completed successfully"),
+                    Err(e) => warn!("[BACKUP] WAL RESTART checkpoint failed: {}", e),
                 }
                 
                 // Strategy 3: Force vacuum to ensure database integrity
                 match conn.execute("PRAGMA vacuum;", []) {
-                    Ok(_) => println!("[BACKUP] Database vacuum completed successfully"),
-                    Err(e) => println!("[BACKUP] Database vacuum failed (non-critical): {}", e),
+                    Ok(_) => info!("[BACKUP] Database vacuum completed successfully"),
+                    Err(e) => warn!("[BACKUP] Database vacuum failed (non-critical): {}", e),
                 }
                 
                 // Strategy 4: Final checkpoint
                 match conn.execute("PRAGMA wal_checkpoint(FULL);", []) {
-                    Ok(_) => println!("[BACKUP] Final WAL checkpoint completed successfully"),
-                    Err(e) => println!("[BACKUP] Final WAL checkpoint failed: {}", e),
+                    Ok(_) => info!("[BACKUP] Final WAL checkpoint completed successfully"),
+                    Err(e) => warn!("[BACKUP] Final WAL checkpoint failed: {}", e),
                 }
                 
                 // Close the connection explicitly
                 drop(conn);
-                println!("[BACKUP] Database connection closed after aggressive checkpoint");
+                info!("[BACKUP] Database connection closed after aggressive checkpoint");
             }
             Err(e) => {
-                println!("[BACKUP] Could not open database for checkpoint: {}", e);
+                warn!("[BACKUP] Could not open database for checkpoint: {}", e);
             }
         }
     }
@@ -175,48 +158,37 @@ async fn close_database_connections() -> Result<(), String> {
     // Add delay to allow file system to release locks
     thread::sleep(Duration::from_millis(500));
     
-    println!("[BACKUP] Database connections close request processed");
+    info!("[BACKUP] Database connections close request processed");
     Ok(())
 }
 
 #[tauri::command]
 async fn atomic_database_replace(backup_data: Vec<u8>) -> Result<(), String> {
-    println!("[BACKUP] Starting atomic database replacement");
-    
-    let app_name = "com.itehadironstore.management";
+    info!("[BACKUP] Starting atomic database replacement");
     
     // Get the database path
-    let app_data_dir = if cfg!(target_os = "windows") {
-        std::env::var("APPDATA")
-            .map_err(|_| "Failed to get APPDATA directory".to_string())?
-    } else {
-        std::env::var("HOME")
-            .map(|home| format!("{}/.local/share", home))
-            .map_err(|_| "Failed to get HOME directory".to_string())?
-    };
-    
-    let db_dir = PathBuf::from(&app_data_dir).join(app_name);
-    let db_path = db_dir.join("store-2025.db");
-    let temp_path = db_dir.join("store-2025.db.restore.tmp");
-    let backup_path = db_dir.join("store-2025.db.backup.tmp");
+    let db_dir = database::get_app_data_dir()?;
+    let db_path = db_dir.join(database::DB_FILE_NAME);
+    let temp_path = db_dir.join(format!("{}.restore.tmp", database::DB_FILE_NAME));
+    let backup_path = db_dir.join(format!("{}.backup.tmp", database::DB_FILE_NAME));
     
-    println!("[BACKUP] Database path: {:?}", db_path);
-    println!("[BACKUP] Writing backup data to temporary file...");
+    info!("[BACKUP] Database path: {:?}", db_path);
+    info!("[BACKUP] Writing backup data to temporary file...");
     
     // Step 1: Write the new data to a temporary file
     std::fs::write(&temp_path, &backup_data)
         .map_err(|e| format!("Failed to write temporary file: {}", e))?;
     
-    println!("[BACKUP] Temporary file written successfully ({} bytes)", backup_data.len());
+    info!("[BACKUP] Temporary file written successfully ({} bytes)", backup_data.len());
     
     // Step 2: Use production-grade Windows file replacement
     if cfg!(target_os = "windows") && db_path.exists() {
-        println!("[BACKUP] Using production-grade Windows file replacement...");
+        info!("[BACKUP] Using production-grade Windows file replacement...");
         
         // Use the enterprise-grade replacement function
         match windows_safe_file_replace(&temp_path, &db_path, &backup_path) {
             Ok(_) => {
-                println!("✅ [BACKUP] Database replacement completed successfully");
+                info!("[BACKUP] Database replacement completed successfully");
                 return Ok(());
             }
             Err(e) => {
@@ -227,7 +199,7 @@ async fn atomic_database_replace(backup_data: Vec<u8>) -> Result<(), String> {
     
     // Fallback for non-Windows or if Windows method fails
     if db_path.exists() {
-        println!("[BACKUP] Using fallback replacement method...");
+        info!("[BACKUP] Using fallback replacement method...");
         
         // Remove old backup if it exists
         if backup_path.exists() {
@@ -241,12 +213,12 @@ async fn atomic_database_replace(backup_data: Vec<u8>) -> Result<(), String> {
         for attempt in 1..=3 {
             match std::fs::rename(&db_path, &backup_path) {
                 Ok(_) => {
-                    println!("[BACKUP] Database moved to backup on attempt {}", attempt);
+                    info!("[BACKUP] Database moved to backup on attempt {}", attempt);
                     success = true;
                     break;
                 }
                 Err(e) => {
-                    println!("[BACKUP] Rename attempt {} failed: {}", attempt, e);
+                    warn!("[BACKUP] Rename attempt {} failed: {}", attempt, e);
                     if attempt < 3 {
                         thread::sleep(Duration::from_millis(500 * attempt as u64));
                     }
@@ -256,7 +228,7 @@ async fn atomic_database_replace(backup_data: Vec<u8>) -> Result<(), String> {
         
         // Strategy 2: If rename failed, try copy + delete with retries
         if !success {
-            println!("[BACKUP] Rename failed, trying copy + delete approach");
+            warn!("[BACKUP] Rename failed, trying copy + delete approach");
             
             // First, copy the file
             std::fs::copy(&db_path, &backup_path)
@@ -266,12 +238,12 @@ async fn atomic_database_replace(backup_data: Vec<u8>) -> Result<(), String> {
             for attempt in 1..=5 {
                 match std::fs::remove_file(&db_path) {
                     Ok(_) => {
-                        println!("[BACKUP] Original database deleted on attempt {}", attempt);
+                        info!("[BACKUP] Original database deleted on attempt {}", attempt);
                         success = true;
                         break;
                     }
                     Err(e) => {
-                        println!("[BACKUP] Delete attempt {} failed: {}", attempt, e);
+                        warn!("[BACKUP] Delete attempt {} failed: {}", attempt, e);
                         if attempt < 5 {
                             thread::sleep(Duration::from_millis(1000 * attempt as u64));
                         }
@@ -286,30 +258,31 @@ async fn atomic_database_replace(backup_data: Vec<u8>) -> Result<(), String> {
     }
     
     // Step 3: Move the temporary file to the database location with retries
-    println!("[BACKUP] Moving temporary file to database location...");
+    info!("[BACKUP] Moving temporary file to database location...");
     
     for attempt in 1..=5 {
         match std::fs::rename(&temp_path, &db_path) {
             Ok(_) => {
-                println!("[BACKUP] Database replacement completed successfully on attempt {}", attempt);
+                info!("[BACKUP] Database replacement completed successfully on attempt {}", attempt);
+                audit::forget_head(&db_path);
                 
                 // Clean up backup file
                 if backup_path.exists() {
                     let _ = std::fs::remove_file(&backup_path);
-                    println!("[BACKUP] Temporary backup file cleaned up");
+                    info!("[BACKUP] Temporary backup file cleaned up");
                 }
                 
                 return Ok(());
             }
             Err(e) => {
-                println!("[BACKUP] Move attempt {} failed: {}", attempt, e);
+                warn!("[BACKUP] Move attempt {} failed: {}", attempt, e);
                 if attempt < 5 {
                     thread::sleep(Duration::from_millis(1000 * attempt as u64));
                 } else {
                     // If all attempts failed, try to restore the backup
                     if backup_path.exists() {
                         let _ = std::fs::rename(&backup_path, &db_path);
-                        println!("[BACKUP] Restored original database from backup");
+                        info!("[BACKUP] Restored original database from backup");
                     }
                     return Err(format!("Failed to move temporary file after {} attempts: {}", attempt, e));
                 }
@@ -322,81 +295,87 @@ async fn atomic_database_replace(backup_data: Vec<u8>) -> Result<(), String> {
 
 #[tauri::command]
 async fn get_database_path() -> Result<String, String> {
-    let app_name = "com.itehadironstore.management"; // Use consistent app name
-    
-    // Use production-grade directory detection
-    let app_data_dir = if cfg!(target_os = "windows") {
-        get_windows_app_data_dir(app_name)?
-    } else {
-        std::env::var("HOME")
-            .map(|path| PathBuf::from(path).join(".local/share").join(app_name))
-            .map_err(|_| "Failed to get HOME directory".to_string())?
-    };
+    let app_data_dir = database::get_app_data_dir()?;
     
-    let db_path = app_data_dir.join("store-2025.db");
+    let db_path = app_data_dir.join(database::DB_FILE_NAME);
     Ok(db_path.to_string_lossy().to_string())
 }
 
 #[tauri::command]
 async fn startup_database_restore(backup_data: Vec<u8>) -> Result<(), String> {
-    println!("🔄 [STARTUP-RESTORE] Starting production-grade database restore at startup");
+    info!("[STARTUP-RESTORE] Starting production-grade database restore at startup");
     
-    let app_name = "com.itehadironstore.management";
-    let app_data_dir = if cfg!(target_os = "windows") {
-        get_windows_app_data_dir(app_name)?
-    } else {
-        dirs::data_dir()
-            .ok_or("Failed to get app data directory")?
-            .join(app_name)
-    };
+    let app_data_dir = database::get_app_data_dir()?;
     
-    let db_path = app_data_dir.join("store-2025.db");
+    let db_path = app_data_dir.join(database::DB_FILE_NAME);
     
     // At startup, database should not be locked
+    restore_database_file(&db_path, &backup_data)?;
+    
+    info!("[STARTUP-RESTORE] Database restored successfully at startup");
+    Ok(())
+}
+
+/// Replace the database file with `backup_data`, keeping the current file as
+/// `store.db.pre-restore-backup` next to it. Nothing may hold the database open.
+fn restore_database_file(db_path: &Path, backup_data: &[u8]) -> Result<(), String> {
     if db_path.exists() {
         // Create safety backup
-        let backup_path = app_data_dir.join("store-2025.db.pre-restore-backup");
-        std::fs::copy(&db_path, &backup_path)
+        let backup_path = db_path.with_file_name(format!("{}.pre-restore-backup", database::DB_FILE_NAME));
+        std::fs::copy(db_path, &backup_path)
             .map_err(|e| format!("Failed to create safety backup: {}", e))?;
-        println!("🛡️ [STARTUP-RESTORE] Created safety backup");
+        info!("[STARTUP-RESTORE] Created safety backup");
     }
     
     // Write new database (should work at startup - no locks)
-    std::fs::write(&db_path, backup_data)
+    std::fs::write(db_path, backup_data)
         .map_err(|e| format!("Failed to write restored database: {}", e))?;
-    
-    println!("✅ [STARTUP-RESTORE] Database restored successfully at startup");
+    audit::forget_head(db_path);
     Ok(())
 }
 
 #[tauri::command]
 async fn create_consistent_backup(backup_file_name: String) -> Result<serde_json::Value, String> {
-    println!("🔄 [CONSISTENT-BACKUP] Creating consistent database backup: {}", backup_file_name);
+    info!("[CONSISTENT-BACKUP] Creating consistent database backup: {}", backup_file_name);
     let start_time = std::time::Instant::now();
     
-    let app_name = "com.itehadironstore.management";
-    
-    // Get the database path
-    let app_data_dir = if cfg!(target_os = "windows") {
-        get_windows_app_data_dir(app_name)?
-    } else {
-        dirs::data_dir()
-            .ok_or("Failed to get app data directory")?
-            .join(app_name)
-    };
+    let app_data_dir = database::get_app_data_dir()?;
     
-    let db_path = app_data_dir.join("store-2025.db");
+    let db_path = app_data_dir.join(database::DB_FILE_NAME);
     let backup_dir = app_data_dir.join("backups");
     let backup_path = backup_dir.join(&backup_file_name);
     
+    let backup_size = consistent_backup(&db_path, &backup_path)?;
+    
+    // Fast checksum - only read first and last 64KB for speed
+    info!("[CONSISTENT-BACKUP] Calculating fast checksum...");
+    let checksum = calculate_fast_checksum(&backup_path)?;
+    
+    let total_duration = start_time.elapsed();
+    info!("[CONSISTENT-BACKUP] Total backup time: {:?}", total_duration);
+    
+    // Return JSON structure that TypeScript expects
+    Ok(serde_json::json!({
+        "success": true,
+        "size": backup_size,
+        "checksum": checksum
+    }))
+}
+
+/// Copy the live database into `backup_path` with SQLite's backup API after a
+/// WAL checkpoint, so the copy is consistent even while the app is writing.
+/// Returns the size of the backup file.
+fn consistent_backup(db_path: &Path, backup_path: &Path) -> Result<u64, String> {
+    let start_time = std::time::Instant::now();
+    
     if !db_path.exists() {
         return Err("Database file not found".to_string());
     }
     
-    println!("[CONSISTENT-BACKUP] 📂 Opening database connection: {:?}", db_path);
+    info!("[CONSISTENT-BACKUP] Opening database connection: {:?}", db_path);
     
     // Open a dedicated connection with optimized settings for backup
-    let conn = Connection::open(&db_path)
+    let conn = Connection::open(db_path)
         .map_err(|e| format!("Failed to open database: {}", e))?;
     
     // Set reasonable timeout (reduced from 60s to 10s)
@@ -404,19 +383,19 @@ async fn create_consistent_backup(backup_file_name: String) -> Result<serde_json
         .map_err(|e| format!("Failed to set busy timeout: {}", e))?;
     
     // Quick checkpoint - try only the most effective one first
-    println!("[CONSISTENT-BACKUP] 🔄 Performing WAL checkpoint...");
-    match conn.execute("PRAGMA wal_checkpoint(RESTART);", []) {
-        Ok(_) => println!("[CONSISTENT-BACKUP] ✅ WAL checkpoint completed"),
+    info!("[CONSISTENT-BACKUP] Performing WAL checkpoint...");
+    match conn.query_row("PRAGMA wal_checkpoint(RESTART);", [], |_| Ok(())) {
+        Ok(_) => info!("[CONSISTENT-BACKUP] WAL checkpoint completed"),
         Err(e) => {
-            println!("[CONSISTENT-BACKUP] ⚠️ WAL checkpoint failed: {}, continuing anyway", e);
+            warn!("[CONSISTENT-BACKUP] WAL checkpoint failed: {}, continuing anyway", e);
         }
     }
     
     // Create backup using SQLite's backup API
-    println!("[CONSISTENT-BACKUP] 📋 Starting SQLite backup API copy...");
+    info!("[CONSISTENT-BACKUP] Starting SQLite backup API copy...");
     
     // Create backup connection
-    let mut backup_conn = Connection::open(&backup_path)
+    let mut backup_conn = Connection::open(backup_path)
         .map_err(|e| format!("Failed to create backup file: {}", e))?;
     
     // Use SQLite's backup API for atomic, consistent copy
@@ -424,11 +403,11 @@ async fn create_consistent_backup(backup_file_name: String) -> Result<serde_json
         .map_err(|e| format!("Failed to initialize backup: {}", e))?;
     
     // Perform the backup with faster settings (larger pages, shorter delays)
-    println!("[CONSISTENT-BACKUP] 🚀 Executing backup copy...");
+    info!("[CONSISTENT-BACKUP] Executing backup copy...");
     match backup.run_to_completion(100, std::time::Duration::from_millis(10), None) {
         Ok(_) => {
             let elapsed = start_time.elapsed();
-            println!("[CONSISTENT-BACKUP] ✅ Backup completed in {:?}", elapsed);
+            info!("[CONSISTENT-BACKUP] Backup completed in {:?}", elapsed);
         }
         Err(e) => {
             return Err(format!("Backup failed: {}", e));
@@ -436,29 +415,17 @@ async fn create_consistent_backup(backup_file_name: String) -> Result<serde_json
     }
     
     // Verify the backup file exists and has reasonable size
-    let backup_metadata = std::fs::metadata(&backup_path)
+    let backup_metadata = std::fs::metadata(backup_path)
         .map_err(|e| format!("Failed to read backup metadata: {}", e))?;
     
     let backup_size = backup_metadata.len();
-    println!("[CONSISTENT-BACKUP] 📊 Backup file created: {:.2} MB", backup_size as f64 / 1024.0 / 1024.0);
+    info!("[CONSISTENT-BACKUP] Backup file created: {:.2} MB", backup_size as f64 / 1024.0 / 1024.0);
     
     if backup_size < 1024 {
         return Err("Backup file is too small, likely corrupted".to_string());
     }
     
-    // Fast checksum - only read first and last 64KB for speed
-    println!("[CONSISTENT-BACKUP] 🔐 Calculating fast checksum...");
-    let checksum = calculate_fast_checksum(&backup_path)?;
-    
-    let total_duration = start_time.elapsed();
-    println!("[CONSISTENT-BACKUP] 🎉 Total backup time: {:?}", total_duration);
-    
-    // Return JSON structure that TypeScript expects
-    Ok(serde_json::json!({
-        "success": true,
-        "size": backup_size,
-        "checksum": checksum
-    }))
+    Ok(backup_size)
 }
 
 fn calculate_checksum(data: &[u8]) -> String {
@@ -507,18 +474,18 @@ fn calculate_fast_checksum(file_path: &std::path::Path) -> Result<String, String
 
 #[tauri::command]
 async fn restore_wal_file(backup_data: Vec<u8>, db_path: String) -> Result<String, String> {
-    println!("[WAL-RESTORE] Restoring WAL file for database: {}", db_path);
+    info!("[WAL-RESTORE] Restoring WAL file for database: {}", db_path);
     
     let wal_path = format!("{}-wal", db_path);
     
     match std::fs::write(&wal_path, backup_data) {
         Ok(_) => {
-            println!("[WAL-RESTORE] WAL file restored successfully: {}", wal_path);
+            info!("[WAL-RESTORE] WAL file restored successfully: {}", wal_path);
             Ok(format!("WAL file restored to: {}", wal_path))
         }
         Err(e) => {
             let error_msg = format!("Failed to restore WAL file: {}", e);
-            println!("[WAL-RESTORE] Error: {}", error_msg);
+            warn!("[WAL-RESTORE] Error: {}", error_msg);
             Err(error_msg)
         }
     }
@@ -526,103 +493,35 @@ async fn restore_wal_file(backup_data: Vec<u8>, db_path: String) -> Result<Strin
 
 #[tauri::command]
 async fn restore_shm_file(backup_data: Vec<u8>, db_path: String) -> Result<String, String> {
-    println!("[SHM-RESTORE] Restoring SHM file for database: {}", db_path);
+    info!("[SHM-RESTORE] Restoring SHM file for database: {}", db_path);
     
     let shm_path = format!("{}-shm", db_path);
     
     match std::fs::write(&shm_path, backup_data) {
         Ok(_) => {
-            println!("[SHM-RESTORE] SHM file restored successfully: {}", shm_path);
+            info!("[SHM-RESTORE] SHM file restored successfully: {}", shm_path);
             Ok(format!("SHM file restored to: {}", shm_path))
         }
         Err(e) => {
             let error_msg = format!("Failed to restore SHM file: {}", e);
-            println!("[SHM-RESTORE] Error: {}", error_msg);
+            warn!("[SHM-RESTORE] Error: {}", error_msg);
             Err(error_msg)
         }
     }
 }
 
-/// Production-grade Windows application restart
-fn windows_restart_application(delay_ms: Option<u64>) -> Result<(), String> {
-    println!("🚀 [WINDOWS-RESTART] Starting Windows-specific restart process...");
-    
-    let delay = delay_ms.unwrap_or(1000);
-    
-    // Close database connections properly
-    std::thread::spawn(move || {
-        std::thread::sleep(std::time::Duration::from_millis(delay));
-        
-        // Get current executable path
-        match std::env::current_exe() {
-            Ok(exe_path) => {
-                println!("🔄 [WINDOWS-RESTART] Executable path: {:?}", exe_path);
-                
-                // Use Windows-specific restart approach
-                #[cfg(target_os = "windows")]
-                {
-                    use std::process::Command;
-                    
-                    // Start new instance and exit current one
-                    match Command::new(&exe_path)
-                        .spawn() {
-                        Ok(_) => {
-                            println!("✅ [WINDOWS-RESTART] New instance started successfully");
-                            std::thread::sleep(std::time::Duration::from_millis(500));
-                            std::process::exit(0);
-                        }
-                        Err(e) => {
-                            println!("❌ [WINDOWS-RESTART] Failed to start new instance: {}", e);
-                            std::process::exit(1);
-                        }
-                    }
-                }
-                
-                #[cfg(not(target_os = "windows"))]
-                {
-                    println!("ℹ️ [WINDOWS-RESTART] Non-Windows platform, using simple exit");
-                    std::process::exit(0);
-                }
-            }
-            Err(e) => {
-                println!("❌ [WINDOWS-RESTART] Failed to get executable path: {}", e);
-                std::process::exit(1);
-            }
-        }
-    });
-    
-    Ok(())
-}
-
-#[tauri::command] 
-async fn restart_application() -> Result<(), String> {
-    println!("🔄 [APP-RESTART] Initiating production-grade restart...");
-    
-    if cfg!(target_os = "windows") {
-        // Use production-grade Windows restart
-        windows_restart_application(None)
-    } else {
-        // For other platforms, simple exit
-        std::thread::spawn(|| {
-            std::thread::sleep(std::time::Duration::from_millis(2000));
-            std::process::exit(0);
-        });
-        Ok(())
-    }
-}
-
 #[tauri::command]
 async fn check_system_compatibility() -> Result<Vec<String>, String> {
-    println!("🔍 [SYSTEM-CHECK] Checking Windows compatibility...");
+    info!("[SYSTEM-CHECK] Checking Windows compatibility...");
     
     if cfg!(target_os = "windows") {
         let warnings = check_windows_compatibility();
         if warnings.is_empty() {
-            println!("✅ [SYSTEM-CHECK] System fully compatible");
+            info!("[SYSTEM-CHECK] System fully compatible");
         } else {
-            println!("⚠️ [SYSTEM-CHECK] Found {} compatibility warnings", warnings.len());
+            warn!("[SYSTEM-CHECK] Found {} compatibility warnings", warnings.len());
             for warning in &warnings {
-                println!("   - {}", warning);
+                info!("   - {}", warning);
             }
         }
         Ok(warnings)
@@ -633,8 +532,13 @@ async fn check_system_compatibility() -> Result<Vec<String>, String> {
 
 #[tauri::command]
 async fn get_system_info() -> Result<serde_json::Value, String> {
-    println!("📊 [SYSTEM-INFO] Gathering system information...");
-    
+    info!("[SYSTEM-INFO] Gathering system information...");
+    Ok(system_info())
+}
+
+/// OS, architecture and (on Windows) data directory and environment details,
+/// also written into diagnostic bundles
+fn system_info() -> serde_json::Value {
     let mut info = serde_json::Map::new();
     
     // Basic system info
@@ -669,35 +573,28 @@ async fn get_system_info() -> Result<serde_json::Value, String> {
         info.insert("environment".to_string(), serde_json::Value::Object(env_info));
     }
     
-    Ok(serde_json::Value::Object(info))
+    serde_json::Value::Object(info)
 }
 
 /// CLEANUP RESTORE FILE COMMAND
 /// Force delete restore files from Rust side for better file system access
 #[tauri::command]
 async fn cleanup_restore_file(relative_path: String) -> Result<(), String> {
-    println!("🧹 [RUST-CLEANUP] Attempting to cleanup file: {}", relative_path);
+    info!("[RUST-CLEANUP] Attempting to cleanup file: {}", relative_path);
     
-    let app_name = "com.itehadironstore.management";
-    let app_data_dir = if cfg!(target_os = "windows") {
-        get_windows_app_data_dir(app_name)?
-    } else {
-        dirs::data_dir()
-            .ok_or("Failed to get app data directory")?
-            .join(app_name)
-    };
+    let app_data_dir = database::get_app_data_dir()?;
     
     let file_path = app_data_dir.join(&relative_path);
     
     if file_path.exists() {
-        println!("📁 [RUST-CLEANUP] File exists, attempting deletion...");
+        info!("[RUST-CLEANUP] File exists, attempting deletion...");
         
         // Try multiple deletion strategies
         let mut success = false;
         
         // Strategy 1: Direct deletion
         if let Err(e) = std::fs::remove_file(&file_path) {
-            println!("⚠️ [RUST-CLEANUP] Direct deletion failed: {}", e);
+            warn!("[RUST-CLEANUP] Direct deletion failed: {}", e);
             
             // Strategy 2: Force deletion with attributes reset (Windows)
             #[cfg(target_os = "windows")]
@@ -707,7 +604,7 @@ async fn cleanup_restore_file(relative_path: String) -> Result<(), String> {
                 
                 // Try to reset file attributes first
                 if let Ok(metadata) = file_path.metadata() {
-                    println!("📋 [RUST-CLEANUP] File attributes: {:?}", metadata.file_attributes());
+                    info!("[RUST-CLEANUP] File attributes: {:?}", metadata.file_attributes());
                 }
                 
                 // Use Windows del command as fallback
@@ -719,14 +616,14 @@ async fn cleanup_restore_file(relative_path: String) -> Result<(), String> {
                     Ok(result) => {
                         if result.status.success() {
                             success = true;
-                            println!("✅ [RUST-CLEANUP] Windows del command succeeded");
+                            info!("[RUST-CLEANUP] Windows del command succeeded");
                         } else {
-                            println!("❌ [RUST-CLEANUP] Windows del command failed: {}", 
+                            error!("[RUST-CLEANUP] Windows del command failed: {}", 
                                 String::from_utf8_lossy(&result.stderr));
                         }
                     }
                     Err(e) => {
-                        println!("❌ [RUST-CLEANUP] Failed to execute del command: {}", e);
+                        error!("[RUST-CLEANUP] Failed to execute del command: {}", e);
                     }
                 }
             }
@@ -735,10 +632,10 @@ async fn cleanup_restore_file(relative_path: String) -> Result<(), String> {
             if !success {
                 let temp_path = file_path.with_extension("tmp_delete");
                 if std::fs::rename(&file_path, &temp_path).is_ok() {
-                    println!("🔄 [RUST-CLEANUP] File renamed, attempting deletion...");
+                    info!("[RUST-CLEANUP] File renamed, attempting deletion...");
                     if std::fs::remove_file(&temp_path).is_ok() {
                         success = true;
-                        println!("✅ [RUST-CLEANUP] Rename and delete succeeded");
+                        info!("[RUST-CLEANUP] Rename and delete succeeded");
                     }
                 }
             }
@@ -748,7 +645,7 @@ async fn cleanup_restore_file(relative_path: String) -> Result<(), String> {
             }
         } else {
             success = true;
-            println!("✅ [RUST-CLEANUP] Direct deletion succeeded");
+            info!("[RUST-CLEANUP] Direct deletion succeeded");
         }
         
         // Verify deletion
@@ -756,138 +653,174 @@ async fn cleanup_restore_file(relative_path: String) -> Result<(), String> {
             return Err("File still exists after deletion attempt".to_string());
         }
         
-        println!("🎉 [RUST-CLEANUP] File successfully deleted and verified");
+        info!("[RUST-CLEANUP] File successfully deleted and verified");
     } else {
-        println!("ℹ️ [RUST-CLEANUP] File doesn't exist, nothing to clean");
+        info!("ℹ[RUST-CLEANUP] File doesn't exist, nothing to clean");
     }
     
     Ok(())
 }
 
-fn main() {
-    // PRODUCTION-GRADE INITIALIZATION
-    println!("🚀 [INIT] Starting production-grade Windows application...");
-    
-    let app_name = "com.itehadironstore.management";
-    
-    // Check Windows compatibility first
-    if cfg!(target_os = "windows") {
-        let warnings = check_windows_compatibility();
-        if !warnings.is_empty() {
-            for warning in &warnings {
-                eprintln!("⚠️ [INIT] Compatibility warning: {}", warning);
-            }
-        }
-    }
-    
-    // Use production-grade app data directory detection
-    let app_data_dir = if cfg!(target_os = "windows") {
-        match get_windows_app_data_dir(app_name) {
-            Ok(dir) => {
-                println!("✅ [INIT] Using Windows app data directory: {}", dir.display());
-                dir
-            }
-            Err(e) => {
-                eprintln!("❌ [INIT] Failed to get Windows app data directory: {}", e);
-                eprintln!("    This may cause issues with backup/restore functionality");
-                // Emergency fallback
-                std::env::current_dir()
-                    .unwrap_or_else(|_| std::path::PathBuf::from("."))
-                    .join("data")
-                    .join(app_name)
-            }
-        }
-    } else {
-        // For non-Windows systems
-        std::env::var("HOME")
-            .map(|path| std::path::PathBuf::from(path).join(".local/share").join(app_name))
-            .unwrap_or_else(|_| {
-                std::env::current_dir()
-                    .expect("Failed to get current directory")
-            })
-    };
-    
-    // Ensure the app data directory exists
-    if let Err(e) = std::fs::create_dir_all(&app_data_dir) {
-        eprintln!("❌ [INIT] Failed to create app data directory: {}", e);
-        // Fallback to current directory
-        let fallback_dir = std::env::current_dir()
-            .expect("Failed to get current directory");
-        println!("[TAURI] Using fallback directory: {}", fallback_dir.display());
-    } else {
-        println!("[TAURI] Using app data directory: {}", app_data_dir.display());
-    }
-    
-    // Define database path in app data directory
-    let db_path: PathBuf = app_data_dir.join("store-2025.db");
-    println!("[TAURI] SQLite DB Path: {}", db_path.display());
-
+/// Open (creating if needed) the store database, apply the connection pragmas
+/// and bring every Rust-owned table and conversion up to date. Runs before the
+/// window starts and from `migrate` on the command line.
+fn initialize_database(db_path: &Path) -> Result<migrations::MigrationReport, String> {
     // Ensure the database file exists by creating a connection
-    match Connection::open(&db_path) {
+    match Connection::open(db_path) {
         Ok(conn) => {
             // Enable WAL mode for better concurrency
             match conn.pragma_update(None, "journal_mode", &"WAL") {
-                Ok(_) => println!("[TAURI] WAL mode enabled successfully"),
-                Err(e) => eprintln!("Failed to enable WAL mode: {}", e),
+                Ok(_) => info!("[TAURI] WAL mode enabled successfully"),
+                Err(e) => error!("Failed to enable WAL mode: {}", e),
             }
             
             // Set busy timeout to 60 seconds (60000 ms)
             match conn.pragma_update(None, "busy_timeout", &60000) {
-                Ok(_) => println!("[TAURI] Busy timeout set to 60 seconds"),
-                Err(e) => eprintln!("Failed to set busy timeout: {}", e),
+                Ok(_) => info!("[TAURI] Busy timeout set to 60 seconds"),
+                Err(e) => error!("Failed to set busy timeout: {}", e),
             }
             
             // Use NORMAL synchronous mode for balance
             match conn.pragma_update(None, "synchronous", &"NORMAL") {
-                Ok(_) => println!("[TAURI] Synchronous mode set to NORMAL"),
-                Err(e) => eprintln!("Failed to set synchronous mode: {}", e),
+                Ok(_) => info!("[TAURI] Synchronous mode set to NORMAL"),
+                Err(e) => error!("Failed to set synchronous mode: {}", e),
             }
             
             // Set cache size for better performance
             match conn.pragma_update(None, "cache_size", &-64000) {
-                Ok(_) => println!("[TAURI] Cache size set to 64MB"),
-                Err(e) => eprintln!("Failed to set cache size: {}", e),
+                Ok(_) => info!("[TAURI] Cache size set to 64MB"),
+                Err(e) => error!("Failed to set cache size: {}", e),
             }
             
             // Enable foreign key constraints
             match conn.pragma_update(None, "foreign_keys", &true) {
-                Ok(_) => println!("[TAURI] Foreign keys enabled"),
-                Err(e) => eprintln!("Failed to enable foreign keys: {}", e),
-            }
-            
-            // Create a simple test table to ensure the database is working
-            if let Err(e) = conn.execute(
-                "CREATE TABLE IF NOT EXISTS app_info (
-                    id INTEGER PRIMARY KEY,
-                    version TEXT,
-                    initialized_at DATETIME DEFAULT CURRENT_TIMESTAMP
-                )",
-                [],
-            ) {
-                eprintln!("Failed to create initial table: {}", e);
+                Ok(_) => info!("[TAURI] Foreign keys enabled"),
+                Err(e) => error!("Failed to enable foreign keys: {}", e),
             }
             
-            // Insert or update app info
-            if let Err(e) = conn.execute(
-                "INSERT OR REPLACE INTO app_info (id, version) VALUES (1, '1.0.0')",
-                [],
-            ) {
-                eprintln!("Failed to insert app info: {}", e);
+            // Every table and conversion in one transaction, snapshot first when it changes anything;
+            // the connection is closed before starting Tauri
+            let report = migrations::migrate(conn, db_path)?;
+            info!("[TAURI] Database initialized successfully and connection closed");
+            Ok(report)
+        }
+        Err(e) => Err(format!("Failed to open database: {}", e)),
+    }
+}
+
+fn main() {
+    // `ittehad-iron-store backup|restore|verify|...` runs headless and exits
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = cli::run(&args) {
+        std::process::exit(code);
+    }
+    
+    // Records are held in memory until the log directory is known
+    logging::init(true);
+    
+    // PRODUCTION-GRADE INITIALIZATION
+    info!("[INIT] Starting production-grade Windows application...");
+    
+    // Check Windows compatibility first
+    if cfg!(target_os = "windows") {
+        let warnings = check_windows_compatibility();
+        if !warnings.is_empty() {
+            for warning in &warnings {
+                warn!("[INIT] Compatibility warning: {}", warning);
             }
-            
-            // IMPORTANT: Close the connection before starting Tauri
-            drop(conn);
-            println!("[TAURI] Database initialized successfully and connection closed");
         }
+    }
+    
+    let app_data_dir = match database::get_app_data_dir() {
+        Ok(dir) => dir,
         Err(e) => {
-            eprintln!("Failed to initialize database: {}", e);
+            // Every command resolves the same directory, so there is no usable fallback
+            error!("[INIT] Failed to resolve the app data directory: {}", e);
+            eprintln!("Failed to resolve the app data directory: {}", e);
             std::process::exit(1);
         }
+    };
+    
+    // Ensure the app data directory exists
+    if let Err(e) = std::fs::create_dir_all(&app_data_dir) {
+        error!("[INIT] Failed to create app data directory: {}", e);
+        // Fallback to current directory
+        let fallback_dir = std::env::current_dir()
+            .expect("Failed to get current directory");
+        info!("[TAURI] Using fallback directory: {}", fallback_dir.display());
+    } else {
+        info!("[TAURI] Using app data directory: {}", app_data_dir.display());
+    }
+    
+    // From here on every record also lands in <app data>/logs/app.log
+    if let Err(e) = logging::attach_dir(&app_data_dir.join("logs")) {
+        error!("[INIT] {}", e);
+    }
+    
+    // A relaunch waits for the process it replaces to close the database and exit
+    let restart_state = restart::take_startup_state();
+    if restart_state.restarted {
+        info!("[INIT] Restarted{}", if restart_state.after_restore { " after a restore" } else { "" });
+        restart::wait_for_previous_instance(&restart_state);
+    }
+    
+    // One instance per data directory: a second launch hands its arguments to the first
+    let launches = match single_instance::acquire(&app_data_dir, &args) {
+        Ok(single_instance::Acquired::Primary(launches)) => Some(launches),
+        Ok(single_instance::Acquired::Forwarded { pid }) => {
+            info!("[INIT] Already running as process {}; focused its window", pid);
+            std::process::exit(0);
+        }
+        Err(e) => {
+            error!("[INIT] Single-instance check failed, continuing: {}", e);
+            None
+        }
+    };
+    
+    // Earlier builds named the file after the year; carry it over to the year-independent name
+    match database::migrate_legacy_db_file(&app_data_dir) {
+        Ok(Some(legacy)) => info!("[TAURI] Renamed legacy database {} to {}", legacy, database::DB_FILE_NAME),
+        Ok(None) => {}
+        Err(e) => error!("[INIT] Failed to rename legacy database: {}", e),
+    }
+    
+    // Define database path in app data directory
+    let db_path: PathBuf = app_data_dir.join(database::DB_FILE_NAME);
+    info!("[TAURI] SQLite DB Path: {}", db_path.display());
+
+    // A terminal joining LAN sync replaces its books with the downloaded snapshot
+    match sync::apply_pending_join(&db_path) {
+        Ok(Some(peer)) => info!("[TAURI] Joined sync with {}", peer),
+        Ok(None) => {}
+        Err(e) => error!("[INIT] Failed to join sync: {}", e),
+    }
+
+    // Ensure the database file exists and every Rust-owned table is up to date
+    if let Err(e) = initialize_database(&db_path) {
+        error!("Failed to initialize database: {}", e);
+        // Right after an offline update, put back the data from before it and reinstall the previous version
+        let next_step = match offline_update::roll_back_failed_update(&app_data_dir, &db_path) {
+            Ok(true) => "The previous version is being installed again.".to_string(),
+            Ok(false) => "Please contact support and send the diagnostic log.".to_string(),
+            Err(e) => {
+                error!("[INIT] Failed to roll back the update: {}", e);
+                format!("Rolling back the update failed as well: {}", e)
+            }
+        };
+        // No window yet to show it in, and opening one on a half-migrated database would only fail later
+        show_startup_error(&format!(
+            "{}\n\n{}\n\nLog: {}",
+            e,
+            next_step,
+            app_data_dir.join("logs").join("app.log").display()
+        ));
+        std::process::exit(1);
     }
+    offline_update::finish_pending(&app_data_dir);
 
     // Build the database URL for the plugin - use app data directory path
     let db_url = format!("sqlite:{}", db_path.display());
-    println!("[TAURI] Database URL: {}", db_url);
+    info!("[TAURI] Database URL: {}", db_url);
 
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
@@ -921,8 +854,24 @@ fn main() {
                 )
                 .build()
         )
+        .setup(move |app| {
+            // Business commands report their commits; the watcher catches the frontend's
+            change_events::start(app.handle().clone(), db_path.clone());
+            // Serves peers and syncs once this terminal has sync enabled
+            sync::start(db_path.clone());
+            // Chains the row changes and command entries the audit triggers queue
+            audit::start(db_path.clone());
+            // Listens for companion apps once the local API is enabled
+            api::start(db_path);
+            // Later launches focus this window instead of opening the database again
+            if let Some(launches) = launches {
+                single_instance::forward_launches(app.handle().clone(), launches);
+            }
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
-            authenticate_user, 
+            session::authenticate_user,
+            session::end_session,
             create_backup_directory,
             delete_backup_file, 
             close_database_connections, 
@@ -932,11 +881,83 @@ fn main() {
             create_consistent_backup,
             restore_wal_file,
             restore_shm_file,
-            restart_application,
+            restart::restart_application,
+            restart::get_restart_state,
             check_system_compatibility,
             get_system_info,
-            cleanup_restore_file
+            cleanup_restore_file,
+            invoice_cancellation::cancel_invoice,
+            returns::process_return,
+            money::get_money_conversion_report,
+            quantity::get_quantity_conversion_issues,
+            quantity::convert_quantity,
+            stock_engine::check_stock_consistency,
+            stock_engine::rebuild_stock,
+            stock_engine::get_stock_history,
+            customer_balance::get_customer_balance_report,
+            customer_balance::recalculate_customer_balance,
+            customer_balance::recalculate_all,
+            customer_balance::get_customer_statement,
+            vendor_payables::record_vendor_payment,
+            vendor_payables::sync_vendor_payables,
+            vendor_payables::get_vendor_aging,
+            journal::sync_journal,
+            journal::get_trial_balance,
+            day_close::close_day,
+            day_close::reopen_day,
+            day_close::get_day_closing,
+            fiscal_year::get_fiscal_years,
+            fiscal_year::close_fiscal_year,
+            fiscal_year::archive_fiscal_year,
+            fiscal_year::reopen_fiscal_year,
+            fiscal_year::get_fiscal_year_report,
+            archive::get_archived_years,
+            archive::move_fiscal_year_to_archive,
+            search::global_search,
+            search::rebuild_search_index,
+            sync::get_sync_status,
+            sync::enable_sync,
+            sync::disable_sync,
+            sync::renew_sync_group_code,
+            sync::set_sync_group_code,
+            sync::add_sync_peer,
+            sync::remove_sync_peer,
+            sync::sync_now,
+            sync::join_sync_group,
+            api::get_api_status,
+            api::configure_api,
+            api::create_api_key,
+            api::revoke_api_key,
+            api::get_api_stock_counts,
+            api::review_api_stock_count,
+            logging::get_recent_logs,
+            diagnostics::export_diagnostic_bundle,
+            offline_update::get_update_settings,
+            offline_update::set_update_source,
+            offline_update::check_offline_updates,
+            offline_update::install_offline_update,
+            offline_update::get_last_update,
+            offline_update::rollback_update,
+            audit::verify_audit_chain,
+            audit::set_audit_actor,
+            audit::record_audit_event,
+            audit::get_audit_trail,
+            recycle_bin::move_to_recycle_bin,
+            recycle_bin::restore_from_recycle_bin,
+            recycle_bin::get_recycle_bin,
+            recycle_bin::purge_recycle_bin,
+            recycle_bin::set_recycle_bin_retention,
+            data_import::preview_import_file,
+            data_import::import_data,
+            data_import::save_import_profile,
+            data_import::get_import_profiles,
+            data_import::delete_import_profile
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running Tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running Tauri application")
+        .run(|_, event| {
+            if let tauri::RunEvent::Exit = event {
+                single_instance::release();
+            }
+        });
 }
\ No newline at end of file
@@ -0,0 +1,401 @@
+/*!
+ * SCHEMA MIGRATIONS
+ *
+ * Every start brings the Rust-owned tables and conversions up to date. The
+ * steps are idempotent, so most starts change nothing; after an upgrade they
+ * add tables, columns, triggers and shadow values.
+ *
+ * The steps run in a single transaction; the connection pragmas, which
+ * SQLite does not allow inside one, are set by the caller beforehand. When
+ * the steps change an existing database at all (its schema or any row), the
+ * transaction is rolled back, a consistent snapshot of the untouched file is
+ * taken, `backups/pre-migration-<from>-to-<to>-<time>.db` with the versions
+ * recorded in `app_info`, and the steps run again. The app version alone
+ * cannot tell: a release may change the schema without bumping it. If a step
+ * fails, the transaction is rolled back (and a snapshot, if one was taken, is
+ * put back), so the database is exactly as the previous version left it. The
+ * error names the failed step and the snapshot, for the startup error message.
+ */
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log::{error, info, warn};
+use rusqlite::Connection;
+
+use crate::database::table_exists;
+use crate::{
+    api, archive, audit, data_import, day_close, fiscal_year, invoice_cancellation, journal, money, quantity,
+    recycle_bin, search, sync, vendor_payables,
+};
+
+pub const APP_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+type StepFn = fn(&mut Connection) -> Result<(), String>;
+
+/// Applied in order on every start
+const STEPS: &[(&str, StepFn)] = &[
+    ("app info", app_info),
+    ("invoice cancellation tables", |conn| {
+        invoice_cancellation::ensure_schema(conn).map_err(|e| e.to_string())
+    }),
+    ("vendor payables tables", |conn| {
+        vendor_payables::ensure_schema(conn).map_err(|e| e.to_string())
+    }),
+    ("general journal tables", |conn| {
+        journal::ensure_schema(conn).map_err(|e| e.to_string())
+    }),
+    ("fiscal year tables", |conn| {
+        fiscal_year::ensure_schema(conn).map_err(|e| e.to_string())
+    }),
+    ("archive tables", |conn| {
+        archive::ensure_schema(conn).map_err(|e| e.to_string())
+    }),
+    ("search indexes", |conn| search::ensure_schema(conn)),
+    ("sync tables", |conn| {
+        sync::ensure_schema(conn).map_err(|e| e.to_string())
+    }),
+    ("API tables", |conn| api::ensure_schema(conn).map_err(|e| e.to_string())),
+    // Also locks closed days on every transaction table, including ones created since the last start
+    ("daily closing tables", |conn| {
+        day_close::ensure_schema(conn).map_err(|e| e.to_string())
+    }),
+    ("money columns", money_columns),
+    ("stock quantities", stock_quantities),
+    ("recycle bin", |conn| recycle_bin::ensure_schema(conn).map_err(|e| e.to_string())),
+    ("data import", |conn| data_import::ensure_schema(conn).map_err(|e| e.to_string())),
+    // Last, so the capture triggers cover every column added above
+    ("audit log", |conn| audit::ensure_schema(conn).map_err(|e| e.to_string())),
+];
+
+#[derive(Debug, Default)]
+pub struct MigrationReport {
+    /// Version that migrated the database last; `None` for a new database
+    pub from_version: Option<String>,
+    /// Taken whenever the migration changed an existing database
+    pub snapshot: Option<PathBuf>,
+}
+
+impl MigrationReport {
+    /// Print the outcome for the `migrate` command
+    pub fn print(&self) -> bool {
+        match (&self.from_version, &self.snapshot) {
+            (Some(version), Some(snapshot)) if version == APP_VERSION => {
+                println!("✅ Brought the database schema up to date for version {}", APP_VERSION);
+                println!("   Snapshot from before the migration: {}", snapshot.display());
+            }
+            (Some(version), Some(snapshot)) => {
+                println!("✅ Upgraded the database from {} to {}", version, APP_VERSION);
+                println!("   Snapshot from before the upgrade: {}", snapshot.display());
+            }
+            _ => println!("✅ Database is up to date for version {}", APP_VERSION),
+        }
+        true
+    }
+}
+
+fn app_info(conn: &mut Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS app_info (
+            id INTEGER PRIMARY KEY,
+            version TEXT,
+            initialized_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )
+    .and_then(|_| {
+        // Unchanged on an ordinary start, so it alone never calls for a snapshot
+        conn.execute(
+            "INSERT INTO app_info (id, version) VALUES (1, ?1)
+             ON CONFLICT(id) DO UPDATE SET version = excluded.version WHERE version IS NOT excluded.version",
+            [APP_VERSION],
+        )
+    })
+    .map(|_| ())
+    .map_err(|e| e.to_string())
+}
+
+/// Exact integer paisa shadows for every monetary column
+fn money_columns(conn: &mut Connection) -> Result<(), String> {
+    let reports = money::ensure_paisa_columns(conn)?;
+    if !reports.is_empty() {
+        info!("[MIGRATE] Converted {} money columns to integer paisa", reports.len());
+    }
+    Ok(())
+}
+
+/// Integer base-unit shadows for the text stock quantities
+fn stock_quantities(conn: &mut Connection) -> Result<(), String> {
+    let summary = quantity::ensure_quantity_columns(conn)?;
+    if summary.products_converted + summary.movements_converted > 0 {
+        info!(
+            "[MIGRATE] Converted {} product stocks and {} stock movements to base units",
+            summary.products_converted, summary.movements_converted
+        );
+    }
+    Ok(())
+}
+
+/// Version recorded by the last successful migration, if the database has any tables yet
+fn recorded_version(conn: &Connection) -> Result<Option<String>, String> {
+    let has_tables: bool = conn
+        .query_row(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%')",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to inspect schema: {}", e))?;
+    if !has_tables {
+        return Ok(None);
+    }
+    if !table_exists(conn, "app_info").map_err(|e| format!("Failed to inspect schema: {}", e))? {
+        return Ok(Some("unknown".to_string()));
+    }
+    conn.query_row("SELECT COALESCE(MAX(version), 'unknown') FROM app_info", [], |row| {
+        row.get(0)
+    })
+    .map(Some)
+    .map_err(|e| format!("Failed to read app_info: {}", e))
+}
+
+/// Schema and row-change counter, to tell whether the steps changed anything
+fn change_marker(conn: &Connection) -> Result<(String, i64), String> {
+    conn.query_row(
+        "SELECT (SELECT group_concat(type || ' ' || name || ' ' || COALESCE(sql, ''), char(10))
+                 FROM (SELECT type, name, sql FROM sqlite_master ORDER BY type, name)),
+                total_changes()",
+        [],
+        |row| Ok((row.get::<_, Option<String>>(0)?.unwrap_or_default(), row.get(1)?)),
+    )
+    .map_err(|e| format!("Failed to inspect schema: {}", e))
+}
+
+/// Apply every step inside a new transaction and leave it open for the caller.
+/// Returns whether the database changed; a failed step rolls back.
+fn apply_steps(conn: &mut Connection) -> Result<bool, String> {
+    conn.execute_batch("BEGIN IMMEDIATE")
+        .map_err(|e| format!("Failed to start the migration: {}", e))?;
+    let applied = change_marker(conn).and_then(|before| {
+        for (name, apply) in STEPS {
+            apply(conn).map_err(|e| format!("{}: {}", name, e))?;
+        }
+        Ok(change_marker(conn)? != before)
+    });
+    if applied.is_err() {
+        let _ = conn.execute_batch("ROLLBACK");
+    }
+    applied
+}
+
+/// Run every step in one transaction. When they change an existing database,
+/// they are rolled back and run again after a snapshot. Consumes the connection
+/// so a failed run can put the snapshot back with nothing holding the file.
+pub fn migrate(mut conn: Connection, db_path: &Path) -> Result<MigrationReport, String> {
+    let from_version = recorded_version(&conn)?;
+    let mut snapshot = None;
+    let mut applied = apply_steps(&mut conn);
+    if let (Some(version), Ok(true)) = (&from_version, &applied) {
+        conn.execute_batch("ROLLBACK")
+            .map_err(|e| format!("Failed to roll back the trial migration: {}", e))?;
+        snapshot = Some(take_snapshot(&conn, db_path, version)?);
+        applied = apply_steps(&mut conn);
+    }
+    let failure = match applied {
+        Ok(_) => conn.execute_batch("COMMIT").err().map(|e| format!("commit: {}", e)),
+        Err(failure) => Some(failure),
+    };
+
+    let Some(failure) = failure else {
+        if let (Some(version), Some(snapshot)) = (from_version.as_deref(), &snapshot) {
+            info!(
+                "[MIGRATE] Migrated the database from {} to {}; previous state in {}",
+                version,
+                APP_VERSION,
+                snapshot.display()
+            );
+        }
+        return Ok(MigrationReport { from_version, snapshot });
+    };
+
+    error!("[MIGRATE] Migration failed at {}", failure);
+    drop(conn);
+    let Some(snapshot) = snapshot else {
+        return Err(format!(
+            "The database could not be brought up to date ({}). No changes were kept.",
+            failure
+        ));
+    };
+    match restore_snapshot(db_path, &snapshot) {
+        Ok(()) => {
+            warn!("[MIGRATE] Restored {}", snapshot.display());
+            Err(format!(
+                "The database could not be upgraded to version {} ({}). It was restored from {} and is as version {} left it.",
+                APP_VERSION,
+                failure,
+                snapshot.display(),
+                from_version.unwrap_or_default()
+            ))
+        }
+        Err(e) => Err(format!(
+            "The database could not be upgraded to version {} ({}), and restoring the snapshot {} failed: {}",
+            APP_VERSION,
+            failure,
+            snapshot.display(),
+            e
+        )),
+    }
+}
+
+fn take_snapshot(conn: &Connection, db_path: &Path, from_version: &str) -> Result<PathBuf, String> {
+    let time: String = conn
+        .query_row("SELECT strftime('%Y%m%d-%H%M%S', 'now', 'localtime')", [], |row| {
+            row.get(0)
+        })
+        .map_err(|e| format!("Failed to read the clock: {}", e))?;
+    let backup_dir = db_path.with_file_name("backups");
+    fs::create_dir_all(&backup_dir).map_err(|e| format!("Failed to create {}: {}", backup_dir.display(), e))?;
+    let snapshot = backup_dir.join(format!("pre-migration-{}-to-{}-{}.db", from_version, APP_VERSION, time));
+    crate::consistent_backup(db_path, &snapshot).map_err(|e| format!("Failed to snapshot the database: {}", e))?;
+    info!(
+        "[MIGRATE] Database from version {} saved to {} before upgrading",
+        from_version,
+        snapshot.display()
+    );
+    Ok(snapshot)
+}
+
+fn restore_snapshot(db_path: &Path, snapshot: &Path) -> Result<(), String> {
+    let data = fs::read(snapshot).map_err(|e| format!("Failed to read {}: {}", snapshot.display(), e))?;
+    crate::restore_database_file(db_path, &data)?;
+    for suffix in ["-wal", "-shm"] {
+        let mut name = db_path.file_name().unwrap_or_default().to_os_string();
+        name.push(suffix);
+        let sidecar = db_path.with_file_name(name);
+        if sidecar.exists() {
+            fs::remove_file(&sidecar).map_err(|e| format!("Failed to remove {}: {}", sidecar.display(), e))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::has_column;
+
+    fn temp_db(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("migrations-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("store.db")
+    }
+
+    fn open_wal(db_path: &Path) -> Connection {
+        let conn = Connection::open(db_path).unwrap();
+        conn.pragma_update(None, "journal_mode", "WAL").unwrap();
+        conn
+    }
+
+    #[test]
+    fn snapshots_only_when_something_changes() {
+        let db_path = temp_db("snapshot");
+
+        let report = migrate(open_wal(&db_path), &db_path).unwrap();
+        assert!(report.from_version.is_none() && report.snapshot.is_none());
+        let report = migrate(open_wal(&db_path), &db_path).unwrap();
+        assert_eq!(report.from_version.as_deref(), Some(APP_VERSION));
+        assert!(report.snapshot.is_none());
+
+        // Same app version, schema behind: a release that adds a table without bumping the version
+        open_wal(&db_path).execute_batch("DROP TABLE import_runs").unwrap();
+        let report = migrate(open_wal(&db_path), &db_path).unwrap();
+        let snapshot = report.snapshot.unwrap();
+        let missing: bool = Connection::open(&snapshot)
+            .unwrap()
+            .query_row(
+                "SELECT NOT EXISTS (SELECT 1 FROM sqlite_master WHERE name = 'import_runs')",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(missing);
+        assert!(table_exists(&open_wal(&db_path), "import_runs").unwrap());
+
+        let _ = fs::remove_dir_all(db_path.parent().unwrap());
+    }
+
+    #[test]
+    fn failed_step_keeps_nothing() {
+        let db_path = temp_db("failure");
+        migrate(open_wal(&db_path), &db_path).unwrap();
+        open_wal(&db_path)
+            .execute_batch(
+                "UPDATE app_info SET version = '0.9.0';
+                 DROP TABLE import_runs;
+                 CREATE TABLE products (id INTEGER PRIMARY KEY, current_stock TEXT, unit_type TEXT);
+                 INSERT INTO products (current_stock, unit_type) VALUES ('12', 'piece');
+                 CREATE TRIGGER products_locked BEFORE UPDATE ON products
+                 BEGIN SELECT RAISE(ABORT, 'products are locked'); END;",
+            )
+            .unwrap();
+
+        let error = migrate(open_wal(&db_path), &db_path).unwrap_err();
+        assert!(error.contains("stock quantities"), "{}", error);
+        let conn = open_wal(&db_path);
+        assert!(!table_exists(&conn, "import_runs").unwrap());
+        let version: String = conn
+            .query_row("SELECT version FROM app_info", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, "0.9.0");
+
+        let _ = fs::remove_dir_all(db_path.parent().unwrap());
+    }
+    #[test]
+    fn failed_upgrade_keeps_the_data() {
+        let db_path = temp_db("restore");
+        migrate(open_wal(&db_path), &db_path).unwrap();
+        let conn = open_wal(&db_path);
+        conn.execute_batch(
+            "UPDATE app_info SET version = '0.9.0';
+             CREATE TABLE customers (id INTEGER PRIMARY KEY, name TEXT NOT NULL, balance REAL NOT NULL DEFAULT 0);
+             INSERT INTO customers (name, balance) VALUES ('Ali', 1500.5), ('Bilal', 0), ('Sara', -200);
+             CREATE TABLE products (id INTEGER PRIMARY KEY, current_stock TEXT, unit_type TEXT);
+             INSERT INTO products (current_stock, unit_type) VALUES ('12', 'piece'), ('10-500', 'kg-grams');
+             CREATE TRIGGER products_locked BEFORE UPDATE ON products
+             BEGIN SELECT RAISE(ABORT, 'products are locked'); END;",
+        )
+        .unwrap();
+        let rows = |conn: &Connection| -> Vec<(String, f64)> {
+            let mut stmt = conn.prepare("SELECT name, balance FROM customers ORDER BY id").unwrap();
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?))).unwrap();
+            rows.map(Result::unwrap).collect()
+        };
+        let before = rows(&conn);
+        drop(conn);
+
+        // The money columns rewrite every customer before the stock step fails; none of it stays
+        let error = migrate(open_wal(&db_path), &db_path).unwrap_err();
+        assert!(
+            error.contains("stock quantities") && error.contains("No changes were kept"),
+            "{}",
+            error
+        );
+        let conn = open_wal(&db_path);
+        assert_eq!(rows(&conn), before);
+        assert!(!has_column(&conn, "customers", "balance_paisa").unwrap());
+        let stocks: Vec<String> = conn
+            .prepare("SELECT current_stock FROM products ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(stocks, ["12", "10-500"]);
+        let version: String = conn
+            .query_row("SELECT version FROM app_info", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, "0.9.0");
+
+        let _ = fs::remove_dir_all(db_path.parent().unwrap());
+    }
+}
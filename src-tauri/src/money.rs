@@ -0,0 +1,538 @@
+/*!
+ * EXACT MONEY ARITHMETIC
+ * `Money` holds an amount as integer paisa so sums and balances reconcile
+ * exactly. Every monetary REAL column also gets an integer `<column>_paisa`
+ * shadow column, backfilled once with a rounding report and kept in sync by
+ * triggers, so frontend writes in rupees land in exact integers as well.
+ * Reports and balances read and sum the shadows (`paisa_sql`), never the REAL.
+ */
+
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+
+use log::warn;
+use rusqlite::types::{Type, ValueRef};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::database::{column_names, has_column, open_connection, table_exists};
+
+/// An amount of Pakistani rupees stored as integer paisa (1 rupee = 100 paisa)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Money(i64);
+
+impl Money {
+    pub const ZERO: Money = Money(0);
+
+    pub const fn from_paisa(paisa: i64) -> Money {
+        Money(paisa)
+    }
+
+    pub const fn paisa(self) -> i64 {
+        self.0
+    }
+
+    /// Convert a rupee amount read from a REAL column, rounding half away
+    /// from zero exactly as SQLite's ROUND() does. NaN and infinities are rejected.
+    pub fn from_rupees(rupees: f64) -> Option<Money> {
+        let paisa = (rupees * 100.0).round();
+        (paisa.is_finite() && paisa.abs() < i64::MAX as f64).then_some(Money(paisa as i64))
+    }
+
+    /// Rupees for REAL columns and the frontend
+    pub fn to_rupees(self) -> f64 {
+        self.0 as f64 / 100.0
+    }
+
+    /// Parse "1250", "1,250.5" or "-12.75" without going through floating point
+    pub fn parse(text: &str) -> Option<Money> {
+        let cleaned: String = text.trim().chars().filter(|c| *c != ',').collect();
+        let (negative, digits) = match cleaned.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, cleaned.as_str()),
+        };
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        if whole.is_empty() && fraction.is_empty()
+            || !whole.chars().all(|c| c.is_ascii_digit())
+            || !fraction.chars().all(|c| c.is_ascii_digit())
+            || fraction.len() > 2
+        {
+            return None;
+        }
+        let whole: i64 = if whole.is_empty() { 0 } else { whole.parse().ok()? };
+        let fraction: i64 = format!("{:0<2}", fraction).parse().ok()?;
+        let paisa = whole.checked_mul(100)?.checked_add(fraction)?;
+        Some(Money(if negative { -paisa } else { paisa }))
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.0 < 0
+    }
+
+    pub fn abs(self) -> Money {
+        Money(self.0.abs())
+    }
+
+    /// Multiply by `numerator / denominator`, rounding half away from zero.
+    /// Used to pro-rate line totals over partial quantities and discounts.
+    pub fn mul_ratio(self, numerator: i64, denominator: i64) -> Money {
+        if denominator == 0 {
+            return Money::ZERO;
+        }
+        let product = self.0 as i128 * numerator as i128;
+        let denominator = denominator as i128;
+        let quotient = product / denominator;
+        let remainder = product % denominator;
+        let rounded = if remainder.abs() * 2 >= denominator.abs() {
+            quotient + if (product < 0) == (denominator < 0) { 1 } else { -1 }
+        } else {
+            quotient
+        };
+        Money(rounded as i64)
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        write!(f, "{}{}.{:02}", sign, self.0.abs() / 100, self.0.abs() % 100)
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+    fn add(self, other: Money) -> Money {
+        Money(self.0 + other.0)
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+    fn sub(self, other: Money) -> Money {
+        Money(self.0 - other.0)
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+    fn neg(self) -> Money {
+        Money(-self.0)
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, other: Money) {
+        self.0 += other.0;
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, other: Money) {
+        self.0 -= other.0;
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, Add::add)
+    }
+}
+
+/// The frontend works in rupees, so Money crosses the IPC boundary as a number
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.to_rupees())
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Money, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Number(f64),
+            Text(String),
+        }
+        match Raw::deserialize(deserializer)? {
+            Raw::Number(rupees) => Money::from_rupees(rupees),
+            Raw::Text(text) => Money::parse(&text),
+        }
+        .ok_or_else(|| serde::de::Error::custom("invalid money amount"))
+    }
+}
+
+/// SQL reading a rupee column as exact integer paisa: its `_paisa` shadow, or
+/// the rounded REAL while the table has no shadow yet (created by the frontend
+/// since the last start). `column` may carry a table alias, as in `l.debit`.
+/// A shadow is NULL where the amount is NULL or not a number; the conversion
+/// report counts those rows.
+pub fn paisa_sql(conn: &Connection, table: &str, column: &str) -> rusqlite::Result<String> {
+    let name = column.rsplit('.').next().unwrap_or(column);
+    Ok(if has_column(conn, table, &format!("{}_paisa", name))? {
+        format!("{}_paisa", column)
+    } else {
+        format!("CAST(ROUND({} * 100) AS INTEGER)", column)
+    })
+}
+
+/// SQL summing an integer paisa expression, zero over no rows
+pub fn sum_paisa_sql(expression: &str) -> String {
+    format!("COALESCE(SUM({}), 0)", expression)
+}
+
+/// Read an integer paisa value selected with `paisa_sql`, treating NULL as zero
+pub fn paisa_from_row(row: &rusqlite::Row, index: usize) -> rusqlite::Result<Money> {
+    Ok(Money::from_paisa(row.get::<_, Option<i64>>(index)?.unwrap_or(0)))
+}
+
+/// Read a rupee value from a column without a paisa shadow, treating NULL as
+/// zero. Amounts stored as text ("1,250.50") are parsed.
+pub fn money_from_row(row: &rusqlite::Row, index: usize) -> rusqlite::Result<Money> {
+    let invalid = |kind, message: &str| {
+        rusqlite::Error::FromSqlConversionFailure(index, kind, message.to_string().into())
+    };
+    match row.get_ref(index)? {
+        ValueRef::Null => Ok(Money::ZERO),
+        ValueRef::Integer(rupees) => rupees
+            .checked_mul(100)
+            .map(Money::from_paisa)
+            .ok_or_else(|| invalid(Type::Integer, "amount is out of range")),
+        ValueRef::Real(rupees) => {
+            Money::from_rupees(rupees).ok_or_else(|| invalid(Type::Real, "amount is not a finite number"))
+        }
+        ValueRef::Text(text) => {
+            let text = String::from_utf8_lossy(text);
+            if text.trim().is_empty() {
+                return Ok(Money::ZERO);
+            }
+            Money::parse(&text)
+                .or_else(|| text.trim().replace(',', "").parse().ok().and_then(Money::from_rupees))
+                .ok_or_else(|| invalid(Type::Text, &format!("amount {:?} is not a number", text)))
+        }
+        ValueRef::Blob(_) => Err(invalid(Type::Blob, "amount is not a number")),
+    }
+}
+
+// ===================================================================
+// PAISA SHADOW COLUMNS
+// ===================================================================
+
+/// Every monetary column that gets an exact integer shadow
+const MONEY_COLUMNS: &[(&str, &[&str])] = &[
+    ("customers", &["balance", "credit_limit"]),
+    ("invoices", &[
+        "subtotal", "discount_amount", "tax_amount", "total_amount", "grand_total",
+        "paid_amount", "payment_amount", "remaining_balance", "due_amount",
+    ]),
+    ("invoice_items", &["unit_price", "rate", "line_total", "amount", "total_price"]),
+    ("payments", &["amount", "payment_amount", "net_amount"]),
+    ("invoice_payments", &["amount"]),
+    ("invoice_payment_allocations", &["allocated_amount"]),
+    ("customer_ledger_entries", &["amount", "balance_before", "balance_after"]),
+    ("ledger_entries", &["amount"]),
+    ("payment_channels", &["current_balance"]),
+    ("vendors", &["balance"]),
+    ("vendor_payments", &["amount", "net_amount"]),
+    ("vendor_ledger_entries", &["amount", "balance_before", "balance_after"]),
+    ("vendor_payment_allocations", &["amount"]),
+    ("journal_lines", &["debit", "credit"]),
+    ("daily_closing_balances", &["opening_balance", "total_in", "total_out", "closing_balance"]),
+    ("fiscal_year_balances", &["balance"]),
+    ("stock_receiving", &["total_cost", "grand_total"]),
+    ("stock_receiving_items", &["unit_cost", "total_cost"]),
+    ("returns", &["total_amount", "refund_amount", "settlement_amount"]),
+    ("return_items", &["unit_price", "total_price"]),
+    ("business_expenses", &["amount", "total_amount"]),
+    ("business_income", &["amount", "net_amount"]),
+    ("salary_payments", &["net_salary", "payment_amount"]),
+];
+
+#[derive(Debug, Serialize)]
+pub struct ColumnConversionReport {
+    pub table_name: String,
+    pub column_name: String,
+    pub total_rows: i64,
+    pub rounded_rows: i64,
+    pub null_rows: i64,
+    pub invalid_rows: i64,
+    pub max_rounding_paisa: f64,
+    pub net_rounding_paisa: f64,
+    pub converted_at: String,
+}
+
+fn ensure_report_table(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS money_conversion_report (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            table_name TEXT NOT NULL,
+            column_name TEXT NOT NULL,
+            total_rows INTEGER NOT NULL,
+            rounded_rows INTEGER NOT NULL,
+            null_rows INTEGER NOT NULL,
+            invalid_rows INTEGER NOT NULL,
+            max_rounding_paisa REAL NOT NULL,
+            net_rounding_paisa REAL NOT NULL,
+            converted_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE(table_name, column_name)
+        )",
+    )
+}
+
+/// Add and backfill the `_paisa` shadow of every monetary column that does not
+/// have one yet, then (re)install the sync triggers of the affected tables.
+/// Safe to run on every startup: tables the frontend creates later are picked
+/// up on the next run. Returns the rounding report of the columns converted now.
+pub fn ensure_paisa_columns(conn: &mut Connection) -> Result<Vec<ColumnConversionReport>, String> {
+    ensure_report_table(conn).map_err(|e| format!("Failed to create money report table: {}", e))?;
+
+    // A savepoint, so the conversion also nests inside the startup migration's transaction
+    let tx = conn
+        .savepoint()
+        .map_err(|e| format!("Failed to start money conversion: {}", e))?;
+    let mut reports = Vec::new();
+
+    for (table, money_columns) in MONEY_COLUMNS {
+        if !table_exists(&tx, table).map_err(|e| format!("Failed to inspect schema: {}", e))? {
+            continue;
+        }
+        let existing = column_names(&tx, table).map_err(|e| format!("Failed to read columns of {}: {}", table, e))?;
+        let present: Vec<&str> = money_columns
+            .iter()
+            .copied()
+            .filter(|column| existing.iter().any(|name| name == column))
+            .collect();
+        let missing: Vec<&str> = present
+            .iter()
+            .copied()
+            .filter(|column| !existing.iter().any(|name| *name == format!("{}_paisa", column)))
+            .collect();
+        if missing.is_empty() {
+            continue;
+        }
+
+        for column in &missing {
+            reports.push(convert_column(&tx, table, column)?);
+        }
+        install_sync_triggers(&tx, table, &present)?;
+    }
+
+    tx.commit()
+        .map_err(|e| format!("Failed to commit money conversion: {}", e))?;
+
+    for report in &reports {
+        if report.rounded_rows > 0 || report.invalid_rows > 0 {
+            warn!(
+                "[MONEY] {}.{}: {} of {} rows rounded (max {:.4} paisa, net {:.4}), {} invalid",
+                report.table_name,
+                report.column_name,
+                report.rounded_rows,
+                report.total_rows,
+                report.max_rounding_paisa,
+                report.net_rounding_paisa,
+                report.invalid_rows
+            );
+        }
+    }
+
+    Ok(reports)
+}
+
+fn convert_column(tx: &Connection, table: &str, column: &str) -> Result<ColumnConversionReport, String> {
+    let numeric = format!("typeof({}) IN ('integer', 'real')", column);
+    let delta = format!("({0} * 100 - ROUND({0} * 100))", column);
+
+    let (total_rows, rounded_rows, null_rows, invalid_rows, max_rounding, net_rounding): (i64, i64, i64, i64, f64, f64) = tx
+        .query_row(
+            &format!(
+                "SELECT COUNT(*),
+                        COALESCE(SUM(CASE WHEN {numeric} AND ABS({delta}) > 0.000001 THEN 1 ELSE 0 END), 0),
+                        COALESCE(SUM(CASE WHEN {column} IS NULL THEN 1 ELSE 0 END), 0),
+                        COALESCE(SUM(CASE WHEN {column} IS NOT NULL AND NOT {numeric} THEN 1 ELSE 0 END), 0),
+                        COALESCE(MAX(CASE WHEN {numeric} THEN ABS({delta}) END), 0),
+                        COALESCE(SUM(CASE WHEN {numeric} THEN {delta} END), 0)
+                 FROM {table}"
+            ),
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?)),
+        )
+        .map_err(|e| format!("Failed to analyse {}.{}: {}", table, column, e))?;
+
+    tx.execute_batch(&format!(
+        "ALTER TABLE {table} ADD COLUMN {column}_paisa INTEGER;
+         UPDATE {table} SET {column}_paisa = CASE WHEN {numeric} THEN CAST(ROUND({column} * 100) AS INTEGER) END;"
+    ))
+    .map_err(|e| format!("Failed to convert {}.{}: {}", table, column, e))?;
+
+    tx.execute(
+        "INSERT OR REPLACE INTO money_conversion_report (
+            table_name, column_name, total_rows, rounded_rows, null_rows, invalid_rows,
+            max_rounding_paisa, net_rounding_paisa
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![table, column, total_rows, rounded_rows, null_rows, invalid_rows, max_rounding, net_rounding],
+    )
+    .map_err(|e| format!("Failed to record conversion report: {}", e))?;
+
+    Ok(ColumnConversionReport {
+        table_name: table.to_string(),
+        column_name: column.to_string(),
+        total_rows,
+        rounded_rows,
+        null_rows,
+        invalid_rows,
+        max_rounding_paisa: max_rounding,
+        net_rounding_paisa: net_rounding,
+        converted_at: String::new(),
+    })
+}
+
+/// Keep the shadows exact whenever the frontend writes rupee amounts.
+/// recursive_triggers is off, so the inner UPDATE does not re-fire them.
+fn install_sync_triggers(tx: &Connection, table: &str, columns: &[&str]) -> Result<(), String> {
+    let assignments = columns
+        .iter()
+        .map(|column| {
+            format!(
+                "{column}_paisa = CASE WHEN typeof(NEW.{column}) IN ('integer', 'real') \
+                 THEN CAST(ROUND(NEW.{column} * 100) AS INTEGER) END"
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    let changed = columns
+        .iter()
+        .map(|column| format!("NEW.{column} IS NOT OLD.{column}"))
+        .collect::<Vec<_>>()
+        .join(" OR ");
+
+    tx.execute_batch(&format!(
+        "DROP TRIGGER IF EXISTS trg_{table}_paisa_insert;
+         DROP TRIGGER IF EXISTS trg_{table}_paisa_update;
+         CREATE TRIGGER trg_{table}_paisa_insert AFTER INSERT ON {table}
+         BEGIN
+             UPDATE {table} SET {assignments} WHERE rowid = NEW.rowid;
+         END;
+         CREATE TRIGGER trg_{table}_paisa_update AFTER UPDATE ON {table}
+         WHEN {changed}
+         BEGIN
+             UPDATE {table} SET {assignments} WHERE rowid = NEW.rowid;
+         END;",
+    ))
+    .map_err(|e| format!("Failed to install paisa triggers on {}: {}", table, e))
+}
+
+/// Rounding report of every column converted so far
+#[tauri::command]
+pub async fn get_money_conversion_report() -> Result<Vec<ColumnConversionReport>, String> {
+    let conn = open_connection()?;
+    ensure_report_table(&conn).map_err(|e| format!("Failed to create money report table: {}", e))?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT table_name, column_name, total_rows, rounded_rows, null_rows, invalid_rows,
+                    max_rounding_paisa, net_rounding_paisa, converted_at
+             FROM money_conversion_report ORDER BY table_name, column_name",
+        )
+        .map_err(|e| format!("Failed to query money report: {}", e))?;
+    let reports = stmt
+        .query_map([], |row| {
+            Ok(ColumnConversionReport {
+                table_name: row.get(0)?,
+                column_name: row.get(1)?,
+                total_rows: row.get(2)?,
+                rounded_rows: row.get(3)?,
+                null_rows: row.get(4)?,
+                invalid_rows: row.get(5)?,
+                max_rounding_paisa: row.get(6)?,
+                net_rounding_paisa: row.get(7)?,
+                converted_at: row.get(8)?,
+            })
+        })
+        .map_err(|e| format!("Failed to read money report: {}", e))?;
+
+    reports
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read money report row: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_reads_rupee_text_exactly() {
+        assert_eq!(Money::parse("1,250.5"), Some(Money::from_paisa(125050)));
+        assert_eq!(Money::parse("-12.75"), Some(Money::from_paisa(-1275)));
+        assert_eq!(Money::parse(".5"), Some(Money::from_paisa(50)));
+        assert_eq!(Money::parse("1.234"), None);
+        assert_eq!(Money::parse("12a"), None);
+        assert_eq!(Money::parse(""), None);
+        assert_eq!(Money::from_paisa(-5).to_string(), "-0.05");
+    }
+
+    #[test]
+    fn rupees_round_half_away_from_zero() {
+        assert_eq!(Money::from_rupees(0.125), Some(Money::from_paisa(13)));
+        assert_eq!(Money::from_rupees(-0.125), Some(Money::from_paisa(-13)));
+        assert_eq!(Money::from_rupees(f64::NAN), None);
+        assert_eq!(Money::from_paisa(1001).mul_ratio(1, 2), Money::from_paisa(501));
+        assert_eq!(Money::from_paisa(-1001).mul_ratio(1, 2), Money::from_paisa(-501));
+        assert_eq!(Money::from_paisa(1000).mul_ratio(1, 0), Money::ZERO);
+    }
+
+    #[test]
+    fn rows_read_numbers_and_text() {
+        let conn = Connection::open_in_memory().unwrap();
+        let read = |sql: &str| conn.query_row(sql, [], |row| money_from_row(row, 0));
+        assert_eq!(read("SELECT 12.5").unwrap(), Money::from_paisa(1250));
+        assert_eq!(read("SELECT 7").unwrap(), Money::from_paisa(700));
+        assert_eq!(read("SELECT NULL").unwrap(), Money::ZERO);
+        assert_eq!(read("SELECT ' '").unwrap(), Money::ZERO);
+        assert_eq!(read("SELECT '1,250.50'").unwrap(), Money::from_paisa(125050));
+        assert_eq!(read("SELECT '10.005'").unwrap(), Money::from_paisa(1001));
+        assert!(read("SELECT 'twelve'").is_err());
+    }
+
+    #[test]
+    fn readers_prefer_the_shadow_column() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE customers (id INTEGER PRIMARY KEY, balance REAL, credit_limit REAL);
+             INSERT INTO customers VALUES (1, 10.005, 0), (2, 'n/a', NULL);",
+        )
+        .unwrap();
+        let balance = |conn: &Connection, id: i64| {
+            let sql = paisa_sql(conn, "customers", "c.balance").unwrap();
+            conn.query_row(&format!("SELECT {sql} FROM customers c WHERE id = ?1"), [id], |row| {
+                paisa_from_row(row, 0)
+            })
+            .unwrap()
+        };
+        assert_eq!(
+            paisa_sql(&conn, "customers", "c.balance").unwrap(),
+            "CAST(ROUND(c.balance * 100) AS INTEGER)"
+        );
+
+        let reports = ensure_paisa_columns(&mut conn).unwrap();
+        assert_eq!((reports[0].rounded_rows, reports[0].invalid_rows), (1, 1));
+        assert_eq!(paisa_sql(&conn, "customers", "c.balance").unwrap(), "c.balance_paisa");
+        assert_eq!(balance(&conn, 1), Money::from_paisa(1001));
+        assert_eq!(balance(&conn, 2), Money::ZERO);
+
+        conn.execute("UPDATE customers SET balance = 99.99 WHERE id = 2", [])
+            .unwrap();
+        assert_eq!(balance(&conn, 2), Money::from_paisa(9999));
+        let total: i64 = conn
+            .query_row(
+                &format!("SELECT {} FROM customers", sum_paisa_sql("balance_paisa")),
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(total, 11000);
+    }
+}
@@ -0,0 +1,730 @@
+/*!
+ * OFFLINE UPDATES
+ *
+ * Shops with patchy internet get new versions from a folder: a configured
+ * directory (a shared folder, a download location) or an
+ * `IttehadIronStoreUpdates` folder at the root of any mounted drive, so a USB
+ * stick works without setup. A folder holds what `tauri build` writes with
+ * `createUpdaterArtifacts`: the installers, their `.sig` files, and a
+ * `latest.json` in the updater's static format whose `url`s name the
+ * installers next to it. Next to it goes `latest.json.sig`, the manifest
+ * signed with the same key (`tauri signer sign latest.json`): the installer
+ * signatures say nothing about the version, and the version decides whether
+ * an update is newer and what a rollback goes back to.
+ *
+ * Every manifest and installer is checked against the minisign public key
+ * embedded at build time from `ITTEHAD_UPDATE_PUBKEY` (the base64 key printed
+ * by `tauri signer generate`); a build without one refuses to install
+ * anything. Only prehashed signatures, as `tauri signer` writes them, count.
+ * Before the installer runs the database is backed up to
+ * `backups/pre-update-<from>-to-<to>-<time>.db` and the update is recorded in
+ * `updates/state.json`, together with a verified installer of the running
+ * version when one is at hand (kept from an earlier update or found in the
+ * same folder).
+ *
+ * If the first start of the new version fails to migrate the database, the
+ * backup is put back and the previous installer, if any, is started. The
+ * owner can do the same later with `rollback_update`.
+ */
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+use base64::Engine;
+use log::{error, info, warn};
+use minisign_verify::{PublicKey, Signature};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::database::{get_app_data_dir, get_db_path, open_connection_at};
+
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Base64 minisign public key the update installers must be signed with
+pub const UPDATE_PUBLIC_KEY: Option<&str> = option_env!("ITTEHAD_UPDATE_PUBKEY");
+
+const MANIFEST_NAME: &str = "latest.json";
+const MANIFEST_SIGNATURE_NAME: &str = "latest.json.sig";
+/// Looked for at the root of every mounted drive
+const DRIVE_FOLDER: &str = "IttehadIronStoreUpdates";
+const UPDATES_DIR: &str = "updates";
+const CONFIG_FILE: &str = "config.json";
+const STATE_FILE: &str = "state.json";
+/// Lets the command's answer reach the window before the installer takes over
+const EXIT_DELAY: Duration = Duration::from_millis(500);
+
+// ============================================================================
+// MANIFESTS
+// ============================================================================
+
+/// `latest.json` as written for the Tauri updater
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    version: String,
+    #[serde(default)]
+    notes: Option<String>,
+    #[serde(default)]
+    pub_date: Option<String>,
+    platforms: HashMap<String, PlatformEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlatformEntry {
+    signature: String,
+    url: String,
+}
+
+/// An update found in a folder, signature not yet checked
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateCandidate {
+    pub version: String,
+    pub notes: Option<String>,
+    pub pub_date: Option<String>,
+    pub manifest: String,
+    pub installer: String,
+    pub size: u64,
+}
+
+struct Release {
+    version: String,
+    installer: PathBuf,
+    signature: String,
+}
+
+/// Updater target of this build, e.g. `windows-x86_64`
+fn target() -> String {
+    let os = match std::env::consts::OS {
+        "macos" => "darwin",
+        os => os,
+    };
+    format!("{}-{}", os, std::env::consts::ARCH)
+}
+
+fn load_manifest(path: &Path) -> Result<Manifest, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&text).map_err(|e| format!("Invalid update manifest {}: {}", path.display(), e))
+}
+
+/// A manifest whose `latest.json.sig` matches the update key, so its version can be believed
+fn load_verified_manifest(path: &Path, public_key: &str) -> Result<Manifest, String> {
+    let data = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let signature_path = path.with_file_name(MANIFEST_SIGNATURE_NAME);
+    let signature = fs::read_to_string(&signature_path)
+        .map_err(|e| format!("The update manifest is not signed ({}): {}", signature_path.display(), e))?;
+    verify_signature(&data, &signature, public_key)
+        .map_err(|e| format!("The update manifest {} was rejected: {}", path.display(), e))?;
+    serde_json::from_slice(&data).map_err(|e| format!("Invalid update manifest {}: {}", path.display(), e))
+}
+
+/// This platform's installer named by a manifest, resolved next to it
+fn release_for_platform(manifest_path: &Path, manifest: &Manifest) -> Result<Release, String> {
+    let target = target();
+    // `windows-x86_64` or an installer-specific `windows-x86_64-nsis`
+    let entry = manifest.platforms.get(&target).or_else(|| {
+        let mut keys: Vec<&String> = manifest
+            .platforms
+            .keys()
+            .filter(|key| key.starts_with(&format!("{}-", target)))
+            .collect();
+        keys.sort();
+        keys.first().and_then(|key| manifest.platforms.get(*key))
+    });
+    let entry = entry.ok_or_else(|| format!("Version {} has no installer for {}", manifest.version, target))?;
+
+    // Only the file name counts: the installer must sit beside the manifest
+    let file_name = entry
+        .url
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .replace("%20", " ");
+    if file_name.is_empty() {
+        return Err(format!("Version {} names no installer file", manifest.version));
+    }
+    let dir = manifest_path.parent().unwrap_or(Path::new("."));
+    Ok(Release {
+        version: manifest.version.clone(),
+        installer: dir.join(file_name),
+        signature: entry.signature.trim().to_string(),
+    })
+}
+
+/// Numeric parts of `1.2.3`, ignoring a `-beta` style suffix
+fn version_parts(version: &str) -> Option<Vec<u64>> {
+    let core = version.trim().trim_start_matches('v').split(['-', '+']).next()?;
+    core.split('.').map(|part| part.parse().ok()).collect()
+}
+
+pub fn compare_versions(a: &str, b: &str) -> Option<Ordering> {
+    let (mut a, mut b) = (version_parts(a)?, version_parts(b)?);
+    let len = a.len().max(b.len());
+    a.resize(len, 0);
+    b.resize(len, 0);
+    Some(a.cmp(&b))
+}
+
+// ============================================================================
+// WHERE TO LOOK
+// ============================================================================
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdateSettings {
+    /// Folder checked besides the drives, holding `latest.json`
+    pub source_dir: Option<String>,
+}
+
+fn updates_dir(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join(UPDATES_DIR)
+}
+
+pub fn load_settings(app_data_dir: &Path) -> UpdateSettings {
+    fs::read_to_string(updates_dir(app_data_dir).join(CONFIG_FILE))
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_settings(app_data_dir: &Path, settings: &UpdateSettings) -> Result<(), String> {
+    write_json(&updates_dir(app_data_dir).join(CONFIG_FILE), settings)
+}
+
+/// Roots of the mounted drives, fixed and removable alike
+fn drive_roots() -> Vec<PathBuf> {
+    if cfg!(target_os = "windows") {
+        // A: and B: are floppy letters, slow to probe and never a USB stick
+        (b'C'..=b'Z')
+            .map(|letter| PathBuf::from(format!("{}:\\", letter as char)))
+            .filter(|root| root.exists())
+            .collect()
+    } else {
+        let parents: &[&str] = if cfg!(target_os = "macos") {
+            &["/Volumes"]
+        } else {
+            &["/media", "/run/media", "/mnt"]
+        };
+        let mut roots = Vec::new();
+        for parent in parents {
+            for entry in fs::read_dir(parent).into_iter().flatten().flatten() {
+                let path = entry.path();
+                roots.push(path.clone());
+                // Linux mounts under /media/<user>/<label>
+                if !cfg!(target_os = "macos") {
+                    roots.extend(
+                        fs::read_dir(&path)
+                            .into_iter()
+                            .flatten()
+                            .flatten()
+                            .map(|entry| entry.path()),
+                    );
+                }
+            }
+        }
+        roots
+    }
+}
+
+/// The configured folder first, then the update folder of each drive
+pub fn search_dirs(app_data_dir: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = load_settings(app_data_dir)
+        .source_dir
+        .into_iter()
+        .map(PathBuf::from)
+        .collect();
+    dirs.extend(drive_roots().into_iter().map(|root| root.join(DRIVE_FOLDER)));
+    dirs.retain(|dir| dir.join(MANIFEST_NAME).is_file());
+    dirs.dedup();
+    dirs
+}
+
+/// Updates newer than `current` in `dirs`, newest first
+pub fn find_updates(dirs: &[PathBuf], current: &str) -> Vec<UpdateCandidate> {
+    let mut candidates = Vec::new();
+    for dir in dirs {
+        let manifest_path = dir.join(MANIFEST_NAME);
+        let found = load_manifest(&manifest_path).and_then(|manifest| {
+            let release = release_for_platform(&manifest_path, &manifest)?;
+            let size = fs::metadata(&release.installer)
+                .map_err(|e| format!("Installer {} is missing: {}", release.installer.display(), e))?
+                .len();
+            Ok((manifest, release, size))
+        });
+        match found {
+            Ok((manifest, release, size)) => {
+                if compare_versions(&manifest.version, current) != Some(Ordering::Greater) {
+                    continue;
+                }
+                candidates.push(UpdateCandidate {
+                    version: manifest.version,
+                    notes: manifest.notes,
+                    pub_date: manifest.pub_date,
+                    manifest: manifest_path.to_string_lossy().to_string(),
+                    installer: release.installer.to_string_lossy().to_string(),
+                    size,
+                });
+            }
+            Err(e) => warn!("[UPDATE] Skipped {}: {}", dir.display(), e),
+        }
+    }
+    candidates.sort_by(|a, b| compare_versions(&b.version, &a.version).unwrap_or(Ordering::Equal));
+    candidates
+}
+
+// ============================================================================
+// SIGNATURES
+// ============================================================================
+
+fn base64_text(value: &str, what: &str) -> Result<String, String> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(value.trim())
+        .map_err(|e| format!("Invalid {}: {}", what, e))?;
+    String::from_utf8(bytes).map_err(|_| format!("Invalid {}: not text", what))
+}
+
+/// Check `data` against a `.sig` produced by `tauri signer sign`, both keys in base64 as the updater stores them
+pub fn verify_signature(data: &[u8], signature: &str, public_key: &str) -> Result<(), String> {
+    let public_key =
+        PublicKey::decode(&base64_text(public_key, "update key")?).map_err(|e| format!("Invalid update key: {}", e))?;
+    let signature =
+        Signature::decode(&base64_text(signature, "signature")?).map_err(|e| format!("Invalid signature: {}", e))?;
+    public_key
+        .verify(data, &signature, false)
+        .map_err(|e| format!("Signature does not match: {}", e))
+}
+
+fn embedded_key() -> Result<&'static str, String> {
+    UPDATE_PUBLIC_KEY
+        .filter(|key| !key.trim().is_empty())
+        .ok_or_else(|| "This build has no update key; it cannot verify updates".to_string())
+}
+
+/// Read the installer and check it before anything is copied or run
+fn read_verified(release: &Release, public_key: &str) -> Result<Vec<u8>, String> {
+    let data =
+        fs::read(&release.installer).map_err(|e| format!("Failed to read {}: {}", release.installer.display(), e))?;
+    verify_signature(&data, &release.signature, public_key)
+        .map_err(|e| format!("Version {} was rejected: {}", release.version, e))?;
+    Ok(data)
+}
+
+// ============================================================================
+// INSTALLING
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateStatus {
+    /// Installer started; the new version has not started yet
+    Installing,
+    /// The new version started and migrated the database
+    Installed,
+    RolledBack,
+}
+
+/// The last update, kept in `updates/state.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateRecord {
+    pub from_version: String,
+    pub to_version: String,
+    pub status: UpdateStatus,
+    pub installer: String,
+    pub backup: String,
+    /// Verified installer of `from_version`, if one was at hand
+    pub rollback_installer: Option<String>,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+}
+
+pub fn load_record(app_data_dir: &Path) -> Option<UpdateRecord> {
+    let text = fs::read_to_string(updates_dir(app_data_dir).join(STATE_FILE)).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+fn save_record(app_data_dir: &Path, record: &UpdateRecord) -> Result<(), String> {
+    write_json(&updates_dir(app_data_dir).join(STATE_FILE), record)
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let text =
+        serde_json::to_string_pretty(value).map_err(|e| format!("Failed to encode {}: {}", path.display(), e))?;
+    fs::write(path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn local_time(format: &str) -> Result<String, String> {
+    Connection::open_in_memory()
+        .and_then(|conn| conn.query_row("SELECT strftime(?1, 'now', 'localtime')", [format], |row| row.get(0)))
+        .map_err(|e| format!("Failed to read the clock: {}", e))
+}
+
+/// Copy a verified installer into `updates/<version>/` so the drive can be removed
+fn keep_installer(app_data_dir: &Path, release: &Release, data: &[u8]) -> Result<PathBuf, String> {
+    let dir = updates_dir(app_data_dir).join(&release.version);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let file_name = release.installer.file_name().unwrap_or_default();
+    let kept = dir.join(file_name);
+    fs::write(&kept, data).map_err(|e| format!("Failed to copy the installer: {}", e))?;
+    let mut signature_name = file_name.to_os_string();
+    signature_name.push(".sig");
+    fs::write(dir.join(signature_name), &release.signature)
+        .map_err(|e| format!("Failed to copy the signature: {}", e))?;
+    Ok(kept)
+}
+
+/// A verified installer of `version`: kept from an earlier update, or in one of `dirs`
+fn installer_for_version(app_data_dir: &Path, dirs: &[PathBuf], version: &str, public_key: &str) -> Option<PathBuf> {
+    let kept_dir = updates_dir(app_data_dir).join(version);
+    let kept = fs::read_dir(&kept_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .find_map(|entry| {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "sig") {
+                return None;
+            }
+            let mut signature_path = path.clone().into_os_string();
+            signature_path.push(".sig");
+            let signature = fs::read_to_string(PathBuf::from(signature_path)).ok()?;
+            let data = fs::read(&path).ok()?;
+            verify_signature(&data, &signature, public_key).ok().map(|_| path)
+        });
+    if kept.is_some() {
+        return kept;
+    }
+
+    dirs.iter().find_map(|dir| {
+        let manifest_path = dir.join(MANIFEST_NAME);
+        let manifest = load_verified_manifest(&manifest_path, public_key).ok()?;
+        if compare_versions(&manifest.version, version) != Some(Ordering::Equal) {
+            return None;
+        }
+        let release = release_for_platform(&manifest_path, &manifest).ok()?;
+        let data = read_verified(&release, public_key).ok()?;
+        keep_installer(app_data_dir, &release, &data).ok()
+    })
+}
+
+/// Verify the update named by `manifest_path`, keep its installer, back up the
+/// database and record the update. The installer is not started.
+pub fn prepare_update(
+    app_data_dir: &Path,
+    db_path: &Path,
+    manifest_path: &Path,
+    public_key: &str,
+) -> Result<UpdateRecord, String> {
+    let manifest = load_verified_manifest(manifest_path, public_key)?;
+    if compare_versions(&manifest.version, CURRENT_VERSION) != Some(Ordering::Greater) {
+        return Err(format!(
+            "Version {} is not newer than the installed {}",
+            manifest.version, CURRENT_VERSION
+        ));
+    }
+    let release = release_for_platform(manifest_path, &manifest)?;
+    let data = read_verified(&release, public_key)?;
+    info!("[UPDATE] Version {} is signed with the update key", release.version);
+
+    let installer = keep_installer(app_data_dir, &release, &data)?;
+    let dirs = manifest_path
+        .parent()
+        .map(Path::to_path_buf)
+        .into_iter()
+        .collect::<Vec<_>>();
+    let rollback_installer = installer_for_version(app_data_dir, &dirs, CURRENT_VERSION, public_key);
+    if rollback_installer.is_none() {
+        warn!(
+            "[UPDATE] No installer of {} at hand; a rollback will restore the data only",
+            CURRENT_VERSION
+        );
+    }
+
+    let backup_dir = db_path.with_file_name("backups");
+    fs::create_dir_all(&backup_dir).map_err(|e| format!("Failed to create {}: {}", backup_dir.display(), e))?;
+    let backup = backup_dir.join(format!(
+        "pre-update-{}-to-{}-{}.db",
+        CURRENT_VERSION,
+        release.version,
+        local_time("%Y%m%d-%H%M%S")?
+    ));
+    crate::consistent_backup(db_path, &backup)?;
+    info!("[UPDATE] Backed up the database to {}", backup.display());
+
+    let record = UpdateRecord {
+        from_version: CURRENT_VERSION.to_string(),
+        to_version: release.version,
+        status: UpdateStatus::Installing,
+        installer: installer.to_string_lossy().to_string(),
+        backup: backup.to_string_lossy().to_string(),
+        rollback_installer: rollback_installer.map(|path| path.to_string_lossy().to_string()),
+        started_at: local_time("%Y-%m-%d %H:%M:%S")?,
+        finished_at: None,
+    };
+    save_record(app_data_dir, &record)?;
+    Ok(record)
+}
+
+/// Start an installer the way the Tauri updater does. The caller exits afterwards.
+pub fn launch_installer(installer: &Path) -> Result<(), String> {
+    let extension = installer
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let started = match extension.as_str() {
+        // Passive, relaunch when done, keep shortcuts and settings
+        "exe" => Command::new(installer).args(["/P", "/R", "/UPDATE"]).spawn(),
+        "msi" => Command::new("msiexec")
+            .arg("/i")
+            .arg(installer)
+            .args(["/passive", "/promptrestart", "AUTOLAUNCHAPP=True"])
+            .spawn(),
+        "appimage" => return replace_appimage(installer),
+        _ => {
+            return Err(format!(
+                "{} cannot be installed offline on this system",
+                installer.display()
+            ))
+        }
+    };
+    started.map_err(|e| format!("Failed to start {}: {}", installer.display(), e))?;
+    info!("[UPDATE] Started installer {}", installer.display());
+    Ok(())
+}
+
+/// An AppImage updates by replacing the running file, then relaunching
+fn replace_appimage(installer: &Path) -> Result<(), String> {
+    let target = std::env::var_os("APPIMAGE")
+        .map(PathBuf::from)
+        .ok_or_else(|| "Not running from an AppImage".to_string())?;
+    let mut staged = target.clone().into_os_string();
+    staged.push(".update");
+    let staged = PathBuf::from(staged);
+    fs::copy(installer, &staged).map_err(|e| format!("Failed to copy {}: {}", installer.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&staged, fs::Permissions::from_mode(0o755))
+            .map_err(|e| format!("Failed to make {} executable: {}", staged.display(), e))?;
+    }
+    fs::rename(&staged, &target).map_err(|e| format!("Failed to replace {}: {}", target.display(), e))?;
+    crate::restart::relaunch(false)?;
+    Ok(())
+}
+
+/// Put the pre-update backup in place of the live database
+fn restore_backup(db_path: &Path, record: &UpdateRecord) -> Result<(), String> {
+    let data = fs::read(&record.backup).map_err(|e| format!("Failed to read backup {}: {}", record.backup, e))?;
+    crate::restore_database_file(db_path, &data)?;
+    for suffix in ["-wal", "-shm"] {
+        let mut name = db_path.file_name().unwrap_or_default().to_os_string();
+        name.push(suffix);
+        let sidecar = db_path.with_file_name(name);
+        if sidecar.exists() {
+            fs::remove_file(&sidecar).map_err(|e| format!("Failed to remove {}: {}", sidecar.display(), e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Restore the pre-update backup and start the previous installer if there is one.
+/// Returns whether an installer was started, in which case the caller exits.
+pub fn roll_back(app_data_dir: &Path, db_path: &Path, record: &mut UpdateRecord) -> Result<bool, String> {
+    restore_backup(db_path, record)?;
+    info!(
+        "[UPDATE] Restored the database from before the update to {}",
+        record.to_version
+    );
+    record.status = UpdateStatus::RolledBack;
+    record.finished_at = Some(local_time("%Y-%m-%d %H:%M:%S")?);
+    save_record(app_data_dir, record)?;
+
+    match &record.rollback_installer {
+        Some(installer) => {
+            launch_installer(Path::new(installer))?;
+            Ok(true)
+        }
+        None => {
+            warn!(
+                "[UPDATE] Install version {} again to finish the rollback",
+                record.from_version
+            );
+            Ok(false)
+        }
+    }
+}
+
+/// At startup after the database was migrated: the update this version came from is done
+pub fn finish_pending(app_data_dir: &Path) {
+    let Some(mut record) = load_record(app_data_dir) else {
+        return;
+    };
+    if record.status != UpdateStatus::Installing || record.to_version != CURRENT_VERSION {
+        return;
+    }
+    record.status = UpdateStatus::Installed;
+    record.finished_at = local_time("%Y-%m-%d %H:%M:%S").ok();
+    match save_record(app_data_dir, &record) {
+        Ok(_) => info!("[UPDATE] Updated from {} to {}", record.from_version, record.to_version),
+        Err(e) => error!("[UPDATE] {}", e),
+    }
+}
+
+/// At startup when migrating the database failed: undo the update this version came from.
+/// Returns whether the previous installer was started.
+pub fn roll_back_failed_update(app_data_dir: &Path, db_path: &Path) -> Result<bool, String> {
+    let Some(mut record) = load_record(app_data_dir) else {
+        return Ok(false);
+    };
+    if record.status != UpdateStatus::Installing || record.to_version != CURRENT_VERSION {
+        return Ok(false);
+    }
+    warn!(
+        "[UPDATE] Version {} failed to migrate the database; rolling back to {}",
+        record.to_version, record.from_version
+    );
+    roll_back(app_data_dir, db_path, &mut record)
+}
+
+// ============================================================================
+// APP COMMANDS
+// ============================================================================
+
+#[tauri::command]
+pub async fn get_update_settings() -> Result<UpdateSettings, String> {
+    Ok(load_settings(&get_app_data_dir()?))
+}
+
+#[tauri::command]
+pub async fn set_update_source(source_dir: Option<String>) -> Result<UpdateSettings, String> {
+    let source_dir = source_dir
+        .map(|dir| dir.trim().to_string())
+        .filter(|dir| !dir.is_empty());
+    if let Some(dir) = &source_dir {
+        if !Path::new(dir).is_dir() {
+            return Err(format!("Folder not found: {}", dir));
+        }
+    }
+    let settings = UpdateSettings { source_dir };
+    save_settings(&get_app_data_dir()?, &settings)?;
+    Ok(settings)
+}
+
+/// Updates newer than the running version in the configured folder and on the drives
+#[tauri::command]
+pub async fn check_offline_updates() -> Result<Vec<UpdateCandidate>, String> {
+    let app_data_dir = get_app_data_dir()?;
+    let dirs = search_dirs(&app_data_dir);
+    let candidates = find_updates(&dirs, CURRENT_VERSION);
+    info!(
+        "[UPDATE] Checked {} folders, {} newer versions",
+        dirs.len(),
+        candidates.len()
+    );
+    Ok(candidates)
+}
+
+/// Verify, back up and start the installer of the update in `manifest`, then exit
+#[tauri::command]
+pub async fn install_offline_update(app: AppHandle, manifest: String) -> Result<UpdateRecord, String> {
+    let app_data_dir = get_app_data_dir()?;
+    let db_path = get_db_path()?;
+    let record = prepare_update(&app_data_dir, &db_path, Path::new(&manifest), embedded_key()?)
+        .map_err(|e| format!("Failed to prepare the update: {}", e))?;
+    launch_installer(Path::new(&record.installer)).map_err(|e| format!("Failed to install the update: {}", e))?;
+    exit_soon(app);
+    Ok(record)
+}
+
+/// The last update, if any
+#[tauri::command]
+pub async fn get_last_update() -> Result<Option<UpdateRecord>, String> {
+    Ok(load_record(&get_app_data_dir()?))
+}
+
+/// Go back to the version and data from before the last update
+#[tauri::command]
+pub async fn rollback_update(app: AppHandle) -> Result<UpdateRecord, String> {
+    let app_data_dir = get_app_data_dir()?;
+    let mut record = load_record(&app_data_dir).ok_or_else(|| "No update to roll back".to_string())?;
+    if record.status != UpdateStatus::Installed || record.to_version != CURRENT_VERSION {
+        return Err(format!(
+            "The update to {} is not the running version's; nothing to roll back",
+            record.to_version
+        ));
+    }
+    // With the newer version still installed the restored data would be migrated again on the next start
+    if record.rollback_installer.is_none() {
+        return Err(format!(
+            "No installer of {} at hand; put it in the update folder and check again",
+            record.from_version
+        ));
+    }
+    let db_path = get_db_path()?;
+    // Fold the WAL in so nothing newer is replayed onto the restored file
+    open_connection_at(&db_path)?
+        .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))
+        .map_err(|e| format!("Failed to checkpoint the database: {}", e))?;
+    let installer_started = roll_back(&app_data_dir, &db_path, &mut record)
+        .map_err(|e| format!("Failed to roll back the update: {}", e))?;
+    if installer_started {
+        exit_soon(app);
+    }
+    Ok(record)
+}
+
+fn exit_soon(app: AppHandle) {
+    thread::spawn(move || {
+        thread::sleep(EXIT_DELAY);
+        app.exit(0);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Key and signatures of the file `test` from minisign's own test vectors
+    const PUBLIC_KEY: &str = "untrusted comment: minisign public key E7620F1842B4E81F
+RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3
+";
+    const PREHASHED_SIGNATURE: &str = "untrusted comment: signature from minisign secret key
+RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=
+trusted comment: timestamp:1556193335\tfile:test
+y/rUw2y8/hOUYjZU71eHp/Wo1KZ40fGy2VJEDl34XMJM+TX48Ss/17u3IvIfbVR1FkZZSNCisQbuQY+bHwhEBg==
+";
+    const LEGACY_SIGNATURE: &str = "untrusted comment: signature from minisign secret key
+RWQf6LRCGA9i59SLOFxz6NxvASXDJeRtuZykwQepbDEGt87ig1BNpWaVWuNrm73YiIiJbq71Wi+dP9eKL8OC351vwIasSSbXxwA=
+trusted comment: timestamp:1555779966\tfile:test
+QtKMXWyYcwdpZAlPF7tE2ENJkRd1ujvKjlj1m9RtHTBnZPa5WKU5uWRs5GoP5M/VqE81QFuMKI5k/SfNQUaOAA==
+";
+
+    fn base64(text: &str) -> String {
+        base64::engine::general_purpose::STANDARD.encode(text)
+    }
+
+    #[test]
+    fn versions_compare_by_number() {
+        assert_eq!(compare_versions("1.10.0", "1.9.2"), Some(Ordering::Greater));
+        assert_eq!(compare_versions("1.2", "1.2.0"), Some(Ordering::Equal));
+        assert_eq!(compare_versions("v1.0.0", "1.0.0-beta"), Some(Ordering::Equal));
+        assert_eq!(compare_versions("0.9.9", "1.0.0"), Some(Ordering::Less));
+        assert_eq!(compare_versions("next", "1.0.0"), None);
+        assert_eq!(compare_versions("1.x", "1.0.0"), None);
+    }
+
+    #[test]
+    fn signatures_must_be_prehashed_and_match() {
+        let key = base64(PUBLIC_KEY);
+        assert!(verify_signature(b"test", &base64(PREHASHED_SIGNATURE), &key).is_ok());
+        assert!(verify_signature(b"Test", &base64(PREHASHED_SIGNATURE), &key).is_err());
+        // Valid, but signed the legacy way
+        assert!(verify_signature(b"test", &base64(LEGACY_SIGNATURE), &key).is_err());
+        assert!(verify_signature(b"test", PREHASHED_SIGNATURE, &key).is_err());
+        assert!(verify_signature(b"test", &base64(PREHASHED_SIGNATURE), "not a key").is_err());
+    }
+}
@@ -0,0 +1,726 @@
+/*!
+ * STOCK QUANTITY UNIT MODEL
+ * The frontend stores stock as unit-formatted text ("12-500", "12kg 500g",
+ * "150 bags", "24 ft"). Quantities are converted to integer base units so
+ * stock arithmetic is exact:
+ *   weight (kg-grams, kg, ton)  -> grams
+ *   count  (piece, bag)         -> pieces
+ *   length (foot, meter)        -> tenths of a millimetre (1 ft = 3048)
+ * Units of the same dimension share a base unit, so converting between them
+ * never changes the stored integer. Every quantity column also gets a
+ * `<column>_base INTEGER` shadow; the startup migration converts the legacy
+ * text and records the rows it cannot parse in `quantity_conversion_issues`.
+ *
+ * The text stays the record. A shadow is a cache of its parse on this
+ * terminal: the frontend rewrites the text without it, so a trigger clears
+ * the shadow, readers parse the text while it is NULL and the next startup
+ * fills it again. Shadows are not synced; each terminal derives its own.
+ */
+
+use std::fmt;
+
+use log::warn;
+use rusqlite::{params, Connection, Transaction};
+use serde::Serialize;
+
+use crate::database::{has_column, open_connection, table_exists};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dimension {
+    Weight,
+    Count,
+    Length,
+}
+
+/// Values of `products.unit_type`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnitType {
+    KgGrams,
+    Kg,
+    Ton,
+    Piece,
+    Bag,
+    Foot,
+    Meter,
+}
+
+impl UnitType {
+    /// Parse a `unit_type` value; empty means the column default (kg-grams)
+    pub fn parse(unit_type: &str) -> Option<UnitType> {
+        match unit_type.trim().to_ascii_lowercase().as_str() {
+            "" | "kg-grams" => Some(UnitType::KgGrams),
+            "kg" => Some(UnitType::Kg),
+            "ton" => Some(UnitType::Ton),
+            "piece" => Some(UnitType::Piece),
+            "bag" => Some(UnitType::Bag),
+            "foot" => Some(UnitType::Foot),
+            "meter" => Some(UnitType::Meter),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            UnitType::KgGrams => "kg-grams",
+            UnitType::Kg => "kg",
+            UnitType::Ton => "ton",
+            UnitType::Piece => "piece",
+            UnitType::Bag => "bag",
+            UnitType::Foot => "foot",
+            UnitType::Meter => "meter",
+        }
+    }
+
+    pub fn dimension(self) -> Dimension {
+        match self {
+            UnitType::KgGrams | UnitType::Kg | UnitType::Ton => Dimension::Weight,
+            UnitType::Piece | UnitType::Bag => Dimension::Count,
+            UnitType::Foot | UnitType::Meter => Dimension::Length,
+        }
+    }
+
+    /// Base units in one unit (one kg, one ton, one piece, one foot...)
+    pub fn base_per_unit(self) -> i64 {
+        match self {
+            UnitType::KgGrams | UnitType::Kg => 1_000,
+            UnitType::Ton => 1_000_000,
+            UnitType::Piece | UnitType::Bag => 1,
+            UnitType::Foot => 3_048,
+            UnitType::Meter => 10_000,
+        }
+    }
+
+    /// Base units represented by a plain number in a movement row: weight
+    /// products record grams, every other unit records its own count
+    fn base_per_movement_number(self) -> i64 {
+        match self {
+            UnitType::KgGrams | UnitType::Kg => 1,
+            other => other.base_per_unit(),
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            UnitType::KgGrams | UnitType::Kg => "kg",
+            UnitType::Ton => "tons",
+            UnitType::Piece => "pieces",
+            UnitType::Bag => "bags",
+            UnitType::Foot => "ft",
+            UnitType::Meter => "m",
+        }
+    }
+}
+
+/// A quantity in integer base units together with the unit it is expressed in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quantity {
+    pub base: i64,
+    pub unit: UnitType,
+}
+
+impl Quantity {
+    pub fn new(base: i64, unit: UnitType) -> Quantity {
+        Quantity { base, unit }
+    }
+
+    /// Re-express in another unit of the same dimension (kg -> ton, ft -> m)
+    pub fn convert_to(self, unit: UnitType) -> Option<Quantity> {
+        (self.unit.dimension() == unit.dimension()).then_some(Quantity { base: self.base, unit })
+    }
+
+    /// Plain number of units (decimal kg for weight products)
+    pub fn to_number(self) -> f64 {
+        self.base as f64 / self.unit.base_per_unit() as f64
+    }
+}
+
+/// Human-readable form: "12kg 500g", "150 bags", "24 ft"
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.unit {
+            UnitType::KgGrams | UnitType::Kg => {
+                let sign = if self.base < 0 { "-" } else { "" };
+                let (kg, grams) = (self.base.abs() / 1000, self.base.abs() % 1000);
+                if grams > 0 {
+                    write!(f, "{}{}kg {}g", sign, kg, grams)
+                } else {
+                    write!(f, "{}{}kg", sign, kg)
+                }
+            }
+            unit => write!(f, "{} {}", format_number(self.to_number()), unit.symbol()),
+        }
+    }
+}
+
+/// Multiply a decimal number of units into base units
+fn scale(value: f64, factor: i64, unit: UnitType) -> Option<i64> {
+    let base = value * factor as f64;
+    if !base.is_finite() || base.abs() >= i64::MAX as f64 {
+        return None;
+    }
+    let rounded = base.round();
+    if unit.dimension() == Dimension::Count && (base - rounded).abs() > 1e-6 {
+        return None;
+    }
+    Some(rounded as i64)
+}
+
+/// Read any SQLite value as text (quantity columns hold TEXT, REAL or INTEGER)
+pub fn value_to_text(value: rusqlite::types::Value) -> String {
+    use rusqlite::types::Value;
+    match value {
+        Value::Null => String::new(),
+        Value::Integer(i) => i.to_string(),
+        Value::Real(f) => format_number(f),
+        Value::Text(s) => s,
+        Value::Blob(_) => String::new(),
+    }
+}
+
+/// Format a number without a trailing ".0", rounded to three decimals
+pub fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{}", value as i64)
+    } else {
+        format!("{}", (value * 1000.0).round() / 1000.0)
+    }
+}
+
+/// Movement quantities are written as plain numbers (grams for weight
+/// products, the unit count otherwise, negative for OUT); older rows may
+/// still hold "12-500" strings.
+pub fn parse_movement_quantity(text: &str, unit_type: &str) -> Option<i64> {
+    let unit = UnitType::parse(unit_type)?;
+    let text = text.trim();
+    if let Ok(value) = text.parse::<f64>() {
+        return scale(value, unit.base_per_movement_number(), unit);
+    }
+    parse_stock_text(text, unit_type)
+}
+
+/// Parse `products.current_stock` ("12-500", "12kg 500g", "150 bags") into base units
+pub fn parse_stock_text(text: &str, unit_type: &str) -> Option<i64> {
+    let unit = UnitType::parse(unit_type)?;
+    let text = text.trim();
+    if text.is_empty() {
+        return Some(0);
+    }
+    let (negative, body) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest.trim()),
+        None => (false, text),
+    };
+    let base = match unit {
+        UnitType::KgGrams | UnitType::Kg => parse_weight_text(body)?,
+        _ => {
+            let split = body
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .unwrap_or(body.len());
+            let (number, suffix) = body.split_at(split);
+            if !suffix.chars().all(|c| c.is_ascii_alphabetic() || c.is_whitespace()) {
+                return None;
+            }
+            scale(number.parse::<f64>().ok()?, unit.base_per_unit(), unit)?
+        }
+    };
+    Some(if negative { -base } else { base })
+}
+
+/// Grams in "12-500", "12kg 500g", "12kg", "500g" or a decimal kg like "12.5"
+fn parse_weight_text(body: &str) -> Option<i64> {
+    let compact: String = body
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    let grams = if let Some((kg, rest)) = compact.split_once("kg") {
+        let grams = match rest {
+            "" => 0.0,
+            _ => rest.strip_suffix('g')?.parse::<f64>().ok()?,
+        };
+        kg.parse::<f64>().ok()? * 1000.0 + grams
+    } else if let Some(grams) = compact.strip_suffix('g') {
+        grams.parse::<f64>().ok()?
+    } else if let Some((kg, grams)) = compact.split_once('-') {
+        kg.parse::<f64>().ok()? * 1000.0 + grams.parse::<f64>().ok()?
+    } else {
+        compact.parse::<f64>().ok()? * 1000.0
+    };
+    scale(grams, 1, UnitType::KgGrams)
+}
+
+/// Format base units back into the raw `current_stock` format the frontend writes
+pub fn format_stock_text(base: i64, unit_type: &str) -> String {
+    match UnitType::parse(unit_type) {
+        Some(UnitType::KgGrams) => {
+            let sign = if base < 0 { "-" } else { "" };
+            let (kg, grams) = (base.abs() / 1000, base.abs() % 1000);
+            if grams > 0 {
+                format!("{}{}-{}", sign, kg, grams)
+            } else {
+                format!("{}{}", sign, kg)
+            }
+        }
+        Some(unit) => format_number(Quantity::new(base, unit).to_number()),
+        None => base.to_string(),
+    }
+}
+
+/// Format base units as a movement quantity (grams for weight products)
+pub fn format_movement_quantity(base: i64, unit_type: &str) -> String {
+    format_number(to_movement_number(base, unit_type))
+}
+
+/// Base units as the plain number movements and `products.stock_quantity` hold
+pub fn to_movement_number(base: i64, unit_type: &str) -> f64 {
+    match UnitType::parse(unit_type) {
+        Some(unit) => base as f64 / unit.base_per_movement_number() as f64,
+        None => base as f64,
+    }
+}
+
+/// Convert base units to the plain number stored in REAL quantity columns
+/// (decimal kg for weight products, the count otherwise)
+pub fn to_unit_number(base: i64, unit_type: &str) -> f64 {
+    match UnitType::parse(unit_type) {
+        Some(unit) => Quantity::new(base, unit).to_number(),
+        None => base as f64,
+    }
+}
+
+/// Write a product's stock as text plus its `current_stock_base` shadow when
+/// the column has been added
+pub fn set_product_stock(tx: &Transaction, product_id: i64, base: i64, unit_type: &str) -> rusqlite::Result<()> {
+    tx.execute(
+        "UPDATE products SET current_stock = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        params![format_stock_text(base, unit_type), product_id],
+    )?;
+    if has_column(tx, "products", "current_stock_base")? {
+        tx.execute(
+            "UPDATE products SET current_stock_base = ?1 WHERE id = ?2",
+            params![base, product_id],
+        )?;
+    }
+    Ok(())
+}
+
+/// Fill the base shadows of a movement the caller has just inserted
+pub fn set_movement_bases(
+    tx: &Transaction,
+    movement_id: i64,
+    quantity: i64,
+    stock_before: i64,
+    stock_after: i64,
+) -> rusqlite::Result<()> {
+    if has_column(tx, "stock_movements", "quantity_base")? {
+        tx.execute(
+            "UPDATE stock_movements
+             SET quantity_base = ?1, stock_before_base = ?2, stock_after_base = ?3
+             WHERE id = ?4",
+            params![quantity, stock_before, stock_after, movement_id],
+        )?;
+    }
+    Ok(())
+}
+
+/// A quantity in every representation the frontend needs
+#[derive(Debug, Serialize)]
+pub struct ConvertedQuantity {
+    pub unit_type: String,
+    pub base: i64,
+    pub number: f64,
+    pub stock_text: String,
+    pub display: String,
+}
+
+/// Convert a stock text between units of the same dimension
+/// ("12-500" kg-grams -> "0.0125" ton, "12" foot -> "3.658" meter)
+#[tauri::command]
+pub async fn convert_quantity(quantity: String, from_unit_type: String, to_unit_type: String) -> Result<ConvertedQuantity, String> {
+    let from = UnitType::parse(&from_unit_type).ok_or_else(|| format!("Unknown unit type '{}'", from_unit_type))?;
+    let to = UnitType::parse(&to_unit_type).ok_or_else(|| format!("Unknown unit type '{}'", to_unit_type))?;
+    let base = parse_stock_text(&quantity, from.as_str())
+        .ok_or_else(|| format!("Cannot parse '{}' as {}", quantity, from.as_str()))?;
+    let converted = Quantity::new(base, from)
+        .convert_to(to)
+        .ok_or_else(|| format!("Cannot convert {} to {}", from.as_str(), to.as_str()))?;
+
+    Ok(ConvertedQuantity {
+        unit_type: to.as_str().to_string(),
+        base: converted.base,
+        number: converted.to_number(),
+        stock_text: format_stock_text(converted.base, to.as_str()),
+        display: converted.to_string(),
+    })
+}
+
+// ===================================================================
+// LEGACY TEXT MIGRATION
+// ===================================================================
+
+/// A quantity the migration could not convert; the base shadow stays NULL
+#[derive(Debug, Serialize)]
+pub struct QuantityConversionIssue {
+    pub table_name: String,
+    pub row_id: i64,
+    pub column_name: String,
+    pub raw_value: String,
+    pub unit_type: String,
+    pub flagged_at: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct QuantityConversionSummary {
+    pub products_converted: usize,
+    pub movements_converted: usize,
+    pub rows_flagged: usize,
+}
+
+/// Shadow columns per table: (text column, base column)
+const QUANTITY_COLUMNS: &[(&str, &[(&str, &str)])] = &[
+    ("products", &[("current_stock", "current_stock_base")]),
+    (
+        "stock_movements",
+        &[
+            ("quantity", "quantity_base"),
+            ("stock_before", "stock_before_base"),
+            ("stock_after", "stock_after_base"),
+        ],
+    ),
+];
+
+/// Whether `column` of `table` is a base shadow, a per-terminal cache of its text
+pub fn is_base_shadow(table: &str, column: &str) -> bool {
+    QUANTITY_COLUMNS
+        .iter()
+        .filter(|(name, _)| *name == table)
+        .any(|(_, columns)| columns.iter().any(|(_, base_column)| *base_column == column))
+}
+
+fn ensure_issue_table(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS quantity_conversion_issues (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            table_name TEXT NOT NULL,
+            row_id INTEGER NOT NULL,
+            column_name TEXT NOT NULL,
+            raw_value TEXT NOT NULL,
+            unit_type TEXT NOT NULL,
+            flagged_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE(table_name, row_id, column_name)
+        )",
+    )
+}
+
+/// Add the `_base` shadow columns, install the triggers that invalidate a
+/// shadow when the frontend rewrites its text, and convert every row whose
+/// shadow is NULL. Safe to run on every startup; rows that still cannot be
+/// parsed stay flagged and are retried on the next run.
+pub fn ensure_quantity_columns(conn: &mut Connection) -> Result<QuantityConversionSummary, String> {
+    ensure_issue_table(conn).map_err(|e| format!("Failed to create quantity issue table: {}", e))?;
+
+    // A savepoint, so the conversion also nests inside the startup migration's transaction
+    let tx = conn
+        .savepoint()
+        .map_err(|e| format!("Failed to start quantity conversion: {}", e))?;
+    let mut summary = QuantityConversionSummary::default();
+
+    for (table, columns) in QUANTITY_COLUMNS {
+        if !table_exists(&tx, table).map_err(|e| format!("Failed to inspect schema: {}", e))? {
+            continue;
+        }
+        for (text_column, base_column) in columns.iter() {
+            if !has_column(&tx, table, base_column).map_err(|e| format!("Failed to read columns of {}: {}", table, e))? {
+                tx.execute_batch(&format!("ALTER TABLE {} ADD COLUMN {} INTEGER", table, base_column))
+                    .map_err(|e| format!("Failed to add {}.{}: {}", table, base_column, e))?;
+            }
+            install_invalidation_trigger(&tx, table, text_column, base_column)?;
+        }
+    }
+
+    if table_exists(&tx, "products").map_err(|e| format!("Failed to inspect schema: {}", e))? {
+        convert_products(&tx, &mut summary)?;
+        if table_exists(&tx, "stock_movements").map_err(|e| format!("Failed to inspect schema: {}", e))? {
+            convert_movements(&tx, &mut summary)?;
+        }
+    }
+
+    tx.commit()
+        .map_err(|e| format!("Failed to commit quantity conversion: {}", e))?;
+
+    if summary.rows_flagged > 0 {
+        warn!(
+            "[QUANTITY] {} quantities could not be parsed; see quantity_conversion_issues",
+            summary.rows_flagged
+        );
+    }
+    Ok(summary)
+}
+
+/// Clear a shadow when its text changes through a write that did not also set
+/// the shadow, so readers fall back to the text until the next conversion run.
+/// The trigger cannot parse unit text itself: the frontend's connection has
+/// none of the Rust functions.
+fn install_invalidation_trigger(tx: &Connection, table: &str, text_column: &str, base_column: &str) -> Result<(), String> {
+    let trigger = format!("trg_{}_{}_invalidate", table, base_column);
+    tx.execute_batch(&format!(
+        "DROP TRIGGER IF EXISTS {trigger};
+         CREATE TRIGGER {trigger}
+         AFTER UPDATE OF {text_column} ON {table}
+         WHEN NEW.{text_column} IS NOT OLD.{text_column} AND NEW.{base_column} IS OLD.{base_column}
+         BEGIN
+           UPDATE {table} SET {base_column} = NULL WHERE id = NEW.id;
+         END;"
+    ))
+    .map_err(|e| format!("Failed to install {}: {}", trigger, e))
+}
+
+fn convert_products(tx: &Connection, summary: &mut QuantityConversionSummary) -> Result<(), String> {
+    let rows: Vec<(i64, String, String)> = {
+        let mut stmt = tx
+            .prepare(
+                "SELECT id, current_stock, COALESCE(unit_type, 'kg-grams')
+                 FROM products WHERE current_stock_base IS NULL",
+            )
+            .map_err(|e| format!("Failed to query products: {}", e))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get(0)?, row.get::<_, rusqlite::types::Value>(1).map(value_to_text)?, row.get(2)?))
+            })
+            .map_err(|e| format!("Failed to read products: {}", e))?;
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read product: {}", e))?
+    };
+
+    for (id, current_stock, unit_type) in rows {
+        match parse_stock_text(&current_stock, &unit_type) {
+            Some(base) => {
+                tx.execute("UPDATE products SET current_stock_base = ?1 WHERE id = ?2", params![base, id])
+                    .map_err(|e| format!("Failed to convert stock of product {}: {}", id, e))?;
+                clear_issue(tx, "products", id, "current_stock")?;
+                summary.products_converted += 1;
+            }
+            None => {
+                flag_issue(tx, "products", id, "current_stock", &current_stock, &unit_type)?;
+                summary.rows_flagged += 1;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn convert_movements(tx: &Connection, summary: &mut QuantityConversionSummary) -> Result<(), String> {
+    type MovementTexts = (i64, String, String, String, String);
+    let rows: Vec<MovementTexts> = {
+        let mut stmt = tx
+            .prepare(
+                "SELECT m.id, m.quantity, m.stock_before, m.stock_after, COALESCE(p.unit_type, 'kg-grams')
+                 FROM stock_movements m
+                 JOIN products p ON p.id = m.product_id
+                 WHERE m.quantity_base IS NULL
+                    OR (m.stock_before_base IS NULL AND m.stock_before != '')
+                    OR (m.stock_after_base IS NULL AND m.stock_after != '')",
+            )
+            .map_err(|e| format!("Failed to query stock movements: {}", e))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get::<_, rusqlite::types::Value>(1).map(value_to_text)?,
+                    row.get::<_, rusqlite::types::Value>(2).map(value_to_text)?,
+                    row.get::<_, rusqlite::types::Value>(3).map(value_to_text)?,
+                    row.get(4)?,
+                ))
+            })
+            .map_err(|e| format!("Failed to read stock movements: {}", e))?;
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read stock movement: {}", e))?
+    };
+
+    for (id, quantity, stock_before, stock_after, unit_type) in rows {
+        let mut bases = [None; 3];
+        let mut flagged = false;
+        for (slot, (column, text)) in [("quantity", &quantity), ("stock_before", &stock_before), ("stock_after", &stock_after)]
+            .into_iter()
+            .enumerate()
+        {
+            // Blank running balances are legitimately unknown, not unparseable
+            if column != "quantity" && text.trim().is_empty() {
+                continue;
+            }
+            match parse_movement_quantity(text, &unit_type) {
+                Some(base) => {
+                    bases[slot] = Some(base);
+                    clear_issue(tx, "stock_movements", id, column)?;
+                }
+                None => {
+                    flag_issue(tx, "stock_movements", id, column, text, &unit_type)?;
+                    flagged = true;
+                }
+            }
+        }
+
+        tx.execute(
+            "UPDATE stock_movements
+             SET quantity_base = ?1, stock_before_base = ?2, stock_after_base = ?3
+             WHERE id = ?4",
+            params![bases[0], bases[1], bases[2], id],
+        )
+        .map_err(|e| format!("Failed to convert stock movement {}: {}", id, e))?;
+        if flagged {
+            summary.rows_flagged += 1;
+        } else {
+            summary.movements_converted += 1;
+        }
+    }
+    Ok(())
+}
+
+fn flag_issue(tx: &Connection, table: &str, row_id: i64, column: &str, raw_value: &str, unit_type: &str) -> Result<(), String> {
+    tx.execute(
+        "INSERT INTO quantity_conversion_issues (table_name, row_id, column_name, raw_value, unit_type)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(table_name, row_id, column_name) DO UPDATE SET
+            raw_value = excluded.raw_value, unit_type = excluded.unit_type, flagged_at = CURRENT_TIMESTAMP",
+        params![table, row_id, column, raw_value, unit_type],
+    )
+    .map_err(|e| format!("Failed to flag {}.{} of row {}: {}", table, column, row_id, e))?;
+    Ok(())
+}
+
+fn clear_issue(tx: &Connection, table: &str, row_id: i64, column: &str) -> Result<(), String> {
+    tx.execute(
+        "DELETE FROM quantity_conversion_issues WHERE table_name = ?1 AND row_id = ?2 AND column_name = ?3",
+        params![table, row_id, column],
+    )
+    .map_err(|e| format!("Failed to clear quantity issue: {}", e))?;
+    Ok(())
+}
+
+/// Quantities the migration could not parse, for manual correction
+#[tauri::command]
+pub async fn get_quantity_conversion_issues() -> Result<Vec<QuantityConversionIssue>, String> {
+    let conn = open_connection()?;
+    ensure_issue_table(&conn).map_err(|e| format!("Failed to create quantity issue table: {}", e))?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT table_name, row_id, column_name, raw_value, unit_type, flagged_at
+             FROM quantity_conversion_issues ORDER BY table_name, row_id, column_name",
+        )
+        .map_err(|e| format!("Failed to query quantity issues: {}", e))?;
+    let issues = stmt
+        .query_map([], |row| {
+            Ok(QuantityConversionIssue {
+                table_name: row.get(0)?,
+                row_id: row.get(1)?,
+                column_name: row.get(2)?,
+                raw_value: row.get(3)?,
+                unit_type: row.get(4)?,
+                flagged_at: row.get(5)?,
+            })
+        })
+        .map_err(|e| format!("Failed to read quantity issues: {}", e))?;
+
+    issues
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read quantity issue: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stock_text_parses_to_base_units() {
+        assert_eq!(parse_stock_text("12-500", "kg-grams"), Some(12_500));
+        assert_eq!(parse_stock_text("12kg 500g", ""), Some(12_500));
+        assert_eq!(parse_stock_text("500g", "kg"), Some(500));
+        assert_eq!(parse_stock_text("12.5", "kg"), Some(12_500));
+        assert_eq!(parse_stock_text("-2-250", "kg-grams"), Some(-2_250));
+        assert_eq!(parse_stock_text(" ", "kg-grams"), Some(0));
+        assert_eq!(parse_stock_text("150 bags", "bag"), Some(150));
+        assert_eq!(parse_stock_text("24 ft", "foot"), Some(73_152));
+        assert_eq!(parse_stock_text("1.5", "meter"), Some(15_000));
+        // Half a bag, a dash in a count, words and unknown units
+        assert_eq!(parse_stock_text("1.5", "bag"), None);
+        assert_eq!(parse_stock_text("12-5", "piece"), None);
+        assert_eq!(parse_stock_text("twelve", "kg-grams"), None);
+        assert_eq!(parse_stock_text("2", "widget"), None);
+    }
+
+    #[test]
+    fn stock_text_round_trips() {
+        for (text, unit_type) in [
+            ("12-500", "kg-grams"),
+            ("12", "kg-grams"),
+            ("-2-250", "kg-grams"),
+            ("150", "bag"),
+            ("24", "foot"),
+            ("1.5", "meter"),
+        ] {
+            let base = parse_stock_text(text, unit_type).unwrap();
+            assert_eq!(format_stock_text(base, unit_type), text);
+        }
+        assert_eq!(format_stock_text(12_000, "kg-grams"), "12");
+    }
+
+    #[test]
+    fn movement_numbers_are_grams_for_weight() {
+        assert_eq!(parse_movement_quantity("-1500", "kg-grams"), Some(-1_500));
+        assert_eq!(parse_movement_quantity("12-500", "kg-grams"), Some(12_500));
+        assert_eq!(parse_movement_quantity("3", "bag"), Some(3));
+        assert_eq!(parse_movement_quantity("2.5", "meter"), Some(25_000));
+        assert_eq!(format_movement_quantity(12_500, "kg-grams"), "12500");
+        assert_eq!(to_movement_number(25_000, "meter"), 2.5);
+        assert_eq!(to_unit_number(12_500, "kg-grams"), 12.5);
+    }
+
+    #[test]
+    fn units_convert_within_a_dimension() {
+        let tons = Quantity::new(1_500_000, UnitType::Kg)
+            .convert_to(UnitType::Ton)
+            .unwrap();
+        assert_eq!((tons.base, tons.to_number()), (1_500_000, 1.5));
+        assert_eq!(tons.to_string(), "1.5 tons");
+        assert_eq!(Quantity::new(1, UnitType::Piece).convert_to(UnitType::Kg), None);
+        assert_eq!(Quantity::new(12_500, UnitType::KgGrams).to_string(), "12kg 500g");
+        assert_eq!(Quantity::new(150, UnitType::Bag).to_string(), "150 bags");
+        assert_eq!(format_number(1.23456), "1.235");
+    }
+
+    #[test]
+    fn a_text_write_clears_the_shadow_until_the_next_conversion() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE products (id INTEGER PRIMARY KEY, current_stock TEXT, unit_type TEXT);
+             INSERT INTO products VALUES (1, '12-500', 'kg-grams');",
+        )
+        .unwrap();
+        ensure_quantity_columns(&mut conn).unwrap();
+        let shadow = |conn: &Connection| -> Option<i64> {
+            conn.query_row("SELECT current_stock_base FROM products WHERE id = 1", [], |row| {
+                row.get(0)
+            })
+            .unwrap()
+        };
+        assert_eq!(shadow(&conn), Some(12_500));
+
+        // A frontend write leaves the shadow behind; one that sets both keeps it
+        conn.execute("UPDATE products SET current_stock = '10' WHERE id = 1", [])
+            .unwrap();
+        assert_eq!(shadow(&conn), None);
+        conn.execute(
+            "UPDATE products SET current_stock = '9-750', current_stock_base = 9750 WHERE id = 1",
+            [],
+        )
+        .unwrap();
+        assert_eq!(shadow(&conn), Some(9_750));
+
+        conn.execute("UPDATE products SET current_stock = '8' WHERE id = 1", [])
+            .unwrap();
+        ensure_quantity_columns(&mut conn).unwrap();
+        assert_eq!(shadow(&conn), Some(8_000));
+        assert!(is_base_shadow("stock_movements", "quantity_base"));
+        assert!(!is_base_shadow("products", "current_stock"));
+    }
+}
@@ -0,0 +1,588 @@
+/*!
+ * SOFT DELETE AND RECYCLE BIN
+ *
+ * Customers, products, invoices and vendors are never deleted outright.
+ * Deleting one marks it with who deleted it, when and why (`deleted_at`,
+ * `deleted_by`, `deletion_reason`); normal lists and searches leave marked
+ * rows out, and the recycle bin lists them for an admin to restore. The
+ * signed-in user is recorded as the one who deleted or restored a record.
+ *
+ * A trigger on each table refuses DELETE, so a hard delete from any path
+ * fails instead of losing history. Only this module's purge, moving a year
+ * to its archive and records deleted on another terminal lift that guard,
+ * for the length of their transaction.
+ *
+ * Checks before a record goes to the bin: a customer must have no open
+ * balance and a vendor nothing owed either way; a product must have no stock
+ * on hand. An invoice is cancelled first, so its stock, ledger and payments
+ * are reversed, and comes back from the bin still cancelled; every invoice in
+ * the bin carries a note saying so.
+ *
+ * Purging removes records for good once they have been in the bin longer
+ * than the retention period, and only for an admin. A record other rows
+ * still refer to (a customer with invoices, a product on an invoice) stays
+ * in the bin and is reported as kept.
+ */
+
+use log::{info, warn};
+use rusqlite::{params, Connection, OptionalExtension, Transaction, TransactionBehavior};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::audit::{self, AuditEvent};
+use crate::customer_balance::{ledger_balance, GUEST_CUSTOMER_ID};
+use crate::database::{has_column, open_connection, table_exists};
+use crate::invoice_cancellation::cancel_invoice_in_transaction;
+use crate::quantity::parse_stock_text;
+use crate::session;
+use crate::vendor_payables::{sync_vendor_in_transaction, VendorSyncResult};
+
+/// A table whose rows go to the recycle bin instead of being deleted
+struct RecycledTable {
+    entity_type: &'static str,
+    table: &'static str,
+    /// Column that names a row in the bin
+    label: &'static str,
+}
+
+const RECYCLED_TABLES: &[RecycledTable] = &[
+    RecycledTable {
+        entity_type: "customer",
+        table: "customers",
+        label: "name",
+    },
+    RecycledTable {
+        entity_type: "product",
+        table: "products",
+        label: "name",
+    },
+    RecycledTable {
+        entity_type: "invoice",
+        table: "invoices",
+        label: "bill_number",
+    },
+    RecycledTable {
+        entity_type: "vendor",
+        table: "vendors",
+        label: "name",
+    },
+];
+
+const DEFAULT_RETENTION_DAYS: i64 = 30;
+const HARD_DELETE_MESSAGE: &str = "SOFT_DELETE_REQUIRED: move the record to the recycle bin instead of deleting it";
+/// Reason given to the cancellation when an invoice is deleted without one
+const DEFAULT_INVOICE_REASON: &str = "Deleted";
+/// Restoring takes an invoice out of the bin, not back out of its cancellation
+const INVOICE_RESTORE_NOTE: &str =
+    "Comes back cancelled: its stock, ledger and payments stay reversed. Bill it again as a new invoice.";
+
+// ==================== SCHEMA ====================
+
+pub fn ensure_schema(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(&format!(
+        "CREATE TABLE IF NOT EXISTS recycle_bin_state (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            retention_days INTEGER NOT NULL DEFAULT {},
+            hard_delete INTEGER NOT NULL DEFAULT 0,
+            updated_by TEXT,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        INSERT OR IGNORE INTO recycle_bin_state (id) VALUES (1);",
+        DEFAULT_RETENTION_DAYS
+    ))?;
+
+    for recycled in RECYCLED_TABLES {
+        let table = recycled.table;
+        if !table_exists(conn, table)? {
+            continue;
+        }
+        for column in ["deleted_at", "deleted_by", "deletion_reason"] {
+            if !has_column(conn, table, column)? {
+                conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} TEXT", table, column), [])?;
+            }
+        }
+        conn.execute_batch(&format!(
+            "CREATE INDEX IF NOT EXISTS idx_{table}_deleted_at ON {table}(deleted_at);
+             DROP TRIGGER IF EXISTS trg_{table}_no_hard_delete;
+             CREATE TRIGGER trg_{table}_no_hard_delete BEFORE DELETE ON {table}
+             WHEN COALESCE((SELECT hard_delete FROM recycle_bin_state WHERE id = 1), 0) = 0
+             BEGIN
+                 SELECT RAISE(ABORT, '{message}');
+             END;",
+            message = HARD_DELETE_MESSAGE,
+        ))?;
+    }
+    Ok(())
+}
+
+fn recycled_table(entity_type: &str) -> Result<&'static RecycledTable, String> {
+    let entity_type = entity_type.trim().to_lowercase();
+    RECYCLED_TABLES
+        .iter()
+        .find(|recycled| recycled.entity_type == entity_type || recycled.table == entity_type)
+        .ok_or_else(|| {
+            format!(
+                "Unknown record type '{}'; expected customer, product, invoice or vendor",
+                entity_type
+            )
+        })
+}
+
+/// Whether hard deletes were allowed before `permit_hard_delete`
+#[derive(Debug)]
+pub struct HardDeletePermit {
+    previous: Option<bool>,
+}
+
+/// Let the open transaction delete recycled tables' rows outright. Undo with
+/// `revoke_hard_delete` before committing; other connections never see it.
+pub fn permit_hard_delete(conn: &Connection) -> Result<HardDeletePermit, String> {
+    if !table_exists(conn, "recycle_bin_state").map_err(|e| format!("Failed to inspect schema: {}", e))? {
+        return Ok(HardDeletePermit { previous: None });
+    }
+    let previous = conn
+        .query_row("SELECT hard_delete FROM recycle_bin_state WHERE id = 1", [], |row| {
+            row.get::<_, bool>(0)
+        })
+        .optional()
+        .map_err(|e| format!("Failed to read the recycle bin state: {}", e))?;
+    conn.execute("UPDATE recycle_bin_state SET hard_delete = 1 WHERE id = 1", [])
+        .map_err(|e| format!("Failed to allow deletes: {}", e))?;
+    Ok(HardDeletePermit { previous })
+}
+
+pub fn revoke_hard_delete(conn: &Connection, permit: HardDeletePermit) -> Result<(), String> {
+    let Some(previous) = permit.previous else {
+        return Ok(());
+    };
+    conn.execute("UPDATE recycle_bin_state SET hard_delete = ?1 WHERE id = 1", [previous])
+        .map(|_| ())
+        .map_err(|e| format!("Failed to guard deletes again: {}", e))
+}
+
+fn retention_days(conn: &Connection) -> Result<i64, String> {
+    conn.query_row("SELECT retention_days FROM recycle_bin_state WHERE id = 1", [], |row| {
+        row.get(0)
+    })
+    .optional()
+    .map(|days| days.unwrap_or(DEFAULT_RETENTION_DAYS))
+    .map_err(|e| format!("Failed to read the retention period: {}", e))
+}
+
+// ==================== RECORDS ====================
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RecycledRecord {
+    pub entity_type: String,
+    pub id: i64,
+    pub label: String,
+    pub deleted_at: String,
+    pub deleted_by: String,
+    pub reason: Option<String>,
+    /// When the record may be purged
+    pub purge_after: String,
+    pub purgeable: bool,
+    /// How the record comes back differently from before it was deleted
+    pub restore_note: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecycleBin {
+    pub retention_days: i64,
+    pub records: Vec<RecycledRecord>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KeptRecord {
+    pub entity_type: String,
+    pub id: i64,
+    pub label: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct PurgeResult {
+    pub purged: Vec<RecycledRecord>,
+    /// Past retention but still referred to by other rows
+    pub kept: Vec<KeptRecord>,
+}
+
+fn load_recycled(
+    conn: &Connection,
+    recycled: &RecycledTable,
+    id: Option<i64>,
+    retention_days: i64,
+) -> Result<Vec<RecycledRecord>, String> {
+    if !table_exists(conn, recycled.table).map_err(|e| format!("Failed to inspect schema: {}", e))? {
+        return Ok(Vec::new());
+    }
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT id, COALESCE(CAST({label} AS TEXT), ''), deleted_at, COALESCE(deleted_by, ''), deletion_reason,
+                    datetime(deleted_at, '+' || ?2 || ' days') AS purge_after,
+                    datetime(deleted_at, '+' || ?2 || ' days') <= strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')
+             FROM {table}
+             WHERE deleted_at IS NOT NULL AND (?1 IS NULL OR id = ?1)
+             ORDER BY deleted_at DESC, id DESC",
+            label = recycled.label,
+            table = recycled.table
+        ))
+        .map_err(|e| format!("Failed to read deleted {}: {}", recycled.table, e))?;
+    let rows = stmt
+        .query_map(params![id, retention_days], |row| {
+            Ok(RecycledRecord {
+                entity_type: recycled.entity_type.to_string(),
+                id: row.get(0)?,
+                label: row.get(1)?,
+                deleted_at: row.get(2)?,
+                deleted_by: row.get(3)?,
+                reason: row.get(4)?,
+                purge_after: row.get(5)?,
+                purgeable: row.get(6)?,
+                restore_note: (recycled.entity_type == "invoice").then(|| INVOICE_RESTORE_NOTE.to_string()),
+            })
+        })
+        .map_err(|e| format!("Failed to read deleted {}: {}", recycled.table, e))?;
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to read deleted {}: {}", recycled.table, e))
+}
+
+/// What stands in the way of deleting a live row, checked on the open transaction
+fn check_deletable(
+    tx: &Transaction,
+    recycled: &RecycledTable,
+    id: i64,
+    label: &str,
+    reason: Option<&str>,
+    deleted_by: &str,
+) -> Result<(), String> {
+    match recycled.entity_type {
+        "customer" => {
+            if id == GUEST_CUSTOMER_ID {
+                return Err("The walk-in customer cannot be deleted".to_string());
+            }
+            let balance = ledger_balance(tx, id).map_err(|e| format!("Failed to compute customer balance: {}", e))?;
+            if !balance.is_zero() {
+                return Err(format!(
+                    "{} has an open balance of Rs.{}; settle it before deleting",
+                    label, balance
+                ));
+            }
+        }
+        "vendor" => {
+            let balance = sync_vendor_in_transaction(tx, id, &mut VendorSyncResult::default())?;
+            if balance.is_negative() {
+                return Err(format!(
+                    "{} holds an advance of Rs.{}; settle it before deleting",
+                    label,
+                    balance.abs()
+                ));
+            } else if !balance.is_zero() {
+                return Err(format!("{} is owed Rs.{}; pay it before deleting", label, balance));
+            }
+        }
+        "product" => {
+            let (stock, unit_type): (Option<String>, Option<String>) = tx
+                .query_row(
+                    "SELECT CAST(current_stock AS TEXT), unit_type FROM products WHERE id = ?1",
+                    [id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .map_err(|e| format!("Failed to read product stock: {}", e))?;
+            let stock = stock.unwrap_or_default();
+            let on_hand = parse_stock_text(&stock, &unit_type.unwrap_or_default());
+            if on_hand.is_some_and(|base| base != 0) {
+                return Err(format!(
+                    "{} has {} in stock; adjust it to zero before deleting",
+                    label, stock
+                ));
+            }
+        }
+        "invoice" => {
+            let status: Option<String> = tx
+                .query_row("SELECT status FROM invoices WHERE id = ?1", [id], |row| row.get(0))
+                .map_err(|e| format!("Failed to read invoice status: {}", e))?;
+            if status.as_deref() != Some("cancelled") {
+                cancel_invoice_in_transaction(tx, id, reason.unwrap_or(DEFAULT_INVOICE_REASON), deleted_by)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Mark a record deleted after its checks pass
+pub fn move_to_bin_in_transaction(
+    tx: &Transaction,
+    entity_type: &str,
+    id: i64,
+    reason: Option<&str>,
+    deleted_by: &str,
+) -> Result<RecycledRecord, String> {
+    let recycled = recycled_table(entity_type)?;
+    let (label, deleted_at): (String, Option<String>) = tx
+        .query_row(
+            &format!(
+                "SELECT COALESCE(CAST({} AS TEXT), ''), deleted_at FROM {} WHERE id = ?1",
+                recycled.label, recycled.table
+            ),
+            [id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to load {} {}: {}", recycled.entity_type, id, e))?
+        .ok_or_else(|| format!("{} {} not found", recycled.entity_type, id))?;
+    if deleted_at.is_some() {
+        return Err(format!("{} is already in the recycle bin", label));
+    }
+
+    check_deletable(tx, recycled, id, &label, reason, deleted_by)?;
+
+    tx.execute(
+        &format!(
+            "UPDATE {} SET deleted_at = strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime'),
+                           deleted_by = ?1, deletion_reason = ?2
+             WHERE id = ?3",
+            recycled.table
+        ),
+        params![deleted_by, reason, id],
+    )
+    .map_err(|e| format!("Failed to delete {}: {}", label, e))?;
+    audit::record(
+        tx,
+        &AuditEvent::new("delete", recycled.entity_type)
+            .entity(id)
+            .by(deleted_by)
+            .after(json!({ "label": label, "reason": reason })),
+    )?;
+
+    load_recycled(tx, recycled, Some(id), retention_days(tx)?)?
+        .pop()
+        .ok_or_else(|| format!("{} {} not found", recycled.entity_type, id))
+}
+
+/// Take a record out of the bin
+pub fn restore_in_transaction(
+    tx: &Transaction,
+    entity_type: &str,
+    id: i64,
+    restored_by: &str,
+) -> Result<RecycledRecord, String> {
+    let recycled = recycled_table(entity_type)?;
+    let record = load_recycled(tx, recycled, Some(id), retention_days(tx)?)?
+        .pop()
+        .ok_or_else(|| format!("{} {} is not in the recycle bin", recycled.entity_type, id))?;
+
+    tx.execute(
+        &format!(
+            "UPDATE {} SET deleted_at = NULL, deleted_by = NULL, deletion_reason = NULL WHERE id = ?1",
+            recycled.table
+        ),
+        [id],
+    )
+    .map_err(|e| format!("Failed to restore {}: {}", record.label, e))?;
+    audit::record(
+        tx,
+        &AuditEvent::new("restore", recycled.entity_type)
+            .entity(id)
+            .by(restored_by)
+            .before(json!(&record)),
+    )?;
+    Ok(record)
+}
+
+/// Permanently remove records past retention, one or all of a type or all of them
+pub fn purge_in_transaction(
+    tx: &Transaction,
+    entity_type: Option<&str>,
+    id: Option<i64>,
+    purged_by: &str,
+) -> Result<PurgeResult, String> {
+    let tables: Vec<&RecycledTable> = match entity_type {
+        Some(entity_type) => vec![recycled_table(entity_type)?],
+        None => RECYCLED_TABLES.iter().collect(),
+    };
+    let retention_days = retention_days(tx)?;
+    let mut result = PurgeResult::default();
+
+    let permit = permit_hard_delete(tx)?;
+    for recycled in tables {
+        for record in load_recycled(tx, recycled, id, retention_days)? {
+            if !record.purgeable {
+                if id.is_some() {
+                    return Err(format!(
+                        "{} stays in the recycle bin until {}",
+                        record.label, record.purge_after
+                    ));
+                }
+                continue;
+            }
+            tx.execute_batch("SAVEPOINT purge_record")
+                .map_err(|e| format!("Failed to start savepoint: {}", e))?;
+            match tx.execute(&format!("DELETE FROM {} WHERE id = ?1", recycled.table), [record.id]) {
+                Ok(_) => {
+                    tx.execute_batch("RELEASE purge_record")
+                        .map_err(|e| format!("Failed to release savepoint: {}", e))?;
+                    result.purged.push(record);
+                }
+                Err(e) => {
+                    tx.execute_batch("ROLLBACK TO purge_record; RELEASE purge_record")
+                        .map_err(|e| format!("Failed to roll back savepoint: {}", e))?;
+                    let reason = if e.to_string().contains("FOREIGN KEY") {
+                        "other records still refer to it".to_string()
+                    } else {
+                        e.to_string()
+                    };
+                    result.kept.push(KeptRecord {
+                        entity_type: record.entity_type,
+                        id: record.id,
+                        label: record.label,
+                        reason,
+                    });
+                }
+            }
+        }
+    }
+    revoke_hard_delete(tx, permit)?;
+
+    if !result.purged.is_empty() {
+        let purged: Vec<_> = result
+            .purged
+            .iter()
+            .map(|record| json!({ "entity_type": record.entity_type, "id": record.id, "label": record.label }))
+            .collect();
+        audit::record(
+            tx,
+            &AuditEvent::new("purge", "recycle_bin")
+                .by(purged_by)
+                .before(json!(purged)),
+        )?;
+    }
+    Ok(result)
+}
+
+fn require_admin(conn: &Connection, user: &str, action: &str) -> Result<(), String> {
+    if user.trim().is_empty() {
+        return Err(format!("A user is required to {}", action));
+    }
+    session::require_admin(conn, user, action)
+}
+
+fn recycle_bin(conn: &Connection, entity_type: Option<&str>) -> Result<RecycleBin, String> {
+    let retention_days = retention_days(conn)?;
+    let tables: Vec<&RecycledTable> = match entity_type {
+        Some(entity_type) => vec![recycled_table(entity_type)?],
+        None => RECYCLED_TABLES.iter().collect(),
+    };
+    let mut records = Vec::new();
+    for recycled in tables {
+        records.extend(load_recycled(conn, recycled, None, retention_days)?);
+    }
+    records.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+    Ok(RecycleBin {
+        retention_days,
+        records,
+    })
+}
+
+// ==================== COMMANDS ====================
+
+/// Move a customer, product, invoice or vendor to the recycle bin
+#[tauri::command]
+pub async fn move_to_recycle_bin(
+    entity_type: String,
+    id: i64,
+    reason: Option<String>,
+) -> Result<RecycledRecord, String> {
+    let deleted_by = session::current_user("delete records")?;
+    let reason = reason.as_deref().map(str::trim).filter(|reason| !reason.is_empty());
+
+    let mut conn = open_connection()?;
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let record = move_to_bin_in_transaction(&tx, &entity_type, id, reason, &deleted_by)?;
+    tx.commit().map_err(|e| format!("Failed to commit delete: {}", e))?;
+
+    info!(
+        "[RECYCLE-BIN] {} {} ({}) deleted by {}",
+        record.entity_type, record.id, record.label, deleted_by
+    );
+    Ok(record)
+}
+
+/// Bring a record back from the recycle bin; admins only
+#[tauri::command]
+pub async fn restore_from_recycle_bin(entity_type: String, id: i64) -> Result<RecycledRecord, String> {
+    let mut conn = open_connection()?;
+    let restored_by = session::require_signed_in_admin(&conn, "restore deleted records")?;
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let record = restore_in_transaction(&tx, &entity_type, id, &restored_by)?;
+    tx.commit().map_err(|e| format!("Failed to commit restore: {}", e))?;
+
+    info!(
+        "[RECYCLE-BIN] {} {} ({}) restored by {}",
+        record.entity_type,
+        record.id,
+        record.label,
+        restored_by
+    );
+    Ok(record)
+}
+
+/// Deleted records, newest first, of one type or all
+#[tauri::command]
+pub async fn get_recycle_bin(entity_type: Option<String>) -> Result<RecycleBin, String> {
+    let conn = open_connection()?;
+    recycle_bin(&conn, entity_type.as_deref())
+}
+
+/// Permanently remove records past retention; admins only
+#[tauri::command]
+pub async fn purge_recycle_bin(
+    purged_by: String,
+    entity_type: Option<String>,
+    id: Option<i64>,
+) -> Result<PurgeResult, String> {
+    let mut conn = open_connection()?;
+    require_admin(&conn, &purged_by, "purge the recycle bin")?;
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let result = purge_in_transaction(&tx, entity_type.as_deref(), id, purged_by.trim())?;
+    tx.commit().map_err(|e| format!("Failed to commit purge: {}", e))?;
+
+    warn!(
+        "[RECYCLE-BIN] {} records purged by {}, {} kept",
+        result.purged.len(),
+        purged_by.trim(),
+        result.kept.len()
+    );
+    Ok(result)
+}
+
+/// Days a record stays in the bin before it can be purged; admins only
+#[tauri::command]
+pub async fn set_recycle_bin_retention(days: i64, changed_by: String) -> Result<RecycleBin, String> {
+    if days < 1 {
+        return Err("The retention period must be at least one day".to_string());
+    }
+    let conn = open_connection()?;
+    require_admin(&conn, &changed_by, "change the retention period")?;
+    conn.execute(
+        "UPDATE recycle_bin_state SET retention_days = ?1, updated_by = ?2, updated_at = CURRENT_TIMESTAMP
+         WHERE id = 1",
+        params![days, changed_by.trim()],
+    )
+    .map_err(|e| format!("Failed to set the retention period: {}", e))?;
+    audit::record(
+        &conn,
+        &AuditEvent::new("set_retention", "recycle_bin")
+            .by(&changed_by)
+            .after(json!({ "retention_days": days })),
+    )?;
+    info!("[RECYCLE-BIN] Retention set to {} days by {}", days, changed_by.trim());
+    recycle_bin(&conn, None)
+}
@@ -0,0 +1,151 @@
+/*!
+ * APPLICATION RESTART
+ *
+ * After a restore the app relaunches itself: the current executable is
+ * started again with the same arguments and this process exits. The same
+ * path is used on every platform.
+ *
+ * The new process is told which process it replaces, and whether the restart
+ * follows a restore, through environment variables. Before it takes the
+ * single-instance lock or opens the database it waits for that process to be
+ * gone, so the old SQLite handles and WAL are closed and the stale lock can
+ * be taken over. The window asks `get_restart_state` on startup.
+ */
+
+use std::env;
+use std::process::Command;
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use log::{info, warn};
+use serde::Serialize;
+use tauri::AppHandle;
+
+const PREVIOUS_PID_ENV: &str = "ITTEHAD_RESTARTED_FROM";
+const AFTER_RESTORE_ENV: &str = "ITTEHAD_RESTARTED_AFTER_RESTORE";
+/// Lets the command's answer reach the window before the process goes away
+const EXIT_DELAY: Duration = Duration::from_millis(500);
+const PREVIOUS_EXIT_TIMEOUT: Duration = Duration::from_secs(30);
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+static STATE: OnceLock<RestartState> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct RestartState {
+    /// Started by `restart_application` rather than by the user
+    pub restarted: bool,
+    pub after_restore: bool,
+    pub previous_pid: Option<u32>,
+}
+
+/// Read the restart handed over by the previous process; later calls return the same
+pub fn take_startup_state() -> RestartState {
+    *STATE.get_or_init(|| {
+        let previous_pid = env::var(PREVIOUS_PID_ENV).ok().and_then(|pid| pid.parse().ok());
+        let after_restore = env::var(AFTER_RESTORE_ENV).is_ok_and(|value| value == "1");
+        // Not inherited by whatever this process starts later
+        env::remove_var(PREVIOUS_PID_ENV);
+        env::remove_var(AFTER_RESTORE_ENV);
+        RestartState {
+            restarted: previous_pid.is_some(),
+            after_restore: previous_pid.is_some() && after_restore,
+            previous_pid,
+        }
+    })
+}
+
+/// Block until the process this one replaces has exited. `false` if it outlived the timeout.
+pub fn wait_for_previous_instance(state: &RestartState) -> bool {
+    let Some(pid) = state.previous_pid else {
+        return true;
+    };
+    info!("[RESTART] Waiting for previous instance {} to exit", pid);
+    let started = Instant::now();
+    while process_alive(pid) {
+        if started.elapsed() >= PREVIOUS_EXIT_TIMEOUT {
+            warn!(
+                "[RESTART] Previous instance {} still running after {}s",
+                pid,
+                PREVIOUS_EXIT_TIMEOUT.as_secs()
+            );
+            return false;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    info!("[RESTART] Previous instance {} has exited", pid);
+    true
+}
+
+/// Start this executable again with the same arguments; returns the new process id
+pub fn relaunch(after_restore: bool) -> Result<u32, String> {
+    let exe = env::current_exe().map_err(|e| format!("Failed to find the executable: {}", e))?;
+    let child = Command::new(&exe)
+        .args(env::args_os().skip(1))
+        .env(PREVIOUS_PID_ENV, std::process::id().to_string())
+        .env(AFTER_RESTORE_ENV, if after_restore { "1" } else { "0" })
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", exe.display(), e))?;
+    Ok(child.id())
+}
+
+/// Relaunch the app and exit this instance
+#[tauri::command]
+pub async fn restart_application(app: AppHandle, after_restore: Option<bool>) -> Result<(), String> {
+    let after_restore = after_restore.unwrap_or(false);
+    info!(
+        "[RESTART] Restarting the application{}",
+        if after_restore { " after a restore" } else { "" }
+    );
+
+    let pid = relaunch(after_restore).map_err(|e| format!("Failed to restart: {}", e))?;
+    info!("[RESTART] Started process {}, exiting", pid);
+    thread::spawn(move || {
+        thread::sleep(EXIT_DELAY);
+        // Runs the exit handlers, which release the single-instance lock
+        app.exit(0);
+    });
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_restart_state() -> Result<RestartState, String> {
+    Ok(STATE.get().copied().unwrap_or_default())
+}
+
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    extern "C" {
+        fn kill(pid: i32, signal: i32) -> i32;
+    }
+    const EPERM: i32 = 1;
+    // Signal 0 only checks that the process exists
+    if unsafe { kill(pid as i32, 0) } == 0 {
+        return true;
+    }
+    // Exists but belongs to another user
+    std::io::Error::last_os_error().raw_os_error() == Some(EPERM)
+}
+
+#[cfg(windows)]
+fn process_alive(pid: u32) -> bool {
+    use std::ffi::c_void;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn OpenProcess(desired_access: u32, inherit_handle: i32, process_id: u32) -> *mut c_void;
+        fn WaitForSingleObject(handle: *mut c_void, milliseconds: u32) -> u32;
+        fn CloseHandle(handle: *mut c_void) -> i32;
+    }
+    const SYNCHRONIZE: u32 = 0x0010_0000;
+    const WAIT_TIMEOUT: u32 = 0x0000_0102;
+    unsafe {
+        let handle = OpenProcess(SYNCHRONIZE, 0, pid);
+        if handle.is_null() {
+            return false;
+        }
+        let alive = WaitForSingleObject(handle, 0) == WAIT_TIMEOUT;
+        CloseHandle(handle);
+        alive
+    }
+}
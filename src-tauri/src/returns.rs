@@ -0,0 +1,928 @@
+/*!
+ * SALES RETURN ENGINE
+ * One validated path for customer returns: quantities are checked against
+ * what was sold minus earlier returns, goods are restocked, and the value is
+ * settled either as a credit note on the customer ledger or as a cash refund
+ * through a payment channel. Every row is linked back to the original invoice.
+ */
+
+use log::info;
+use rusqlite::{params, OptionalExtension, Transaction, TransactionBehavior};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::audit::{self, AuditEvent};
+use crate::customer_balance::ledger_balance;
+use crate::database::{current_date, current_time, open_connection};
+use crate::journal::{post_document_in_transaction, SourceType};
+use crate::money::{paisa_from_row, paisa_sql, sum_paisa_sql, Money};
+use crate::quantity::{
+    format_movement_quantity, format_stock_text, parse_stock_text, set_movement_bases, set_product_stock,
+    to_unit_number, value_to_text,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct ReturnItemRequest {
+    pub invoice_item_id: i64,
+    /// Quantity in the product's unit format ("2-500", "2.5", "3")
+    pub return_quantity: String,
+    /// good | damaged | expired | defective; only good items go back to stock
+    pub condition: Option<String>,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReturnRequest {
+    pub invoice_id: i64,
+    pub items: Vec<ReturnItemRequest>,
+    pub reason: String,
+    /// "ledger" (credit note) or "cash" (refund)
+    pub settlement_type: String,
+    /// Channel the refund is paid from; defaults to the cash channel
+    pub payment_channel_id: Option<i64>,
+    pub processed_by: String,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReturnResult {
+    pub return_id: i64,
+    pub return_number: String,
+    pub invoice_id: i64,
+    pub bill_number: String,
+    pub total_amount: Money,
+    pub settlement_type: String,
+    pub items_restocked: usize,
+    pub customer_balance_after: Money,
+}
+
+struct InvoiceRow {
+    bill_number: String,
+    customer_id: i64,
+    customer_name: String,
+    status: String,
+    subtotal: Money,
+    grand_total: Money,
+    paid_amount: Money,
+    remaining_balance: Money,
+}
+
+struct SoldItem {
+    product_id: Option<i64>,
+    product_name: String,
+    quantity: String,
+    unit: String,
+    unit_price: Money,
+    line_total: Money,
+    is_non_stock: bool,
+    unit_type: String,
+    track_inventory: bool,
+}
+
+/// A validated line, ready to be written
+struct ReturnLine {
+    invoice_item_id: i64,
+    sold: SoldItem,
+    sold_base: i64,
+    /// Returned by earlier returns of the same line
+    already_returned: i64,
+    return_base: i64,
+    amount: Money,
+    condition: String,
+    reason: String,
+}
+
+#[tauri::command]
+pub async fn process_return(request: ReturnRequest) -> Result<ReturnResult, String> {
+    info!(
+        "[RETURN] Processing return on invoice {} ({} items, {} settlement)",
+        request.invoice_id,
+        request.items.len(),
+        request.settlement_type
+    );
+
+    let mut conn = open_connection()?;
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let result = process_return_in_transaction(&tx, &request)?;
+    audit::record(
+        &tx,
+        &AuditEvent::new("return", "invoice")
+            .entity(request.invoice_id)
+            .by(&request.processed_by)
+            .after(json!({ "reason": request.reason.trim(), "result": &result })),
+    )?;
+
+    tx.commit()
+        .map_err(|e| format!("Failed to commit return: {}", e))?;
+
+    info!(
+        "[RETURN] {} recorded against {}: Rs.{} settled via {}",
+        result.return_number, result.bill_number, result.total_amount, result.settlement_type
+    );
+
+    Ok(result)
+}
+
+/// Validate and write the whole return on an open transaction
+pub fn process_return_in_transaction(tx: &Transaction, request: &ReturnRequest) -> Result<ReturnResult, String> {
+    if request.items.is_empty() {
+        return Err("A return needs at least one item".to_string());
+    }
+    if request.reason.trim().is_empty() {
+        return Err("A return reason is required".to_string());
+    }
+    if request.settlement_type != "ledger" && request.settlement_type != "cash" {
+        return Err("Settlement type must be \"ledger\" or \"cash\"".to_string());
+    }
+    let processed_by = match request.processed_by.trim() {
+        "" => "system",
+        user => user,
+    };
+
+    let invoice = load_invoice(tx, request.invoice_id)?;
+    if invoice.status == "cancelled" {
+        return Err(format!("Invoice {} is cancelled; nothing can be returned", invoice.bill_number));
+    }
+
+    let lines = validate_lines(tx, request, &invoice)?;
+    let total_amount: Money = lines.iter().map(|line| line.amount).sum();
+    let total_quantity: f64 = lines
+        .iter()
+        .map(|line| to_unit_number(line.return_base, &line.sold.unit_type))
+        .sum();
+
+    let date = current_date(tx).map_err(|e| format!("Failed to read date: {}", e))?;
+    let time = current_time(tx).map_err(|e| format!("Failed to read time: {}", e))?;
+    let return_number = next_return_number(tx, &date)?;
+
+    let fully_returned = fully_returned(tx, request.invoice_id, &lines)?;
+
+    tx.execute(
+        "INSERT INTO returns (
+            return_number, original_invoice_id, original_invoice_number, customer_id, customer_name,
+            return_type, reason, total_items, total_quantity, subtotal, total_amount,
+            refund_amount, refund_method, settlement_type, settlement_amount, settlement_processed,
+            status, date, time, processed_date, notes, processed_by, created_by
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?10, ?11, ?12, ?13, ?10, 1,
+            'completed', ?14, ?15, ?14, ?16, ?17, ?17)",
+        params![
+            return_number,
+            request.invoice_id,
+            invoice.bill_number,
+            invoice.customer_id,
+            invoice.customer_name,
+            if fully_returned { "full" } else { "partial" },
+            request.reason.trim(),
+            lines.len() as i64,
+            total_quantity,
+            total_amount.to_rupees(),
+            if request.settlement_type == "cash" { total_amount } else { Money::ZERO }.to_rupees(),
+            if request.settlement_type == "cash" { "cash" } else { "store_credit" },
+            request.settlement_type,
+            date,
+            time,
+            request.notes.clone().unwrap_or_default(),
+            processed_by
+        ],
+    )
+    .map_err(|e| format!("Failed to create return record: {}", e))?;
+    let return_id = tx.last_insert_rowid();
+
+    let mut items_restocked = 0;
+    for line in &lines {
+        let restock = line.condition == "good"
+            && line.sold.track_inventory
+            && !line.sold.is_non_stock;
+
+        tx.execute(
+            "INSERT INTO return_items (
+                return_id, original_invoice_item_id, product_id, product_name,
+                original_quantity, return_quantity, unit, unit_price, total_price,
+                condition_status, reason, action, restocked
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            params![
+                return_id,
+                line.invoice_item_id,
+                line.sold.product_id,
+                line.sold.product_name,
+                to_unit_number(line.sold_base, &line.sold.unit_type),
+                to_unit_number(line.return_base, &line.sold.unit_type),
+                line.sold.unit,
+                line.sold.unit_price.to_rupees(),
+                line.amount.to_rupees(),
+                line.condition,
+                line.reason,
+                if restock { "refund" } else { "discard" },
+                restock as i64
+            ],
+        )
+        .map_err(|e| format!("Failed to record returned item {}: {}", line.sold.product_name, e))?;
+
+        if restock {
+            restock_line(tx, line, return_id, &return_number, &invoice, processed_by, &date, &time)?;
+            items_restocked += 1;
+        }
+    }
+
+    let customer_balance_after = settle_return(
+        tx,
+        request,
+        &invoice,
+        return_id,
+        &return_number,
+        total_amount,
+        processed_by,
+        &date,
+        &time,
+    )?;
+
+    post_document_in_transaction(tx, SourceType::Return, return_id)?;
+
+    Ok(ReturnResult {
+        return_id,
+        return_number,
+        invoice_id: request.invoice_id,
+        bill_number: invoice.bill_number,
+        total_amount,
+        settlement_type: request.settlement_type.clone(),
+        items_restocked,
+        customer_balance_after,
+    })
+}
+
+fn load_invoice(tx: &Transaction, invoice_id: i64) -> Result<InvoiceRow, String> {
+    let paisa = |column| paisa_sql(tx, "invoices", column).map_err(|e| format!("Failed to inspect schema: {}", e));
+    tx.query_row(
+        &format!(
+            "SELECT bill_number, customer_id, customer_name, status,
+                    {}, COALESCE(NULLIF({}, 0), {}), {}, {}
+             FROM invoices WHERE id = ?1",
+            paisa("subtotal")?,
+            paisa("grand_total")?,
+            paisa("total_amount")?,
+            paisa("paid_amount")?,
+            paisa("remaining_balance")?
+        ),
+        [invoice_id],
+        |row| {
+            Ok(InvoiceRow {
+                bill_number: row.get(0)?,
+                customer_id: row.get(1)?,
+                customer_name: row.get(2)?,
+                status: row.get(3)?,
+                subtotal: paisa_from_row(row, 4)?,
+                grand_total: paisa_from_row(row, 5)?,
+                paid_amount: paisa_from_row(row, 6)?,
+                remaining_balance: paisa_from_row(row, 7)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| format!("Failed to load invoice: {}", e))?
+    .ok_or_else(|| format!("Invoice {} not found", invoice_id))
+}
+
+/// Whether nothing of the invoice is left to return once these lines are
+/// counted with the earlier returns. Miscellaneous items cannot be returned
+/// and do not count.
+fn fully_returned(tx: &Transaction, invoice_id: i64, lines: &[ReturnLine]) -> Result<bool, String> {
+    let mut stmt = tx
+        .prepare("SELECT id FROM invoice_items WHERE invoice_id = ?1 AND product_id IS NOT NULL")
+        .map_err(|e| format!("Failed to query invoice items: {}", e))?;
+    let item_ids = stmt
+        .query_map([invoice_id], |row| row.get::<_, i64>(0))
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| format!("Failed to read invoice items: {}", e))?;
+
+    for item_id in item_ids {
+        let (sold_base, returned) = match lines.iter().find(|line| line.invoice_item_id == item_id) {
+            Some(line) => (line.sold_base, line.already_returned + line.return_base),
+            None => {
+                let sold = load_sold_item(tx, invoice_id, item_id)?;
+                let Some(sold_base) = parse_stock_text(&sold.quantity, &sold.unit_type) else {
+                    return Ok(false);
+                };
+                (sold_base, previously_returned(tx, item_id, &sold.unit_type)?)
+            }
+        };
+        if returned < sold_base {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Check every requested line against the invoice and earlier returns
+fn validate_lines(tx: &Transaction, request: &ReturnRequest, invoice: &InvoiceRow) -> Result<Vec<ReturnLine>, String> {
+    // Invoice-level discounts are spread over the lines so the credit
+    // matches what the customer was actually charged.
+    let (discounted, undiscounted) = if invoice.subtotal > Money::ZERO && invoice.grand_total < invoice.subtotal {
+        (invoice.grand_total.paisa(), invoice.subtotal.paisa())
+    } else {
+        (1, 1)
+    };
+
+    let mut lines: Vec<ReturnLine> = Vec::with_capacity(request.items.len());
+    for item in &request.items {
+        if lines.iter().any(|line| line.invoice_item_id == item.invoice_item_id) {
+            return Err(format!("Invoice item {} is listed more than once", item.invoice_item_id));
+        }
+
+        let sold = load_sold_item(tx, request.invoice_id, item.invoice_item_id)?;
+        if sold.product_id.is_none() {
+            return Err(format!(
+                "{} is a miscellaneous item and cannot be returned; post a ledger adjustment instead",
+                sold.product_name
+            ));
+        }
+        let sold_base = parse_stock_text(&sold.quantity, &sold.unit_type)
+            .filter(|quantity| *quantity > 0)
+            .ok_or_else(|| format!("Cannot read sold quantity '{}' of {}", sold.quantity, sold.product_name))?;
+        let return_base = parse_stock_text(&item.return_quantity, &sold.unit_type)
+            .filter(|quantity| *quantity > 0)
+            .ok_or_else(|| {
+                format!("Invalid return quantity '{}' for {}", item.return_quantity, sold.product_name)
+            })?;
+
+        let already_returned = previously_returned(tx, item.invoice_item_id, &sold.unit_type)?;
+        let returnable = sold_base - already_returned;
+        if return_base > returnable {
+            return Err(format!(
+                "Cannot return {} of {}: sold {}, already returned {}, returnable {}",
+                format_stock_text(return_base, &sold.unit_type),
+                sold.product_name,
+                format_stock_text(sold_base, &sold.unit_type),
+                format_stock_text(already_returned, &sold.unit_type),
+                format_stock_text(returnable.max(0), &sold.unit_type)
+            ));
+        }
+
+        let condition = item.condition.clone().unwrap_or_else(|| "good".to_string());
+        if !matches!(condition.as_str(), "good" | "damaged" | "expired" | "defective") {
+            return Err(format!("Unknown item condition '{}'", condition));
+        }
+
+        let amount = sold
+            .line_total
+            .mul_ratio(return_base, sold_base)
+            .mul_ratio(discounted, undiscounted);
+        lines.push(ReturnLine {
+            invoice_item_id: item.invoice_item_id,
+            sold,
+            sold_base,
+            already_returned,
+            return_base,
+            amount,
+            condition,
+            reason: item.reason.clone().unwrap_or_else(|| request.reason.trim().to_string()),
+        });
+    }
+
+    Ok(lines)
+}
+
+fn load_sold_item(tx: &Transaction, invoice_id: i64, invoice_item_id: i64) -> Result<SoldItem, String> {
+    let paisa = |column| paisa_sql(tx, "invoice_items", column).map_err(|e| format!("Failed to inspect schema: {}", e));
+    tx.query_row(
+        &format!(
+            "SELECT ii.product_id, ii.product_name, ii.quantity, ii.unit,
+                    COALESCE({}, {}),
+                    COALESCE(NULLIF({}, 0), {}, {}),
+                    COALESCE(ii.is_non_stock_item, 0) OR COALESCE(ii.is_misc_item, 0),
+                    COALESCE(p.unit_type, 'piece'),
+                    COALESCE(p.track_inventory, 1)
+             FROM invoice_items ii
+             LEFT JOIN products p ON p.id = ii.product_id
+             WHERE ii.id = ?1 AND ii.invoice_id = ?2",
+            paisa("ii.unit_price")?,
+            paisa("ii.rate")?,
+            paisa("ii.line_total")?,
+            paisa("ii.total_price")?,
+            paisa("ii.amount")?
+        ),
+        params![invoice_item_id, invoice_id],
+        |row| {
+            Ok(SoldItem {
+                product_id: row.get(0)?,
+                product_name: row.get(1)?,
+                quantity: row.get::<_, rusqlite::types::Value>(2).map(value_to_text)?,
+                unit: row.get(3)?,
+                unit_price: paisa_from_row(row, 4)?,
+                line_total: paisa_from_row(row, 5)?,
+                is_non_stock: row.get(6)?,
+                unit_type: row.get(7)?,
+                track_inventory: row.get(8)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| format!("Failed to load invoice item: {}", e))?
+    .ok_or_else(|| format!("Item {} does not belong to invoice {}", invoice_item_id, invoice_id))
+}
+
+/// Base units already returned for an invoice line, ignoring cancelled returns
+fn previously_returned(tx: &Transaction, invoice_item_id: i64, unit_type: &str) -> Result<i64, String> {
+    let mut stmt = tx
+        .prepare(
+            "SELECT ri.return_quantity
+             FROM return_items ri
+             JOIN returns r ON r.id = ri.return_id
+             WHERE ri.original_invoice_item_id = ?1 AND r.status NOT IN ('cancelled', 'rejected')",
+        )
+        .map_err(|e| format!("Failed to query earlier returns: {}", e))?;
+    let quantities = stmt
+        .query_map([invoice_item_id], |row| row.get::<_, rusqlite::types::Value>(0).map(value_to_text))
+        .map_err(|e| format!("Failed to read earlier returns: {}", e))?;
+
+    let mut total = 0;
+    for quantity in quantities {
+        let quantity = quantity.map_err(|e| format!("Failed to read earlier return: {}", e))?;
+        total += parse_stock_text(&quantity, unit_type)
+            .ok_or_else(|| format!("Cannot read earlier return quantity '{}'", quantity))?;
+    }
+    Ok(total)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn restock_line(
+    tx: &Transaction,
+    line: &ReturnLine,
+    return_id: i64,
+    return_number: &str,
+    invoice: &InvoiceRow,
+    processed_by: &str,
+    date: &str,
+    time: &str,
+) -> Result<(), String> {
+    let product_id = line.sold.product_id.unwrap_or_default();
+    let current_stock: String = tx
+        .query_row("SELECT current_stock FROM products WHERE id = ?1", [product_id], |row| {
+            row.get::<_, rusqlite::types::Value>(0).map(value_to_text)
+        })
+        .map_err(|e| format!("Failed to load stock of {}: {}", line.sold.product_name, e))?;
+    let stock_before = parse_stock_text(&current_stock, &line.sold.unit_type)
+        .ok_or_else(|| format!("Cannot parse current stock '{}' of {}", current_stock, line.sold.product_name))?;
+    let stock_after = stock_before + line.return_base;
+
+    set_product_stock(tx, product_id, stock_after, &line.sold.unit_type)
+        .map_err(|e| format!("Failed to restock {}: {}", line.sold.product_name, e))?;
+
+    tx.execute(
+        "INSERT INTO stock_movements (
+            product_id, product_name, movement_type, transaction_type, quantity, unit,
+            previous_stock, stock_before, stock_after, new_stock, unit_price, total_value,
+            reason, reference_type, reference_id, reference_number,
+            customer_id, customer_name, notes, date, time, created_by
+        ) VALUES (?1, ?2, 'in', 'return', ?3, ?4, ?5, ?5, ?6, ?6, ?7, ?8,
+            'Customer return', 'return', ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
+        params![
+            product_id,
+            line.sold.product_name,
+            format_movement_quantity(line.return_base, &line.sold.unit_type),
+            line.sold.unit,
+            format_movement_quantity(stock_before, &line.sold.unit_type),
+            format_movement_quantity(stock_after, &line.sold.unit_type),
+            line.sold.unit_price.to_rupees(),
+            line.amount.to_rupees(),
+            return_id,
+            return_number,
+            invoice.customer_id,
+            invoice.customer_name,
+            format!("Return {} against invoice {}", return_number, invoice.bill_number),
+            date,
+            time,
+            processed_by
+        ],
+    )
+    .map_err(|e| format!("Failed to record return stock movement: {}", e))?;
+    set_movement_bases(tx, tx.last_insert_rowid(), line.return_base, stock_before, stock_after)
+        .map_err(|e| format!("Failed to record return stock movement: {}", e))?;
+
+    Ok(())
+}
+
+/// Post the credit note or the cash refund; returns the customer balance afterwards
+#[allow(clippy::too_many_arguments)]
+fn settle_return(
+    tx: &Transaction,
+    request: &ReturnRequest,
+    invoice: &InvoiceRow,
+    return_id: i64,
+    return_number: &str,
+    amount: Money,
+    processed_by: &str,
+    date: &str,
+    time: &str,
+) -> Result<Money, String> {
+    let balance_before = ledger_balance(tx, invoice.customer_id)
+        .map_err(|e| format!("Failed to compute customer balance: {}", e))?;
+
+    // The return always credits the customer for the goods taken back
+    let balance_after_credit = balance_before - amount;
+    insert_customer_entry(
+        tx,
+        invoice,
+        request.invoice_id,
+        "credit",
+        "return",
+        amount,
+        balance_before,
+        balance_after_credit,
+        &format!("Return {} - Invoice {}", return_number, invoice.bill_number),
+        return_id,
+        return_number,
+        None,
+        processed_by,
+        date,
+        time,
+    )?;
+
+    let mut balance_after = balance_after_credit;
+    if request.settlement_type == "cash" {
+        // A refund can only hand back money that was actually received for this invoice
+        let settlement_amount = paisa_sql(tx, "returns", "settlement_amount")
+            .map_err(|e| format!("Failed to inspect schema: {}", e))?;
+        let refunded_before = tx
+            .query_row(
+                &format!(
+                    "SELECT {} FROM returns
+                     WHERE original_invoice_id = ?1 AND settlement_type = 'cash'
+                       AND status NOT IN ('cancelled', 'rejected') AND id != ?2",
+                    sum_paisa_sql(&settlement_amount)
+                ),
+                params![request.invoice_id, return_id],
+                |row| row.get(0).map(Money::from_paisa),
+            )
+            .map_err(|e| format!("Failed to sum earlier refunds: {}", e))?;
+        let refundable = invoice.paid_amount - refunded_before;
+        if amount > refundable {
+            return Err(format!(
+                "Cash refund of Rs.{} exceeds the Rs.{} received on invoice {}; settle to the ledger instead",
+                amount, refundable, invoice.bill_number
+            ));
+        }
+
+        let (channel_id, channel_name) = resolve_refund_channel(tx, request.payment_channel_id)?;
+
+        // Paying the cash out cancels the credit again on the customer account
+        balance_after = balance_after_credit + amount;
+        insert_customer_entry(
+            tx,
+            invoice,
+            request.invoice_id,
+            "debit",
+            "adjustment",
+            amount,
+            balance_after_credit,
+            balance_after,
+            &format!("Cash refund for return {} via {}", return_number, channel_name),
+            return_id,
+            return_number,
+            Some(&channel_name),
+            processed_by,
+            date,
+            time,
+        )?;
+
+        tx.execute(
+            "INSERT INTO ledger_entries (
+                date, time, type, category, description, amount,
+                customer_id, customer_name, reference_type, reference_id, reference_number, bill_number,
+                payment_method, payment_channel_id, payment_channel_name, notes, created_by
+            ) VALUES (?1, ?2, 'outgoing', 'Cash Refund', ?3, ?4, ?5, ?6, 'other', ?7, ?8, ?9, ?10, ?11, ?10, ?12, ?13)",
+            params![
+                date,
+                time,
+                format!("Refund for return {}", return_number),
+                amount.to_rupees(),
+                invoice.customer_id,
+                invoice.customer_name,
+                return_id,
+                return_number,
+                invoice.bill_number,
+                channel_name,
+                channel_id,
+                format!("Return against invoice {}", invoice.bill_number),
+                processed_by
+            ],
+        )
+        .map_err(|e| format!("Failed to record refund in daily ledger: {}", e))?;
+    } else {
+        // A credit note first settles what is still open on the same invoice
+        tx.execute(
+            "UPDATE invoices SET remaining_balance = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+            params![(invoice.remaining_balance - amount).max(Money::ZERO).to_rupees(), request.invoice_id],
+        )
+        .map_err(|e| format!("Failed to update invoice balance: {}", e))?;
+    }
+
+    tx.execute(
+        "UPDATE customers SET balance = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        params![balance_after.to_rupees(), invoice.customer_id],
+    )
+    .map_err(|e| format!("Failed to update customer balance: {}", e))?;
+
+    Ok(balance_after)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn insert_customer_entry(
+    tx: &Transaction,
+    invoice: &InvoiceRow,
+    invoice_id: i64,
+    entry_type: &str,
+    transaction_type: &str,
+    amount: Money,
+    balance_before: Money,
+    balance_after: Money,
+    description: &str,
+    return_id: i64,
+    return_number: &str,
+    payment_method: Option<&str>,
+    processed_by: &str,
+    date: &str,
+    time: &str,
+) -> Result<(), String> {
+    tx.execute(
+        "INSERT INTO customer_ledger_entries (
+            customer_id, customer_name, entry_type, transaction_type, amount,
+            balance_before, balance_after, description,
+            reference_type, reference_id, reference_number, invoice_id, invoice_number,
+            payment_method, date, time, created_by
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, 'return', ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
+        params![
+            invoice.customer_id,
+            invoice.customer_name,
+            entry_type,
+            transaction_type,
+            amount.to_rupees(),
+            balance_before.to_rupees(),
+            balance_after.to_rupees(),
+            description,
+            return_id,
+            return_number,
+            invoice_id,
+            invoice.bill_number,
+            payment_method,
+            date,
+            time,
+            processed_by
+        ],
+    )
+    .map_err(|e| format!("Failed to post customer ledger entry: {}", e))?;
+    Ok(())
+}
+
+/// Use the requested channel, or fall back to the default cash channel
+fn resolve_refund_channel(tx: &Transaction, channel_id: Option<i64>) -> Result<(Option<i64>, String), String> {
+    if let Some(id) = channel_id {
+        let name: String = tx
+            .query_row(
+                "SELECT name FROM payment_channels WHERE id = ?1 AND is_active = 1",
+                [id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("Failed to load payment channel: {}", e))?
+            .ok_or_else(|| format!("Payment channel {} not found or inactive", id))?;
+        return Ok((Some(id), name));
+    }
+
+    let cash_channel: Option<(i64, String)> = tx
+        .query_row(
+            "SELECT id, name FROM payment_channels
+             WHERE type = 'cash' AND is_active = 1
+             ORDER BY is_default DESC, id LIMIT 1",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to find cash channel: {}", e))?;
+
+    Ok(match cash_channel {
+        Some((id, name)) => (Some(id), name),
+        None => (None, "Cash".to_string()),
+    })
+}
+
+/// RET-YYYYMMDD-NNNN, numbered per day
+fn next_return_number(tx: &Transaction, date: &str) -> Result<String, String> {
+    let prefix = format!("RET-{}-", date.replace('-', ""));
+    let last: Option<String> = tx
+        .query_row(
+            "SELECT return_number FROM returns WHERE return_number GLOB ?1 || '[0-9][0-9][0-9][0-9]'
+             ORDER BY return_number DESC LIMIT 1",
+            [&prefix],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to generate return number: {}", e))?;
+
+    let next = last
+        .and_then(|number| number[prefix.len()..].parse::<u32>().ok())
+        .unwrap_or(0)
+        + 1;
+    Ok(format!("{}{:04}", prefix, next))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    /// Ali bought ten pipes and two valves for Rs.1100 and paid Rs.500
+    fn database() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE products (
+                id INTEGER PRIMARY KEY, name TEXT, unit_type TEXT, current_stock TEXT, track_inventory INTEGER,
+                cost_price REAL, updated_at TEXT
+            );
+            CREATE TABLE customers (id INTEGER PRIMARY KEY, name TEXT, balance REAL, updated_at TEXT);
+            CREATE TABLE invoices (
+                id INTEGER PRIMARY KEY, bill_number TEXT, customer_id INTEGER, customer_name TEXT, status TEXT,
+                subtotal REAL, grand_total REAL, total_amount REAL, paid_amount REAL, remaining_balance REAL,
+                date TEXT, time TEXT, updated_at TEXT
+            );
+            CREATE TABLE invoice_items (
+                id INTEGER PRIMARY KEY, invoice_id INTEGER, product_id INTEGER, product_name TEXT, quantity TEXT,
+                unit TEXT, unit_price REAL, rate REAL, line_total REAL, total_price REAL, amount REAL,
+                cost_price REAL, is_non_stock_item INTEGER DEFAULT 0, is_misc_item INTEGER DEFAULT 0
+            );
+            CREATE TABLE returns (
+                id INTEGER PRIMARY KEY, return_number TEXT, original_invoice_id INTEGER,
+                original_invoice_number TEXT, customer_id INTEGER, customer_name TEXT, return_type TEXT,
+                reason TEXT, total_items INTEGER, total_quantity REAL, subtotal REAL, total_amount REAL,
+                refund_amount REAL, refund_method TEXT, settlement_type TEXT, settlement_amount REAL,
+                settlement_processed INTEGER, status TEXT, date TEXT, time TEXT, processed_date TEXT, notes TEXT,
+                processed_by TEXT, created_by TEXT
+            );
+            CREATE TABLE return_items (
+                id INTEGER PRIMARY KEY, return_id INTEGER, original_invoice_item_id INTEGER, product_id INTEGER,
+                product_name TEXT, original_quantity REAL, return_quantity REAL, unit TEXT, unit_price REAL,
+                total_price REAL, condition_status TEXT, reason TEXT, action TEXT, restocked INTEGER
+            );
+            CREATE TABLE stock_movements (
+                id INTEGER PRIMARY KEY, product_id INTEGER, product_name TEXT, movement_type TEXT,
+                transaction_type TEXT, quantity TEXT, unit TEXT, previous_stock TEXT, stock_before TEXT,
+                stock_after TEXT, new_stock TEXT, unit_price REAL, total_value REAL, reason TEXT,
+                reference_type TEXT, reference_id INTEGER, reference_number TEXT, customer_id INTEGER,
+                customer_name TEXT, notes TEXT, date TEXT, time TEXT, created_by TEXT
+            );
+            CREATE TABLE customer_ledger_entries (
+                id INTEGER PRIMARY KEY, customer_id INTEGER, customer_name TEXT, entry_type TEXT,
+                transaction_type TEXT, amount REAL, balance_before REAL, balance_after REAL, description TEXT,
+                reference_type TEXT, reference_id INTEGER, reference_number TEXT, invoice_id INTEGER,
+                invoice_number TEXT, payment_method TEXT, date TEXT, time TEXT, created_by TEXT
+            );
+            CREATE TABLE ledger_entries (
+                id INTEGER PRIMARY KEY, date TEXT, time TEXT, type TEXT, category TEXT, description TEXT,
+                amount REAL, customer_id INTEGER, customer_name TEXT, reference_type TEXT, reference_id INTEGER,
+                reference_number TEXT, bill_number TEXT, payment_method TEXT, payment_channel_id INTEGER,
+                payment_channel_name TEXT, notes TEXT, created_by TEXT
+            );
+            CREATE TABLE payment_channels (
+                id INTEGER PRIMARY KEY, name TEXT, type TEXT, is_active INTEGER DEFAULT 1, is_default INTEGER DEFAULT 0
+            );
+            INSERT INTO products (id, name, unit_type, current_stock, track_inventory, cost_price)
+                VALUES (1, 'Pipe', 'piece', '5', 1, 60), (2, 'Valve', 'piece', '3', 1, 30);
+            INSERT INTO customers (id, name, balance) VALUES (1, 'Ali', 600);
+            INSERT INTO invoices (id, bill_number, customer_id, customer_name, status, subtotal, grand_total,
+                                  total_amount, paid_amount, remaining_balance, date, time)
+                VALUES (1, 'I00001', 1, 'Ali', 'partially_paid', 1100, 1100, 1100, 500, 600,
+                        '2026-03-01', '10:00:00');
+            INSERT INTO invoice_items (id, invoice_id, product_id, product_name, quantity, unit, unit_price,
+                                       line_total)
+                VALUES (1, 1, 1, 'Pipe', '10', 'piece', 100, 1000), (2, 1, 2, 'Valve', '2', 'piece', 50, 100);
+            INSERT INTO customer_ledger_entries (customer_id, customer_name, entry_type, transaction_type, amount,
+                                                 balance_before, balance_after, invoice_id, date, time)
+                VALUES (1, 'Ali', 'debit', 'invoice', 1100, 0, 1100, 1, '2026-03-01', '10:00:00'),
+                       (1, 'Ali', 'credit', 'payment', 500, 1100, 600, NULL, '2026-03-01', '10:05:00');
+            INSERT INTO payment_channels (id, name, type, is_default) VALUES (1, 'Cash', 'cash', 1);",
+        )
+        .unwrap();
+        crate::journal::ensure_schema(&conn).unwrap();
+        conn
+    }
+
+    /// Return (invoice item, quantity, condition) lines of invoice 1
+    fn request(items: &[(i64, &str, &str)], settlement_type: &str) -> ReturnRequest {
+        ReturnRequest {
+            invoice_id: 1,
+            items: items
+                .iter()
+                .map(|&(invoice_item_id, quantity, condition)| ReturnItemRequest {
+                    invoice_item_id,
+                    return_quantity: quantity.to_string(),
+                    condition: Some(condition.to_string()),
+                    reason: None,
+                })
+                .collect(),
+            reason: "Wrong size".to_string(),
+            settlement_type: settlement_type.to_string(),
+            payment_channel_id: None,
+            processed_by: "sara".to_string(),
+            notes: None,
+        }
+    }
+
+    /// Process a return in its own transaction, rolled back when it fails
+    fn process(conn: &mut Connection, request: &ReturnRequest) -> Result<ReturnResult, String> {
+        let tx = conn.transaction().unwrap();
+        let result = process_return_in_transaction(&tx, request)?;
+        tx.commit().unwrap();
+        Ok(result)
+    }
+
+    fn read<T: rusqlite::types::FromSql>(conn: &Connection, sql: &str) -> T {
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn quantities_are_checked_against_earlier_returns() {
+        let mut conn = database();
+        let first = process(&mut conn, &request(&[(1, "4", "good")], "ledger")).unwrap();
+        assert_eq!(first.total_amount, Money::from_paisa(40_000));
+
+        let error = process(&mut conn, &request(&[(1, "7", "good")], "ledger")).unwrap_err();
+        assert!(error.contains("already returned 4, returnable 6"), "{}", error);
+        assert!(
+            process(&mut conn, &request(&[(1, "1", "good"), (1, "1", "good")], "ledger"))
+                .unwrap_err()
+                .contains("more than once")
+        );
+        assert!(process(&mut conn, &request(&[(3, "1", "good")], "ledger")).is_err());
+        assert_eq!(read::<i64>(&conn, "SELECT COUNT(*) FROM returns"), 1);
+
+        // The rest of the invoice completes it, counting the first return
+        process(&mut conn, &request(&[(1, "6", "good"), (2, "2", "good")], "ledger")).unwrap();
+        let types: String = read(&conn, "SELECT group_concat(return_type, ',') FROM returns ORDER BY id");
+        assert_eq!(types, "partial,full");
+        assert!(process(&mut conn, &request(&[(2, "1", "good")], "ledger"))
+            .unwrap_err()
+            .contains("returnable 0"));
+    }
+
+    #[test]
+    fn good_items_go_back_to_stock_and_the_rest_do_not() {
+        let mut conn = database();
+        let result = process(&mut conn, &request(&[(1, "2", "good"), (2, "1", "damaged")], "ledger")).unwrap();
+        assert_eq!(result.items_restocked, 1);
+
+        let pipes: String = read(&conn, "SELECT current_stock FROM products WHERE id = 1");
+        let valves: String = read(&conn, "SELECT current_stock FROM products WHERE id = 2");
+        assert_eq!((pipes.as_str(), valves.as_str()), ("7", "3"));
+        let movement: (i64, String, String, String) = conn
+            .query_row(
+                "SELECT product_id, quantity, stock_before, stock_after FROM stock_movements
+                 WHERE reference_type = 'return'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .unwrap();
+        assert_eq!(movement, (1, "2".to_string(), "5".to_string(), "7".to_string()));
+        let actions: String = read(
+            &conn,
+            "SELECT group_concat(action || ':' || restocked, ',') FROM return_items",
+        );
+        assert_eq!(actions, "refund:1,discard:0");
+    }
+
+    #[test]
+    fn a_credit_note_lowers_the_balance_and_a_refund_pays_out_what_was_received() {
+        let mut conn = database();
+
+        // Two pipes credited to the account, off what is still open on the invoice
+        let credit = process(&mut conn, &request(&[(1, "2", "good")], "ledger")).unwrap();
+        assert_eq!(credit.customer_balance_after, Money::from_paisa(40_000));
+        assert_eq!(read::<f64>(&conn, "SELECT remaining_balance FROM invoices"), 400.0);
+        assert_eq!(read::<f64>(&conn, "SELECT balance FROM customers"), 400.0);
+
+        // Three pipes refunded in cash: the credit and the payout cancel on the account
+        let cash = process(&mut conn, &request(&[(1, "3", "good")], "cash")).unwrap();
+        assert_eq!(cash.customer_balance_after, Money::from_paisa(40_000));
+        assert_eq!(read::<f64>(&conn, "SELECT remaining_balance FROM invoices"), 400.0);
+        let refund: (f64, i64) = conn
+            .query_row(
+                "SELECT amount, payment_channel_id FROM ledger_entries WHERE category = 'Cash Refund'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(refund, (300.0, 1));
+        let entries: String = read(
+            &conn,
+            "SELECT group_concat(entry_type || ' ' || amount, ', ') FROM customer_ledger_entries
+             WHERE reference_type = 'return'",
+        );
+        assert_eq!(entries, "credit 200.0, credit 300.0, debit 300.0");
+
+        // Only Rs.200 of the Rs.500 received is left to refund
+        let error = process(&mut conn, &request(&[(1, "3", "good")], "cash")).unwrap_err();
+        assert!(error.contains("exceeds the Rs.200"), "{}", error);
+        process(&mut conn, &request(&[(1, "2", "good")], "cash")).unwrap();
+    }
+}
@@ -0,0 +1,630 @@
+/*!
+ * GLOBAL SEARCH
+ * Products, customers, invoices and vendors each have an FTS5 index
+ * (`search_<table>`) kept current by triggers on the source table, so every
+ * write, from this layer or the frontend, is searchable at once. Phone and
+ * CNIC numbers are also indexed with their separators stripped. The bundled
+ * SQLite both connections use is built with FTS5.
+ *
+ * `global_search` first matches every query word as a prefix. When that
+ * finds too little, each word is widened to the indexed terms within a small
+ * edit distance (typos) or with the same Roman Urdu skeleton: spelling
+ * variants such as Muhammad/Mohammed or saria/sarya reduce to the same
+ * consonants, and Urdu script is transliterated before comparing. Results
+ * rank prefix matches first, then by bm25 with name columns weighted
+ * highest.
+ */
+
+use std::collections::HashSet;
+
+use log::info;
+use rusqlite::{params, Connection, Transaction, TransactionBehavior};
+use serde::Serialize;
+
+use crate::database::{has_column, open_connection, table_exists};
+use crate::money::{paisa_from_row, paisa_sql, Money};
+use crate::quantity::value_to_text;
+
+/// An FTS5 index over one source table
+struct SearchIndex {
+    entity_type: &'static str,
+    table: &'static str,
+    /// Indexed source columns with their bm25 weights
+    columns: &'static [(&'static str, f64)],
+    /// Columns also indexed as bare digits in the `digits` column
+    digit_columns: &'static [&'static str],
+    /// title, subtitle, category, stock over the source row `t`
+    display: &'static str,
+    /// Money column shown as the hit's amount, read as exact paisa
+    amount: Option<&'static str>,
+    filter: &'static str,
+}
+
+const SEARCH_INDEXES: &[SearchIndex] = &[
+    SearchIndex {
+        entity_type: "customer",
+        table: "customers",
+        columns: &[("name", 10.0), ("phone", 6.0), ("cnic", 6.0), ("address", 2.0)],
+        digit_columns: &["phone", "cnic"],
+        display: "t.name, COALESCE(t.phone, ''), NULL, NULL",
+        amount: Some("t.balance"),
+        filter: "t.deleted_at IS NULL",
+    },
+    SearchIndex {
+        entity_type: "product",
+        table: "products",
+        columns: &[
+            ("name", 10.0),
+            ("base_name", 6.0),
+            ("sku", 8.0),
+            ("barcode", 8.0),
+            ("size", 2.0),
+            ("grade", 2.0),
+        ],
+        digit_columns: &[],
+        display: "t.name, COALESCE(t.category, ''), t.category, t.current_stock",
+        amount: None,
+        filter: "COALESCE(t.status, 'active') = 'active' AND t.deleted_at IS NULL",
+    },
+    SearchIndex {
+        entity_type: "invoice",
+        table: "invoices",
+        columns: &[("bill_number", 10.0), ("customer_name", 4.0)],
+        digit_columns: &[],
+        display: "t.bill_number, COALESCE(t.customer_name, ''), NULL, NULL",
+        amount: Some("t.grand_total"),
+        filter: "t.deleted_at IS NULL",
+    },
+    SearchIndex {
+        entity_type: "vendor",
+        table: "vendors",
+        columns: &[
+            ("name", 10.0),
+            ("company_name", 6.0),
+            ("contact_person", 4.0),
+            ("phone", 6.0),
+            ("city", 2.0),
+        ],
+        digit_columns: &["phone"],
+        display: "t.name, COALESCE(t.company_name, t.phone, ''), NULL, NULL",
+        amount: Some("t.balance"),
+        filter: "t.deleted_at IS NULL",
+    },
+];
+
+/// Weight of the stripped-digits column
+const DIGITS_WEIGHT: f64 = 6.0;
+
+/// Most indexed terms a single query word is widened to
+const MAX_ALTERNATIVES: usize = 12;
+
+impl SearchIndex {
+    fn fts_table(&self) -> String {
+        format!("search_{}", self.table)
+    }
+
+    fn terms_table(&self) -> String {
+        format!("search_{}_terms", self.table)
+    }
+
+    fn fts_columns(&self) -> Vec<&'static str> {
+        let mut columns: Vec<&'static str> = self.columns.iter().map(|(column, _)| *column).collect();
+        if !self.digit_columns.is_empty() {
+            columns.push("digits");
+        }
+        columns
+    }
+
+    fn weights(&self) -> String {
+        let mut weights: Vec<String> = self.columns.iter().map(|(_, weight)| weight.to_string()).collect();
+        if !self.digit_columns.is_empty() {
+            weights.push(DIGITS_WEIGHT.to_string());
+        }
+        weights.join(", ")
+    }
+
+    /// Indexed values of the source row `row`; columns the table lacks index as ''
+    fn values(&self, conn: &Connection, row: &str) -> Result<Vec<String>, String> {
+        let present = |column: &str| {
+            has_column(conn, self.table, column).map_err(|e| format!("Failed to read {} columns: {}", self.table, e))
+        };
+        let mut values = Vec::with_capacity(self.columns.len() + 1);
+        for (column, _) in self.columns {
+            values.push(if present(column)? {
+                format!("COALESCE({row}.{column}, '')")
+            } else {
+                "''".to_string()
+            });
+        }
+        if !self.digit_columns.is_empty() {
+            let mut digits = Vec::new();
+            for column in self.digit_columns {
+                if present(column)? {
+                    digits.push(format!(
+                        "REPLACE(REPLACE(REPLACE(COALESCE({row}.{column}, ''), '-', ''), ' ', ''), '+', '')"
+                    ));
+                }
+            }
+            values.push(if digits.is_empty() {
+                "''".to_string()
+            } else {
+                digits.join(" || ' ' || ")
+            });
+        }
+        Ok(values)
+    }
+}
+
+/// Create the FTS5 indexes and their triggers, and rebuild any index that
+/// has drifted from its table. Tables the frontend has not created yet are
+/// picked up on a later start.
+pub fn ensure_schema(conn: &Connection) -> Result<(), String> {
+    for index in SEARCH_INDEXES {
+        if !table_exists(conn, index.table).map_err(|e| format!("Failed to inspect schema: {}", e))? {
+            continue;
+        }
+        let fts_table = index.fts_table();
+        conn.execute_batch(&format!(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS {fts_table} USING fts5(
+                {}, tokenize = 'unicode61 remove_diacritics 2', prefix = '2 3'
+             );
+             CREATE VIRTUAL TABLE IF NOT EXISTS {} USING fts5vocab({fts_table}, row);",
+            index.fts_columns().join(", "),
+            index.terms_table()
+        ))
+        .map_err(|e| format!("Failed to create {}: {}", fts_table, e))?;
+
+        // Recreated on every start so they follow columns added since
+        let columns = index.fts_columns().join(", ");
+        let new_values = index.values(conn, "NEW")?.join(", ");
+        let mut watched: Vec<&str> = vec!["id"];
+        for (column, _) in index.columns {
+            if has_column(conn, index.table, column).map_err(|e| format!("Failed to inspect schema: {}", e))? {
+                watched.push(column);
+            }
+        }
+        let table = index.table;
+        conn.execute_batch(&format!(
+            "DROP TRIGGER IF EXISTS trg_{table}_search_insert;
+             DROP TRIGGER IF EXISTS trg_{table}_search_update;
+             DROP TRIGGER IF EXISTS trg_{table}_search_delete;
+             CREATE TRIGGER trg_{table}_search_insert AFTER INSERT ON {table} BEGIN
+                 INSERT INTO {fts_table} (rowid, {columns}) VALUES (NEW.id, {new_values});
+             END;
+             CREATE TRIGGER trg_{table}_search_update AFTER UPDATE OF {} ON {table} BEGIN
+                 DELETE FROM {fts_table} WHERE rowid = OLD.id;
+                 INSERT INTO {fts_table} (rowid, {columns}) VALUES (NEW.id, {new_values});
+             END;
+             CREATE TRIGGER trg_{table}_search_delete AFTER DELETE ON {table} BEGIN
+                 DELETE FROM {fts_table} WHERE rowid = OLD.id;
+             END;",
+            watched.join(", ")
+        ))
+        .map_err(|e| format!("Failed to create search triggers on {}: {}", table, e))?;
+
+        let count = |sql: &str| -> Result<i64, String> {
+            conn.query_row(sql, [], |row| row.get(0))
+                .map_err(|e| format!("Failed to count {}: {}", table, e))
+        };
+        let indexed = count(&format!("SELECT COUNT(*) FROM {fts_table}"))?;
+        let rows = count(&format!("SELECT COUNT(*) FROM {table}"))?;
+        if indexed != rows {
+            let rebuilt = rebuild_index(conn, index)?;
+            info!("[SEARCH] Rebuilt {} index: {} rows", index.entity_type, rebuilt);
+        }
+    }
+    Ok(())
+}
+
+/// Refill one index from its source table
+fn rebuild_index(conn: &Connection, index: &SearchIndex) -> Result<usize, String> {
+    let fts_table = index.fts_table();
+    conn.execute(&format!("DELETE FROM {fts_table}"), [])
+        .map_err(|e| format!("Failed to clear {}: {}", fts_table, e))?;
+    conn.execute(
+        &format!(
+            "INSERT INTO {fts_table} (rowid, {}) SELECT t.id, {} FROM {} t",
+            index.fts_columns().join(", "),
+            index.values(conn, "t")?.join(", "),
+            index.table
+        ),
+        [],
+    )
+    .map_err(|e| format!("Failed to rebuild {}: {}", fts_table, e))
+}
+
+/// Rebuild every search index inside the transaction
+pub fn rebuild_in_transaction(tx: &Transaction) -> Result<usize, String> {
+    let mut rebuilt = 0;
+    for index in SEARCH_INDEXES {
+        if table_exists(tx, &index.fts_table()).map_err(|e| format!("Failed to inspect schema: {}", e))? {
+            rebuilt += rebuild_index(tx, index)?;
+        }
+    }
+    Ok(rebuilt)
+}
+
+/// Latin spelling of Urdu letters, close enough for matching Roman Urdu
+fn transliterate(c: char) -> Option<&'static str> {
+    Some(match c {
+        'ا' | 'آ' | 'أ' | 'ع' => "a",
+        'ب' => "b",
+        'پ' => "p",
+        'ت' | 'ٹ' | 'ط' | 'ة' => "t",
+        'ث' | 'س' | 'ص' => "s",
+        'ج' => "j",
+        'چ' => "ch",
+        'ح' | 'ہ' | 'ھ' | 'ه' => "h",
+        'خ' => "kh",
+        'د' | 'ڈ' => "d",
+        'ذ' | 'ز' | 'ض' | 'ظ' | 'ژ' => "z",
+        'ر' | 'ڑ' => "r",
+        'ش' => "sh",
+        'غ' => "gh",
+        'ف' => "f",
+        'ق' => "q",
+        'ک' | 'ك' => "k",
+        'گ' => "g",
+        'ل' => "l",
+        'م' => "m",
+        'ن' | 'ں' => "n",
+        'و' | 'ؤ' => "o",
+        'ی' | 'ي' | 'ے' | 'ئ' => "i",
+        _ => return None,
+    })
+}
+
+/// Consonant skeleton of a word: Urdu script transliterated, sound-alike
+/// letters merged, vowels and 'h' dropped after the first letter, and
+/// doubled letters collapsed. Roman Urdu spellings of one word share it.
+fn skeleton(word: &str) -> String {
+    let mut latin = String::with_capacity(word.len());
+    for c in word.chars().flat_map(char::to_lowercase) {
+        match transliterate(c) {
+            Some(letters) => latin.push_str(letters),
+            None if c.is_alphanumeric() => latin.push(c),
+            None => {}
+        }
+    }
+    let latin = latin
+        .replace("ph", "f")
+        .replace("ch", "c")
+        .replace("sh", "s")
+        .replace("kh", "k")
+        .replace("gh", "g");
+
+    let mut skeleton = String::with_capacity(latin.len());
+    for (position, c) in latin.chars().enumerate() {
+        let c = match c {
+            'q' => 'k',
+            'w' => 'v',
+            'e' | 'i' | 'o' | 'u' if position == 0 => 'a',
+            other => other,
+        };
+        if position > 0 && matches!(c, 'a' | 'e' | 'i' | 'o' | 'u' | 'y' | 'h') {
+            continue;
+        }
+        if skeleton.ends_with(c) {
+            continue;
+        }
+        skeleton.push(c);
+    }
+    skeleton
+}
+
+/// Levenshtein distance between two words
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Typos tolerated in a word of this length
+fn allowed_typos(length: usize) -> usize {
+    match length {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+/// Query words: lowercase runs of letters and digits
+fn query_words(query: &str) -> Vec<String> {
+    query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn quote(term: &str) -> String {
+    format!("\"{}\"", term.replace('"', "\"\""))
+}
+
+/// Indexed terms close to `word`, nearest first: same skeleton, or within
+/// the allowed typos of the whole term or of its first `word.len()` letters.
+/// Numbers (phones, bill numbers) are only ever matched as typed.
+fn similar_terms(word: &str, terms: &[String]) -> Vec<String> {
+    let has_digit = |text: &str| text.chars().any(|c| c.is_ascii_digit());
+    if has_digit(word) {
+        return Vec::new();
+    }
+    let typos = allowed_typos(word.chars().count());
+    let word_skeleton = skeleton(word);
+    let word_chars: Vec<char> = word.chars().collect();
+
+    let mut scored: Vec<(usize, &String)> = terms
+        .iter()
+        .filter(|term| term.as_str() != word && !has_digit(term))
+        .filter_map(|term| {
+            let term_chars: Vec<char> = term.chars().collect();
+            let prefix = &term_chars[..term_chars.len().min(word_chars.len())];
+            let distance = edit_distance(&word_chars, &term_chars).min(edit_distance(&word_chars, prefix));
+            if distance <= typos {
+                Some((distance, term))
+            } else if word_skeleton.chars().count() >= 2 && skeleton(term) == word_skeleton {
+                Some((typos + 1, term))
+            } else {
+                None
+            }
+        })
+        .collect();
+    scored.sort();
+    scored
+        .into_iter()
+        .take(MAX_ALTERNATIVES)
+        .map(|(_, term)| term.clone())
+        .collect()
+}
+
+/// One search result
+#[derive(Debug, Serialize)]
+pub struct SearchHit {
+    pub entity_type: String,
+    pub id: i64,
+    pub title: String,
+    pub subtitle: String,
+    pub category: Option<String>,
+    pub stock: Option<String>,
+    /// Customer or vendor balance, or invoice total
+    pub amount: Option<Money>,
+    /// 'prefix' when every word matched as typed, 'fuzzy' for typos and spelling variants
+    pub matched_by: String,
+    /// Higher is better; comparable within one `matched_by`
+    pub score: f64,
+}
+
+/// Run one MATCH expression against an index, skipping rows already found
+fn run_match(
+    conn: &Connection,
+    index: &SearchIndex,
+    expression: &str,
+    matched_by: &str,
+    limit: usize,
+    found: &HashSet<(&'static str, i64)>,
+) -> Result<Vec<SearchHit>, String> {
+    let fts_table = index.fts_table();
+    let amount = match index.amount {
+        Some(column) => paisa_sql(conn, index.table, column).map_err(|e| format!("Failed to inspect schema: {}", e))?,
+        None => "NULL".to_string(),
+    };
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT t.id, {}, {amount}, bm25({fts_table}, {}) AS rank
+             FROM {fts_table} JOIN {} t ON t.id = {fts_table}.rowid
+             WHERE {fts_table} MATCH ?1 AND {}
+             ORDER BY rank
+             LIMIT ?2",
+            index.display,
+            index.weights(),
+            index.table,
+            index.filter
+        ))
+        .map_err(|e| format!("Failed to prepare {} search: {}", index.entity_type, e))?;
+    let hits = stmt
+        .query_map(params![expression, (limit + found.len()) as i64], |row| {
+            Ok(SearchHit {
+                entity_type: index.entity_type.to_string(),
+                id: row.get(0)?,
+                title: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                subtitle: row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                category: row.get(3)?,
+                stock: row.get::<_, rusqlite::types::Value>(4).map(|value| match value {
+                    rusqlite::types::Value::Null => None,
+                    value => Some(value_to_text(value)),
+                })?,
+                amount: match row.get_ref(5)? {
+                    rusqlite::types::ValueRef::Null => None,
+                    _ => Some(paisa_from_row(row, 5)?),
+                },
+                matched_by: matched_by.to_string(),
+                score: -row.get::<_, f64>(6)?,
+            })
+        })
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| format!("Failed to search {}: {}", index.table, e))?;
+    Ok(hits
+        .into_iter()
+        .filter(|hit| !found.contains(&(index.entity_type, hit.id)))
+        .take(limit)
+        .collect())
+}
+
+/// Search every index (or the given entity types), at most `limit` hits each
+pub fn search(
+    conn: &Connection,
+    query: &str,
+    entity_types: Option<&[String]>,
+    limit: usize,
+) -> Result<Vec<SearchHit>, String> {
+    let words = query_words(query);
+    if words.is_empty() {
+        return Ok(Vec::new());
+    }
+    let prefix_expression = words
+        .iter()
+        .map(|word| format!("{}*", quote(word)))
+        .collect::<Vec<_>>()
+        .join(" AND ");
+
+    let mut hits = Vec::new();
+    for index in SEARCH_INDEXES {
+        if entity_types.is_some_and(|types| !types.iter().any(|kind| kind == index.entity_type)) {
+            continue;
+        }
+        if !table_exists(conn, &index.fts_table()).map_err(|e| format!("Failed to inspect schema: {}", e))? {
+            continue;
+        }
+
+        let mut found = HashSet::new();
+        let mut index_hits = run_match(conn, index, &prefix_expression, "prefix", limit, &found)?;
+        if index_hits.len() < limit {
+            let mut stmt = conn
+                .prepare(&format!("SELECT term FROM {}", index.terms_table()))
+                .map_err(|e| format!("Failed to read {} terms: {}", index.entity_type, e))?;
+            let terms: Vec<String> = stmt
+                .query_map([], |row| row.get(0))
+                .and_then(|rows| rows.collect())
+                .map_err(|e| format!("Failed to read {} terms: {}", index.entity_type, e))?;
+
+            let mut widened = false;
+            let fuzzy_expression = words
+                .iter()
+                .map(|word| {
+                    let alternatives = similar_terms(word, &terms);
+                    widened |= !alternatives.is_empty();
+                    let mut any = vec![format!("{}*", quote(word))];
+                    any.extend(alternatives.iter().map(|term| quote(term)));
+                    format!("({})", any.join(" OR "))
+                })
+                .collect::<Vec<_>>()
+                .join(" AND ");
+            if widened {
+                found.extend(index_hits.iter().map(|hit| (index.entity_type, hit.id)));
+                let remaining = limit - index_hits.len();
+                index_hits.extend(run_match(conn, index, &fuzzy_expression, "fuzzy", remaining, &found)?);
+            }
+        }
+        hits.extend(index_hits);
+    }
+
+    // Prefix matches first; among them exact and leading titles, then bm25
+    let query_text = words.join(" ");
+    let title_rank = |hit: &SearchHit| {
+        let title = hit.title.to_lowercase();
+        if title == query_text {
+            0
+        } else if title.starts_with(&query_text) {
+            1
+        } else {
+            2
+        }
+    };
+    hits.sort_by(|a, b| {
+        (a.matched_by != "prefix", title_rank(a))
+            .cmp(&(b.matched_by != "prefix", title_rank(b)))
+            .then(b.score.total_cmp(&a.score))
+    });
+    Ok(hits)
+}
+
+/// Ranked search over products, customers, invoices and vendors
+#[tauri::command]
+pub async fn global_search(
+    query: String,
+    entity_types: Option<Vec<String>>,
+    limit: Option<usize>,
+) -> Result<Vec<SearchHit>, String> {
+    let conn = open_connection()?;
+    search(&conn, &query, entity_types.as_deref(), limit.unwrap_or(5).clamp(1, 50))
+}
+
+/// Refill every search index from its table
+#[tauri::command]
+pub async fn rebuild_search_index() -> Result<usize, String> {
+    let mut conn = open_connection()?;
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let rebuilt = rebuild_in_transaction(&tx)?;
+    tx.commit()
+        .map_err(|e| format!("Failed to commit search index rebuild: {}", e))?;
+
+    info!("[SEARCH] Rebuilt search indexes: {} rows", rebuilt);
+    Ok(rebuilt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| word.to_string()).collect()
+    }
+
+    fn chars(word: &str) -> Vec<char> {
+        word.chars().collect()
+    }
+
+    #[test]
+    fn urdu_letters_transliterate_to_latin() {
+        assert_eq!(transliterate('م'), Some("m"));
+        assert_eq!(transliterate('خ'), Some("kh"));
+        assert_eq!(transliterate('ع'), Some("a"));
+        assert_eq!(transliterate('ے'), Some("i"));
+        assert_eq!(transliterate('m'), None);
+        assert_eq!(transliterate('۱'), None);
+    }
+
+    #[test]
+    fn spellings_of_one_name_share_a_skeleton() {
+        assert_eq!(skeleton("Muhammad"), "md");
+        assert_eq!(skeleton("Mohammed"), skeleton("Muhammad"));
+        assert_eq!(skeleton("محمد"), skeleton("Muhammad"));
+        assert_eq!(skeleton("saria"), "sr");
+        assert_eq!(skeleton("sarya"), "sr");
+        assert_eq!(skeleton("Khan"), skeleton("کان"));
+        assert_eq!(skeleton("Shafiq"), skeleton("Safik"));
+        // A leading vowel is kept, whichever it is
+        assert_eq!(skeleton("Usman"), skeleton("Osman"));
+        assert_ne!(skeleton("Usman"), skeleton("Saman"));
+        assert_eq!(skeleton("12-B"), "12b");
+    }
+
+    #[test]
+    fn edit_distance_counts_single_letter_edits() {
+        assert_eq!(edit_distance(&chars("kitten"), &chars("sitting")), 3);
+        assert_eq!(edit_distance(&chars("saria"), &chars("sarya")), 1);
+        assert_eq!(edit_distance(&chars(""), &chars("pipe")), 4);
+        assert_eq!(edit_distance(&chars("محمد"), &chars("محمد")), 0);
+        assert_eq!(allowed_typos(3), 0);
+        assert_eq!(allowed_typos(4), 1);
+        assert_eq!(allowed_typos(7), 1);
+        assert_eq!(allowed_typos(8), 2);
+    }
+
+    #[test]
+    fn similar_terms_find_typos_prefixes_and_spellings() {
+        let index = terms(&[
+            "muhammad", "mohammed", "mahmood", "sarya", "saria", "cement", "cemented", "0300",
+        ]);
+        // The typo first, then the spellings sharing its consonants
+        assert_eq!(similar_terms("muhamad", &index), ["muhammad", "mahmood", "mohammed"]);
+        assert_eq!(similar_terms("saria", &index), ["sarya"]);
+        // A prefix with a typo still finds the longer term
+        assert_eq!(similar_terms("cemet", &index), ["cement", "cemented"]);
+        // Urdu script meets its Roman spelling through the skeleton
+        assert_eq!(similar_terms("محمد", &index), ["mahmood", "mohammed", "muhammad"]);
+        // A three-letter word tolerates no typo but still completes; numbers are matched as typed
+        assert!(similar_terms("cam", &index).is_empty());
+        assert_eq!(similar_terms("sar", &index), ["saria", "sarya"]);
+        assert!(similar_terms("0301", &index).is_empty());
+    }
+}
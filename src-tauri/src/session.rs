@@ -0,0 +1,257 @@
+/*!
+ * SIGNED-IN SESSION
+ *
+ * Who is signed in on this terminal, established by `authenticate_user`
+ * after checking the password and held in the process, never taken from a
+ * command's arguments. Commands that only record who acted take the name
+ * from the session. Admin-only commands still name the acting user for
+ * the record, but pass only when that name is the signed-in user and the
+ * session carries the admin role. A staff session is checked against the
+ * staff table again each time, so deactivating someone or changing their
+ * role takes effect at once.
+ *
+ * The session lives as long as the process: after a restart the window
+ * keeps its user, but admin-only commands ask for a fresh sign-in.
+ */
+
+use std::sync::Mutex;
+
+use log::{info, warn};
+use rusqlite::{Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::database::{has_column, open_connection, table_exists};
+
+/// The app's own administrator login, checked before the staff table
+const BUILT_IN_LOGIN: (&str, &str) = ("admin", "admin123");
+
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub username: String,
+    pub role: String,
+    /// `staff_management` row the session belongs to; `None` for the built-in login
+    pub staff_id: Option<i64>,
+}
+
+static SESSION: Mutex<Option<Session>> = Mutex::new(None);
+
+pub fn start(session: Session) {
+    info!("[SESSION] {} signed in as {}", session.username, session.role);
+    *SESSION.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(session);
+}
+
+pub fn end() {
+    if let Some(session) = SESSION.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).take() {
+        info!("[SESSION] {} signed out", session.username);
+    }
+}
+
+pub fn current() -> Option<Session> {
+    SESSION.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+}
+
+/// Check `username`/`password` against the built-in login and the staff
+/// table's login columns. Passwords are bcrypt hashes, as the staff form stores them.
+pub fn verify_login(conn: &Connection, username: &str, password: &str) -> Result<Option<Session>, String> {
+    let username = username.trim();
+    if username == BUILT_IN_LOGIN.0 && password == BUILT_IN_LOGIN.1 {
+        return Ok(Some(Session {
+            username: username.to_string(),
+            role: "admin".to_string(),
+            staff_id: None,
+        }));
+    }
+    if !table_exists(conn, "staff_management").map_err(|e| format!("Failed to inspect schema: {}", e))?
+        || !has_column(conn, "staff_management", "login_password_hash")
+            .map_err(|e| format!("Failed to inspect schema: {}", e))?
+    {
+        return Ok(None);
+    }
+    let staff: Option<(i64, String, String, Option<String>)> = conn
+        .query_row(
+            "SELECT id, COALESCE(login_username, full_name), COALESCE(role, 'worker'), login_password_hash
+             FROM staff_management
+             WHERE COALESCE(is_active, 1) = 1 AND COALESCE(can_login, 0) = 1
+               AND (login_username = ?1 COLLATE NOCASE OR full_name = ?1 COLLATE NOCASE)
+             ORDER BY login_username = ?1 COLLATE NOCASE DESC
+             LIMIT 1",
+            [username],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to look up staff login: {}", e))?;
+    let Some((staff_id, name, role, Some(hash))) = staff else {
+        return Ok(None);
+    };
+    if !bcrypt::verify(password, &hash).unwrap_or(false) {
+        return Ok(None);
+    }
+    Ok(Some(Session {
+        username: name,
+        role,
+        staff_id: Some(staff_id),
+    }))
+}
+
+/// Pass only when `user` is the signed-in user of this terminal and an admin
+pub fn require_admin(conn: &Connection, user: &str, action: &str) -> Result<(), String> {
+    check_admin(conn, current().as_ref(), user, action)
+}
+
+/// Name of the signed-in user for an audit entry, empty when no one is
+/// signed in so the entry keeps the actor the frontend set
+pub fn current_username() -> String {
+    current().map(|session| session.username).unwrap_or_default()
+}
+
+/// Name of the signed-in user, for commands that record who acted
+pub fn current_user(action: &str) -> Result<String, String> {
+    current()
+        .map(|session| session.username)
+        .ok_or_else(|| format!("Sign in to {}", action))
+}
+
+/// Name of the signed-in user when that user is an admin
+pub fn require_signed_in_admin(conn: &Connection, action: &str) -> Result<String, String> {
+    let session = current();
+    let user = session
+        .as_ref()
+        .map(|session| session.username.clone())
+        .unwrap_or_default();
+    check_admin(conn, session.as_ref(), &user, action)?;
+    Ok(user)
+}
+
+fn check_admin(conn: &Connection, session: Option<&Session>, user: &str, action: &str) -> Result<(), String> {
+    let user = user.trim();
+    let Some(session) = session else {
+        return Err(format!("Sign in as an admin to {}", action));
+    };
+    if !session.username.eq_ignore_ascii_case(user) {
+        return Err(format!(
+            "Only the signed-in user can {} (signed in as {}, not {})",
+            action, session.username, user
+        ));
+    }
+    let is_admin = match session.staff_id {
+        None => session.role == "admin",
+        Some(staff_id) => conn
+            .query_row(
+                "SELECT role = 'admin' AND COALESCE(is_active, 1) = 1 FROM staff_management WHERE id = ?1",
+                [staff_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("Failed to check user role: {}", e))?
+            .unwrap_or(false),
+    };
+    if !is_admin {
+        return Err(format!("Only an admin can {} ({} is not an admin)", action, user));
+    }
+    Ok(())
+}
+
+// ==================== COMMANDS ====================
+
+#[derive(Serialize)]
+pub struct AuthResult {
+    success: bool,
+    role: String,
+    id: String,
+}
+
+/// Sign in on this terminal; a failed attempt signs the previous user out
+#[tauri::command]
+pub async fn authenticate_user(username: String, password: String) -> Result<AuthResult, String> {
+    info!("Authentication attempt: {}", username);
+
+    let conn = open_connection()?;
+    match verify_login(&conn, &username, &password)? {
+        Some(session) => {
+            let result = AuthResult {
+                success: true,
+                role: session.role.clone(),
+                id: session.staff_id.unwrap_or(1).to_string(),
+            };
+            start(session);
+            Ok(result)
+        }
+        None => {
+            warn!("[SESSION] Sign-in refused for {}", username.trim());
+            end();
+            Ok(AuthResult {
+                success: false,
+                role: "worker".to_string(),
+                id: "0".to_string(),
+            })
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn end_session() -> Result<(), String> {
+    end();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn database() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE staff_management (
+                id INTEGER PRIMARY KEY, full_name TEXT, role TEXT, is_active INTEGER DEFAULT 1,
+                can_login INTEGER DEFAULT 0, login_username TEXT UNIQUE, login_password_hash TEXT
+            );",
+        )
+        .unwrap();
+        let hash = bcrypt::hash("s3cret!", 4).unwrap();
+        conn.execute(
+            "INSERT INTO staff_management (id, full_name, role, can_login, login_username, login_password_hash)
+             VALUES (7, 'Sara Khan', 'admin', 1, 'sara', ?1), (8, 'Bilal', 'worker', 1, 'bilal', ?1)",
+            [&hash],
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn staff_sign_in_checks_the_password_hash() {
+        let conn = database();
+        let session = verify_login(&conn, "Sara", "s3cret!").unwrap().unwrap();
+        assert_eq!(
+            (session.username.as_str(), session.role.as_str(), session.staff_id),
+            ("sara", "admin", Some(7))
+        );
+        assert!(verify_login(&conn, "sara", "wrong").unwrap().is_none());
+        assert!(verify_login(&conn, "ittehad", "store!123").unwrap().is_none());
+
+        conn.execute("UPDATE staff_management SET can_login = 0 WHERE id = 7", [])
+            .unwrap();
+        assert!(verify_login(&conn, "sara", "s3cret!").unwrap().is_none());
+    }
+
+    #[test]
+    fn admin_actions_follow_the_session_not_the_name() {
+        let conn = database();
+        let sara = verify_login(&conn, "sara", "s3cret!").unwrap();
+        let bilal = verify_login(&conn, "bilal", "s3cret!").unwrap();
+
+        assert!(check_admin(&conn, None, "admin", "purge")
+            .unwrap_err()
+            .contains("Sign in"));
+        assert!(check_admin(&conn, sara.as_ref(), "admin", "purge")
+            .unwrap_err()
+            .contains("signed-in"));
+        assert!(check_admin(&conn, bilal.as_ref(), "bilal", "purge")
+            .unwrap_err()
+            .contains("not an admin"));
+        check_admin(&conn, sara.as_ref(), "Sara", "purge").unwrap();
+
+        conn.execute("UPDATE staff_management SET role = 'worker' WHERE id = 7", [])
+            .unwrap();
+        assert!(check_admin(&conn, sara.as_ref(), "sara", "purge").is_err());
+    }
+}
@@ -0,0 +1,287 @@
+/*!
+ * SINGLE INSTANCE
+ *
+ * Only one copy of the app may have the database open: two writers racing
+ * through `atomic_database_replace`, migrations or a sync join would corrupt
+ * it. The running instance owns `app.lock` in the app data directory, which
+ * records its process id and a loopback port it listens on.
+ *
+ * A second launch reads the lock and connects to that port. If the running
+ * instance answers, the new one hands over its command-line arguments and
+ * working directory (a file to open, a deep link) and exits; the running
+ * instance brings its window to the front and emits `second-instance` with
+ * them. If nothing answers, the lock was left by a crash or power cut and is
+ * taken over.
+ *
+ * The lock lives in the data directory, so an instance started with
+ * `ITTEHAD_DATA_DIR` pointing at another directory runs alongside.
+ *
+ * The headless `restore` command refuses to run while an instance answers.
+ */
+
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+pub const SECOND_INSTANCE_EVENT: &str = "second-instance";
+
+const LOCK_FILE_NAME: &str = "app.lock";
+/// First line of every exchange, so a stray program on the port is not mistaken for us
+const HANDSHAKE: &str = "ittehad-iron-store-instance 1";
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(1500);
+/// A lock younger than this without a port is an instance still starting
+const STARTING_GRACE: Duration = Duration::from_secs(10);
+const ACQUIRE_ATTEMPTS: usize = 50;
+const RETRY_DELAY: Duration = Duration::from_millis(200);
+const MAIN_WINDOW: &str = "main";
+
+/// Lock file of this process, removed on exit or before a restart
+static OWNED_LOCK: Mutex<Option<PathBuf>> = Mutex::new(None);
+/// Set once the lock is released; the listener then stops answering
+static RELEASED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LockInfo {
+    pid: u32,
+    port: u16,
+}
+
+/// What a second launch hands to the running instance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecondInstance {
+    pub args: Vec<String>,
+    pub cwd: String,
+}
+
+pub enum Acquired {
+    /// This process is the instance; launches handed over arrive on the receiver
+    Primary(Receiver<SecondInstance>),
+    /// Another instance is running and has been handed the arguments
+    Forwarded { pid: u32 },
+}
+
+fn lock_path(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join(LOCK_FILE_NAME)
+}
+
+/// Become the running instance, or hand `args` to the one already running
+pub fn acquire(app_data_dir: &Path, args: &[String]) -> Result<Acquired, String> {
+    let path = lock_path(app_data_dir);
+    let launch = SecondInstance {
+        args: args.to_vec(),
+        cwd: std::env::current_dir()
+            .map(|dir| dir.to_string_lossy().to_string())
+            .unwrap_or_default(),
+    };
+
+    for _ in 0..ACQUIRE_ATTEMPTS {
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                let (listener, port) = match listen() {
+                    Ok(listening) => listening,
+                    Err(e) => {
+                        let _ = fs::remove_file(&path);
+                        return Err(e);
+                    }
+                };
+                let lock = LockInfo {
+                    pid: std::process::id(),
+                    port,
+                };
+                let contents = serde_json::to_string(&lock).map_err(|e| format!("Failed to encode lock: {}", e))?;
+                file.write_all(contents.as_bytes())
+                    .and_then(|_| file.sync_all())
+                    .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+                *OWNED_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(path.clone());
+                RELEASED.store(false, Ordering::SeqCst);
+                let (sender, receiver) = mpsc::channel();
+                thread::spawn(move || serve(listener, sender));
+                info!("[INSTANCE] Holding {} (port {})", path.display(), port);
+                return Ok(Acquired::Primary(receiver));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(format!("Failed to create {}: {}", path.display(), e)),
+        }
+
+        match read_lock(&path) {
+            Some(lock) => match send(lock.port, &serde_json::to_string(&launch).unwrap_or_default()) {
+                Ok(_) => {
+                    info!("[INSTANCE] Handed the launch to running instance {}", lock.pid);
+                    return Ok(Acquired::Forwarded { pid: lock.pid });
+                }
+                Err(e) => {
+                    warn!("[INSTANCE] Clearing stale lock of process {}: {}", lock.pid, e);
+                    remove_stale(&path);
+                }
+            },
+            // Written a moment ago by an instance that has not stored its port yet
+            None if lock_age(&path).is_some_and(|age| age < STARTING_GRACE) => thread::sleep(RETRY_DELAY),
+            None => {
+                warn!("[INSTANCE] Clearing unreadable lock {}", path.display());
+                remove_stale(&path);
+            }
+        }
+    }
+
+    Err(format!(
+        "Could not take {} after {} attempts",
+        path.display(),
+        ACQUIRE_ATTEMPTS
+    ))
+}
+
+/// Process id of an instance that answers on the lock's port
+pub fn running_instance(app_data_dir: &Path) -> Option<u32> {
+    let lock = read_lock(&lock_path(app_data_dir))?;
+    if lock.pid == std::process::id() {
+        return None;
+    }
+    send(lock.port, "ping").ok().map(|_| lock.pid)
+}
+
+/// Give up the lock as the app exits
+pub fn release() {
+    RELEASED.store(true, Ordering::SeqCst);
+    let owned = OWNED_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .take();
+    if let Some(path) = owned {
+        // Only our own lock: a relaunch may already have written its own
+        if read_lock(&path).is_some_and(|lock| lock.pid == std::process::id()) {
+            if let Err(e) = fs::remove_file(&path) {
+                warn!("[INSTANCE] Failed to remove {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+/// Bring the window forward and emit `second-instance` for each handed-over launch
+pub fn forward_launches(app: AppHandle, launches: Receiver<SecondInstance>) {
+    thread::spawn(move || {
+        for launch in launches {
+            info!("[INSTANCE] Another launch was handed over: {:?}", launch.args);
+            if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
+                let _ = window.unminimize();
+                let _ = window.show();
+                if let Err(e) = window.set_focus() {
+                    warn!("[INSTANCE] Failed to focus the window: {}", e);
+                }
+            }
+            if let Err(e) = app.emit(SECOND_INSTANCE_EVENT, launch) {
+                error!("[INSTANCE] Failed to emit second launch: {}", e);
+            }
+        }
+    });
+}
+
+fn listen() -> Result<(TcpListener, u16), String> {
+    let listener = TcpListener::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)))
+        .map_err(|e| format!("Failed to listen for other launches: {}", e))?;
+    let port = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to read listening port: {}", e))?
+        .port();
+    Ok((listener, port))
+}
+
+fn serve(listener: TcpListener, launches: Sender<SecondInstance>) {
+    for stream in listener.incoming() {
+        if RELEASED.load(Ordering::SeqCst) {
+            return;
+        }
+        let Ok(stream) = stream else {
+            continue;
+        };
+        match answer(stream) {
+            Ok(Some(launch)) => {
+                if launches.send(launch).is_err() {
+                    return;
+                }
+            }
+            Ok(None) => {}
+            Err(e) => warn!("[INSTANCE] Ignored a connection: {}", e),
+        }
+    }
+}
+
+/// `<handshake>\n<ping | launch json>\n`, answered with `<handshake>\n<pid>\n`
+fn answer(mut stream: TcpStream) -> Result<Option<SecondInstance>, String> {
+    stream
+        .set_read_timeout(Some(CONNECT_TIMEOUT))
+        .map_err(|e| format!("Failed to set timeout: {}", e))?;
+    let mut reader = BufReader::new(stream.try_clone().map_err(|e| format!("Failed to read: {}", e))?);
+    let mut handshake = String::new();
+    let mut request = String::new();
+    reader
+        .read_line(&mut handshake)
+        .and_then(|_| reader.read_line(&mut request))
+        .map_err(|e| format!("Failed to read: {}", e))?;
+    if handshake.trim_end() != HANDSHAKE {
+        return Err("unknown handshake".to_string());
+    }
+
+    let launch = match request.trim_end() {
+        "ping" => None,
+        json => Some(serde_json::from_str(json).map_err(|e| format!("Invalid launch: {}", e))?),
+    };
+    stream
+        .write_all(format!("{}\n{}\n", HANDSHAKE, std::process::id()).as_bytes())
+        .map_err(|e| format!("Failed to answer: {}", e))?;
+    Ok(launch)
+}
+
+fn send(port: u16, request: &str) -> Result<(), String> {
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let mut stream = TcpStream::connect_timeout(&address, CONNECT_TIMEOUT)
+        .map_err(|e| format!("no answer on port {}: {}", port, e))?;
+    stream
+        .set_read_timeout(Some(CONNECT_TIMEOUT))
+        .map_err(|e| format!("Failed to set timeout: {}", e))?;
+    stream
+        .write_all(format!("{}\n{}\n", HANDSHAKE, request).as_bytes())
+        .map_err(|e| format!("Failed to send: {}", e))?;
+
+    // Read the whole reply: closing early would fail the instance's answer
+    let mut reader = BufReader::new(&stream);
+    let mut reply = String::new();
+    let mut pid = String::new();
+    reader
+        .read_line(&mut reply)
+        .and_then(|_| reader.read_line(&mut pid))
+        .map_err(|e| format!("no answer on port {}: {}", port, e))?;
+    if reply.trim_end() == HANDSHAKE {
+        Ok(())
+    } else {
+        Err(format!("port {} is not the app", port))
+    }
+}
+
+fn read_lock(path: &Path) -> Option<LockInfo> {
+    serde_json::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+fn lock_age(path: &Path) -> Option<Duration> {
+    let modified = fs::metadata(path).and_then(|metadata| metadata.modified()).ok()?;
+    SystemTime::now().duration_since(modified).ok()
+}
+
+fn remove_stale(path: &Path) {
+    if let Err(e) = fs::remove_file(path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            warn!("[INSTANCE] Failed to remove {}: {}", path.display(), e);
+            thread::sleep(RETRY_DELAY);
+        }
+    }
+}
@@ -0,0 +1,752 @@
+/*!
+ * STOCK LEDGER ENGINE
+ * `stock_movements` is the single source of truth for stock. Running
+ * balances are recomputed per product in chronological order (date,
+ * openings first, then insertion order) and compared with the redundant copies the frontend keeps:
+ * `products.current_stock`, `products.stock_quantity` and each movement's
+ * `stock_before`/`stock_after`.
+ *
+ * Products are created with their opening stock in `current_stock` and no
+ * movement, so the opening balance is taken from an explicit 'initial'
+ * movement when there is one, else from the `stock_before` of the earliest
+ * movement. The frontend writes only `previous_stock`/`new_stock`, so those
+ * stand in wherever `stock_before`/`stock_after` are blank. A rebuild records
+ * that opening as an 'initial' movement so the ledger no longer depends on
+ * any stored balance.
+ *
+ * Once a closed year has been moved to its archive, a product's live
+ * movements start at that year's opening marker. The stock history replays
+ * the archived movements as well.
+ */
+
+use std::collections::BTreeMap;
+
+use log::info;
+use rusqlite::{params, Connection, Transaction, TransactionBehavior};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::archive::attach_archives;
+use crate::audit::{self, AuditEvent};
+use crate::database::{has_column, open_connection};
+use crate::fiscal_year::{OPENING_FIRST_SQL, OPENING_REFERENCE_PREFIX};
+use crate::quantity::{
+    format_movement_quantity, format_stock_text, parse_movement_quantity, parse_stock_text, set_movement_bases,
+    set_product_stock, to_movement_number, value_to_text,
+};
+
+/// Signed effect of a movement on stock. IN/OUT rows may store the quantity
+/// with or without a sign; adjustments and transfers carry their own sign.
+pub fn movement_effect(movement_type: &str, quantity: i64) -> i64 {
+    match movement_type {
+        "out" | "waste" | "damage" => -quantity.abs(),
+        "in" | "return" => quantity.abs(),
+        _ => quantity,
+    }
+}
+
+/// One product whose stored stock disagrees with its movements
+#[derive(Debug, Serialize)]
+pub struct StockDivergence {
+    pub product_id: i64,
+    pub product_name: String,
+    pub unit_type: String,
+    pub recorded_stock: String,
+    pub computed_stock: String,
+    pub difference: String,
+    pub recorded_stock_quantity: Option<f64>,
+    pub movement_count: usize,
+    pub mismatched_movements: usize,
+    pub missing_opening: bool,
+}
+
+/// A product whose movements cannot all be parsed; it is never rebuilt
+#[derive(Debug, Serialize)]
+pub struct UnreadableStock {
+    pub product_id: i64,
+    pub product_name: String,
+    pub movement_id: Option<i64>,
+    pub raw_value: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StockConsistencyReport {
+    pub products_checked: usize,
+    pub consistent_products: usize,
+    pub divergent: Vec<StockDivergence>,
+    pub unreadable: Vec<UnreadableStock>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StockRebuildResult {
+    pub products_rebuilt: usize,
+    pub movements_corrected: usize,
+    pub openings_recorded: usize,
+    pub changes: Vec<StockDivergence>,
+    pub skipped: Vec<UnreadableStock>,
+}
+
+struct ProductRow {
+    id: i64,
+    name: String,
+    unit_type: String,
+    unit: String,
+    current_stock: String,
+    stock_quantity: Option<f64>,
+}
+
+struct MovementRow {
+    id: i64,
+    movement_type: String,
+    reference_type: Option<String>,
+    reference_number: Option<String>,
+    date: String,
+    quantity: String,
+    quantity_base: Option<i64>,
+    stock_before: String,
+    stock_after: String,
+}
+
+/// Movements of one product replayed from the opening balance
+struct ProductLedger {
+    product: ProductRow,
+    opening: i64,
+    has_initial: bool,
+    /// The ledger starts at a year opening marker (earlier years moved to archives)
+    opened_by_marker: bool,
+    recorded: Option<i64>,
+    /// (movement, recomputed before, recomputed after)
+    balances: Vec<(MovementRow, i64, i64)>,
+}
+
+impl ProductLedger {
+    fn computed(&self) -> i64 {
+        self.balances.last().map(|(_, _, after)| *after).unwrap_or(self.opening)
+    }
+
+    fn needs_opening(&self) -> bool {
+        !self.has_initial && !self.opened_by_marker && self.opening != 0 && !self.balances.is_empty()
+    }
+
+    fn mismatched_movements(&self) -> usize {
+        let unit_type = &self.product.unit_type;
+        self.balances
+            .iter()
+            .filter(|(movement, before, after)| {
+                parse_movement_quantity(&movement.stock_before, unit_type) != Some(*before)
+                    || parse_movement_quantity(&movement.stock_after, unit_type) != Some(*after)
+            })
+            .count()
+    }
+
+    fn stock_quantity_matches(&self) -> bool {
+        let expected = to_movement_number(self.computed(), &self.product.unit_type);
+        self.product
+            .stock_quantity
+            .is_some_and(|stored| (stored - expected).abs() < 0.0005)
+    }
+
+    fn divergence(&self) -> Option<StockDivergence> {
+        let computed = self.computed();
+        let mismatched = self.mismatched_movements();
+        if self.recorded == Some(computed) && mismatched == 0 && self.stock_quantity_matches() && !self.needs_opening() {
+            return None;
+        }
+        let unit_type = &self.product.unit_type;
+        Some(StockDivergence {
+            product_id: self.product.id,
+            product_name: self.product.name.clone(),
+            unit_type: unit_type.clone(),
+            recorded_stock: self.product.current_stock.clone(),
+            computed_stock: format_stock_text(computed, unit_type),
+            difference: match self.recorded {
+                Some(recorded) => format_stock_text(computed - recorded, unit_type),
+                None => "unreadable".to_string(),
+            },
+            recorded_stock_quantity: self.product.stock_quantity,
+            movement_count: self.balances.len(),
+            mismatched_movements: mismatched,
+            missing_opening: self.needs_opening(),
+        })
+    }
+}
+
+fn load_products(conn: &Connection, product_id: Option<i64>) -> Result<Vec<ProductRow>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, name, COALESCE(unit_type, 'kg-grams'), COALESCE(unit, 'kg'), current_stock, stock_quantity
+             FROM products
+             WHERE ?1 IS NULL OR id = ?1
+             ORDER BY id",
+        )
+        .map_err(|e| format!("Failed to query products: {}", e))?;
+    let rows = stmt
+        .query_map([product_id], |row| {
+            Ok(ProductRow {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                unit_type: row.get(2)?,
+                unit: row.get(3)?,
+                current_stock: row.get::<_, rusqlite::types::Value>(4).map(value_to_text)?,
+                stock_quantity: row.get(5)?,
+            })
+        })
+        .map_err(|e| format!("Failed to read products: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read product: {}", e))
+}
+
+/// Balance columns with the frontend's `previous_stock`/`new_stock` standing in when blank
+const STOCK_BEFORE_SQL: &str =
+    "CASE WHEN TRIM(COALESCE(stock_before, '')) = '' THEN previous_stock ELSE stock_before END";
+const STOCK_AFTER_SQL: &str = "CASE WHEN TRIM(COALESCE(stock_after, '')) = '' THEN new_stock ELSE stock_after END";
+
+/// All movements of `table` (the live table or the `all_stock_movements`
+/// view over the archives) grouped by product in replay order
+fn load_movements(
+    conn: &Connection,
+    table: &str,
+    product_id: Option<i64>,
+) -> Result<BTreeMap<i64, Vec<MovementRow>>, String> {
+    let base_column = if has_column(conn, table, "quantity_base")
+        .map_err(|e| format!("Failed to read stock movement columns: {}", e))?
+    {
+        "quantity_base"
+    } else {
+        "NULL"
+    };
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT product_id, id, movement_type, reference_type, reference_number, date, quantity, {},
+                    {}, {}
+             FROM {}
+             WHERE ?1 IS NULL OR product_id = ?1
+             ORDER BY product_id, date, CASE WHEN reference_type = 'initial' THEN 0 ELSE 1 END, {}, id",
+            base_column, STOCK_BEFORE_SQL, STOCK_AFTER_SQL, table, OPENING_FIRST_SQL
+        ))
+        .map_err(|e| format!("Failed to query stock movements: {}", e))?;
+    let rows = stmt
+        .query_map([product_id], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                MovementRow {
+                    id: row.get(1)?,
+                    movement_type: row.get(2)?,
+                    reference_type: row.get(3)?,
+                    reference_number: row.get(4)?,
+                    date: row.get(5)?,
+                    quantity: row.get::<_, rusqlite::types::Value>(6).map(value_to_text)?,
+                    quantity_base: row.get(7)?,
+                    stock_before: row.get::<_, rusqlite::types::Value>(8).map(value_to_text)?,
+                    stock_after: row.get::<_, rusqlite::types::Value>(9).map(value_to_text)?,
+                },
+            ))
+        })
+        .map_err(|e| format!("Failed to read stock movements: {}", e))?;
+
+    let mut grouped: BTreeMap<i64, Vec<MovementRow>> = BTreeMap::new();
+    for row in rows {
+        let (product_id, movement) = row.map_err(|e| format!("Failed to read stock movement: {}", e))?;
+        grouped.entry(product_id).or_default().push(movement);
+    }
+    Ok(grouped)
+}
+
+/// Replay a product's movements. Fails with the first value that cannot be parsed.
+fn replay(product: ProductRow, movements: Vec<MovementRow>) -> Result<ProductLedger, UnreadableStock> {
+    let unreadable = |product: &ProductRow, movement_id: Option<i64>, raw_value: &str| UnreadableStock {
+        product_id: product.id,
+        product_name: product.name.clone(),
+        movement_id,
+        raw_value: raw_value.to_string(),
+    };
+
+    let recorded = parse_stock_text(&product.current_stock, &product.unit_type);
+    let has_initial = movements
+        .iter()
+        .any(|movement| movement.reference_type.as_deref() == Some("initial"));
+    let opened_by_marker = movements.first().is_some_and(|first| {
+        first
+            .reference_number
+            .as_deref()
+            .is_some_and(|reference| reference.starts_with(OPENING_REFERENCE_PREFIX))
+    });
+    let opening = match movements.first() {
+        None => recorded.ok_or_else(|| unreadable(&product, None, &product.current_stock))?,
+        Some(_) if has_initial => 0,
+        Some(first) if first.stock_before.trim().is_empty() => 0,
+        Some(first) => parse_movement_quantity(&first.stock_before, &product.unit_type)
+            .ok_or_else(|| unreadable(&product, Some(first.id), &first.stock_before))?,
+    };
+
+    let mut running = opening;
+    let mut balances = Vec::with_capacity(movements.len());
+    for movement in movements {
+        let quantity = match movement.quantity_base {
+            Some(base) => base,
+            None => parse_movement_quantity(&movement.quantity, &product.unit_type)
+                .ok_or_else(|| unreadable(&product, Some(movement.id), &movement.quantity))?,
+        };
+        let before = running;
+        running += movement_effect(&movement.movement_type, quantity);
+        balances.push((movement, before, running));
+    }
+
+    Ok(ProductLedger {
+        product,
+        opening,
+        has_initial,
+        opened_by_marker,
+        recorded,
+        balances,
+    })
+}
+
+/// Replay every product (or one) and split the results into ledgers and unreadable products
+fn replay_all(conn: &Connection, product_id: Option<i64>) -> Result<(Vec<ProductLedger>, Vec<UnreadableStock>), String> {
+    let products = load_products(conn, product_id)?;
+    let mut movements = load_movements(conn, "stock_movements", product_id)?;
+    let mut ledgers = Vec::with_capacity(products.len());
+    let mut unreadable = Vec::new();
+
+    for product in products {
+        let product_movements = movements.remove(&product.id).unwrap_or_default();
+        match replay(product, product_movements) {
+            Ok(ledger) => ledgers.push(ledger),
+            Err(issue) => unreadable.push(issue),
+        }
+    }
+    Ok((ledgers, unreadable))
+}
+
+/// Compare stored stock with the movement ledger without changing anything
+pub fn check_stock(conn: &Connection, product_id: Option<i64>) -> Result<StockConsistencyReport, String> {
+    let (ledgers, unreadable) = replay_all(conn, product_id)?;
+    let divergent: Vec<StockDivergence> = ledgers.iter().filter_map(ProductLedger::divergence).collect();
+
+    Ok(StockConsistencyReport {
+        products_checked: ledgers.len() + unreadable.len(),
+        consistent_products: ledgers.len() - divergent.len(),
+        divergent,
+        unreadable,
+    })
+}
+
+/// A product's stock at the end of a day, replayed from its movements
+#[derive(Debug)]
+pub struct StockAsOf {
+    pub product_id: i64,
+    pub product_name: String,
+    pub unit_type: String,
+    pub base: i64,
+}
+
+/// Replay every product's movements up to and including `date`.
+/// Products with unparseable movements are returned separately.
+pub fn stock_as_of(conn: &Connection, date: &str) -> Result<(Vec<StockAsOf>, Vec<UnreadableStock>), String> {
+    let (ledgers, unreadable) = replay_all(conn, None)?;
+    let stocks = ledgers
+        .into_iter()
+        .map(|ledger| {
+            let base = ledger
+                .balances
+                .iter()
+                .take_while(|(movement, _, _)| movement.date.get(..10).unwrap_or(&movement.date) <= date)
+                .last()
+                .map(|(_, _, after)| *after)
+                .unwrap_or(ledger.opening);
+            StockAsOf {
+                product_id: ledger.product.id,
+                product_name: ledger.product.name,
+                unit_type: ledger.product.unit_type,
+                base,
+            }
+        })
+        .collect();
+    Ok((stocks, unreadable))
+}
+
+/// A movement on a product's stock history with the replayed balances around it
+#[derive(Debug, Serialize)]
+pub struct StockHistoryLine {
+    pub movement_id: i64,
+    pub date: String,
+    pub movement_type: String,
+    pub reference_type: Option<String>,
+    pub reference_number: Option<String>,
+    pub quantity: String,
+    pub stock_before: String,
+    pub stock_after: String,
+    pub archived: bool,
+}
+
+/// A product's movements between two dates, replayed across every archived year
+#[derive(Debug, Serialize)]
+pub struct StockHistory {
+    pub product_id: i64,
+    pub product_name: String,
+    pub unit_type: String,
+    pub opening_stock: String,
+    pub closing_stock: String,
+    pub lines: Vec<StockHistoryLine>,
+    /// Fiscal years whose archives were read
+    pub archives_attached: Vec<String>,
+}
+
+/// Build a product's history on a connection that has the archives attached
+pub fn stock_history(
+    conn: &Connection,
+    product_id: i64,
+    from_date: Option<&str>,
+    to_date: Option<&str>,
+) -> Result<StockHistory, String> {
+    let product = load_products(conn, Some(product_id))?
+        .pop()
+        .ok_or_else(|| format!("Product {} not found", product_id))?;
+    let movements = load_movements(conn, "all_stock_movements", Some(product_id))?
+        .remove(&product_id)
+        .unwrap_or_default();
+    let live: Vec<i64> = conn
+        .prepare("SELECT id FROM main.stock_movements WHERE product_id = ?1")
+        .and_then(|mut stmt| stmt.query_map([product_id], |row| row.get(0))?.collect())
+        .map_err(|e| format!("Failed to read stock movements: {}", e))?;
+    let ledger = replay(product, movements).map_err(|issue| {
+        format!(
+            "Stock history of {} cannot be replayed: unreadable value '{}'",
+            issue.product_name, issue.raw_value
+        )
+    })?;
+
+    let unit_type = ledger.product.unit_type.clone();
+    let day = |movement: &MovementRow| movement.date.get(..10).unwrap_or(&movement.date).to_string();
+    let mut opening = ledger.opening;
+    let mut closing = ledger.opening;
+    let mut lines = Vec::new();
+    for (movement, before, after) in ledger.balances {
+        let date = day(&movement);
+        if from_date.is_some_and(|from_date| date.as_str() < from_date) {
+            opening = after;
+            closing = after;
+            continue;
+        }
+        if to_date.is_some_and(|to_date| date.as_str() > to_date) {
+            break;
+        }
+        closing = after;
+        lines.push(StockHistoryLine {
+            archived: !live.contains(&movement.id),
+            movement_id: movement.id,
+            date: movement.date,
+            movement_type: movement.movement_type,
+            reference_type: movement.reference_type,
+            reference_number: movement.reference_number,
+            quantity: format_movement_quantity((after - before).abs(), &unit_type),
+            stock_before: format_stock_text(before, &unit_type),
+            stock_after: format_stock_text(after, &unit_type),
+        });
+    }
+
+    Ok(StockHistory {
+        product_id,
+        product_name: ledger.product.name,
+        unit_type: unit_type.clone(),
+        opening_stock: format_stock_text(opening, &unit_type),
+        closing_stock: format_stock_text(closing, &unit_type),
+        lines,
+        archives_attached: Vec::new(),
+    })
+}
+
+/// Rewrite stored stock and running balances from the movement ledger.
+/// Products with unparseable movements are skipped and reported.
+pub fn rebuild_stock_in_transaction(
+    tx: &Transaction,
+    product_id: Option<i64>,
+    performed_by: &str,
+) -> Result<StockRebuildResult, String> {
+    let (ledgers, skipped) = replay_all(tx, product_id)?;
+
+    let mut result = StockRebuildResult {
+        products_rebuilt: 0,
+        movements_corrected: 0,
+        openings_recorded: 0,
+        changes: Vec::new(),
+        skipped,
+    };
+
+    for ledger in ledgers {
+        let Some(change) = ledger.divergence() else {
+            continue;
+        };
+        let product = &ledger.product;
+        let unit_type = &product.unit_type;
+
+        if ledger.needs_opening() {
+            record_opening(tx, &ledger, performed_by)?;
+            result.openings_recorded += 1;
+        }
+
+        for (movement, before, after) in &ledger.balances {
+            if parse_movement_quantity(&movement.stock_before, unit_type) == Some(*before)
+                && parse_movement_quantity(&movement.stock_after, unit_type) == Some(*after)
+            {
+                continue;
+            }
+            tx.execute(
+                "UPDATE stock_movements
+                 SET previous_stock = ?1, stock_before = ?1, stock_after = ?2, new_stock = ?2
+                 WHERE id = ?3",
+                params![
+                    format_movement_quantity(*before, unit_type),
+                    format_movement_quantity(*after, unit_type),
+                    movement.id
+                ],
+            )
+            .map_err(|e| format!("Failed to correct stock movement {}: {}", movement.id, e))?;
+            let quantity = movement.quantity_base.unwrap_or_else(|| after - before);
+            set_movement_bases(tx, movement.id, quantity, *before, *after)
+                .map_err(|e| format!("Failed to correct stock movement {}: {}", movement.id, e))?;
+            result.movements_corrected += 1;
+        }
+
+        let computed = ledger.computed();
+        set_product_stock(tx, product.id, computed, unit_type)
+            .map_err(|e| format!("Failed to update stock of {}: {}", product.name, e))?;
+        tx.execute(
+            "UPDATE products SET stock_quantity = ?1 WHERE id = ?2",
+            params![to_movement_number(computed, unit_type), product.id],
+        )
+        .map_err(|e| format!("Failed to update stock of {}: {}", product.name, e))?;
+
+        info!(
+            "[STOCK] {}: {} -> {} ({} movements corrected)",
+            product.name, change.recorded_stock, change.computed_stock, change.mismatched_movements
+        );
+        result.products_rebuilt += 1;
+        result.changes.push(change);
+    }
+
+    info!(
+        "[STOCK] Rebuild by {}: {} products, {} movements corrected, {} openings recorded",
+        performed_by, result.products_rebuilt, result.movements_corrected, result.openings_recorded
+    );
+    Ok(result)
+}
+
+/// Record the opening balance as an explicit 'initial' movement dated with
+/// the product's first movement, so replays no longer rely on stock_before
+fn record_opening(tx: &Transaction, ledger: &ProductLedger, performed_by: &str) -> Result<(), String> {
+    let product = &ledger.product;
+    let first_date = ledger
+        .balances
+        .first()
+        .map(|(movement, _, _)| movement.date.clone())
+        .unwrap_or_default();
+
+    tx.execute(
+        "INSERT INTO stock_movements (
+            product_id, product_name, movement_type, transaction_type, quantity, unit,
+            previous_stock, stock_before, stock_after, new_stock,
+            reason, reference_type, notes, date, time, created_by
+        ) VALUES (?1, ?2, 'adjustment', 'adjustment', ?3, ?4, '0', '0', ?3, ?3,
+            'Opening stock', 'initial', 'Recorded by stock rebuild', ?5, '00:00:00', ?6)",
+        params![
+            product.id,
+            product.name,
+            format_movement_quantity(ledger.opening, &product.unit_type),
+            product.unit,
+            first_date,
+            performed_by
+        ],
+    )
+    .map_err(|e| format!("Failed to record opening stock of {}: {}", product.name, e))?;
+    set_movement_bases(tx, tx.last_insert_rowid(), ledger.opening, 0, ledger.opening)
+        .map_err(|e| format!("Failed to record opening stock of {}: {}", product.name, e))
+}
+
+/// Report products whose stored stock disagrees with their movements
+#[tauri::command]
+pub async fn check_stock_consistency(product_id: Option<i64>) -> Result<StockConsistencyReport, String> {
+    let conn = open_connection()?;
+    let report = check_stock(&conn, product_id)?;
+
+    info!(
+        "[STOCK] Checked {} products: {} divergent, {} unreadable",
+        report.products_checked,
+        report.divergent.len(),
+        report.unreadable.len()
+    );
+    Ok(report)
+}
+
+/// A product's stock movements between two dates, reading archived years as well
+#[tauri::command]
+pub async fn get_stock_history(
+    product_id: i64,
+    from_date: Option<String>,
+    to_date: Option<String>,
+) -> Result<StockHistory, String> {
+    let conn = open_connection()?;
+    let archives_attached = attach_archives(&conn)?;
+    let mut history = stock_history(&conn, product_id, from_date.as_deref(), to_date.as_deref())?;
+    history.archives_attached = archives_attached;
+    Ok(history)
+}
+
+/// Recompute stock from the movement ledger and repair every stored copy
+#[tauri::command]
+pub async fn rebuild_stock(product_id: Option<i64>, performed_by: String) -> Result<StockRebuildResult, String> {
+    let performed_by = match performed_by.trim() {
+        "" => "system".to_string(),
+        user => user.to_string(),
+    };
+
+    let mut conn = open_connection()?;
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let result = rebuild_stock_in_transaction(&tx, product_id, &performed_by)?;
+    let event = AuditEvent::new("rebuild_stock", "product").by(&performed_by);
+    let event = match product_id {
+        Some(product_id) => event.entity(product_id),
+        None => event,
+    };
+    audit::record(&tx, &event.after(json!(&result)))?;
+
+    tx.commit()
+        .map_err(|e| format!("Failed to commit stock rebuild: {}", e))?;
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn movement(id: i64, movement_type: &str, quantity: &str, before: &str, after: &str) -> MovementRow {
+        MovementRow {
+            id,
+            movement_type: movement_type.to_string(),
+            reference_type: None,
+            reference_number: None,
+            date: "2025-01-01".to_string(),
+            quantity: quantity.to_string(),
+            quantity_base: None,
+            stock_before: before.to_string(),
+            stock_after: after.to_string(),
+        }
+    }
+
+    fn product(current_stock: &str, stock_quantity: f64) -> ProductRow {
+        ProductRow {
+            id: 1,
+            name: "Steel".to_string(),
+            unit_type: "kg-grams".to_string(),
+            unit: "kg".to_string(),
+            current_stock: current_stock.to_string(),
+            stock_quantity: Some(stock_quantity),
+        }
+    }
+
+    /// The columns the frontend and this module write
+    fn database() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE products (
+                id INTEGER PRIMARY KEY, name TEXT NOT NULL, unit_type TEXT, unit TEXT,
+                current_stock TEXT NOT NULL DEFAULT '0', stock_quantity REAL DEFAULT 0, updated_at TEXT
+            );
+            CREATE TABLE stock_movements (
+                id INTEGER PRIMARY KEY, product_id INTEGER NOT NULL, product_name TEXT NOT NULL,
+                movement_type TEXT NOT NULL, transaction_type TEXT, quantity TEXT NOT NULL DEFAULT '0',
+                unit TEXT NOT NULL DEFAULT 'kg', previous_stock TEXT NOT NULL DEFAULT '',
+                stock_before TEXT NOT NULL DEFAULT '', stock_after TEXT NOT NULL DEFAULT '',
+                new_stock TEXT NOT NULL DEFAULT '', reason TEXT NOT NULL DEFAULT '', reference_type TEXT,
+                reference_id INTEGER, reference_number TEXT, notes TEXT, date TEXT NOT NULL, time TEXT NOT NULL,
+                created_by TEXT NOT NULL DEFAULT 'system'
+            );",
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn movement_effect_follows_type() {
+        assert_eq!(movement_effect("out", 500), -500);
+        assert_eq!(movement_effect("out", -500), -500);
+        assert_eq!(movement_effect("return", -500), 500);
+        assert_eq!(movement_effect("adjustment", -500), -500);
+    }
+
+    #[test]
+    fn replay_opens_from_first_stock_before() {
+        let movements = vec![
+            movement(1, "out", "1500", "12000", "10500"),
+            movement(2, "in", "2000", "10500", "12500"),
+        ];
+        let ledger = replay(product("12-500", 12_500.0), movements).unwrap();
+        assert_eq!(ledger.opening, 12_000);
+        assert_eq!(ledger.computed(), 12_500);
+        assert_eq!(ledger.mismatched_movements(), 0);
+        assert!(ledger.needs_opening());
+
+        // An explicit opening replaces the stored balance
+        let mut opening = movement(1, "adjustment", "12000", "0", "12000");
+        opening.reference_type = Some("initial".to_string());
+        let ledger = replay(
+            product("10-500", 10_500.0),
+            vec![opening, movement(2, "out", "1500", "12000", "10500")],
+        )
+        .unwrap();
+        assert_eq!((ledger.opening, ledger.computed()), (0, 10_500));
+        assert!(ledger.divergence().is_none());
+    }
+
+    #[test]
+    fn replay_reports_unreadable_quantities() {
+        let issue = replay(product("12", 12_000.0), vec![movement(7, "out", "lots", "12000", "")])
+            .err()
+            .unwrap();
+        assert_eq!((issue.movement_id, issue.raw_value.as_str()), (Some(7), "lots"));
+    }
+
+    #[test]
+    fn frontend_movements_keep_the_opening_stock() {
+        let mut conn = database();
+        // Created with 12 kg and sold 1.5 kg, as createStockMovement writes it (grams, numeric balances)
+        conn.execute_batch(
+            "INSERT INTO products (id, name, unit_type, unit, current_stock, stock_quantity)
+                VALUES (1, 'Steel', 'kg-grams', 'kg', '10-500', 10500);
+             INSERT INTO stock_movements (product_id, product_name, movement_type, quantity, previous_stock, new_stock, date, time)
+                VALUES (1, 'Steel', 'out', '-1500', 12000, 10500, '2025-01-01', '10:00:00');",
+        )
+        .unwrap();
+
+        let report = check_stock(&conn, None).unwrap();
+        assert_eq!(report.divergent.len(), 1);
+        let divergence = &report.divergent[0];
+        assert_eq!(divergence.mismatched_movements, 0);
+        assert_eq!(divergence.computed_stock, divergence.recorded_stock);
+        assert!(divergence.missing_opening);
+
+        let tx = conn.transaction().unwrap();
+        let result = rebuild_stock_in_transaction(&tx, None, "test").unwrap();
+        assert_eq!((result.openings_recorded, result.movements_corrected), (1, 0));
+        let opening: String = tx
+            .query_row(
+                "SELECT quantity FROM stock_movements WHERE reference_type = 'initial'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(opening, "12000");
+        let stock: String = tx
+            .query_row("SELECT current_stock FROM products", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(stock, "10-500");
+        tx.commit().unwrap();
+
+        assert!(check_stock(&conn, None).unwrap().divergent.is_empty());
+    }
+}
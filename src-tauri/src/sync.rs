@@ -0,0 +1,2450 @@
+/*!
+ * MULTI-TERMINAL LAN SYNC
+ * Every counter keeps its own copy of the books and exchanges row changes
+ * with the other terminals on the local network.
+ *
+ * Capture: the frontend writes through its own connection, so changes are
+ * not recorded as they happen. Each round diffs the live database against a
+ * baseline copy of what was last synced (`store.db.sync-baseline`) with the
+ * SQLite session extension, queues the changed rows in `sync_outbox` and
+ * brings the baseline up to date. A row is known across terminals by the
+ * terminal that created it and its id there; `sync_row_map` maps those keys
+ * to local ids, and id columns that refer to other rows travel as keys.
+ *
+ * Exchange: each terminal serves its own outbox over TCP (one JSON request
+ * line, one JSON response line) and pulls every peer's records after the
+ * last one it received. A terminal that pulls is added to the peers of the
+ * terminal it pulled from, so every terminal ends up pulling from every
+ * other one.
+ *
+ * Authentication: the group code is a 256-bit secret that never crosses the
+ * network. The serving terminal opens with a random challenge; the request
+ * and the response each carry an HMAC-SHA256 of the challenge, the caller's
+ * nonce and the line, keyed with the group code, so neither side answers or
+ * believes a terminal outside the group and recorded lines cannot be
+ * replayed. An address that fails the check repeatedly is refused for a
+ * while. The short codes of earlier versions are refused: renew the code on
+ * one terminal and enter it on the others.
+ *
+ * Conflicts: whole rows, last writer wins by capture time with ties broken
+ * by terminal id, so every terminal keeps the same row. A delete always
+ * wins and is never undone by a late update. Stock and balances are never
+ * taken from a peer: after an import the affected products, customers and
+ * vendors are rebuilt from their ledgers here. Derived postings (the
+ * journal, vendor payables) and terminal-bound state (day and year
+ * closings, archives) are not synced at all. Records that cannot be applied
+ * yet (a row they refer to has not arrived, a closed day, a duplicate bill
+ * number) are kept as rejections and retried every round.
+ *
+ * Enabling sync starts from the books as they are. Another terminal joins
+ * by downloading a snapshot of a synced terminal's database with the group
+ * code shown there; the snapshot replaces its database at the next start.
+ */
+
+use std::collections::{BTreeSet, HashMap};
+use std::ffi::CString;
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::os::raw::{c_int, c_void};
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::sync::{Mutex, OnceLock, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use hmac::{Hmac, Mac};
+use log::{error, info, warn};
+use rusqlite::types::Value;
+use rusqlite::{ffi, params, params_from_iter, Connection, OptionalExtension, Transaction, TransactionBehavior};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value as JsonValue};
+use sha2::{Digest, Sha256};
+
+use crate::audit::{self, AuditEvent};
+use crate::customer_balance;
+use crate::database::{get_db_path, open_connection, open_connection_at};
+use crate::journal;
+use crate::quantity;
+use crate::recycle_bin;
+use crate::stock_engine;
+use crate::vendor_payables::{sync_vendor_in_transaction, VendorSyncResult};
+
+pub const DEFAULT_PORT: u16 = 47820;
+
+/// How often the background loop runs a round
+const SYNC_INTERVAL: Duration = Duration::from_secs(10);
+const NETWORK_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Random bytes in a group code; it is shown and entered as twice as many hex digits
+const GROUP_CODE_BYTES: usize = 32;
+
+/// Failed group code checks from one address before it is refused for the rest of the window
+const MAX_FAILED_ATTEMPTS: u32 = 5;
+const FAILURE_WINDOW: Duration = Duration::from_secs(600);
+/// Pause before answering a failed check, so codes cannot be tried quickly
+const FAILURE_DELAY: Duration = Duration::from_secs(1);
+
+/// Failed checks per address: how many, and when the first of the window was
+static FAILED_ATTEMPTS: OnceLock<Mutex<HashMap<IpAddr, (u32, Instant)>>> = OnceLock::new();
+
+/// Records per pull request, and pull requests per peer in one round
+const PULL_LIMIT: usize = 500;
+const MAX_PULLS: usize = 20;
+
+const BASELINE_ALIAS: &str = "sync_baseline";
+const BASELINE_SUFFIX: &str = ".sync-baseline";
+const SNAPSHOT_SUFFIX: &str = ".sync-snapshot";
+const JOIN_SUFFIX: &str = ".sync-join";
+const JOIN_REQUEST_SUFFIX: &str = ".sync-join.json";
+const PRE_JOIN_SUFFIX: &str = ".pre-join-backup";
+
+/// Performer recorded by the rebuilds an import triggers
+const SYNC_USER: &str = "sync";
+
+/// Tables that stay on each terminal: derived postings rebuilt locally,
+/// closings and archives bound to this terminal, and machine state
+const LOCAL_TABLES: &[&str] = &[
+    "journal_entries",
+    "journal_lines",
+    "gl_accounts",
+    "vendor_ledger_entries",
+    "vendor_payment_allocations",
+    "daily_closings",
+    "daily_closing_balances",
+    "daily_closing_events",
+    "fiscal_years",
+    "fiscal_year_balances",
+    "archive_moves",
+    "money_conversion_report",
+    "quantity_conversion_issues",
+    "staff_sessions",
+    "notifications",
+    "app_info",
+    "app_metadata",
+    "audit_chain",
+    "audit_pending",
+    "audit_actor",
+    "recycle_bin_state",
+    "import_profiles",
+    "import_runs",
+];
+const LOCAL_PREFIXES: &[&str] = &["sqlite_", "sync_", "search_", "api_", "_sqlx"];
+
+/// Columns derived from ledgers; a peer's values are taken for new rows
+/// only and rebuilt here after every import
+const DERIVED_COLUMNS: &[(&str, &[&str])] = &[
+    ("products", &["current_stock", "stock_quantity"]),
+    (
+        "stock_movements",
+        &["previous_stock", "stock_before", "stock_after", "new_stock"],
+    ),
+    ("customers", &["balance", "balance_paisa"]),
+    (
+        "customer_ledger_entries",
+        &[
+            "balance_before",
+            "balance_after",
+            "balance_before_paisa",
+            "balance_after_paisa",
+        ],
+    ),
+    ("vendors", &["balance", "balance_paisa"]),
+    ("stock_receiving", &["payment_status"]),
+];
+
+/// A change to nothing but these columns (set by the rebuilds) is not sent
+const TOUCH_COLUMNS: &[&str] = &["updated_at"];
+
+/// Id columns without a declared foreign key: (table, column, referenced table)
+const REFERENCES: &[(&str, &str, &str)] = &[
+    ("products", "supplier_id", "vendors"),
+    ("stock_movements", "customer_id", "customers"),
+    ("stock_movements", "supplier_id", "vendors"),
+    ("stock_movements", "vendor_id", "vendors"),
+    ("stock_receiving", "vendor_id", "vendors"),
+    ("payments", "customer_id", "customers"),
+    ("payments", "vendor_id", "vendors"),
+    ("payments", "invoice_id", "invoices"),
+    ("payments", "payment_channel_id", "payment_channels"),
+    ("ledger_entries", "customer_id", "customers"),
+    ("ledger_entries", "vendor_id", "vendors"),
+    ("ledger_entries", "staff_id", "staff_management"),
+    ("ledger_entries", "payment_channel_id", "payment_channels"),
+    ("ledger_entries", "parent_entry_id", "ledger_entries"),
+    ("customer_ledger_entries", "invoice_id", "invoices"),
+    ("customer_ledger_entries", "payment_id", "payments"),
+    ("vendor_payments", "payment_channel_id", "payment_channels"),
+    ("salary_payments", "payment_channel_id", "payment_channels"),
+    ("business_expenses", "payment_channel_id", "payment_channels"),
+    ("business_expenses", "vendor_id", "vendors"),
+    ("business_income", "payment_channel_id", "payment_channels"),
+    ("business_income", "customer_id", "customers"),
+    ("business_income", "invoice_id", "invoices"),
+    ("return_items", "original_invoice_item_id", "invoice_items"),
+    ("invoice_payments", "payment_id", "payments"),
+    ("invoice_payments", "payment_channel_id", "payment_channels"),
+    ("invoice_cancellations", "invoice_id", "invoices"),
+    ("invoice_cancellations", "customer_id", "customers"),
+];
+
+/// `reference_id` points at the table named by the row's `reference_type`
+const REFERENCE_TYPES: &[(&str, &str)] = &[
+    ("invoice", "invoices"),
+    ("payment", "payments"),
+    ("return", "returns"),
+    ("purchase", "stock_receiving"),
+    ("receiving", "stock_receiving"),
+    ("expense", "business_expenses"),
+    ("income", "business_income"),
+    ("salary", "salary_payments"),
+    ("salary_payment", "salary_payments"),
+];
+
+/// Create the sync bookkeeping tables
+pub fn ensure_schema(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS sync_state (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            node_id TEXT NOT NULL,
+            node_name TEXT NOT NULL,
+            group_code TEXT NOT NULL,
+            port INTEGER NOT NULL,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            last_round_at DATETIME,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE TABLE IF NOT EXISTS sync_peers (
+            address TEXT PRIMARY KEY,
+            node_id TEXT,
+            node_name TEXT,
+            acked_seq INTEGER NOT NULL DEFAULT 0,
+            last_sync_at DATETIME,
+            last_error TEXT,
+            added_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE TABLE IF NOT EXISTS sync_cursors (
+            node_id TEXT PRIMARY KEY,
+            last_seq INTEGER NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS sync_outbox (
+            seq INTEGER PRIMARY KEY AUTOINCREMENT,
+            table_name TEXT NOT NULL,
+            record TEXT NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE TABLE IF NOT EXISTS sync_row_map (
+            table_name TEXT NOT NULL,
+            local_id INTEGER NOT NULL,
+            origin TEXT NOT NULL,
+            origin_id INTEGER NOT NULL,
+            PRIMARY KEY (table_name, local_id),
+            UNIQUE (table_name, origin, origin_id)
+        );
+        CREATE TABLE IF NOT EXISTS sync_row_versions (
+            table_name TEXT NOT NULL,
+            origin TEXT NOT NULL,
+            origin_id INTEGER NOT NULL,
+            changed_at TEXT NOT NULL,
+            changed_by TEXT NOT NULL,
+            deleted INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (table_name, origin, origin_id)
+        );
+        CREATE TABLE IF NOT EXISTS sync_conflicts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            table_name TEXT NOT NULL,
+            origin TEXT NOT NULL,
+            origin_id INTEGER NOT NULL,
+            kept_changed_at TEXT NOT NULL,
+            kept_changed_by TEXT NOT NULL,
+            discarded_changed_at TEXT NOT NULL,
+            discarded_changed_by TEXT NOT NULL,
+            resolved_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE TABLE IF NOT EXISTS sync_rejections (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            table_name TEXT NOT NULL,
+            origin TEXT NOT NULL,
+            origin_id INTEGER NOT NULL,
+            record TEXT NOT NULL,
+            reason TEXT NOT NULL,
+            attempts INTEGER NOT NULL DEFAULT 1,
+            rejected_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            last_attempt_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );",
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordOp {
+    Upsert,
+    Delete,
+}
+
+/// One row change as it travels between terminals
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncRecord {
+    pub table: String,
+    /// Terminal that created the row, and the row's id there
+    pub origin: String,
+    pub origin_id: i64,
+    pub op: RecordOp,
+    /// UTC capture time and capturing terminal: the row's version
+    pub changed_at: String,
+    pub changed_by: String,
+    /// Every column but `id`; ids of other rows as `{"ref": RowKey}`, blobs as `{"blob": hex}`
+    #[serde(default)]
+    pub values: Map<String, JsonValue>,
+}
+
+/// A row by the terminal that created it
+#[derive(Debug, Serialize, Deserialize)]
+struct RowKey {
+    table: String,
+    origin: String,
+    id: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum SyncRequest {
+    Pull {
+        node_id: String,
+        node_name: String,
+        port: u16,
+        after: i64,
+    },
+    Snapshot,
+}
+
+/// First line from the serving terminal
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Hello {
+    Challenge(String),
+    /// Not serving this caller now: sync is off, or the address is locked out
+    Refused(String),
+}
+
+/// A request or response line as JSON text with its proof of the group code
+#[derive(Debug, Serialize, Deserialize)]
+struct Envelope {
+    nonce: String,
+    body: String,
+    proof: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum SyncResponse {
+    Records {
+        node_id: String,
+        node_name: String,
+        records: Vec<SyncRecord>,
+        last_seq: i64,
+        more: bool,
+    },
+    /// Followed on the connection by `bytes` bytes of database file
+    Snapshot {
+        node_id: String,
+        node_name: String,
+        bytes: u64,
+        sha256: String,
+    },
+    Error {
+        message: String,
+    },
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncPeer {
+    pub address: String,
+    pub node_id: Option<String>,
+    pub node_name: Option<String>,
+    pub last_sync_at: Option<String>,
+    pub last_error: Option<String>,
+    /// Our outbox records this peer has pulled
+    pub acked_seq: i64,
+    /// Its records we have pulled
+    pub received_seq: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncConflict {
+    pub table_name: String,
+    pub origin: String,
+    pub origin_id: i64,
+    pub kept_changed_at: String,
+    pub kept_changed_by: String,
+    pub discarded_changed_at: String,
+    pub discarded_changed_by: String,
+    pub resolved_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncRejection {
+    pub id: i64,
+    pub table_name: String,
+    pub origin: String,
+    pub origin_id: i64,
+    pub reason: String,
+    pub attempts: i64,
+    pub rejected_at: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct SyncStatus {
+    pub enabled: bool,
+    pub node_id: Option<String>,
+    pub node_name: Option<String>,
+    pub group_code: Option<String>,
+    pub port: u16,
+    pub last_round_at: Option<String>,
+    pub peers: Vec<SyncPeer>,
+    /// Captured changes some peer has not pulled yet
+    pub pending_outbox: i64,
+    pub conflicts_resolved: i64,
+    pub recent_conflicts: Vec<SyncConflict>,
+    pub rejections: Vec<SyncRejection>,
+    /// A downloaded snapshot replaces this database at the next start
+    pub join_pending: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PeerSyncResult {
+    pub address: String,
+    pub node_name: Option<String>,
+    pub received: usize,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct SyncRoundResult {
+    pub captured: usize,
+    pub received: usize,
+    pub applied: usize,
+    /// Incoming changes discarded because a later version of the row won
+    pub conflicts: usize,
+    /// Records waiting in the rejection list after this round
+    pub rejected: usize,
+    /// Rejected records that applied this time
+    pub recovered: usize,
+    pub peers: Vec<PeerSyncResult>,
+}
+
+struct SyncState {
+    node_id: String,
+    node_name: String,
+    group_code: String,
+    port: u16,
+    enabled: bool,
+}
+
+/// A synced table as it exists in this database
+struct SyncTable {
+    name: String,
+    columns: Vec<String>,
+    types: Vec<String>,
+    /// Id column -> referenced table; `None` for `reference_id` (table named by `reference_type`)
+    references: HashMap<String, Option<String>>,
+    derived: &'static [&'static str],
+}
+
+impl SyncTable {
+    fn column_index(&self, column: &str) -> Option<usize> {
+        self.columns.iter().position(|name| name == column)
+    }
+
+    fn is_derived(&self, column: &str) -> bool {
+        self.derived.contains(&column)
+    }
+
+    /// A quantity base shadow, cached per terminal and never sent
+    fn is_cache(&self, column: &str) -> bool {
+        quantity::is_base_shadow(&self.name, column)
+    }
+}
+
+struct Schema {
+    /// Referenced tables before the tables referring to them
+    tables: Vec<SyncTable>,
+}
+
+impl Schema {
+    fn table(&self, name: &str) -> Option<&SyncTable> {
+        self.tables.iter().find(|table| table.name == name)
+    }
+}
+
+enum Outcome {
+    Applied,
+    Skipped,
+    /// A later version of the row won
+    Conflict,
+}
+
+/// Rows an import touched, to rebuild their derived values
+#[derive(Default)]
+struct Affected {
+    products: BTreeSet<i64>,
+    customers: BTreeSet<i64>,
+    vendors: BTreeSet<i64>,
+    any: bool,
+}
+
+/// Records pulled from one peer
+struct Batch {
+    address: String,
+    node_id: String,
+    node_name: String,
+    records: Vec<SyncRecord>,
+    last_seq: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PendingJoin {
+    address: String,
+    node_name: String,
+}
+
+fn sibling(db_path: &Path, suffix: &str) -> PathBuf {
+    let mut name = db_path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    db_path.with_file_name(name)
+}
+
+fn is_local(table: &str) -> bool {
+    LOCAL_TABLES.contains(&table) || LOCAL_PREFIXES.iter().any(|prefix| table.starts_with(prefix))
+}
+
+/// `host` alone means the default port
+fn peer_address(address: &str) -> String {
+    let address = address.trim();
+    if address.parse::<SocketAddr>().is_ok() || address.contains(':') {
+        address.to_string()
+    } else {
+        format!("{}:{}", address, DEFAULT_PORT)
+    }
+}
+
+fn now_utc(conn: &Connection) -> rusqlite::Result<String> {
+    conn.query_row("SELECT strftime('%Y-%m-%d %H:%M:%f', 'now')", [], |row| row.get(0))
+}
+
+fn load_state(conn: &Connection) -> Result<Option<SyncState>, String> {
+    conn.query_row(
+        "SELECT node_id, node_name, group_code, port, enabled FROM sync_state WHERE id = 1",
+        [],
+        |row| {
+            Ok(SyncState {
+                node_id: row.get(0)?,
+                node_name: row.get(1)?,
+                group_code: row.get(2)?,
+                port: row.get(3)?,
+                enabled: row.get(4)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| format!("Failed to read sync settings: {}", e))
+}
+
+fn enabled_state(conn: &Connection) -> Result<SyncState, String> {
+    match load_state(conn)? {
+        Some(state) if state.enabled => Ok(state),
+        _ => Err("Sync is not enabled on this terminal".to_string()),
+    }
+}
+
+/// Every table with an integer `id` primary key that is not kept per terminal
+fn load_schema(conn: &Connection) -> Result<Schema, String> {
+    let read = || -> rusqlite::Result<Vec<SyncTable>> {
+        let mut stmt = conn.prepare(
+            "SELECT name FROM main.sqlite_master
+             WHERE type = 'table' AND sql NOT LIKE 'CREATE VIRTUAL%' ORDER BY name",
+        )?;
+        let names = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut tables = Vec::new();
+        for name in names.into_iter().filter(|name| !is_local(name)) {
+            let mut columns = Vec::new();
+            let mut types = Vec::new();
+            let mut keys = Vec::new();
+            let mut stmt = conn.prepare(&format!("PRAGMA main.table_info(\"{}\")", name))?;
+            let mut rows = stmt.query([])?;
+            while let Some(row) = rows.next()? {
+                let column: String = row.get(1)?;
+                if row.get::<_, i64>(5)? > 0 {
+                    keys.push(column.clone());
+                }
+                types.push(row.get(2)?);
+                columns.push(column);
+            }
+            if keys != ["id"] {
+                continue;
+            }
+
+            let mut references = HashMap::new();
+            let mut stmt = conn.prepare(&format!("PRAGMA main.foreign_key_list(\"{}\")", name))?;
+            let declared = stmt
+                .query_map([], |row| Ok((row.get::<_, String>(3)?, row.get::<_, String>(2)?)))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            for (column, target) in declared {
+                references.insert(column, Some(target));
+            }
+            for (_, column, target) in REFERENCES.iter().filter(|(table, _, _)| *table == name) {
+                if columns.iter().any(|name| name == column) {
+                    references.insert(column.to_string(), Some(target.to_string()));
+                }
+            }
+            if columns.iter().any(|c| c == "reference_id") && columns.iter().any(|c| c == "reference_type") {
+                references.insert("reference_id".to_string(), None);
+            }
+
+            let derived = DERIVED_COLUMNS
+                .iter()
+                .find(|(table, _)| *table == name)
+                .map_or(&[][..], |(_, columns)| *columns);
+            tables.push(SyncTable {
+                name,
+                columns,
+                types,
+                references,
+                derived,
+            });
+        }
+        Ok(tables)
+    };
+    let mut remaining = read().map_err(|e| format!("Failed to read the synced tables: {}", e))?;
+
+    let mut tables = Vec::new();
+    while !remaining.is_empty() {
+        let waiting: Vec<String> = remaining.iter().map(|table| table.name.clone()).collect();
+        let (ready, blocked): (Vec<_>, Vec<_>) = remaining.into_iter().partition(|table| {
+            !table
+                .references
+                .values()
+                .flatten()
+                .any(|target| *target != table.name && waiting.contains(target))
+        });
+        if ready.is_empty() {
+            // A reference cycle: keep the rest in name order
+            tables.extend(blocked);
+            break;
+        }
+        tables.extend(ready);
+        remaining = blocked;
+    }
+    Ok(Schema { tables })
+}
+
+fn attach_baseline(conn: &Connection, db_path: &Path) -> Result<(), String> {
+    let attached: bool = conn
+        .query_row(
+            "SELECT EXISTS (SELECT 1 FROM pragma_database_list WHERE name = ?1)",
+            [BASELINE_ALIAS],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to list attached databases: {}", e))?;
+    if attached {
+        return Ok(());
+    }
+    let path = sibling(db_path, BASELINE_SUFFIX);
+    conn.execute(
+        &format!("ATTACH DATABASE ?1 AS {}", BASELINE_ALIAS),
+        [path.to_string_lossy()],
+    )
+    .map(|_| ())
+    .map_err(|e| format!("Failed to open the sync baseline: {}", e))
+}
+
+fn baseline_columns(conn: &Connection, table: &str) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare(&format!("PRAGMA {}.table_info(\"{}\")", BASELINE_ALIAS, table))?;
+    let names = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    names.collect()
+}
+
+fn create_baseline_table(conn: &Connection, table: &SyncTable) -> rusqlite::Result<()> {
+    let columns = table
+        .columns
+        .iter()
+        .zip(&table.types)
+        .map(|(column, kind)| match column.as_str() {
+            "id" => "\"id\" INTEGER PRIMARY KEY".to_string(),
+            _ => format!("\"{}\" {}", column, kind).trim_end().to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    conn.execute_batch(&format!(
+        "CREATE TABLE {alias}.\"{name}\" ({columns});
+         INSERT INTO {alias}.\"{name}\" SELECT * FROM main.\"{name}\";",
+        alias = BASELINE_ALIAS,
+        name = table.name,
+    ))
+}
+
+/// Give the baseline every synced table and column. A table or column the
+/// baseline has not seen yet starts out as it is now, so rows the frontend
+/// seeds on every terminal when it creates a table are not sent around.
+fn prepare_baseline(conn: &Connection, schema: &Schema) -> Result<(), String> {
+    let prepare = |table: &SyncTable| -> rusqlite::Result<()> {
+        let existing = baseline_columns(conn, &table.name)?;
+        if existing.is_empty() {
+            return create_baseline_table(conn, table);
+        }
+        if existing.len() <= table.columns.len() && existing[..] == table.columns[..existing.len()] {
+            for (column, kind) in table.columns.iter().zip(&table.types).skip(existing.len()) {
+                conn.execute_batch(&format!(
+                    "ALTER TABLE {alias}.\"{name}\" ADD COLUMN \"{column}\" {kind};
+                     UPDATE {alias}.\"{name}\"
+                     SET \"{column}\" = (SELECT m.\"{column}\" FROM main.\"{name}\" m WHERE m.id = \"{name}\".id);",
+                    alias = BASELINE_ALIAS,
+                    name = table.name,
+                ))?;
+            }
+            return Ok(());
+        }
+        // Columns dropped or reordered: start the table over
+        conn.execute_batch(&format!("DROP TABLE {}.\"{}\"", BASELINE_ALIAS, table.name))?;
+        create_baseline_table(conn, table)
+    };
+    for table in &schema.tables {
+        prepare(table).map_err(|e| format!("Failed to prepare the sync baseline of {}: {}", table.name, e))?;
+    }
+    Ok(())
+}
+
+/// Ids of the rows that differ between the live table and the baseline,
+/// from a session extension diff
+fn changed_ids(conn: &Connection, table: &SyncTable) -> rusqlite::Result<Vec<i64>> {
+    let id_column = table.column_index("id").unwrap_or_default() as c_int;
+    let table_name =
+        CString::new(table.name.as_str()).map_err(|e| rusqlite::Error::ToSqlConversionFailure(e.into()))?;
+    let baseline = CString::new(BASELINE_ALIAS).map_err(|e| rusqlite::Error::ToSqlConversionFailure(e.into()))?;
+    let failed = |code: c_int, what: &str| {
+        rusqlite::Error::SqliteFailure(
+            ffi::Error::new(code),
+            Some(format!("{} failed for {}", what, table.name)),
+        )
+    };
+
+    // SAFETY: the session and the changeset buffer live only inside this
+    // block, are freed on every path, and the connection outlives both
+    unsafe {
+        let db = conn.handle();
+        let mut session = ptr::null_mut();
+        let code = ffi::sqlite3session_create(db, c"main".as_ptr(), &mut session);
+        if code != ffi::SQLITE_OK {
+            return Err(failed(code, "sqlite3session_create"));
+        }
+        let mut size = 0;
+        let mut buffer = ptr::null_mut();
+        let mut code = ffi::sqlite3session_attach(session, table_name.as_ptr());
+        if code == ffi::SQLITE_OK {
+            let mut message = ptr::null_mut();
+            code = ffi::sqlite3session_diff(session, baseline.as_ptr(), table_name.as_ptr(), &mut message);
+            ffi::sqlite3_free(message as *mut c_void);
+        }
+        if code == ffi::SQLITE_OK {
+            code = ffi::sqlite3session_changeset(session, &mut size, &mut buffer);
+        }
+        ffi::sqlite3session_delete(session);
+        if code != ffi::SQLITE_OK {
+            ffi::sqlite3_free(buffer);
+            return Err(failed(code, "sqlite3session_diff"));
+        }
+
+        let mut ids = Vec::new();
+        let mut iter = ptr::null_mut();
+        let mut code = ffi::sqlite3changeset_start(&mut iter, size, buffer);
+        while code == ffi::SQLITE_OK && ffi::sqlite3changeset_next(iter) == ffi::SQLITE_ROW {
+            let mut name = ptr::null();
+            let (mut columns, mut op, mut indirect) = (0, 0, 0);
+            ffi::sqlite3changeset_op(iter, &mut name, &mut columns, &mut op, &mut indirect);
+            // Only the key is read: a diff leaves unchanged columns unset
+            let mut key = ptr::null_mut();
+            code = if op == ffi::SQLITE_INSERT {
+                ffi::sqlite3changeset_new(iter, id_column, &mut key)
+            } else {
+                ffi::sqlite3changeset_old(iter, id_column, &mut key)
+            };
+            if code == ffi::SQLITE_OK && !key.is_null() && ffi::sqlite3_value_type(key) == ffi::SQLITE_INTEGER {
+                ids.push(ffi::sqlite3_value_int64(key));
+            }
+        }
+        let finalized = ffi::sqlite3changeset_finalize(iter);
+        ffi::sqlite3_free(buffer);
+        match (code, finalized) {
+            (ffi::SQLITE_OK, ffi::SQLITE_OK) => Ok(ids),
+            (ffi::SQLITE_OK, code) | (code, _) => Err(failed(code, "reading the changeset")),
+        }
+    }
+}
+
+fn read_row(conn: &Connection, database: &str, table: &SyncTable, id: i64) -> rusqlite::Result<Option<Vec<Value>>> {
+    conn.query_row(
+        &format!("SELECT * FROM {}.\"{}\" WHERE id = ?1", database, table.name),
+        [id],
+        |row| {
+            (0..table.columns.len())
+                .map(|index| row.get::<_, Value>(index))
+                .collect()
+        },
+    )
+    .optional()
+}
+
+fn copy_to_baseline(conn: &Connection, table: &SyncTable, id: i64) -> rusqlite::Result<()> {
+    conn.execute(
+        &format!("DELETE FROM {}.\"{}\" WHERE id = ?1", BASELINE_ALIAS, table.name),
+        [id],
+    )?;
+    conn.execute(
+        &format!(
+            "INSERT INTO {alias}.\"{name}\" SELECT * FROM main.\"{name}\" WHERE id = ?1",
+            alias = BASELINE_ALIAS,
+            name = table.name
+        ),
+        [id],
+    )?;
+    Ok(())
+}
+
+/// Whether a row changed in anything worth sending
+fn differs(table: &SyncTable, row: &[Value], base: &[Value]) -> bool {
+    table.columns.iter().enumerate().any(|(index, column)| {
+        !table.is_derived(column)
+            && !table.is_cache(column)
+            && !TOUCH_COLUMNS.contains(&column.as_str())
+            && row.get(index) != base.get(index)
+    })
+}
+
+/// Global key of a local row: its mapping, or this terminal's own id
+fn global_key(conn: &Connection, node_id: &str, table: &str, local_id: i64) -> rusqlite::Result<(String, i64)> {
+    let mapped = conn
+        .query_row(
+            "SELECT origin, origin_id FROM sync_row_map WHERE table_name = ?1 AND local_id = ?2",
+            params![table, local_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    Ok(mapped.unwrap_or_else(|| (node_id.to_string(), local_id)))
+}
+
+/// Local id of a row known by its global key, if it exists here
+fn local_id(
+    conn: &Connection,
+    node_id: &str,
+    table: &str,
+    origin: &str,
+    origin_id: i64,
+) -> rusqlite::Result<Option<i64>> {
+    let mapped = conn
+        .query_row(
+            "SELECT local_id FROM sync_row_map WHERE table_name = ?1 AND origin = ?2 AND origin_id = ?3",
+            params![table, origin, origin_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(mapped.or_else(|| (origin == node_id).then_some(origin_id)))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(text: &str) -> Result<Vec<u8>, String> {
+    (0..text.len())
+        .step_by(2)
+        .map(|index| {
+            text.get(index..index + 2)
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| "Unreadable blob value".to_string())
+        })
+        .collect()
+}
+
+/// Lowercase hex of `bytes` random bytes from SQLite's generator, seeded by the system
+fn random_hex(bytes: usize) -> Result<String, String> {
+    Connection::open_in_memory()
+        .and_then(|conn| conn.query_row("SELECT lower(hex(randomblob(?1)))", [bytes as i64], |row| row.get(0)))
+        .map_err(|e| format!("Failed to generate random bytes: {}", e))
+}
+
+/// The HMAC key a group code stands for. Codes from before the codes were
+/// lengthened (8 hex digits) are refused.
+fn group_key(group_code: &str) -> Result<Vec<u8>, String> {
+    let code = group_code.trim();
+    if code.len() != GROUP_CODE_BYTES * 2 || !code.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!(
+            "The sync group code must be {} hex digits; renew it on one terminal and enter the new code on the others",
+            GROUP_CODE_BYTES * 2
+        ));
+    }
+    from_hex(&code.to_ascii_lowercase())
+}
+
+fn proof_mac(key: &[u8], direction: &str, challenge: &str, nonce: &str, body: &str) -> Result<Hmac<Sha256>, String> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).map_err(|e| format!("Unusable group code: {}", e))?;
+    for part in [direction, challenge, nonce] {
+        mac.update(part.as_bytes());
+        mac.update(b"\n");
+    }
+    mac.update(body.as_bytes());
+    Ok(mac)
+}
+
+/// Proof that whoever wrote `body` for this challenge and nonce holds the group code
+fn prove(key: &[u8], direction: &str, challenge: &str, nonce: &str, body: &str) -> Result<String, String> {
+    Ok(to_hex(&proof_mac(key, direction, challenge, nonce, body)?.finalize().into_bytes()))
+}
+
+/// Check a proof in constant time
+fn proof_holds(key: &[u8], direction: &str, challenge: &str, nonce: &str, body: &str, proof: &str) -> bool {
+    match (proof_mac(key, direction, challenge, nonce, body), from_hex(proof)) {
+        (Ok(mac), Ok(proof)) => mac.verify_slice(&proof).is_ok(),
+        _ => false,
+    }
+}
+
+fn value_to_json(value: Value) -> JsonValue {
+    match value {
+        Value::Null => JsonValue::Null,
+        Value::Integer(number) => JsonValue::from(number),
+        Value::Real(number) => serde_json::Number::from_f64(number).map_or(JsonValue::Null, JsonValue::Number),
+        Value::Text(text) => JsonValue::String(text),
+        Value::Blob(bytes) => json!({ "blob": to_hex(&bytes) }),
+    }
+}
+
+/// A row's columns for a record, with ids of synced rows as global keys
+fn encode_row(
+    conn: &Connection,
+    state: &SyncState,
+    schema: &Schema,
+    table: &SyncTable,
+    row: Vec<Value>,
+) -> rusqlite::Result<Map<String, JsonValue>> {
+    let reference_type = table
+        .column_index("reference_type")
+        .and_then(|index| match &row[index] {
+            Value::Text(kind) => Some(kind.clone()),
+            _ => None,
+        });
+
+    let mut values = Map::new();
+    for (column, value) in table.columns.iter().zip(row) {
+        if column == "id" || table.is_cache(column) {
+            continue;
+        }
+        let target = match table.references.get(column) {
+            Some(Some(target)) => Some(target.as_str()),
+            Some(None) => reference_type
+                .as_deref()
+                .and_then(|kind| REFERENCE_TYPES.iter().find(|(name, _)| *name == kind))
+                .map(|(_, target)| *target),
+            None => None,
+        };
+        let encoded = match (target, &value) {
+            (Some(target), Value::Integer(id)) if schema.table(target).is_some() => {
+                let (origin, origin_id) = global_key(conn, &state.node_id, target, *id)?;
+                json!({ "ref": RowKey { table: target.to_string(), origin, id: origin_id } })
+            }
+            _ => value_to_json(value),
+        };
+        values.insert(column.clone(), encoded);
+    }
+    Ok(values)
+}
+
+fn decode_value(conn: &Connection, state: &SyncState, value: &JsonValue) -> Result<Value, String> {
+    Ok(match value {
+        JsonValue::Null => Value::Null,
+        JsonValue::Bool(flag) => Value::Integer(i64::from(*flag)),
+        JsonValue::Number(number) => match number.as_i64() {
+            Some(integer) => Value::Integer(integer),
+            None => Value::Real(number.as_f64().unwrap_or_default()),
+        },
+        JsonValue::String(text) => Value::Text(text.clone()),
+        JsonValue::Object(object) => {
+            if let Some(hex) = object.get("blob").and_then(JsonValue::as_str) {
+                Value::Blob(from_hex(hex)?)
+            } else if let Some(reference) = object.get("ref") {
+                let key: RowKey =
+                    serde_json::from_value(reference.clone()).map_err(|e| format!("Unreadable reference: {}", e))?;
+                let id = local_id(conn, &state.node_id, &key.table, &key.origin, key.id)
+                    .map_err(|e| format!("Failed to look up {} {}/{}: {}", key.table, key.origin, key.id, e))?;
+                match id {
+                    Some(id) => Value::Integer(id),
+                    None => {
+                        return Err(format!(
+                            "The {} row {}/{} it refers to has not arrived yet",
+                            key.table, key.origin, key.id
+                        ))
+                    }
+                }
+            } else {
+                return Err("Unreadable value".to_string());
+            }
+        }
+        JsonValue::Array(_) => return Err("Unreadable value".to_string()),
+    })
+}
+
+fn store_version(conn: &Connection, record: &SyncRecord) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO sync_row_versions (table_name, origin, origin_id, changed_at, changed_by, deleted)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            record.table,
+            record.origin,
+            record.origin_id,
+            record.changed_at,
+            record.changed_by,
+            record.op == RecordOp::Delete
+        ],
+    )?;
+    Ok(())
+}
+
+/// Diff the live tables against the baseline and queue every changed row
+fn capture_in_transaction(tx: &Transaction, state: &SyncState, schema: &Schema) -> Result<usize, String> {
+    prepare_baseline(tx, schema)?;
+    let changed_at = now_utc(tx).map_err(|e| format!("Failed to read the clock: {}", e))?;
+
+    let capture = || -> rusqlite::Result<usize> {
+        let mut records = Vec::new();
+        let mut deleted = Vec::new();
+        for table in &schema.tables {
+            for id in changed_ids(tx, table)? {
+                let Some(row) = read_row(tx, "main", table, id)? else {
+                    deleted.push((table, id));
+                    continue;
+                };
+                let changed = match read_row(tx, BASELINE_ALIAS, table, id)? {
+                    Some(base) => differs(table, &row, &base),
+                    None => true,
+                };
+                if changed {
+                    let (origin, origin_id) = global_key(tx, &state.node_id, &table.name, id)?;
+                    records.push(SyncRecord {
+                        table: table.name.clone(),
+                        origin,
+                        origin_id,
+                        op: RecordOp::Upsert,
+                        changed_at: changed_at.clone(),
+                        changed_by: state.node_id.clone(),
+                        values: encode_row(tx, state, schema, table, row)?,
+                    });
+                }
+                copy_to_baseline(tx, table, id)?;
+            }
+        }
+        // Children go before the rows they refer to
+        for (table, id) in deleted.into_iter().rev() {
+            let (origin, origin_id) = global_key(tx, &state.node_id, &table.name, id)?;
+            records.push(SyncRecord {
+                table: table.name.clone(),
+                origin,
+                origin_id,
+                op: RecordOp::Delete,
+                changed_at: changed_at.clone(),
+                changed_by: state.node_id.clone(),
+                values: Map::new(),
+            });
+            copy_to_baseline(tx, table, id)?;
+        }
+
+        for record in &records {
+            let text = serde_json::to_string(record).map_err(|e| rusqlite::Error::ToSqlConversionFailure(e.into()))?;
+            tx.execute(
+                "INSERT INTO sync_outbox (table_name, record) VALUES (?1, ?2)",
+                params![record.table, text],
+            )?;
+            store_version(tx, record)?;
+        }
+        Ok(records.len())
+    };
+    capture().map_err(|e| format!("Failed to capture changes: {}", e))
+}
+
+/// Capture in a transaction of its own
+fn capture(conn: &mut Connection, state: &SyncState) -> Result<usize, String> {
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let schema = load_schema(&tx)?;
+    let captured = capture_in_transaction(&tx, state, &schema)?;
+    tx.commit()
+        .map_err(|e| format!("Failed to commit captured changes: {}", e))?;
+    Ok(captured)
+}
+
+/// Remember the rows whose derived values an import may have changed
+fn note_affected(conn: &Connection, table: &SyncTable, id: i64, affected: &mut Affected) -> rusqlite::Result<()> {
+    affected.any = true;
+    match table.name.as_str() {
+        "products" => {
+            affected.products.insert(id);
+        }
+        "customers" => {
+            affected.customers.insert(id);
+        }
+        "vendors" => {
+            affected.vendors.insert(id);
+        }
+        _ => {}
+    }
+    for (column, ids) in [
+        ("product_id", &mut affected.products),
+        ("customer_id", &mut affected.customers),
+        ("vendor_id", &mut affected.vendors),
+    ] {
+        if table.column_index(column).is_none() {
+            continue;
+        }
+        let owner: Option<i64> = conn
+            .query_row(
+                &format!("SELECT \"{}\" FROM main.\"{}\" WHERE id = ?1", column, table.name),
+                [id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .flatten();
+        ids.extend(owner);
+    }
+    Ok(())
+}
+
+/// Apply one incoming record; `Err` is the reason it cannot be applied here
+fn apply_record(
+    tx: &Transaction,
+    state: &SyncState,
+    schema: &Schema,
+    record: &SyncRecord,
+    affected: &mut Affected,
+) -> Result<Outcome, String> {
+    let failed = |e: rusqlite::Error| e.to_string();
+    if record.changed_by == state.node_id {
+        return Ok(Outcome::Skipped);
+    }
+    let Some(table) = schema.table(&record.table) else {
+        if is_local(&record.table) {
+            return Ok(Outcome::Skipped);
+        }
+        return Err(format!("Table {} does not exist on this terminal yet", record.table));
+    };
+
+    let version = tx
+        .query_row(
+            "SELECT changed_at, changed_by, deleted FROM sync_row_versions
+             WHERE table_name = ?1 AND origin = ?2 AND origin_id = ?3",
+            params![record.table, record.origin, record.origin_id],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, bool>(2)?,
+                ))
+            },
+        )
+        .optional()
+        .map_err(failed)?;
+    // Deletes win: nothing brings a deleted row back
+    if version.as_ref().is_some_and(|(_, _, deleted)| *deleted) {
+        return Ok(Outcome::Skipped);
+    }
+    let existing = local_id(tx, &state.node_id, &table.name, &record.origin, record.origin_id).map_err(failed)?;
+
+    if record.op == RecordOp::Delete {
+        if let Some(id) = existing {
+            note_affected(tx, table, id, affected).map_err(failed)?;
+            tx.execute(&format!("DELETE FROM main.\"{}\" WHERE id = ?1", table.name), [id])
+                .map_err(failed)?;
+            copy_to_baseline(tx, table, id).map_err(failed)?;
+        }
+        store_version(tx, record).map_err(failed)?;
+        return Ok(Outcome::Applied);
+    }
+
+    if let Some((changed_at, changed_by)) = version.map(|(at, by, _)| (at, by)) {
+        if (changed_at.as_str(), changed_by.as_str()) >= (record.changed_at.as_str(), record.changed_by.as_str()) {
+            if changed_by == record.changed_by {
+                return Ok(Outcome::Skipped);
+            }
+            tx.execute(
+                "INSERT INTO sync_conflicts (table_name, origin, origin_id, kept_changed_at, kept_changed_by,
+                                             discarded_changed_at, discarded_changed_by)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    record.table,
+                    record.origin,
+                    record.origin_id,
+                    changed_at,
+                    changed_by,
+                    record.changed_at,
+                    record.changed_by
+                ],
+            )
+            .map_err(failed)?;
+            return Ok(Outcome::Conflict);
+        }
+    }
+
+    let mut columns = Vec::new();
+    let mut values = Vec::new();
+    for (column, value) in &record.values {
+        if column != "id" && !table.is_cache(column) && table.column_index(column).is_some() {
+            columns.push(column.as_str());
+            values.push(decode_value(tx, state, value)?);
+        }
+    }
+
+    let present = match existing {
+        Some(id) => read_row(tx, "main", table, id).map_err(failed)?.map(|_| id),
+        None => None,
+    };
+    let id = match present {
+        Some(id) => {
+            let (columns, values): (Vec<_>, Vec<_>) = columns
+                .into_iter()
+                .zip(values)
+                .filter(|(column, _)| !table.is_derived(column))
+                .unzip();
+            if !columns.is_empty() {
+                let assignments = columns
+                    .iter()
+                    .enumerate()
+                    .map(|(index, column)| format!("\"{}\" = ?{}", column, index + 1))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                tx.execute(
+                    &format!(
+                        "UPDATE main.\"{}\" SET {} WHERE id = ?{}",
+                        table.name,
+                        assignments,
+                        columns.len() + 1
+                    ),
+                    params_from_iter(values.into_iter().chain([Value::Integer(id)])),
+                )
+                .map_err(failed)?;
+            }
+            id
+        }
+        None => {
+            let placeholders = (1..=columns.len())
+                .map(|index| format!("?{}", index))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let names = columns
+                .iter()
+                .map(|column| format!("\"{}\"", column))
+                .collect::<Vec<_>>()
+                .join(", ");
+            tx.execute(
+                &format!(
+                    "INSERT INTO main.\"{}\" ({}) VALUES ({})",
+                    table.name, names, placeholders
+                ),
+                params_from_iter(values),
+            )
+            .map_err(failed)?;
+            let id = tx.last_insert_rowid();
+            tx.execute(
+                "INSERT OR REPLACE INTO sync_row_map (table_name, local_id, origin, origin_id) VALUES (?1, ?2, ?3, ?4)",
+                params![table.name, id, record.origin, record.origin_id],
+            )
+            .map_err(failed)?;
+            id
+        }
+    };
+
+    note_affected(tx, table, id, affected).map_err(failed)?;
+    copy_to_baseline(tx, table, id).map_err(failed)?;
+    store_version(tx, record).map_err(failed)?;
+    Ok(Outcome::Applied)
+}
+
+/// Apply a record all or nothing
+fn apply_in_savepoint(
+    tx: &Transaction,
+    state: &SyncState,
+    schema: &Schema,
+    record: &SyncRecord,
+    affected: &mut Affected,
+) -> Result<Outcome, String> {
+    tx.execute_batch("SAVEPOINT sync_record")
+        .map_err(|e| format!("Failed to start savepoint: {}", e))?;
+    let outcome = apply_record(tx, state, schema, record, affected);
+    let end = if outcome.is_ok() {
+        "RELEASE sync_record"
+    } else {
+        "ROLLBACK TO sync_record; RELEASE sync_record"
+    };
+    tx.execute_batch(end)
+        .map_err(|e| format!("Failed to end savepoint: {}", e))?;
+    outcome
+}
+
+fn reject(tx: &Transaction, record: &SyncRecord, reason: &str) -> Result<(), String> {
+    let text = serde_json::to_string(record).map_err(|e| format!("Failed to store rejected record: {}", e))?;
+    tx.execute(
+        "INSERT INTO sync_rejections (table_name, origin, origin_id, record, reason) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![record.table, record.origin, record.origin_id, text, reason],
+    )
+    .map(|_| ())
+    .map_err(|e| format!("Failed to store rejected record: {}", e))
+}
+
+/// Try every rejected record again, oldest first; returns how many applied
+fn retry_rejections(
+    tx: &Transaction,
+    state: &SyncState,
+    schema: &Schema,
+    affected: &mut Affected,
+) -> Result<usize, String> {
+    let rejected = {
+        let mut stmt = tx
+            .prepare("SELECT id, record FROM sync_rejections ORDER BY id")
+            .map_err(|e| format!("Failed to read rejected records: {}", e))?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))
+            .map_err(|e| format!("Failed to read rejected records: {}", e))?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(|e| format!("Failed to read rejected record: {}", e))?
+    };
+
+    let mut recovered = 0;
+    for (id, text) in rejected {
+        let outcome = serde_json::from_str::<SyncRecord>(&text)
+            .map_err(|e| format!("Unreadable record: {}", e))
+            .and_then(|record| apply_in_savepoint(tx, state, schema, &record, affected));
+        let result = match outcome {
+            Ok(outcome) => {
+                if matches!(outcome, Outcome::Applied) {
+                    recovered += 1;
+                }
+                tx.execute("DELETE FROM sync_rejections WHERE id = ?1", [id])
+            }
+            Err(reason) => tx.execute(
+                "UPDATE sync_rejections
+                 SET reason = ?1, attempts = attempts + 1, last_attempt_at = CURRENT_TIMESTAMP WHERE id = ?2",
+                params![reason, id],
+            ),
+        };
+        result.map_err(|e| format!("Failed to update rejected record: {}", e))?;
+    }
+    Ok(recovered)
+}
+
+/// Rebuild stock, balances, payables and the journal the import touched.
+/// A step that fails is rolled back on its own and logged; the records
+/// already applied still commit, and the next round rebuilds again.
+fn rebuild_derived(tx: &Transaction, affected: &Affected) -> Result<(), String> {
+    for &product_id in &affected.products {
+        rebuild_step(tx, &format!("rebuild stock of product {}", product_id), || {
+            stock_engine::rebuild_stock_in_transaction(tx, Some(product_id), SYNC_USER).map(|_| ())
+        })?;
+    }
+    for &customer_id in &affected.customers {
+        rebuild_step(tx, &format!("recalculate customer {}", customer_id), || {
+            customer_balance::recalculate_in_transaction(tx, Some(customer_id)).map(|_| ())
+        })?;
+    }
+    let mut vendors = VendorSyncResult::default();
+    for &vendor_id in &affected.vendors {
+        rebuild_step(tx, &format!("sync payables of vendor {}", vendor_id), || {
+            sync_vendor_in_transaction(tx, vendor_id, &mut vendors).map(|_| ())
+        })?;
+    }
+    if affected.any {
+        rebuild_step(tx, "post the journal", || {
+            journal::sync_journal_in_transaction(tx).map(|_| ())
+        })?;
+    }
+    Ok(())
+}
+
+fn rebuild_step(tx: &Transaction, what: &str, step: impl FnOnce() -> Result<(), String>) -> Result<(), String> {
+    tx.execute_batch("SAVEPOINT sync_rebuild")
+        .map_err(|e| format!("Failed to start savepoint: {}", e))?;
+    let end = match step() {
+        Ok(()) => "RELEASE sync_rebuild",
+        Err(e) => {
+            warn!("[SYNC] Failed to {}: {}", what, e);
+            "ROLLBACK TO sync_rebuild; RELEASE sync_rebuild"
+        }
+    };
+    tx.execute_batch(end)
+        .map_err(|e| format!("Failed to end savepoint: {}", e))
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(reader: &mut BufReader<TcpStream>, address: &str) -> Result<T, String> {
+    let mut line = String::new();
+    reader
+        .read_line(&mut line)
+        .map_err(|e| format!("Failed to read from {}: {}", address, e))?;
+    serde_json::from_str(&line).map_err(|e| format!("Unreadable answer from {}: {}", address, e))
+}
+
+fn write_json<T: Serialize>(stream: &mut TcpStream, value: &T, address: &str) -> Result<(), String> {
+    let mut line = serde_json::to_string(value).map_err(|e| format!("Failed to encode message: {}", e))?;
+    line.push('\n');
+    stream
+        .write_all(line.as_bytes())
+        .and_then(|_| stream.flush())
+        .map_err(|e| format!("Failed to send to {}: {}", address, e))
+}
+
+/// Answer the peer's challenge with a request line and read its response
+/// line; a response without a valid proof is not believed
+fn exchange(
+    address: &str,
+    group_code: &str,
+    request: &SyncRequest,
+) -> Result<(SyncResponse, BufReader<TcpStream>), String> {
+    let key = group_key(group_code)?;
+    let socket = address
+        .to_socket_addrs()
+        .map_err(|e| format!("Unknown address {}: {}", address, e))?
+        .next()
+        .ok_or_else(|| format!("Unknown address {}", address))?;
+    let stream = TcpStream::connect_timeout(&socket, NETWORK_TIMEOUT)
+        .map_err(|e| format!("Failed to reach {}: {}", address, e))?;
+    stream
+        .set_read_timeout(Some(NETWORK_TIMEOUT))
+        .and_then(|_| stream.set_write_timeout(Some(NETWORK_TIMEOUT)))
+        .map_err(|e| format!("Failed to configure connection: {}", e))?;
+    let mut reader = BufReader::new(stream);
+
+    let challenge = match read_json(&mut reader, address)? {
+        Hello::Challenge(challenge) => challenge,
+        Hello::Refused(message) => return Err(message),
+    };
+    let nonce = random_hex(16)?;
+    let body = serde_json::to_string(request).map_err(|e| format!("Failed to encode request: {}", e))?;
+    let proof = prove(&key, "request", &challenge, &nonce, &body)?;
+    write_json(reader.get_mut(), &Envelope { nonce: nonce.clone(), body, proof }, address)?;
+
+    let answer: Envelope = read_json(&mut reader, address)?;
+    let response =
+        serde_json::from_str(&answer.body).map_err(|e| format!("Unreadable response from {}: {}", address, e));
+    if !proof_holds(&key, "response", &challenge, &nonce, &answer.body, &answer.proof) {
+        // Refusals are not signed; believing one changes nothing
+        return Err(match response {
+            Ok(SyncResponse::Error { message }) => message,
+            _ => format!("{} did not prove it holds the sync group code", address),
+        });
+    }
+    match response? {
+        SyncResponse::Error { message } => Err(message),
+        response => Ok((response, reader)),
+    }
+}
+
+fn received_seq(conn: &Connection, node_id: &str) -> rusqlite::Result<i64> {
+    conn.query_row(
+        "SELECT COALESCE((SELECT last_seq FROM sync_cursors WHERE node_id = ?1), 0)",
+        [node_id],
+        |row| row.get(0),
+    )
+}
+
+/// Pull a peer's records after the last one received from it
+fn pull_peer(conn: &Connection, state: &SyncState, address: &str, known_node: Option<&str>) -> Result<Batch, String> {
+    let cursor = |node_id: &str| received_seq(conn, node_id).map_err(|e| format!("Failed to read sync cursor: {}", e));
+    let mut after = match known_node {
+        Some(node_id) => cursor(node_id)?,
+        None => 0,
+    };
+    let mut batch = Batch {
+        address: address.to_string(),
+        node_id: String::new(),
+        node_name: String::new(),
+        records: Vec::new(),
+        last_seq: after,
+    };
+
+    for _ in 0..MAX_PULLS {
+        let request = SyncRequest::Pull {
+            node_id: state.node_id.clone(),
+            node_name: state.node_name.clone(),
+            port: state.port,
+            after,
+        };
+        let SyncResponse::Records {
+            node_id,
+            node_name,
+            records,
+            last_seq,
+            more,
+        } = exchange(address, &state.group_code, &request)?.0
+        else {
+            return Err(format!("Unexpected response from {}", address));
+        };
+        // Another terminal answers at this address now: start from its own cursor
+        if batch.node_id.is_empty() && known_node != Some(node_id.as_str()) && after != cursor(&node_id)? {
+            after = cursor(&node_id)?;
+            batch.node_id = node_id;
+            batch.last_seq = after;
+            continue;
+        }
+        batch.node_id = node_id;
+        batch.node_name = node_name;
+        batch.records.extend(records);
+        batch.last_seq = batch.last_seq.max(last_seq);
+        after = batch.last_seq;
+        if !more {
+            break;
+        }
+    }
+    Ok(batch)
+}
+
+/// One sync round for the database at `db_path`: capture local changes,
+/// pull every peer's, and apply them in one transaction
+pub fn sync_round(db_path: &Path) -> Result<SyncRoundResult, String> {
+    let mut conn = open_connection_at(db_path)?;
+    let state = enabled_state(&conn)?;
+    attach_baseline(&conn, db_path)?;
+
+    let mut result = SyncRoundResult {
+        captured: capture(&mut conn, &state)?,
+        ..SyncRoundResult::default()
+    };
+
+    let peers = {
+        let mut stmt = conn
+            .prepare("SELECT address, node_id FROM sync_peers ORDER BY address")
+            .map_err(|e| format!("Failed to read sync peers: {}", e))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
+            })
+            .map_err(|e| format!("Failed to read sync peers: {}", e))?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(|e| format!("Failed to read sync peer: {}", e))?
+    };
+    let mut batches = Vec::new();
+    for (address, node_id) in peers {
+        match pull_peer(&conn, &state, &address, node_id.as_deref()) {
+            Ok(batch) => batches.push(batch),
+            Err(e) => {
+                conn.execute(
+                    "UPDATE sync_peers SET last_error = ?1 WHERE address = ?2",
+                    params![e, address],
+                )
+                .map_err(|e| format!("Failed to update sync peer: {}", e))?;
+                result.peers.push(PeerSyncResult {
+                    address,
+                    node_name: None,
+                    received: 0,
+                    error: Some(e),
+                });
+            }
+        }
+    }
+
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let schema = load_schema(&tx)?;
+    // Writes made while pulling get their version before anything is compared
+    result.captured += capture_in_transaction(&tx, &state, &schema)?;
+    // Imported rows are the peers' changes, not the signed-in user's
+    let actor = audit::act_as(&tx, SYNC_USER, true)?;
+    // Deletions on a peer went through its own recycle bin
+    let permit = recycle_bin::permit_hard_delete(&tx)?;
+
+    let mut affected = Affected::default();
+    for batch in batches {
+        for record in &batch.records {
+            match apply_in_savepoint(&tx, &state, &schema, record, &mut affected) {
+                Ok(Outcome::Applied) => result.applied += 1,
+                Ok(Outcome::Conflict) => result.conflicts += 1,
+                Ok(Outcome::Skipped) => {}
+                Err(reason) => reject(&tx, record, &reason)?,
+            }
+        }
+        tx.execute(
+            "INSERT INTO sync_cursors (node_id, last_seq) VALUES (?1, ?2)
+             ON CONFLICT(node_id) DO UPDATE SET last_seq = MAX(last_seq, excluded.last_seq)",
+            params![batch.node_id, batch.last_seq],
+        )
+        .and_then(|_| {
+            tx.execute(
+                "UPDATE sync_peers SET node_id = ?1, node_name = ?2, last_sync_at = CURRENT_TIMESTAMP, last_error = NULL
+                 WHERE address = ?3",
+                params![batch.node_id, batch.node_name, batch.address],
+            )
+        })
+        .map_err(|e| format!("Failed to update sync peer: {}", e))?;
+
+        result.received += batch.records.len();
+        result.peers.push(PeerSyncResult {
+            address: batch.address,
+            node_name: Some(batch.node_name),
+            received: batch.records.len(),
+            error: None,
+        });
+    }
+    // A record can refer to one that came later in the same round
+    result.recovered = retry_rejections(&tx, &state, &schema, &mut affected)?;
+    result.rejected = tx
+        .query_row("SELECT COUNT(*) FROM sync_rejections", [], |row| row.get::<_, i64>(0))
+        .map_err(|e| format!("Failed to count rejected records: {}", e))? as usize;
+    rebuild_derived(&tx, &affected)?;
+
+    // Every peer has pulled what is below its acknowledged sequence
+    tx.execute_batch(
+        "DELETE FROM sync_outbox
+         WHERE EXISTS (SELECT 1 FROM sync_peers)
+           AND seq <= (SELECT MIN(acked_seq) FROM sync_peers);
+         UPDATE sync_state SET last_round_at = CURRENT_TIMESTAMP WHERE id = 1;",
+    )
+    .map_err(|e| format!("Failed to trim the sync outbox: {}", e))?;
+    recycle_bin::revoke_hard_delete(&tx, permit)?;
+    audit::restore_actor(&tx, actor)?;
+    tx.commit().map_err(|e| format!("Failed to commit sync round: {}", e))?;
+
+    if result.captured + result.received > 0 {
+        info!(
+            "[SYNC] Sent {} changes, received {} ({} applied, {} conflicts, {} rejected)",
+            result.captured, result.received, result.applied, result.conflicts, result.rejected
+        );
+    }
+    Ok(result)
+}
+
+/// Answer one peer request; the bytes follow the response line
+fn answer(request: SyncRequest, db_path: &Path, peer: SocketAddr) -> Result<(SyncResponse, Vec<u8>), String> {
+    let mut conn = open_connection_at(db_path)?;
+    let state = enabled_state(&conn)?;
+    attach_baseline(&conn, db_path)?;
+    // Hand out the latest changes, not the last round's
+    capture(&mut conn, &state)?;
+
+    match request {
+        SyncRequest::Pull {
+            node_id,
+            node_name,
+            port,
+            after,
+            ..
+        } => {
+            let address = SocketAddr::new(peer.ip(), port).to_string();
+            conn.execute(
+                "INSERT INTO sync_peers (address, node_id, node_name)
+                 SELECT ?1, ?2, ?3 WHERE NOT EXISTS (SELECT 1 FROM sync_peers WHERE node_id = ?2)",
+                params![address, node_id, node_name],
+            )
+            .and_then(|_| {
+                conn.execute(
+                    "UPDATE sync_peers SET acked_seq = MAX(acked_seq, ?1), node_name = ?2 WHERE node_id = ?3",
+                    params![after, node_name, node_id],
+                )
+            })
+            .map_err(|e| format!("Failed to record sync peer: {}", e))?;
+
+            let mut stmt = conn
+                .prepare("SELECT seq, record FROM sync_outbox WHERE seq > ?1 ORDER BY seq LIMIT ?2")
+                .map_err(|e| format!("Failed to read the sync outbox: {}", e))?;
+            let rows = stmt
+                .query_map(params![after, PULL_LIMIT as i64 + 1], |row| {
+                    Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+                })
+                .map_err(|e| format!("Failed to read the sync outbox: {}", e))?
+                .collect::<rusqlite::Result<Vec<_>>>()
+                .map_err(|e| format!("Failed to read outbox record: {}", e))?;
+            let more = rows.len() > PULL_LIMIT;
+            let mut records = Vec::new();
+            let mut last_seq = after;
+            for (seq, text) in rows.into_iter().take(PULL_LIMIT) {
+                records
+                    .push(serde_json::from_str(&text).map_err(|e| format!("Unreadable outbox record {}: {}", seq, e))?);
+                last_seq = seq;
+            }
+            Ok((
+                SyncResponse::Records {
+                    node_id: state.node_id,
+                    node_name: state.node_name,
+                    records,
+                    last_seq,
+                    more,
+                },
+                Vec::new(),
+            ))
+        }
+        SyncRequest::Snapshot => {
+            let path = sibling(db_path, SNAPSHOT_SUFFIX);
+            let _ = fs::remove_file(&path);
+            conn.execute("VACUUM main INTO ?1", [path.to_string_lossy()])
+                .map_err(|e| format!("Failed to write snapshot: {}", e))?;
+            let bytes = fs::read(&path).map_err(|e| format!("Failed to read snapshot: {}", e));
+            let _ = fs::remove_file(&path);
+            let bytes = bytes?;
+            info!("[SYNC] Sent a {} byte snapshot to {}", bytes.len(), peer);
+            Ok((
+                SyncResponse::Snapshot {
+                    node_id: state.node_id,
+                    node_name: state.node_name,
+                    bytes: bytes.len() as u64,
+                    sha256: format!("{:x}", Sha256::digest(&bytes)),
+                },
+                bytes,
+            ))
+        }
+    }
+}
+
+/// Count a connection from `ip` as a failed check until it proves the group
+/// code. False when the address has used up its attempts for the window.
+fn begin_attempt(ip: IpAddr) -> bool {
+    let mut attempts = FAILED_ATTEMPTS
+        .get_or_init(Mutex::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    attempts.retain(|_, (_, since)| since.elapsed() < FAILURE_WINDOW);
+    let (count, _) = attempts.entry(ip).or_insert((0, Instant::now()));
+    if *count >= MAX_FAILED_ATTEMPTS {
+        return false;
+    }
+    *count += 1;
+    true
+}
+
+fn attempt_succeeded(ip: IpAddr) {
+    let mut attempts = FAILED_ATTEMPTS
+        .get_or_init(Mutex::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    if let Some((count, _)) = attempts.get_mut(&ip) {
+        *count = count.saturating_sub(1);
+    }
+}
+
+fn handle_peer(stream: TcpStream, db_path: &Path) -> Result<(), String> {
+    let peer = stream
+        .peer_addr()
+        .map_err(|e| format!("Failed to read peer address: {}", e))?;
+    let address = peer.to_string();
+    stream
+        .set_read_timeout(Some(NETWORK_TIMEOUT))
+        .and_then(|_| stream.set_write_timeout(Some(NETWORK_TIMEOUT)))
+        .map_err(|e| format!("Failed to configure connection: {}", e))?;
+    let mut reader = BufReader::new(stream);
+
+    let key = if begin_attempt(peer.ip()) {
+        open_connection_at(db_path)
+            .and_then(|conn| enabled_state(&conn))
+            .and_then(|state| group_key(&state.group_code))
+    } else {
+        Err("Too many failed group code checks from this address; try again later".to_string())
+    };
+    let key = match key {
+        Ok(key) => key,
+        Err(message) => return write_json(reader.get_mut(), &Hello::Refused(message), &address),
+    };
+    let challenge = random_hex(GROUP_CODE_BYTES)?;
+    write_json(reader.get_mut(), &Hello::Challenge(challenge.clone()), &address)?;
+
+    let envelope = read_json::<Envelope>(&mut reader, &address)
+        .ok()
+        .filter(|envelope| proof_holds(&key, "request", &challenge, &envelope.nonce, &envelope.body, &envelope.proof));
+    let Some(envelope) = envelope else {
+        warn!("[SYNC] {} failed the group code check", peer);
+        thread::sleep(FAILURE_DELAY);
+        let body = serde_json::to_string(&SyncResponse::Error {
+            message: "Wrong sync group code".to_string(),
+        })
+        .map_err(|e| format!("Failed to encode response: {}", e))?;
+        let refusal = Envelope {
+            nonce: String::new(),
+            body,
+            proof: String::new(),
+        };
+        return write_json(reader.get_mut(), &refusal, &address);
+    };
+    attempt_succeeded(peer.ip());
+
+    let (response, bytes) = serde_json::from_str(&envelope.body)
+        .map_err(|e| format!("Unreadable request: {}", e))
+        .and_then(|request| answer(request, db_path, peer))
+        .unwrap_or_else(|message| (SyncResponse::Error { message }, Vec::new()));
+    let body = serde_json::to_string(&response).map_err(|e| format!("Failed to encode response: {}", e))?;
+    let proof = prove(&key, "response", &challenge, &envelope.nonce, &body)?;
+    let mut writer = reader.into_inner();
+    write_json(&mut writer, &Envelope { nonce: envelope.nonce, body, proof }, &address)?;
+    writer
+        .write_all(&bytes)
+        .and_then(|_| writer.flush())
+        .map_err(|e| format!("Failed to answer {}: {}", peer, e))
+}
+
+/// Serve pull and snapshot requests from peers until the listener fails
+pub fn serve(listener: TcpListener, db_path: PathBuf) {
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let db_path = db_path.clone();
+        thread::spawn(move || {
+            if let Err(e) = handle_peer(stream, &db_path) {
+                error!("[SYNC] Peer request failed: {}", e);
+            }
+        });
+    }
+}
+
+/// Start serving peers and syncing in the background once sync is enabled.
+/// A changed port takes effect at the next start.
+pub fn start(db_path: PathBuf) {
+    thread::spawn(move || {
+        let mut listening = false;
+        loop {
+            let state = open_connection_at(&db_path)
+                .ok()
+                .and_then(|conn| load_state(&conn).ok().flatten());
+            if let Some(state) = state.filter(|state| state.enabled) {
+                if !listening {
+                    match TcpListener::bind(("0.0.0.0", state.port)) {
+                        Ok(listener) => {
+                            let path = db_path.clone();
+                            thread::spawn(move || serve(listener, path));
+                            listening = true;
+                            info!("[SYNC] Serving peers on port {}", state.port);
+                        }
+                        Err(e) => error!("[SYNC] Failed to listen on port {}: {}", state.port, e),
+                    }
+                }
+                if let Err(e) = sync_round(&db_path) {
+                    error!("[SYNC] Sync round failed: {}", e);
+                }
+            }
+            thread::sleep(SYNC_INTERVAL);
+        }
+    });
+}
+
+/// Start the baseline over as a copy of the live database
+fn reset_baseline(conn: &Connection, db_path: &Path) -> Result<(), String> {
+    let _ = conn.execute(&format!("DETACH DATABASE {}", BASELINE_ALIAS), []);
+    let path = sibling(db_path, BASELINE_SUFFIX);
+    if path.exists() {
+        fs::remove_file(&path).map_err(|e| format!("Failed to remove the old sync baseline: {}", e))?;
+    }
+    attach_baseline(conn, db_path)?;
+    prepare_baseline(conn, &load_schema(conn)?)
+}
+
+/// Turn sync on for the database at `db_path`. The first time, this terminal
+/// gets its id and group code and the books as they are become the baseline.
+pub fn enable(conn: &Connection, db_path: &Path, node_name: &str, port: Option<u16>) -> Result<(), String> {
+    let node_name = node_name.trim();
+    if node_name.is_empty() {
+        return Err("A terminal name is required".to_string());
+    }
+    let port = port.unwrap_or(DEFAULT_PORT);
+    let updated = conn
+        .execute(
+            "UPDATE sync_state SET node_name = ?1, port = ?2, enabled = 1 WHERE id = 1",
+            params![node_name, port],
+        )
+        .map_err(|e| format!("Failed to enable sync: {}", e))?;
+    if updated == 0 {
+        conn.execute(
+            "INSERT INTO sync_state (id, node_id, node_name, group_code, port)
+             VALUES (1, lower(hex(randomblob(8))), ?1, ?2, ?3)",
+            params![node_name, random_hex(GROUP_CODE_BYTES)?, port],
+        )
+        .map_err(|e| format!("Failed to enable sync: {}", e))?;
+    }
+    if updated == 0 || !sibling(db_path, BASELINE_SUFFIX).exists() {
+        reset_baseline(conn, db_path)?;
+    }
+    Ok(())
+}
+
+/// Give the group a new code. The other terminals cannot sync with this one
+/// until the new code is entered there with `set_group_code`.
+pub fn renew_group_code(conn: &Connection) -> Result<(), String> {
+    enabled_state(conn)?;
+    conn.execute(
+        "UPDATE sync_state SET group_code = ?1 WHERE id = 1",
+        [random_hex(GROUP_CODE_BYTES)?],
+    )
+    .map_err(|e| format!("Failed to renew the group code: {}", e))?;
+    Ok(())
+}
+
+/// Take the group code renewed on another terminal
+pub fn set_group_code(conn: &Connection, group_code: &str) -> Result<(), String> {
+    group_key(group_code)?;
+    enabled_state(conn)?;
+    conn.execute(
+        "UPDATE sync_state SET group_code = ?1 WHERE id = 1",
+        [group_code.trim().to_ascii_lowercase()],
+    )
+    .map_err(|e| format!("Failed to set the group code: {}", e))?;
+    Ok(())
+}
+
+/// Download a peer's database to replace this one at the next start
+pub fn request_join(db_path: &Path, address: &str, group_code: &str, node_name: &str) -> Result<String, String> {
+    let node_name = node_name.trim();
+    if node_name.is_empty() {
+        return Err("A terminal name is required".to_string());
+    }
+    let address = peer_address(address);
+    let (
+        SyncResponse::Snapshot {
+            node_name: peer_name,
+            bytes,
+            sha256,
+            ..
+        },
+        reader,
+    ) = exchange(&address, group_code, &SyncRequest::Snapshot)?
+    else {
+        return Err(format!("Unexpected response from {}", address));
+    };
+    let mut snapshot = Vec::new();
+    reader
+        .take(bytes)
+        .read_to_end(&mut snapshot)
+        .map_err(|e| format!("Failed to download snapshot: {}", e))?;
+    if snapshot.len() as u64 != bytes
+        || format!("{:x}", Sha256::digest(&snapshot)) != sha256
+        || !snapshot.starts_with(b"SQLite format 3\0")
+    {
+        return Err("The snapshot arrived incomplete".to_string());
+    }
+
+    fs::write(sibling(db_path, JOIN_SUFFIX), &snapshot).map_err(|e| format!("Failed to save snapshot: {}", e))?;
+    let pending = PendingJoin {
+        address,
+        node_name: node_name.to_string(),
+    };
+    let text = serde_json::to_string(&pending).map_err(|e| format!("Failed to save join request: {}", e))?;
+    fs::write(sibling(db_path, JOIN_REQUEST_SUFFIX), text)
+        .map_err(|e| format!("Failed to save join request: {}", e))?;
+    Ok(peer_name)
+}
+
+/// Replace the database with a downloaded snapshot before anything opens it.
+/// The old file is kept as `store.db.pre-join-backup`; this terminal gets an
+/// id of its own and knows every row of the snapshot by the peer's ids.
+/// Returns the name of the terminal joined.
+pub fn apply_pending_join(db_path: &Path) -> Result<Option<String>, String> {
+    let snapshot = sibling(db_path, JOIN_SUFFIX);
+    let request_path = sibling(db_path, JOIN_REQUEST_SUFFIX);
+    if !snapshot.exists() {
+        return Ok(None);
+    }
+    let pending: PendingJoin = fs::read_to_string(&request_path)
+        .map_err(|e| e.to_string())
+        .and_then(|text| serde_json::from_str(&text).map_err(|e| e.to_string()))
+        .map_err(|e| format!("Failed to read the join request: {}", e))?;
+
+    if db_path.exists() {
+        // Fold the WAL in so the backup is complete on its own
+        Connection::open(db_path)
+            .and_then(|conn| conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(())))
+            .map_err(|e| format!("Failed to checkpoint the database: {}", e))?;
+        fs::copy(db_path, sibling(db_path, PRE_JOIN_SUFFIX))
+            .map_err(|e| format!("Failed to back up the database: {}", e))?;
+        for suffix in ["-wal", "-shm"] {
+            let _ = fs::remove_file(sibling(db_path, suffix));
+        }
+    }
+    fs::rename(&snapshot, db_path).map_err(|e| format!("Failed to move the snapshot into place: {}", e))?;
+    audit::forget_head(db_path);
+
+    let mut conn = open_connection_at(db_path)?;
+    ensure_schema(&conn).map_err(|e| format!("Failed to create sync tables: {}", e))?;
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let (peer_id, peer_name): (String, String) = tx
+        .query_row("SELECT node_id, node_name FROM sync_state WHERE id = 1", [], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .map_err(|e| format!("The snapshot is not from a synced terminal: {}", e))?;
+
+    let adopt = || -> rusqlite::Result<()> {
+        tx.execute(
+            "UPDATE sync_state SET node_id = lower(hex(randomblob(8))), node_name = ?1, enabled = 1 WHERE id = 1",
+            [&pending.node_name],
+        )?;
+        // Rows the peer created itself are known here by its ids
+        for table in load_schema(&tx)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(e.into()))?
+            .tables
+        {
+            tx.execute(
+                &format!(
+                    "INSERT OR IGNORE INTO sync_row_map (table_name, local_id, origin, origin_id)
+                     SELECT ?1, id, ?2, id FROM main.\"{}\"
+                     WHERE id NOT IN (SELECT local_id FROM sync_row_map WHERE table_name = ?1)",
+                    table.name
+                ),
+                params![table.name, peer_id],
+            )?;
+        }
+        // Everything the peer had sent is in the snapshot
+        tx.execute(
+            "INSERT OR REPLACE INTO sync_cursors (node_id, last_seq)
+             VALUES (?1, (SELECT COALESCE(MAX(seq), 0) FROM sync_outbox))",
+            [&peer_id],
+        )?;
+        tx.execute_batch(
+            "DELETE FROM sync_outbox;
+             DELETE FROM sync_rejections;
+             DELETE FROM sync_conflicts;
+             UPDATE sync_peers SET acked_seq = 0, last_sync_at = NULL, last_error = NULL;",
+        )?;
+        tx.execute(
+            "INSERT OR REPLACE INTO sync_peers (address, node_id, node_name) VALUES (?1, ?2, ?3)",
+            params![pending.address, peer_id, peer_name],
+        )?;
+        Ok(())
+    };
+    adopt().map_err(|e| format!("Failed to take over the snapshot: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to commit the join: {}", e))?;
+
+    reset_baseline(&conn, db_path)?;
+    let _ = fs::remove_file(&request_path);
+    Ok(Some(peer_name))
+}
+
+/// Sync settings, peers, pending changes, conflicts and rejections
+pub fn status(conn: &Connection, db_path: &Path) -> Result<SyncStatus, String> {
+    let read = || -> rusqlite::Result<SyncStatus> {
+        let mut status = SyncStatus {
+            port: DEFAULT_PORT,
+            join_pending: sibling(db_path, JOIN_SUFFIX).exists(),
+            ..SyncStatus::default()
+        };
+        let state = conn
+            .query_row(
+                "SELECT node_id, node_name, group_code, port, enabled, last_round_at FROM sync_state WHERE id = 1",
+                [],
+                |row| {
+                    Ok((
+                        row.get(0)?,
+                        row.get(1)?,
+                        row.get(2)?,
+                        row.get(3)?,
+                        row.get(4)?,
+                        row.get(5)?,
+                    ))
+                },
+            )
+            .optional()?;
+        if let Some((node_id, node_name, group_code, port, enabled, last_round_at)) = state {
+            status.node_id = Some(node_id);
+            status.node_name = Some(node_name);
+            status.group_code = Some(group_code);
+            status.port = port;
+            status.enabled = enabled;
+            status.last_round_at = last_round_at;
+        }
+
+        let mut stmt = conn.prepare(
+            "SELECT p.address, p.node_id, p.node_name, p.last_sync_at, p.last_error, p.acked_seq,
+                    COALESCE(c.last_seq, 0)
+             FROM sync_peers p LEFT JOIN sync_cursors c ON c.node_id = p.node_id
+             ORDER BY p.address",
+        )?;
+        let peers = stmt.query_map([], |row| {
+            Ok(SyncPeer {
+                address: row.get(0)?,
+                node_id: row.get(1)?,
+                node_name: row.get(2)?,
+                last_sync_at: row.get(3)?,
+                last_error: row.get(4)?,
+                acked_seq: row.get(5)?,
+                received_seq: row.get(6)?,
+            })
+        })?;
+        status.peers = peers.collect::<rusqlite::Result<_>>()?;
+
+        status.pending_outbox = conn.query_row(
+            "SELECT COUNT(*) FROM sync_outbox
+             WHERE seq > COALESCE((SELECT MIN(acked_seq) FROM sync_peers), 0)",
+            [],
+            |row| row.get(0),
+        )?;
+        status.conflicts_resolved = conn.query_row("SELECT COUNT(*) FROM sync_conflicts", [], |row| row.get(0))?;
+
+        let mut stmt = conn.prepare(
+            "SELECT table_name, origin, origin_id, kept_changed_at, kept_changed_by,
+                    discarded_changed_at, discarded_changed_by, resolved_at
+             FROM sync_conflicts ORDER BY id DESC LIMIT 20",
+        )?;
+        let conflicts = stmt.query_map([], |row| {
+            Ok(SyncConflict {
+                table_name: row.get(0)?,
+                origin: row.get(1)?,
+                origin_id: row.get(2)?,
+                kept_changed_at: row.get(3)?,
+                kept_changed_by: row.get(4)?,
+                discarded_changed_at: row.get(5)?,
+                discarded_changed_by: row.get(6)?,
+                resolved_at: row.get(7)?,
+            })
+        })?;
+        status.recent_conflicts = conflicts.collect::<rusqlite::Result<_>>()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT id, table_name, origin, origin_id, reason, attempts, rejected_at
+             FROM sync_rejections ORDER BY id",
+        )?;
+        let rejections = stmt.query_map([], |row| {
+            Ok(SyncRejection {
+                id: row.get(0)?,
+                table_name: row.get(1)?,
+                origin: row.get(2)?,
+                origin_id: row.get(3)?,
+                reason: row.get(4)?,
+                attempts: row.get(5)?,
+                rejected_at: row.get(6)?,
+            })
+        })?;
+        status.rejections = rejections.collect::<rusqlite::Result<_>>()?;
+        Ok(status)
+    };
+    read().map_err(|e| format!("Failed to read sync status: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_sync_status() -> Result<SyncStatus, String> {
+    let conn = open_connection()?;
+    status(&conn, &get_db_path()?)
+}
+
+#[tauri::command]
+pub async fn enable_sync(node_name: String, port: Option<u16>) -> Result<SyncStatus, String> {
+    info!("[SYNC] Enabling sync as {}", node_name);
+    let db_path = get_db_path()?;
+    let conn = open_connection()?;
+    enable(&conn, &db_path, &node_name, port)?;
+    audit::record(
+        &conn,
+        &AuditEvent::new("enable", "sync").after(json!({ "node_name": node_name.trim(), "port": port })),
+    )?;
+    status(&conn, &db_path)
+}
+
+#[tauri::command]
+pub async fn disable_sync() -> Result<SyncStatus, String> {
+    let conn = open_connection()?;
+    conn.execute("UPDATE sync_state SET enabled = 0 WHERE id = 1", [])
+        .map_err(|e| format!("Failed to disable sync: {}", e))?;
+    audit::record(&conn, &AuditEvent::new("disable", "sync"))?;
+    info!("[SYNC] Sync disabled");
+    status(&conn, &get_db_path()?)
+}
+
+/// New group code for this terminal, to be entered on the other terminals
+#[tauri::command]
+pub async fn renew_sync_group_code() -> Result<SyncStatus, String> {
+    let conn = open_connection()?;
+    renew_group_code(&conn)?;
+    audit::record(&conn, &AuditEvent::new("renew_group_code", "sync"))?;
+    info!("[SYNC] Group code renewed");
+    status(&conn, &get_db_path()?)
+}
+
+#[tauri::command]
+pub async fn set_sync_group_code(group_code: String) -> Result<SyncStatus, String> {
+    let conn = open_connection()?;
+    set_group_code(&conn, &group_code)?;
+    audit::record(&conn, &AuditEvent::new("set_group_code", "sync"))?;
+    info!("[SYNC] Group code set");
+    status(&conn, &get_db_path()?)
+}
+
+#[tauri::command]
+pub async fn add_sync_peer(address: String) -> Result<SyncStatus, String> {
+    let conn = open_connection()?;
+    enabled_state(&conn)?;
+    let address = peer_address(&address);
+    conn.execute("INSERT OR IGNORE INTO sync_peers (address) VALUES (?1)", [&address])
+        .map_err(|e| format!("Failed to add sync peer: {}", e))?;
+    audit::record(&conn, &AuditEvent::new("add_peer", "sync").entity(&address))?;
+    info!("[SYNC] Added peer {}", address);
+    status(&conn, &get_db_path()?)
+}
+
+#[tauri::command]
+pub async fn remove_sync_peer(address: String) -> Result<SyncStatus, String> {
+    let conn = open_connection()?;
+    let address = peer_address(&address);
+    conn.execute("DELETE FROM sync_peers WHERE address = ?1", [&address])
+        .map_err(|e| format!("Failed to remove sync peer: {}", e))?;
+    audit::record(&conn, &AuditEvent::new("remove_peer", "sync").entity(&address))?;
+    status(&conn, &get_db_path()?)
+}
+
+#[tauri::command]
+pub async fn sync_now() -> Result<SyncRoundResult, String> {
+    sync_round(&get_db_path()?)
+}
+
+/// Download a synced terminal's books; restart the application to finish joining
+#[tauri::command]
+pub async fn join_sync_group(address: String, group_code: String, node_name: String) -> Result<String, String> {
+    let peer_name = request_join(&get_db_path()?, &address, &group_code, &node_name)?;
+    info!(
+        "[SYNC] Downloaded the books of {}; joining at the next start",
+        peer_name
+    );
+    Ok(peer_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The frontend's columns these tables need, plus the Rust-side sync tables
+    fn terminal(dir: &Path) -> PathBuf {
+        let _ = fs::remove_dir_all(dir);
+        fs::create_dir_all(dir).unwrap();
+        let db_path = dir.join("store.db");
+        let conn = Connection::open(&db_path).unwrap();
+        conn.pragma_update(None, "journal_mode", "WAL").unwrap();
+        conn.execute_batch(
+            "CREATE TABLE products (
+                id INTEGER PRIMARY KEY, name TEXT NOT NULL, unit_type TEXT, unit TEXT,
+                current_stock TEXT NOT NULL DEFAULT '0', stock_quantity REAL DEFAULT 0, updated_at TEXT
+            );
+            CREATE TABLE stock_movements (
+                id INTEGER PRIMARY KEY, product_id INTEGER NOT NULL, product_name TEXT NOT NULL,
+                movement_type TEXT NOT NULL, transaction_type TEXT, quantity TEXT NOT NULL DEFAULT '0',
+                unit TEXT NOT NULL DEFAULT 'kg', previous_stock TEXT NOT NULL DEFAULT '',
+                stock_before TEXT NOT NULL DEFAULT '', stock_after TEXT NOT NULL DEFAULT '',
+                new_stock TEXT NOT NULL DEFAULT '', reason TEXT NOT NULL DEFAULT '', reference_type TEXT,
+                reference_id INTEGER, reference_number TEXT, notes TEXT, date TEXT NOT NULL, time TEXT NOT NULL,
+                created_by TEXT NOT NULL DEFAULT 'system'
+            );
+            CREATE TABLE customers (
+                id INTEGER PRIMARY KEY, customer_code TEXT UNIQUE, name TEXT NOT NULL, phone TEXT,
+                balance REAL NOT NULL DEFAULT 0, updated_at TEXT
+            );
+            CREATE TABLE customer_ledger_entries (
+                id INTEGER PRIMARY KEY, customer_id INTEGER NOT NULL, customer_name TEXT NOT NULL,
+                entry_type TEXT NOT NULL, transaction_type TEXT NOT NULL, amount REAL NOT NULL,
+                description TEXT, reference_id INTEGER, reference_number TEXT,
+                balance_before REAL NOT NULL DEFAULT 0, balance_after REAL NOT NULL DEFAULT 0,
+                date TEXT NOT NULL, time TEXT NOT NULL
+            );
+            INSERT INTO products (id, name, unit_type, current_stock, stock_quantity)
+                VALUES (1, 'Pipe', 'piece', '10', 10);
+            INSERT INTO stock_movements (product_id, product_name, movement_type, quantity, stock_before, stock_after,
+                                         reference_type, date, time)
+                VALUES (1, 'Pipe', 'in', '10', '0', '10', 'initial', '2026-01-01', '09:00:00');
+            INSERT INTO customers (id, customer_code, name) VALUES (1, 'C1', 'Ali');",
+        )
+        .unwrap();
+        recycle_bin::ensure_schema(&conn).unwrap();
+        ensure_schema(&conn).unwrap();
+        db_path
+    }
+
+    fn serve_locally(db_path: &Path) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let path = db_path.to_path_buf();
+        thread::spawn(move || serve(listener, path));
+        port
+    }
+
+    fn read<T: rusqlite::types::FromSql>(db_path: &Path, sql: &str) -> T {
+        open_connection_at(db_path)
+            .unwrap()
+            .query_row(sql, [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn two_terminals_converge_over_loopback() {
+        let dir = std::env::temp_dir().join(format!("sync-loopback-{}", std::process::id()));
+        let (a, b) = (terminal(&dir.join("a")), terminal(&dir.join("b")));
+        let (port_a, port_b) = (serve_locally(&a), serve_locally(&b));
+        enable(&open_connection_at(&a).unwrap(), &a, "Counter 1", Some(port_a)).unwrap();
+        let code = status(&open_connection_at(&a).unwrap(), &a)
+            .unwrap()
+            .group_code
+            .unwrap();
+        assert_eq!(code.len(), GROUP_CODE_BYTES * 2);
+
+        // Counter 2 joins with Counter 1's books; a wrong code gets nothing
+        let address = format!("127.0.0.1:{}", port_a);
+        let wrong = "0".repeat(GROUP_CODE_BYTES * 2);
+        assert_eq!(
+            request_join(&b, &address, &wrong, "Counter 2").unwrap_err(),
+            "Wrong sync group code"
+        );
+        assert!(request_join(&b, &address, "1A2B3C4D", "Counter 2").is_err());
+        assert_eq!(request_join(&b, &address, &code, "Counter 2").unwrap(), "Counter 1");
+        assert_eq!(apply_pending_join(&b).unwrap().as_deref(), Some("Counter 1"));
+        open_connection_at(&b)
+            .unwrap()
+            .execute("UPDATE sync_state SET port = ?1", [port_b])
+            .unwrap();
+
+        // Counter 1 sells and books the sale, counter 2 receives stock; both
+        // store the stock they see, and both edit Ali, counter 2 last
+        open_connection_at(&a)
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO stock_movements (product_id, product_name, movement_type, quantity, date, time)
+                    VALUES (1, 'Pipe', 'out', '3', '2026-02-01', '10:00:00');
+                 UPDATE products SET current_stock = '7' WHERE id = 1;
+                 INSERT INTO customer_ledger_entries (customer_id, customer_name, entry_type, transaction_type,
+                                                      amount, balance_after, date, time)
+                    VALUES (1, 'Ali', 'debit', 'invoice', 300, 300, '2026-02-01', '10:00:00');
+                 UPDATE customers SET balance = 300, phone = '111' WHERE id = 1;",
+            )
+            .unwrap();
+        sync_round(&a).unwrap();
+        thread::sleep(Duration::from_millis(20));
+        open_connection_at(&b)
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO stock_movements (product_id, product_name, movement_type, quantity, date, time)
+                    VALUES (1, 'Pipe', 'in', '5', '2026-02-01', '11:00:00');
+                 UPDATE products SET current_stock = '15' WHERE id = 1;
+                 UPDATE customers SET phone = '222' WHERE id = 1;",
+            )
+            .unwrap();
+
+        let round = sync_round(&b).unwrap();
+        assert_eq!((round.conflicts, round.rejected), (1, 0));
+        assert!(round.peers.iter().all(|peer| peer.error.is_none()));
+        let round = sync_round(&a).unwrap();
+        assert_eq!(round.rejected, 0);
+        assert!(round.peers.iter().all(|peer| peer.error.is_none()));
+
+        for db_path in [&a, &b] {
+            let phone: String = read(db_path, "SELECT phone FROM customers WHERE id = 1");
+            assert_eq!(phone, "222");
+            let stock: String = read(db_path, "SELECT current_stock FROM products WHERE id = 1");
+            assert_eq!(stock, "12");
+            let balance: f64 = read(db_path, "SELECT balance FROM customers WHERE name = 'Ali'");
+            assert_eq!(balance, 300.0);
+        }
+
+        // A renewed code shuts counter 2 out until it is entered there
+        renew_group_code(&open_connection_at(&a).unwrap()).unwrap();
+        let round = sync_round(&b).unwrap();
+        assert_eq!(round.peers[0].error.as_deref(), Some("Wrong sync group code"));
+        let code = status(&open_connection_at(&a).unwrap(), &a)
+            .unwrap()
+            .group_code
+            .unwrap();
+        set_group_code(&open_connection_at(&b).unwrap(), &code.to_uppercase()).unwrap();
+        assert!(sync_round(&b).unwrap().peers[0].error.is_none());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn a_failed_rebuild_step_leaves_nothing_half_written() {
+        let dir = std::env::temp_dir().join(format!("sync-rebuild-{}", std::process::id()));
+        let db_path = terminal(&dir);
+        let mut conn = open_connection_at(&db_path).unwrap();
+        // Ali's ledger row carries a stale running balance, and the balance
+        // update after it fails; the stock on hand is stale as well
+        conn.execute_batch(
+            "INSERT INTO customer_ledger_entries (customer_id, customer_name, entry_type, transaction_type, amount,
+                                                  balance_after, date, time)
+                VALUES (1, 'Ali', 'debit', 'invoice', 300, 999, '2026-02-01', '10:00:00');
+             UPDATE products SET current_stock = '4' WHERE id = 1;
+             CREATE TRIGGER refuse_balance BEFORE UPDATE OF balance ON customers
+             BEGIN SELECT RAISE(ABORT, 'balance is locked'); END;",
+        )
+        .unwrap();
+
+        let tx = conn.transaction().unwrap();
+        let affected = Affected {
+            products: BTreeSet::from([1]),
+            customers: BTreeSet::from([1]),
+            ..Affected::default()
+        };
+        rebuild_derived(&tx, &affected).unwrap();
+        tx.commit().unwrap();
+
+        let balance_after: f64 = read(&db_path, "SELECT balance_after FROM customer_ledger_entries");
+        assert_eq!(balance_after, 999.0);
+        let stock: String = read(&db_path, "SELECT current_stock FROM products WHERE id = 1");
+        assert_eq!(stock, "10");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn quantity_shadows_are_not_sent() {
+        let dir = std::env::temp_dir().join(format!("sync-shadows-{}", std::process::id()));
+        let db_path = terminal(&dir);
+        let mut conn = open_connection_at(&db_path).unwrap();
+        quantity::ensure_quantity_columns(&mut conn).unwrap();
+        enable(&conn, &db_path, "Counter 1", Some(0)).unwrap();
+        let state = enabled_state(&conn).unwrap();
+        attach_baseline(&conn, &db_path).unwrap();
+
+        // Refilling a cleared shadow is not a change
+        conn.execute(
+            "UPDATE products SET name = 'PVC Pipe', current_stock = '8' WHERE id = 1",
+            [],
+        )
+        .unwrap();
+        assert_eq!(capture(&mut conn, &state).unwrap(), 1);
+        quantity::ensure_quantity_columns(&mut conn).unwrap();
+        assert_eq!(capture(&mut conn, &state).unwrap(), 0);
+
+        let record: String = conn
+            .query_row(
+                "SELECT record FROM sync_outbox WHERE table_name = 'products' ORDER BY seq DESC LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        let record: SyncRecord = serde_json::from_str(&record).unwrap();
+        assert_eq!(record.values["name"], "PVC Pipe");
+        assert!(!record.values.contains_key("current_stock_base"));
+        drop(conn);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn failed_checks_lock_an_address_out() {
+        let ip: IpAddr = "192.0.2.7".parse().unwrap();
+        for _ in 0..MAX_FAILED_ATTEMPTS {
+            assert!(begin_attempt(ip));
+        }
+        assert!(!begin_attempt(ip));
+        // A proven attempt gives back only its own slot
+        attempt_succeeded(ip);
+        assert!(begin_attempt(ip));
+        assert!(!begin_attempt("192.0.2.7".parse().unwrap()));
+        assert!(begin_attempt("192.0.2.8".parse().unwrap()));
+    }
+
+    #[test]
+    fn proofs_bind_the_code_challenge_and_line() {
+        let key = group_key(&"ab".repeat(GROUP_CODE_BYTES)).unwrap();
+        let other = group_key(&"CD".repeat(GROUP_CODE_BYTES)).unwrap();
+        let proof = prove(&key, "request", "challenge", "nonce", "{}").unwrap();
+        assert!(proof_holds(&key, "request", "challenge", "nonce", "{}", &proof));
+        assert!(!proof_holds(&other, "request", "challenge", "nonce", "{}", &proof));
+        assert!(!proof_holds(&key, "response", "challenge", "nonce", "{}", &proof));
+        assert!(!proof_holds(&key, "request", "other", "nonce", "{}", &proof));
+        assert!(!proof_holds(&key, "request", "challenge", "nonce", "[]", &proof));
+        assert!(!proof_holds(&key, "request", "challenge", "nonce", "{}", "zz"));
+        assert!(group_key("1A2B3C4D").is_err());
+    }
+}
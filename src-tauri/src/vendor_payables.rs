@@ -0,0 +1,1017 @@
+/*!
+ * VENDOR PAYABLES LEDGER
+ * Every stock receiving is a payable and every completed vendor payment
+ * settles payables, both posted to `vendor_ledger_entries` from the
+ * payables point of view: a credit raises what we owe the vendor, a debit
+ * lowers it, so `vendors.balance` = credits - debits.
+ *
+ * The frontend still creates receivings and payments directly, so a sync
+ * brings a vendor up to date in one transaction: missing or changed
+ * postings are posted as differences, payments are re-allocated (the
+ * receiving they name first, then oldest receiving first), and
+ * `stock_receiving.payment_status` and `vendors.balance` are derived from
+ * the result. Money paid beyond all payables is an advance to the vendor.
+ *
+ * A document posts on its own date unless that day is already closed, in
+ * which case it posts today; day and year closings sync payables first.
+ * Aging reads the ledger as posted and never syncs itself.
+ */
+
+use std::collections::BTreeMap;
+
+use log::info;
+use rusqlite::{params, Connection, OptionalExtension, Transaction, TransactionBehavior};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::audit::{self, AuditEvent};
+use crate::database::{current_date, current_time, has_column, open_connection};
+use crate::day_close::is_date_closed;
+use crate::journal::{post_document_in_transaction, SourceType};
+use crate::money::{paisa_from_row, paisa_sql, sum_paisa_sql, Money};
+
+/// Payable of a receiving in paisa: its grand total, or the item cost when no total was stored
+pub fn payable_sql(conn: &Connection) -> rusqlite::Result<String> {
+    let grand_total = paisa_sql(conn, "stock_receiving", "grand_total")?;
+    let total_cost = paisa_sql(conn, "stock_receiving", "total_cost")?;
+    Ok(format!("CASE WHEN {grand_total} > 0 THEN {grand_total} ELSE {total_cost} END"))
+}
+
+/// Create the payment allocation table (payment -> receiving, many to many)
+pub fn ensure_schema(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS vendor_payment_allocations (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            vendor_payment_id INTEGER NOT NULL,
+            receiving_id INTEGER NOT NULL,
+            vendor_id INTEGER NOT NULL,
+            amount REAL NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE(vendor_payment_id, receiving_id),
+            FOREIGN KEY (vendor_payment_id) REFERENCES vendor_payments(id) ON DELETE CASCADE,
+            FOREIGN KEY (receiving_id) REFERENCES stock_receiving(id) ON DELETE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS idx_vendor_payment_allocations_receiving
+            ON vendor_payment_allocations(receiving_id);
+        CREATE INDEX IF NOT EXISTS idx_vendor_payment_allocations_vendor
+            ON vendor_payment_allocations(vendor_id);",
+    )
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VendorPaymentRequest {
+    pub vendor_id: i64,
+    pub amount: Money,
+    pub payment_channel_id: i64,
+    /// Receiving to settle first; the rest goes to the oldest open receivings
+    pub receiving_id: Option<i64>,
+    pub reference_number: Option<String>,
+    pub cheque_number: Option<String>,
+    pub notes: Option<String>,
+    pub created_by: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PaymentAllocation {
+    pub receiving_id: i64,
+    pub receiving_number: String,
+    pub amount: Money,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VendorPaymentResult {
+    pub payment_id: i64,
+    pub payment_number: String,
+    pub allocations: Vec<PaymentAllocation>,
+    pub advance: Money,
+    pub vendor_balance_after: Money,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct VendorSyncResult {
+    pub vendors_synced: usize,
+    pub payables_posted: usize,
+    pub payments_posted: usize,
+    pub adjustments_posted: usize,
+    pub payment_statuses_updated: usize,
+    pub balances_updated: usize,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct VendorAging {
+    pub vendor_id: i64,
+    pub vendor_name: String,
+    pub balance: Money,
+    pub advance: Money,
+    pub current: Money,
+    pub days_1_30: Money,
+    pub days_31_60: Money,
+    pub days_61_90: Money,
+    pub days_over_90: Money,
+    pub open_receivings: usize,
+}
+
+struct VendorRow {
+    id: i64,
+    name: String,
+    balance: Money,
+}
+
+struct ReceivingRow {
+    id: i64,
+    receiving_number: String,
+    date: String,
+    time: String,
+    cancelled: bool,
+    payable: Money,
+    payment_status: Option<String>,
+}
+
+struct PaymentRow {
+    id: i64,
+    payment_number: String,
+    receiving_id: Option<i64>,
+    date: String,
+    time: String,
+    amount: Money,
+    payment_method: String,
+    completed: bool,
+}
+
+/// One vendor ledger posting
+struct Posting<'a> {
+    entry_type: &'a str,
+    transaction_type: &'a str,
+    amount: Money,
+    description: String,
+    reference_type: &'a str,
+    reference_id: i64,
+    reference_number: &'a str,
+    payment_method: Option<&'a str>,
+    date: &'a str,
+    time: &'a str,
+}
+
+/// Signed effect of a ledger row on what the vendor is owed, given its amount in paisa
+pub fn vendor_ledger_effect_sql(amount: &str) -> String {
+    format!("CASE WHEN entry_type = 'credit' THEN {amount} WHEN entry_type = 'debit' THEN -{amount} ELSE 0 END")
+}
+
+/// What the vendor is owed according to the ledger (credits - debits)
+pub fn vendor_ledger_balance(conn: &Connection, vendor_id: i64) -> rusqlite::Result<Money> {
+    let amount = paisa_sql(conn, "vendor_ledger_entries", "amount")?;
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM vendor_ledger_entries WHERE vendor_id = ?1",
+            sum_paisa_sql(&vendor_ledger_effect_sql(&amount))
+        ),
+        [vendor_id],
+        |row| row.get(0).map(Money::from_paisa),
+    )
+}
+
+fn post_entry(tx: &Transaction, vendor: &VendorRow, posting: Posting, created_by: &str) -> Result<(), String> {
+    let balance_before =
+        vendor_ledger_balance(tx, vendor.id).map_err(|e| format!("Failed to compute vendor balance: {}", e))?;
+    let balance_after = match posting.entry_type {
+        "credit" => balance_before + posting.amount,
+        _ => balance_before - posting.amount,
+    };
+
+    tx.execute(
+        "INSERT INTO vendor_ledger_entries (
+            vendor_id, vendor_name, entry_type, transaction_type, amount, balance_before, balance_after,
+            description, reference_type, reference_id, reference_number, payment_method, date, time, created_by
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+        params![
+            vendor.id,
+            vendor.name,
+            posting.entry_type,
+            posting.transaction_type,
+            posting.amount.to_rupees(),
+            balance_before.to_rupees(),
+            balance_after.to_rupees(),
+            posting.description,
+            posting.reference_type,
+            posting.reference_id,
+            posting.reference_number,
+            posting.payment_method,
+            posting.date,
+            posting.time,
+            created_by
+        ],
+    )
+    .map_err(|e| format!("Failed to post vendor ledger entry: {}", e))?;
+    Ok(())
+}
+
+/// Net amount already posted for a document, signed so that a payable is
+/// positive for receivings and a payment is positive for payments
+fn posted_amount(tx: &Transaction, vendor_id: i64, reference_type: &str, reference_id: i64) -> Result<Money, String> {
+    let positive = if reference_type == "purchase" { "credit" } else { "debit" };
+    let amount =
+        paisa_sql(tx, "vendor_ledger_entries", "amount").map_err(|e| format!("Failed to inspect schema: {}", e))?;
+    tx.query_row(
+        &format!(
+            "SELECT {} FROM vendor_ledger_entries
+             WHERE vendor_id = ?1 AND reference_type = ?2 AND reference_id = ?3",
+            sum_paisa_sql(&format!(
+                "CASE WHEN entry_type = '{positive}' THEN {amount}
+                      WHEN entry_type IN ('credit', 'debit') THEN -{amount} ELSE 0 END"
+            ))
+        ),
+        params![vendor_id, reference_type, reference_id],
+        |row| row.get(0).map(Money::from_paisa),
+    )
+    .map_err(|e| format!("Failed to read vendor postings: {}", e))
+}
+
+fn load_vendor(tx: &Transaction, vendor_id: i64) -> Result<VendorRow, String> {
+    let balance = paisa_sql(tx, "vendors", "balance").map_err(|e| format!("Failed to inspect schema: {}", e))?;
+    tx.query_row(&format!("SELECT id, name, {balance} FROM vendors WHERE id = ?1"), [vendor_id], |row| {
+        Ok(VendorRow {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            balance: paisa_from_row(row, 2)?,
+        })
+    })
+    .optional()
+    .map_err(|e| format!("Failed to load vendor: {}", e))?
+    .ok_or_else(|| format!("Vendor {} not found", vendor_id))
+}
+
+fn load_receivings(tx: &Transaction, vendor_id: i64) -> Result<Vec<ReceivingRow>, String> {
+    let mut stmt = tx
+        .prepare(&format!(
+            "SELECT id, receiving_number, received_date, received_time, status, {}, payment_status
+             FROM stock_receiving WHERE vendor_id = ?1
+             ORDER BY received_date, id",
+            payable_sql(tx).map_err(|e| format!("Failed to inspect schema: {}", e))?
+        ))
+        .map_err(|e| format!("Failed to query stock receivings: {}", e))?;
+    let rows = stmt
+        .query_map([vendor_id], |row| {
+            let status: String = row.get(4)?;
+            Ok(ReceivingRow {
+                id: row.get(0)?,
+                receiving_number: row.get(1)?,
+                date: row.get(2)?,
+                time: row.get(3)?,
+                cancelled: status == "cancelled",
+                payable: paisa_from_row(row, 5)?,
+                payment_status: row.get(6)?,
+            })
+        })
+        .map_err(|e| format!("Failed to read stock receivings: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read stock receiving: {}", e))
+}
+
+fn load_payments(tx: &Transaction, vendor_id: i64) -> Result<Vec<PaymentRow>, String> {
+    let amount = paisa_sql(tx, "vendor_payments", "amount").map_err(|e| format!("Failed to inspect schema: {}", e))?;
+    let mut stmt = tx
+        .prepare(&format!(
+            "SELECT id, payment_number, receiving_id, date, time, {amount}, payment_method, status
+             FROM vendor_payments WHERE vendor_id = ?1
+             ORDER BY date, id"
+        ))
+        .map_err(|e| format!("Failed to query vendor payments: {}", e))?;
+    let rows = stmt
+        .query_map([vendor_id], |row| {
+            let status: String = row.get(7)?;
+            Ok(PaymentRow {
+                id: row.get(0)?,
+                payment_number: row.get(1)?,
+                receiving_id: row.get(2)?,
+                date: row.get(3)?,
+                time: row.get(4)?,
+                amount: paisa_from_row(row, 5)?,
+                payment_method: row.get(6)?,
+                completed: status == "completed",
+            })
+        })
+        .map_err(|e| format!("Failed to read vendor payments: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read vendor payment: {}", e))
+}
+
+/// Bring one vendor's payables ledger, allocations, payment statuses and
+/// balance up to date with its receivings and payments
+pub fn sync_vendor_in_transaction(tx: &Transaction, vendor_id: i64, result: &mut VendorSyncResult) -> Result<Money, String> {
+    let vendor = load_vendor(tx, vendor_id)?;
+    let receivings = load_receivings(tx, vendor_id)?;
+    let payments = load_payments(tx, vendor_id)?;
+    let today = current_date(tx).map_err(|e| format!("Failed to read date: {}", e))?;
+    let now = current_time(tx).map_err(|e| format!("Failed to read time: {}", e))?;
+
+    // 1. Payables: post the difference between what each receiving owes and what is posted
+    for receiving in &receivings {
+        let expected = if receiving.cancelled { Money::ZERO } else { receiving.payable };
+        let posted = posted_amount(tx, vendor.id, "purchase", receiving.id)?;
+        let difference = expected - posted;
+        if difference.is_zero() {
+            continue;
+        }
+        let first_posting = posted.is_zero() && !difference.is_negative();
+        let own_date = first_posting && !is_date_closed(tx, &receiving.date)?;
+        post_entry(
+            tx,
+            &vendor,
+            Posting {
+                entry_type: if difference.is_negative() { "debit" } else { "credit" },
+                transaction_type: if first_posting { "purchase" } else { "adjustment" },
+                amount: difference.abs(),
+                description: if first_posting {
+                    format!("Stock receiving {}", receiving.receiving_number)
+                } else if receiving.cancelled {
+                    format!("Stock receiving {} cancelled", receiving.receiving_number)
+                } else {
+                    format!("Stock receiving {} revised", receiving.receiving_number)
+                },
+                reference_type: "purchase",
+                reference_id: receiving.id,
+                reference_number: &receiving.receiving_number,
+                payment_method: None,
+                date: if own_date { &receiving.date } else { &today },
+                time: if own_date { &receiving.time } else { &now },
+            },
+            "system",
+        )?;
+        if first_posting {
+            result.payables_posted += 1;
+        } else {
+            result.adjustments_posted += 1;
+        }
+    }
+
+    // 2. Payments: same for every payment, completed ones count in full
+    for payment in &payments {
+        let expected = if payment.completed { payment.amount } else { Money::ZERO };
+        let posted = posted_amount(tx, vendor.id, "payment", payment.id)?;
+        let difference = expected - posted;
+        if difference.is_zero() {
+            continue;
+        }
+        let first_posting = posted.is_zero() && !difference.is_negative();
+        let own_date = first_posting && !is_date_closed(tx, &payment.date)?;
+        post_entry(
+            tx,
+            &vendor,
+            Posting {
+                entry_type: if difference.is_negative() { "credit" } else { "debit" },
+                transaction_type: if first_posting { "payment" } else { "adjustment" },
+                amount: difference.abs(),
+                description: if first_posting {
+                    format!("Payment {}", payment.payment_number)
+                } else {
+                    format!("Payment {} reversed", payment.payment_number)
+                },
+                reference_type: "payment",
+                reference_id: payment.id,
+                reference_number: &payment.payment_number,
+                payment_method: Some(&payment.payment_method),
+                date: if own_date { &payment.date } else { &today },
+                time: if own_date { &payment.time } else { &now },
+            },
+            "system",
+        )?;
+        if first_posting {
+            result.payments_posted += 1;
+        } else {
+            result.adjustments_posted += 1;
+        }
+    }
+
+    // 3. Allocations: the named receiving first, then oldest receiving first
+    tx.execute("DELETE FROM vendor_payment_allocations WHERE vendor_id = ?1", [vendor.id])
+        .map_err(|e| format!("Failed to reset payment allocations: {}", e))?;
+    let mut outstanding: BTreeMap<i64, Money> = receivings
+        .iter()
+        .filter(|receiving| !receiving.cancelled)
+        .map(|receiving| (receiving.id, receiving.payable))
+        .collect();
+    let mut allocated: BTreeMap<i64, Money> = BTreeMap::new();
+
+    for payment in payments.iter().filter(|payment| payment.completed) {
+        let mut remaining = payment.amount;
+        let preferred = payment.receiving_id.filter(|id| outstanding.contains_key(id));
+        let order = preferred.into_iter().chain(
+            receivings
+                .iter()
+                .map(|receiving| receiving.id)
+                .filter(|id| Some(*id) != preferred),
+        );
+        for receiving_id in order {
+            if remaining.is_zero() {
+                break;
+            }
+            let Some(open) = outstanding.get_mut(&receiving_id) else {
+                continue;
+            };
+            let amount = remaining.min(*open);
+            if amount <= Money::ZERO {
+                continue;
+            }
+            tx.execute(
+                "INSERT INTO vendor_payment_allocations (vendor_payment_id, receiving_id, vendor_id, amount)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![payment.id, receiving_id, vendor.id, amount.to_rupees()],
+            )
+            .map_err(|e| format!("Failed to allocate payment {}: {}", payment.payment_number, e))?;
+            *open -= amount;
+            remaining -= amount;
+            *allocated.entry(receiving_id).or_default() += amount;
+        }
+    }
+
+    // 4. Payment status follows from the allocations
+    for receiving in receivings.iter().filter(|receiving| !receiving.cancelled) {
+        let paid = allocated.get(&receiving.id).copied().unwrap_or_default();
+        let status = if paid >= receiving.payable && receiving.payable > Money::ZERO {
+            "paid"
+        } else if paid > Money::ZERO {
+            "partial"
+        } else {
+            "pending"
+        };
+        if receiving.payment_status.as_deref() != Some(status) {
+            tx.execute(
+                "UPDATE stock_receiving SET payment_status = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+                params![status, receiving.id],
+            )
+            .map_err(|e| format!("Failed to update payment status of {}: {}", receiving.receiving_number, e))?;
+            result.payment_statuses_updated += 1;
+        }
+    }
+
+    // 5. The stored balance is whatever the ledger says
+    let balance = vendor_ledger_balance(tx, vendor.id).map_err(|e| format!("Failed to compute vendor balance: {}", e))?;
+    if balance != vendor.balance {
+        tx.execute(
+            "UPDATE vendors SET balance = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+            params![balance.to_rupees(), vendor.id],
+        )
+        .map_err(|e| format!("Failed to update vendor balance: {}", e))?;
+        result.balances_updated += 1;
+    }
+    result.vendors_synced += 1;
+
+    Ok(balance)
+}
+
+/// Sync one vendor, or every vendor when `vendor_id` is None
+pub fn sync_vendors_in_transaction(tx: &Transaction, vendor_id: Option<i64>) -> Result<VendorSyncResult, String> {
+    let vendor_ids: Vec<i64> = {
+        let mut stmt = tx
+            .prepare("SELECT id FROM vendors WHERE ?1 IS NULL OR id = ?1 ORDER BY id")
+            .map_err(|e| format!("Failed to query vendors: {}", e))?;
+        let rows = stmt
+            .query_map([vendor_id], |row| row.get(0))
+            .map_err(|e| format!("Failed to read vendors: {}", e))?;
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read vendor: {}", e))?
+    };
+    if let (Some(id), true) = (vendor_id, vendor_ids.is_empty()) {
+        return Err(format!("Vendor {} not found", id));
+    }
+
+    let mut result = VendorSyncResult::default();
+    for id in vendor_ids {
+        sync_vendor_in_transaction(tx, id, &mut result)?;
+    }
+    Ok(result)
+}
+
+/// VP-YYYYMMDD-NNNN, numbered per day
+fn next_payment_number(tx: &Transaction, date: &str) -> Result<String, String> {
+    let prefix = format!("VP-{}-", date.replace('-', ""));
+    let last: Option<String> = tx
+        .query_row(
+            "SELECT payment_number FROM vendor_payments WHERE payment_number GLOB ?1 || '[0-9][0-9][0-9][0-9]'
+             ORDER BY payment_number DESC LIMIT 1",
+            [&prefix],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to generate payment number: {}", e))?;
+
+    let next = last
+        .and_then(|number| number[prefix.len()..].parse::<u32>().ok())
+        .unwrap_or(0)
+        + 1;
+    Ok(format!("{}{:04}", prefix, next))
+}
+
+/// Map a payment channel type onto the vendor_payments.payment_method CHECK values
+fn payment_method_for_channel(channel_type: &str) -> &'static str {
+    match channel_type {
+        "cash" => "cash",
+        "bank" => "bank",
+        "cheque" => "cheque",
+        "card" => "card",
+        "online" | "mobile_money" => "online",
+        _ => "other",
+    }
+}
+
+/// Record a vendor payment, post it and allocate it across the vendor's receivings
+pub fn record_vendor_payment_in_transaction(
+    tx: &Transaction,
+    request: &VendorPaymentRequest,
+) -> Result<VendorPaymentResult, String> {
+    if request.amount <= Money::ZERO {
+        return Err("Payment amount must be greater than 0".to_string());
+    }
+    let vendor = load_vendor(tx, request.vendor_id)?;
+
+    if let Some(receiving_id) = request.receiving_id {
+        let status: String = tx
+            .query_row(
+                "SELECT status FROM stock_receiving WHERE id = ?1 AND vendor_id = ?2",
+                params![receiving_id, vendor.id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("Failed to load stock receiving: {}", e))?
+            .ok_or_else(|| format!("Stock receiving {} does not belong to {}", receiving_id, vendor.name))?;
+        if status == "cancelled" {
+            return Err(format!("Stock receiving {} is cancelled", receiving_id));
+        }
+    }
+
+    let (channel_name, channel_type): (String, String) = tx
+        .query_row(
+            "SELECT name, type FROM payment_channels WHERE id = ?1 AND is_active = 1",
+            [request.payment_channel_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to load payment channel: {}", e))?
+        .ok_or_else(|| format!("Payment channel {} not found or inactive", request.payment_channel_id))?;
+
+    let date = current_date(tx).map_err(|e| format!("Failed to read date: {}", e))?;
+    let time = current_time(tx).map_err(|e| format!("Failed to read time: {}", e))?;
+    let payment_number = next_payment_number(tx, &date)?;
+    let created_by = match request.created_by.trim() {
+        "" => "system",
+        user => user,
+    };
+
+    tx.execute(
+        "INSERT INTO vendor_payments (
+            payment_number, vendor_id, vendor_name, receiving_id, amount, net_amount, payment_method,
+            payment_channel_id, payment_channel_name, reference_number, cheque_number, notes,
+            status, date, time, created_by
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?5, ?6, ?7, ?8, ?9, ?10, ?11, 'completed', ?12, ?13, ?14)",
+        params![
+            payment_number,
+            vendor.id,
+            vendor.name,
+            request.receiving_id,
+            request.amount.to_rupees(),
+            payment_method_for_channel(&channel_type),
+            request.payment_channel_id,
+            channel_name,
+            request.reference_number,
+            request.cheque_number,
+            request.notes.clone().unwrap_or_default(),
+            date,
+            time,
+            created_by
+        ],
+    )
+    .map_err(|e| format!("Failed to record vendor payment: {}", e))?;
+    let payment_id = tx.last_insert_rowid();
+
+    if has_column(tx, "payment_channels", "total_outgoing").map_err(|e| format!("Failed to inspect schema: {}", e))? {
+        tx.execute(
+            "UPDATE payment_channels SET total_outgoing = COALESCE(total_outgoing, 0) + ?1,
+                updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+            params![request.amount.to_rupees(), request.payment_channel_id],
+        )
+        .map_err(|e| format!("Failed to update payment channel totals: {}", e))?;
+    }
+
+    let vendor_balance_after = sync_vendor_in_transaction(tx, vendor.id, &mut VendorSyncResult::default())?;
+    post_document_in_transaction(tx, SourceType::VendorPayment, payment_id)?;
+
+    let allocations: Vec<PaymentAllocation> = {
+        let amount = paisa_sql(tx, "vendor_payment_allocations", "a.amount")
+            .map_err(|e| format!("Failed to inspect schema: {}", e))?;
+        let mut stmt = tx
+            .prepare(&format!(
+                "SELECT a.receiving_id, r.receiving_number, {amount}
+                 FROM vendor_payment_allocations a
+                 JOIN stock_receiving r ON r.id = a.receiving_id
+                 WHERE a.vendor_payment_id = ?1
+                 ORDER BY a.id"
+            ))
+            .map_err(|e| format!("Failed to query allocations: {}", e))?;
+        let rows = stmt
+            .query_map([payment_id], |row| {
+                Ok(PaymentAllocation {
+                    receiving_id: row.get(0)?,
+                    receiving_number: row.get(1)?,
+                    amount: paisa_from_row(row, 2)?,
+                })
+            })
+            .map_err(|e| format!("Failed to read allocations: {}", e))?;
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read allocation: {}", e))?
+    };
+    let advance = request.amount - allocations.iter().map(|allocation| allocation.amount).sum::<Money>();
+
+    info!(
+        "[VENDOR] {} paid Rs.{} to {} ({} receivings settled, Rs.{} advance)",
+        payment_number,
+        request.amount,
+        vendor.name,
+        allocations.len(),
+        advance
+    );
+
+    Ok(VendorPaymentResult {
+        payment_id,
+        payment_number,
+        allocations,
+        advance,
+        vendor_balance_after,
+    })
+}
+
+/// Outstanding payables per vendor bucketed by days past the vendor's credit terms
+pub fn vendor_aging(conn: &Connection, vendor_id: Option<i64>) -> Result<Vec<VendorAging>, String> {
+    let mut aging: BTreeMap<i64, VendorAging> = BTreeMap::new();
+    {
+        let balance = paisa_sql(conn, "vendors", "balance").map_err(|e| format!("Failed to inspect schema: {}", e))?;
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT id, name, {balance} FROM vendors
+                 WHERE (?1 IS NULL OR id = ?1) ORDER BY id"
+            ))
+            .map_err(|e| format!("Failed to query vendors: {}", e))?;
+        let rows = stmt
+            .query_map([vendor_id], |row| {
+                Ok(VendorAging {
+                    vendor_id: row.get(0)?,
+                    vendor_name: row.get(1)?,
+                    balance: paisa_from_row(row, 2)?,
+                    ..VendorAging::default()
+                })
+            })
+            .map_err(|e| format!("Failed to read vendors: {}", e))?;
+        for row in rows {
+            let vendor = row.map_err(|e| format!("Failed to read vendor: {}", e))?;
+            aging.insert(vendor.vendor_id, vendor);
+        }
+    }
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT r.vendor_id, r.payable, COALESCE(a.paid, 0),
+                    CAST(julianday(date('now', 'localtime')) - julianday(r.received_date) AS INTEGER)
+                        - COALESCE(v.credit_days, 0)
+             FROM (SELECT id, vendor_id, received_date, {payable} AS payable
+                   FROM stock_receiving WHERE status != 'cancelled') r
+             JOIN vendors v ON v.id = r.vendor_id
+             LEFT JOIN (SELECT receiving_id, {paid} AS paid
+                        FROM vendor_payment_allocations GROUP BY receiving_id) a ON a.receiving_id = r.id
+             WHERE ?1 IS NULL OR r.vendor_id = ?1",
+            payable = payable_sql(conn).map_err(|e| format!("Failed to inspect schema: {}", e))?,
+            paid = sum_paisa_sql(
+                &paisa_sql(conn, "vendor_payment_allocations", "amount")
+                    .map_err(|e| format!("Failed to inspect schema: {}", e))?
+            ),
+        ))
+        .map_err(|e| format!("Failed to query open receivings: {}", e))?;
+    let rows = stmt
+        .query_map([vendor_id], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                paisa_from_row(row, 1)?,
+                Money::from_paisa(row.get(2)?),
+                row.get::<_, Option<i64>>(3)?.unwrap_or(0),
+            ))
+        })
+        .map_err(|e| format!("Failed to read open receivings: {}", e))?;
+
+    for row in rows {
+        let (vendor_id, payable, paid, days_overdue) = row.map_err(|e| format!("Failed to read open receiving: {}", e))?;
+        let Some(vendor) = aging.get_mut(&vendor_id) else {
+            continue;
+        };
+        let open = payable - paid;
+        if open <= Money::ZERO {
+            continue;
+        }
+        vendor.open_receivings += 1;
+        let bucket = match days_overdue {
+            i64::MIN..=0 => &mut vendor.current,
+            1..=30 => &mut vendor.days_1_30,
+            31..=60 => &mut vendor.days_31_60,
+            61..=90 => &mut vendor.days_61_90,
+            _ => &mut vendor.days_over_90,
+        };
+        *bucket += open;
+    }
+
+    // Whatever the ledger balance does not explain by open receivings is paid in advance
+    for vendor in aging.values_mut() {
+        let open = vendor.current + vendor.days_1_30 + vendor.days_31_60 + vendor.days_61_90 + vendor.days_over_90;
+        vendor.advance = (open - vendor.balance).max(Money::ZERO);
+    }
+
+    Ok(aging.into_values().collect())
+}
+
+/// Record a vendor payment and allocate it to the vendor's open receivings
+#[tauri::command]
+pub async fn record_vendor_payment(request: VendorPaymentRequest) -> Result<VendorPaymentResult, String> {
+    let mut conn = open_connection()?;
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let result = record_vendor_payment_in_transaction(&tx, &request)?;
+    audit::record(
+        &tx,
+        &AuditEvent::new("payment", "vendor")
+            .entity(request.vendor_id)
+            .by(&request.created_by)
+            .after(json!(&result)),
+    )?;
+
+    tx.commit()
+        .map_err(|e| format!("Failed to commit vendor payment: {}", e))?;
+
+    Ok(result)
+}
+
+/// Post receivings and payments the frontend created, re-allocate payments
+/// and derive payment statuses and balances for one vendor or all of them
+#[tauri::command]
+pub async fn sync_vendor_payables(vendor_id: Option<i64>) -> Result<VendorSyncResult, String> {
+    let mut conn = open_connection()?;
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let result = sync_vendors_in_transaction(&tx, vendor_id)?;
+
+    tx.commit()
+        .map_err(|e| format!("Failed to commit vendor sync: {}", e))?;
+
+    info!(
+        "[VENDOR] Synced {} vendors: {} payables, {} payments, {} adjustments posted",
+        result.vendors_synced, result.payables_posted, result.payments_posted, result.adjustments_posted
+    );
+    Ok(result)
+}
+
+/// Vendor balances and payables aging from the payables ledger as posted.
+/// Reading takes no write lock; receivings and payments entered since the
+/// last sync count once `sync_vendor_payables` or a day closing has run.
+#[tauri::command]
+pub async fn get_vendor_aging(vendor_id: Option<i64>) -> Result<Vec<VendorAging>, String> {
+    let mut conn = open_connection()?;
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Deferred)
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let aging = vendor_aging(&tx, vendor_id)?;
+    tx.finish()
+        .map_err(|e| format!("Failed to finish reading vendor aging: {}", e))?;
+
+    Ok(aging)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{day_close, journal};
+
+    /// The frontend's columns these tables need, plus the journal and day locks
+    fn database() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE vendors (
+                id INTEGER PRIMARY KEY, name TEXT NOT NULL, balance REAL NOT NULL DEFAULT 0,
+                credit_days INTEGER DEFAULT 0, updated_at TEXT
+            );
+            CREATE TABLE stock_receiving (
+                id INTEGER PRIMARY KEY, receiving_number TEXT UNIQUE NOT NULL, vendor_id INTEGER,
+                vendor_name TEXT NOT NULL, received_date TEXT NOT NULL, received_time TEXT NOT NULL DEFAULT '09:00:00',
+                status TEXT NOT NULL DEFAULT 'completed', total_cost REAL NOT NULL DEFAULT 0,
+                grand_total REAL NOT NULL DEFAULT 0, payment_status TEXT DEFAULT 'pending', updated_at TEXT
+            );
+            CREATE TABLE vendor_payments (
+                id INTEGER PRIMARY KEY, payment_number TEXT UNIQUE NOT NULL, vendor_id INTEGER NOT NULL,
+                vendor_name TEXT NOT NULL, receiving_id INTEGER, amount REAL NOT NULL, net_amount REAL NOT NULL,
+                payment_method TEXT NOT NULL DEFAULT 'cash', payment_channel_id INTEGER, payment_channel_name TEXT,
+                reference_number TEXT, cheque_number TEXT, status TEXT NOT NULL DEFAULT 'completed', notes TEXT,
+                date TEXT NOT NULL, time TEXT NOT NULL DEFAULT '10:00:00', created_by TEXT NOT NULL DEFAULT 'system'
+            );
+            CREATE TABLE vendor_ledger_entries (
+                id INTEGER PRIMARY KEY, vendor_id INTEGER NOT NULL, vendor_name TEXT NOT NULL,
+                entry_type TEXT NOT NULL, transaction_type TEXT NOT NULL, amount REAL NOT NULL,
+                balance_before REAL NOT NULL DEFAULT 0, balance_after REAL NOT NULL DEFAULT 0,
+                description TEXT NOT NULL, reference_type TEXT, reference_id INTEGER, reference_number TEXT,
+                payment_method TEXT, date TEXT NOT NULL, time TEXT NOT NULL, created_by TEXT NOT NULL DEFAULT 'system'
+            );
+            CREATE TABLE payment_channels (
+                id INTEGER PRIMARY KEY, name TEXT NOT NULL, type TEXT NOT NULL, is_active INTEGER NOT NULL DEFAULT 1,
+                total_outgoing REAL DEFAULT 0, updated_at TEXT
+            );
+            INSERT INTO vendors (id, name) VALUES (1, 'Steel Mills');
+            INSERT INTO payment_channels (id, name, type) VALUES (1, 'Cash', 'cash');",
+        )
+        .unwrap();
+        ensure_schema(&conn).unwrap();
+        journal::ensure_schema(&conn).unwrap();
+        day_close::ensure_schema(&conn).unwrap();
+        conn
+    }
+
+    fn rs(rupees: i64) -> Money {
+        Money::from_paisa(rupees * 100)
+    }
+
+    /// A receiving the frontend wrote `days_ago` days back
+    fn receive(conn: &Connection, number: &str, days_ago: i64, total: f64) -> i64 {
+        conn.execute(
+            "INSERT INTO stock_receiving (receiving_number, vendor_id, vendor_name, received_date, grand_total)
+             VALUES (?1, 1, 'Steel Mills', date('now', 'localtime', '-' || ?2 || ' days'), ?3)",
+            params![number, days_ago, total],
+        )
+        .unwrap();
+        conn.last_insert_rowid()
+    }
+
+    fn pay(tx: &Transaction, amount: i64, receiving_id: Option<i64>) -> VendorPaymentResult {
+        let request = VendorPaymentRequest {
+            vendor_id: 1,
+            amount: rs(amount),
+            payment_channel_id: 1,
+            receiving_id,
+            reference_number: None,
+            cheque_number: None,
+            notes: None,
+            created_by: "admin".to_string(),
+        };
+        record_vendor_payment_in_transaction(tx, &request).unwrap()
+    }
+
+    fn payment_statuses(conn: &Connection) -> Vec<String> {
+        let mut stmt = conn
+            .prepare("SELECT payment_status FROM stock_receiving ORDER BY id")
+            .unwrap();
+        let rows = stmt.query_map([], |row| row.get(0)).unwrap();
+        rows.collect::<Result<_, _>>().unwrap()
+    }
+
+    fn postings(conn: &Connection) -> Vec<(String, String, String)> {
+        let mut stmt = conn
+            .prepare("SELECT reference_number, transaction_type, date FROM vendor_ledger_entries ORDER BY id")
+            .unwrap();
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap();
+        rows.collect::<Result<_, _>>().unwrap()
+    }
+
+    fn days_ago(conn: &Connection, days: i64) -> String {
+        conn.query_row("SELECT date('now', 'localtime', '-' || ?1 || ' days')", [days], |row| {
+            row.get(0)
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn payments_settle_the_named_receiving_then_the_oldest() {
+        let mut conn = database();
+        let oldest = receive(&conn, "SR-1", 60, 1000.0);
+        let named = receive(&conn, "SR-2", 10, 500.0);
+        receive(&conn, "SR-3", 0, 300.0);
+        let tx = conn.transaction().unwrap();
+
+        let first = pay(&tx, 700, Some(named));
+        let settled: Vec<(i64, Money)> = first.allocations.iter().map(|a| (a.receiving_id, a.amount)).collect();
+        assert_eq!(settled, vec![(named, rs(500)), (oldest, rs(200))]);
+        assert_eq!(first.advance, Money::ZERO);
+        assert_eq!(first.vendor_balance_after, rs(1100));
+        assert_eq!(payment_statuses(&tx), ["partial", "paid", "pending"]);
+
+        // Paying beyond every payable leaves an advance with the vendor
+        let second = pay(&tx, 1500, None);
+        assert_eq!(second.allocations.len(), 2);
+        assert_eq!(second.advance, rs(400));
+        assert_eq!(second.vendor_balance_after, rs(-400));
+        assert_eq!(payment_statuses(&tx), ["paid", "paid", "paid"]);
+
+        let aging = vendor_aging(&tx, Some(1)).unwrap();
+        assert_eq!((aging[0].open_receivings, aging[0].advance), (0, rs(400)));
+
+        // Cancelling a receiving reverses its payable and frees what was allocated to it
+        tx.execute(
+            "UPDATE stock_receiving SET status = 'cancelled' WHERE id = ?1",
+            [oldest],
+        )
+        .unwrap();
+        let mut result = VendorSyncResult::default();
+        let balance = sync_vendor_in_transaction(&tx, 1, &mut result).unwrap();
+        assert_eq!((balance, result.adjustments_posted), (rs(-1400), 1));
+        assert_eq!(vendor_aging(&tx, Some(1)).unwrap()[0].advance, rs(1400));
+    }
+
+    #[test]
+    fn aging_buckets_open_amounts_past_credit_terms() {
+        let mut conn = database();
+        for (number, days, total) in [
+            ("SR-1", 0, 100.0),
+            ("SR-2", 15, 200.0),
+            ("SR-3", 45, 300.0),
+            ("SR-4", 75, 400.0),
+            ("SR-5", 120, 500.0),
+        ] {
+            receive(&conn, number, days, total);
+        }
+        let tx = conn.transaction().unwrap();
+        // Oldest first: SR-5 settled, SR-4 half paid
+        pay(&tx, 700, None);
+
+        let before = postings(&tx).len();
+        let aging = vendor_aging(&tx, None).unwrap();
+        assert_eq!(postings(&tx).len(), before);
+        let vendor = &aging[0];
+        assert_eq!(vendor.balance, rs(800));
+        assert_eq!(
+            [
+                vendor.current,
+                vendor.days_1_30,
+                vendor.days_31_60,
+                vendor.days_61_90,
+                vendor.days_over_90
+            ],
+            [100, 200, 300, 200, 0].map(rs)
+        );
+        assert_eq!((vendor.open_receivings, vendor.advance), (4, Money::ZERO));
+
+        // Thirty days of credit move everything one bucket younger
+        tx.execute("UPDATE vendors SET credit_days = 30 WHERE id = 1", [])
+            .unwrap();
+        let vendor = &vendor_aging(&tx, Some(1)).unwrap()[0];
+        assert_eq!(
+            [
+                vendor.current,
+                vendor.days_1_30,
+                vendor.days_31_60,
+                vendor.days_61_90,
+                vendor.days_over_90
+            ],
+            [300, 300, 200, 0, 0].map(rs)
+        );
+    }
+
+    #[test]
+    fn closing_a_day_posts_its_unposted_receivings() {
+        let mut conn = database();
+        receive(&conn, "SR-1", 1, 1000.0);
+        conn.execute(
+            "INSERT INTO vendor_payments (payment_number, vendor_id, vendor_name, amount, net_amount,
+                                          payment_channel_id, status, date)
+             VALUES ('VP-1', 1, 'Steel Mills', 400, 400, 1, 'pending', date('now', 'localtime', '-1 day'))",
+            [],
+        )
+        .unwrap();
+        let (yesterday, today) = (days_ago(&conn, 1), days_ago(&conn, 0));
+
+        let tx = conn.transaction().unwrap();
+        day_close::close_day_in_transaction(&tx, &yesterday, "admin", None).unwrap();
+        assert_eq!(
+            postings(&tx),
+            [("SR-1".to_string(), "purchase".to_string(), yesterday.clone())]
+        );
+
+        // Completed after the close: posts today instead of failing on the locked day
+        tx.execute("UPDATE vendor_payments SET status = 'completed'", [])
+            .unwrap();
+        let result = sync_vendors_in_transaction(&tx, Some(1)).unwrap();
+        assert_eq!(result.payments_posted, 1);
+        assert_eq!(
+            postings(&tx)[1],
+            ("VP-1".to_string(), "payment".to_string(), today.clone())
+        );
+
+        // A day closed before its receivings were posted
+        tx.execute_batch("DROP TRIGGER trg_stock_receiving_day_lock_insert")
+            .unwrap();
+        receive(&tx, "SR-2", 3, 250.0);
+        tx.execute(
+            "INSERT INTO daily_closings (date, status, closed_by) VALUES (?1, 'closed', 'admin')",
+            [days_ago(&tx, 3)],
+        )
+        .unwrap();
+        let balance = sync_vendor_in_transaction(&tx, 1, &mut VendorSyncResult::default()).unwrap();
+        assert_eq!(balance, rs(850));
+        assert_eq!(postings(&tx)[2], ("SR-2".to_string(), "purchase".to_string(), today));
+    }
+}
@@ -6,8 +6,8 @@
 
 use std::path::PathBuf;
 use std::fs;
-use std::process::Command;
 use std::env;
+use log::{error, info, warn};
 
 /// Get the proper Windows app data directory for any Windows system
 pub fn get_windows_app_data_dir(app_name: &str) -> Result<PathBuf, String> {
@@ -75,60 +75,17 @@ fn ensure_directory_writable(path: &PathBuf) -> Result<(), String> {
     }
 }
 
-/// Production-grade Windows restart mechanism
-pub fn windows_restart_application(exe_path: Option<String>) -> Result<(), String> {
-    println!("🔄 [WINDOWS-RESTART] Initiating production-grade restart...");
-    
-    // Strategy 1: Use Windows Shell to restart (most reliable)
-    let restart_command = if let Some(exe) = exe_path {
-        format!("timeout /t 2 /nobreak && start \"\" \"{}\"", exe)
-    } else {
-        // Use the current process path
-        match env::current_exe() {
-            Ok(current_exe) => {
-                let exe_str = current_exe.to_string_lossy();
-                format!("timeout /t 2 /nobreak && start \"\" \"{}\"", exe_str)
-            }
-            Err(_) => {
-                return Err("Cannot determine executable path for restart".to_string());
-            }
-        }
-    };
-    
-    // Launch restart command in background
-    match Command::new("cmd")
-        .args(&["/C", &restart_command])
-        .spawn()
-    {
-        Ok(_) => {
-            println!("✅ [WINDOWS-RESTART] Restart command launched successfully");
-            // Exit current process after delay
-            std::thread::spawn(|| {
-                std::thread::sleep(std::time::Duration::from_millis(1000));
-                std::process::exit(0);
-            });
-            Ok(())
-        }
-        Err(e) => {
-            println!("❌ [WINDOWS-RESTART] Command failed: {}", e);
-            
-            // Fallback: Manual exit with clear instructions
-            Err(format!("Automatic restart failed. Please restart the application manually. Error: {}", e))
-        }
-    }
-}
-
 /// Enterprise-grade database file replacement for Windows
 pub fn windows_safe_file_replace(
     source: &PathBuf,
     target: &PathBuf,
     backup_target: &PathBuf
 ) -> Result<(), String> {
-    println!("🔧 [WINDOWS-REPLACE] Starting enterprise file replacement...");
+    info!("[WINDOWS-REPLACE] Starting enterprise file replacement...");
     
     // Step 1: Create backup if target exists
     if target.exists() {
-        println!("🛡️ [WINDOWS-REPLACE] Creating safety backup...");
+        info!("[WINDOWS-REPLACE] Creating safety backup...");
         fs::copy(target, backup_target)
             .map_err(|e| format!("Failed to create backup: {}", e))?;
     }
@@ -138,28 +95,28 @@ pub fn windows_safe_file_replace(
     
     // Strategy 1: Direct replace (works if no locks)
     if let Ok(_) = fs::rename(source, target) {
-        println!("✅ [WINDOWS-REPLACE] Direct replacement successful");
+        info!("[WINDOWS-REPLACE] Direct replacement successful");
         success = true;
     } else {
         // Strategy 2: Copy + Delete with retries
-        println!("🔄 [WINDOWS-REPLACE] Using copy+delete strategy...");
+        info!("[WINDOWS-REPLACE] Using copy+delete strategy...");
         
         // Copy new file to target
         match fs::copy(source, target) {
             Ok(_) => {
-                println!("📁 [WINDOWS-REPLACE] File copied successfully");
+                info!("[WINDOWS-REPLACE] File copied successfully");
                 // Try to remove source
                 for attempt in 1..=3 {
                     match fs::remove_file(source) {
                         Ok(_) => {
-                            println!("🗑️ [WINDOWS-REPLACE] Source cleaned up on attempt {}", attempt);
+                            info!("[WINDOWS-REPLACE] Source cleaned up on attempt {}", attempt);
                             success = true;
                             break;
                         }
                         Err(e) => {
-                            println!("⚠️ [WINDOWS-REPLACE] Cleanup attempt {} failed: {}", attempt, e);
+                            warn!("[WINDOWS-REPLACE] Cleanup attempt {} failed: {}", attempt, e);
                             if attempt == 3 {
-                                println!("⚠️ [WINDOWS-REPLACE] Source file cleanup failed, but replacement succeeded");
+                                warn!("[WINDOWS-REPLACE] Source file cleanup failed, but replacement succeeded");
                                 success = true; // File was copied successfully
                             } else {
                                 std::thread::sleep(std::time::Duration::from_millis(500));
@@ -175,13 +132,13 @@ pub fn windows_safe_file_replace(
     }
     
     if success {
-        println!("✅ [WINDOWS-REPLACE] File replacement completed successfully");
+        info!("[WINDOWS-REPLACE] File replacement completed successfully");
         Ok(())
     } else {
         // Restore backup if replacement failed
         if backup_target.exists() {
             let _ = fs::copy(backup_target, target);
-            println!("🔄 [WINDOWS-REPLACE] Backup restored due to failure");
+            warn!("[WINDOWS-REPLACE] Backup restored due to failure");
         }
         Err("File replacement failed after all attempts".to_string())
     }
@@ -210,3 +167,43 @@ pub fn check_windows_compatibility() -> Vec<String> {
     
     warnings
 }
+
+/// Tell the user why the app cannot start, before any window exists
+pub fn show_startup_error(message: &str) {
+    error!("[STARTUP] {}", message);
+    eprintln!("{}", message);
+
+    #[cfg(target_os = "windows")]
+    {
+        use std::ffi::c_void;
+
+        #[link(name = "user32")]
+        extern "system" {
+            fn MessageBoxW(window: *mut c_void, text: *const u16, caption: *const u16, kind: u32) -> i32;
+        }
+        const MB_ICONERROR: u32 = 0x10;
+        let wide = |text: &str| text.encode_utf16().chain(std::iter::once(0)).collect::<Vec<u16>>();
+        let (text, caption) = (wide(message), wide("Ittehad Iron Store cannot start"));
+        unsafe {
+            MessageBoxW(std::ptr::null_mut(), text.as_ptr(), caption.as_ptr(), MB_ICONERROR);
+        }
+    }
+
+    // Best effort elsewhere: a terminal may not be attached
+    #[cfg(target_os = "macos")]
+    let _ = std::process::Command::new("osascript")
+        .args([
+            "-e",
+            "on run argv",
+            "-e",
+            "display alert \"Ittehad Iron Store cannot start\" message (item 1 of argv) as critical",
+            "-e",
+            "end run",
+            message,
+        ])
+        .status();
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let _ = std::process::Command::new("zenity")
+        .args(["--error", "--title=Ittehad Iron Store cannot start", "--text", message])
+        .status();
+}
@@ -18,6 +18,7 @@ import {
     User,
     Package,
     FileText,
+    Truck,
     Loader2,
     AlertCircle,
     Zap,
@@ -168,6 +169,8 @@ export default function OptimizedSearch({
                 return <Package className="h-4 w-4 text-green-600" />;
             case 'invoice':
                 return <FileText className="h-4 w-4 text-purple-600" />;
+            case 'vendor':
+                return <Truck className="h-4 w-4 text-orange-600" />;
             default:
                 return <Search className="h-4 w-4 text-gray-600" />;
         }
@@ -180,7 +183,7 @@ export default function OptimizedSearch({
         const { metadata } = result;
         if (!metadata) return null;
 
-        if (result.type === 'customer' && metadata.balance !== undefined) {
+        if ((result.type === 'customer' || result.type === 'vendor') && metadata.balance !== undefined) {
             return (
                 <span className={`text-sm font-semibold ${metadata.balance > 0 ? 'text-red-600' : 'text-green-600'
                     }`}>
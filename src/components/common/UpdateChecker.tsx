@@ -1,9 +1,18 @@
 import React, { useState, useEffect } from 'react';
-import { check } from '@tauri-apps/plugin-updater';
+import { invoke } from '@tauri-apps/api/core';
 import { RefreshCw, Download, CheckCircle, AlertCircle } from 'lucide-react';
 
+interface UpdateCandidate {
+    version: string;
+    notes: string | null;
+    pub_date: string | null;
+    manifest: string;
+    installer: string;
+    size: number;
+}
+
 const UpdateChecker: React.FC = () => {
-    const [updateInfo, setUpdateInfo] = useState<any>(null);
+    const [updateInfo, setUpdateInfo] = useState<UpdateCandidate | null>(null);
     const [downloading, setDownloading] = useState(false);
     const [downloaded, setDownloaded] = useState(false);
     const [error, setError] = useState<string | null>(null);
@@ -11,9 +20,10 @@ const UpdateChecker: React.FC = () => {
 
     const checkForUpdates = async () => {
         try {
-            const update = await check();
-            if (update) {
-                setUpdateInfo(update);
+            // Looks in the configured update folder and on attached USB drives
+            const updates = await invoke<UpdateCandidate[]>('check_offline_updates');
+            if (updates.length > 0) {
+                setUpdateInfo(updates[0]);
                 setShowDialog(true);
             }
         } catch (err) {
@@ -29,27 +39,15 @@ const UpdateChecker: React.FC = () => {
             setDownloading(true);
             setError(null);
 
-            // Download the update
-            await updateInfo.downloadAndInstall((event: any) => {
-                switch (event.event) {
-                    case 'Started':
-                        console.log('Download started');
-                        break;
-                    case 'Progress':
-                        console.log(`Downloaded ${event.data.chunkLength} bytes of ${event.data.contentLength}`);
-                        break;
-                    case 'Finished':
-                        console.log('Download finished');
-                        setDownloaded(true);
-                        break;
-                }
-            });
+            // Verifies the signature and backs up the database, then the installer takes over
+            await invoke('install_offline_update', { manifest: updateInfo.manifest });
+            setDownloaded(true);
 
             // Show completion message
             setDownloading(false);
         } catch (err) {
             console.error('Update failed:', err);
-            setError('Failed to download and install update');
+            setError(`Failed to install update: ${err}`);
             setDownloading(false);
         }
     };
@@ -94,11 +92,11 @@ const UpdateChecker: React.FC = () => {
 
                 <div className="mb-6 text-gray-600">
                     {downloaded ? (
-                        'Update has been downloaded successfully. Please restart the application to apply the update.'
+                        'The update is verified and the database is backed up. The installer is starting and the application will close.'
                     ) : error ? (
                         error
                     ) : (
-                        `A new version (${updateInfo?.version}) is available. Would you like to download and install it now?`
+                        `A new version (${updateInfo?.version}) was found in the update folder. Would you like to install it now?`
                     )}
                 </div>
 
@@ -121,27 +119,17 @@ const UpdateChecker: React.FC = () => {
                             {downloading ? (
                                 <>
                                     <RefreshCw className="h-4 w-4 animate-spin" />
-                                    <span>Downloading...</span>
+                                    <span>Installing...</span>
                                 </>
                             ) : (
                                 <>
                                     <Download className="h-4 w-4" />
-                                    <span>Download & Install</span>
+                                    <span>Install</span>
                                 </>
                             )}
                         </button>
                     )}
 
-                    {downloaded && (
-                        <button
-                            onClick={() => window.location.reload()}
-                            className="flex items-center space-x-2 px-4 py-2 bg-green-600 text-white rounded-lg hover:bg-green-700 transition-colors"
-                        >
-                            <RefreshCw className="h-4 w-4" />
-                            <span>Restart App</span>
-                        </button>
-                    )}
-
                     {error && (
                         <button
                             onClick={() => { setError(null); checkForUpdates(); }}
@@ -43,8 +43,6 @@ export default function CustomerList() {
   const [showDeleteModal, setShowDeleteModal] = useState(false);
   const [customerToDelete, setCustomerToDelete] = useState<Customer | null>(null);
   const [deleteLoading, setDeleteLoading] = useState(false);
-  const [permanentDeleteLoading, setPermanentDeleteLoading] = useState(false);
-  const [showPermanentDeleteConfirm, setShowPermanentDeleteConfirm] = useState(false);
 
   // FIFO Payment state
   const [showFIFOPayment, setShowFIFOPayment] = useState(false);
@@ -142,7 +140,6 @@ export default function CustomerList() {
 
   const handleDeleteClick = (customer: Customer) => {
     setCustomerToDelete(customer);
-    setShowPermanentDeleteConfirm(false);
     setShowDeleteModal(true);
   };
 
@@ -170,36 +167,6 @@ export default function CustomerList() {
     }
   };
 
-  const handlePermanentDeleteClick = () => {
-    setShowPermanentDeleteConfirm(true);
-  };
-
-  const handlePermanentDeleteConfirm = async () => {
-    if (!customerToDelete) return;
-
-    setPermanentDeleteLoading(true);
-    try {
-      console.log(`🔥 PERMANENT DELETION: Attempting to permanently erase customer: ${customerToDelete.name} (ID: ${customerToDelete.id})`);
-
-      const result = await db.permanentlyEraseCustomer(customerToDelete.id, 'ERASE_ALL_DATA_PERMANENTLY');
-
-      const totalDeleted = Object.values(result.deletedRecords).reduce((sum, count) => sum + count, 0);
-      toast.success(`Customer "${customerToDelete.name}" permanently deleted. ${totalDeleted} records removed.`);
-      setShowDeleteModal(false);
-      setCustomerToDelete(null);
-      setShowPermanentDeleteConfirm(false);
-
-      // Force reload of customers
-      await loadCustomers();
-    } catch (error: any) {
-      console.error('❌ Permanent delete customer error:', error);
-      const errorMessage = error.message || 'Failed to permanently delete customer';
-      toast.error(errorMessage);
-    } finally {
-      setPermanentDeleteLoading(false);
-    }
-  };
-
   const handleSearch = useCallback((value: string) => {
     setSearchQuery(value);
   }, []);
@@ -678,7 +645,6 @@ export default function CustomerList() {
         onClose={() => {
           setShowDeleteModal(false);
           setCustomerToDelete(null);
-          setShowPermanentDeleteConfirm(false);
         }}
         title="Delete Customer"
       >
@@ -688,11 +654,11 @@ export default function CustomerList() {
               Delete "{customerToDelete?.name}"?
             </h3>
             <p className="text-sm text-gray-600">
-              Choose how you want to delete this customer:
+              The customer moves to the recycle bin and can be restored from there.
             </p>
           </div>
 
-          {/* Option 1: Regular Delete */}
+          {/* Move to the recycle bin */}
           <div className="bg-orange-50 border border-orange-200 rounded-lg p-4">
             <div className="flex items-center justify-between">
               <div>
@@ -709,56 +675,15 @@ export default function CustomerList() {
             </div>
           </div>
 
-          {/* Option 2: Permanent Delete */}
-          <div className="bg-red-50 border border-red-200 rounded-lg p-4">
-            <div className="space-y-3">
-              <div>
-                <h4 className="font-medium text-red-800">Permanent Delete</h4>
-                <p className="text-sm text-red-700">Completely erase all data - cannot be undone</p>
-              </div>
-
-              {!showPermanentDeleteConfirm ? (
-                <button
-                  onClick={handlePermanentDeleteClick}
-                  className="w-full px-4 py-2 text-sm font-medium text-white bg-red-600 border border-transparent rounded-md hover:bg-red-700"
-                >
-                  Permanent Delete
-                </button>
-              ) : (
-                <div className="space-y-2">
-                  <p className="text-sm font-medium text-red-800">
-                    Are you absolutely sure? This will permanently erase all data for "{customerToDelete?.name}".
-                  </p>
-                  <div className="flex space-x-2">
-                    <button
-                      onClick={() => setShowPermanentDeleteConfirm(false)}
-                      className="flex-1 px-3 py-2 text-sm font-medium text-gray-700 bg-gray-100 border border-gray-300 rounded-md hover:bg-gray-50"
-                    >
-                      Cancel
-                    </button>
-                    <button
-                      onClick={handlePermanentDeleteConfirm}
-                      disabled={permanentDeleteLoading}
-                      className="flex-1 px-3 py-2 text-sm font-medium text-white bg-red-600 border border-transparent rounded-md hover:bg-red-700 disabled:bg-gray-400"
-                    >
-                      {permanentDeleteLoading ? 'Deleting...' : 'Yes, Delete Forever'}
-                    </button>
-                  </div>
-                </div>
-              )}
-            </div>
-          </div>
-
           {/* Cancel Button */}
           <div className="flex justify-center">
             <button
               onClick={() => {
                 setShowDeleteModal(false);
                 setCustomerToDelete(null);
-                setShowPermanentDeleteConfirm(false);
               }}
               className="px-6 py-2 text-sm font-medium text-gray-700 bg-gray-100 border border-gray-300 rounded-md hover:bg-gray-50"
-              disabled={deleteLoading || permanentDeleteLoading}
+              disabled={deleteLoading}
             >
               Cancel
             </button>
@@ -98,22 +98,6 @@ const TestDataGenerator: React.FC = () => {
         }
     };
 
-    const clearAllProducts = async () => {
-        if (!window.confirm('Are you sure you want to delete ALL products? This cannot be undone!')) {
-            return;
-        }
-
-        try {
-            await db.executeSmartQuery('DELETE FROM products');
-            const count = await getCurrentProductCount();
-            toast.success('All products deleted successfully!');
-            console.log(`🗑️ All products deleted. Current count: ${count}`);
-        } catch (error) {
-            console.error('Error deleting products:', error);
-            toast.error('Failed to delete products');
-        }
-    };
-
     React.useEffect(() => {
         getCurrentProductCount();
     }, []);
@@ -170,14 +154,6 @@ const TestDataGenerator: React.FC = () => {
                     >
                         {generating ? 'Generating...' : 'Generate Test Data'}
                     </button>
-
-                    <button
-                        onClick={clearAllProducts}
-                        disabled={generating}
-                        className="px-4 py-2 bg-red-600 text-white rounded-lg hover:bg-red-700 disabled:opacity-50"
-                    >
-                        Clear All
-                    </button>
                 </div>
             </div>
 
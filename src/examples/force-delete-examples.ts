@@ -1,176 +0,0 @@
-/**
- * 🚨 FORCE DELETE DEMONSTRATION
- * 
- * This file demonstrates the exact implementation and expected results
- * for the force delete invoice functionality.
- */
-
-import { DatabaseService } from '../services/database';
-
-// Example 1: Basic Force Delete Usage
-async function basicForceDeleteExample() {
-    console.log('📘 EXAMPLE 1: Basic Force Delete');
-    console.log('=====================================');
-
-    const db = DatabaseService.getInstance();
-
-    // SCENARIO: Invoice with payments that cannot be normally deleted
-    const invoiceId = 123; // Example invoice ID
-
-    try {
-        // Step 1: Try normal deletion (will fail for paid invoices)
-        console.log('🔄 Attempting normal deletion...');
-        const normalResult = await db.deleteInvoiceWithValidation(invoiceId);
-        console.log('✅ Normal deletion succeeded:', normalResult);
-
-    } catch (error: any) {
-        console.log('❌ Normal deletion failed (expected for paid invoices):', error.message);
-
-        // Step 2: Use force delete with payment reversal
-        console.log('\n🚨 Using force delete...');
-        const forceResult = await db.forceDeleteInvoice(invoiceId, {
-            handlePayments: 'reverse', // Convert payments to customer credit
-            reason: 'Administrative correction',
-            authorizedBy: 'manager-001',
-            createBackup: true
-        });
-
-        if (forceResult.success) {
-            console.log('✅ Force delete succeeded:', {
-                invoiceId: forceResult.data.invoiceId,
-                executionTime: forceResult.data.executionTime,
-                relatedRecordsDeleted: forceResult.data.relatedRecordsDeleted,
-                paymentsHandled: forceResult.data.paymentsHandled,
-                stockRestored: forceResult.data.stockRestored
-            });
-        } else {
-            console.log('❌ Force delete failed:', forceResult.error?.message);
-        }
-    }
-}
-
-// Example 2: Different Payment Handling Options
-async function paymentHandlingOptionsExample() {
-    console.log('\n📘 EXAMPLE 2: Payment Handling Options');
-    console.log('=======================================');
-
-    const db = DatabaseService.getInstance();
-
-    // Option 1: Reverse payments to customer credit
-    console.log('💰 Option 1: Reverse to Customer Credit');
-    await db.forceDeleteInvoice(123, {
-        handlePayments: 'reverse', // Customer gets credit for payments made
-        reason: 'Customer dispute resolution'
-    });
-
-    // Option 2: Transfer payments to advance payments
-    console.log('📤 Option 2: Transfer to Advance Payments');
-    await db.forceDeleteInvoice(124, {
-        handlePayments: 'transfer', // Payments become advance payments
-        reason: 'Invoice cancellation'
-    });
-
-    // Option 3: Ignore payments (delete them)
-    console.log('🗑️ Option 3: Delete Payments');
-    await db.forceDeleteInvoice(125, {
-        handlePayments: 'ignore', // Payments are deleted (use with caution)
-        reason: 'Fraudulent transaction'
-    });
-}
-
-// Example 3: Comprehensive Validation
-async function validationExample() {
-    console.log('\n📘 EXAMPLE 3: Comprehensive Validation');
-    console.log('======================================');
-
-    const db = DatabaseService.getInstance();
-
-    // Run full validation suite
-    const validation = await db.validateForceDeleteFunctionality();
-
-    console.log('🧪 Validation Results:');
-    console.log(`   Success: ${validation.success}`);
-    console.log(`   Tests Run: ${validation.summary.total}`);
-    console.log(`   Passed: ${validation.summary.passed}`);
-    console.log(`   Failed: ${validation.summary.failed}`);
-
-    // Show failed tests
-    const failedTests = validation.tests.filter((t: any) => !t.passed);
-    if (failedTests.length > 0) {
-        console.log('\n❌ Failed Tests:');
-        failedTests.forEach((test: any) => {
-            console.log(`   - ${test.name}: ${test.message}`);
-        });
-    }
-}
-
-// Example 4: What Gets Deleted
-async function whatGetsDeletedExample() {
-    console.log('\n📘 EXAMPLE 4: What Gets Deleted');
-    console.log('=================================');
-
-    console.log('🗑️ When force deleting invoice ID 123, the following will be cleaned up:');
-    console.log('');
-    console.log('📋 INVOICE DATA:');
-    console.log('   ├── invoices table: Invoice record');
-    console.log('   └── invoice_items table: All line items');
-    console.log('');
-    console.log('💰 FINANCIAL DATA:');
-    console.log('   ├── payments table: Payment records (handled per option)');
-    console.log('   ├── customer_ledger_entries: Customer transaction history');
-    console.log('   └── ledger_entries: Daily business ledger entries');
-    console.log('');
-    console.log('📦 INVENTORY DATA:');
-    console.log('   ├── stock_movements: Related stock changes');
-    console.log('   └── products table: Stock quantities restored');
-    console.log('');
-    console.log('🔗 RELATED DATA:');
-    console.log('   ├── invoice_payment_allocations: Payment allocations');
-    console.log('   ├── returns: Invoice associations removed (returns preserved)');
-    console.log('   └── return_items: Item associations removed');
-    console.log('');
-    console.log('📝 AUDIT DATA:');
-    console.log('   ├── audit_log: Comprehensive deletion record created');
-    console.log('   └── Backup of all deleted data for recovery');
-}
-
-// Example 5: Expected Database State After Force Delete
-async function expectedStateExample() {
-    console.log('\n📘 EXAMPLE 5: Expected Database State After Force Delete');
-    console.log('=========================================================');
-
-    console.log('✅ SUCCESSFUL FORCE DELETE RESULTS IN:');
-    console.log('');
-    console.log('📊 INVOICE DATA:');
-    console.log('   ✓ Invoice completely removed from invoices table');
-    console.log('   ✓ All invoice_items deleted');
-    console.log('   ✓ No orphaned records remaining');
-    console.log('');
-    console.log('💰 CUSTOMER BALANCE:');
-    console.log('   ✓ Customer balance adjusted for unpaid amount');
-    console.log('   ✓ Payment amounts credited back (if reverse option used)');
-    console.log('   ✓ Customer ledger updated with adjustment entries');
-    console.log('');
-    console.log('📦 PRODUCT STOCK:');
-    console.log('   ✓ Product quantities restored to pre-invoice levels');
-    console.log('   ✓ Stock movements recorded for audit trail');
-    console.log('   ✓ Inventory consistency maintained');
-    console.log('');
-    console.log('📝 AUDIT TRAIL:');
-    console.log('   ✓ Comprehensive backup of all deleted data');
-    console.log('   ✓ Detailed audit log entry with reason and authorization');
-    console.log('   ✓ All related record counts and details preserved');
-    console.log('');
-    console.log('🔄 REAL-TIME UPDATES:');
-    console.log('   ✓ All UI components refreshed automatically');
-    console.log('   ✓ Dashboard metrics updated');
-    console.log('   ✓ Customer balance displays current amount');
-}
-
-export {
-    basicForceDeleteExample,
-    paymentHandlingOptionsExample,
-    validationExample,
-    whatGetsDeletedExample,
-    expectedStateExample
-};
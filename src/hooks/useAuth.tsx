@@ -93,6 +93,14 @@ export const AuthProvider: React.FC<{ children: React.ReactNode }> = ({ children
     setTimeout(initAuth, 10);
   }, []);
 
+  // Row changes in the audit log are attributed to whoever is signed in
+  useEffect(() => {
+    if (!isInitialized || !isTauri()) return;
+    invoke('set_audit_actor', { actor: user?.username ?? null }).catch((error) => {
+      console.warn('Failed to set the audit actor:', error);
+    });
+  }, [user, isInitialized]);
+
   // Don't render children until initialized
   if (!isInitialized) {
     return (
@@ -286,6 +294,13 @@ export const AuthProvider: React.FC<{ children: React.ReactNode }> = ({ children
 
     setUser(null);
 
+    // Admin-only commands check the session the Rust side opened at login
+    if (isTauri()) {
+      invoke('end_session').catch((error) => {
+        console.warn('Failed to end the session:', error);
+      });
+    }
+
     // Clear localStorage
     try {
       localStorage.removeItem('auth_user');
@@ -16,7 +16,7 @@ import { optimizedSearchService } from '../services/optimizedSearchService';
 
 interface SearchResult {
     id: number;
-    type: 'customer' | 'product' | 'invoice';
+    type: 'customer' | 'product' | 'invoice' | 'vendor';
     title: string;
     subtitle: string;
     url: string;
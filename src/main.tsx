@@ -106,6 +106,14 @@ async function initializeApp(): Promise<void> {
   } catch (error) {
     console.error('❌ [STARTUP] Restore check failed:', error);
     // If restore fails, we should still continue with normal startup
+  }
+
+  // LIVE UPDATES: Forward database change events from Rust to the eventBus
+  try {
+    const { startDatabaseChangeEvents } = await import('./services/databaseChangeEvents');
+    await startDatabaseChangeEvents();
+  } catch (error) {
+    console.error('❌ [STARTUP] Database change events unavailable:', error);
   }  // STEP 1: Run cleanup AFTER restore processing to clean up any stuck old commands
   // TEMPORARILY DISABLED to allow restore testing
   /*
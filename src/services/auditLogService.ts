@@ -3,6 +3,7 @@
  * Comprehensive audit trail for all system operations
  */
 
+import { invoke } from '@tauri-apps/api/core';
 import { db } from './database';
 
 // PERFORMANCE: Track initialization to prevent repeated calls
@@ -156,6 +157,16 @@ class AuditLogService {
         ]
       );
 
+      // Also into the tamper-evident log kept by the backend
+      await invoke('record_audit_event', {
+        actor: user_name,
+        action,
+        entityType: entity_type,
+        entityId: entity_id,
+        before: data.old_values ?? null,
+        after: { ...(data.new_values ?? {}), description },
+      }).catch((error) => console.warn('Failed to record audit event in the chain:', error));
+
       console.log(`📝 Audit logged: ${action} on ${entity_type} by ${user_name}`);
     } catch (error) {
       console.error('❌ Error logging audit event:', error);
@@ -250,7 +250,7 @@ export class ProductionFileBackupService {
       const metadata: FileBackupMetadata = {
         id: backupId,
         filename,
-        originalFilename: 'store-2025.db',
+        originalFilename: 'store.db',
         size: backupResult.size,
         checksum: backupResult.checksum,
         createdAt: new Date(),
@@ -796,7 +796,7 @@ export class ProductionFileBackupService {
       const metadata: FileBackupMetadata = {
         id: backupId,
         filename: driveFile.name,
-        originalFilename: 'store-2025.db',
+        originalFilename: 'store.db',
         size: driveFile.size,
         checksum: '', // Will be calculated during restore if needed
         createdAt: new Date(driveFile.createdTime),
@@ -1397,7 +1397,7 @@ export class ProductionFileBackupService {
             const driveBackup: FileBackupMetadata = {
               id: `drive-${driveFile.id}`,
               filename: driveFile.name,
-              originalFilename: 'store-2025.db',
+              originalFilename: 'store.db',
               size: driveFile.size,
               checksum: '', // We'll calculate this when downloading if needed
               createdAt: new Date(driveFile.createdTime),
@@ -1,4 +1,5 @@
 
+import { invoke } from '@tauri-apps/api/core';
 import { addCurrency } from '../utils/calculations';
 import { parseUnit, formatUnitString, getStockAsNumber, createUnitFromNumericValue } from '../utils/unitUtils';
 import { eventBus, BUSINESS_EVENTS, triggerStockAdjustmentRefresh } from '../utils/eventBus';
@@ -1461,6 +1462,18 @@ export class DatabaseService {
     console.log('🔄 Invoice cache invalidated for real-time updates');
   }
 
+  /**
+   * SOFT DELETE: Mark a record deleted through the Rust recycle bin, which
+   * checks balances and stock and records the signed-in user and the reason
+   */
+  private async moveToRecycleBin(
+    entityType: 'customer' | 'product' | 'invoice' | 'vendor',
+    id: number,
+    reason?: string
+  ): Promise<unknown> {
+    return invoke('move_to_recycle_bin', { entityType, id, reason: reason ?? null });
+  }
+
   // HEALTH: Database connection health check
   private async checkConnectionHealth(): Promise<boolean> {
     try {
@@ -2182,19 +2195,15 @@ export class DatabaseService {
   /**
    * Delete product and remove all references from related tables (with confirmation)
    */
-  async deleteProduct(id: number): Promise<void> {
+  async deleteProduct(id: number, reason?: string): Promise<void> {
     try {
       if (!this.isInitialized) {
         await this.initialize();
       }
 
-      // Remove from related tables first (to avoid FK errors)
-      await this.dbConnection.execute(`DELETE FROM invoice_items WHERE product_id = ?`, [id]);
-      await this.dbConnection.execute(`DELETE FROM stock_receiving_items WHERE product_id = ?`, [id]);
-      await this.dbConnection.execute(`DELETE FROM stock_movements WHERE product_id = ?`, [id]);
-
-      // Remove from products
-      await this.dbConnection.execute(`DELETE FROM products WHERE id = ?`, [id]);
+      // Soft delete: invoice lines and stock movements keep their product.
+      // Rust refuses products that still have stock on hand.
+      await this.moveToRecycleBin('product', id, reason);
 
       // CACHE INVALIDATION: Clear product cache for real-time updates
       this.invalidateProductCache();
@@ -2296,15 +2305,14 @@ export class DatabaseService {
     }
   }
 
-  async deleteVendor(id: number): Promise<void> {
+  async deleteVendor(id: number, reason?: string): Promise<void> {
     try {
       if (!this.isInitialized) {
         await this.initialize();
       }
 
-
-
-      await this.dbConnection.execute(`DELETE FROM vendors WHERE id = ?`, [id]);
+      // Rust refuses vendors that are still owed money or hold an advance
+      await this.moveToRecycleBin('vendor', id, reason);
 
       // REAL-TIME UPDATE: Emit vendor delete event using EventBus
       try {
@@ -4001,12 +4009,9 @@ export class DatabaseService {
       console.log('🔧 [ROOT CAUSE FIX] Synchronizing with Tauri backend database path...');
 
       try {
-        // Use the EXACT same path logic as Tauri backend main.rs
-        const { appDataDir } = await import('@tauri-apps/api/path');
-        const { join } = await import('@tauri-apps/api/path');
-
-        const appDataPath = await appDataDir();
-        const dbPath = await join(appDataPath, 'store-2025.db');
+        // Ask the backend for the path so the filename is defined in one place
+        const { invoke } = await import('@tauri-apps/api/core');
+        const dbPath = await invoke<string>('get_database_path');
 
         // This matches EXACTLY what Tauri backend uses
         dbUrl = `sqlite:${dbPath}`;
@@ -5465,8 +5470,8 @@ export class DatabaseService {
     if (!this.isInitialized) {
       await this.initialize();
     }
-    let query = 'SELECT * FROM products WHERE status = ?';
-    const params: any[] = ['active']; // Only show active products
+    let query = 'SELECT * FROM products WHERE status = ? AND deleted_at IS NULL';
+    const params: any[] = ['active']; // Only show active products, never ones in the recycle bin
 
     if (search && search.trim()) {
       const searchTerm = search.trim();
@@ -10418,7 +10423,7 @@ export class DatabaseService {
       const countParams: any[] = [];
 
       // Add WHERE conditions for search and balance filtering
-      let whereClause = ' WHERE c.id != -1'; // CRITICAL: Hide guest customer from customer list
+      let whereClause = ' WHERE c.id != -1 AND c.deleted_at IS NULL'; // CRITICAL: Hide guest customer and recycled customers from customer list
 
       if (search) {
         whereClause += ` AND (c.name LIKE ? OR c.phone LIKE ? OR c.cnic LIKE ?)`;
@@ -10665,7 +10670,7 @@ export class DatabaseService {
       }
 
       // Build WHERE clause
-      let whereClause = ` WHERE p.status = ?`;
+      let whereClause = ` WHERE p.status = ? AND p.deleted_at IS NULL`;
       params.push(status);
       countParams.push(status);
 
@@ -10823,7 +10828,7 @@ export class DatabaseService {
       }
 
       // Build WHERE clause
-      let whereClause = ' WHERE 1=1';
+      let whereClause = ' WHERE i.deleted_at IS NULL';
       if (customerId) {
         whereClause += ` AND i.customer_id = ?`;
         params.push(customerId);
@@ -11440,7 +11445,7 @@ export class DatabaseService {
                c.address as customer_address
         FROM invoices i
         LEFT JOIN customers c ON i.customer_id = c.id AND i.customer_id > 0
-        WHERE 1=1
+        WHERE i.deleted_at IS NULL
       `;
       const params: any[] = [];
 
@@ -11504,14 +11509,14 @@ export class DatabaseService {
                c.address as customer_address
         FROM invoices i
         LEFT JOIN customers c ON i.customer_id = c.id AND i.customer_id > 0
-        WHERE 1=1
+        WHERE i.deleted_at IS NULL
       `;
 
       let countQuery = `
         SELECT COUNT(*) as total
         FROM invoices i
         LEFT JOIN customers c ON i.customer_id = c.id AND i.customer_id > 0
-        WHERE 1=1
+        WHERE i.deleted_at IS NULL
       `;
 
       const params: any[] = [];
@@ -11794,510 +11799,426 @@ export class DatabaseService {
   }
 
   /**
-   * ✅ UNIFIED DELETE: Delete invoice and all related records (handles paid/unpaid/partial)
+   * ✅ UNIFIED DELETE: Cancel the invoice and move it to the recycle bin
    */
   async deleteInvoice(invoiceId: number): Promise<void> {
+    await this.deleteInvoiceEnhanced(invoiceId, 'credit');
+  }
+
+  /**
+   * ✅ Delete invoice into the recycle bin; it can be restored there, still cancelled
+   * @param invoiceId - The invoice ID to delete
+   * @param paymentHandling - Kept for callers; payments always stay as customer credit
+   * @param reason - Why the invoice was deleted, recorded with the cancellation
+   */
+  async deleteInvoiceEnhanced(
+    invoiceId: number,
+    paymentHandling: 'credit' | 'delete' = 'credit',
+    reason?: string
+  ): Promise<void> {
     try {
       if (!this.isInitialized) {
         await this.initialize();
       }
 
-      console.log(`🗑️ [UNIFIED-DELETE] Starting deletion of invoice ${invoiceId}`);
-
-      // Start transaction for safe deletion
-      await this.dbConnection.execute('BEGIN TRANSACTION');
-
-      try {
-        // Get invoice details before deletion for rollback purposes
-        const invoice = await this.getInvoiceDetails(invoiceId);
-        if (!invoice) {
-          throw new Error('Invoice not found');
-        }
+      const invoice = await this.getInvoiceDetails(invoiceId);
+      if (!invoice) {
+        throw new Error('Invoice not found');
+      }
 
-        console.log(`🗑️ [UNIFIED-DELETE] Invoice ${invoice.bill_number}: Total=${invoice.grand_total}, Paid=${invoice.amount_paid || 0}`);
+      if (paymentHandling === 'delete') {
+        console.warn(`⚠️ [ENHANCED-DELETE] Payments are no longer deleted; invoice ${invoice.bill_number} payments stay as customer credit`);
+      }
 
-        // ✅ UNIFIED PAYMENT HANDLING: Handle payments if they exist
-        const hasPayments = (invoice.amount_paid || 0) > 0;
-        if (hasPayments) {
-          console.log(`💰 [UNIFIED-DELETE] Invoice has payments of Rs.${invoice.amount_paid}, handling automatically...`);
+      // Rust cancels the invoice first (stock back, ledger reversed, payments
+      // kept as credit) and then moves it to the recycle bin, in one transaction
+      await this.moveToRecycleBin('invoice', invoiceId, reason);
 
-          // Get all payments for this invoice
-          const payments = await this.dbConnection.select(
-            'SELECT * FROM payments WHERE invoice_id = ?',
-            [invoiceId]
-          );
+      this.invalidateInvoiceCache();
+      this.invalidateCustomerCache();
+      this.invalidateProductCache();
 
-          if (payments.length > 0) {
-            console.log(`💰 [UNIFIED-DELETE] Found ${payments.length} payment(s), reversing as customer credit...`);
+      // IMMEDIATE: Emit invoice deletion event for instant UI update
+      eventBus.emit('INVOICE_DELETED_IMMEDIATE', {
+        invoiceId: invoiceId,
+        customerId: invoice.customer_id,
+        billNumber: invoice.bill_number,
+        paymentHandling: 'credit',
+        timestamp: getCurrentSystemDateTime().dbTimestamp
+      });
 
-            // Create customer credit entries for each payment (reversal)
-            for (const payment of payments) {
-              try {
-                await this.dbConnection.execute(
-                  'INSERT INTO customer_ledger_entries (customer_id, customer_name, entry_type, transaction_type, amount, description, reference_id, reference_number, date, time, created_by, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)',
-                  [
-                    invoice.customer_id,
-                    invoice.customer_name,
-                    'credit',
-                    'payment_reversal',
-                    payment.amount,
-                    `Payment reversal for deleted invoice ${invoice.bill_number}`,
-                    invoiceId,
-                    `REV-${invoice.bill_number}`,
-                    getCurrentSystemDateTime().dbDate,
-                    getCurrentSystemDateTime().dbTime,
-                    'system',
-                    getCurrentSystemDateTime().dbTimestamp
-                  ]
-                );
-                console.log(`✅ [UNIFIED-DELETE] Reversed payment of Rs.${payment.amount} as customer credit`);
-              } catch (error) {
-                console.warn(`⚠️ [UNIFIED-DELETE] Could not create reversal entry for payment ${payment.id}:`, error);
-              }
-            }
-          }
-        }
+      // IMMEDIATE: Force customer balance refresh after the delete
+      eventBus.emit('CUSTOMER_BALANCE_REFRESH_IMMEDIATE', {
+        customerId: invoice.customer_id,
+        paymentHandling: 'credit',
+        timestamp: getCurrentSystemDateTime().dbTimestamp
+      });
 
-        // Get invoice items to restore stock
-        const items = await this.getInvoiceItems(invoiceId);
+      // IMMEDIATE: Force comprehensive customer data refresh
+      this.forceCustomerDataRefresh(invoice.customer_id);
 
-        // Restore stock for each item
-        for (const item of items) {
-          // PRODUCTION FIX: Skip stock restoration for miscellaneous items (they don't have product_id)
-          if (!item.product_id || Boolean(item.is_misc_item)) {
-            console.log(`📦 Skipping stock restoration for miscellaneous item: ${item.misc_description || item.product_name}`);
-            continue;
-          }
+      // Emit comprehensive real-time update events
+      this.emitInvoiceDeletedEvents(invoice);
 
-          const product = await this.getProduct(item.product_id);
+      console.log(`✅ [ENHANCED-DELETE] Invoice ${invoice.bill_number} cancelled and moved to the recycle bin`);
+    } catch (error) {
+      console.error(`❌ [ENHANCED-DELETE] Error deleting invoice ${invoiceId}:`, error);
+      throw error;
+    }
+  }
+  /**
+   * Force immediate UI refresh for customer-related data
+   */
+  private forceCustomerDataRefresh(customerId: number): void {
+    try {
+      // Clear customer balance cache
+      eventBus.emit('CLEAR_CUSTOMER_CACHE', { customerId });
 
-          if (product && product.track_inventory) {
-            // Parse current stock and item quantity
-            const currentStockData = parseUnit(product.current_stock, product.unit_type || 'piece');
-            const itemQuantityData = parseUnit(item.quantity, product.unit_type || 'piece');
+      // Force customer dropdown refresh
+      eventBus.emit('REFRESH_CUSTOMER_DROPDOWN', { customerId });
 
-            const currentStock = currentStockData.numericValue;
-            const itemQuantity = itemQuantityData.numericValue;
-            const newStock = currentStock + itemQuantity; // Add back the stock
+      // Force customer ledger refresh
+      eventBus.emit('REFRESH_CUSTOMER_LEDGER', { customerId });
 
-            // Update product stock
-            const newStockString = formatUnitString(
-              createUnitFromNumericValue(newStock, product.unit_type || 'piece'),
-              product.unit_type || 'piece'
-            );
+      // General UI refresh
+      eventBus.emit('FORCE_UI_REFRESH', {
+        type: 'customer_data',
+        customerId,
+        timestamp: Date.now()
+      });
 
-            await this.dbConnection.execute(
-              'UPDATE products SET current_stock = ?, updated_at = ? WHERE id = ?',
-              [newStockString, getCurrentSystemDateTime().dbTimestamp, item.product_id]
-            );
+      console.log(`🔄 [UI-REFRESH] Forced immediate refresh for customer ${customerId}`);
+    } catch (error) {
+      console.warn(`⚠️ [UI-REFRESH] Failed to force customer refresh:`, error);
+    }
+  }
 
-            // Create stock movement record for audit trail
-            const { dbDate: date, dbTime: time } = getCurrentSystemDateTime();
+  /**
+   * Emit events for invoice deletion - Enhanced for immediate UI updates
+   */
+  private emitInvoiceDeletedEvents(invoice: any): void {
+    try {
+      // Core business event
+      eventBus.emit(BUSINESS_EVENTS.INVOICE_DELETED, {
+        invoiceId: invoice.id,
+        billNumber: invoice.bill_number,
+        customerId: invoice.customer_id,
+        customerName: invoice.customer_name,
+        timestamp: getCurrentSystemDateTime().dbTimestamp
+      });
 
-            // 🚀 CRITICAL FIX: Validate and ensure valid quantity for stock movement
-            let validQuantity = 0;
-            try {
-              const quantityData = parseUnit(item.quantity, product.unit_type || 'piece');
-              if (quantityData && !isNaN(quantityData.numericValue) && quantityData.numericValue >= 0) {
-                validQuantity = quantityData.numericValue;
-              } else {
-                console.warn(`⚠️ [STOCK-RESTORE] Invalid quantity for item ${item.product_name}: ${item.quantity}, using fallback`);
-                // Fallback: try to parse as a simple number
-                const simpleNumber = parseFloat(String(item.quantity || '0'));
-                validQuantity = !isNaN(simpleNumber) && simpleNumber >= 0 ? simpleNumber : 0;
-              }
-            } catch (error) {
-              console.error(`❌ [STOCK-RESTORE] Error parsing quantity for ${item.product_name}:`, error);
-              validQuantity = 0; // Safe fallback - zero is valid
-            }
+      // CRITICAL: Customer-specific events for immediate UI updates
+      eventBus.emit(BUSINESS_EVENTS.CUSTOMER_BALANCE_UPDATED, {
+        customerId: invoice.customer_id,
+        customerName: invoice.customer_name,
+        timestamp: getCurrentSystemDateTime().dbTimestamp
+      });
 
-            console.log(`📦 [STOCK-RESTORE] Restoring ${validQuantity} units of ${product.name}`);
+      // CRITICAL: Customer ledger refresh events
+      eventBus.emit('CUSTOMER_LEDGER_UPDATED', {
+        customerId: invoice.customer_id,
+        invoiceId: invoice.id,
+        action: 'deleted',
+        timestamp: getCurrentSystemDateTime().dbTimestamp
+      });
 
-            await this.dbConnection.execute(
-              `INSERT INTO stock_movements (
-                product_id, product_name, movement_type, quantity, previous_stock, new_stock,
-                reason, reference_type, reference_id, reference_number, customer_id, customer_name,
-                notes, date, time, created_by, created_at, updated_at
-              ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)`,
-              [
-                item.product_id,
-                product.name,
-                'in',
-                validQuantity, // Use validated quantity
-                currentStock, // previous_stock as numeric
-                newStock, // new_stock as numeric
-                'Stock restoration from deleted invoice',
-                'adjustment',
-                invoiceId,
-                `DELETED-${invoice.bill_number}-${invoice.customer_name}`, // reference_number for UI display
-                invoice.customer_id, // customer_id
-                invoice.customer_name, // customer_name
-                `STOCK RESTORED: Invoice ${invoice.bill_number} deleted - restoring ${validQuantity} ${product.unit_type || 'piece'}`,
-                date, // date
-                time, // time
-                'system', // created_by
-                getCurrentSystemDateTime().dbTimestamp, // created_at
-                getCurrentSystemDateTime().dbTimestamp // updated_at
-              ]
-            );
+      // CRITICAL: Force customer dropdown refresh in invoice forms
+      eventBus.emit('CUSTOMER_BALANCE_REFRESH', {
+        customerId: invoice.customer_id,
+        timestamp: getCurrentSystemDateTime().dbTimestamp
+      });
 
-          } else {
-            // Product not found, skip stock restoration
-          }
-        }
+      // CRITICAL: Invoice list refresh events
+      eventBus.emit('INVOICES_UPDATED', {
+        action: 'deleted',
+        invoiceId: invoice.id,
+        customerId: invoice.customer_id,
+        timestamp: getCurrentSystemDateTime().dbTimestamp
+      });
 
-        // CRITICAL FIX: Update customer balance atomically within transaction
-        if (invoice.remaining_balance > 0 && !this.isGuestCustomer(invoice.customer_id)) {
-          try {
-            // Get current customer balance
-            const currentBalance = await this.calculateCustomerBalanceFromLedgerQuick(invoice.customer_id);
-            const newBalance = currentBalance - invoice.remaining_balance;
+      // CRITICAL: Stock update events for inventory UI
+      eventBus.emit(BUSINESS_EVENTS.STOCK_UPDATED, {
+        message: `Stock restored from deleted invoice ${invoice.bill_number}`,
+        invoiceId: invoice.id,
+        timestamp: getCurrentSystemDateTime().dbTimestamp
+      });
 
-            // Update customer balance directly (we'll delete the original ledger entries later)
-            await this.dbConnection.execute(
-              'UPDATE customers SET balance = ?, updated_at = ? WHERE id = ?',
-              [newBalance, getCurrentSystemDateTime().dbTimestamp, invoice.customer_id]
-            );
+      // CRITICAL: Force comprehensive UI refresh
+      eventBus.emit('UI_REFRESH_REQUESTED', {
+        type: 'invoice_deleted',
+        invoiceId: invoice.id,
+        customerId: invoice.customer_id,
+        timestamp: getCurrentSystemDateTime().dbTimestamp
+      });
 
-            console.log(`✅ [DELETE-INVOICE] Customer balance updated: ${currentBalance.toFixed(2)} -> ${newBalance.toFixed(2)}`);
-          } catch (balanceError) {
-            console.error('❌ [DELETE-INVOICE] Failed to update customer balance:', balanceError);
-            throw balanceError; // Fail the entire transaction if balance update fails
-          }
-        }
+      // CRITICAL: Clear caches that might show stale data
+      eventBus.emit('CACHE_INVALIDATE', {
+        types: ['customer_balance', 'invoice_list', 'customer_ledger'],
+        customerId: invoice.customer_id,
+        invoiceId: invoice.id,
+        timestamp: getCurrentSystemDateTime().dbTimestamp
+      });
 
-        // Delete related records in correct order
-        await this.dbConnection.execute('DELETE FROM invoice_items WHERE invoice_id = ?', [invoiceId]);
+      // CRITICAL: Comprehensive data refresh event
+      eventBus.emit('COMPREHENSIVE_DATA_REFRESH', {
+        type: 'invoice_deleted',
+        customerId: invoice.customer_id,
+        timestamp: getCurrentSystemDateTime().dbTimestamp
+      });
 
-        // ✅ IMPROVED APPROACH: Reverse stock changes by getting movement details first, then delete movements
-        try {
-          // 1. Get all stock movements for this invoice with full details
-          const originalMovements = await this.dbConnection.select(
-            'SELECT product_id, quantity, movement_type FROM stock_movements WHERE reference_type = ? AND reference_id = ?',
-            ['invoice', invoiceId]
-          );
+      console.log(`🚀 [REAL-TIME] Comprehensive deletion events emitted for invoice ${invoice.bill_number} - customer ${invoice.customer_id}`);
+    } catch (error) {
+      console.warn('❌ [REAL-TIME] Could not emit invoice deleted events:', error);
+    }
+  }
 
-          console.log(`📦 [DELETE-INVOICE] Found ${originalMovements.length} stock movements to reverse`);
+  /**
+   * PRODUCTION-READY: Enhanced Edit Invoice functionality
+   * Handles all aspects of invoice editing with data integrity
+   */
+  async updateInvoice(invoiceId: number, updateData: {
+    discount?: number;
+    notes?: string;
+    payment_amount?: number;
+    payment_method?: string;
+    items?: Array<{
+      id?: number;
+      product_id: number | null;
+      product_name: string;
+      quantity: string | number;
+      unit_price: number;
+      total_price: number;
+      unit?: string;
+      length?: number;
+      pieces?: number;
+      is_misc_item?: boolean;
+      misc_description?: string;
+      t_iron_pieces?: number;
+      t_iron_length_per_piece?: number;
+      t_iron_total_feet?: number;
+      t_iron_unit?: string;
+      is_non_stock_item?: boolean;
+    }>;
+    expected_version?: number; // OPTIMISTIC LOCKING: Expected version for concurrent edit protection
+  }): Promise<DatabaseOperationResult> {
+    const startTime = Date.now();
 
-          // 2. Reverse each movement by directly updating product stock BEFORE deleting movements
-          for (const movement of originalMovements) {
-            try {
-              const product = await this.getProduct(movement.product_id);
-              if (product && product.track_inventory) {
-                // Parse current stock and movement quantity
-                const currentStockData = parseUnit(product.current_stock, product.unit_type || 'piece');
-                const movementQuantityData = parseUnit(movement.quantity, product.unit_type || 'piece');
-
-                if (currentStockData && movementQuantityData && !isNaN(movementQuantityData.numericValue)) {
-                  // Reverse the movement: if it was 'out' (reducing stock), add it back; if it was 'in' (adding stock), subtract it
-                  let newStockValue = currentStockData.numericValue || 0;
-
-                  if (movement.movement_type === 'out') {
-                    // This movement reduced stock, so add it back
-                    newStockValue += movementQuantityData.numericValue;
-                    console.log(`🔄 [DELETE-INVOICE] Reversing OUT movement: ${product.name} +${movementQuantityData.numericValue}`);
-                  } else if (movement.movement_type === 'in') {
-                    // This movement increased stock, so subtract it
-                    newStockValue -= movementQuantityData.numericValue;
-                    console.log(`🔄 [DELETE-INVOICE] Reversing IN movement: ${product.name} -${movementQuantityData.numericValue}`);
-                  }
+    console.log(`🔥 [INVOICE-DEBUG] updateInvoice called for invoice ${invoiceId} with data:`, updateData);
 
-                  // Ensure stock doesn't go negative
-                  newStockValue = Math.max(0, newStockValue);
+    try {
+      if (!this.isInitialized) {
+        await this.initialize();
+      }
 
-                  // Format and update the stock
-                  const newStockString = formatUnitString(
-                    createUnitFromNumericValue(newStockValue, product.unit_type || 'piece'),
-                    product.unit_type || 'piece'
-                  );
+      // VALIDATION: Check if invoice can be edited (must be fully unpaid)
+      await this.validateInvoiceEditability(invoiceId);
 
-                  await this.dbConnection.execute(
-                    'UPDATE products SET current_stock = ?, updated_at = ? WHERE id = ?',
-                    [newStockString, getCurrentSystemDateTime().dbTimestamp, movement.product_id]
-                  );
+      // OPTIMISTIC LOCKING: Check version before starting transaction
+      if (updateData.expected_version !== undefined) {
+        const versionCheck = await this.dbConnection.select(
+          'SELECT updated_at, version FROM invoices WHERE id = ?',
+          [invoiceId]
+        );
 
-                  console.log(`✅ [DELETE-INVOICE] Updated ${product.name} stock: ${product.current_stock} → ${newStockString}`);
-                }
-              }
-            } catch (productError) {
-              console.error(`❌ [DELETE-INVOICE] Failed to reverse stock for product ${movement.product_id}:`, productError);
-              // Continue with other products even if one fails
-            }
-          }
+        if (!versionCheck || versionCheck.length === 0) {
+          throw new Error('Invoice not found for version check');
+        }
 
-          // 3. Now delete the stock movements after stock has been properly reversed
-          const deleteResult = await this.dbConnection.execute(
-            'DELETE FROM stock_movements WHERE reference_type = ? AND reference_id = ?',
-            ['invoice', invoiceId]
+        const currentVersion = versionCheck[0].version || 0;
+        if (currentVersion !== updateData.expected_version) {
+          throw new Error(
+            `Concurrent modification detected. Expected version ${updateData.expected_version}, ` +
+            `but current version is ${currentVersion}. Please refresh and try again.`
           );
-
-          console.log(`✅ [DELETE-INVOICE] Deleted ${deleteResult.changes || 0} original stock movements after reversing stock changes`);
-
-        } catch (error) {
-          console.error(`❌ [DELETE-INVOICE] Error reversing stock movements:`, error);
-          // Continue with deletion even if stock cleanup fails
-        }        // ✅ COMPREHENSIVE FIX: Delete daily ledger entries with all possible reference patterns
-        try {
-          // Get invoice details for comprehensive cleanup
-          const invoiceDetails = await this.dbConnection.select(
-            'SELECT bill_number FROM invoices WHERE id = ?',
-            [invoiceId]
-          );
-          const billNumber = invoiceDetails[0]?.bill_number;
-
-          // Pattern 1: Daily ledger entries are stored with reference_type = 'payment' for invoice payments
-          const ledgerResult = await this.dbConnection.execute(
-            'DELETE FROM ledger_entries WHERE reference_type = ? AND reference_id = ?',
-            ['payment', invoiceId]
-          );
-          console.log(`✅ [DELETE-INVOICE] ${ledgerResult.changes || 0} daily ledger entries deleted (payment ref)`);
-
-          // Pattern 2: Check for any entries with reference_type = 'invoice'
-          const invoiceRefResult = await this.dbConnection.execute(
-            'DELETE FROM ledger_entries WHERE reference_type = ? AND reference_id = ?',
-            ['invoice', invoiceId]
-          );
-          if (invoiceRefResult.changes > 0) {
-            console.log(`✅ [DELETE-INVOICE] ${invoiceRefResult.changes} additional ledger entries deleted (invoice ref)`);
-          }
-
-          // Pattern 3: Check for entries using bill_number as reference
-          if (billNumber) {
-            const billRefResult = await this.dbConnection.execute(
-              'DELETE FROM ledger_entries WHERE bill_number = ?',
-              [billNumber]
-            );
-            if (billRefResult.changes > 0) {
-              console.log(`✅ [DELETE-INVOICE] ${billRefResult.changes} additional ledger entries deleted (bill number ref)`);
-            }
-          }
-        } catch (error: any) {
-          if (error.message?.includes('no such table: customer_ledger') || error.message?.includes('no such table: ledger_entries')) {
-            console.warn('⚠️ [DELETE-INVOICE] Ledger table not found, skipping ledger cleanup');
-          } else {
-            throw error;
-          }
-        }
-
-        // PERMANENT SOLUTION: Delete miscellaneous item ledger entries
-        try {
-          await this.deleteMiscellaneousItemLedgerEntries(invoiceId);
-        } catch (error: any) {
-          if (error.message?.includes('no such table')) {
-            console.warn('⚠️ [DELETE-INVOICE] Miscellaneous ledger table not found, skipping misc cleanup');
-          } else {
-            throw error;
-          }
         }
+        console.log(`🔒 [OPTIMISTIC-LOCK] Version check passed: ${currentVersion}`);
+      }
 
-        await this.dbConnection.execute('DELETE FROM payments WHERE invoice_id = ?', [invoiceId]);
-
-        // CRITICAL FIX: Delete customer ledger entries to prevent orphaned data (Use correct reference pattern)
-        try {
-          // The correct pattern: Invoice entries are stored with reference_id = invoiceId
-          const result = await this.dbConnection.execute(
-            'DELETE FROM customer_ledger_entries WHERE reference_id = ?',
-            [invoiceId]
-          );
-
-          console.log(`✅ [DELETE-INVOICE] ${result.changes || 0} customer ledger entries deleted for invoice ${invoiceId}`);
-
-          // IMMEDIATE: Emit customer ledger update event for real-time UI refresh
-          eventBus.emit('CUSTOMER_LEDGER_UPDATED', {
-            customerId: invoice.customer_id,
-            invoiceId: invoiceId,
-            action: 'entries_deleted',
-            timestamp: getCurrentSystemDateTime().dbTimestamp
-          });
+      // Start transaction for atomicity
+      await this.dbConnection.execute('BEGIN TRANSACTION');
+      console.log(`🔥 [INVOICE-DEBUG] Transaction started for invoice ${invoiceId}`);
 
-        } catch (error: any) {
-          if (error.message?.includes('no such table')) {
-            console.warn('⚠️ [DELETE-INVOICE] Customer ledger entries table not found, skipping cleanup');
-          } else {
-            console.warn('⚠️ [DELETE-INVOICE] Warning during customer ledger cleanup:', error.message);
-          }
-        }        // CRITICAL FIX: Delete invoice payment records to prevent orphaned entries
-        try {
-          await this.dbConnection.execute('DELETE FROM invoice_payment_allocations WHERE invoice_id = ?', [invoiceId]);
-          await this.dbConnection.execute('DELETE FROM invoice_payments WHERE invoice_id = ?', [invoiceId]);
-          console.log('✅ [DELETE-INVOICE] Invoice payment records deleted');
-        } catch (error: any) {
-          if (error.message?.includes('no such table')) {
-            console.warn('⚠️ [DELETE-INVOICE] Invoice payment tables not found, skipping cleanup');
-          } else {
-            console.warn('⚠️ [DELETE-INVOICE] Warning during payment cleanup:', error.message);
-          }
+      try {
+        // Get existing invoice details
+        const existingInvoice = await this.getInvoiceDetails(invoiceId);
+        if (!existingInvoice) {
+          throw new Error('Invoice not found');
         }
 
-        // Finally delete the invoice
-        await this.dbConnection.execute('DELETE FROM invoices WHERE id = ?', [invoiceId]);
-
-        // IMMEDIATE: Emit invoice deletion event for instant UI update
-        eventBus.emit('INVOICE_DELETED_IMMEDIATE', {
-          invoiceId: invoiceId,
-          customerId: invoice.customer_id,
-          billNumber: invoice.bill_number,
-          timestamp: getCurrentSystemDateTime().dbTimestamp
-        });
+        // BUSINESS RULE: Check if invoice can be edited
+        if (existingInvoice.payment_amount > 0 && existingInvoice.status === 'paid') {
+          throw new Error('Cannot edit fully paid invoices');
+        }
 
-        // Commit transaction
-        await this.dbConnection.execute('COMMIT');
+        // Get existing items for comparison
+        const existingItems = await this.getInvoiceItems(invoiceId);
+        const existingItemsMap = new Map(existingItems.map(item => [item.id, item]));
 
-        // IMMEDIATE: Force customer balance refresh after transaction commit
-        eventBus.emit('CUSTOMER_BALANCE_REFRESH_IMMEDIATE', {
-          customerId: invoice.customer_id,
-          timestamp: getCurrentSystemDateTime().dbTimestamp
-        });
+        let totalItemsValue = 0;
 
-        // IMMEDIATE: Force comprehensive customer data refresh
-        this.forceCustomerDataRefresh(invoice.customer_id);
+        // Process item changes if provided
+        if (updateData.items) {
+          // 1. Handle removed items (restore stock)
+          for (const existingItem of existingItems) {
+            const stillExists = updateData.items.some(newItem => newItem.id === existingItem.id);
+            if (!stillExists && existingItem.product_id) {
+              const product = await this.getProduct(existingItem.product_id);
+              if (product && product.track_inventory) {
+                // Restore stock for removed item
+                const currentStockData = parseUnit(product.current_stock, product.unit_type || 'piece');
+                const itemQuantityData = parseUnit(existingItem.quantity, product.unit_type || 'piece');
 
-        // Emit comprehensive real-time update events
-        this.emitInvoiceDeletedEvents(invoice);
+                const newStock = currentStockData.numericValue + itemQuantityData.numericValue;
+                const newStockString = formatUnitString(
+                  createUnitFromNumericValue(newStock, product.unit_type || 'piece'),
+                  product.unit_type || 'piece'
+                );
 
-      } catch (error) {
-        // Rollback on error
-        await this.dbConnection.execute('ROLLBACK');
-        throw error;
-      }
+                await this.dbConnection.execute(
+                  'UPDATE products SET current_stock = ?, updated_at = ? WHERE id = ?',
+                  [newStockString, getCurrentSystemDateTime().dbTimestamp, existingItem.product_id]
+                );
 
-    } catch (error) {
-      console.error('Error deleting invoice:', error);
-      throw error;
-    }
-  }
+                // Record stock movement
+                const { dbDate: date, dbTime: time } = getCurrentSystemDateTime();
+                await this.dbConnection.execute(
+                  `INSERT INTO stock_movements (
+                    product_id, product_name, movement_type, quantity, previous_stock, new_stock,
+                    reason, reference_type, reference_id, notes, date, time, created_by, created_at, updated_at
+                  ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)`,
+                  [
+                    existingItem.product_id,
+                    product.name,
+                    'in',
+                    itemQuantityData.numericValue,
+                    currentStockData.numericValue,
+                    newStock,
+                    'Stock restored due to invoice item removal',
+                    'invoice',
+                    invoiceId,
+                    `Item removed from invoice ${existingInvoice.bill_number}`,
+                    date, time, 'system',
+                    getCurrentSystemDateTime().dbTimestamp,
+                    getCurrentSystemDateTime().dbTimestamp
+                  ]
+                );
+              }
 
-  /**
-   * ✅ ENHANCED Delete invoice with payment handling options
-   * @param invoiceId - The invoice ID to delete
-   * @param paymentHandling - How to handle payments: 'credit' (reverse as customer credit) or 'delete' (remove completely)
-   */
-  async deleteInvoiceEnhanced(invoiceId: number, paymentHandling: 'credit' | 'delete' = 'credit'): Promise<void> {
-    try {
-      if (!this.isInitialized) {
-        await this.initialize();
-      }
+              // Delete the item
+              await this.dbConnection.execute('DELETE FROM invoice_items WHERE id = ?', [existingItem.id]);
+            }
+          }
 
-      console.log(`🗑️ [ENHANCED-DELETE] Starting deletion of invoice ${invoiceId} with ${paymentHandling} payment handling`);
+          // 2. Handle updated and new items
+          for (const newItem of updateData.items) {
+            if (newItem.id && existingItemsMap.has(newItem.id)) {
+              // Update existing item
+              const existingItem = existingItemsMap.get(newItem.id)!;
 
-      // Start transaction for safe deletion
-      await this.dbConnection.execute('BEGIN TRANSACTION');
+              // Handle stock changes for inventory items
+              if (newItem.product_id && !newItem.is_misc_item && !newItem.is_non_stock_item) {
+                const product = await this.getProduct(newItem.product_id);
+                console.log(`🔍 [STOCK-DEBUG] Checking product ${newItem.product_id}: ${product?.name}, track_inventory: ${product?.track_inventory}`);
 
-      try {
-        // Get invoice details before deletion for rollback purposes
-        const invoice = await this.getInvoiceDetails(invoiceId);
-        if (!invoice) {
-          throw new Error('Invoice not found');
-        }
+                if (product && product.track_inventory) {
+                  const oldQuantityData = parseUnit(existingItem.quantity, product.unit_type || 'piece');
+                  const newQuantityData = parseUnit(newItem.quantity, product.unit_type || 'piece');
+                  const quantityDiff = newQuantityData.numericValue - oldQuantityData.numericValue;
 
-        console.log(`🗑️ [ENHANCED-DELETE] Invoice ${invoice.bill_number}: Total=${invoice.grand_total}, Paid=${invoice.amount_paid || 0}, Payment Handling=${paymentHandling}`);
+                  console.log(`📊 [STOCK-DEBUG] Quantity change: ${oldQuantityData.numericValue} → ${newQuantityData.numericValue} (diff: ${quantityDiff})`);
 
-        // ✅ ENHANCED PAYMENT HANDLING: Handle payments based on user choice
-        const hasPayments = (invoice.amount_paid || 0) > 0;
-        if (hasPayments) {
-          console.log(`💰 [ENHANCED-DELETE] Invoice has payments of Rs.${invoice.amount_paid}, handling via ${paymentHandling} option...`);
+                  if (quantityDiff !== 0) {
+                    // Check stock availability for increases
+                    if (quantityDiff > 0) {
+                      const currentStockData = parseUnit(product.current_stock, product.unit_type || 'piece');
+                      if (currentStockData.numericValue < quantityDiff) {
+                        throw new Error(`Insufficient stock for ${product.name}. Available: ${product.current_stock}, Required: ${quantityDiff}`);
+                      }
+                    }
 
-          if (paymentHandling === 'credit') {
-            console.log(`💳 [ENHANCED-DELETE] CREDIT mode: Keeping existing payment entries as customer credit`);
-            // No additional action needed - existing payment entries in customer ledger already represent credit
-            // The customer balance will remain properly credited from the original payment entries
-          } else if (paymentHandling === 'delete') {
-            console.log(`🗑️ [ENHANCED-DELETE] DELETE mode: Will delete all payment records and entries`);
-            // Payment deletion will be handled in the conditional deletion section below
-          }
-        }
+                    // Update stock
+                    const currentStockData = parseUnit(product.current_stock, product.unit_type || 'piece');
+                    const newStock = currentStockData.numericValue - quantityDiff;
+                    const newStockString = formatUnitString(
+                      createUnitFromNumericValue(newStock, product.unit_type || 'piece'),
+                      product.unit_type || 'piece'
+                    );
 
-        // Continue with the rest of the deletion process
+                    await this.dbConnection.execute(
+                      'UPDATE products SET current_stock = ?, updated_at = ? WHERE id = ?',
+                      [newStockString, getCurrentSystemDateTime().dbTimestamp, newItem.product_id]
+                    );
 
-        // ✅ IMPROVED APPROACH: Reverse stock changes by getting movement details first, then delete movements
-        console.log(`🗑️ [STOCK-DELETE] Reversing stock movements for invoice ${invoiceId}`);
+                    console.log(`📦 [STOCK-DEBUG] Stock updated: ${product.current_stock} → ${newStockString}`);
 
-        try {
-          // 1. Get all stock movements for this invoice with full details
-          const originalMovements = await this.dbConnection.select(
-            'SELECT product_id, quantity, movement_type FROM stock_movements WHERE reference_type = ? AND reference_id = ?',
-            ['invoice', invoiceId]
-          );
+                    // Record stock movement
+                    const { dbDate: date, dbTime: time } = getCurrentSystemDateTime();
+                    console.log(`🔄 [STOCK-DEBUG] Creating stock movement: ${quantityDiff > 0 ? 'out' : 'in'} ${Math.abs(quantityDiff)} for ${product.name}`);
 
-          console.log(`📦 [STOCK-DELETE] Found ${originalMovements.length} stock movements to reverse`);
+                    const stockMovementResult = await this.dbConnection.execute(
+                      `INSERT INTO stock_movements (
+                        product_id, product_name, movement_type, quantity, previous_stock, new_stock,
+                        reason, reference_type, reference_id, notes, date, time, created_by, created_at, updated_at
+                      ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)`,
+                      [
+                        newItem.product_id,
+                        product.name,
+                        quantityDiff > 0 ? 'out' : 'in',
+                        Math.abs(quantityDiff),
+                        currentStockData.numericValue,
+                        newStock,
+                        `Stock ${quantityDiff > 0 ? 'deducted' : 'restored'} due to invoice edit`,
+                        'invoice',
+                        invoiceId,
+                        `Invoice ${existingInvoice.bill_number} edited`,
+                        date, time, 'system',
+                        getCurrentSystemDateTime().dbTimestamp,
+                        getCurrentSystemDateTime().dbTimestamp
+                      ]
+                    );
 
-          // 2. Reverse each movement by directly updating product stock BEFORE deleting movements
-          for (const movement of originalMovements) {
-            try {
-              const product = await this.getProduct(movement.product_id);
-              if (product && product.track_inventory) {
-                // Parse current stock and movement quantity
-                const currentStockData = parseUnit(product.current_stock, product.unit_type || 'piece');
-                const movementQuantityData = parseUnit(movement.quantity, product.unit_type || 'piece');
-
-                if (currentStockData && movementQuantityData && !isNaN(movementQuantityData.numericValue)) {
-                  // Reverse the movement: if it was 'out' (reducing stock), add it back; if it was 'in' (adding stock), subtract it
-                  let newStockValue = currentStockData.numericValue || 0;
-
-                  if (movement.movement_type === 'out') {
-                    // This movement reduced stock, so add it back
-                    newStockValue += movementQuantityData.numericValue;
-                    console.log(`🔄 [STOCK-DELETE] Reversing OUT movement: ${product.name} +${movementQuantityData.numericValue} (was reduced by invoice)`);
-                  } else if (movement.movement_type === 'in') {
-                    // This movement increased stock, so subtract it
-                    newStockValue -= movementQuantityData.numericValue;
-                    console.log(`🔄 [STOCK-DELETE] Reversing IN movement: ${product.name} -${movementQuantityData.numericValue} (was added by invoice)`);
+                    console.log(`✅ [STOCK-DEBUG] Stock movement created successfully with ID: ${stockMovementResult.lastInsertId}`);
+                  } else {
+                    console.log(`⏭️ [STOCK-DEBUG] No quantity change, skipping stock movement`);
                   }
-
-                  // Ensure stock doesn't go negative
-                  newStockValue = Math.max(0, newStockValue);
-
-                  // Format and update the stock
-                  const newStockString = formatUnitString(
-                    createUnitFromNumericValue(newStockValue, product.unit_type || 'piece'),
-                    product.unit_type || 'piece'
-                  );
-
-                  await this.dbConnection.execute(
-                    'UPDATE products SET current_stock = ?, updated_at = ? WHERE id = ?',
-                    [newStockString, getCurrentSystemDateTime().dbTimestamp, movement.product_id]
-                  );
-
-                  console.log(`✅ [STOCK-DELETE] Updated ${product.name} stock: ${product.current_stock} → ${newStockString}`);
+                } else {
+                  console.log(`⚠️ [STOCK-DEBUG] Product ${newItem.product_id} does not have inventory tracking enabled or product not found`);
                 }
+              } else {
+                console.log(`⏭️ [STOCK-DEBUG] Skipping stock update - misc item or non-stock item`);
               }
-            } catch (productError) {
-              console.error(`❌ [STOCK-DELETE] Failed to reverse stock for product ${movement.product_id}:`, productError);
-              // Continue with other products even if one fails
-            }
-          }
 
-          // 3. Now delete the stock movements after stock has been properly reversed
-          const deleteResult = await this.dbConnection.execute(
-            'DELETE FROM stock_movements WHERE reference_type = ? AND reference_id = ?',
-            ['invoice', invoiceId]
-          );
-
-          console.log(`✅ [STOCK-DELETE] Deleted ${deleteResult.changes || 0} original stock movements after reversing stock changes`);
-
-        } catch (error) {
-          console.error(`❌ [STOCK-DELETE] Error reversing stock movements:`, error);
-          // Fallback to manual item-based stock adjustment
-          console.warn(`⚠️ [STOCK-DELETE] Falling back to manual stock adjustment using invoice items`);
-
-          // Get invoice items for manual stock restoration as fallback
-          const items = await this.getInvoiceItems(invoiceId);
+              // Update the item
+              await this.dbConnection.execute(
+                `UPDATE invoice_items SET 
+                  product_id = ?, product_name = ?, quantity = ?, unit_price = ?, total_price = ?,
+                  unit = ?, length = ?, pieces = ?, is_misc_item = ?, misc_description = ?,
+                  t_iron_pieces = ?, t_iron_length_per_piece = ?, t_iron_total_feet = ?, t_iron_unit = ?,
+                  is_non_stock_item = ?, updated_at = ?
+                WHERE id = ?`,
+                [
+                  newItem.product_id, newItem.product_name, newItem.quantity, newItem.unit_price, newItem.total_price,
+                  newItem.unit, newItem.length, newItem.pieces, newItem.is_misc_item ? 1 : 0, newItem.misc_description,
+                  newItem.t_iron_pieces, newItem.t_iron_length_per_piece, newItem.t_iron_total_feet, newItem.t_iron_unit,
+                  newItem.is_non_stock_item ? 1 : 0, getCurrentSystemDateTime().dbTimestamp,
+                  newItem.id
+                ]
+              );
+            } else {
+              // Add new item
+              // Check stock for new inventory items
+              if (newItem.product_id && !newItem.is_misc_item && !newItem.is_non_stock_item) {
+                const product = await this.getProduct(newItem.product_id);
+                if (product && product.track_inventory) {
+                  const quantityData = parseUnit(newItem.quantity, product.unit_type || 'piece');
+                  const currentStockData = parseUnit(product.current_stock, product.unit_type || 'piece');
 
-          for (const item of items) {
-            // Skip misc items
-            if (!item.product_id || Boolean(item.is_misc_item)) {
-              continue;
-            }
+                  if (currentStockData.numericValue < quantityData.numericValue) {
+                    throw new Error(`Insufficient stock for ${product.name}. Available: ${product.current_stock}, Required: ${newItem.quantity}`);
+                  }
 
-            const product = await this.getProduct(item.product_id);
-            if (product && product.track_inventory) {
-              try {
-                // Try to parse quantity safely
-                const itemQuantityData = parseUnit(item.quantity, product.unit_type || 'piece');
-                if (itemQuantityData && !isNaN(itemQuantityData.numericValue) && itemQuantityData.numericValue > 0) {
-                  // Update stock directly (fallback method)
-                  const currentStockData = parseUnit(product.current_stock, product.unit_type || 'piece');
-                  const newStock = (currentStockData.numericValue || 0) + itemQuantityData.numericValue;
+                  // Deduct stock
+                  const newStock = currentStockData.numericValue - quantityData.numericValue;
                   const newStockString = formatUnitString(
                     createUnitFromNumericValue(newStock, product.unit_type || 'piece'),
                     product.unit_type || 'piece'
@@ -12305,1789 +12226,234 @@ export class DatabaseService {
 
                   await this.dbConnection.execute(
                     'UPDATE products SET current_stock = ?, updated_at = ? WHERE id = ?',
-                    [newStockString, getCurrentSystemDateTime().dbTimestamp, item.product_id]
+                    [newStockString, getCurrentSystemDateTime().dbTimestamp, newItem.product_id]
                   );
 
-                  console.log(`🔧 [STOCK-DELETE] Fallback: Manually restored stock for ${item.product_name}`);
-                }
-              } catch (itemError) {
-                console.error(`❌ [STOCK-DELETE] Fallback failed for ${item.product_name}:`, itemError);
-              }
-            }
-          }
-        }
-
-        // Delete related records in proper order
-        await this.dbConnection.execute('DELETE FROM invoice_items WHERE invoice_id = ?', [invoiceId]);
-
-        // Note: Stock movements are already deleted in the new approach above
-        // No need for additional cancellation or deletion of stock movements
-
-        // ✅ COMPREHENSIVE FIX: Delete daily ledger entries with all possible reference patterns
-        try {
-          // Get invoice details and payment IDs for comprehensive cleanup
-          const invoiceDetails = await this.dbConnection.select(
-            'SELECT bill_number FROM invoices WHERE id = ?',
-            [invoiceId]
-          );
-          const billNumber = invoiceDetails[0]?.bill_number;
-
-          // Get payment IDs for this invoice to clean up payment-related daily ledger entries
-          const paymentIds = await this.dbConnection.select(
-            'SELECT id FROM payments WHERE invoice_id = ?',
-            [invoiceId]
-          );
-
-          let totalDailyLedgerDeleted = 0;
-
-          // Pattern 1: Daily ledger entries are stored with reference_type = 'payment' for invoice payments
-          const ledgerResult = await this.dbConnection.execute(
-            'DELETE FROM ledger_entries WHERE reference_type = ? AND reference_id = ?',
-            ['payment', invoiceId]
-          );
-          totalDailyLedgerDeleted += ledgerResult.changes || 0;
-
-          // Pattern 2: Check for any entries with reference_type = 'invoice'
-          const invoiceRefResult = await this.dbConnection.execute(
-            'DELETE FROM ledger_entries WHERE reference_type = ? AND reference_id = ?',
-            ['invoice', invoiceId]
-          );
-          totalDailyLedgerDeleted += invoiceRefResult.changes || 0;
-
-          // Pattern 3: Delete payment-specific daily ledger entries by payment ID
-          for (const payment of paymentIds) {
-            const paymentRefResult = await this.dbConnection.execute(
-              'DELETE FROM ledger_entries WHERE reference_type = ? AND reference_id = ?',
-              ['payment', payment.id]
-            );
-            totalDailyLedgerDeleted += paymentRefResult.changes || 0;
-          }
-
-          // Pattern 4: Delete entries using bill_number as reference
-          // Pattern 4: Delete entries using bill_number as reference
-          if (billNumber) {
-            const billRefResult = await this.dbConnection.execute(
-              'DELETE FROM ledger_entries WHERE bill_number = ?',
-              [billNumber]
-            );
-            totalDailyLedgerDeleted += billRefResult.changes || 0;
-          }
-
-          console.log(`✅ [ENHANCED-DELETE] ${totalDailyLedgerDeleted} daily ledger entries deleted for invoice ${invoiceId} and its ${paymentIds.length} payment(s)`);
-        } catch (error: any) {
-          if (error.message?.includes('no such table: customer_ledger') || error.message?.includes('no such table: ledger_entries')) {
-            console.warn('⚠️ [ENHANCED-DELETE] Ledger table not found, skipping ledger cleanup');
-          } else {
-            throw error;
-          }
-        }
-
-        // PERMANENT SOLUTION: Delete miscellaneous item ledger entries
-        try {
-          await this.deleteMiscellaneousItemLedgerEntries(invoiceId);
-        } catch (error: any) {
-          if (error.message?.includes('no such table')) {
-            console.warn('⚠️ [ENHANCED-DELETE] Miscellaneous ledger table not found, skipping misc cleanup');
-          } else {
-            throw error;
-          }
-        }
-
-        // CONDITIONAL DELETION: Handle payment records based on user choice
-        if (paymentHandling === 'delete') {
-          console.log('🗑️ [ENHANCED-DELETE] DELETE mode: Removing all payment records and ledger entries');
-
-          // Delete payment records completely
-          await this.dbConnection.execute('DELETE FROM payments WHERE invoice_id = ?', [invoiceId]);
-
-          // Delete ALL customer ledger entries and daily ledger entries for this invoice
-          try {
-            // Get all payment IDs for this invoice to clean up payment-related ledger entries
-            const paymentIds = await this.dbConnection.select(
-              'SELECT id FROM payments WHERE invoice_id = ?',
-              [invoiceId]
-            );
-
-            let totalDeleted = 0;
-
-            // Pattern 1: Delete entries with reference_id = invoiceId (invoice-related entries)
-            const invoiceResult = await this.dbConnection.execute(
-              'DELETE FROM customer_ledger_entries WHERE reference_id = ?',
-              [invoiceId]
-            );
-            totalDeleted += invoiceResult.changes || 0;
-
-            // Pattern 2: Delete entries with invoice_id = invoiceId (payment entries linked to invoice)
-            const invoiceLinkedResult = await this.dbConnection.execute(
-              'DELETE FROM customer_ledger_entries WHERE invoice_id = ?',
-              [invoiceId]
-            );
-            totalDeleted += invoiceLinkedResult.changes || 0;
-
-            // Pattern 3: Delete entries where reference_id matches payment IDs (payment-specific entries)
-            for (const payment of paymentIds) {
-              const paymentResult = await this.dbConnection.execute(
-                'DELETE FROM customer_ledger_entries WHERE reference_id = ?',
-                [payment.id]
-              );
-              totalDeleted += paymentResult.changes || 0;
-            }
-
-            // Pattern 4: Delete daily ledger entries for this invoice and its payments
-            await this.dbConnection.execute(
-              'DELETE FROM ledger_entries WHERE reference_id = ? AND reference_type = ?',
-              [invoiceId, 'invoice']
-            );
-
-            for (const payment of paymentIds) {
-              await this.dbConnection.execute(
-                'DELETE FROM ledger_entries WHERE reference_id = ? AND reference_type = ?',
-                [payment.id, 'payment']
-              );
-            }
-
-            console.log(`✅ [ENHANCED-DELETE] DELETE mode: ${totalDeleted} customer ledger entries deleted for invoice ${invoiceId} and its ${paymentIds.length} payment(s)`);
-
-            // IMMEDIATE: Emit customer ledger update event for real-time UI refresh
-            eventBus.emit('CUSTOMER_LEDGER_UPDATED', {
-              customerId: invoice.customer_id,
-              invoiceId: invoiceId,
-              action: 'all_entries_deleted',
-              mode: 'delete',
-              timestamp: getCurrentSystemDateTime().dbTimestamp
-            });
-
-          } catch (error: any) {
-            if (error.message?.includes('no such table')) {
-              console.warn('⚠️ [ENHANCED-DELETE] Customer ledger entries table not found, skipping cleanup');
-            } else {
-              console.warn('⚠️ [ENHANCED-DELETE] Warning during customer ledger cleanup:', error.message);
-            }
-          }
-        } else if (paymentHandling === 'credit') {
-          console.log('💳 [ENHANCED-DELETE] CREDIT mode: Keeping payment records and ledger entries, only removing invoice entries');
-
-          // Keep payment records in payments table (don't delete)
-          console.log('💳 [ENHANCED-DELETE] Keeping payment records in payments table');
-
-          // Only delete INVOICE-RELATED customer ledger entries, keep payment entries
-          try {
-            let totalDeleted = 0;
-
-            // Only delete entries with reference_id = invoiceId AND transaction_type related to invoice creation
-            // This keeps payment entries but removes invoice creation entries
-            const invoiceResult = await this.dbConnection.execute(
-              'DELETE FROM customer_ledger_entries WHERE reference_id = ? AND (transaction_type = ? OR transaction_type = ? OR transaction_type = ?)',
-              [invoiceId, 'invoice', 'sale', 'invoice_creation']
-            );
-            totalDeleted += invoiceResult.changes || 0;
-
-            // Also delete any entries with invoice_id = invoiceId that are NOT payment entries
-            const invoiceLinkedResult = await this.dbConnection.execute(
-              'DELETE FROM customer_ledger_entries WHERE invoice_id = ? AND transaction_type != ?',
-              [invoiceId, 'payment']
-            );
-            totalDeleted += invoiceLinkedResult.changes || 0;
-
-            // Only delete invoice-related daily ledger entries, keep payment entries
-            await this.dbConnection.execute(
-              'DELETE FROM ledger_entries WHERE reference_id = ? AND reference_type = ?',
-              [invoiceId, 'invoice']
-            );
-
-            console.log(`✅ [ENHANCED-DELETE] CREDIT mode: ${totalDeleted} invoice-related ledger entries deleted, payment entries preserved`);
-
-            // IMMEDIATE: Emit customer ledger update event for real-time UI refresh
-            eventBus.emit('CUSTOMER_LEDGER_UPDATED', {
-              customerId: invoice.customer_id,
-              invoiceId: invoiceId,
-              action: 'invoice_entries_deleted',
-              mode: 'credit',
-              timestamp: getCurrentSystemDateTime().dbTimestamp
-            });
-
-          } catch (error: any) {
-            if (error.message?.includes('no such table')) {
-              console.warn('⚠️ [ENHANCED-DELETE] Customer ledger entries table not found, skipping cleanup');
-            } else {
-              console.warn('⚠️ [ENHANCED-DELETE] Warning during customer ledger cleanup:', error.message);
-            }
-          }
-        }
-
-        // CRITICAL FIX: Delete invoice payment records to prevent orphaned entries
-        try {
-          await this.dbConnection.execute('DELETE FROM invoice_payment_allocations WHERE invoice_id = ?', [invoiceId]);
-          await this.dbConnection.execute('DELETE FROM invoice_payments WHERE invoice_id = ?', [invoiceId]);
-          console.log('✅ [ENHANCED-DELETE] Invoice payment records deleted');
-        } catch (error: any) {
-          if (error.message?.includes('no such table')) {
-            console.warn('⚠️ [ENHANCED-DELETE] Invoice payment tables not found, skipping cleanup');
-          } else {
-            console.warn('⚠️ [ENHANCED-DELETE] Warning during payment cleanup:', error.message);
-          }
-        }
-
-        // Finally delete the invoice
-        await this.dbConnection.execute('DELETE FROM invoices WHERE id = ?', [invoiceId]);
-
-        // IMMEDIATE: Emit invoice deletion event for instant UI update
-        eventBus.emit('INVOICE_DELETED_IMMEDIATE', {
-          invoiceId: invoiceId,
-          customerId: invoice.customer_id,
-          billNumber: invoice.bill_number,
-          paymentHandling: paymentHandling,
-          timestamp: getCurrentSystemDateTime().dbTimestamp
-        });
-
-        // Commit transaction
-        await this.dbConnection.execute('COMMIT');
-
-        // IMMEDIATE: Force customer balance refresh after transaction commit
-        eventBus.emit('CUSTOMER_BALANCE_REFRESH_IMMEDIATE', {
-          customerId: invoice.customer_id,
-          paymentHandling: paymentHandling,
-          timestamp: getCurrentSystemDateTime().dbTimestamp
-        });
-
-        // IMMEDIATE: Force comprehensive customer data refresh
-        this.forceCustomerDataRefresh(invoice.customer_id);
-
-        // Emit comprehensive real-time update events
-        this.emitInvoiceDeletedEvents(invoice);
-
-        console.log(`✅ [ENHANCED-DELETE] Invoice ${invoice.bill_number} successfully deleted with ${paymentHandling} payment handling`);
-
-      } catch (error) {
-        // Rollback on error
-        await this.dbConnection.execute('ROLLBACK');
-        throw error;
-      }
-
-    } catch (error) {
-      console.error(`❌ [ENHANCED-DELETE] Error deleting invoice with ${paymentHandling} payment handling:`, error);
-      throw error;
-    }
-  }
-  /**
-   * Force immediate UI refresh for customer-related data
-   */
-  private forceCustomerDataRefresh(customerId: number): void {
-    try {
-      // Clear customer balance cache
-      eventBus.emit('CLEAR_CUSTOMER_CACHE', { customerId });
-
-      // Force customer dropdown refresh
-      eventBus.emit('REFRESH_CUSTOMER_DROPDOWN', { customerId });
-
-      // Force customer ledger refresh
-      eventBus.emit('REFRESH_CUSTOMER_LEDGER', { customerId });
-
-      // General UI refresh
-      eventBus.emit('FORCE_UI_REFRESH', {
-        type: 'customer_data',
-        customerId,
-        timestamp: Date.now()
-      });
-
-      console.log(`🔄 [UI-REFRESH] Forced immediate refresh for customer ${customerId}`);
-    } catch (error) {
-      console.warn(`⚠️ [UI-REFRESH] Failed to force customer refresh:`, error);
-    }
-  }
-
-  /**
-   * Emit events for invoice deletion - Enhanced for immediate UI updates
-   */
-  private emitInvoiceDeletedEvents(invoice: any): void {
-    try {
-      // Core business event
-      eventBus.emit(BUSINESS_EVENTS.INVOICE_DELETED, {
-        invoiceId: invoice.id,
-        billNumber: invoice.bill_number,
-        customerId: invoice.customer_id,
-        customerName: invoice.customer_name,
-        timestamp: getCurrentSystemDateTime().dbTimestamp
-      });
-
-      // CRITICAL: Customer-specific events for immediate UI updates
-      eventBus.emit(BUSINESS_EVENTS.CUSTOMER_BALANCE_UPDATED, {
-        customerId: invoice.customer_id,
-        customerName: invoice.customer_name,
-        timestamp: getCurrentSystemDateTime().dbTimestamp
-      });
-
-      // CRITICAL: Customer ledger refresh events
-      eventBus.emit('CUSTOMER_LEDGER_UPDATED', {
-        customerId: invoice.customer_id,
-        invoiceId: invoice.id,
-        action: 'deleted',
-        timestamp: getCurrentSystemDateTime().dbTimestamp
-      });
-
-      // CRITICAL: Force customer dropdown refresh in invoice forms
-      eventBus.emit('CUSTOMER_BALANCE_REFRESH', {
-        customerId: invoice.customer_id,
-        timestamp: getCurrentSystemDateTime().dbTimestamp
-      });
-
-      // CRITICAL: Invoice list refresh events
-      eventBus.emit('INVOICES_UPDATED', {
-        action: 'deleted',
-        invoiceId: invoice.id,
-        customerId: invoice.customer_id,
-        timestamp: getCurrentSystemDateTime().dbTimestamp
-      });
-
-      // CRITICAL: Stock update events for inventory UI
-      eventBus.emit(BUSINESS_EVENTS.STOCK_UPDATED, {
-        message: `Stock restored from deleted invoice ${invoice.bill_number}`,
-        invoiceId: invoice.id,
-        timestamp: getCurrentSystemDateTime().dbTimestamp
-      });
-
-      // CRITICAL: Force comprehensive UI refresh
-      eventBus.emit('UI_REFRESH_REQUESTED', {
-        type: 'invoice_deleted',
-        invoiceId: invoice.id,
-        customerId: invoice.customer_id,
-        timestamp: getCurrentSystemDateTime().dbTimestamp
-      });
-
-      // CRITICAL: Clear caches that might show stale data
-      eventBus.emit('CACHE_INVALIDATE', {
-        types: ['customer_balance', 'invoice_list', 'customer_ledger'],
-        customerId: invoice.customer_id,
-        invoiceId: invoice.id,
-        timestamp: getCurrentSystemDateTime().dbTimestamp
-      });
-
-      // CRITICAL: Comprehensive data refresh event
-      eventBus.emit('COMPREHENSIVE_DATA_REFRESH', {
-        type: 'invoice_deleted',
-        customerId: invoice.customer_id,
-        timestamp: getCurrentSystemDateTime().dbTimestamp
-      });
-
-      console.log(`🚀 [REAL-TIME] Comprehensive deletion events emitted for invoice ${invoice.bill_number} - customer ${invoice.customer_id}`);
-    } catch (error) {
-      console.warn('❌ [REAL-TIME] Could not emit invoice deleted events:', error);
-    }
-  }
-
-  /**
-   * PRODUCTION-READY: Enhanced Edit Invoice functionality
-   * Handles all aspects of invoice editing with data integrity
-   */
-  async updateInvoice(invoiceId: number, updateData: {
-    discount?: number;
-    notes?: string;
-    payment_amount?: number;
-    payment_method?: string;
-    items?: Array<{
-      id?: number;
-      product_id: number | null;
-      product_name: string;
-      quantity: string | number;
-      unit_price: number;
-      total_price: number;
-      unit?: string;
-      length?: number;
-      pieces?: number;
-      is_misc_item?: boolean;
-      misc_description?: string;
-      t_iron_pieces?: number;
-      t_iron_length_per_piece?: number;
-      t_iron_total_feet?: number;
-      t_iron_unit?: string;
-      is_non_stock_item?: boolean;
-    }>;
-    expected_version?: number; // OPTIMISTIC LOCKING: Expected version for concurrent edit protection
-  }): Promise<DatabaseOperationResult> {
-    const startTime = Date.now();
-
-    console.log(`🔥 [INVOICE-DEBUG] updateInvoice called for invoice ${invoiceId} with data:`, updateData);
-
-    try {
-      if (!this.isInitialized) {
-        await this.initialize();
-      }
-
-      // VALIDATION: Check if invoice can be edited (must be fully unpaid)
-      await this.validateInvoiceEditability(invoiceId);
-
-      // OPTIMISTIC LOCKING: Check version before starting transaction
-      if (updateData.expected_version !== undefined) {
-        const versionCheck = await this.dbConnection.select(
-          'SELECT updated_at, version FROM invoices WHERE id = ?',
-          [invoiceId]
-        );
-
-        if (!versionCheck || versionCheck.length === 0) {
-          throw new Error('Invoice not found for version check');
-        }
-
-        const currentVersion = versionCheck[0].version || 0;
-        if (currentVersion !== updateData.expected_version) {
-          throw new Error(
-            `Concurrent modification detected. Expected version ${updateData.expected_version}, ` +
-            `but current version is ${currentVersion}. Please refresh and try again.`
-          );
-        }
-        console.log(`🔒 [OPTIMISTIC-LOCK] Version check passed: ${currentVersion}`);
-      }
-
-      // Start transaction for atomicity
-      await this.dbConnection.execute('BEGIN TRANSACTION');
-      console.log(`🔥 [INVOICE-DEBUG] Transaction started for invoice ${invoiceId}`);
-
-      try {
-        // Get existing invoice details
-        const existingInvoice = await this.getInvoiceDetails(invoiceId);
-        if (!existingInvoice) {
-          throw new Error('Invoice not found');
-        }
-
-        // BUSINESS RULE: Check if invoice can be edited
-        if (existingInvoice.payment_amount > 0 && existingInvoice.status === 'paid') {
-          throw new Error('Cannot edit fully paid invoices');
-        }
-
-        // Get existing items for comparison
-        const existingItems = await this.getInvoiceItems(invoiceId);
-        const existingItemsMap = new Map(existingItems.map(item => [item.id, item]));
-
-        let totalItemsValue = 0;
-
-        // Process item changes if provided
-        if (updateData.items) {
-          // 1. Handle removed items (restore stock)
-          for (const existingItem of existingItems) {
-            const stillExists = updateData.items.some(newItem => newItem.id === existingItem.id);
-            if (!stillExists && existingItem.product_id) {
-              const product = await this.getProduct(existingItem.product_id);
-              if (product && product.track_inventory) {
-                // Restore stock for removed item
-                const currentStockData = parseUnit(product.current_stock, product.unit_type || 'piece');
-                const itemQuantityData = parseUnit(existingItem.quantity, product.unit_type || 'piece');
-
-                const newStock = currentStockData.numericValue + itemQuantityData.numericValue;
-                const newStockString = formatUnitString(
-                  createUnitFromNumericValue(newStock, product.unit_type || 'piece'),
-                  product.unit_type || 'piece'
-                );
-
-                await this.dbConnection.execute(
-                  'UPDATE products SET current_stock = ?, updated_at = ? WHERE id = ?',
-                  [newStockString, getCurrentSystemDateTime().dbTimestamp, existingItem.product_id]
-                );
-
-                // Record stock movement
-                const { dbDate: date, dbTime: time } = getCurrentSystemDateTime();
-                await this.dbConnection.execute(
-                  `INSERT INTO stock_movements (
-                    product_id, product_name, movement_type, quantity, previous_stock, new_stock,
-                    reason, reference_type, reference_id, notes, date, time, created_by, created_at, updated_at
-                  ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)`,
-                  [
-                    existingItem.product_id,
-                    product.name,
-                    'in',
-                    itemQuantityData.numericValue,
-                    currentStockData.numericValue,
-                    newStock,
-                    'Stock restored due to invoice item removal',
-                    'invoice',
-                    invoiceId,
-                    `Item removed from invoice ${existingInvoice.bill_number}`,
-                    date, time, 'system',
-                    getCurrentSystemDateTime().dbTimestamp,
-                    getCurrentSystemDateTime().dbTimestamp
-                  ]
-                );
-              }
-
-              // Delete the item
-              await this.dbConnection.execute('DELETE FROM invoice_items WHERE id = ?', [existingItem.id]);
-            }
-          }
-
-          // 2. Handle updated and new items
-          for (const newItem of updateData.items) {
-            if (newItem.id && existingItemsMap.has(newItem.id)) {
-              // Update existing item
-              const existingItem = existingItemsMap.get(newItem.id)!;
-
-              // Handle stock changes for inventory items
-              if (newItem.product_id && !newItem.is_misc_item && !newItem.is_non_stock_item) {
-                const product = await this.getProduct(newItem.product_id);
-                console.log(`🔍 [STOCK-DEBUG] Checking product ${newItem.product_id}: ${product?.name}, track_inventory: ${product?.track_inventory}`);
-
-                if (product && product.track_inventory) {
-                  const oldQuantityData = parseUnit(existingItem.quantity, product.unit_type || 'piece');
-                  const newQuantityData = parseUnit(newItem.quantity, product.unit_type || 'piece');
-                  const quantityDiff = newQuantityData.numericValue - oldQuantityData.numericValue;
-
-                  console.log(`📊 [STOCK-DEBUG] Quantity change: ${oldQuantityData.numericValue} → ${newQuantityData.numericValue} (diff: ${quantityDiff})`);
-
-                  if (quantityDiff !== 0) {
-                    // Check stock availability for increases
-                    if (quantityDiff > 0) {
-                      const currentStockData = parseUnit(product.current_stock, product.unit_type || 'piece');
-                      if (currentStockData.numericValue < quantityDiff) {
-                        throw new Error(`Insufficient stock for ${product.name}. Available: ${product.current_stock}, Required: ${quantityDiff}`);
-                      }
-                    }
-
-                    // Update stock
-                    const currentStockData = parseUnit(product.current_stock, product.unit_type || 'piece');
-                    const newStock = currentStockData.numericValue - quantityDiff;
-                    const newStockString = formatUnitString(
-                      createUnitFromNumericValue(newStock, product.unit_type || 'piece'),
-                      product.unit_type || 'piece'
-                    );
-
-                    await this.dbConnection.execute(
-                      'UPDATE products SET current_stock = ?, updated_at = ? WHERE id = ?',
-                      [newStockString, getCurrentSystemDateTime().dbTimestamp, newItem.product_id]
-                    );
-
-                    console.log(`📦 [STOCK-DEBUG] Stock updated: ${product.current_stock} → ${newStockString}`);
-
-                    // Record stock movement
-                    const { dbDate: date, dbTime: time } = getCurrentSystemDateTime();
-                    console.log(`🔄 [STOCK-DEBUG] Creating stock movement: ${quantityDiff > 0 ? 'out' : 'in'} ${Math.abs(quantityDiff)} for ${product.name}`);
-
-                    const stockMovementResult = await this.dbConnection.execute(
-                      `INSERT INTO stock_movements (
-                        product_id, product_name, movement_type, quantity, previous_stock, new_stock,
-                        reason, reference_type, reference_id, notes, date, time, created_by, created_at, updated_at
-                      ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)`,
-                      [
-                        newItem.product_id,
-                        product.name,
-                        quantityDiff > 0 ? 'out' : 'in',
-                        Math.abs(quantityDiff),
-                        currentStockData.numericValue,
-                        newStock,
-                        `Stock ${quantityDiff > 0 ? 'deducted' : 'restored'} due to invoice edit`,
-                        'invoice',
-                        invoiceId,
-                        `Invoice ${existingInvoice.bill_number} edited`,
-                        date, time, 'system',
-                        getCurrentSystemDateTime().dbTimestamp,
-                        getCurrentSystemDateTime().dbTimestamp
-                      ]
-                    );
-
-                    console.log(`✅ [STOCK-DEBUG] Stock movement created successfully with ID: ${stockMovementResult.lastInsertId}`);
-                  } else {
-                    console.log(`⏭️ [STOCK-DEBUG] No quantity change, skipping stock movement`);
-                  }
-                } else {
-                  console.log(`⚠️ [STOCK-DEBUG] Product ${newItem.product_id} does not have inventory tracking enabled or product not found`);
-                }
-              } else {
-                console.log(`⏭️ [STOCK-DEBUG] Skipping stock update - misc item or non-stock item`);
-              }
-
-              // Update the item
-              await this.dbConnection.execute(
-                `UPDATE invoice_items SET 
-                  product_id = ?, product_name = ?, quantity = ?, unit_price = ?, total_price = ?,
-                  unit = ?, length = ?, pieces = ?, is_misc_item = ?, misc_description = ?,
-                  t_iron_pieces = ?, t_iron_length_per_piece = ?, t_iron_total_feet = ?, t_iron_unit = ?,
-                  is_non_stock_item = ?, updated_at = ?
-                WHERE id = ?`,
-                [
-                  newItem.product_id, newItem.product_name, newItem.quantity, newItem.unit_price, newItem.total_price,
-                  newItem.unit, newItem.length, newItem.pieces, newItem.is_misc_item ? 1 : 0, newItem.misc_description,
-                  newItem.t_iron_pieces, newItem.t_iron_length_per_piece, newItem.t_iron_total_feet, newItem.t_iron_unit,
-                  newItem.is_non_stock_item ? 1 : 0, getCurrentSystemDateTime().dbTimestamp,
-                  newItem.id
-                ]
-              );
-            } else {
-              // Add new item
-              // Check stock for new inventory items
-              if (newItem.product_id && !newItem.is_misc_item && !newItem.is_non_stock_item) {
-                const product = await this.getProduct(newItem.product_id);
-                if (product && product.track_inventory) {
-                  const quantityData = parseUnit(newItem.quantity, product.unit_type || 'piece');
-                  const currentStockData = parseUnit(product.current_stock, product.unit_type || 'piece');
-
-                  if (currentStockData.numericValue < quantityData.numericValue) {
-                    throw new Error(`Insufficient stock for ${product.name}. Available: ${product.current_stock}, Required: ${newItem.quantity}`);
-                  }
-
-                  // Deduct stock
-                  const newStock = currentStockData.numericValue - quantityData.numericValue;
-                  const newStockString = formatUnitString(
-                    createUnitFromNumericValue(newStock, product.unit_type || 'piece'),
-                    product.unit_type || 'piece'
-                  );
-
-                  await this.dbConnection.execute(
-                    'UPDATE products SET current_stock = ?, updated_at = ? WHERE id = ?',
-                    [newStockString, getCurrentSystemDateTime().dbTimestamp, newItem.product_id]
-                  );
-
-                  // Record stock movement
-                  const { dbDate: date, dbTime: time } = getCurrentSystemDateTime();
-                  await this.dbConnection.execute(
-                    `INSERT INTO stock_movements (
-                      product_id, product_name, movement_type, quantity, previous_stock, new_stock,
-                      reason, reference_type, reference_id, notes, date, time, created_by, created_at, updated_at
-                    ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)`,
-                    [
-                      newItem.product_id,
-                      product.name,
-                      'out',
-                      quantityData.numericValue,
-                      currentStockData.numericValue,
-                      newStock,
-                      'Stock deducted for new invoice item',
-                      'invoice',
-                      invoiceId,
-                      `New item added to invoice ${existingInvoice.bill_number}`,
-                      date, time, 'system',
-                      getCurrentSystemDateTime().dbTimestamp,
-                      getCurrentSystemDateTime().dbTimestamp
-                    ]
-                  );
-                }
-              }
-
-              // Insert new item
-              await this.dbConnection.execute(
-                `INSERT INTO invoice_items (
-                  invoice_id, product_id, product_name, quantity, unit_price, total_price,
-                  unit, length, pieces, is_misc_item, misc_description,
-                  t_iron_pieces, t_iron_length_per_piece, t_iron_total_feet, t_iron_unit,
-                  is_non_stock_item, created_at, updated_at
-                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)`,
-                [
-                  invoiceId, newItem.product_id, newItem.product_name, newItem.quantity, newItem.unit_price, newItem.total_price,
-                  newItem.unit, newItem.length, newItem.pieces, newItem.is_misc_item ? 1 : 0, newItem.misc_description,
-                  newItem.t_iron_pieces, newItem.t_iron_length_per_piece, newItem.t_iron_total_feet, newItem.t_iron_unit,
-                  newItem.is_non_stock_item ? 1 : 0, getCurrentSystemDateTime().dbTimestamp, getCurrentSystemDateTime().dbTimestamp
-                ]
-              );
-            }
-
-            totalItemsValue += newItem.total_price;
-          }
-        } else {
-          // No items updated, calculate total from existing items
-          totalItemsValue = existingItems.reduce((sum, item) => sum + item.total_price, 0);
-        }
-
-        // Update invoice header
-        const discount = updateData.discount !== undefined ? updateData.discount : existingInvoice.discount;
-        const grandTotal = totalItemsValue - discount;
-        const paymentAmount = updateData.payment_amount !== undefined ? updateData.payment_amount : existingInvoice.payment_amount;
-        const remainingBalance = grandTotal - paymentAmount;
-
-        await this.dbConnection.execute(
-          `UPDATE invoices SET 
-            subtotal = ?, discount = ?, grand_total = ?, payment_amount = ?, remaining_balance = ?,
-            payment_method = ?, notes = ?, 
-            status = CASE 
-              WHEN ? >= ? THEN 'paid'
-              WHEN ? > 0 THEN 'partially_paid'
-              ELSE 'pending'
-            END,
-            version = COALESCE(version, 0) + 1,
-            updated_at = ?
-          WHERE id = ?`,
-          [
-            totalItemsValue, discount, grandTotal, paymentAmount, remainingBalance,
-            updateData.payment_method || existingInvoice.payment_method,
-            updateData.notes !== undefined ? updateData.notes : existingInvoice.notes,
-            paymentAmount, grandTotal, paymentAmount,
-            getCurrentSystemDateTime().dbTimestamp,
-            invoiceId
-          ]
-        );
-
-        console.log(`🔒 [OPTIMISTIC-LOCK] Invoice version incremented for concurrent edit protection`);
-
-        // Update customer balance if total changed
-        const totalChange = grandTotal - existingInvoice.grand_total;
-        const paymentChange = paymentAmount - existingInvoice.payment_amount;
-        const balanceChange = totalChange - paymentChange;
-
-        if (balanceChange !== 0) {
-          try {
-            await this.customerBalanceManager.updateBalance(
-              existingInvoice.customer_id,
-              Math.abs(balanceChange),
-              balanceChange > 0 ? 'add' : 'subtract',
-              `Invoice ${invoiceId} edited - balance adjustment`,
-              invoiceId,
-              `EDIT-${invoiceId}`,
-              true // Skip transaction since we're already in one
-            );
-
-            this.clearCustomerCaches();
-            console.log(`✅ [EDIT-INVOICE] Customer balance updated by ${balanceChange > 0 ? '+' : ''}${balanceChange.toFixed(2)}`);
-          } catch (balanceError) {
-            console.error('❌ [EDIT-INVOICE] Failed to update balance through CustomerBalanceManager:', balanceError);
-            // Fallback to direct update
-            await this.dbConnection.execute(
-              'UPDATE customers SET balance = balance + ?, updated_at = ? WHERE id = ?',
-              [balanceChange, getCurrentSystemDateTime().dbTimestamp, existingInvoice.customer_id]
-            );
-            console.log('⚠️ [EDIT-INVOICE] Used fallback direct balance update');
-          }
-        }
-
-        // Commit transaction
-        await this.dbConnection.execute('COMMIT');
-
-        // Emit real-time update events
-        this.emitInvoiceUpdatedEvents(invoiceId, existingInvoice);
-
-        const executionTime = Date.now() - startTime;
-        console.log(`✅ [EDIT-INVOICE] Invoice ${invoiceId} updated successfully in ${executionTime}ms`);
-
-        return {
-          success: true,
-          data: { invoiceId, executionTime },
-          executionTime,
-          affectedRows: 1
-        };
-
-      } catch (error) {
-        // Rollback on error
-        await this.dbConnection.execute('ROLLBACK');
-        throw error;
-      }
-
-    } catch (error) {
-      const executionTime = Date.now() - startTime;
-      console.error(`❌ [EDIT-INVOICE] Failed to update invoice ${invoiceId}:`, error);
-
-      return {
-        success: false,
-        error: error as DatabaseError,
-        executionTime
-      };
-    }
-  }
-
-  /**
-   * Emit events for invoice updates
-   */
-  private emitInvoiceUpdatedEvents(invoiceId: number, originalInvoice: any): void {
-    try {
-      eventBus.emit(BUSINESS_EVENTS.INVOICE_UPDATED, {
-        invoiceId: invoiceId,
-        billNumber: originalInvoice.bill_number,
-        customerId: originalInvoice.customer_id,
-        customerName: originalInvoice.customer_name,
-        timestamp: getCurrentSystemDateTime().dbTimestamp
-      });
-
-      eventBus.emit(BUSINESS_EVENTS.STOCK_UPDATED, {
-        message: `Stock updated from invoice ${originalInvoice.bill_number} edit`
-      });
-
-      eventBus.emit(BUSINESS_EVENTS.CUSTOMER_BALANCE_UPDATED, {
-        customerId: originalInvoice.customer_id,
-        customerName: originalInvoice.customer_name
-      });
-
-      console.log(`🚀 Real-time update events emitted for invoice ${originalInvoice.bill_number}`);
-    } catch (error) {
-      console.warn('Could not emit invoice updated events:', error);
-    }
-  }
-
-  /**
-   * ✅ ENHANCED delete with payment handling options
-   */
-  async deleteInvoiceWithValidation(
-    invoiceId: number,
-    paymentHandling: 'credit' | 'delete' = 'credit'
-  ): Promise<DatabaseOperationResult> {
-    const startTime = Date.now();
-
-    try {
-      if (!this.isInitialized) {
-        await this.initialize();
-      }
-
-      // Get invoice details for validation
-      const invoice = await this.getInvoiceDetails(invoiceId);
-      if (!invoice) {
-        throw new Error('Invoice not found');
-      }
-
-      // Check for associated returns (still block returns)
-      const returns = await this.dbConnection.select(
-        'SELECT COUNT(*) as count FROM returns WHERE original_invoice_id = ?',
-        [invoiceId]
-      );
-
-      if (returns[0]?.count > 0) {
-        throw new Error('Cannot delete invoice with associated returns. Please handle returns first.');
-      }
-
-      console.log(`✅ [ENHANCED-DELETE] Invoice ${invoice.bill_number} validation passed - proceeding with ${paymentHandling} payment handling`);
-
-      // Proceed with enhanced deletion with payment handling option
-      await this.deleteInvoiceEnhanced(invoiceId, paymentHandling);
-
-      const executionTime = Date.now() - startTime;
-      console.log(`✅ [DELETE-INVOICE] Invoice ${invoiceId} deleted successfully in ${executionTime}ms with ${paymentHandling} payment handling`);
-
-      return {
-        success: true,
-        data: { invoiceId, executionTime, paymentHandling },
-        executionTime,
-        affectedRows: 1
-      };
-
-    } catch (error) {
-      const executionTime = Date.now() - startTime;
-      console.error(`❌ [DELETE-INVOICE] Failed to delete invoice ${invoiceId}:`, error);
-
-      return {
-        success: false,
-        error: error as DatabaseError,
-        executionTime
-      };
-    }
-  }
-
-  /**
-   * 🚨 FORCE DELETE: Delete invoice regardless of payment status
-   * ⚠️ WARNING: This bypasses all safety checks - use with extreme caution
-   * Handles ALL related entries and maintains data consistency
-   */
-  async forceDeleteInvoice(invoiceId: number, options: {
-    handlePayments?: 'reverse' | 'transfer' | 'ignore';
-    reason?: string;
-    authorizedBy?: string;
-    createBackup?: boolean;
-  } = {}): Promise<DatabaseOperationResult> {
-    const startTime = Date.now();
-
-    try {
-      if (!this.isInitialized) {
-        await this.initialize();
-      }
-
-      console.log(`🚨 [FORCE-DELETE] Starting force deletion of invoice ${invoiceId} with options:`, options);
-
-      // Get complete invoice details for cleanup and audit
-      const invoice = await this.getInvoiceDetails(invoiceId);
-      if (!invoice) {
-        throw new Error('Invoice not found');
-      }
-
-      // Get all related data before deletion for audit trail
-      const relatedData = await this.getInvoiceRelatedData(invoiceId);
-
-      // Create backup if requested
-      if (options.createBackup !== false) {
-        await this.createInvoiceBackup(invoiceId, invoice, relatedData);
-      }
-
-      // Start comprehensive transaction
-      await this.dbConnection.execute('BEGIN TRANSACTION');
-
-      try {
-        // Step 1: Handle payments based on option
-        await this.handleInvoicePaymentsOnDeletion(invoiceId, invoice, options.handlePayments || 'reverse');
-
-        // Step 2: Restore stock for all items
-        await this.restoreStockOnInvoiceDeletion(invoiceId, invoice.items);
-
-        // Step 3: Reverse customer balance changes
-        await this.reverseCustomerBalanceOnDeletion(invoiceId, invoice);
-
-        // Step 4: Clean up ALL related entries in proper order
-        await this.cleanupAllRelatedEntries(invoiceId);
-
-        // Step 5: Create comprehensive audit trail
-        await this.createDeletionAuditTrail(invoiceId, invoice, relatedData, options);
-
-        // Step 6: Delete the invoice itself (final step)
-        await this.dbConnection.execute('DELETE FROM invoices WHERE id = ?', [invoiceId]);
-
-        await this.dbConnection.execute('COMMIT');
-
-        // Step 7: Emit comprehensive events for UI updates
-        this.emitForceDeleteEvents(invoice);
-
-        // Step 8: Invalidate related caches
-        this.invalidateInvoiceCache();
-        this.invalidateCustomerCache();
-        this.invalidateProductCache();
-
-        const executionTime = Date.now() - startTime;
-        console.log(`✅ [FORCE-DELETE] Invoice ${invoiceId} force deleted successfully in ${executionTime}ms`);
-
-        return {
-          success: true,
-          data: {
-            invoiceId,
-            executionTime,
-            relatedRecordsDeleted: relatedData.totalRelatedRecords,
-            paymentsHandled: relatedData.payments.length,
-            stockRestored: relatedData.items.length
-          },
-          executionTime,
-          affectedRows: 1
-        };
-
-      } catch (error) {
-        await this.dbConnection.execute('ROLLBACK');
-        throw error;
-      }
-
-    } catch (error) {
-      const executionTime = Date.now() - startTime;
-      console.error(`❌ [FORCE-DELETE] Failed to force delete invoice ${invoiceId}:`, error);
-
-      return {
-        success: false,
-        error: error as DatabaseError,
-        executionTime
-      };
-    }
-  }
-
-  /**
-   * Get all related data for an invoice before deletion
-   */
-  private async getInvoiceRelatedData(invoiceId: number): Promise<{
-    items: any[];
-    payments: any[];
-    customerLedgerEntries: any[];
-    ledgerEntries: any[];
-    stockMovements: any[];
-    returns: any[];
-    totalRelatedRecords: number;
-  }> {
-    try {
-      // Get all related data
-      const items = await this.dbConnection.select('SELECT * FROM invoice_items WHERE invoice_id = ?', [invoiceId]);
-
-      const payments = await this.dbConnection.select('SELECT * FROM payments WHERE invoice_id = ?', [invoiceId]);
-
-      const customerLedgerEntries = await this.dbConnection.select(
-        'SELECT * FROM customer_ledger_entries WHERE reference_id = ? AND reference_type = ?',
-        [invoiceId, 'invoice']
-      );
-
-      const ledgerEntries = await this.dbConnection.select(
-        'SELECT * FROM ledger_entries WHERE reference_id = ? AND reference_type = ?',
-        [invoiceId, 'invoice']
-      );
-
-      const stockMovements = await this.dbConnection.select(
-        'SELECT * FROM stock_movements WHERE reference_id = ? AND reference_type = ?',
-        [invoiceId, 'invoice']
-      );
-
-      const returns = await this.dbConnection.select('SELECT * FROM returns WHERE original_invoice_id = ?', [invoiceId]);
-
-      const totalRelatedRecords = items.length + payments.length + customerLedgerEntries.length +
-        ledgerEntries.length + stockMovements.length + returns.length;
-
-      console.log(`📊 [FORCE-DELETE] Found ${totalRelatedRecords} related records for invoice ${invoiceId}:`, {
-        items: items.length,
-        payments: payments.length,
-        customerLedgerEntries: customerLedgerEntries.length,
-        ledgerEntries: ledgerEntries.length,
-        stockMovements: stockMovements.length,
-        returns: returns.length
-      });
-
-      return {
-        items,
-        payments,
-        customerLedgerEntries,
-        ledgerEntries,
-        stockMovements,
-        returns,
-        totalRelatedRecords
-      };
-
-    } catch (error) {
-      console.error('❌ [FORCE-DELETE] Error getting related data:', error);
-      throw error;
-    }
-  }
-
-  /**
-   * Create backup of invoice and all related data before force deletion
-   */
-  private async createInvoiceBackup(invoiceId: number, invoice: any, relatedData: any): Promise<void> {
-    try {
-      // Ensure audit_log table exists
-      await this.ensureTableExists('audit_log');
-
-      const backupData = {
-        invoice,
-        relatedData,
-        backupTimestamp: new Date().toISOString(),
-        backupReason: 'force_deletion_backup'
-      };
-
-      await this.dbConnection.execute(
-        `INSERT INTO audit_log (
-          action, entity_type, entity_id, data, created_at, created_by
-        ) VALUES (?, ?, ?, ?, datetime('now'), ?)`,
-        [
-          'BACKUP_BEFORE_FORCE_DELETE',
-          'invoice',
-          invoiceId,
-          JSON.stringify(backupData),
-          'system'
-        ]
-      );
-
-      console.log(`💾 [FORCE-DELETE] Backup created for invoice ${invoiceId}`);
-
-    } catch (error) {
-      console.warn('⚠️ [FORCE-DELETE] Could not create backup:', error);
-      // Don't fail the operation for backup issues
-    }
-  }
-
-  /**
-   * Handle payments when force deleting invoice
-   */
-  private async handleInvoicePaymentsOnDeletion(
-    invoiceId: number,
-    invoice: any,
-    handleOption: string
-  ): Promise<void> {
-    try {
-      const payments = await this.dbConnection.select(
-        'SELECT * FROM payments WHERE invoice_id = ?',
-        [invoiceId]
-      );
-
-      if (payments.length === 0) {
-        console.log(`✅ [FORCE-DELETE] No payments found for invoice ${invoiceId}`);
-        return;
-      }
-
-      console.log(`💰 [FORCE-DELETE] Processing ${payments.length} payments with option: ${handleOption}`);
-
-      for (const payment of payments) {
-        switch (handleOption) {
-          case 'reverse':
-            // Convert payment to customer credit
-            console.log(`🔄 [FORCE-DELETE] Converting payment ${payment.id} (Rs.${payment.amount}) to customer credit`);
-
-            // Update customer balance (add credit)
-            const customer = await this.getCustomer(invoice.customer_id);
-            if (customer) {
-              const newBalance = (customer.balance || 0) - payment.amount; // Credit reduces balance
-              await this.dbConnection.execute(
-                'UPDATE customers SET balance = ?, updated_at = ? WHERE id = ?',
-                [newBalance, new Date().toISOString(), invoice.customer_id]
-              );
-
-              // Create customer ledger entry for the credit
-              await this.dbConnection.execute(
-                `INSERT INTO customer_ledger_entries (
-                  customer_id, customer_name, entry_type, transaction_type, amount, description,
-                  reference_id, reference_number, date, time, created_by, created_at
-                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, datetime('now'))`,
-                [
-                  invoice.customer_id,
-                  customer.name,
-                  'credit',
-                  'adjustment',
-                  payment.amount,
-                  `Credit from force deleted invoice ${invoice.bill_number}`,
-                  invoiceId,
-                  `CREDIT-${invoice.bill_number}`,
-                  new Date().toISOString().split('T')[0],
-                  new Date().toLocaleTimeString('en-PK', { hour: '2-digit', minute: '2-digit', hour12: true }),
-                  'system'
-                ]
-              );
-            }
-            break;
-
-          case 'transfer':
-            // Keep payment as advance payment (remove invoice association)
-            console.log(`📤 [FORCE-DELETE] Converting payment ${payment.id} to advance payment`);
-            await this.dbConnection.execute(
-              'UPDATE payments SET invoice_id = NULL, payment_type = ?, notes = ? WHERE id = ?',
-              ['advance_payment', `Converted from deleted invoice ${invoice.bill_number}`, payment.id]
-            );
-            break;
-
-          case 'ignore':
-            // Just delete payment records
-            console.log(`🗑️ [FORCE-DELETE] Deleting payment ${payment.id} (ignore option)`);
-            break;
-        }
-      }
-
-      // Delete payment records (unless transferred)
-      if (handleOption !== 'transfer') {
-        await this.dbConnection.execute('DELETE FROM payments WHERE invoice_id = ?', [invoiceId]);
-      }
-
-      console.log(`✅ [FORCE-DELETE] Payments handled successfully with option: ${handleOption}`);
-
-    } catch (error) {
-      console.error('❌ [FORCE-DELETE] Error handling payments:', error);
-      throw error;
-    }
-  }
-
-  /**
-   * Restore stock when force deleting invoice
-   */
-  private async restoreStockOnInvoiceDeletion(invoiceId: number, items: any[]): Promise<void> {
-    try {
-      if (!items || items.length === 0) {
-        console.log(`✅ [FORCE-DELETE] No items to restore stock for invoice ${invoiceId}`);
-        return;
-      }
-
-      // Get invoice details for reference numbers
-      const invoice = await this.getInvoiceDetails(invoiceId);
-      const billNumber = invoice?.bill_number || `INV-${invoiceId}`;
-
-      console.log(`📦 [FORCE-DELETE] Restoring stock for ${items.length} items`);
-
-      for (const item of items) {
-        // Skip miscellaneous items (they don't have stock)
-        if (item.is_misc_item || !item.product_id) {
-          console.log(`⏭️ [FORCE-DELETE] Skipping stock restoration for misc item: ${item.product_name}`);
-          continue;
-        }
-
-        try {
-          // Get current product stock
-          const product = await this.getProduct(item.product_id);
-          if (!product) {
-            console.warn(`⚠️ [FORCE-DELETE] Product ${item.product_id} not found, skipping stock restoration`);
-            continue;
-          }
-
-          // Parse quantities
-          const itemQuantityParsed = this.parseUnitSelfContained(item.quantity, product.unit_type);
-          const currentStockParsed = this.parseUnitSelfContained(product.current_stock, product.unit_type);
-
-          // Add back the quantity
-          const newStockNumeric = currentStockParsed.numericValue + itemQuantityParsed.numericValue;
-          const newStockString = this.createUnitStringSelfContained(newStockNumeric, product.unit_type);
-
-          // Update product stock
-          await this.dbConnection.execute(
-            'UPDATE products SET current_stock = ?, stock_quantity = ?, updated_at = ? WHERE id = ?',
-            [newStockString, newStockNumeric, new Date().toISOString(), item.product_id]
-          );
-
-          // Create stock movement record
-          await this.dbConnection.execute(
-            `INSERT INTO stock_movements (
-              product_id, product_name, movement_type, transaction_type, quantity, unit,
-              reference_type, reference_id, reference_number, notes, date, time, created_by, created_at
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, datetime('now'))`,
-            [
-              item.product_id,
-              item.product_name,
-              'in',
-              'adjustment',
-              item.quantity,
-              item.unit || product.unit,
-              'force_delete',
-              invoiceId,
-              `RESTORE-${billNumber}`,
-              `Stock restored from force deleted invoice ${billNumber}`,
-              new Date().toISOString().split('T')[0],
-              new Date().toLocaleTimeString('en-PK', { hour: '2-digit', minute: '2-digit', hour12: true }),
-              'system'
-            ]
-          );
-
-          console.log(`✅ [FORCE-DELETE] Stock restored for ${item.product_name}: +${item.quantity}`);
-
-        } catch (itemError) {
-          console.error(`❌ [FORCE-DELETE] Error restoring stock for item ${item.product_name}:`, itemError);
-          // Continue with other items
-        }
-      }
-
-      console.log(`✅ [FORCE-DELETE] Stock restoration completed`);
-
-    } catch (error) {
-      console.error('❌ [FORCE-DELETE] Error restoring stock:', error);
-      throw error;
-    }
-  }
-
-  /**
-   * Reverse customer balance changes when force deleting invoice
-   */
-  private async reverseCustomerBalanceOnDeletion(invoiceId: number, invoice: any): Promise<void> {
-    try {
-      const customer = await this.getCustomer(invoice.customer_id);
-      if (!customer) {
-        console.warn(`⚠️ [FORCE-DELETE] Invoice ${invoiceId} - Customer ${invoice.customer_id} not found, skipping balance reversal`);
-        return;
-      }
-
-      // Calculate balance adjustment
-      const invoiceAmount = invoice.grand_total || invoice.total_amount || 0;
-      const paidAmount = invoice.payment_amount || 0;
-      const remainingBalance = invoiceAmount - paidAmount;
-
-      if (remainingBalance > 0) {
-        // Reduce customer balance (the original ledger entries will be deleted)
-        const currentBalance = customer.balance || 0;
-        const newBalance = currentBalance - remainingBalance;
-
-        await this.dbConnection.execute(
-          'UPDATE customers SET balance = ?, updated_at = ? WHERE id = ?',
-          [newBalance, new Date().toISOString(), invoice.customer_id]
-        );
-
-        console.log(`💰 [FORCE-DELETE] Customer balance adjusted: Rs.${currentBalance} → Rs.${newBalance} (Removed: Rs.${remainingBalance})`);
-      }
-
-    } catch (error) {
-      console.error('❌ [FORCE-DELETE] Error reversing customer balance:', error);
-      throw error;
-    }
-  }
-
-  /**
-   * Comprehensive cleanup of ALL related entries
-   */
-  private async cleanupAllRelatedEntries(invoiceId: number): Promise<void> {
-    try {
-      console.log(`🧹 [FORCE-DELETE] Starting comprehensive cleanup for invoice ${invoiceId}`);
-
-      // Define all cleanup operations in dependency order
-      const cleanupOperations = [
-        // 1. Invoice items (CASCADE DELETE should handle this, but be explicit)
-        {
-          table: 'invoice_items',
-          query: 'DELETE FROM invoice_items WHERE invoice_id = ?',
-          description: 'Invoice items'
-        },
-
-        // 2. Stock movements related to this invoice
-        {
-          table: 'stock_movements',
-          query: 'DELETE FROM stock_movements WHERE reference_type = ? AND reference_id = ?',
-          params: ['invoice', invoiceId],
-          description: 'Stock movements'
-        },
-
-        // 3. Customer ledger entries (Use correct reference pattern)
-        {
-          table: 'customer_ledger_entries',
-          query: 'DELETE FROM customer_ledger_entries WHERE reference_id = ?',
-          params: [invoiceId],
-          description: 'Customer ledger entries'
-        },
-
-        // 4. General ledger entries
-        {
-          table: 'ledger_entries',
-          query: 'DELETE FROM ledger_entries WHERE reference_type = ? AND reference_id = ?',
-          params: ['invoice', invoiceId],
-          description: 'General ledger entries'
-        },
-
-        // 5. Miscellaneous item ledger entries (special case)
-        {
-          table: 'ledger_entries',
-          query: `DELETE FROM ledger_entries 
-                  WHERE reference_type = 'invoice_item' 
-                  AND reference_id IN (SELECT id FROM invoice_items WHERE invoice_id = ?)`,
-          description: 'Miscellaneous item ledger entries'
-        },
-
-        // 6. Payment allocations
-        {
-          table: 'invoice_payment_allocations',
-          query: 'DELETE FROM invoice_payment_allocations WHERE invoice_id = ?',
-          description: 'Payment allocations'
-        },
-
-        // 7. Update returns to remove invoice association (don't delete returns)
-        {
-          table: 'returns',
-          query: 'UPDATE returns SET original_invoice_id = NULL WHERE original_invoice_id = ?',
-          description: 'Return associations (updated, not deleted)'
-        },
-
-        // 8. Update return items to remove invoice item association
-        {
-          table: 'return_items',
-          query: `UPDATE return_items SET original_invoice_item_id = NULL 
-                  WHERE original_invoice_item_id IN (SELECT id FROM invoice_items WHERE invoice_id = ?)`,
-          description: 'Return item associations (updated, not deleted)'
-        }
-      ];
-
-      // Execute all cleanup operations
-      for (const operation of cleanupOperations) {
-        try {
-          const params = operation.params || [invoiceId];
-          const result = await this.dbConnection.execute(operation.query, params);
-          const affectedRows = result.affectedRows || result.rowsAffected || 0;
-
-          console.log(`🧹 [FORCE-DELETE] ${operation.description}: ${affectedRows} records processed`);
-
-        } catch (operationError: any) {
-          // Log but don't fail for missing tables or other non-critical errors
-          if (operationError.message?.includes('no such table')) {
-            console.log(`⏭️ [FORCE-DELETE] ${operation.table} table not found, skipping`);
-          } else {
-            console.warn(`⚠️ [FORCE-DELETE] Warning in ${operation.description}:`, operationError.message);
-          }
-        }
-      }
-
-      console.log(`✅ [FORCE-DELETE] Comprehensive cleanup completed for invoice ${invoiceId}`);
-
-    } catch (error) {
-      console.error('❌ [FORCE-DELETE] Error in comprehensive cleanup:', error);
-      throw error;
-    }
-  }
+                  // Record stock movement
+                  const { dbDate: date, dbTime: time } = getCurrentSystemDateTime();
+                  await this.dbConnection.execute(
+                    `INSERT INTO stock_movements (
+                      product_id, product_name, movement_type, quantity, previous_stock, new_stock,
+                      reason, reference_type, reference_id, notes, date, time, created_by, created_at, updated_at
+                    ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)`,
+                    [
+                      newItem.product_id,
+                      product.name,
+                      'out',
+                      quantityData.numericValue,
+                      currentStockData.numericValue,
+                      newStock,
+                      'Stock deducted for new invoice item',
+                      'invoice',
+                      invoiceId,
+                      `New item added to invoice ${existingInvoice.bill_number}`,
+                      date, time, 'system',
+                      getCurrentSystemDateTime().dbTimestamp,
+                      getCurrentSystemDateTime().dbTimestamp
+                    ]
+                  );
+                }
+              }
 
-  /**
-   * Create comprehensive audit trail for force deletion
-   */
-  private async createDeletionAuditTrail(
-    invoiceId: number,
-    invoice: any,
-    relatedData: any,
-    options: any
-  ): Promise<void> {
-    try {
-      await this.ensureTableExists('audit_log');
+              // Insert new item
+              await this.dbConnection.execute(
+                `INSERT INTO invoice_items (
+                  invoice_id, product_id, product_name, quantity, unit_price, total_price,
+                  unit, length, pieces, is_misc_item, misc_description,
+                  t_iron_pieces, t_iron_length_per_piece, t_iron_total_feet, t_iron_unit,
+                  is_non_stock_item, created_at, updated_at
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)`,
+                [
+                  invoiceId, newItem.product_id, newItem.product_name, newItem.quantity, newItem.unit_price, newItem.total_price,
+                  newItem.unit, newItem.length, newItem.pieces, newItem.is_misc_item ? 1 : 0, newItem.misc_description,
+                  newItem.t_iron_pieces, newItem.t_iron_length_per_piece, newItem.t_iron_total_feet, newItem.t_iron_unit,
+                  newItem.is_non_stock_item ? 1 : 0, getCurrentSystemDateTime().dbTimestamp, getCurrentSystemDateTime().dbTimestamp
+                ]
+              );
+            }
 
-      const auditData = {
-        action: 'FORCE_DELETE_INVOICE',
-        invoice_id: invoiceId,
-        invoice_number: invoice.bill_number,
-        customer_id: invoice.customer_id,
-        customer_name: invoice.customer_name,
-        original_amount: invoice.grand_total || invoice.total_amount,
-        payment_amount: invoice.payment_amount || 0,
-        remaining_balance: invoice.remaining_balance || 0,
-        payment_handling: options.handlePayments || 'reverse',
-        reason: options.reason || 'Administrative force deletion',
-        authorized_by: options.authorizedBy || 'system',
-        deleted_at: new Date().toISOString(),
-        items_count: relatedData.items.length,
-        payments_count: relatedData.payments.length,
-        total_related_records: relatedData.totalRelatedRecords,
-        detailed_counts: {
-          items: relatedData.items.length,
-          payments: relatedData.payments.length,
-          customerLedgerEntries: relatedData.customerLedgerEntries.length,
-          ledgerEntries: relatedData.ledgerEntries.length,
-          stockMovements: relatedData.stockMovements.length,
-          returns: relatedData.returns.length
+            totalItemsValue += newItem.total_price;
+          }
+        } else {
+          // No items updated, calculate total from existing items
+          totalItemsValue = existingItems.reduce((sum, item) => sum + item.total_price, 0);
         }
-      };
-
-      await this.dbConnection.execute(
-        `INSERT INTO audit_log (
-          action, entity_type, entity_id, data, created_at, created_by
-        ) VALUES (?, ?, ?, ?, datetime('now'), ?)`,
-        [
-          'FORCE_DELETE_INVOICE',
-          'invoice',
-          invoiceId,
-          JSON.stringify(auditData),
-          options.authorizedBy || 'system'
-        ]
-      );
 
-      console.log(`📝 [FORCE-DELETE] Comprehensive audit trail created for invoice ${invoiceId}`);
+        // Update invoice header
+        const discount = updateData.discount !== undefined ? updateData.discount : existingInvoice.discount;
+        const grandTotal = totalItemsValue - discount;
+        const paymentAmount = updateData.payment_amount !== undefined ? updateData.payment_amount : existingInvoice.payment_amount;
+        const remainingBalance = grandTotal - paymentAmount;
 
-    } catch (error) {
-      console.warn('⚠️ [FORCE-DELETE] Could not create audit trail:', error);
-      // Don't fail the operation for audit issues
-    }
-  }
+        await this.dbConnection.execute(
+          `UPDATE invoices SET 
+            subtotal = ?, discount = ?, grand_total = ?, payment_amount = ?, remaining_balance = ?,
+            payment_method = ?, notes = ?, 
+            status = CASE 
+              WHEN ? >= ? THEN 'paid'
+              WHEN ? > 0 THEN 'partially_paid'
+              ELSE 'pending'
+            END,
+            version = COALESCE(version, 0) + 1,
+            updated_at = ?
+          WHERE id = ?`,
+          [
+            totalItemsValue, discount, grandTotal, paymentAmount, remainingBalance,
+            updateData.payment_method || existingInvoice.payment_method,
+            updateData.notes !== undefined ? updateData.notes : existingInvoice.notes,
+            paymentAmount, grandTotal, paymentAmount,
+            getCurrentSystemDateTime().dbTimestamp,
+            invoiceId
+          ]
+        );
 
-  /**
-   * Emit comprehensive events for force deletion
-   */
-  private emitForceDeleteEvents(invoice: any): void {
-    try {
-      // Import and use the event bus
-      import('../utils/eventBus').then(({ triggerInvoiceDeletedRefresh, eventBus, BUSINESS_EVENTS }) => {
-        // Emit standard deletion events
-        triggerInvoiceDeletedRefresh({
-          id: invoice.id,
-          bill_number: invoice.bill_number,
-          customer_id: invoice.customer_id,
-          customer_name: invoice.customer_name,
-          force_deleted: true
-        });
+        console.log(`🔒 [OPTIMISTIC-LOCK] Invoice version incremented for concurrent edit protection`);
 
-        // Emit specific force delete event using existing INVOICE_DELETED event
-        eventBus.emit(BUSINESS_EVENTS.INVOICE_DELETED, {
-          invoiceId: invoice.id,
-          billNumber: invoice.bill_number,
-          customerId: invoice.customer_id,
-          customerName: invoice.customer_name,
-          amount: invoice.grand_total || invoice.total_amount,
-          timestamp: new Date().toISOString(),
-          forceDeleted: true // Flag to indicate this was a force deletion
-        });
+        // Update customer balance if total changed
+        const totalChange = grandTotal - existingInvoice.grand_total;
+        const paymentChange = paymentAmount - existingInvoice.payment_amount;
+        const balanceChange = totalChange - paymentChange;
 
-        console.log(`📡 [FORCE-DELETE] Events emitted for invoice ${invoice.id}`);
-      }).catch((error) => {
-        console.warn('⚠️ [FORCE-DELETE] Could not emit events:', error);
-      });
+        if (balanceChange !== 0) {
+          try {
+            await this.customerBalanceManager.updateBalance(
+              existingInvoice.customer_id,
+              Math.abs(balanceChange),
+              balanceChange > 0 ? 'add' : 'subtract',
+              `Invoice ${invoiceId} edited - balance adjustment`,
+              invoiceId,
+              `EDIT-${invoiceId}`,
+              true // Skip transaction since we're already in one
+            );
 
-    } catch (error) {
-      console.warn('⚠️ [FORCE-DELETE] Could not emit events:', error);
-    }
-  }
+            this.clearCustomerCaches();
+            console.log(`✅ [EDIT-INVOICE] Customer balance updated by ${balanceChange > 0 ? '+' : ''}${balanceChange.toFixed(2)}`);
+          } catch (balanceError) {
+            console.error('❌ [EDIT-INVOICE] Failed to update balance through CustomerBalanceManager:', balanceError);
+            // Fallback to direct update
+            await this.dbConnection.execute(
+              'UPDATE customers SET balance = balance + ?, updated_at = ? WHERE id = ?',
+              [balanceChange, getCurrentSystemDateTime().dbTimestamp, existingInvoice.customer_id]
+            );
+            console.log('⚠️ [EDIT-INVOICE] Used fallback direct balance update');
+          }
+        }
 
-  /**
-   * 🧪 TESTING: Comprehensive validation of force delete functionality
-   * Tests all aspects of the force delete to ensure data integrity
-   */
-  async validateForceDeleteFunctionality(): Promise<{
-    success: boolean;
-    tests: Array<{
-      name: string;
-      passed: boolean;
-      message: string;
-      details?: any;
-    }>;
-    summary: {
-      total: number;
-      passed: number;
-      failed: number;
-    };
-  }> {
-    const tests: Array<{
-      name: string;
-      passed: boolean;
-      message: string;
-      details?: any;
-    }> = [];
+        // Commit transaction
+        await this.dbConnection.execute('COMMIT');
 
-    console.log('🧪 [FORCE-DELETE-TEST] Starting comprehensive force delete validation...');
+        // Emit real-time update events
+        this.emitInvoiceUpdatedEvents(invoiceId, existingInvoice);
 
-    try {
-      // Test 1: Create test invoice with payments
-      const testInvoice = await this.createTestInvoiceWithPayments();
-      tests.push({
-        name: 'Create Test Invoice with Payments',
-        passed: !!testInvoice,
-        message: testInvoice ? `Created test invoice ${testInvoice.id}` : 'Failed to create test invoice'
-      });
+        const executionTime = Date.now() - startTime;
+        console.log(`✅ [EDIT-INVOICE] Invoice ${invoiceId} updated successfully in ${executionTime}ms`);
 
-      if (!testInvoice) {
         return {
-          success: false,
-          tests,
-          summary: { total: 1, passed: 0, failed: 1 }
+          success: true,
+          data: { invoiceId, executionTime },
+          executionTime,
+          affectedRows: 1
         };
-      }
-
-      // Test 2: Verify all related records exist before deletion
-      const relatedDataBefore = await this.getInvoiceRelatedData(testInvoice.id);
-      tests.push({
-        name: 'Verify Related Records Exist',
-        passed: relatedDataBefore.totalRelatedRecords > 0,
-        message: `Found ${relatedDataBefore.totalRelatedRecords} related records`,
-        details: relatedDataBefore
-      });
 
-      // Test 3: Attempt normal deletion (should fail)
-      let normalDeleteFailed = false;
-      try {
-        await this.deleteInvoiceWithValidation(testInvoice.id);
       } catch (error) {
-        normalDeleteFailed = true;
-      }
-      tests.push({
-        name: 'Normal Delete Protection',
-        passed: normalDeleteFailed,
-        message: normalDeleteFailed ? 'Normal delete correctly blocked paid invoice' : 'Normal delete incorrectly allowed paid invoice'
-      });
-
-      // Test 4: Force delete with payment reversal
-      const forceDeleteResult = await this.forceDeleteInvoice(testInvoice.id, {
-        handlePayments: 'reverse',
-        reason: 'Testing force delete functionality',
-        authorizedBy: 'test-system',
-        createBackup: true
-      });
-
-      tests.push({
-        name: 'Force Delete Execution',
-        passed: forceDeleteResult.success,
-        message: forceDeleteResult.success ? 'Force delete completed successfully' : `Force delete failed: ${forceDeleteResult.error?.message}`,
-        details: forceDeleteResult.data
-      });
-
-      // Test 5: Verify invoice is completely removed
-      const invoiceExists = await this.getInvoiceDetails(testInvoice.id);
-      tests.push({
-        name: 'Invoice Removal Verification',
-        passed: !invoiceExists,
-        message: !invoiceExists ? 'Invoice successfully removed' : 'Invoice still exists after deletion'
-      });
-
-      // Test 6: Verify all related records are cleaned up
-      const relatedDataAfter = await this.getInvoiceRelatedData(testInvoice.id);
-      tests.push({
-        name: 'Related Records Cleanup',
-        passed: relatedDataAfter.totalRelatedRecords === 0,
-        message: `${relatedDataAfter.totalRelatedRecords} related records remaining (should be 0)`,
-        details: relatedDataAfter
-      });
-
-      // Test 7: Verify customer balance adjustment
-      const customer = await this.getCustomer(testInvoice.customer_id);
-      tests.push({
-        name: 'Customer Balance Adjustment',
-        passed: !!customer,
-        message: customer ? `Customer balance: ${customer.balance}` : 'Customer not found',
-        details: { customerId: testInvoice.customer_id, balance: customer?.balance }
-      });
-
-      // Test 8: Verify stock restoration
-      const stockVerification = await this.verifyStockRestoration(testInvoice.items);
-      tests.push({
-        name: 'Stock Restoration',
-        passed: stockVerification.success,
-        message: stockVerification.message,
-        details: stockVerification.details
-      });
-
-      // Test 9: Verify audit trail creation
-      const auditEntries = await this.dbConnection.select(
-        'SELECT * FROM audit_log WHERE entity_id = ? AND action = ?',
-        [testInvoice.id, 'FORCE_DELETE_INVOICE']
-      );
-      tests.push({
-        name: 'Audit Trail Creation',
-        passed: auditEntries.length > 0,
-        message: auditEntries.length > 0 ? `${auditEntries.length} audit entries created` : 'No audit trail found'
-      });
-
-    } catch (error: any) {
-      tests.push({
-        name: 'Test Execution',
-        passed: false,
-        message: `Test execution failed: ${error?.message || 'Unknown error'}`,
-        details: error
-      });
-    }
-
-    const passed = tests.filter(t => t.passed).length;
-    const failed = tests.filter(t => !t.passed).length;
-
-    console.log('🧪 [FORCE-DELETE-TEST] Validation completed:', {
-      total: tests.length,
-      passed,
-      failed
-    });
-
-    return {
-      success: failed === 0,
-      tests,
-      summary: {
-        total: tests.length,
-        passed,
-        failed
+        // Rollback on error
+        await this.dbConnection.execute('ROLLBACK');
+        throw error;
       }
-    };
-  }
-
-  /**
-   * 🧪 TESTING: Create a test invoice with payments for validation
-   */
-  private async createTestInvoiceWithPayments(): Promise<any> {
-    try {
-      console.log('🧪 [TEST] Creating test invoice with payments...');
-
-      // Create test customer if not exists
-      const testCustomer = await this.createTestCustomer();
-
-      // Create test product if not exists
-      const testProduct = await this.createTestProduct();
-
-      // Create invoice
-      const invoiceData = {
-        customer_id: testCustomer.id,
-        customer_name: testCustomer.name,
-        date: new Date().toISOString().split('T')[0],
-        time: new Date().toLocaleTimeString('en-PK', { hour: '2-digit', minute: '2-digit', hour12: true }),
-        items: [
-          {
-            product_id: testProduct.id,
-            product_name: testProduct.name,
-            quantity: '5',
-            unit_price: 100,
-            total_price: 500,
-            unit: 'kg'
-          }
-        ],
-        subtotal: 500,
-        grand_total: 500,
-        payment_amount: 300, // Partial payment
-        payment_method: 'cash'
-      };
-
-      const invoice = await this.createInvoice(invoiceData);
 
-      console.log(`✅ [TEST] Created test invoice ${invoice.invoiceId} with partial payment`);
+    } catch (error) {
+      const executionTime = Date.now() - startTime;
+      console.error(`❌ [EDIT-INVOICE] Failed to update invoice ${invoiceId}:`, error);
 
       return {
-        id: invoice.invoiceId,
-        customer_id: testCustomer.id,
-        items: invoiceData.items,
-        bill_number: invoice.billNumber
+        success: false,
+        error: error as DatabaseError,
+        executionTime
       };
-
-    } catch (error) {
-      console.error('❌ [TEST] Failed to create test invoice:', error);
-      return null;
     }
   }
 
   /**
-   * 🧪 TESTING: Create test customer
+   * Emit events for invoice updates
    */
-  private async createTestCustomer(): Promise<any> {
-    const customerData = {
-      customer_code: `TEST-${Date.now()}`,
-      name: 'Test Customer for Force Delete',
-      phone: '1234567890',
-      address: 'Test Address',
-      balance: 0
-    };
-
+  private emitInvoiceUpdatedEvents(invoiceId: number, originalInvoice: any): void {
     try {
-      const existingCustomer = await this.dbConnection.select(
-        'SELECT * FROM customers WHERE name = ?',
-        [customerData.name]
-      );
-
-      if (existingCustomer.length > 0) {
-        return existingCustomer[0];
-      }
-
-      await this.dbConnection.execute(
-        'INSERT INTO customers (customer_code, name, phone, address, balance, created_at) VALUES (?, ?, ?, ?, ?, datetime("now"))',
-        [customerData.customer_code, customerData.name, customerData.phone, customerData.address, customerData.balance]
-      );
+      eventBus.emit(BUSINESS_EVENTS.INVOICE_UPDATED, {
+        invoiceId: invoiceId,
+        billNumber: originalInvoice.bill_number,
+        customerId: originalInvoice.customer_id,
+        customerName: originalInvoice.customer_name,
+        timestamp: getCurrentSystemDateTime().dbTimestamp
+      });
 
-      const newCustomer = await this.dbConnection.select(
-        'SELECT * FROM customers WHERE customer_code = ?',
-        [customerData.customer_code]
-      );
+      eventBus.emit(BUSINESS_EVENTS.STOCK_UPDATED, {
+        message: `Stock updated from invoice ${originalInvoice.bill_number} edit`
+      });
 
-      return newCustomer[0];
+      eventBus.emit(BUSINESS_EVENTS.CUSTOMER_BALANCE_UPDATED, {
+        customerId: originalInvoice.customer_id,
+        customerName: originalInvoice.customer_name
+      });
 
+      console.log(`🚀 Real-time update events emitted for invoice ${originalInvoice.bill_number}`);
     } catch (error) {
-      console.error('❌ [TEST] Failed to create test customer:', error);
-      throw error;
+      console.warn('Could not emit invoice updated events:', error);
     }
   }
 
   /**
-   * 🧪 TESTING: Create test product
+   * ✅ ENHANCED delete with payment handling options
    */
-  private async createTestProduct(): Promise<any> {
-    const productData = {
-      name: 'Test Product for Force Delete',
-      unit_type: 'kg-grams',
-      unit: 'kg',
-      current_stock: '100',
-      stock_quantity: 100,
-      selling_price: 100
-    };
+  async deleteInvoiceWithValidation(
+    invoiceId: number,
+    paymentHandling: 'credit' | 'delete' = 'credit'
+  ): Promise<DatabaseOperationResult> {
+    const startTime = Date.now();
 
     try {
-      const existingProduct = await this.dbConnection.select(
-        'SELECT * FROM products WHERE name = ?',
-        [productData.name]
-      );
-
-      if (existingProduct.length > 0) {
-        return existingProduct[0];
+      if (!this.isInitialized) {
+        await this.initialize();
       }
 
-      await this.dbConnection.execute(
-        'INSERT INTO products (name, unit_type, unit, current_stock, stock_quantity, selling_price, created_at) VALUES (?, ?, ?, ?, ?, ?, datetime("now"))',
-        [productData.name, productData.unit_type, productData.unit, productData.current_stock, productData.stock_quantity, productData.selling_price]
-      );
+      // Get invoice details for validation
+      const invoice = await this.getInvoiceDetails(invoiceId);
+      if (!invoice) {
+        throw new Error('Invoice not found');
+      }
 
-      const newProduct = await this.dbConnection.select(
-        'SELECT * FROM products WHERE name = ?',
-        [productData.name]
+      // Check for associated returns (still block returns)
+      const returns = await this.dbConnection.select(
+        'SELECT COUNT(*) as count FROM returns WHERE original_invoice_id = ?',
+        [invoiceId]
       );
 
-      return newProduct[0];
-
-    } catch (error) {
-      console.error('❌ [TEST] Failed to create test product:', error);
-      throw error;
-    }
-  }
+      if (returns[0]?.count > 0) {
+        throw new Error('Cannot delete invoice with associated returns. Please handle returns first.');
+      }
 
-  /**
-   * 🧪 TESTING: Verify stock restoration after force delete
-   */
-  private async verifyStockRestoration(items: any[]): Promise<{
-    success: boolean;
-    message: string;
-    details: any;
-  }> {
-    try {
-      const verificationResults = [];
+      console.log(`✅ [ENHANCED-DELETE] Invoice ${invoice.bill_number} validation passed - proceeding with ${paymentHandling} payment handling`);
 
-      for (const item of items) {
-        if (!item.product_id || item.is_misc_item) {
-          continue; // Skip non-stock items
-        }
+      // Proceed with enhanced deletion with payment handling option
+      await this.deleteInvoiceEnhanced(invoiceId, paymentHandling);
 
-        const product = await this.getProduct(item.product_id);
-        if (product) {
-          verificationResults.push({
-            productId: item.product_id,
-            productName: item.product_name,
-            currentStock: product.current_stock,
-            stockQuantity: product.stock_quantity
-          });
-        }
-      }
+      const executionTime = Date.now() - startTime;
+      console.log(`✅ [DELETE-INVOICE] Invoice ${invoiceId} deleted successfully in ${executionTime}ms with ${paymentHandling} payment handling`);
 
       return {
         success: true,
-        message: `Verified stock for ${verificationResults.length} products`,
-        details: verificationResults
+        data: { invoiceId, executionTime, paymentHandling },
+        executionTime,
+        affectedRows: 1
       };
 
-    } catch (error: any) {
+    } catch (error) {
+      const executionTime = Date.now() - startTime;
+      console.error(`❌ [DELETE-INVOICE] Failed to delete invoice ${invoiceId}:`, error);
+
       return {
         success: false,
-        message: `Stock verification failed: ${error?.message || 'Unknown error'}`,
-        details: error
+        error: error as DatabaseError,
+        executionTime
       };
     }
   }
@@ -17331,153 +15697,27 @@ export class DatabaseService {
   }
 
   // Delete customer
-  async deleteCustomer(id: number): Promise<void> {
+  async deleteCustomer(id: number, reason?: string): Promise<void> {
     try {
-      console.log(`🗑️ PRODUCTION: Attempting to delete customer with ID: ${id}`);
-      const startTime = performance.now();
-
-      // CRITICAL: Prevent deletion of guest customer
-      if (id === -1) {
-        throw new Error('Cannot delete the guest customer record (ID: -1). This is a system record required for proper operation.');
-      }
-
-      // EFFICIENT: Single query to get customer and balance
-      const customer = await this.dbConnection.select(`
-        SELECT name, 
-               COALESCE((SELECT SUM(CASE WHEN entry_type = 'debit' THEN amount ELSE -amount END) 
-                         FROM customer_ledger_entries WHERE customer_id = ?), 0) as balance
-        FROM customers WHERE id = ?
-      `, [id, id]);
-
-      if (!customer.length) {
-        throw new Error('Customer not found');
-      }
-
-      const customerName = customer[0].name;
-      const balance = customer[0].balance;
-
-      // BUSINESS RULE: Balance must be under Rs. 100 to allow deletion
-      if (Math.abs(balance) >= 100) {
-        throw new Error(`Cannot delete customer with balance Rs. ${balance.toFixed(2)}. Balance must be under Rs. 100. Please settle the balance first.`);
-      }
+      console.log(`🗑️ PRODUCTION: Moving customer ${id} to the recycle bin`);
 
-      console.log(`✅ Customer ${customerName} eligible for deletion (balance: Rs. ${balance.toFixed(2)})`);
+      // Rust refuses the guest customer and any customer with an open balance;
+      // invoices, ledger entries and payments keep pointing at the customer
+      const record: any = await this.moveToRecycleBin('customer', id, reason);
 
-      // Ensure guest customer exists for foreign key constraints
-      await this.ensureGuestCustomerExists();
-
-      await this.dbConnection.execute('BEGIN IMMEDIATE TRANSACTION');
+      // PERFORMANCE: Clear only affected caches
+      this.invalidateCustomerCache();
 
+      // REAL-TIME UPDATE: Emit customer delete event using EventBus
       try {
-        // CRITICAL: Handle all foreign key constraints in correct order
-        console.log('🔧 Step 1: Updating invoices to preserve history...');
-        const invoiceUpdateResult = await this.dbConnection.execute(`
-          UPDATE invoices 
-          SET customer_name = '[DELETED] ' || customer_name
-          WHERE customer_id = ? AND customer_name NOT LIKE '[DELETED]%'
-        `, [id]);
-
-        // CRITICAL: Preserve audit trail - Update customer ledger entries and transfer to guest customer
-        console.log('🔧 Step 2: Updating customer ledger entries to preserve audit trail...');
-        const ledgerUpdateResult = await this.dbConnection.execute(`
-          UPDATE customer_ledger_entries 
-          SET customer_name = '[DELETED] ' || customer_name,
-              customer_id = -1
-          WHERE customer_id = ? AND customer_name NOT LIKE '[DELETED]%'
-        `, [id]);
-
-        // CRITICAL: Preserve audit trail - Update payments and transfer to guest customer
-        console.log('🔧 Step 3: Updating customer payments to preserve audit trail...');
-        const paymentsUpdateResult = await this.dbConnection.execute(`
-          UPDATE payments 
-          SET customer_name = '[DELETED] ' || customer_name,
-              customer_id = -1
-          WHERE customer_id = ? AND customer_name NOT LIKE '[DELETED]%'
-        `, [id]);
-
-        // CRITICAL: Update daily ledger entries to preserve audit trail
-        console.log('🔧 Step 4: Updating daily ledger entries to preserve audit trail...');
-        const dailyLedgerUpdateResult = await this.dbConnection.execute(`
-          UPDATE ledger_entries 
-          SET customer_name = '[DELETED] ' || customer_name,
-              customer_id = -1
-          WHERE customer_id = ? AND customer_name NOT LIKE '[DELETED]%'
-        `, [id]);
-
-        // CRITICAL: Update stock movements to preserve audit trail
-        console.log('🔧 Step 5: Updating stock movements to preserve audit trail...');
-        const stockMovementsUpdateResult = await this.dbConnection.execute(`
-          UPDATE stock_movements 
-          SET customer_name = '[DELETED] ' || customer_name,
-              customer_id = -1
-          WHERE customer_id = ? AND customer_name NOT LIKE '[DELETED]%'
-        `, [id]);
-
-        console.log('🔧 Step 6: Transferring invoices to guest customer (preserve invoice data)...');
-        const invoiceTransferResult = await this.dbConnection.execute(`
-          UPDATE invoices 
-          SET customer_id = -1
-          WHERE customer_id = ?
-        `, [id]);
-
-        // Tables with ON DELETE SET NULL will handle themselves:
-        // - stock_movements.customer_id will be set to NULL automatically
-        // Note: ledger_entries.customer_id is now preserved with [DELETED] customer_name
-
-        console.log('🔧 Step 7: Deleting customer record...');
-        const customerDeleteResult = await this.dbConnection.execute('DELETE FROM customers WHERE id = ?', [id]);
-
-        await this.dbConnection.execute('COMMIT');
-
-        const endTime = performance.now();
-        console.log(`✅ PRODUCTION: Customer deleted successfully with audit trail preserved in ${(endTime - startTime).toFixed(2)}ms`);
-        console.log(`📊 Deletion summary:`, {
-          customerId: id,
-          customerName,
-          deletedBalance: balance,
-          invoicesUpdated: invoiceUpdateResult.changes || 0,
-          invoicesTransferred: invoiceTransferResult.changes || 0,
-          ledgerEntriesUpdated: ledgerUpdateResult.changes || 0,
-          paymentsUpdated: paymentsUpdateResult.changes || 0,
-          dailyLedgerEntriesUpdated: dailyLedgerUpdateResult.changes || 0,
-          stockMovementsUpdated: stockMovementsUpdateResult.changes || 0,
-          customerDeleted: customerDeleteResult.changes || 0,
-          executionTime: `${(endTime - startTime).toFixed(2)}ms`
-        });
-
-        // PERFORMANCE: Clear only affected caches
-        this.invalidateCustomerCache();
-
-        // REAL-TIME UPDATE: Emit customer delete event using EventBus
-        try {
-          const eventBusInstance = eventBus || (typeof window !== 'undefined' ? (window as any).eventBus : null);
-          if (eventBusInstance) {
-            eventBusInstance.emit('customer:deleted', {
-              customerId: id,
-              customerName,
-              deletedBalance: balance,
-              preservedInvoices: invoiceUpdateResult.changes || 0,
-              updatedPayments: paymentsUpdateResult.changes || 0,
-              transferredInvoices: invoiceTransferResult.changes || 0,
-              updatedStockMovements: stockMovementsUpdateResult.changes || 0
-            });
-            eventBusInstance.emit('CUSTOMER_DELETED', { customerId: id });
-            console.log(`✅ CUSTOMER_DELETED event emitted for customer ID: ${id}`);
-          }
-        } catch (eventError) {
-          console.warn('Could not emit CUSTOMER_DELETED event:', eventError);
-        }
-
-      } catch (transactionError) {
-        await this.dbConnection.execute('ROLLBACK');
-        console.error('❌ Transaction failed, rolling back:', transactionError);
-        throw transactionError;
+        eventBus.emit('customer:deleted', { customerId: id, customerName: record?.label });
+        eventBus.emit('CUSTOMER_DELETED', { customerId: id });
+        console.log(`✅ CUSTOMER_DELETED event emitted for customer ID: ${id}`);
+      } catch (eventError) {
+        console.warn('Could not emit CUSTOMER_DELETED event:', eventError);
       }
 
-      // PERFORMANCE: Invalidate customer cache for real-time updates
-      this.invalidateCustomerCache();
-
-      console.log('✅ Customer deleted successfully');
+      console.log('✅ Customer moved to the recycle bin');
     } catch (error) {
       console.error('❌ Error deleting customer:', error);
       throw error;
@@ -17485,7 +15725,7 @@ export class DatabaseService {
   }
 
   /**
-   * PRODUCTION: Check if customer can be deleted (balance under Rs. 100)
+   * PRODUCTION: Check if customer can be deleted (no open balance)
    */
   async canDeleteCustomer(customerId: number): Promise<{
     canDelete: boolean;
@@ -17510,10 +15750,10 @@ export class DatabaseService {
 
       const balance = customer[0].balance;
 
-      if (Math.abs(balance) >= 100) {
+      if (Math.abs(balance) >= 0.01) {
         return {
           canDelete: false,
-          reason: `Cannot delete customer with balance Rs. ${balance.toFixed(2)}. Balance must be under Rs. 100.`,
+          reason: `Cannot delete customer with balance Rs. ${balance.toFixed(2)}. Please settle the balance first.`,
           balance
         };
       }
@@ -17532,406 +15772,6 @@ export class DatabaseService {
     }
   }
 
-  /**
-   * 🔥 PERMANENT CUSTOMER DELETION - COMPLETE DATA ERASURE
-   * ⚠️ WARNING: This permanently removes ALL customer data from the database
-   * ⚠️ This action is IRREVERSIBLE and will delete:
-   * - Customer record
-   * - All invoices and invoice items
-   * - All payments and transactions
-   * - All ledger entries and history
-   * - All stock movements related to this customer
-   * 
-   * Use with EXTREME caution - requires explicit confirmation phrase
-   */
-  async permanentlyEraseCustomer(
-    customerId: number,
-    confirmationPhrase: string
-  ): Promise<{
-    success: boolean;
-    customerId: number;
-    customerName: string;
-    deletedRecords: {
-      customerLedgerEntries: number;
-      payments: number;
-      stockMovements: number;
-      ledgerEntries: number;
-      invoiceItems: number;
-      invoices: number;
-      customer: number;
-    };
-    repairedRunningBalances: number;
-    backupCreated: string;
-    executionTime: string;
-    warnings: string[];
-  }> {
-    const startTime = performance.now();
-    const warnings: string[] = [];
-
-    try {
-      console.log(`🔥 PERMANENT DELETION: Starting complete erasure of customer ID: ${customerId}`);
-
-      // CRITICAL: Validate confirmation phrase
-      if (confirmationPhrase !== "ERASE_ALL_DATA_PERMANENTLY") {
-        throw new Error('Invalid confirmation phrase. Expected: "ERASE_ALL_DATA_PERMANENTLY"');
-      }
-
-      // CRITICAL: Prevent deletion of guest customer
-      if (customerId === -1) {
-        throw new Error('Cannot delete the guest customer record (ID: -1). This is a system record required for proper operation.');
-      }
-
-      // STEP 1: Get customer information and analyze deletion impact
-      const customer = await this.dbConnection.select(`
-        SELECT name FROM customers WHERE id = ?
-      `, [customerId]);
-
-      if (!customer.length) {
-        throw new Error('Customer not found');
-      }
-
-      const customerName = customer[0].name;
-      console.log(`🔍 Analyzing deletion impact for customer: ${customerName}`);
-
-      // STEP 2: Analyze what will be deleted
-      const deletionImpact = await this.analyzeCustomerDeletionImpact(customerId);
-      console.log(`📊 Deletion impact:`, deletionImpact);
-
-      // STEP 3: Create backup before deletion
-      const backupId = await this.createCustomerDataBackup(customerId, customerName);
-      console.log(`💾 Backup created with ID: ${backupId}`);
-
-      // STEP 4: Begin atomic transaction
-      await this.dbConnection.execute('BEGIN IMMEDIATE TRANSACTION');
-
-      try {
-        const deletedCounts = {
-          customerLedgerEntries: 0,
-          payments: 0,
-          stockMovements: 0,
-          ledgerEntries: 0,
-          invoiceItems: 0,
-          invoices: 0,
-          customer: 0
-        };
-
-        // STEP 5: Break circular references first (if column exists)
-        console.log('🔧 Breaking circular payment references...');
-        try {
-          // Check if reference_invoice_id column exists
-          const paymentTableInfo = await this.dbConnection.select("PRAGMA table_info(payments)");
-          const hasReferenceInvoiceId = paymentTableInfo.some((col: any) => col.name === 'reference_invoice_id');
-
-          if (hasReferenceInvoiceId) {
-            await this.dbConnection.execute(`
-              UPDATE payments 
-              SET reference_invoice_id = NULL 
-              WHERE customer_id = ? AND reference_invoice_id IN (
-                SELECT id FROM invoices WHERE customer_id = ?
-              )
-            `, [customerId, customerId]);
-            console.log('✅ Circular references cleared');
-          } else {
-            console.log('ℹ️ No reference_invoice_id column found, skipping circular reference cleanup');
-          }
-        } catch (referenceError) {
-          console.warn('⚠️ Could not clear circular references:', referenceError);
-          // Continue with deletion anyway
-        }
-
-        // STEP 6: Execute deletion in safe dependency order
-        console.log('🗑️ Step 1: Deleting customer ledger entries...');
-        const ledgerResult = await this.safeDeletion('customer_ledger_entries', 'customer_id', customerId);
-        deletedCounts.customerLedgerEntries = ledgerResult;
-
-        console.log('🗑️ Step 2: Deleting customer payments...');
-        const paymentsResult = await this.safeDeletion('payments', 'customer_id', customerId);
-        deletedCounts.payments = paymentsResult;
-
-        console.log('🗑️ Step 3: Deleting stock movements...');
-        const stockResult = await this.safeDeletion('stock_movements', 'customer_id', customerId);
-        deletedCounts.stockMovements = stockResult;
-
-        console.log('🗑️ Step 4: Deleting daily ledger entries...');
-        const dailyLedgerResult = await this.safeDeletion('ledger_entries', 'customer_id', customerId);
-        deletedCounts.ledgerEntries = dailyLedgerResult;
-
-        console.log('🗑️ Step 5: Deleting invoice items...');
-        const invoiceItemsResult = await this.safeSubqueryDeletion('invoice_items', 'invoice_id', 'invoices', 'customer_id', customerId);
-        deletedCounts.invoiceItems = invoiceItemsResult;
-
-        console.log('🗑️ Step 6: Deleting invoices...');
-        const invoicesResult = await this.safeDeletion('invoices', 'customer_id', customerId);
-        deletedCounts.invoices = invoicesResult;
-
-        console.log('🗑️ Step 7: Deleting customer record...');
-        const customerResult = await this.safeDeletion('customers', 'id', customerId);
-        deletedCounts.customer = customerResult;
-
-        // STEP 7: Repair running balances if needed
-        console.log('🔧 Repairing running balances...');
-        const repairedCount = await this.repairRunningBalancesAfterDeletion(deletionImpact.affectedDateRange);
-
-        await this.dbConnection.execute('COMMIT');
-
-        const endTime = performance.now();
-        const executionTime = `${(endTime - startTime).toFixed(2)}ms`;
-
-        console.log(`✅ PERMANENT DELETION COMPLETE in ${executionTime}`);
-        console.log(`📊 Final deletion summary:`, deletedCounts);
-
-        // STEP 8: Clear caches and emit events
-        this.invalidateCustomerCache();
-
-        try {
-          const eventBusInstance = eventBus || (typeof window !== 'undefined' ? (window as any).eventBus : null);
-          if (eventBusInstance) {
-            eventBusInstance.emit('customer:permanently_deleted', {
-              customerId,
-              customerName,
-              deletedCounts,
-              backupId
-            });
-          }
-        } catch (eventError) {
-          warnings.push('Could not emit permanent deletion event');
-        }
-
-        return {
-          success: true,
-          customerId,
-          customerName,
-          deletedRecords: deletedCounts,
-          repairedRunningBalances: repairedCount,
-          backupCreated: backupId,
-          executionTime,
-          warnings
-        };
-
-      } catch (transactionError) {
-        await this.dbConnection.execute('ROLLBACK');
-        console.error('❌ Transaction failed, rolling back:', transactionError);
-        throw transactionError;
-      }
-
-    } catch (error) {
-      console.error('❌ Error during permanent customer deletion:', error);
-      throw error;
-    }
-  }
-
-  /**
-   * Analyze what will be deleted when permanently erasing a customer
-   */
-  private async analyzeCustomerDeletionImpact(customerId: number): Promise<{
-    customerLedgerEntries: number;
-    payments: number;
-    stockMovements: number;
-    ledgerEntries: number;
-    invoiceItems: number;
-    invoices: number;
-    totalRecords: number;
-    affectedDateRange: { start: string; end: string };
-  }> {
-    try {
-      const counts = await Promise.all([
-        // Use safe queries that handle missing tables gracefully
-        this.safeTableCount('customer_ledger_entries', 'customer_id', customerId),
-        this.safeTableCount('payments', 'customer_id', customerId),
-        this.safeTableCount('stock_movements', 'customer_id', customerId),
-        this.safeTableCount('ledger_entries', 'customer_id', customerId),
-        this.safeSubqueryCount('invoice_items', 'invoice_id', 'invoices', 'customer_id', customerId),
-        this.safeTableCount('invoices', 'customer_id', customerId),
-        this.safeTableDateRange('ledger_entries', 'customer_id', customerId)
-      ]);
-
-      const impact = {
-        customerLedgerEntries: counts[0],
-        payments: counts[1],
-        stockMovements: counts[2],
-        ledgerEntries: counts[3],
-        invoiceItems: counts[4],
-        invoices: counts[5],
-        totalRecords: 0,
-        affectedDateRange: counts[6] || {
-          start: new Date().toISOString().split('T')[0],
-          end: new Date().toISOString().split('T')[0]
-        }
-      };
-
-      impact.totalRecords = impact.customerLedgerEntries + impact.payments + impact.stockMovements +
-        impact.ledgerEntries + impact.invoiceItems + impact.invoices + 1; // +1 for customer record
-
-      return impact;
-    } catch (error) {
-      console.error('Error analyzing deletion impact:', error);
-      throw error;
-    }
-  }
-
-  /**
-   * Safe table count that handles missing tables/columns
-   */
-  private async safeTableCount(tableName: string, columnName: string, value: number): Promise<number> {
-    try {
-      const result = await this.dbConnection.select(
-        `SELECT COUNT(*) as count FROM ${tableName} WHERE ${columnName} = ?`,
-        [value]
-      );
-      return result[0]?.count || 0;
-    } catch (error) {
-      console.warn(`Could not count ${tableName}.${columnName}:`, error);
-      return 0;
-    }
-  }
-
-  /**
-   * Safe subquery count for related tables
-   */
-  private async safeSubqueryCount(
-    tableName: string,
-    joinColumn: string,
-    parentTable: string,
-    parentColumn: string,
-    value: number
-  ): Promise<number> {
-    try {
-      const result = await this.dbConnection.select(
-        `SELECT COUNT(*) as count FROM ${tableName} WHERE ${joinColumn} IN (SELECT id FROM ${parentTable} WHERE ${parentColumn} = ?)`,
-        [value]
-      );
-      return result[0]?.count || 0;
-    } catch (error) {
-      console.warn(`Could not count ${tableName} via ${parentTable}:`, error);
-      return 0;
-    }
-  }
-
-  /**
-   * Safe deletion that handles missing tables/columns gracefully
-   */
-  private async safeDeletion(tableName: string, columnName: string, value: number): Promise<number> {
-    try {
-      const result = await this.dbConnection.execute(
-        `DELETE FROM ${tableName} WHERE ${columnName} = ?`,
-        [value]
-      );
-      return result.changes || 0;
-    } catch (error) {
-      console.warn(`Could not delete from ${tableName}.${columnName}:`, error);
-      return 0;
-    }
-  }
-
-  /**
-   * Safe subquery deletion for related tables
-   */
-  private async safeSubqueryDeletion(
-    tableName: string,
-    joinColumn: string,
-    parentTable: string,
-    parentColumn: string,
-    value: number
-  ): Promise<number> {
-    try {
-      const result = await this.dbConnection.execute(
-        `DELETE FROM ${tableName} WHERE ${joinColumn} IN (SELECT id FROM ${parentTable} WHERE ${parentColumn} = ?)`,
-        [value]
-      );
-      return result.changes || 0;
-    } catch (error) {
-      console.warn(`Could not delete ${tableName} via ${parentTable}:`, error);
-      return 0;
-    }
-  }
-
-  /**
-   * Safe date range query
-   */
-  private async safeTableDateRange(tableName: string, columnName: string, value: number): Promise<{ start: string; end: string }> {
-    try {
-      const result = await this.dbConnection.select(
-        `SELECT MIN(date) as start, MAX(date) as end FROM ${tableName} WHERE ${columnName} = ?`,
-        [value]
-      );
-      return {
-        start: result[0]?.start || new Date().toISOString().split('T')[0],
-        end: result[0]?.end || new Date().toISOString().split('T')[0]
-      };
-    } catch (error) {
-      console.warn(`Could not get date range from ${tableName}:`, error);
-      return {
-        start: new Date().toISOString().split('T')[0],
-        end: new Date().toISOString().split('T')[0]
-      };
-    }
-  }
-
-  /**
-   * Create a backup of customer data before permanent deletion
-   */
-  private async createCustomerDataBackup(customerId: number, customerName: string): Promise<string> {
-    try {
-      const backupId = `customer_${customerId}_${Date.now()}`;
-      const backupData = {
-        customer: await this.dbConnection.select(`SELECT * FROM customers WHERE id = ?`, [customerId]),
-        customerLedgerEntries: await this.dbConnection.select(`SELECT * FROM customer_ledger_entries WHERE customer_id = ?`, [customerId]),
-        payments: await this.dbConnection.select(`SELECT * FROM payments WHERE customer_id = ?`, [customerId]),
-        stockMovements: await this.dbConnection.select(`SELECT * FROM stock_movements WHERE customer_id = ?`, [customerId]),
-        ledgerEntries: await this.dbConnection.select(`SELECT * FROM ledger_entries WHERE customer_id = ?`, [customerId]),
-        invoices: await this.dbConnection.select(`SELECT * FROM invoices WHERE customer_id = ?`, [customerId]),
-        invoiceItems: await this.dbConnection.select(`SELECT ii.* FROM invoice_items ii JOIN invoices i ON ii.invoice_id = i.id WHERE i.customer_id = ?`, [customerId])
-      };
-
-      // Store backup in deletion_log table (create if doesn't exist)
-      await this.dbConnection.execute(`
-        CREATE TABLE IF NOT EXISTS deletion_log (
-          id TEXT PRIMARY KEY,
-          customer_id INTEGER,
-          customer_name TEXT,
-          backup_data TEXT,
-          deletion_date DATETIME DEFAULT CURRENT_TIMESTAMP
-        )
-      `);
-
-      await this.dbConnection.execute(`
-        INSERT INTO deletion_log (id, customer_id, customer_name, backup_data)
-        VALUES (?, ?, ?, ?)
-      `, [backupId, customerId, customerName, JSON.stringify(backupData)]);
-
-      return backupId;
-    } catch (error) {
-      console.error('Error creating backup:', error);
-      throw error;
-    }
-  }
-
-  /**
-   * Repair running balances after customer deletion
-   */
-  private async repairRunningBalancesAfterDeletion(dateRange: { start: string; end: string }): Promise<number> {
-    try {
-      // Recalculate running balances for the affected date range
-      const result = await this.dbConnection.execute(`
-        UPDATE ledger_entries 
-        SET running_balance = (
-          SELECT COALESCE(SUM(
-            CASE WHEN type = 'incoming' THEN amount ELSE -amount END
-          ), 0) 
-          FROM ledger_entries le2 
-          WHERE le2.date <= ledger_entries.date 
-          AND (le2.date < ledger_entries.date OR le2.id <= ledger_entries.id)
-        )
-        WHERE date BETWEEN ? AND ?
-      `, [dateRange.start, dateRange.end]);
-
-      return result.changes || 0;
-    } catch (error) {
-      console.warn('Could not repair running balances:', error);
-      return 0;
-    }
-  }
-
   // Get customer with balance information
   /**
    * CRITICAL: Real-time consistency validation for customer balance
@@ -18434,39 +16274,6 @@ export class DatabaseService {
     }
   }
 
-  // Clear all data from database for restore operations
-  async clearAllData(): Promise<void> {
-    try {
-      console.log('🔄 Clearing all database data...');
-
-      // Clear database tables in the correct order (reverse dependency order)
-      const tables = ['payments', 'stock_movements', 'ledger_entries', 'ledger', 'invoice_items', 'invoices', 'customers', 'products'];
-
-      for (const table of tables) {
-        try {
-          await this.dbConnection.execute(`DELETE FROM ${table}`);
-          console.log(`✅ Cleared ${table} table`);
-        } catch (error) {
-          console.warn(`Failed to clear ${table}:`, error);
-        }
-      }
-
-      // Reset auto-increment sequences
-      try {
-        await this.dbConnection.execute('DELETE FROM sqlite_sequence');
-        console.log('✅ Reset auto-increment sequences');
-      } catch (error) {
-        console.warn('Failed to reset sequences:', error);
-      }
-
-      console.log('✅ Successfully cleared all database data');
-
-    } catch (error) {
-      console.error('❌ Error clearing database data:', error);
-      throw new Error(`Failed to clear database data: ${error instanceof Error ? error.message : 'Unknown error'}`);
-    }
-  }
-
   // SECURITY FIX: Enhanced input validation methods
   private validateCustomerData(customer: any): void {
     if (!customer || typeof customer !== 'object') {
@@ -18708,9 +16515,9 @@ export class DatabaseService {
 
 
       // Build query for Tauri database
-      let query = 'SELECT * FROM products WHERE 1=1';
+      let query = 'SELECT * FROM products WHERE deleted_at IS NULL';
       const params: any[] = [];
-      let countQuery = 'SELECT COUNT(*) as total FROM products WHERE 1=1';
+      let countQuery = 'SELECT COUNT(*) as total FROM products WHERE deleted_at IS NULL';
       const countParams: any[] = [];
 
       if (search) {
@@ -20837,7 +18644,7 @@ export class DatabaseService {
           FROM vendor_payments 
           GROUP BY vendor_id
         ) payments ON v.id = payments.vendor_id
-        WHERE v.is_active = 1
+        WHERE v.is_active = 1 AND v.deleted_at IS NULL
         ORDER BY v.name ASC
       `);
 
@@ -0,0 +1,116 @@
+/**
+ * 📡 LIVE DATABASE CHANGE EVENTS
+ *
+ * The Rust side watches SQLite and emits debounced Tauri events to every
+ * window (see src-tauri/src/change_events.rs):
+ * - `db-tables-changed`: rows inserted/updated/deleted per table, committed by
+ *   Rust commands; `external: true` when another connection (a window's own
+ *   SQL plugin) committed and the tables are unknown
+ * - `db-stock-changed`: products whose stock may have changed
+ *
+ * This bridge re-emits them on the in-window eventBus, so existing listeners
+ * refresh without polling. External commits only raise DATABASE_CHANGED: the
+ * window that made them has already announced them itself.
+ */
+
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import { eventBus, BUSINESS_EVENTS } from '../utils/eventBus';
+
+// Mirrors `TableChange` in src-tauri/src/change_events.rs
+export interface TableChange {
+    table: string;
+    inserted: number;
+    updated: number;
+    deleted: number;
+    row_ids: number[];
+    deleted_ids: number[];
+}
+
+export interface TablesChanged {
+    tables: TableChange[];
+    external: boolean;
+}
+
+export interface StockChanged {
+    product_ids: number[];
+}
+
+/** Business events announced for changes to each table: [created, updated, deleted] */
+const TABLE_EVENTS: Record<string, [string, string, string]> = {
+    invoices: [BUSINESS_EVENTS.INVOICE_CREATED, BUSINESS_EVENTS.INVOICE_UPDATED, BUSINESS_EVENTS.INVOICE_DELETED],
+    customers: [BUSINESS_EVENTS.CUSTOMER_CREATED, BUSINESS_EVENTS.CUSTOMER_UPDATED, BUSINESS_EVENTS.CUSTOMER_DELETED],
+    products: [BUSINESS_EVENTS.PRODUCT_CREATED, BUSINESS_EVENTS.PRODUCT_UPDATED, BUSINESS_EVENTS.PRODUCT_DELETED],
+    vendors: [BUSINESS_EVENTS.VENDOR_CREATED, BUSINESS_EVENTS.VENDOR_UPDATED, BUSINESS_EVENTS.VENDOR_DELETED],
+    customer_ledger_entries: [
+        BUSINESS_EVENTS.CUSTOMER_LEDGER_UPDATED,
+        BUSINESS_EVENTS.CUSTOMER_LEDGER_UPDATED,
+        BUSINESS_EVENTS.CUSTOMER_LEDGER_UPDATED
+    ],
+    vendor_ledger_entries: [
+        BUSINESS_EVENTS.VENDOR_FINANCIAL_UPDATED,
+        BUSINESS_EVENTS.VENDOR_FINANCIAL_UPDATED,
+        BUSINESS_EVENTS.VENDOR_FINANCIAL_UPDATED
+    ],
+    payments: [
+        BUSINESS_EVENTS.PAYMENT_RECORDED,
+        BUSINESS_EVENTS.PAYMENT_RECORDED,
+        BUSINESS_EVENTS.PAYMENT_RECORDED
+    ],
+    stock_movements: [
+        BUSINESS_EVENTS.STOCK_MOVEMENT_CREATED,
+        BUSINESS_EVENTS.STOCK_UPDATED,
+        BUSINESS_EVENTS.STOCK_UPDATED
+    ],
+    ledger_entries: [
+        BUSINESS_EVENTS.DAILY_LEDGER_UPDATED,
+        BUSINESS_EVENTS.DAILY_LEDGER_UPDATED,
+        BUSINESS_EVENTS.DAILY_LEDGER_UPDATED
+    ]
+};
+
+let unlisteners: UnlistenFn[] = [];
+
+function forwardTablesChanged(payload: TablesChanged): void {
+    eventBus.emit(BUSINESS_EVENTS.DATABASE_CHANGED, payload);
+
+    for (const change of payload.tables) {
+        const events = TABLE_EVENTS[change.table];
+        if (!events) continue;
+        const [created, updated, deleted] = events;
+        const detail = { source: 'database', table: change.table };
+
+        if (change.inserted > 0) eventBus.emit(created, { ...detail, ids: change.row_ids });
+        if (change.updated > 0) eventBus.emit(updated, { ...detail, ids: change.row_ids });
+        if (change.deleted > 0) eventBus.emit(deleted, { ...detail, ids: change.deleted_ids });
+
+        if (change.table === 'customer_ledger_entries') {
+            eventBus.emit(BUSINESS_EVENTS.CUSTOMER_BALANCE_UPDATED, detail);
+        }
+        if (change.table === 'vendor_ledger_entries') {
+            eventBus.emit(BUSINESS_EVENTS.VENDOR_BALANCE_UPDATED, detail);
+        }
+    }
+}
+
+/**
+ * Start forwarding database change events to the eventBus (idempotent)
+ */
+export async function startDatabaseChangeEvents(): Promise<void> {
+    if (unlisteners.length > 0) return;
+
+    unlisteners = await Promise.all([
+        listen<TablesChanged>('db-tables-changed', (event) => forwardTablesChanged(event.payload)),
+        listen<StockChanged>('db-stock-changed', (event) => {
+            eventBus.emit(BUSINESS_EVENTS.STOCK_UPDATED, {
+                source: 'database',
+                products: event.payload.product_ids
+            });
+        })
+    ]);
+    console.log('📡 [DB-EVENTS] Listening for live database changes');
+}
+
+export function stopDatabaseChangeEvents(): void {
+    unlisteners.forEach((unlisten) => unlisten());
+    unlisteners = [];
+}
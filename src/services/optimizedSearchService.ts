@@ -7,16 +7,16 @@
  * 1. Intelligent caching with TTL
  * 2. Debounced queries (800ms instead of 300ms)
  * 3. Batch database queries
- * 4. Optimized SQL with indexes
+ * 4. FTS5 indexes with typo and Roman Urdu matching (Rust `global_search`)
  * 5. Minimal data transfer
  * 6. Smart result prioritization
  */
 
-import { db } from './database';
+type SearchEntityType = 'customer' | 'product' | 'invoice' | 'vendor';
 
 interface SearchResult {
     id: number;
-    type: 'customer' | 'product' | 'invoice';
+    type: SearchEntityType;
     title: string;
     subtitle: string;
     url: string;
@@ -24,6 +24,19 @@ interface SearchResult {
     score: number; // Relevance score for ranking
 }
 
+// Mirrors `SearchHit` in src-tauri/src/search.rs
+interface GlobalSearchHit {
+    entity_type: SearchEntityType;
+    id: number;
+    title: string;
+    subtitle: string;
+    category: string | null;
+    stock: string | null;
+    amount: number | null;
+    matched_by: 'prefix' | 'fuzzy';
+    score: number;
+}
+
 interface CachedSearchResult {
     results: SearchResult[];
     timestamp: number;
@@ -105,7 +118,7 @@ export class OptimizedSearchService {
             const batchResults = await this.executeBatchSearchQuery(query);
 
             // Process and rank results
-            const processedResults = this.processAndRankResults(batchResults, query);
+            const processedResults = this.processAndRankResults(batchResults);
 
             const queryTime = Date.now() - startTime;
             console.log(`✅ [SEARCH] Completed in ${queryTime}ms, found ${processedResults.length} results`);
@@ -119,120 +132,32 @@ export class OptimizedSearchService {
     }
 
     /**
-     * 🎯 OPTIMIZED BATCH QUERY
-     * Single query that searches all entities efficiently
+     * 🎯 RANKED FULL-TEXT SEARCH
+     * FTS5 indexes kept current by the Rust database layer: prefix matching,
+     * typo tolerance and Roman Urdu spelling variants, ranked server-side
      */
-    private async executeBatchSearchQuery(query: string): Promise<any[]> {
-        const searchTerm = `%${query}%`;
-
-        // 🚀 OPTIMIZED: Single UNION query with selective columns only
-        const batchQuery = `
-      SELECT 
-        'customer' as type,
-        id,
-        name as title,
-        COALESCE(phone, '') as subtitle,
-        balance,
-        NULL as category,
-        NULL as stock_quantity
-      FROM customers 
-      WHERE name LIKE ? OR phone LIKE ?
-      LIMIT ${this.MAX_RESULTS_PER_TYPE}
-      
-      UNION ALL
-      
-      SELECT 
-        'product' as type,
-        id,
-        name as title,
-        COALESCE(category, '') as subtitle,
-        NULL as balance,
-        category,
-        current_stock as stock_quantity
-      FROM products 
-      WHERE (name LIKE ? OR category LIKE ?) AND status = 'active'
-      LIMIT ${this.MAX_RESULTS_PER_TYPE}
-      
-      UNION ALL
-      
-      SELECT 
-        'invoice' as type,
-        id,
-        bill_number as title,
-        COALESCE(customer_name, '') as subtitle,
-        grand_total as balance,
-        NULL as category,
-        NULL as stock_quantity
-      FROM invoices 
-      WHERE bill_number LIKE ? OR customer_name LIKE ?
-      LIMIT ${this.MAX_RESULTS_PER_TYPE}
-    `;
-
-        return await db.executeRawQuery(batchQuery, [
-            searchTerm, searchTerm, // customers
-            searchTerm, searchTerm, // products  
-            searchTerm, searchTerm  // invoices
-        ]);
-    }
-
-    /**
-     * 🎯 SMART RESULT PROCESSING
-     * - Calculate relevance scores
-     * - Format for UI
-     * - Sort by relevance
-     */
-    private processAndRankResults(rawResults: any[], query: string): SearchResult[] {
-        const results: SearchResult[] = [];
-
-        for (const row of rawResults) {
-            const result: SearchResult = {
-                id: row.id,
-                type: row.type as 'customer' | 'product' | 'invoice',
-                title: row.title || '',
-                subtitle: row.subtitle || '',
-                url: this.generateUrl(row.type, row.id),
-                score: this.calculateRelevanceScore(row, query),
-                metadata: this.extractMetadata(row)
-            };
-
-            results.push(result);
-        }
-
-        // Sort by relevance score (highest first)
-        return results.sort((a, b) => b.score - a.score);
+    private async executeBatchSearchQuery(query: string): Promise<GlobalSearchHit[]> {
+        const { invoke } = await import('@tauri-apps/api/core');
+        return await invoke<GlobalSearchHit[]>('global_search', {
+            query,
+            limit: this.MAX_RESULTS_PER_TYPE
+        });
     }
 
     /**
-     * 🎯 RELEVANCE SCORING
-     * Higher scores for better matches
+     * 🎯 RESULT PROCESSING
+     * Hits arrive ranked; only format them for the UI
      */
-    private calculateRelevanceScore(row: any, query: string): number {
-        const lowerQuery = query.toLowerCase();
-        const lowerTitle = (row.title || '').toLowerCase();
-
-        let score = 0;
-
-        // Exact match gets highest score
-        if (lowerTitle === lowerQuery) {
-            score += 100;
-        }
-        // Starts with query gets high score
-        else if (lowerTitle.startsWith(lowerQuery)) {
-            score += 80;
-        }
-        // Contains query gets medium score
-        else if (lowerTitle.includes(lowerQuery)) {
-            score += 60;
-        }
-
-        // Boost score based on type priority
-        switch (row.type) {
-            case 'customer': score += 10; break;
-            case 'product': score += 8; break;
-            case 'invoice': score += 5; break;
-        }
-
-        return score;
+    private processAndRankResults(hits: GlobalSearchHit[]): SearchResult[] {
+        return hits.map((hit, position) => ({
+            id: hit.id,
+            type: hit.entity_type,
+            title: hit.title || '',
+            subtitle: hit.subtitle || '',
+            url: this.generateUrl(hit.entity_type, hit.id),
+            score: hits.length - position,
+            metadata: this.extractMetadata(hit)
+        }));
     }
 
     /**
@@ -243,6 +168,7 @@ export class OptimizedSearchService {
             case 'customer': return `/customers/${id}`;
             case 'product': return `/products/${id}`;
             case 'invoice': return `/billing/view/${id}`;
+            case 'vendor': return `/vendors/${id}`;
             default: return '/';
         }
     }
@@ -250,20 +176,20 @@ export class OptimizedSearchService {
     /**
      * Extract metadata for display
      */
-    private extractMetadata(row: any): Record<string, any> {
-        const metadata: Record<string, any> = {};
+    private extractMetadata(hit: GlobalSearchHit): Record<string, any> {
+        const metadata: Record<string, any> = { matchedBy: hit.matched_by };
 
-        if (row.type === 'customer' && row.balance !== null) {
-            metadata.balance = parseFloat(row.balance) || 0;
+        if ((hit.entity_type === 'customer' || hit.entity_type === 'vendor') && hit.amount !== null) {
+            metadata.balance = hit.amount;
         }
 
-        if (row.type === 'product') {
-            if (row.category) metadata.category = row.category;
-            if (row.stock_quantity !== null) metadata.stock = parseFloat(row.stock_quantity) || 0;
+        if (hit.entity_type === 'product') {
+            if (hit.category) metadata.category = hit.category;
+            if (hit.stock !== null) metadata.stock = parseFloat(hit.stock) || 0;
         }
 
-        if (row.type === 'invoice' && row.balance !== null) {
-            metadata.amount = parseFloat(row.balance) || 0;
+        if (hit.entity_type === 'invoice' && hit.amount !== null) {
+            metadata.amount = hit.amount;
         }
 
         return metadata;
@@ -188,24 +188,18 @@ export class RestartRestoreService {
         // Show user message before restart
         const message = `🎯 RESTORE OPERATION STAGED SUCCESSFULLY!\n\n` +
             `✅ Your backup has been prepared for restore\n` +
-            `🔄 Application will close in 2 seconds\n` +
-            `⚡ IMPORTANT: Please restart the application manually\n` +
-            `� The restore will complete automatically on next startup\n\n` +
+            `🔄 Application will restart in a few seconds\n` +
+            `✅ The restore will complete automatically on startup\n\n` +
             `Ready to proceed?`;
 
         if (!confirm(message)) {
             throw new Error('Restore operation cancelled by user');
         }
 
-        // Show final instruction
-        setTimeout(() => {
-            alert('🔄 Application is closing now.\n\n⚡ Please restart the application to complete the restore.\n\nThe restore will happen automatically on startup!');
-        }, 500);
-
         // Initiate restart with delay
         setTimeout(async () => {
             try {
-                await invoke('restart_application');
+                await invoke('restart_application', { afterRestore: true });
             } catch (error) {
                 console.error('❌ [RESTART-RESTORE] Restart failed:', error);
                 alert('⚠️ Please manually close and restart the application to complete the restore.');
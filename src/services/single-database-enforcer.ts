@@ -21,13 +21,9 @@ export async function getSingleDatabasePath(): Promise<{ path: string; url: stri
   try {
     console.log('[SINGLE_DB] Getting Tauri backend database path...');
 
-    // Import Tauri path API - same as main.rs uses
-    const { appDataDir } = await import('@tauri-apps/api/path');
-    const { join } = await import('@tauri-apps/api/path');
-
-    // Get app data directory - matches main.rs logic exactly
-    const appDataPath = await appDataDir();
-    const dbPath = await join(appDataPath, 'store-2025.db');
+    // Ask the backend for the path so the filename is defined in one place
+    const { invoke } = await import('@tauri-apps/api/core');
+    const dbPath = await invoke<string>('get_database_path');
 
     // Create database URL in format expected by tauri-plugin-sql
     const dbUrl = `sqlite:${dbPath}`;
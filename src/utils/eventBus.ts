@@ -131,7 +131,10 @@ export const BUSINESS_EVENTS = {
   // Return events (existing)
   RETURN_CREATED: 'return:created',
   RETURN_PROCESSED: 'return:processed',
-  RETURN_UPDATED: 'return:updated'
+  RETURN_UPDATED: 'return:updated',
+
+  // Any committed database change (payload from the Rust change watcher)
+  DATABASE_CHANGED: 'database:changed'
 };
 
 // Register default listeners to prevent "no listeners" warnings
@@ -149,6 +152,7 @@ const registerDefaultListeners = () => {
   eventBus.on(BUSINESS_EVENTS.VENDOR_PAYMENT_CREATED, defaultHandler);
   eventBus.on(BUSINESS_EVENTS.VENDOR_BALANCE_UPDATED, defaultHandler);
   eventBus.on(BUSINESS_EVENTS.CUSTOMER_CREATED, defaultHandler);
+  eventBus.on(BUSINESS_EVENTS.DATABASE_CHANGED, defaultHandler);
 };
 
 // Initialize default listeners (called after BUSINESS_EVENTS is defined)
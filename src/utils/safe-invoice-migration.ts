@@ -199,43 +199,13 @@ export class SafeInvoiceNumberMigrator {
                 return false;
             }
 
-            // Delete any new format invoices that were created during failed migration
-            // Use a different approach since REGEXP is not available
-            for (const backupInvoice of backupData) {
-                const oldNumber = backupInvoice.bill_number;
-                const numericPart = oldNumber.substring(1);
-                const number = parseInt(numericPart);
-
-                if (!isNaN(number)) {
-                    // Generate what the new format would be
-                    let newNumber: string;
-                    if (number < 10) {
-                        newNumber = `0${number}`;
-                    } else if (number < 100) {
-                        newNumber = `0${number}`;
-                    } else if (number < 1000) {
-                        newNumber = `0${number}`;
-                    } else {
-                        newNumber = number.toString();
-                    }
-
-                    // Delete the new format if it was created for this invoice
-                    await this.dbConnection.execute(
-                        `DELETE FROM invoices WHERE bill_number = ? AND id = ?`,
-                        [newNumber, backupInvoice.id]
-                    );
-                }
-            }
-
-            // Restore from backup
+            // The migration only renumbers invoices, so put the old numbers back in
+            // place; deleting and re-inserting would cascade to the invoice items
+            // and is refused by the recycle bin's delete guard
             for (const invoice of backupData) {
-                const columns = Object.keys(invoice).join(', ');
-                const placeholders = Object.keys(invoice).map(() => '?').join(', ');
-                const values = Object.values(invoice);
-
                 await this.dbConnection.execute(
-                    `INSERT OR REPLACE INTO invoices (${columns}) VALUES (${placeholders})`,
-                    values
+                    `UPDATE invoices SET bill_number = ? WHERE id = ?`,
+                    [invoice.bill_number, invoice.id]
                 );
             }
 